@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustler::etf::EtfTerm;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok((term, used)) = EtfTerm::from_bytes(data) {
+        assert!(used <= data.len());
+        // Whatever was read must be written back in a readable form.
+        let bytes = term.to_bytes();
+        let (_, reread) = EtfTerm::from_bytes(&bytes).expect("could not read written term");
+        assert_eq!(reread, bytes.len());
+    }
+});
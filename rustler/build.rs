@@ -4,15 +4,31 @@ use std::process::Command;
 
 // keep this sorted by version number
 const NIF_VERSION: &[&str] = &[
-    "2.7", "2.8", "2.9", "2.10", "2.11", "2.12", "2.13", "2.14", "2.15",
+    "2.7", "2.8", "2.9", "2.10", "2.11", "2.12", "2.13", "2.14", "2.15", "2.16",
 ];
 
+// The version of the precompiled API of `rustler_sys`, used when `erl` is not available.
+const PRECOMPILED_NIF_VERSION: &str = "2.15";
+
 fn main() {
-    let latest_version = NIF_VERSION.last().unwrap().to_string();
-    let version = env::var("RUSTLER_NIF_VERSION")
-        .unwrap_or_else(|_| get_version_from_erl().unwrap_or(latest_version));
+    let version = env::var("RUSTLER_NIF_VERSION").unwrap_or_else(|_| {
+        get_version_from_erl().unwrap_or_else(|| PRECOMPILED_NIF_VERSION.to_string())
+    });
 
     activate_versions(&version);
+
+    #[cfg(feature = "experimental-crash-guard")]
+    build_crash_guard();
+}
+
+/// Compiles the signal handling of `rustler::crash_guard`, which needs `sigsetjmp`.
+#[cfg(feature = "experimental-crash-guard")]
+fn build_crash_guard() {
+    if env::var("CARGO_CFG_UNIX").is_ok() {
+        cc::Build::new()
+            .file("src/crash_guard.c")
+            .compile("rustler_crash_guard");
+    }
 }
 
 fn get_version_from_erl() -> Option<String> {
@@ -40,12 +56,12 @@ fn activate_versions(version: &str) {
             )
         });
 
-    #[allow(clippy::needless_range_loop)]
-    for i in 0..=index {
-        println!(
-            "cargo:rustc-cfg=nif_version_{}",
-            version_feature(NIF_VERSION[i])
-        );
+    for (i, nif_version) in NIF_VERSION.iter().enumerate() {
+        let cfg = format!("nif_version_{}", version_feature(nif_version));
+        println!("cargo:rustc-check-cfg=cfg({})", cfg);
+        if i <= index {
+            println!("cargo:rustc-cfg={}", cfg);
+        }
     }
 }
 
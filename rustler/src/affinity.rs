@@ -0,0 +1,219 @@
+//! CPU affinity hints for threads spawned by NIFs.
+//!
+//! Threads spawned with `rustler::thread::spawn` or by `ParallelMap` are not pinned, and a pool
+//! sized to the number of cores competes with the schedulers of the VM for every core of the host.
+//! `ThreadHints` bounds the number of threads and pins them to a set of cores, which is most
+//! useful together with scheduler binding (`+sbt`): the logical processors the schedulers are
+//! bound to are returned by `:erlang.system_info(:scheduler_bindings)`, which decodes as
+//! `SchedulerBindings`, and `ThreadHints::avoid_schedulers` keeps the threads off them:
+//!
+//! ```ignore
+//! #[rustler::nif(schedule = "DirtyCpu")]
+//! fn index_all(docs: Vec<String>, bindings: SchedulerBindings) -> Vec<u64> {
+//!     let hints = ThreadHints::new().avoid_schedulers(&bindings);
+//!     ParallelMap::new().hints(hints).run(docs, |doc| index(&doc)).unwrap()
+//! }
+//! ```
+//!
+//! Pinning is only supported on Linux. Elsewhere, hints only bound the number of threads.
+
+use crate::types::atom::Atom;
+use crate::{Decoder, NifResult, Term};
+use std::io;
+use std::num::NonZeroUsize;
+use std::thread;
+
+mod atoms {
+    crate::atoms! {
+        unbound,
+    }
+}
+
+/// The logical processors the schedulers of the VM are bound to, as returned by
+/// `:erlang.system_info(:scheduler_bindings)`.
+///
+/// Decodes from a tuple with an element per scheduler, either the logical processor it is bound
+/// to or `:unbound`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SchedulerBindings {
+    bindings: Vec<Option<usize>>,
+}
+
+impl SchedulerBindings {
+    pub fn new(bindings: Vec<Option<usize>>) -> Self {
+        SchedulerBindings { bindings }
+    }
+
+    /// Returns the number of schedulers.
+    pub fn schedulers(&self) -> usize {
+        self.bindings.len()
+    }
+
+    /// Returns the logical processors bound schedulers run on.
+    pub fn bound_cores(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bindings.iter().filter_map(|binding| *binding)
+    }
+}
+
+impl<'a> Decoder<'a> for SchedulerBindings {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let bindings = crate::types::tuple::get_tuple(term)?
+            .into_iter()
+            .map(|binding| {
+                if binding.decode::<Atom>().ok() == Some(atoms::unbound()) {
+                    Ok(None)
+                } else {
+                    binding.decode().map(Some)
+                }
+            })
+            .collect::<NifResult<_>>()?;
+        Ok(SchedulerBindings { bindings })
+    }
+}
+
+/// Hints for the threads of a pool: how many there should be, and on which cores they should run.
+///
+/// See the module documentation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ThreadHints {
+    cores: Option<Vec<usize>>,
+    max_threads: Option<usize>,
+}
+
+impl ThreadHints {
+    /// Hints without constraints: as many threads as cores, running anywhere.
+    pub fn new() -> Self {
+        ThreadHints::default()
+    }
+
+    /// Pins the threads to `cores`, numbered as logical processors.
+    ///
+    /// Cores the process is not allowed to run on are ignored.
+    pub fn cores<I: IntoIterator<Item = usize>>(mut self, cores: I) -> Self {
+        let allowed = available_cores();
+        let mut cores: Vec<usize> = cores
+            .into_iter()
+            .filter(|core| allowed.contains(core))
+            .collect();
+        cores.sort_unstable();
+        cores.dedup();
+        self.cores = Some(cores);
+        self
+    }
+
+    /// Sets the maximum number of threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_threads` is 0.
+    pub fn max_threads(mut self, max_threads: usize) -> Self {
+        assert!(max_threads > 0, "max_threads must be positive");
+        self.max_threads = Some(max_threads);
+        self
+    }
+
+    /// Keeps the threads off the cores of the schedulers.
+    ///
+    /// Threads are pinned to the cores no scheduler is bound to. If the schedulers are not bound,
+    /// or are bound to every core, the number of threads is instead limited to the number of cores
+    /// left over by the schedulers, with a minimum of one.
+    pub fn avoid_schedulers(mut self, bindings: &SchedulerBindings) -> Self {
+        let available = self.cores.clone().unwrap_or_else(available_cores);
+        let spare: Vec<usize> = available
+            .iter()
+            .copied()
+            .filter(|core| !bindings.bound_cores().any(|bound| bound == *core))
+            .collect();
+        if bindings.bound_cores().next().is_some() && !spare.is_empty() {
+            self.cores = Some(spare);
+        } else {
+            let spare = available.len().saturating_sub(bindings.schedulers()).max(1);
+            self.max_threads = Some(self.max_threads.map_or(spare, |max| max.min(spare)));
+        }
+        self
+    }
+
+    /// Returns the cores threads are pinned to, if any.
+    pub fn pinned_cores(&self) -> Option<&[usize]> {
+        self.cores.as_deref()
+    }
+
+    /// Returns the number of threads a pool following these hints should have.
+    pub fn thread_count(&self) -> usize {
+        let cores = match self.cores {
+            Some(ref cores) => cores.len(),
+            None => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        };
+        let max = self.max_threads.unwrap_or(usize::MAX);
+        cores.min(max).max(1)
+    }
+
+    /// Pins the calling thread to the cores of the hints, if any.
+    pub fn apply(&self) -> io::Result<()> {
+        match self.cores {
+            Some(ref cores) if !cores.is_empty() => pin_current_thread(cores),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns the logical processors the process is allowed to run on.
+#[cfg(target_os = "linux")]
+pub fn available_cores() -> Vec<usize> {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return fallback_cores();
+        }
+        (0..libc::CPU_SETSIZE as usize)
+            .filter(|&core| libc::CPU_ISSET(core, &set))
+            .collect()
+    }
+}
+
+/// Returns the logical processors the process is allowed to run on.
+#[cfg(not(target_os = "linux"))]
+pub fn available_cores() -> Vec<usize> {
+    fallback_cores()
+}
+
+fn fallback_cores() -> Vec<usize> {
+    (0..thread::available_parallelism().map_or(1, NonZeroUsize::get)).collect()
+}
+
+/// Pins the calling thread to `cores`, numbered as logical processors.
+///
+/// Returns an error when called from a thread of the VM, which must not be pinned.
+#[cfg(target_os = "linux")]
+pub fn pin_current_thread(cores: &[usize]) -> io::Result<()> {
+    check_unmanaged_thread()?;
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &core in cores {
+            if core >= libc::CPU_SETSIZE as usize {
+                return Err(io::Error::from(io::ErrorKind::InvalidInput));
+            }
+            libc::CPU_SET(core, &mut set);
+        }
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Pins the calling thread to `cores`, numbered as logical processors.
+///
+/// Returns an error when called from a thread of the VM, which must not be pinned.
+#[cfg(not(target_os = "linux"))]
+pub fn pin_current_thread(_cores: &[usize]) -> io::Result<()> {
+    check_unmanaged_thread()?;
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+fn check_unmanaged_thread() -> io::Result<()> {
+    if unsafe { rustler_sys::enif_thread_type() } != rustler_sys::ERL_NIF_THR_UNDEFINED {
+        return Err(io::Error::other("can't pin a thread of the VM"));
+    }
+    Ok(())
+}
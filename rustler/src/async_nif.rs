@@ -0,0 +1,159 @@
+//! `async fn` NIFs, completed by a message to the caller.
+//!
+//! A NIF defined as an `async fn` returns a new reference right away, and its future is spawned
+//! on a runtime. When the future completes, its output is sent to the calling process as
+//! `{ref, output}`:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! async fn fetch(url: String) -> Result<String, String> {
+//!     client().get(&url).await.map_err(|err| err.to_string())
+//! }
+//! ```
+//!
+//! ```elixir
+//! ref = MyNif.fetch("https://example.com")
+//!
+//! receive do
+//!   {^ref, {:ok, body}} -> body
+//! end
+//! ```
+//!
+//! The arguments are decoded before the future is spawned, and moved into it, so they must be
+//! owned values: an `async fn` NIF can't take an `Env`, a `Term` or a `Binary`. The output can be
+//! any type implementing `Encoder`. If the future panics, `{ref, {:error, reason}}` is sent
+//! instead, where `reason` is what the NIF would raise in the mode of `rustler::panic_handler`:
+//! `:nif_panicked` by default.
+//!
+//! Futures run on the runtime set with `set_runtime`, usually from the `load` callback. By
+//! default, each future runs on a thread of its own, which suits a few long calls. With the
+//! `tokio` feature, a `tokio::runtime::Handle` is a runtime:
+//!
+//! ```ignore
+//! fn load(env: Env, _info: Term) -> bool {
+//!     let runtime = tokio::runtime::Runtime::new().unwrap();
+//!     rustler::async_nif::set_runtime(runtime.handle().clone());
+//!     // The runtime must outlive the library.
+//!     std::mem::forget(runtime);
+//!     true
+//! }
+//! ```
+//!
+//! A runtime must not poll futures on the threads of the VM, where messages can't be sent from
+//! an `OwnedEnv`.
+
+use crate::env::OwnedEnv;
+use crate::{Encoder, Env, NifResult, Term};
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+/// A spawned future, with its output already sent.
+pub type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// Runs the futures of `async fn` NIFs to completion.
+pub trait Runtime: Send + Sync + 'static {
+    /// Runs `future` in the background, on threads that are not managed by the VM.
+    fn spawn(&self, future: BoxFuture);
+}
+
+/// The default `Runtime`, which runs each future on a new thread.
+pub struct ThreadRuntime;
+
+impl Runtime for ThreadRuntime {
+    fn spawn(&self, future: BoxFuture) {
+        thread::spawn(move || block_on(future));
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Runtime for tokio::runtime::Handle {
+    fn spawn(&self, future: BoxFuture) {
+        tokio::runtime::Handle::spawn(self, future);
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref RUNTIME: RwLock<Arc<dyn Runtime>> = RwLock::new(Arc::new(ThreadRuntime));
+}
+
+/// Sets the runtime of the futures of `async fn` NIFs called from now on.
+pub fn set_runtime<R: Runtime>(runtime: R) {
+    *RUNTIME
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(runtime);
+}
+
+/// Spawns `future` on the runtime, and returns the reference tagging the message that carries
+/// its output to the calling process. Called by the NIFs generated for `async fn`s.
+///
+/// # Errors
+///
+/// Returns `{:error, :insufficient_memory}` if the environment of the message can't be
+/// allocated.
+///
+/// # Panics
+///
+/// Panics if `env` is process-independent.
+pub fn spawn<'a, F>(env: Env<'a>, future: F) -> NifResult<Term<'a>>
+where
+    F: Future + Send + 'static,
+    F::Output: Encoder,
+{
+    let pid = env.pid();
+    let reference = env.make_ref();
+    let mut owned_env = OwnedEnv::try_new()?;
+    let saved_reference = owned_env.save(reference);
+
+    let runtime = RUNTIME
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone();
+    runtime.spawn(Box::pin(async move {
+        let output = CatchUnwind(Box::pin(future)).await;
+        owned_env.send_and_clear(&pid, |env| {
+            let output = match output {
+                Ok(output) => output.encode(env),
+                Err(err) => env.error_tuple(crate::panic_handler::reason(env, err)),
+            };
+            (saved_reference.load(env), output).encode(env)
+        });
+    }));
+
+    Ok(reference)
+}
+
+/// Catches the panics of the inner future, which would otherwise unwind into the runtime.
+struct CatchUnwind<F>(Pin<Box<F>>);
+
+impl<F: Future> Future for CatchUnwind<F> {
+    type Output = thread::Result<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match panic::catch_unwind(AssertUnwindSafe(|| self.0.as_mut().poll(cx))) {
+            Ok(Poll::Pending) => Poll::Pending,
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Err(err) => Poll::Ready(Err(err)),
+        }
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Polls `future` on the current thread, parking it until the future is woken.
+fn block_on(mut future: BoxFuture) {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    while future.as_mut().poll(&mut cx).is_pending() {
+        thread::park();
+    }
+}
@@ -0,0 +1,362 @@
+//! Building terms against other backends than the NIF API.
+//!
+//! Most of Rustler talks to the Erlang VM through `rustler_sys`. Code that only builds terms can
+//! instead be written against the `Backend` trait, with `BuildTerm` instead of `Encoder`, to also
+//! run on other targets: lightweight VMs like AtomVM, which have their own native interface, or
+//! test shims that don't need a running VM.
+//!
+//! `Env` is the default backend, using `rustler_sys`. The `Encoder` and `Decoder` implementations
+//! of numbers, booleans, strings, tuples, lists and maps go through it, so that the calls to
+//! `rustler_sys` building and reading those terms live in one place. With the `etf` feature,
+//! `EtfBackend` builds `EtfTerm`s in Rust memory, which can be written in the External Term
+//! Format.
+//!
+//! An alternative backend only has to implement `Backend`. The terms it builds are opaque to
+//! Rustler.
+
+use crate::types::atom;
+use crate::types::binary::Binary;
+use crate::wrapper::{self, NIF_TERM};
+use crate::{Env, Term};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Builds and reads terms. `Term` is the representation of terms in the backend.
+pub trait Backend: Copy {
+    type Term: Clone;
+
+    fn atom(self, name: &str) -> Self::Term;
+    fn bool(self, value: bool) -> Self::Term {
+        self.atom(if value { "true" } else { "false" })
+    }
+    fn int(self, value: i64) -> Self::Term;
+    fn uint(self, value: u64) -> Self::Term;
+    fn float(self, value: f64) -> Self::Term;
+    fn binary(self, data: &[u8]) -> Self::Term;
+    fn tuple(self, elements: Vec<Self::Term>) -> Self::Term;
+    fn list(self, elements: Vec<Self::Term>) -> Self::Term;
+    /// Builds a map from `entries`. When keys are repeated, the last value wins.
+    fn map(self, entries: Vec<(Self::Term, Self::Term)>) -> Self::Term;
+
+    /// Returns the value of an integer term, or `None` if it is not an integer or doesn't fit.
+    fn get_int(self, term: &Self::Term) -> Option<i64>;
+    /// Returns the value of a non-negative integer term, or `None` if it is not one or doesn't
+    /// fit.
+    fn get_uint(self, term: &Self::Term) -> Option<u64>;
+    /// Returns the value of a float term, or `None` if it is not a float.
+    fn get_float(self, term: &Self::Term) -> Option<f64>;
+    /// Returns the elements of a tuple term, or `None` if it is not a tuple.
+    fn get_tuple(self, term: &Self::Term) -> Option<Vec<Self::Term>>;
+}
+
+/// A value that can be built as a term with any `Backend`.
+pub trait BuildTerm {
+    fn build<B: Backend>(&self, backend: B) -> B::Term;
+}
+
+impl<'a> Backend for Env<'a> {
+    type Term = Term<'a>;
+
+    fn atom(self, name: &str) -> Term<'a> {
+        unsafe {
+            Term::new(
+                self,
+                wrapper::atom::make_atom(self.as_c_arg(), name.as_bytes()),
+            )
+        }
+    }
+
+    fn bool(self, value: bool) -> Term<'a> {
+        if value {
+            atom::true_().to_term(self)
+        } else {
+            atom::false_().to_term(self)
+        }
+    }
+
+    fn int(self, value: i64) -> Term<'a> {
+        unsafe { Term::new(self, rustler_sys::enif_make_int64(self.as_c_arg(), value)) }
+    }
+
+    fn uint(self, value: u64) -> Term<'a> {
+        unsafe { Term::new(self, rustler_sys::enif_make_uint64(self.as_c_arg(), value)) }
+    }
+
+    fn float(self, value: f64) -> Term<'a> {
+        unsafe { Term::new(self, rustler_sys::enif_make_double(self.as_c_arg(), value)) }
+    }
+
+    /// Allocates the binary like `Binary::from_bytes`.
+    fn binary(self, data: &[u8]) -> Term<'a> {
+        Binary::from_bytes(self, data).to_term(self)
+    }
+
+    fn tuple(self, elements: Vec<Term<'a>>) -> Term<'a> {
+        let terms = raw_terms(&elements);
+        unsafe { Term::new(self, wrapper::tuple::make_tuple(self.as_c_arg(), &terms)) }
+    }
+
+    fn list(self, elements: Vec<Term<'a>>) -> Term<'a> {
+        let terms = raw_terms(&elements);
+        unsafe { Term::new(self, wrapper::list::make_list(self.as_c_arg(), &terms)) }
+    }
+
+    fn map(self, entries: Vec<(Term<'a>, Term<'a>)>) -> Term<'a> {
+        let env = self.as_c_arg();
+        let mut map = unsafe { wrapper::map::map_new(env) };
+        for (key, value) in entries {
+            map = unsafe { wrapper::map::map_put(env, map, key.as_c_arg(), value.as_c_arg()) }
+                .expect("enif_make_map_put failed on a map");
+        }
+        unsafe { Term::new(self, map) }
+    }
+
+    fn get_int(self, term: &Term<'a>) -> Option<i64> {
+        let mut value = 0;
+        let ok =
+            unsafe { rustler_sys::enif_get_int64(self.as_c_arg(), term.as_c_arg(), &mut value) };
+        if ok == 0 {
+            None
+        } else {
+            Some(value)
+        }
+    }
+
+    fn get_uint(self, term: &Term<'a>) -> Option<u64> {
+        let mut value = 0;
+        let ok =
+            unsafe { rustler_sys::enif_get_uint64(self.as_c_arg(), term.as_c_arg(), &mut value) };
+        if ok == 0 {
+            None
+        } else {
+            Some(value)
+        }
+    }
+
+    fn get_float(self, term: &Term<'a>) -> Option<f64> {
+        let mut value = 0.0;
+        let ok =
+            unsafe { rustler_sys::enif_get_double(self.as_c_arg(), term.as_c_arg(), &mut value) };
+        if ok == 0 {
+            None
+        } else {
+            Some(value)
+        }
+    }
+
+    fn get_tuple(self, term: &Term<'a>) -> Option<Vec<Term<'a>>> {
+        let elements = unsafe { wrapper::tuple::get_tuple(self.as_c_arg(), term.as_c_arg()) };
+        elements
+            .ok()
+            .map(|elements| unsafe { elements.iter().map(|&e| Term::new(self, e)).collect() })
+    }
+}
+
+fn raw_terms(terms: &[Term]) -> Vec<NIF_TERM> {
+    terms.iter().map(|term| term.as_c_arg()).collect()
+}
+
+/// Builds `EtfTerm`s, without a running VM.
+///
+/// ```
+/// use rustler::backend::{BuildTerm, EtfBackend};
+/// use rustler::etf::EtfTerm;
+///
+/// let term = ("ok", vec![1u32, 2]).build(EtfBackend);
+/// assert_eq!(
+///     term,
+///     EtfTerm::Tuple(vec![
+///         EtfTerm::Binary(b"ok".to_vec()),
+///         EtfTerm::List(vec![EtfTerm::Integer(1), EtfTerm::Integer(2)]),
+///     ])
+/// );
+/// ```
+#[cfg(feature = "etf")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EtfBackend;
+
+#[cfg(feature = "etf")]
+impl Backend for EtfBackend {
+    type Term = crate::etf::EtfTerm;
+
+    fn atom(self, name: &str) -> Self::Term {
+        crate::etf::EtfTerm::atom(name)
+    }
+
+    fn int(self, value: i64) -> Self::Term {
+        crate::etf::EtfTerm::Integer(value)
+    }
+
+    fn uint(self, value: u64) -> Self::Term {
+        match std::convert::TryFrom::try_from(value) {
+            Ok(value) => crate::etf::EtfTerm::Integer(value),
+            Err(_) => crate::etf::EtfTerm::BigInteger {
+                negative: false,
+                digits: value.to_le_bytes().to_vec(),
+            },
+        }
+    }
+
+    fn float(self, value: f64) -> Self::Term {
+        crate::etf::EtfTerm::Float(value)
+    }
+
+    fn binary(self, data: &[u8]) -> Self::Term {
+        crate::etf::EtfTerm::Binary(data.to_vec())
+    }
+
+    fn tuple(self, elements: Vec<Self::Term>) -> Self::Term {
+        crate::etf::EtfTerm::Tuple(elements)
+    }
+
+    fn list(self, elements: Vec<Self::Term>) -> Self::Term {
+        crate::etf::EtfTerm::List(elements)
+    }
+
+    fn map(self, entries: Vec<(Self::Term, Self::Term)>) -> Self::Term {
+        let mut map: Vec<(Self::Term, Self::Term)> = Vec::with_capacity(entries.len());
+        for (key, value) in entries {
+            match map.iter_mut().find(|(existing, _)| *existing == key) {
+                Some(entry) => entry.1 = value,
+                None => map.push((key, value)),
+            }
+        }
+        crate::etf::EtfTerm::Map(map)
+    }
+
+    fn get_int(self, term: &Self::Term) -> Option<i64> {
+        match term {
+            crate::etf::EtfTerm::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn get_uint(self, term: &Self::Term) -> Option<u64> {
+        match term {
+            crate::etf::EtfTerm::Integer(value) => std::convert::TryFrom::try_from(*value).ok(),
+            crate::etf::EtfTerm::BigInteger {
+                negative: false,
+                digits,
+            } if digits.len() <= 8 => {
+                let mut bytes = [0; 8];
+                bytes[..digits.len()].copy_from_slice(digits);
+                Some(u64::from_le_bytes(bytes))
+            }
+            _ => None,
+        }
+    }
+
+    fn get_float(self, term: &Self::Term) -> Option<f64> {
+        match term {
+            crate::etf::EtfTerm::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn get_tuple(self, term: &Self::Term) -> Option<Vec<Self::Term>> {
+        match term {
+            crate::etf::EtfTerm::Tuple(elements) => Some(elements.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl BuildTerm for bool {
+    fn build<B: Backend>(&self, backend: B) -> B::Term {
+        backend.bool(*self)
+    }
+}
+
+macro_rules! impl_build_integer {
+    ($method:ident, $as:ty, $($ty:ty),*) => {
+        $(
+            impl BuildTerm for $ty {
+                fn build<B: Backend>(&self, backend: B) -> B::Term {
+                    backend.$method(*self as $as)
+                }
+            }
+        )*
+    };
+}
+
+impl_build_integer!(int, i64, i8, i16, i32, i64, isize);
+impl_build_integer!(uint, u64, u8, u16, u32, u64, usize);
+
+impl BuildTerm for f32 {
+    fn build<B: Backend>(&self, backend: B) -> B::Term {
+        backend.float(f64::from(*self))
+    }
+}
+
+impl BuildTerm for f64 {
+    fn build<B: Backend>(&self, backend: B) -> B::Term {
+        backend.float(*self)
+    }
+}
+
+/// Strings are built as binaries.
+impl BuildTerm for str {
+    fn build<B: Backend>(&self, backend: B) -> B::Term {
+        backend.binary(self.as_bytes())
+    }
+}
+
+impl BuildTerm for String {
+    fn build<B: Backend>(&self, backend: B) -> B::Term {
+        backend.binary(self.as_bytes())
+    }
+}
+
+/// `None` is built as `nil`.
+impl<T: BuildTerm> BuildTerm for Option<T> {
+    fn build<B: Backend>(&self, backend: B) -> B::Term {
+        match self {
+            Some(value) => value.build(backend),
+            None => backend.atom("nil"),
+        }
+    }
+}
+
+impl<T: BuildTerm> BuildTerm for [T] {
+    fn build<B: Backend>(&self, backend: B) -> B::Term {
+        backend.list(self.iter().map(|value| value.build(backend)).collect())
+    }
+}
+
+impl<T: BuildTerm> BuildTerm for Vec<T> {
+    fn build<B: Backend>(&self, backend: B) -> B::Term {
+        self.as_slice().build(backend)
+    }
+}
+
+impl<K: BuildTerm + Eq + Hash, V: BuildTerm> BuildTerm for HashMap<K, V> {
+    fn build<B: Backend>(&self, backend: B) -> B::Term {
+        backend.map(
+            self.iter()
+                .map(|(key, value)| (key.build(backend), value.build(backend)))
+                .collect(),
+        )
+    }
+}
+
+impl<T: BuildTerm + ?Sized> BuildTerm for &T {
+    fn build<B: Backend>(&self, backend: B) -> B::Term {
+        (**self).build(backend)
+    }
+}
+
+macro_rules! impl_build_tuple {
+    ($($name:ident: $index:tt),+) => {
+        impl<$($name: BuildTerm),+> BuildTerm for ($($name,)+) {
+            fn build<B: Backend>(&self, backend: B) -> B::Term {
+                backend.tuple(vec![$(self.$index.build(backend)),+])
+            }
+        }
+    };
+}
+
+impl_build_tuple!(T0: 0);
+impl_build_tuple!(T0: 0, T1: 1);
+impl_build_tuple!(T0: 0, T1: 1, T2: 2);
+impl_build_tuple!(T0: 0, T1: 1, T2: 2, T3: 3);
+impl_build_tuple!(T0: 0, T1: 1, T2: 2, T3: 3, T4: 4);
@@ -0,0 +1,71 @@
+//! Helpers for benchmarking `Encoder` and `Decoder` implementations.
+//!
+//! Terms can only be created while the Erlang VM is running, so benchmarks have to run inside
+//! a loaded NIF library, typically from a NIF scheduled on a dirty CPU scheduler. From there, a
+//! benchmarking library like `criterion` can be driven as usual, using these helpers to get an
+//! environment to encode to and decode from. See `rustler_benchmarks` in the rustler repository
+//! for a complete setup.
+//!
+//! ```ignore
+//! #[rustler::nif(schedule = "DirtyCpu")]
+//! fn run_benchmarks() -> Atom {
+//!     let mut env = BenchEnv::new();
+//!     let mut criterion = Criterion::default();
+//!     criterion.bench_function("encode point", |b| {
+//!         b.iter(|| env.run(|env| { Point { x: 1, y: 2 }.encode(env); }))
+//!     });
+//!     atoms::ok()
+//! }
+//! ```
+
+use crate::env::OwnedEnv;
+use crate::{Decoder, Encoder, Env, NifResult};
+
+/// Runs `fun` in a fresh process-independent environment, which is freed afterwards.
+pub fn with_env<F, R>(fun: F) -> R
+where
+    F: for<'a> FnOnce(Env<'a>) -> R,
+{
+    OwnedEnv::new().run(fun)
+}
+
+/// Encodes `value` and decodes it back, in a fresh environment.
+pub fn roundtrip<T>(value: &T) -> NifResult<T>
+where
+    T: Encoder + for<'a> Decoder<'a>,
+{
+    with_env(|env| value.encode(env).decode())
+}
+
+/// A reusable environment for benchmark iterations.
+///
+/// Allocating an environment is much more expensive than most encoders, so benchmarks should
+/// not allocate one per iteration. A `BenchEnv` is cleared after each call to `run()`, so that
+/// memory use does not grow with the number of iterations.
+pub struct BenchEnv {
+    env: OwnedEnv,
+}
+
+impl BenchEnv {
+    pub fn new() -> BenchEnv {
+        BenchEnv {
+            env: OwnedEnv::new(),
+        }
+    }
+
+    /// Runs `fun` in this environment, then clears it.
+    pub fn run<F, R>(&mut self, fun: F) -> R
+    where
+        F: for<'a> FnOnce(Env<'a>) -> R,
+    {
+        let result = self.env.run(fun);
+        self.env.clear();
+        result
+    }
+}
+
+impl Default for BenchEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
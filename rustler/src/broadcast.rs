@@ -0,0 +1,165 @@
+//! Sets of subscriber processes, for publish/subscribe NIF libraries.
+//!
+//! A `PidSet` is a set of local pids, stored in a resource, that messages can be broadcast to.
+//! Every member is monitored, so that processes that exit are removed from the set without
+//! having to unsubscribe. The set can be returned to Elixir and passed back to other NIFs, like
+//! any resource.
+//!
+//! The resource type must be registered by calling `rustler::broadcast::load(env)` from the
+//! `load` callback of the NIF library.
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn subscribe(env: Env, topic: ResourceArc<Topic>) -> bool {
+//!     topic.subscribers.subscribe(env, &env.pid())
+//! }
+//!
+//! #[rustler::nif]
+//! fn publish(env: Env, topic: ResourceArc<Topic>, event: Term) -> usize {
+//!     topic.subscribers.broadcast(env, (atoms::event(), event))
+//! }
+//! ```
+
+use crate::monitor::{Monitor, MonitorDown};
+use crate::{Decoder, Encoder, Env, LocalPid, NifResult, ResourceArc, Term};
+use std::sync::{Mutex, MutexGuard};
+
+/// A set of monitored subscriber processes. See the module documentation.
+///
+/// Cloning a `PidSet` returns another reference to the same set.
+#[derive(Clone)]
+pub struct PidSet {
+    resource: ResourceArc<Members>,
+}
+
+struct Members {
+    members: Mutex<Vec<Member>>,
+}
+
+struct Member {
+    pid: LocalPid,
+    monitor: Monitor,
+}
+
+impl PidSet {
+    pub fn new() -> Self {
+        PidSet {
+            resource: ResourceArc::new(Members {
+                members: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Adds `pid` to the set, and starts monitoring it.
+    ///
+    /// Returns `false` if `pid` is already a member, or if the process is not alive.
+    pub fn subscribe(&self, env: Env, pid: &LocalPid) -> bool {
+        let mut members = self.resource.lock();
+        if members.iter().any(|member| member.pid == *pid) {
+            return false;
+        }
+
+        match self.resource.monitor(env, pid) {
+            Some(monitor) => {
+                members.push(Member {
+                    pid: pid.clone(),
+                    monitor,
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes `pid` from the set. Returns `false` if it was not a member.
+    pub fn unsubscribe(&self, env: Env, pid: &LocalPid) -> bool {
+        let mut members = self.resource.lock();
+        match members.iter().position(|member| member.pid == *pid) {
+            Some(index) => {
+                let member = members.swap_remove(index);
+                self.resource.demonitor(env, &member.monitor);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn contains(&self, pid: &LocalPid) -> bool {
+        self.resource.lock().iter().any(|member| member.pid == *pid)
+    }
+
+    pub fn len(&self) -> usize {
+        self.resource.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the pids of the members of the set.
+    pub fn pids(&self) -> Vec<LocalPid> {
+        self.resource
+            .lock()
+            .iter()
+            .map(|member| member.pid.clone())
+            .collect()
+    }
+
+    /// Sends `message` to all the members of the set, encoding it only once. Returns the number
+    /// of processes the message was sent to.
+    ///
+    /// Like `Env::send`, `env` must be the environment of the calling process, or the
+    /// environment of an `OwnedEnv` on a thread that is not managed by the Erlang VM.
+    pub fn broadcast<T: Encoder>(&self, env: Env, message: T) -> usize {
+        let message = message.encode(env);
+        let pids = self.pids();
+        for pid in &pids {
+            env.send(pid, message);
+        }
+        pids.len()
+    }
+}
+
+impl Default for PidSet {
+    fn default() -> Self {
+        PidSet::new()
+    }
+}
+
+impl Encoder for PidSet {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        self.resource.encode(env)
+    }
+}
+
+impl<'a> Decoder<'a> for PidSet {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        Ok(PidSet {
+            resource: term.decode()?,
+        })
+    }
+}
+
+impl Members {
+    fn lock(&self) -> MutexGuard<'_, Vec<Member>> {
+        // A panic while the lock is held can't leave the set inconsistent.
+        self.members
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl MonitorDown for Members {
+    /// Removes members that exit.
+    fn down(&self, _env: Env, _pid: LocalPid, monitor: Monitor) {
+        self.lock().retain(|member| member.monitor != monitor);
+    }
+}
+
+crate::resource_type!(Members, name = "PidSetMembers", monitor);
+
+/// Registers the resource type holding the members of `PidSet`s. Call this from the `load`
+/// callback.
+pub fn load(env: Env) -> bool {
+    crate::resource::register::<Members>(env)
+}
@@ -0,0 +1,109 @@
+//! A canonical External Term Format encoding, for hashing and signing terms.
+//!
+//! `Term::to_binary`, like `:erlang.term_to_binary/1`, writes the entries of maps in the order of
+//! their internal layout, which depends on how the map was built and on the size of the map. Two
+//! equal maps can therefore encode to different binaries. `Term::to_canonical_binary` writes the
+//! entries of every map, at any depth, sorted by key in term order, so that equal terms always
+//! encode to the same binary:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn digest(env: Env, payload: Term) -> String {
+//!     sha256_hex(payload.to_canonical_binary().as_slice())
+//! }
+//! ```
+//!
+//! The result is a valid encoding, which `:erlang.binary_to_term/1` decodes. Terms other than
+//! maps, tuples and lists are encoded by `enif_term_to_binary`, so the encoding of atoms and
+//! floats is only stable across releases of OTP that encode them the same way.
+
+use crate::types::tuple::get_tuple;
+use crate::{MapIterator, OwnedBinary, Term, TermType};
+
+const VERSION: u8 = 131;
+const SMALL_TUPLE_EXT: u8 = 104;
+const LARGE_TUPLE_EXT: u8 = 105;
+const NIL_EXT: u8 = 106;
+const LIST_EXT: u8 = 108;
+const MAP_EXT: u8 = 116;
+
+/// ## Canonical encoding
+impl<'a> Term<'a> {
+    /// Encodes the term in the External Term Format, with the entries of maps sorted by key.
+    ///
+    /// See the `canonical` module documentation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a binary can't be allocated.
+    pub fn to_canonical_binary(self) -> OwnedBinary {
+        let mut out = vec![VERSION];
+        write_term(&mut out, self);
+        let mut binary =
+            OwnedBinary::new(out.len()).expect("failed to allocate the canonical binary");
+        binary.as_mut_slice().copy_from_slice(&out);
+        binary
+    }
+}
+
+fn write_term(out: &mut Vec<u8>, term: Term) {
+    match term.get_type() {
+        TermType::Map => write_map(out, term),
+        TermType::Tuple => write_tuple(out, term),
+        TermType::List => write_list(out, term),
+        TermType::EmptyList => out.push(NIL_EXT),
+        _ => out.extend_from_slice(&term.to_binary().as_slice()[1..]),
+    }
+}
+
+fn write_map(out: &mut Vec<u8>, map: Term) {
+    // Keys equal in term order, like `1` and `1.0`, are ordered by their encoding.
+    let mut entries: Vec<_> = MapIterator::new(map)
+        .expect("a term of type map can be iterated")
+        .map(|(key, value)| {
+            let mut encoded = Vec::new();
+            write_term(&mut encoded, key);
+            (key, encoded, value)
+        })
+        .collect();
+    entries.sort_by(|(a, a_encoded, _), (b, b_encoded, _)| {
+        a.cmp(b).then_with(|| a_encoded.cmp(b_encoded))
+    });
+
+    out.push(MAP_EXT);
+    out.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+    for (_, encoded, value) in entries {
+        out.extend_from_slice(&encoded);
+        write_term(out, value);
+    }
+}
+
+fn write_tuple(out: &mut Vec<u8>, tuple: Term) {
+    let items = get_tuple(tuple).expect("a term of type tuple is a tuple");
+    if items.len() <= u8::MAX as usize {
+        out.extend_from_slice(&[SMALL_TUPLE_EXT, items.len() as u8]);
+    } else {
+        out.push(LARGE_TUPLE_EXT);
+        out.extend_from_slice(&(items.len() as u32).to_be_bytes());
+    }
+    for item in items {
+        write_term(out, item);
+    }
+}
+
+fn write_list(out: &mut Vec<u8>, list: Term) {
+    // The length is only known once the list is walked, since it can be improper.
+    out.push(LIST_EXT);
+    let length_at = out.len();
+    out.extend_from_slice(&[0; 4]);
+
+    let mut length: u32 = 0;
+    let mut tail = list;
+    while let Ok((head, rest)) = tail.list_get_cell() {
+        write_term(out, head);
+        length += 1;
+        tail = rest;
+    }
+    out[length_at..length_at + 4].copy_from_slice(&length.to_be_bytes());
+    write_term(out, tail);
+}
@@ -0,0 +1,155 @@
+//! Encoding of huge lists across several reschedules.
+//!
+//! Returning a list of millions of elements from a NIF encodes it in one go, which blocks a
+//! normal scheduler for far longer than the recommended millisecond. The usual remedy is a dirty
+//! scheduler. `ChunkedList` is an alternative for NIFs that should stay on normal schedulers: it
+//! encodes the list one chunk at a time, starting from the end, and yields back to the VM with
+//! `enif_schedule_nif` whenever the timeslice of the NIF is used up. The remaining items and the
+//! part of the list that is already built are carried over to the next call.
+//!
+//! The state is kept in a resource, whose type must be registered by calling
+//! `rustler::chunked::load(env)` from the `load` callback of the NIF library.
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn all_ids() -> ChunkedList<u64> {
+//!     ChunkedList::new(load_ids()).chunk_size(5_000)
+//! }
+//! ```
+//!
+//! Lists of at most `chunk_size` items are encoded right away.
+
+use crate::codegen_runtime::{NifReturnable, NifReturned, NIF_ENV, NIF_TERM};
+use crate::schedule::{consume_timeslice, SchedulerFlags};
+use crate::{Encoder, Env, ResourceArc, Term};
+use std::ffi::CString;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// The number of items encoded between two checks of the timeslice, unless configured otherwise.
+pub const DEFAULT_CHUNK_SIZE: usize = 1000;
+
+/// A list that is encoded in chunks, yielding to the VM between chunks when needed.
+///
+/// See the module documentation.
+pub struct ChunkedList<T> {
+    items: Vec<T>,
+    chunk_size: usize,
+}
+
+impl<T> ChunkedList<T> {
+    pub fn new(items: Vec<T>) -> Self {
+        ChunkedList {
+            items,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    /// Sets the number of items encoded between two checks of the timeslice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk_size must be positive");
+        self.chunk_size = chunk_size;
+        self
+    }
+}
+
+/// An encoding in progress, with the element type erased so that a single resource type and a
+/// single continuation function serve all lists.
+trait PendingList: Send {
+    /// Prepends the encoding of the last chunk of the remaining items to `tail`.
+    fn encode_chunk<'a>(&mut self, env: Env<'a>, tail: Term<'a>) -> Term<'a>;
+
+    fn is_done(&self) -> bool;
+}
+
+impl<T: Encoder + Send> PendingList for ChunkedList<T> {
+    fn encode_chunk<'a>(&mut self, env: Env<'a>, tail: Term<'a>) -> Term<'a> {
+        let start = self.items.len().saturating_sub(self.chunk_size);
+        self.items
+            .drain(start..)
+            .rev()
+            .fold(tail, |tail, item| tail.list_prepend(item.encode(env)))
+    }
+
+    fn is_done(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+struct EncodeState {
+    pending: Mutex<Box<dyn PendingList>>,
+}
+
+crate::resource_type!(EncodeState, name = "ChunkedListState");
+
+/// Registers the resource type holding the state of chunked encodings. Call this from the `load`
+/// callback.
+pub fn load(env: Env) -> bool {
+    crate::resource::register::<EncodeState>(env)
+}
+
+unsafe impl<T> NifReturnable for ChunkedList<T>
+where
+    T: Encoder + Send + 'static,
+{
+    unsafe fn into_returned(self, env: Env) -> NifReturned {
+        if self.items.len() <= self.chunk_size {
+            return NifReturned::Term(self.items.encode(env).as_c_arg());
+        }
+
+        let state = ResourceArc::new(EncodeState {
+            pending: Mutex::new(Box::new(self)),
+        });
+        encode_chunks(env, &state, Term::list_new_empty(env))
+    }
+}
+
+/// Encodes chunks until the list is complete, or the timeslice is used up.
+fn encode_chunks<'a>(
+    env: Env<'a>,
+    state: &ResourceArc<EncodeState>,
+    mut tail: Term<'a>,
+) -> NifReturned {
+    let mut pending = state.pending.lock().unwrap();
+    loop {
+        let started = Instant::now();
+        tail = pending.encode_chunk(env, tail);
+        if pending.is_done() {
+            return NifReturned::Term(tail.as_c_arg());
+        }
+
+        // A timeslice is about a millisecond, so every 10µs spent is 1% of it.
+        let percent = (started.elapsed().as_micros() / 10).clamp(1, 100) as i32;
+        if consume_timeslice(env, percent) {
+            return NifReturned::Reschedule {
+                fun_name: CString::new("rustler_chunked_list").unwrap(),
+                flags: SchedulerFlags::Normal,
+                fun: resume,
+                args: vec![state.encode(env).as_c_arg(), tail.as_c_arg()],
+            };
+        }
+    }
+}
+
+unsafe extern "C" fn resume(nif_env: NIF_ENV, argc: i32, argv: *const NIF_TERM) -> NIF_TERM {
+    let lifetime = ();
+    let env = Env::new(&lifetime, nif_env);
+    let args = std::slice::from_raw_parts(argv, argc as usize);
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let state: ResourceArc<EncodeState> = Term::new(env, args[0])
+            .decode()
+            .expect("chunked list state");
+        encode_chunks(env, &state, Term::new(env, args[1]))
+    }));
+
+    match result {
+        Ok(returned) => returned.apply(env),
+        Err(err) => crate::panic_handler::panicked(env, err).apply(env),
+    }
+}
@@ -1,9 +1,13 @@
 //! Functions used by runtime generated code. Should not be used.
 
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use std::fmt;
+use std::marker::PhantomData;
+use std::os::raw::c_char;
 
-use crate::{Encoder, Env, OwnedBinary, Term};
+use crate::load_data::LoadData;
+use crate::types::atom;
+use crate::{Binary, Decoder, Encoder, Env, OwnedBinary, Term};
 
 // Names used by the `rustler::init!` macro or other generated code.
 pub use crate::wrapper::exception::raise_exception;
@@ -15,6 +19,99 @@ pub use crate::wrapper::{
 #[cfg(windows)]
 pub use rustler_sys::{TWinDynNifCallbacks, WIN_DYN_NIF_CALLBACKS};
 
+/// Returns whether two NIFs are registered under the same name and arity. Used by `rustler::init!`
+/// to reject duplicates at compile time.
+pub const fn nif_conflicts(name: &str, arity: u32, other_name: &str, other_arity: u32) -> bool {
+    let (name, other_name) = (name.as_bytes(), other_name.as_bytes());
+    if arity != other_arity || name.len() != other_name.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < name.len() {
+        if name[i] != other_name[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Prepends `prefix` to the names of `funcs`. Used by the `prefix` option of `rustler::init!`.
+pub fn prefix_nif_names(prefix: &str, funcs: &mut [DEF_NIF_FUNC]) {
+    for func in funcs {
+        let name = unsafe { CStr::from_ptr(func.name as *const c_char) };
+        let mut prefixed = prefix.as_bytes().to_vec();
+        prefixed.extend_from_slice(name.to_bytes());
+        let prefixed = CString::new(prefixed).expect("NIF name prefixes can't contain NUL bytes");
+        // Leaked on purpose: the VM refers to the name as long as the library is loaded.
+        func.name = CString::into_raw(prefixed) as *const u8;
+    }
+}
+
+/// Returns the NIFs that Rustler adds to every library, depending on the enabled features.
+#[cfg(feature = "resource-tracking")]
+pub fn builtin_nifs() -> Vec<DEF_NIF_FUNC> {
+    vec![crate::resource_tracking::RESOURCES_NIF]
+}
+
+#[cfg(not(feature = "resource-tracking"))]
+pub fn builtin_nifs() -> Vec<DEF_NIF_FUNC> {
+    Vec::new()
+}
+
+/// The features of `rustler` that are reported by `__rustler_features__/0`, prefixed with
+/// `rustler/`.
+const RUSTLER_FEATURES: &[(&str, bool)] = &[
+    (
+        "rustler/alternative_nif_init_name",
+        cfg!(feature = "alternative_nif_init_name"),
+    ),
+    ("rustler/big_integer", cfg!(feature = "big_integer")),
+    ("rustler/chrono", cfg!(feature = "chrono")),
+    ("rustler/compress", cfg!(feature = "compress")),
+    ("rustler/decode-trace", cfg!(feature = "decode-trace")),
+    ("rustler/derive", cfg!(feature = "derive")),
+    ("rustler/dist", cfg!(feature = "dist")),
+    ("rustler/etf", cfg!(feature = "etf")),
+    (
+        "rustler/experimental-crash-guard",
+        cfg!(feature = "experimental-crash-guard"),
+    ),
+    ("rustler/indexmap", cfg!(feature = "indexmap")),
+    ("rustler/port", cfg!(feature = "port")),
+    ("rustler/regex", cfg!(feature = "regex")),
+    (
+        "rustler/resource-backtraces",
+        cfg!(feature = "resource-backtraces"),
+    ),
+    (
+        "rustler/resource-tracking",
+        cfg!(feature = "resource-tracking"),
+    ),
+    ("rustler/serde", cfg!(feature = "serde")),
+    ("rustler/text", cfg!(feature = "text")),
+    ("rustler/tokio", cfg!(feature = "tokio")),
+];
+
+/// Returns the result of the `__rustler_features__/0` NIF generated by `rustler::init!`: the
+/// enabled `features` of the library, followed by the enabled features of `rustler`.
+pub fn features_term(env: Env, features: &[(&str, bool)]) -> NIF_TERM {
+    let enabled: Vec<&str> = features
+        .iter()
+        .chain(RUSTLER_FEATURES)
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| *name)
+        .collect();
+    enabled.encode(env).as_c_arg()
+}
+
+/// Raises `{:error, :not_implemented}`, from the NIFs generated by `#[rustler::nif(feature = ...)]`
+/// when their feature is disabled.
+pub fn not_implemented(env: Env) -> NIF_TERM {
+    let reason = (atom::error(), atom::not_implemented()).encode(env);
+    unsafe { raise_exception(env.as_c_arg(), reason.as_c_arg()) }
+}
+
 pub unsafe trait NifReturnable {
     unsafe fn into_returned(self, env: Env) -> NifReturned;
 }
@@ -46,6 +143,52 @@ unsafe impl NifReturnable for OwnedBinary {
     }
 }
 
+unsafe impl NifReturnable for Vec<OwnedBinary> {
+    unsafe fn into_returned(self, env: Env) -> NifReturned {
+        let binaries: Vec<Binary> = self.into_iter().map(|owned| owned.release(env)).collect();
+        NifReturned::Term(binaries.encode(env).as_c_arg())
+    }
+}
+
+/// Maps a return value of `()` to `:ok`. Used by `#[rustler::nif(unit_ok)]`.
+pub trait UnitAsOk {
+    type Output: NifReturnable;
+
+    fn unit_as_ok(self) -> Self::Output;
+}
+
+impl UnitAsOk for () {
+    type Output = crate::types::atom::Atom;
+
+    fn unit_as_ok(self) -> Self::Output {
+        crate::types::atom::ok()
+    }
+}
+
+impl UnitAsOk for Result<(), crate::error::Error> {
+    type Output = Result<crate::types::atom::Atom, crate::error::Error>;
+
+    fn unit_as_ok(self) -> Self::Output {
+        self.map(|()| crate::types::atom::ok())
+    }
+}
+
+/// The result of a handler called by a `#[rustler::dispatch]` NIF. Handlers return different
+/// types, which are converted when the handler returns.
+pub struct Dispatched(NifReturned);
+
+impl Dispatched {
+    pub fn new<T: NifReturnable>(env: Env, returned: T) -> Self {
+        Dispatched(unsafe { returned.into_returned(env) })
+    }
+}
+
+unsafe impl NifReturnable for Dispatched {
+    unsafe fn into_returned(self, _env: Env) -> NifReturned {
+        self.0
+    }
+}
+
 pub enum NifReturned {
     Term(NIF_TERM),
     Raise(NIF_TERM),
@@ -116,6 +259,91 @@ pub unsafe fn handle_nif_init_call(
     }
 }
 
+/// Checks that the running VM provides at least version `major.minor` of the NIF API, and logs
+/// an error otherwise. Used by the `min_nif_version` option of `rustler::init!`.
+///
+/// # Unsafe
+///
+/// `r_env` must be the environment passed to the `load` callback, or that of a NIF call.
+pub unsafe fn check_nif_version(r_env: NIF_ENV, major: u32, minor: u32) -> bool {
+    let mut info = std::mem::MaybeUninit::<rustler_sys::ErlNifSysInfo>::zeroed();
+    rustler_sys::enif_system_info(
+        info.as_mut_ptr(),
+        std::mem::size_of::<rustler_sys::ErlNifSysInfo>(),
+    );
+    let info = info.assume_init();
+    let (running_major, running_minor) =
+        (info.nif_major_version as u32, info.nif_minor_version as u32);
+
+    if running_major > major || (running_major == major && running_minor >= minor) {
+        return true;
+    }
+
+    let message = format!(
+        "NIF library requires NIF version {}.{}, but the VM provides {}.{}",
+        major, minor, running_major, running_minor
+    );
+    crate::log::log(Env::new(&(), r_env), crate::log::Level::Error, &message);
+    false
+}
+
+/// Implemented by the functions accepted as `load` and `upgrade` callbacks by `rustler::init!`:
+/// functions taking the load data as a `Term`, and functions taking it as any type that can be
+/// decoded. `Marker` tells the two kinds apart.
+pub trait LoadCallback<Marker> {
+    fn call_load<'a>(&self, env: Env<'a>, load_info: Term<'a>) -> bool;
+}
+
+pub struct TermLoadData;
+pub struct DecodedLoadData<T>(PhantomData<T>);
+
+impl<F> LoadCallback<TermLoadData> for F
+where
+    F: for<'a> Fn(Env<'a>, Term<'a>) -> bool,
+{
+    fn call_load<'a>(&self, env: Env<'a>, load_info: Term<'a>) -> bool {
+        self(env, load_info)
+    }
+}
+
+impl<F, T> LoadCallback<DecodedLoadData<T>> for F
+where
+    F: for<'a> Fn(Env<'a>, T) -> bool,
+    T: for<'a> Decoder<'a>,
+{
+    fn call_load<'a>(&self, env: Env<'a>, load_info: Term<'a>) -> bool {
+        match LoadData::new(load_info).decode() {
+            Ok(load_data) => self(env, load_data),
+            Err(err) => {
+                err.report(env);
+                false
+            }
+        }
+    }
+}
+
+/// Calls the `load` or `upgrade` callback of `rustler::init!`.
+///
+/// # Unsafe
+///
+/// This takes arguments, including raw pointers, that must be correct.
+pub unsafe fn handle_load_call<F, M>(function: F, r_env: NIF_ENV, load_info: NIF_TERM) -> c_int
+where
+    F: LoadCallback<M>,
+{
+    let env = Env::new(&(), r_env);
+    if function.call_load(env, Term::new(env, load_info)) {
+        0
+    } else {
+        1
+    }
+}
+
+/// Returns what a NIF raises for the panic with payload `err`, for `rustler_export_nifs!`.
+pub fn panicked(env: Env, err: Box<dyn std::any::Any + Send>) -> NifReturned {
+    crate::panic_handler::panicked(env, err)
+}
+
 pub fn handle_nif_result<T>(
     result: std::thread::Result<Result<T, crate::error::Error>>,
     env: Env,
@@ -131,10 +359,7 @@ where
             },
             Err(err) => match err.downcast::<NifReturned>() {
                 Ok(ty) => NifReturned::Term(ty.apply(env)),
-                Err(_) => {
-                    let term = crate::types::atom::nif_panicked().as_c_arg();
-                    NifReturned::Raise(term)
-                }
+                Err(err) => crate::panic_handler::panicked(env, err),
             },
         }
     }
@@ -0,0 +1,231 @@
+//! Compression of binaries with gzip and zstd.
+//!
+//! The functions of this module compress and decompress straight into an `OwnedBinary`, which
+//! is grown as the output is written, instead of writing to a `Vec<u8>` first and copying it
+//! into a binary. Decompression takes the maximum size of its output, so that a small malicious
+//! input can't exhaust the memory of the VM:
+//!
+//! ```ignore
+//! #[rustler::nif(schedule = "DirtyCpu")]
+//! fn unpack<'a>(env: Env<'a>, data: Binary<'a>) -> NifResult<Binary<'a>> {
+//!     Ok(rustler::compress::gzip_decompress(&data, 16 * 1024 * 1024)?.release(env))
+//! }
+//! ```
+//!
+//! Other formats can be plugged in by implementing `Codec`, and used with `compress` and
+//! `decompress`.
+//!
+//! This module is only available with the `compress` feature.
+
+use crate::types::binary::{AllocError, OwnedBinary};
+use crate::{Encoder, Env, Error, Term};
+use std::fmt;
+use std::io::{self, Write};
+
+mod atoms {
+    crate::atoms! {
+        too_large,
+        invalid_data,
+        insufficient_memory,
+    }
+}
+
+/// A compression format.
+pub trait Codec {
+    /// Writes the compressed `data` to `output`.
+    fn compress(&self, data: &[u8], output: &mut dyn Write) -> io::Result<()>;
+
+    /// Writes the decompressed `data` to `output`.
+    fn decompress(&self, data: &[u8], output: &mut dyn Write) -> io::Result<()>;
+}
+
+/// The gzip format, with a compression level from 0 to 9.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Gzip {
+    pub level: u32,
+}
+
+impl Default for Gzip {
+    fn default() -> Self {
+        Gzip { level: 6 }
+    }
+}
+
+impl Codec for Gzip {
+    fn compress(&self, data: &[u8], output: &mut dyn Write) -> io::Result<()> {
+        let level = flate2::Compression::new(self.level);
+        let mut encoder = flate2::write::GzEncoder::new(output, level);
+        encoder.write_all(data)?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    fn decompress(&self, data: &[u8], output: &mut dyn Write) -> io::Result<()> {
+        // The reading decoder reports truncated input.
+        io::copy(&mut flate2::read::GzDecoder::new(data), output)?;
+        Ok(())
+    }
+}
+
+/// The zstd format, with a compression level from 1 to 22, or 0 for the default one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Zstd {
+    pub level: i32,
+}
+
+impl Codec for Zstd {
+    fn compress(&self, data: &[u8], output: &mut dyn Write) -> io::Result<()> {
+        zstd::stream::copy_encode(data, output, self.level)
+    }
+
+    fn decompress(&self, data: &[u8], output: &mut dyn Write) -> io::Result<()> {
+        zstd::stream::copy_decode(data, output)
+    }
+}
+
+/// The error returned when compressing or decompressing fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompressError {
+    /// The output would be larger than the maximum size.
+    TooLarge { max_size: usize },
+    /// The input is not valid compressed data.
+    InvalidData,
+    /// The output binary could not be allocated.
+    Alloc(AllocError),
+}
+
+impl fmt::Display for CompressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompressError::TooLarge { max_size } => {
+                write!(f, "the output is larger than {} bytes", max_size)
+            }
+            CompressError::InvalidData => write!(f, "invalid compressed data"),
+            CompressError::Alloc(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for CompressError {}
+
+impl Encoder for CompressError {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match self {
+            CompressError::TooLarge { .. } => atoms::too_large().encode(env),
+            CompressError::InvalidData => atoms::invalid_data().encode(env),
+            CompressError::Alloc(_) => atoms::insufficient_memory().encode(env),
+        }
+    }
+}
+
+impl From<AllocError> for CompressError {
+    fn from(err: AllocError) -> CompressError {
+        CompressError::Alloc(err)
+    }
+}
+
+impl From<CompressError> for Error {
+    fn from(err: CompressError) -> Error {
+        Error::Term(Box::new(err))
+    }
+}
+
+/// Compresses `data` with `codec` into a new binary.
+pub fn compress<C: Codec + ?Sized>(codec: &C, data: &[u8]) -> Result<OwnedBinary, CompressError> {
+    let mut output = BinaryOutput::new(data.len() / 2 + 64, usize::MAX)?;
+    let result = codec.compress(data, &mut output);
+    output.finish(result)
+}
+
+/// Decompresses `data` with `codec` into a new binary of at most `max_size` bytes.
+pub fn decompress<C: Codec + ?Sized>(
+    codec: &C,
+    data: &[u8],
+    max_size: usize,
+) -> Result<OwnedBinary, CompressError> {
+    let initial = data.len().saturating_mul(4).max(64).min(max_size);
+    let mut output = BinaryOutput::new(initial, max_size)?;
+    let result = codec.decompress(data, &mut output);
+    output.finish(result)
+}
+
+/// Compresses `data` with gzip, at the default level.
+pub fn gzip_compress(data: &[u8]) -> Result<OwnedBinary, CompressError> {
+    compress(&Gzip::default(), data)
+}
+
+/// Decompresses gzip `data` into a binary of at most `max_size` bytes.
+pub fn gzip_decompress(data: &[u8], max_size: usize) -> Result<OwnedBinary, CompressError> {
+    decompress(&Gzip::default(), data, max_size)
+}
+
+/// Compresses `data` with zstd, at the default level.
+pub fn zstd_compress(data: &[u8]) -> Result<OwnedBinary, CompressError> {
+    compress(&Zstd::default(), data)
+}
+
+/// Decompresses zstd `data` into a binary of at most `max_size` bytes.
+pub fn zstd_decompress(data: &[u8], max_size: usize) -> Result<OwnedBinary, CompressError> {
+    decompress(&Zstd::default(), data, max_size)
+}
+
+/// A binary that grows as it is written to, up to a maximum size.
+struct BinaryOutput {
+    binary: OwnedBinary,
+    len: usize,
+    max_size: usize,
+    /// The error that made the last write fail, which codecs report as an `io::Error`.
+    error: Option<CompressError>,
+}
+
+impl BinaryOutput {
+    fn new(capacity: usize, max_size: usize) -> Result<Self, CompressError> {
+        Ok(BinaryOutput {
+            binary: OwnedBinary::try_new(capacity)?,
+            len: 0,
+            max_size,
+            error: None,
+        })
+    }
+
+    fn fail(&mut self, error: CompressError) -> io::Error {
+        self.error = Some(error);
+        io::Error::other(error)
+    }
+
+    fn finish(mut self, result: io::Result<()>) -> Result<OwnedBinary, CompressError> {
+        if result.is_err() {
+            // Errors that were not raised by the output come from the input.
+            return Err(self.error.unwrap_or(CompressError::InvalidData));
+        }
+        if self.len < self.binary.len() && !self.binary.realloc(self.len) {
+            return Err(AllocError { size: self.len }.into());
+        }
+        Ok(self.binary)
+    }
+}
+
+impl Write for BinaryOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let needed = self.len.saturating_add(buf.len());
+        if needed > self.max_size {
+            let max_size = self.max_size;
+            return Err(self.fail(CompressError::TooLarge { max_size }));
+        }
+        if needed > self.binary.len() {
+            let size = needed
+                .max(self.binary.len().saturating_mul(2))
+                .min(self.max_size);
+            if !self.binary.realloc(size) {
+                return Err(self.fail(AllocError { size }.into()));
+            }
+        }
+        self.binary.as_mut_slice()[self.len..needed].copy_from_slice(buf);
+        self.len = needed;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+/*
+ * Signal handling for `rustler::crash_guard`.
+ *
+ * `sigsetjmp` can't be called from Rust, since it returns twice, so the guarded call is made
+ * from C: `rustler_crash_guard_call` saves the context, and the signal handler jumps back to
+ * it when a fault happens on the same thread while a call is guarded.
+ */
+
+#include <setjmp.h>
+#include <signal.h>
+#include <stddef.h>
+#include <string.h>
+
+static __thread sigjmp_buf *guard_jump = NULL;
+static __thread volatile sig_atomic_t guard_signal = 0;
+static __thread void *volatile guard_address = NULL;
+
+static struct sigaction previous_segv;
+static struct sigaction previous_bus;
+
+/* Hands a fault outside of guarded calls to the handler installed before ours. */
+static void chain(int sig, siginfo_t *info, void *context) {
+    const struct sigaction *previous = sig == SIGSEGV ? &previous_segv : &previous_bus;
+
+    if (previous->sa_flags & SA_SIGINFO) {
+        previous->sa_sigaction(sig, info, context);
+    } else if (previous->sa_handler != SIG_DFL && previous->sa_handler != SIG_IGN) {
+        previous->sa_handler(sig);
+    } else {
+        /* The faulting instruction triggers the default action, terminating the VM, when it is
+         * executed again. Our handler is only removed on the way out. */
+        signal(sig, SIG_DFL);
+    }
+}
+
+static void handler(int sig, siginfo_t *info, void *context) {
+    if (guard_jump != NULL) {
+        guard_signal = sig;
+        guard_address = info->si_addr;
+        siglongjmp(*guard_jump, 1);
+    }
+
+    chain(sig, info, context);
+}
+
+int rustler_crash_guard_install(void) {
+    struct sigaction action;
+    memset(&action, 0, sizeof(action));
+    action.sa_sigaction = handler;
+    /* No SA_ONSTACK: scheduler threads have no alternate signal stack, so stack overflows can't
+     * be caught anyway. */
+    action.sa_flags = SA_SIGINFO;
+    sigemptyset(&action.sa_mask);
+
+    if (sigaction(SIGSEGV, &action, &previous_segv) != 0) {
+        return -1;
+    }
+    if (sigaction(SIGBUS, &action, &previous_bus) != 0) {
+        return -1;
+    }
+    return 0;
+}
+
+/* Calls `callback(data)`. Returns 0 if it returned, or the number of the signal it raised, in
+ * which case the faulting address is stored in `address`. */
+int rustler_crash_guard_call(void (*callback)(void *), void *data, void **address) {
+    sigjmp_buf jump;
+    sigjmp_buf *outer = guard_jump;
+
+    if (sigsetjmp(jump, 1) != 0) {
+        guard_jump = outer;
+        *address = guard_address;
+        return guard_signal;
+    }
+
+    guard_jump = &jump;
+    callback(data);
+    guard_jump = outer;
+    return 0;
+}
@@ -0,0 +1,190 @@
+//! Converting native faults in delimited callouts into Erlang exceptions.
+//!
+//! A segmentation fault in a NIF takes the whole VM down, which makes bugs in unsafe code, or in
+//! C libraries called through FFI, painful to track down during development. This module is only
+//! available with the `experimental-crash-guard` feature. `guard` installs handlers for `SIGSEGV`
+//! and `SIGBUS`, and a fault raised by the guarded closure returns a `Crash` describing it instead:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn parse(data: Binary) -> NifResult<u64> {
+//!     let count = unsafe {
+//!         crash_guard::guard("ffi_parse", || ffi_parse(data.as_ptr(), data.len()))
+//!     }?;
+//!     Ok(count)
+//! }
+//! ```
+//!
+//! The `unsafe_crash_guard` flag of `#[rustler::nif]` guards the whole NIF function the same
+//! way. The `Crash` is raised as `{:native_crash, signal, address, callout}`.
+//!
+//! This is a development aid, not a recovery mechanism, and it is unsafe: the guarded closure is
+//! abandoned at the fault by a `siglongjmp`, without running destructors or releasing locks, and
+//! the memory it was working on may be corrupted. Never use it in production. Stack overflows are
+//! not caught, since scheduler threads have no alternate signal stack. Faults outside of guarded
+//! calls are handed to the handlers installed before. On other platforms than Unix, `guard` calls
+//! the closure directly and faults are not caught.
+
+use crate::{Encoder, Env, Error, Term};
+use std::fmt;
+
+mod atoms {
+    crate::atoms! {
+        native_crash,
+        sigsegv,
+        sigbus,
+    }
+}
+
+/// The signals caught by `guard`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signal {
+    Segv,
+    Bus,
+}
+
+/// A fault raised by a guarded callout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Crash {
+    pub signal: Signal,
+    /// The faulting address.
+    pub address: usize,
+    /// The name given to the callout.
+    pub callout: &'static str,
+}
+
+impl fmt::Display for Crash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at address {:#x} in {}",
+            match self.signal {
+                Signal::Segv => "SIGSEGV",
+                Signal::Bus => "SIGBUS",
+            },
+            self.address,
+            self.callout
+        )
+    }
+}
+
+impl std::error::Error for Crash {}
+
+impl Encoder for Crash {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        let signal = match self.signal {
+            Signal::Segv => atoms::sigsegv(),
+            Signal::Bus => atoms::sigbus(),
+        };
+        (atoms::native_crash(), signal, self.address, self.callout).encode(env)
+    }
+}
+
+impl From<Crash> for Error {
+    fn from(crash: Crash) -> Self {
+        Error::RaiseTerm(Box::new(crash))
+    }
+}
+
+/// Calls `f`, turning a `SIGSEGV` or `SIGBUS` raised by it into a `Crash`. `callout` names the
+/// guarded code in the diagnostics. See the module documentation.
+///
+/// # Safety
+///
+/// When `f` faults, it is abandoned where it stands. It must not hold locks, own values with
+/// destructors, or leave shared state half-updated at any point where it may fault, and the
+/// process must be treated as possibly corrupted afterwards.
+pub unsafe fn guard<F, R>(callout: &'static str, f: F) -> Result<R, Crash>
+where
+    F: FnOnce() -> R,
+{
+    imp::guard(callout, f)
+}
+
+#[cfg(unix)]
+mod imp {
+    use super::{Crash, Signal};
+    use std::os::raw::{c_int, c_void};
+    use std::panic::{self, AssertUnwindSafe};
+    use std::ptr;
+    use std::sync::Once;
+
+    extern "C" {
+        fn rustler_crash_guard_install() -> c_int;
+        fn rustler_crash_guard_call(
+            callback: extern "C" fn(*mut c_void),
+            data: *mut c_void,
+            address: *mut *mut c_void,
+        ) -> c_int;
+    }
+
+    static INSTALL: Once = Once::new();
+
+    struct Call<F, R> {
+        f: Option<F>,
+        result: Option<std::thread::Result<R>>,
+    }
+
+    extern "C" fn trampoline<F, R>(data: *mut c_void)
+    where
+        F: FnOnce() -> R,
+    {
+        let call = unsafe { &mut *(data as *mut Call<F, R>) };
+        let f = call.f.take().unwrap();
+        // Panics must not unwind through the C frame.
+        call.result = Some(panic::catch_unwind(AssertUnwindSafe(f)));
+    }
+
+    pub fn guard<F, R>(callout: &'static str, f: F) -> Result<R, Crash>
+    where
+        F: FnOnce() -> R,
+    {
+        INSTALL.call_once(|| {
+            if unsafe { rustler_crash_guard_install() } != 0 {
+                panic!("rustler::crash_guard: could not install the signal handlers");
+            }
+        });
+
+        let mut call = Call {
+            f: Some(f),
+            result: None,
+        };
+        let mut address = ptr::null_mut();
+        let signal = unsafe {
+            rustler_crash_guard_call(
+                trampoline::<F, R>,
+                &mut call as *mut Call<F, R> as *mut c_void,
+                &mut address,
+            )
+        };
+
+        if signal != 0 {
+            return Err(Crash {
+                signal: if signal == libc::SIGBUS {
+                    Signal::Bus
+                } else {
+                    Signal::Segv
+                },
+                address: address as usize,
+                callout,
+            });
+        }
+
+        match call.result.take().unwrap() {
+            Ok(result) => Ok(result),
+            Err(panic) => panic::resume_unwind(panic),
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use super::Crash;
+
+    pub fn guard<F, R>(_callout: &'static str, f: F) -> Result<R, Crash>
+    where
+        F: FnOnce() -> R,
+    {
+        Ok(f())
+    }
+}
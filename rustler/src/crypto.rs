@@ -0,0 +1,35 @@
+//! Helpers for NIFs handling secrets.
+//!
+//! Comparing a secret with `==` returns as soon as a byte differs, so the time it takes tells
+//! how many leading bytes of a guess are right. `secure_compare` always reads every byte:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn verify(token: Binary, expected: Binary) -> bool {
+//!     rustler::crypto::secure_compare(token, expected)
+//! }
+//! ```
+
+use crate::types::binary::Binary;
+use std::hint::black_box;
+
+/// Returns whether `a` and `b` are equal, in a time that only depends on their length.
+///
+/// Binaries of different lengths are not equal, and are told apart right away: the length of a
+/// secret is not hidden, like with `Plug.Crypto.secure_compare/2`.
+pub fn secure_compare(a: Binary, b: Binary) -> bool {
+    constant_time_eq(a.as_slice(), b.as_slice())
+}
+
+/// Returns whether `a` and `b` are equal, like `secure_compare`, for byte slices.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        // `black_box` keeps the compiler from turning the loop into one with an early exit.
+        diff = black_box(diff | (x ^ y));
+    }
+    diff == 0
+}
@@ -0,0 +1,138 @@
+//! Deadlines passed from Elixir, for timeouts that hold across the NIF boundary.
+//!
+//! By convention, a deadline is an absolute time of the monotonic clock of the VM, in
+//! milliseconds, or `:infinity`. A caller with a timeout computes it once, and passes it to
+//! every NIF doing work on its behalf:
+//!
+//! ```elixir
+//! deadline = System.monotonic_time(:millisecond) + timeout
+//! Native.search(index, query, deadline)
+//! ```
+//!
+//! The deadline is decoded as a `CallContext`, which long running work checks at checkpoints:
+//!
+//! ```ignore
+//! #[rustler::nif(schedule = "DirtyCpu")]
+//! fn search(index: ResourceArc<Index>, query: String, ctx: CallContext) -> NifResult<Vec<u64>> {
+//!     let mut hits = Vec::new();
+//!     for segment in index.segments() {
+//!         ctx.check()?;
+//!         hits.extend(segment.search(&query));
+//!     }
+//!     Ok(hits)
+//! }
+//! ```
+//!
+//! `CallContext::check` returns `{:error, :deadline_exceeded}` once the deadline has passed.
+//! `CallContext::remaining` can be used as the timeout of blocking calls, like
+//! `dist::Connection::set_read_timeout`.
+
+use crate::types::atom::Atom;
+use crate::{Decoder, Encoder, Env, Error, NifResult, Term};
+use rustler_sys::ErlNifTimeUnit;
+use std::time::Duration;
+
+mod atoms {
+    crate::atoms! {
+        infinity,
+        deadline_exceeded,
+    }
+}
+
+/// The deadline of a NIF call. See the module documentation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CallContext {
+    /// The deadline, in milliseconds of monotonic time.
+    deadline: Option<i64>,
+}
+
+impl CallContext {
+    /// A context without deadline.
+    pub fn infinite() -> Self {
+        CallContext { deadline: None }
+    }
+
+    /// A context whose deadline is `deadline`, in milliseconds of monotonic time.
+    pub fn with_deadline(deadline: i64) -> Self {
+        CallContext {
+            deadline: Some(deadline),
+        }
+    }
+
+    /// A context whose deadline is `timeout` from now.
+    pub fn with_timeout(timeout: Duration) -> Self {
+        let timeout = timeout.as_millis().min(i64::MAX as u128) as i64;
+        CallContext::with_deadline(now().saturating_add(timeout))
+    }
+
+    /// The deadline, in milliseconds of monotonic time, or `None` without deadline.
+    pub fn deadline(&self) -> Option<i64> {
+        self.deadline
+    }
+
+    /// The time left until the deadline, zero once it has passed, or `None` without deadline.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| Duration::from_millis(deadline.saturating_sub(now()).max(0) as u64))
+    }
+
+    /// Whether the deadline has passed.
+    pub fn deadline_exceeded(&self) -> bool {
+        match self.deadline {
+            Some(deadline) => now() >= deadline,
+            None => false,
+        }
+    }
+
+    /// Returns an error encoded as `{:error, :deadline_exceeded}` once the deadline has passed.
+    pub fn check(&self) -> NifResult<()> {
+        if self.deadline_exceeded() {
+            return Err(Error::Term(Box::new(atoms::deadline_exceeded())));
+        }
+        Ok(())
+    }
+
+    /// Returns the earliest of the deadlines of `self` and `other`, to give a part of the work a
+    /// shorter deadline.
+    pub fn min(self, other: CallContext) -> Self {
+        match (self.deadline, other.deadline) {
+            (Some(a), Some(b)) => CallContext::with_deadline(a.min(b)),
+            (Some(_), None) => self,
+            (None, _) => other,
+        }
+    }
+}
+
+impl Default for CallContext {
+    fn default() -> Self {
+        CallContext::infinite()
+    }
+}
+
+impl<'a> Decoder<'a> for CallContext {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        if let Ok(deadline) = term.decode::<i64>() {
+            return Ok(CallContext::with_deadline(deadline));
+        }
+        let atom: Atom = term.decode()?;
+        if atom == atoms::infinity() {
+            Ok(CallContext::infinite())
+        } else {
+            Err(Error::BadArg)
+        }
+    }
+}
+
+impl Encoder for CallContext {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match self.deadline {
+            Some(deadline) => deadline.encode(env),
+            None => atoms::infinity().encode(env),
+        }
+    }
+}
+
+/// Returns the monotonic time of the VM, in milliseconds, as `System.monotonic_time(:millisecond)`.
+fn now() -> i64 {
+    unsafe { rustler_sys::enif_monotonic_time(ErlNifTimeUnit::ERL_NIF_MSEC) }
+}
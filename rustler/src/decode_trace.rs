@@ -0,0 +1,102 @@
+//! Tracing of failures in derived decoders.
+//!
+//! When the `decode-trace` feature is enabled, decoders generated by the `Nif*` derive macros
+//! record every failure they encounter: the Rust type that was being decoded, the field or
+//! variant that failed, and the type of the offending term. The trail of failures for the
+//! current NIF call can be fetched with `take_trace()`, innermost failure first.
+//!
+//! This is useful to diagnose schema drift between Elixir and Rust code in production, where a
+//! `badarg` alone says very little. Failures of alternatives that were tried and rejected by
+//! `NifUntaggedEnum` decoders are recorded as well.
+//!
+//! Without the feature, recording is a no-op and `take_trace()` always returns an empty vector.
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn load_user(term: Term) -> NifResult<User> {
+//!     term.decode().map_err(|err| {
+//!         for failure in rustler::decode_trace::take_trace() {
+//!             eprintln!("{}", failure);
+//!         }
+//!         err
+//!     })
+//! }
+//! ```
+
+use crate::dynamic::TermType;
+use crate::Term;
+use std::fmt;
+
+/// A single failure recorded by a derived decoder.
+#[derive(Clone, Copy, Debug)]
+pub struct DecodeFailure {
+    /// Name of the Rust type being decoded.
+    pub type_name: &'static str,
+    /// Name of the field, tuple position or variant that failed, if any.
+    pub field: Option<&'static str>,
+    /// Type of the term that could not be decoded.
+    pub term_type: TermType,
+}
+
+impl fmt::Display for DecodeFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.field {
+            Some(field) => write!(
+                f,
+                "could not decode field {} of {} from {:?} term",
+                field, self.type_name, self.term_type
+            ),
+            None => write!(
+                f,
+                "could not decode {} from {:?} term",
+                self.type_name, self.term_type
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "decode-trace")]
+mod imp {
+    use super::DecodeFailure;
+    use std::cell::RefCell;
+
+    thread_local! {
+        pub static TRACE: RefCell<Vec<DecodeFailure>> = const { RefCell::new(Vec::new()) };
+    }
+}
+
+/// Records a decode failure. Used by generated code.
+#[doc(hidden)]
+#[inline(always)]
+pub fn record(type_name: &'static str, field: Option<&'static str>, term: Term) {
+    #[cfg(feature = "decode-trace")]
+    {
+        let failure = DecodeFailure {
+            type_name,
+            field,
+            term_type: term.get_type(),
+        };
+        imp::TRACE.with(|trace| trace.borrow_mut().push(failure));
+    }
+
+    #[cfg(not(feature = "decode-trace"))]
+    let _ = (type_name, field, term);
+}
+
+/// Forgets all recorded failures. Called at the start of every NIF call.
+#[doc(hidden)]
+#[inline(always)]
+pub fn clear() {
+    #[cfg(feature = "decode-trace")]
+    imp::TRACE.with(|trace| trace.borrow_mut().clear());
+}
+
+/// Returns and clears the failures recorded on the current thread during the current NIF call,
+/// innermost failure first.
+pub fn take_trace() -> Vec<DecodeFailure> {
+    #[cfg(feature = "decode-trace")]
+    return imp::TRACE.with(|trace| std::mem::take(&mut *trace.borrow_mut()));
+
+    #[cfg(not(feature = "decode-trace"))]
+    Vec::new()
+}
@@ -0,0 +1,410 @@
+//! A client for the Erlang distribution protocol, to talk to a running node from a standalone
+//! Rust program, like `erl_interface` does for C.
+//!
+//! A `Connection` connects to a node as a hidden node: it is not listed by `Node.list/0`, and it
+//! can't be connected to. Messages are `EtfTerm`s, which can be built from Rust values with
+//! `BuildTerm` and `rustler::backend::EtfBackend`. Derived `Encoder` implementations need a
+//! running VM, but `NifTuple`, `NifMap` and `NifUnitEnum` implement `BuildTerm` too with
+//! `#[rustler(build_term)]`:
+//!
+//! ```ignore
+//! use rustler::backend::{BuildTerm, EtfBackend};
+//! use rustler::dist::Connection;
+//! use rustler::NifTuple;
+//!
+//! #[derive(NifTuple)]
+//! #[rustler(build_term)]
+//! struct Log {
+//!     message: String,
+//!     level: u32,
+//! }
+//!
+//! let mut conn = Connection::connect("client@localhost", "app@localhost", "secret")?;
+//! let me = conn.make_pid();
+//! let log = Log { message: "hello".to_string(), level: 1 };
+//! conn.send_named(&me, "logger_server", &log.build(EtfBackend))?;
+//! let reply = conn.receive()?;
+//! ```
+//!
+//! The remote node sends ticks to check that the connection is alive, and disconnects if they
+//! are not answered: `receive` answers them, otherwise `tick` must be called regularly.
+//!
+//! Frames larger than `DEFAULT_MAX_FRAME_SIZE` are refused, so that a peer can't make the client
+//! allocate an arbitrary amount of memory. The limit can be changed with `set_max_frame_size`.
+//!
+//! This module is only available with the `dist` feature.
+
+use crate::etf::{EtfError, EtfTerm};
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+const EPMD_PORT: u16 = 4369;
+const PORT_PLEASE2_REQ: u8 = 122;
+const PORT2_RESP: u8 = 119;
+
+const PASS_THROUGH: u8 = 112;
+
+const DFLAG_EXTENDED_REFERENCES: u64 = 0x4;
+const DFLAG_FUN_TAGS: u64 = 0x10;
+const DFLAG_NEW_FUN_TAGS: u64 = 0x80;
+const DFLAG_EXTENDED_PIDS_PORTS: u64 = 0x100;
+const DFLAG_EXPORT_PTR_TAG: u64 = 0x200;
+const DFLAG_BIT_BINARIES: u64 = 0x400;
+const DFLAG_NEW_FLOATS: u64 = 0x800;
+const DFLAG_UTF8_ATOMS: u64 = 0x10000;
+const DFLAG_MAP_TAG: u64 = 0x20000;
+const DFLAG_BIG_CREATION: u64 = 0x40000;
+const DFLAG_HANDSHAKE_23: u64 = 0x1000000;
+const DFLAG_UNLINK_ID: u64 = 0x2000000;
+const DFLAG_V4_NC: u64 = 0x4_0000_0000;
+
+/// The capabilities of this client. They include the flags that OTP 26 requires.
+const FLAGS: u64 = DFLAG_EXTENDED_REFERENCES
+    | DFLAG_FUN_TAGS
+    | DFLAG_NEW_FUN_TAGS
+    | DFLAG_EXTENDED_PIDS_PORTS
+    | DFLAG_EXPORT_PTR_TAG
+    | DFLAG_BIT_BINARIES
+    | DFLAG_NEW_FLOATS
+    | DFLAG_UTF8_ATOMS
+    | DFLAG_MAP_TAG
+    | DFLAG_BIG_CREATION
+    | DFLAG_HANDSHAKE_23
+    | DFLAG_UNLINK_ID
+    | DFLAG_V4_NC;
+
+/// The size of the largest frame accepted by a new `Connection`, in bytes.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 64 * 1024 * 1024;
+
+const SEND: i64 = 2;
+const REG_SEND: i64 = 6;
+const SEND_TT: i64 = 12;
+const REG_SEND_TT: i64 = 16;
+const SEND_SENDER: i64 = 22;
+const SEND_SENDER_TT: i64 = 23;
+
+/// An error of the distribution client.
+#[derive(Debug)]
+pub enum DistError {
+    Io(io::Error),
+    /// A node name is not of the form `name@host`.
+    BadNodeName(String),
+    /// EPMD doesn't know the remote node.
+    NodeNotFound(String),
+    /// The remote node refused the connection, for example because of a wrong cookie.
+    Handshake(String),
+    /// The remote node sent a term that could not be read.
+    Etf(EtfError),
+    /// The remote node sent something unexpected.
+    Protocol(&'static str),
+    /// The remote node sent a frame of this size, larger than the maximum.
+    FrameTooLarge(usize),
+}
+
+impl fmt::Display for DistError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DistError::Io(ref err) => write!(f, "{}", err),
+            DistError::BadNodeName(ref name) => write!(f, "bad node name {:?}", name),
+            DistError::NodeNotFound(ref name) => write!(f, "node {} is not registered", name),
+            DistError::Handshake(ref reason) => write!(f, "handshake failed: {}", reason),
+            DistError::Etf(ref err) => write!(f, "bad term: {}", err),
+            DistError::Protocol(what) => write!(f, "protocol error: {}", what),
+            DistError::FrameTooLarge(size) => write!(f, "frame of {} bytes is too large", size),
+        }
+    }
+}
+
+impl std::error::Error for DistError {}
+
+impl From<io::Error> for DistError {
+    fn from(err: io::Error) -> Self {
+        DistError::Io(err)
+    }
+}
+
+impl From<EtfError> for DistError {
+    fn from(err: EtfError) -> Self {
+        DistError::Etf(err)
+    }
+}
+
+/// The recipient of a received message.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Recipient {
+    Pid(EtfTerm),
+    /// A registered name, which is whatever the remote node chose to send to.
+    Name(String),
+}
+
+/// A message received from the remote node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message {
+    /// The sender, when the remote node told it.
+    pub from: Option<EtfTerm>,
+    pub to: Recipient,
+    pub payload: EtfTerm,
+}
+
+/// A connection to a remote node. See the module documentation.
+pub struct Connection {
+    stream: TcpStream,
+    local: String,
+    remote: String,
+    creation: u32,
+    next_id: u32,
+    max_frame_size: usize,
+}
+
+impl Connection {
+    /// Connects to the node named `remote`, as the hidden node `local`. Both names have the form
+    /// `name@host`, and the port of `remote` is looked up with EPMD on its host.
+    pub fn connect(local: &str, remote: &str, cookie: &str) -> Result<Connection, DistError> {
+        split_node_name(local)?;
+        let (alive, host) = split_node_name(remote)?;
+
+        let port = epmd_port(host, alive)?;
+        let stream = TcpStream::connect((host, port))?;
+        stream.set_nodelay(true)?;
+
+        let mut conn = Connection {
+            stream,
+            local: local.to_string(),
+            remote: remote.to_string(),
+            creation: random_u32().max(1),
+            next_id: 1,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+        };
+        conn.handshake(cookie)?;
+        Ok(conn)
+    }
+
+    pub fn local_node(&self) -> &str {
+        &self.local
+    }
+
+    pub fn remote_node(&self) -> &str {
+        &self.remote
+    }
+
+    /// Sets the timeout of `receive`. With `None`, it blocks until a message arrives.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), DistError> {
+        Ok(self.stream.set_read_timeout(timeout)?)
+    }
+
+    /// Sets the size of the largest frame `receive` accepts, in bytes. Larger frames fail with
+    /// `DistError::FrameTooLarge`, after which the connection can't be used anymore.
+    pub fn set_max_frame_size(&mut self, size: usize) {
+        self.max_frame_size = size;
+    }
+
+    /// Returns a new pid of this node, that messages can be sent from, and replied to.
+    pub fn make_pid(&mut self) -> EtfTerm {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        EtfTerm::Pid {
+            node: self.local.clone(),
+            id,
+            serial: 0,
+            creation: self.creation,
+        }
+    }
+
+    /// Sends `message` to the process `to`, a pid of the remote node.
+    pub fn send(&mut self, to: &EtfTerm, message: &EtfTerm) -> Result<(), DistError> {
+        let control = EtfTerm::Tuple(vec![EtfTerm::Integer(SEND), EtfTerm::atom(""), to.clone()]);
+        self.write_message(&control, message)
+    }
+
+    /// Sends `message` to the process registered as `name` on the remote node. Replies are sent
+    /// to `from`, a pid returned by `make_pid`.
+    pub fn send_named(
+        &mut self,
+        from: &EtfTerm,
+        name: &str,
+        message: &EtfTerm,
+    ) -> Result<(), DistError> {
+        let control = EtfTerm::Tuple(vec![
+            EtfTerm::Integer(REG_SEND),
+            from.clone(),
+            EtfTerm::atom(""),
+            EtfTerm::atom(name),
+        ]);
+        self.write_message(&control, message)
+    }
+
+    /// Tells the remote node that this node is alive.
+    pub fn tick(&mut self) -> Result<(), DistError> {
+        Ok(self.stream.write_all(&[0; 4])?)
+    }
+
+    /// Waits for the next message sent to a process of this node. Ticks are answered, and the
+    /// other signals, like links and exits, are ignored.
+    pub fn receive(&mut self) -> Result<Message, DistError> {
+        loop {
+            let frame = self.read_frame(4)?;
+            if frame.is_empty() {
+                self.tick()?;
+                continue;
+            }
+            if frame[0] != PASS_THROUGH {
+                return Err(DistError::Protocol("expected a pass-through message"));
+            }
+
+            let (control, used) = EtfTerm::from_bytes(&frame[1..])?;
+            let payload = &frame[1 + used..];
+            if let Some(message) = parse_message(control, payload)? {
+                return Ok(message);
+            }
+        }
+    }
+
+    fn write_message(&mut self, control: &EtfTerm, message: &EtfTerm) -> Result<(), DistError> {
+        let mut data = vec![PASS_THROUGH];
+        data.extend_from_slice(&control.to_bytes()?);
+        data.extend_from_slice(&message.to_bytes()?);
+        self.write_frame(4, &data)
+    }
+
+    fn handshake(&mut self, cookie: &str) -> Result<(), DistError> {
+        let mut name = vec![b'N'];
+        name.extend_from_slice(&FLAGS.to_be_bytes());
+        name.extend_from_slice(&self.creation.to_be_bytes());
+        name.extend_from_slice(&(self.local.len() as u16).to_be_bytes());
+        name.extend_from_slice(self.local.as_bytes());
+        self.write_frame(2, &name)?;
+
+        let status = self.read_frame(2)?;
+        match status.split_first() {
+            Some((b's', b"ok")) | Some((b's', b"ok_simultaneous")) => {}
+            Some((b's', status)) => {
+                return Err(DistError::Handshake(
+                    String::from_utf8_lossy(status).into_owned(),
+                ))
+            }
+            _ => return Err(DistError::Protocol("expected the handshake status")),
+        }
+
+        // 'N', flags, challenge, creation and the length of the name.
+        let challenge = self.read_frame(2)?;
+        if challenge.len() < 19 || challenge[0] != b'N' {
+            return Err(DistError::Protocol("expected a challenge"));
+        }
+        let their_challenge =
+            u32::from_be_bytes([challenge[9], challenge[10], challenge[11], challenge[12]]);
+
+        let our_challenge = random_u32();
+        let mut reply = vec![b'r'];
+        reply.extend_from_slice(&our_challenge.to_be_bytes());
+        reply.extend_from_slice(&digest(cookie, their_challenge));
+        self.write_frame(2, &reply)?;
+
+        // The remote node closes the connection when our digest is wrong.
+        let ack = self.read_frame(2).map_err(|_| {
+            DistError::Handshake("connection closed, the cookie is probably wrong".to_string())
+        })?;
+        match ack.split_first() {
+            Some((b'a', their_digest)) if *their_digest == digest(cookie, our_challenge) => Ok(()),
+            Some((b'a', _)) => Err(DistError::Handshake("the cookies don't match".to_string())),
+            _ => Err(DistError::Protocol(
+                "expected the challenge acknowledgement",
+            )),
+        }
+    }
+
+    /// Reads a frame prefixed with its length, in `header` bytes.
+    fn read_frame(&mut self, header: usize) -> Result<Vec<u8>, DistError> {
+        let mut len = [0; 4];
+        self.stream.read_exact(&mut len[4 - header..])?;
+        let len = u32::from_be_bytes(len) as usize;
+        if len > self.max_frame_size {
+            return Err(DistError::FrameTooLarge(len));
+        }
+        let mut frame = vec![0; len];
+        self.stream.read_exact(&mut frame)?;
+        Ok(frame)
+    }
+
+    fn write_frame(&mut self, header: usize, data: &[u8]) -> Result<(), DistError> {
+        let len = (data.len() as u32).to_be_bytes();
+        let mut frame = Vec::with_capacity(header + data.len());
+        frame.extend_from_slice(&len[4 - header..]);
+        frame.extend_from_slice(data);
+        Ok(self.stream.write_all(&frame)?)
+    }
+}
+
+/// Turns a control message and its payload into a `Message`, if it sends one.
+fn parse_message(control: EtfTerm, payload: &[u8]) -> Result<Option<Message>, DistError> {
+    let elements = match control {
+        EtfTerm::Tuple(elements) => elements,
+        _ => return Err(DistError::Protocol("the control message is not a tuple")),
+    };
+
+    let (from, to) = match (elements.first(), elements.len()) {
+        (Some(EtfTerm::Integer(SEND)), 3) | (Some(EtfTerm::Integer(SEND_TT)), 4) => {
+            (None, Recipient::Pid(elements[2].clone()))
+        }
+        (Some(EtfTerm::Integer(SEND_SENDER)), 3) | (Some(EtfTerm::Integer(SEND_SENDER_TT)), 4) => (
+            Some(elements[1].clone()),
+            Recipient::Pid(elements[2].clone()),
+        ),
+        (Some(EtfTerm::Integer(REG_SEND)), 4) | (Some(EtfTerm::Integer(REG_SEND_TT)), 5) => {
+            match elements[3] {
+                EtfTerm::Atom(ref name) => {
+                    (Some(elements[1].clone()), Recipient::Name(name.clone()))
+                }
+                _ => return Err(DistError::Protocol("bad registered name")),
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    let (payload, _) = EtfTerm::from_bytes(payload)?;
+    Ok(Some(Message { from, to, payload }))
+}
+
+/// Splits `name@host`.
+fn split_node_name(name: &str) -> Result<(&str, &str), DistError> {
+    let mut parts = name.splitn(2, '@');
+    match (parts.next(), parts.next()) {
+        (Some(alive), Some(host)) if !alive.is_empty() && !host.is_empty() => Ok((alive, host)),
+        _ => Err(DistError::BadNodeName(name.to_string())),
+    }
+}
+
+/// Asks the EPMD of `host` for the distribution port of `alive`.
+fn epmd_port(host: &str, alive: &str) -> Result<u16, DistError> {
+    let mut stream = TcpStream::connect((host, EPMD_PORT))?;
+    let mut request = Vec::with_capacity(3 + alive.len());
+    request.extend_from_slice(&(1 + alive.len() as u16).to_be_bytes());
+    request.push(PORT_PLEASE2_REQ);
+    request.extend_from_slice(alive.as_bytes());
+    stream.write_all(&request)?;
+
+    let mut response = [0; 2];
+    stream.read_exact(&mut response)?;
+    match response {
+        [PORT2_RESP, 0] => {
+            let mut port = [0; 2];
+            stream.read_exact(&mut port)?;
+            Ok(u16::from_be_bytes(port))
+        }
+        [PORT2_RESP, _] => Err(DistError::NodeNotFound(format!("{}@{}", alive, host))),
+        _ => Err(DistError::Protocol("unexpected EPMD response")),
+    }
+}
+
+/// The digest proving the knowledge of the cookie, for `challenge`.
+fn digest(cookie: &str, challenge: u32) -> [u8; 16] {
+    md5::compute(format!("{}{}", cookie, challenge)).0
+}
+
+/// A random number, from the randomly seeded keys of `RandomState`.
+fn random_u32() -> u32 {
+    RandomState::new().build_hasher().finish() as u32
+}
@@ -1,23 +1,55 @@
 use crate::wrapper::check;
 use crate::Term;
 
-#[derive(Debug)]
+/// The kind of a term, as returned by `Term::get_type()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TermType {
     Atom,
+    /// A binary, i.e. a bitstring whose size is a whole number of bytes.
     Binary,
     EmptyList,
+    /// The special term returned by `enif_raise_exception`, never passed to NIFs.
     Exception,
     Fun,
+    /// A non-empty list.
     List,
     Map,
+    /// An integer or a float.
     Number,
     Pid,
     Port,
     Ref,
     Tuple,
+    /// A bitstring which is not a binary, or a term of a type added in a later OTP release.
     Unknown,
 }
 
+/// Returns the kind of `term` with a single call to `enif_term_type`.
+#[cfg(nif_version_2_15)]
+pub fn get_type(term: Term) -> TermType {
+    use rustler_sys::ErlNifTermType::*;
+
+    let env = term.get_env().as_c_arg();
+    match unsafe { check::term_type(env, term.as_c_arg()) } {
+        ERL_NIF_TERM_TYPE_ATOM => TermType::Atom,
+        // Binaries are reported as bitstrings, like the bitstrings of any size.
+        ERL_NIF_TERM_TYPE_BITSTRING if term.is_binary() => TermType::Binary,
+        ERL_NIF_TERM_TYPE_FLOAT | ERL_NIF_TERM_TYPE_INTEGER => TermType::Number,
+        ERL_NIF_TERM_TYPE_FUN => TermType::Fun,
+        ERL_NIF_TERM_TYPE_LIST if term.is_empty_list() => TermType::EmptyList,
+        ERL_NIF_TERM_TYPE_LIST => TermType::List,
+        ERL_NIF_TERM_TYPE_MAP => TermType::Map,
+        ERL_NIF_TERM_TYPE_PID => TermType::Pid,
+        ERL_NIF_TERM_TYPE_PORT => TermType::Port,
+        ERL_NIF_TERM_TYPE_REFERENCE => TermType::Ref,
+        ERL_NIF_TERM_TYPE_TUPLE => TermType::Tuple,
+        _ => TermType::Unknown,
+    }
+}
+
+/// Returns the kind of `term` by checking each kind in turn, as `enif_term_type` is only
+/// available from NIF version 2.15.
+#[cfg(not(nif_version_2_15))]
 pub fn get_type(term: Term) -> TermType {
     if term.is_atom() {
         TermType::Atom
@@ -58,9 +90,11 @@ macro_rules! impl_check {
 
 /// ## Type checks
 impl<'a> Term<'a> {
-    /// Returns an enum representing which type the term is.
-    /// Note that using the individual `is_*` functions is more
-    /// efficient for checking a single type.
+    /// Returns an enum representing which type the term is, to branch on the kind of a term
+    /// without probing each kind with the `is_*` functions.
+    ///
+    /// From NIF version 2.15 (OTP 22), this is a single call to `enif_term_type`. Checking for a
+    /// single type is still as cheap with the corresponding `is_*` function.
     pub fn get_type(self) -> TermType {
         get_type(self)
     }
@@ -0,0 +1,104 @@
+//! Calling resources of other NIF libraries, with `enif_dynamic_resource_call`.
+//!
+//! A resource type registered with the `dyncall` option of `resource!` can be called by any NIF
+//! library loaded in the VM, which identifies it by the module of the library that opened it and
+//! the name it was registered with. This lets a library use, say, the socket resources of
+//! another one without depending on it:
+//!
+//! ```ignore
+//! // In the library owning the sockets, loaded by `MyApp.Sockets`.
+//! #[repr(C)]
+//! pub struct RawFd {
+//!     pub fd: i32,
+//! }
+//!
+//! unsafe impl CallData for RawFd {}
+//!
+//! impl DynamicCall for Socket {
+//!     type CallData = RawFd;
+//!
+//!     fn dynamic_call(&self, _env: Env, data: &mut RawFd) {
+//!         data.fd = self.stream.as_raw_fd();
+//!     }
+//! }
+//!
+//! rustler::resource!(Socket, env, name = "MyApp.Socket", dyncall);
+//!
+//! // In another library, with the same definition of `RawFd`.
+//! #[rustler::nif]
+//! fn socket_fd(env: Env, socket: Term) -> NifResult<i32> {
+//!     let mut data = RawFd { fd: -1 };
+//!     env.call_dynamic(atoms::sockets_module(), "MyApp.Socket", socket, &mut data)?;
+//!     Ok(data.fd)
+//! }
+//! ```
+//!
+//! The VM does not check the data passed between the libraries, so both must use the same
+//! `#[repr(C)]` definition of it.
+//!
+//! This module is only available with NIF 2.16 and later.
+
+use crate::resource::{resource_data, ResourceTypeProvider};
+use crate::wrapper::c_void;
+use crate::{Atom, Encoder, Env, Error, NifResult, Term};
+use rustler_sys::ErlNifEnv;
+
+/// The data passed between the caller and the callee of a dynamic call.
+///
+/// # Safety
+///
+/// The type must be `#[repr(C)]`, and the callee and its callers must use the same definition
+/// of it.
+pub unsafe trait CallData {}
+
+/// The callback of resources that can be called with `Env::call_dynamic`.
+pub trait DynamicCall: ResourceTypeProvider {
+    type CallData: CallData;
+
+    /// Called with the data passed to `Env::call_dynamic`, from the calling process.
+    fn dynamic_call(&self, env: Env, data: &mut Self::CallData);
+}
+
+pub(crate) unsafe extern "C" fn dyncall<T: DynamicCall>(
+    env: *mut ErlNifEnv,
+    obj: *mut c_void,
+    call_data: *mut c_void,
+) {
+    let resource: &T = resource_data(obj);
+    let lifetime = ();
+    resource.dynamic_call(
+        Env::new(&lifetime, env),
+        &mut *(call_data as *mut T::CallData),
+    );
+}
+
+impl<'a> Env<'a> {
+    /// Calls the resource `resource` with `data`. Its type must have been registered with the
+    /// `dyncall` option of `resource!` and the name `name`, by the NIF library of `module`.
+    ///
+    /// # Errors
+    ///
+    /// If `resource` is not a resource of that type, `Error::BadArg` is returned.
+    pub fn call_dynamic<D: CallData>(
+        self,
+        module: Atom,
+        name: &str,
+        resource: Term<'a>,
+        data: &mut D,
+    ) -> NifResult<()> {
+        let name = Atom::from_str(self, name)?;
+        let result = unsafe {
+            rustler_sys::enif_dynamic_resource_call(
+                self.as_c_arg(),
+                module.encode(self).as_c_arg(),
+                name.encode(self).as_c_arg(),
+                resource.as_c_arg(),
+                data as *mut D as *mut c_void,
+            )
+        };
+        if result != 0 {
+            return Err(Error::BadArg);
+        }
+        Ok(())
+    }
+}
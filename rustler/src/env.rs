@@ -1,6 +1,9 @@
+use crate::schedule::Budgeted;
+use crate::types::atom;
 use crate::types::LocalPid;
 use crate::wrapper::{NIF_ENV, NIF_TERM};
-use crate::{Encoder, Term};
+use crate::{Encoder, Error, Term};
+use std::fmt;
 use std::marker::PhantomData;
 use std::ptr;
 use std::sync::{Arc, Weak};
@@ -51,6 +54,11 @@ impl<'a> Env<'a> {
         self.env
     }
 
+    /// Returns a new reference, unique like the ones made by `make_ref/0`.
+    pub fn make_ref(self) -> Term<'a> {
+        unsafe { Term::new(self, rustler_sys::enif_make_ref(self.as_c_arg())) }
+    }
+
     /// Convenience method for building a tuple `{error, Reason}`.
     pub fn error_tuple<T>(self, reason: T) -> Term<'a>
     where
@@ -98,6 +106,28 @@ impl<'a> Env<'a> {
         }
     }
 
+    /// Reports that the NIF used `percent` of its timeslice, since the start of the call or the
+    /// previous report, and returns whether the timeslice is used up. `percent` is clamped to
+    /// 1..=100.
+    ///
+    /// A NIF on a normal scheduler that gets `true` should return soon, or yield with
+    /// `enif_schedule_nif`, as `rustler::yielding` does.
+    pub fn consume_timeslice(self, percent: i32) -> bool {
+        crate::schedule::consume_timeslice(self, percent.clamp(1, 100))
+    }
+
+    /// Wraps `iter` in an iterator that stops once the timeslice of the NIF is used up, and
+    /// gives back the remaining items:
+    ///
+    /// ```ignore
+    /// let mut budgeted = env.budgeted(items.into_iter());
+    /// let processed: Vec<_> = budgeted.by_ref().map(process).collect();
+    /// let remaining: Vec<_> = budgeted.into_inner().collect();
+    /// ```
+    pub fn budgeted<I: IntoIterator>(self, iter: I) -> Budgeted<'a, I::IntoIter> {
+        Budgeted::new(self, iter.into_iter())
+    }
+
     /// Decodes binary data to a term.
     ///
     /// Follows the erlang
@@ -142,9 +172,22 @@ unsafe impl Send for OwnedEnv {}
 
 impl OwnedEnv {
     /// Allocates a new process-independent environment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the environment can't be allocated. Use `try_new()` to handle that case.
     pub fn new() -> OwnedEnv {
-        OwnedEnv {
-            env: Arc::new(unsafe { rustler_sys::enif_alloc_env() }),
+        OwnedEnv::try_new().expect("enif_alloc_env failed")
+    }
+
+    /// Allocates a new process-independent environment, or fails with `EnvAllocError` if
+    /// allocation fails.
+    pub fn try_new() -> Result<OwnedEnv, EnvAllocError> {
+        let env = unsafe { rustler_sys::enif_alloc_env() };
+        if env.is_null() {
+            Err(EnvAllocError)
+        } else {
+            Ok(OwnedEnv { env: Arc::new(env) })
         }
     }
 
@@ -176,13 +219,23 @@ impl OwnedEnv {
             panic!("send_and_clear: current thread is managed");
         }
 
+        self.send_unchecked(recipient, closure);
+    }
+
+    /// Like `send_and_clear`, without checking the calling thread. Returns `false` if
+    /// `recipient` is not alive.
+    pub(crate) fn send_unchecked<F>(&mut self, recipient: &LocalPid, closure: F) -> bool
+    where
+        F: for<'a> FnOnce(Env<'a>) -> Term<'a>,
+    {
         let message = self.run(|env| closure(env).as_c_arg());
 
-        unsafe {
-            rustler_sys::enif_send(ptr::null_mut(), recipient.as_c_arg(), *self.env, message);
-        }
+        let sent = unsafe {
+            rustler_sys::enif_send(ptr::null_mut(), recipient.as_c_arg(), *self.env, message)
+        };
 
         self.clear();
+        sent != 0
     }
 
     /// Free all terms in this environment and clear it for reuse.
@@ -237,6 +290,33 @@ impl OwnedEnv {
     }
 }
 
+/// The error of an `OwnedEnv` that couldn't be allocated.
+///
+/// Encodes as `:insufficient_memory`, and converts into an `Error` returning
+/// `{:error, :insufficient_memory}`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EnvAllocError;
+
+impl fmt::Display for EnvAllocError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("could not allocate a process-independent environment")
+    }
+}
+
+impl std::error::Error for EnvAllocError {}
+
+impl Encoder for EnvAllocError {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        atom::insufficient_memory().encode(env)
+    }
+}
+
+impl From<EnvAllocError> for Error {
+    fn from(err: EnvAllocError) -> Error {
+        Error::Term(Box::new(err))
+    }
+}
+
 impl Drop for OwnedEnv {
     fn drop(&mut self) {
         unsafe {
@@ -0,0 +1,615 @@
+//! A pure-Rust reader and writer for the
+//! [External Term Format](http://erlang.org/doc/apps/erts/erl_ext_dist.html).
+//!
+//! Unlike `Env::binary_to_term` and `Term::to_binary`, this module does not need an `Env`, so
+//! it can be used from any thread, for example to parse ETF files on a background thread while
+//! the schedulers stay free. The resulting `EtfTerm` values can later be encoded into terms like
+//! any other Rust value, or decoded from terms.
+//!
+//! This module is only available with the `etf` feature.
+//!
+//! ```
+//! use rustler::etf::EtfTerm;
+//!
+//! let term = EtfTerm::Tuple(vec![EtfTerm::atom("ok"), EtfTerm::Integer(42)]);
+//! let bytes = term.to_bytes().unwrap();
+//! assert_eq!(bytes, [131, 104, 2, 119, 2, b'o', b'k', 97, 42]);
+//!
+//! let (decoded, used) = EtfTerm::from_bytes(&bytes).unwrap();
+//! assert_eq!(decoded, term);
+//! assert_eq!(used, bytes.len());
+//! ```
+//!
+//! Ports, funs and compressed terms are not supported.
+
+use crate::types::tuple::make_tuple;
+use crate::{Binary, Decoder, Encoder, Env, Error, NifResult, Term};
+use std::convert::TryFrom;
+use std::fmt;
+
+/// The maximum nesting of the terms read by `EtfTerm::from_bytes`, which reads nested terms
+/// recursively and would otherwise overflow the stack of the scheduler on malicious input.
+pub const MAX_DEPTH: usize = 512;
+
+/// The maximum number of characters of an atom.
+const MAX_ATOM_CHARACTERS: usize = 255;
+
+const VERSION: u8 = 131;
+
+const NEW_FLOAT_EXT: u8 = 70;
+const BIT_BINARY_EXT: u8 = 77;
+const NEW_PID_EXT: u8 = 88;
+const NEWER_REFERENCE_EXT: u8 = 90;
+const SMALL_INTEGER_EXT: u8 = 97;
+const INTEGER_EXT: u8 = 98;
+const FLOAT_EXT: u8 = 99;
+const ATOM_EXT: u8 = 100;
+const PID_EXT: u8 = 103;
+const SMALL_TUPLE_EXT: u8 = 104;
+const LARGE_TUPLE_EXT: u8 = 105;
+const NIL_EXT: u8 = 106;
+const STRING_EXT: u8 = 107;
+const LIST_EXT: u8 = 108;
+const BINARY_EXT: u8 = 109;
+const SMALL_BIG_EXT: u8 = 110;
+const LARGE_BIG_EXT: u8 = 111;
+const NEW_REFERENCE_EXT: u8 = 114;
+const SMALL_ATOM_EXT: u8 = 115;
+const MAP_EXT: u8 = 116;
+const ATOM_UTF8_EXT: u8 = 118;
+const SMALL_ATOM_UTF8_EXT: u8 = 119;
+
+/// An Erlang term that lives in Rust memory, independently of any `Env`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EtfTerm {
+    /// An integer that fits in an `i64`.
+    Integer(i64),
+    /// An integer that does not fit in an `i64`. `digits` is the magnitude, in little-endian
+    /// order.
+    BigInteger {
+        negative: bool,
+        digits: Vec<u8>,
+    },
+    Float(f64),
+    Atom(String),
+    Binary(Vec<u8>),
+    /// A bitstring whose last byte only has `bits` significant bits, in its high bits.
+    BitBinary {
+        data: Vec<u8>,
+        bits: u8,
+    },
+    Tuple(Vec<EtfTerm>),
+    /// A proper list. The empty list is `List(vec![])`.
+    List(Vec<EtfTerm>),
+    /// A list whose tail is not the empty list.
+    ImproperList(Vec<EtfTerm>, Box<EtfTerm>),
+    Map(Vec<(EtfTerm, EtfTerm)>),
+    /// A process identifier of the node named `node`.
+    Pid {
+        node: String,
+        id: u32,
+        serial: u32,
+        creation: u32,
+    },
+    Reference {
+        node: String,
+        creation: u32,
+        ids: Vec<u32>,
+    },
+}
+
+/// An error while reading the External Term Format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EtfError {
+    /// The data ended in the middle of a term.
+    UnexpectedEnd,
+    /// The data does not start with the version byte `131`.
+    BadVersion(u8),
+    /// The term uses a tag that is unknown or not supported by this module.
+    UnsupportedTag(u8),
+    /// An atom or float is not valid.
+    Invalid(&'static str),
+    /// Terms are nested deeper than `MAX_DEPTH`.
+    TooDeep,
+    /// A binary, tuple, list, map or reference is too large for the length field of the format.
+    TooLarge(&'static str),
+}
+
+impl fmt::Display for EtfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EtfError::UnexpectedEnd => write!(f, "unexpected end of data"),
+            EtfError::BadVersion(version) => write!(f, "bad version byte {}", version),
+            EtfError::UnsupportedTag(tag) => write!(f, "unsupported tag {}", tag),
+            EtfError::Invalid(what) => write!(f, "invalid {}", what),
+            EtfError::TooDeep => write!(f, "terms nested deeper than {}", MAX_DEPTH),
+            EtfError::TooLarge(what) => write!(f, "{} too large", what),
+        }
+    }
+}
+
+impl std::error::Error for EtfError {}
+
+impl EtfTerm {
+    /// Convenience constructor for atoms.
+    pub fn atom(name: &str) -> EtfTerm {
+        EtfTerm::Atom(name.to_string())
+    }
+
+    /// Serializes this term, including the leading version byte.
+    ///
+    /// Fails with `EtfError::Invalid` for atoms of more than 255 characters, and with
+    /// `EtfError::TooLarge` for terms whose length doesn't fit in the format.
+    ///
+    /// ```
+    /// use rustler::etf::{EtfError, EtfTerm};
+    ///
+    /// let atom = EtfTerm::atom(&"a".repeat(256));
+    /// assert_eq!(atom.to_bytes(), Err(EtfError::Invalid("atom")));
+    /// ```
+    pub fn to_bytes(&self) -> Result<Vec<u8>, EtfError> {
+        let mut out = vec![VERSION];
+        self.write(&mut out)?;
+        Ok(out)
+    }
+
+    /// Parses a term, which must start with the version byte. Returns the term and the number
+    /// of bytes it used, so that consecutive terms can be read from the same buffer.
+    ///
+    /// Fails with `EtfError::TooDeep` if terms are nested deeper than `MAX_DEPTH`.
+    pub fn from_bytes(data: &[u8]) -> Result<(EtfTerm, usize), EtfError> {
+        let mut reader = Reader {
+            data,
+            pos: 0,
+            depth: 0,
+        };
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(EtfError::BadVersion(version));
+        }
+        let term = reader.term()?;
+        Ok((term, reader.pos))
+    }
+
+    fn write(&self, out: &mut Vec<u8>) -> Result<(), EtfError> {
+        match *self {
+            EtfTerm::Integer(value) => {
+                if let Ok(small) = u8::try_from(value) {
+                    out.push(SMALL_INTEGER_EXT);
+                    out.push(small);
+                } else if let Ok(int) = i32::try_from(value) {
+                    out.push(INTEGER_EXT);
+                    out.extend_from_slice(&int.to_be_bytes());
+                } else {
+                    let mut digits = value.unsigned_abs().to_le_bytes().to_vec();
+                    while digits.last() == Some(&0) {
+                        digits.pop();
+                    }
+                    write_big(out, value < 0, &digits)?;
+                }
+            }
+            EtfTerm::BigInteger {
+                negative,
+                ref digits,
+            } => write_big(out, negative, digits)?,
+            EtfTerm::Float(value) => {
+                out.push(NEW_FLOAT_EXT);
+                out.extend_from_slice(&value.to_bits().to_be_bytes());
+            }
+            EtfTerm::Atom(ref name) => {
+                if name.chars().count() > MAX_ATOM_CHARACTERS {
+                    return Err(EtfError::Invalid("atom"));
+                }
+                if let Ok(len) = u8::try_from(name.len()) {
+                    out.push(SMALL_ATOM_UTF8_EXT);
+                    out.push(len);
+                } else {
+                    out.push(ATOM_UTF8_EXT);
+                    out.extend_from_slice(&length_u16(name.len(), "atom")?.to_be_bytes());
+                }
+                out.extend_from_slice(name.as_bytes());
+            }
+            EtfTerm::Binary(ref data) => {
+                out.push(BINARY_EXT);
+                out.extend_from_slice(&length_u32(data.len(), "binary")?.to_be_bytes());
+                out.extend_from_slice(data);
+            }
+            EtfTerm::BitBinary { ref data, bits } => {
+                out.push(BIT_BINARY_EXT);
+                out.extend_from_slice(&length_u32(data.len(), "bitstring")?.to_be_bytes());
+                out.push(bits);
+                out.extend_from_slice(data);
+            }
+            EtfTerm::Tuple(ref elements) => {
+                if let Ok(len) = u8::try_from(elements.len()) {
+                    out.push(SMALL_TUPLE_EXT);
+                    out.push(len);
+                } else {
+                    out.push(LARGE_TUPLE_EXT);
+                    out.extend_from_slice(&length_u32(elements.len(), "tuple")?.to_be_bytes());
+                }
+                for element in elements {
+                    element.write(out)?;
+                }
+            }
+            EtfTerm::List(ref elements) if elements.is_empty() => out.push(NIL_EXT),
+            EtfTerm::List(ref elements) => {
+                write_list(out, elements)?;
+                out.push(NIL_EXT);
+            }
+            EtfTerm::ImproperList(ref elements, ref tail) => {
+                write_list(out, elements)?;
+                tail.write(out)?;
+            }
+            EtfTerm::Pid {
+                ref node,
+                id,
+                serial,
+                creation,
+            } => {
+                out.push(NEW_PID_EXT);
+                EtfTerm::atom(node).write(out)?;
+                out.extend_from_slice(&id.to_be_bytes());
+                out.extend_from_slice(&serial.to_be_bytes());
+                out.extend_from_slice(&creation.to_be_bytes());
+            }
+            EtfTerm::Reference {
+                ref node,
+                creation,
+                ref ids,
+            } => {
+                out.push(NEWER_REFERENCE_EXT);
+                out.extend_from_slice(&length_u16(ids.len(), "reference")?.to_be_bytes());
+                EtfTerm::atom(node).write(out)?;
+                out.extend_from_slice(&creation.to_be_bytes());
+                for id in ids {
+                    out.extend_from_slice(&id.to_be_bytes());
+                }
+            }
+            EtfTerm::Map(ref entries) => {
+                out.push(MAP_EXT);
+                out.extend_from_slice(&length_u32(entries.len(), "map")?.to_be_bytes());
+                for (key, value) in entries {
+                    key.write(out)?;
+                    value.write(out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes the header and elements of a list, but not its tail.
+fn write_list(out: &mut Vec<u8>, elements: &[EtfTerm]) -> Result<(), EtfError> {
+    out.push(LIST_EXT);
+    out.extend_from_slice(&length_u32(elements.len(), "list")?.to_be_bytes());
+    for element in elements {
+        element.write(out)?;
+    }
+    Ok(())
+}
+
+fn write_big(out: &mut Vec<u8>, negative: bool, digits: &[u8]) -> Result<(), EtfError> {
+    if let Ok(len) = u8::try_from(digits.len()) {
+        out.push(SMALL_BIG_EXT);
+        out.push(len);
+    } else {
+        out.push(LARGE_BIG_EXT);
+        out.extend_from_slice(&length_u32(digits.len(), "integer")?.to_be_bytes());
+    }
+    out.push(negative as u8);
+    out.extend_from_slice(digits);
+    Ok(())
+}
+
+/// Converts the length of `what` for a 16-bit length field.
+fn length_u16(len: usize, what: &'static str) -> Result<u16, EtfError> {
+    u16::try_from(len).map_err(|_| EtfError::TooLarge(what))
+}
+
+/// Converts the length of `what` for a 32-bit length field.
+fn length_u32(len: usize, what: &'static str) -> Result<u32, EtfError> {
+    u32::try_from(len).map_err(|_| EtfError::TooLarge(what))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    /// The number of terms being read, enclosing the next one.
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], EtfError> {
+        let end = self.pos.checked_add(len).ok_or(EtfError::UnexpectedEnd)?;
+        let bytes = self
+            .data
+            .get(self.pos..end)
+            .ok_or(EtfError::UnexpectedEnd)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, EtfError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<usize, EtfError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]) as usize)
+    }
+
+    fn u32(&mut self) -> Result<usize, EtfError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    }
+
+    /// Reads the name of the node of a pid or reference.
+    fn node(&mut self) -> Result<String, EtfError> {
+        match self.term()? {
+            EtfTerm::Atom(name) => Ok(name),
+            _ => Err(EtfError::Invalid("node name")),
+        }
+    }
+
+    fn ids(&mut self, len: usize) -> Result<Vec<u32>, EtfError> {
+        let mut ids = Vec::with_capacity(len.min(self.data.len() - self.pos));
+        for _ in 0..len {
+            ids.push(self.u32()? as u32);
+        }
+        Ok(ids)
+    }
+
+    fn atom(&mut self, len: usize, latin1: bool) -> Result<EtfTerm, EtfError> {
+        let bytes = self.bytes(len)?;
+        let name = if latin1 {
+            bytes.iter().map(|&byte| byte as char).collect()
+        } else {
+            String::from_utf8(bytes.to_vec()).map_err(|_| EtfError::Invalid("atom"))?
+        };
+        Ok(EtfTerm::Atom(name))
+    }
+
+    fn big(&mut self, len: usize) -> Result<EtfTerm, EtfError> {
+        let negative = self.u8()? != 0;
+        let digits = self.bytes(len)?;
+
+        let significant = digits
+            .iter()
+            .rposition(|&digit| digit != 0)
+            .map_or(0, |i| i + 1);
+        if significant <= 8 {
+            let mut magnitude = [0; 8];
+            magnitude[..significant].copy_from_slice(&digits[..significant]);
+            let magnitude = u64::from_le_bytes(magnitude);
+            let value = if negative {
+                0i64.checked_sub_unsigned(magnitude)
+            } else {
+                i64::try_from(magnitude).ok()
+            };
+            if let Some(value) = value {
+                return Ok(EtfTerm::Integer(value));
+            }
+        }
+
+        Ok(EtfTerm::BigInteger {
+            negative,
+            digits: digits[..significant].to_vec(),
+        })
+    }
+
+    fn terms(&mut self, len: usize) -> Result<Vec<EtfTerm>, EtfError> {
+        // Don't trust `len` for the allocation: every element takes at least one byte.
+        let mut terms = Vec::with_capacity(len.min(self.data.len() - self.pos));
+        for _ in 0..len {
+            terms.push(self.term()?);
+        }
+        Ok(terms)
+    }
+
+    fn term(&mut self) -> Result<EtfTerm, EtfError> {
+        if self.depth == MAX_DEPTH {
+            return Err(EtfError::TooDeep);
+        }
+        self.depth += 1;
+        let term = self.nested_term();
+        self.depth -= 1;
+        term
+    }
+
+    fn nested_term(&mut self) -> Result<EtfTerm, EtfError> {
+        let tag = self.u8()?;
+        let term = match tag {
+            SMALL_INTEGER_EXT => EtfTerm::Integer(self.u8()?.into()),
+            INTEGER_EXT => {
+                let bytes = self.bytes(4)?;
+                let value = i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                EtfTerm::Integer(value.into())
+            }
+            SMALL_BIG_EXT => {
+                let len = self.u8()?.into();
+                self.big(len)?
+            }
+            LARGE_BIG_EXT => {
+                let len = self.u32()?;
+                self.big(len)?
+            }
+            NEW_FLOAT_EXT => {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(self.bytes(8)?);
+                EtfTerm::Float(f64::from_bits(u64::from_be_bytes(bytes)))
+            }
+            FLOAT_EXT => {
+                let bytes = self.bytes(31)?;
+                let text = std::str::from_utf8(bytes).map_err(|_| EtfError::Invalid("float"))?;
+                let value = text
+                    .trim_end_matches('\0')
+                    .trim()
+                    .parse()
+                    .map_err(|_| EtfError::Invalid("float"))?;
+                EtfTerm::Float(value)
+            }
+            ATOM_UTF8_EXT => {
+                let len = self.u16()?;
+                self.atom(len, false)?
+            }
+            SMALL_ATOM_UTF8_EXT => {
+                let len = self.u8()?.into();
+                self.atom(len, false)?
+            }
+            ATOM_EXT => {
+                let len = self.u16()?;
+                self.atom(len, true)?
+            }
+            SMALL_ATOM_EXT => {
+                let len = self.u8()?.into();
+                self.atom(len, true)?
+            }
+            BINARY_EXT => {
+                let len = self.u32()?;
+                EtfTerm::Binary(self.bytes(len)?.to_vec())
+            }
+            BIT_BINARY_EXT => {
+                let len = self.u32()?;
+                let bits = self.u8()?;
+                EtfTerm::BitBinary {
+                    data: self.bytes(len)?.to_vec(),
+                    bits,
+                }
+            }
+            SMALL_TUPLE_EXT => {
+                let len = self.u8()?.into();
+                EtfTerm::Tuple(self.terms(len)?)
+            }
+            LARGE_TUPLE_EXT => {
+                let len = self.u32()?;
+                EtfTerm::Tuple(self.terms(len)?)
+            }
+            NIL_EXT => EtfTerm::List(Vec::new()),
+            STRING_EXT => {
+                let len = self.u16()?;
+                let bytes = self.bytes(len)?;
+                EtfTerm::List(bytes.iter().map(|&b| EtfTerm::Integer(b.into())).collect())
+            }
+            LIST_EXT => {
+                let len = self.u32()?;
+                let elements = self.terms(len)?;
+                match self.term()? {
+                    EtfTerm::List(ref tail) if tail.is_empty() => EtfTerm::List(elements),
+                    tail => EtfTerm::ImproperList(elements, Box::new(tail)),
+                }
+            }
+            MAP_EXT => {
+                let len = self.u32()?;
+                let mut entries = Vec::with_capacity(len.min(self.data.len() - self.pos));
+                for _ in 0..len {
+                    let key = self.term()?;
+                    let value = self.term()?;
+                    entries.push((key, value));
+                }
+                EtfTerm::Map(entries)
+            }
+            PID_EXT | NEW_PID_EXT => {
+                let node = self.node()?;
+                let id = self.u32()? as u32;
+                let serial = self.u32()? as u32;
+                let creation = if tag == PID_EXT {
+                    self.u8()?.into()
+                } else {
+                    self.u32()? as u32
+                };
+                EtfTerm::Pid {
+                    node,
+                    id,
+                    serial,
+                    creation,
+                }
+            }
+            NEW_REFERENCE_EXT | NEWER_REFERENCE_EXT => {
+                let len = self.u16()?;
+                let node = self.node()?;
+                let creation = if tag == NEW_REFERENCE_EXT {
+                    self.u8()?.into()
+                } else {
+                    self.u32()? as u32
+                };
+                EtfTerm::Reference {
+                    node,
+                    creation,
+                    ids: self.ids(len)?,
+                }
+            }
+            _ => return Err(EtfError::UnsupportedTag(tag)),
+        };
+        Ok(term)
+    }
+}
+
+impl EtfTerm {
+    /// Encodes the term in `env`, or fails like `to_bytes` if it can't be represented, for
+    /// example because an atom has more than 255 characters.
+    pub fn try_encode<'a>(&self, env: Env<'a>) -> Result<Term<'a>, EtfError> {
+        let term = match *self {
+            EtfTerm::Integer(value) => value.encode(env),
+            EtfTerm::Float(value) => value.encode(env),
+            EtfTerm::Atom(ref name) => crate::Atom::from_str(env, name)
+                .map_err(|_| EtfError::Invalid("atom"))?
+                .encode(env),
+            EtfTerm::Binary(ref data) => Binary::from_bytes(env, data).encode(env),
+            EtfTerm::Tuple(ref elements) => {
+                let terms = elements
+                    .iter()
+                    .map(|e| e.try_encode(env))
+                    .collect::<Result<Vec<Term>, EtfError>>()?;
+                make_tuple(env, &terms)
+            }
+            EtfTerm::List(ref elements) => {
+                let terms = elements
+                    .iter()
+                    .map(|e| e.try_encode(env))
+                    .collect::<Result<Vec<Term>, EtfError>>()?;
+                terms.encode(env)
+            }
+            EtfTerm::Map(ref entries) => {
+                let mut map = Term::map_new(env);
+                for (key, value) in entries {
+                    map = map
+                        .map_put(key.try_encode(env)?, value.try_encode(env)?)
+                        .map_err(|_| EtfError::Invalid("map"))?;
+                }
+                map
+            }
+            // Rare shapes that have no dedicated constructor in the NIF API.
+            EtfTerm::BigInteger { .. }
+            | EtfTerm::BitBinary { .. }
+            | EtfTerm::ImproperList(..)
+            | EtfTerm::Pid { .. }
+            | EtfTerm::Reference { .. } => {
+                let (term, _) = env
+                    .binary_to_term(&self.to_bytes()?)
+                    .ok_or(EtfError::Invalid("term"))?;
+                term
+            }
+        };
+        Ok(term)
+    }
+}
+
+/// # Panics
+///
+/// Panics if the term can't be represented, like `to_bytes`. Use `EtfTerm::try_encode` to get an
+/// error instead.
+impl Encoder for EtfTerm {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        self.try_encode(env).expect("EtfTerm can't be encoded")
+    }
+}
+
+impl<'a> Decoder<'a> for EtfTerm {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let binary = term.to_binary();
+        EtfTerm::from_bytes(binary.as_slice())
+            .map(|(term, _)| term)
+            .map_err(|_| Error::BadArg)
+    }
+}
@@ -0,0 +1,139 @@
+//! Large results inserted into ETS tables.
+//!
+//! A NIF returning millions of rows builds them as a single list, which is copied to the heap
+//! of the caller at once, and often only inserted into an ETS table by the caller. `EtsInsert`
+//! writes the rows into a table provided by the caller instead, in chunks.
+//!
+//! The NIF API can't access ETS directly. Like `persistent_term`, chunks are sent to the
+//! `Rustler.Ets` helper process from the `rustler` Mix package, which must be running under the
+//! supervision tree of the application, and handles one message:
+//!
+//! * `{:insert, table, rows}` calls `:ets.insert(table, rows)`.
+//!
+//! The table must be public, or owned by the helper. Returned from a NIF, `EtsInsert` encodes
+//! and sends one chunk of rows at a time, yielding with `rustler::yielding` when the timeslice
+//! is used up, so that the NIF can run on a normal scheduler. It returns `{:ok, count}`, where
+//! `count` is the number of rows, or `{:error, :ets_helper_not_running}`. Inserts are
+//! asynchronous: `Rustler.Ets.sync/0` waits until the rows sent by the calling process are
+//! inserted.
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn export(table: Term, count: u64) -> EtsInsert<impl Iterator<Item = (u64, String)>> {
+//!     EtsInsert::new(table, (0..count).map(|id| (id, format!("row {}", id))))
+//! }
+//! ```
+//!
+//! ```elixir
+//! table = :ets.new(:rows, [:public])
+//! {:ok, 1_000_000} = MyNif.export(table, 1_000_000)
+//! :ok = Rustler.Ets.sync()
+//! ```
+//!
+//! The yielding machinery must be registered by calling `rustler::yielding::load(env)` from the
+//! `load` callback of the NIF library.
+
+use crate::codegen_runtime::{NifReturnable, NifReturned};
+use crate::types::atom;
+use crate::types::LocalPid;
+use crate::yielding::{self, PendingTask};
+use crate::{Encoder, Env, Error, Term};
+
+/// Name under which the helper process is registered.
+pub const HELPER_NAME: &str = "Elixir.Rustler.Ets";
+
+/// The number of rows sent in a message by default.
+pub const DEFAULT_CHUNK_SIZE: usize = 1000;
+
+mod atoms {
+    crate::atoms! {
+        insert,
+        ets_helper_not_running,
+    }
+}
+
+/// Rows inserted into an ETS table when returned from a NIF. See the module documentation.
+pub struct EtsInsert<I> {
+    /// The table, in the external term format, to be decoded in the environment of each slice.
+    table: Vec<u8>,
+    rows: I,
+    chunk_size: usize,
+    helper: Option<LocalPid>,
+    inserted: u64,
+}
+
+impl<I> EtsInsert<I>
+where
+    I: Iterator + Send + 'static,
+    I::Item: Encoder,
+{
+    /// Returns the insertion of `rows` into `table`, the name or identifier of a table.
+    pub fn new<R>(table: Term, rows: R) -> Self
+    where
+        R: IntoIterator<IntoIter = I>,
+    {
+        EtsInsert {
+            table: table.to_binary().as_slice().to_vec(),
+            rows: rows.into_iter(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            helper: None,
+            inserted: 0,
+        }
+    }
+
+    /// Sets the number of rows sent in a message, `DEFAULT_CHUNK_SIZE` by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunks must hold rows");
+        self.chunk_size = chunk_size;
+        self
+    }
+}
+
+impl<I> PendingTask for EtsInsert<I>
+where
+    I: Iterator + Send + 'static,
+    I::Item: Encoder,
+{
+    fn step(&mut self, env: Env) -> Option<NifReturned> {
+        let helper = self.helper.as_ref().unwrap();
+        let (table, _) = env.binary_to_term(&self.table).unwrap();
+
+        let rows: Vec<Term> = self
+            .rows
+            .by_ref()
+            .take(self.chunk_size)
+            .map(|row| row.encode(env))
+            .collect();
+        let done = rows.len() < self.chunk_size;
+        if !rows.is_empty() {
+            self.inserted += rows.len() as u64;
+            env.send(helper, (atoms::insert(), table, rows).encode(env));
+        }
+
+        if done {
+            Some(unsafe { (atom::ok(), self.inserted).into_returned(env) })
+        } else {
+            None
+        }
+    }
+}
+
+unsafe impl<I> NifReturnable for EtsInsert<I>
+where
+    I: Iterator + Send + 'static,
+    I::Item: Encoder,
+{
+    unsafe fn into_returned(mut self, env: Env) -> NifReturned {
+        match env.whereis_pid(HELPER_NAME) {
+            Some(helper) => {
+                self.helper = Some(helper);
+                yielding::start(env, Box::new(self))
+            }
+            None => Error::Term(Box::new(atoms::ets_helper_not_running())).into_returned(env),
+        }
+    }
+}
@@ -101,10 +101,17 @@ macro_rules! rustler_export_nifs {
 
         match result {
             Ok(res) => res.apply(env),
-            Err(_err) => $crate::codegen_runtime::raise_exception(
-                env.as_c_arg(),
-                $crate::types::atom::Atom::from_bytes(env, b"nif_panic").ok().unwrap().as_c_arg(),
-            ),
+            Err(err) => match $crate::panic_handler::mode() {
+                // `rustler_export_nifs!` has always raised `:nif_panic` by default.
+                $crate::panic_handler::PanicMode::Atom => {
+                    let reason = $crate::types::atom::Atom::from_bytes(env, b"nif_panic");
+                    $crate::codegen_runtime::raise_exception(
+                        env.as_c_arg(),
+                        reason.ok().unwrap().as_c_arg(),
+                    )
+                }
+                _ => $crate::codegen_runtime::panicked(env, err).apply(env),
+            },
         }
     });
 
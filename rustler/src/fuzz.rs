@@ -0,0 +1,48 @@
+//! Helpers to fuzz decoders, like the ones generated by the derive macros.
+//!
+//! `decode_arbitrary` builds a term from arbitrary bytes in the External Term Format, and decodes
+//! it as `T`. Decoders must reject malformed terms with an error: any panic is a bug, which the
+//! fuzzer will report.
+//!
+//! Terms can only be created while the Erlang VM is running, so `decode_arbitrary` must be
+//! called from a NIF, with inputs generated on the Elixir side, for example by mutating
+//! `:erlang.term_to_binary/1` output:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn fuzz_config(data: Binary) -> bool {
+//!     rustler::fuzz::decode_arbitrary::<Config>(data.as_slice()) != DecodeOutcome::NotEtf
+//! }
+//! ```
+//!
+//! The `fuzz` directory of the repository has `cargo fuzz` targets for the parts of Rustler that
+//! don't need the VM, like the `etf` reader.
+
+use crate::env::OwnedEnv;
+use crate::Decoder;
+
+/// The result of `decode_arbitrary`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeOutcome {
+    /// The bytes are not a valid term in the External Term Format.
+    NotEtf,
+    /// The term was decoded.
+    Decoded,
+    /// The decoder returned an error.
+    Rejected,
+}
+
+/// Builds a term from `bytes` in a fresh environment, and decodes it as `T`. Trailing bytes
+/// after the term are ignored. Panics of the decoder are not caught.
+pub fn decode_arbitrary<T>(bytes: &[u8]) -> DecodeOutcome
+where
+    T: for<'a> Decoder<'a>,
+{
+    OwnedEnv::new().run(|env| match env.binary_to_term(bytes) {
+        Some((term, _)) => match term.decode::<T>() {
+            Ok(_) => DecodeOutcome::Decoded,
+            Err(_) => DecodeOutcome::Rejected,
+        },
+        None => DecodeOutcome::NotEtf,
+    })
+}
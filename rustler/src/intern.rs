@@ -0,0 +1,57 @@
+//! Interning of binary terms for strings that are encoded repeatedly within a NIF call.
+//!
+//! Encoding JSON-like data with binary keys allocates a new binary for every occurrence of every
+//! key. An `Interner` encodes each distinct string once and returns the same term for later
+//! occurrences:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn rows<'a>(env: Env<'a>, rows: Vec<Row>) -> Vec<Term<'a>> {
+//!     let mut keys = env.interner();
+//!     rows.iter()
+//!         .map(|row| map_new(env).map_put(keys.intern("id"), row.id.encode(env)).unwrap())
+//!         .collect()
+//! }
+//! ```
+//!
+//! The interned terms belong to the environment of the interner, which can't outlive it.
+
+use crate::types::string::encode_binary;
+use crate::{Env, Term};
+use std::collections::HashMap;
+
+/// A cache of the binary terms of strings, in an environment. See the module documentation.
+pub struct Interner<'a> {
+    env: Env<'a>,
+    terms: HashMap<Box<str>, Term<'a>>,
+}
+
+impl<'a> Interner<'a> {
+    pub fn new(env: Env<'a>) -> Self {
+        Interner {
+            env,
+            terms: HashMap::new(),
+        }
+    }
+
+    /// Returns a binary term for `string`, reusing the term returned by an earlier call with the
+    /// same string, if any.
+    ///
+    /// Only use this for strings that repeat, like map keys: every distinct string is kept until
+    /// the interner is dropped.
+    pub fn intern(&mut self, string: &str) -> Term<'a> {
+        if let Some(&term) = self.terms.get(string) {
+            return term;
+        }
+        let term = encode_binary(self.env, string);
+        self.terms.insert(string.into(), term);
+        term
+    }
+}
+
+impl<'a> Env<'a> {
+    /// Returns an `Interner` for strings encoded repeatedly in this environment.
+    pub fn interner(self) -> Interner<'a> {
+        Interner::new(self)
+    }
+}
@@ -37,11 +37,12 @@ pub extern crate lazy_static;
 #[macro_use]
 pub mod types;
 
+mod size;
 mod term;
 
 pub use crate::term::Term;
 pub use crate::types::{
-    Atom, Binary, Decoder, Encoder, ListIterator, LocalPid, MapIterator, OwnedBinary,
+    Atom, Binary, Decoder, Encoder, ListIterator, LocalPid, MapIterator, NewBinary, OwnedBinary,
 };
 pub mod resource;
 pub use crate::resource::ResourceArc;
@@ -52,25 +53,99 @@ pub use crate::dynamic::TermType;
 
 pub mod schedule;
 pub use crate::schedule::SchedulerFlags;
+pub mod profile;
+pub use crate::profile::EncodingProfile;
 pub mod env;
 pub use crate::env::{Env, OwnedEnv};
 pub mod thread;
 pub use crate::thread::{spawn, JobSpawner, ThreadSpawner};
+pub mod threadpool;
+pub use crate::threadpool::ThreadPool;
 
+pub mod affinity;
+pub mod async_nif;
+pub mod backend;
+pub mod bench;
+pub mod broadcast;
+pub mod canonical;
+pub mod chunked;
+pub use crate::chunked::ChunkedList;
+#[cfg(feature = "compress")]
+pub mod compress;
+#[cfg(feature = "experimental-crash-guard")]
+pub mod crash_guard;
+pub mod crypto;
+pub mod deadline;
+pub mod decode_trace;
+#[cfg(feature = "dist")]
+pub mod dist;
+#[cfg(nif_version_2_16)]
+pub mod dyncall;
 pub mod error;
+#[cfg(feature = "etf")]
+pub mod etf;
+pub mod ets;
 pub mod export;
+pub mod fuzz;
+pub mod intern;
+pub mod load_data;
+pub mod log;
 pub use crate::error::Error;
+pub mod messenger;
+pub use crate::messenger::Messenger;
+pub mod monitor;
+pub mod overload;
+pub mod panic_handler;
+pub mod parallel;
+
+pub mod persistent_term;
+#[cfg(feature = "port")]
+pub mod port;
+pub mod rate_limit;
+#[cfg(feature = "regex")]
+pub mod regex;
+pub mod reply;
+pub mod resource_hooks;
+#[cfg(feature = "resource-tracking")]
+pub mod resource_tracking;
+pub use crate::reply::ReplyStream;
+
+pub mod scratch;
+pub mod select;
+#[cfg(feature = "serde")]
+pub mod serde;
+
+pub mod stats;
+pub use crate::stats::NifStats;
+
+pub mod subprocess;
+pub use crate::subprocess::SubprocessResource;
+
+#[cfg(feature = "text")]
+pub mod text;
+
+pub mod snapshot;
+pub mod summary;
+pub mod trace_context;
+pub use crate::trace_context::TraceContext;
+
+pub mod work_queue;
+pub use crate::work_queue::WorkQueue;
+
+pub mod yielding;
+pub use crate::yielding::Yielding;
 
 pub mod r#return;
 pub use crate::r#return::Return;
 
 #[doc(hidden)]
 mod nif;
-pub use nif::Nif;
+pub use nif::{nif_version_at_least, Nif, NifRegistration};
 
 pub type NifResult<T> = Result<T, Error>;
 
 #[cfg(feature = "derive")]
 pub use rustler_codegen::{
-    init, nif, NifMap, NifRecord, NifStruct, NifTuple, NifUnitEnum, NifUntaggedEnum,
+    dispatch, init, nif, NifMap, NifRecord, NifStruct, NifTaggedEnum, NifTuple, NifUnitEnum,
+    NifUntaggedEnum,
 };
@@ -0,0 +1,169 @@
+//! Decoding of the load data passed to the `load` callback.
+//!
+//! The `:load_data` option of `use Rustler` is passed to the `load` and `upgrade` callbacks of
+//! the NIF library. With `:load_data_fun`, it is computed when the library is loaded, which
+//! allows passing environment variables or application configuration:
+//!
+//! ```elixir
+//! use Rustler, otp_app: :my_app, crate: :my_nif, load_data_fun: {MyApp.Config, :nif_config}
+//! ```
+//!
+//! The `load` callback can receive the load data decoded as any type, like a `NifMap` struct,
+//! in which case decoding errors are logged by `rustler::init!`. For finer control, or for
+//! keyword lists, the load data can be taken as a `Term` and decoded with `LoadData`.
+//!
+//! Load data is usually a keyword list or a map with atom keys. `LoadData` decodes its values,
+//! with errors naming the offending key, which can be logged with `LoadError::report`:
+//!
+//! ```ignore
+//! fn load(env: Env, info: Term) -> bool {
+//!     let data = LoadData::new(info);
+//!     let pool_size = match data.get_or("pool_size", 4) {
+//!         Ok(pool_size) => pool_size,
+//!         Err(err) => {
+//!             err.report(env);
+//!             return false;
+//!         }
+//!     };
+//!     ...
+//! }
+//! ```
+
+use crate::decode_trace;
+use crate::log::{self, Level};
+use crate::types::atom::Atom;
+use crate::types::tuple::get_tuple;
+use crate::{Decoder, Env, Term};
+use std::any;
+use std::fmt;
+
+/// Load data received by the `load` or `upgrade` callback. See the module documentation.
+#[derive(Clone, Copy)]
+pub struct LoadData<'a> {
+    term: Term<'a>,
+}
+
+impl<'a> LoadData<'a> {
+    pub fn new(term: Term<'a>) -> Self {
+        LoadData { term }
+    }
+
+    pub fn term(&self) -> Term<'a> {
+        self.term
+    }
+
+    /// Decodes the whole load data.
+    ///
+    /// With the `decode-trace` feature, the error lists the fields that could not be decoded.
+    pub fn decode<T: Decoder<'a>>(&self) -> Result<T, LoadError> {
+        decode_trace::clear();
+        self.term.decode().map_err(|_| {
+            let mut message = format!(
+                "could not decode load data as {}, got: {:?}",
+                any::type_name::<T>(),
+                self.term
+            );
+            for failure in decode_trace::take_trace() {
+                message.push_str(&format!("\n  {}", failure));
+            }
+            LoadError { message }
+        })
+    }
+
+    /// Decodes the value of `key`, failing if it is missing.
+    pub fn get<T: Decoder<'a>>(&self, key: &str) -> Result<T, LoadError> {
+        match self.get_optional(key)? {
+            Some(value) => Ok(value),
+            None => Err(LoadError {
+                message: format!("missing key :{} in load data", key),
+            }),
+        }
+    }
+
+    /// Decodes the value of `key`, or returns `default` if it is missing.
+    pub fn get_or<T: Decoder<'a>>(&self, key: &str, default: T) -> Result<T, LoadError> {
+        Ok(self.get_optional(key)?.unwrap_or(default))
+    }
+
+    /// Decodes the value of `key`, if it is present.
+    pub fn get_optional<T: Decoder<'a>>(&self, key: &str) -> Result<Option<T>, LoadError> {
+        let value = match self.lookup(key)? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        value.decode().map(Some).map_err(|_| LoadError {
+            message: format!(
+                "could not decode :{} in load data as {}, got: {:?}",
+                key,
+                any::type_name::<T>(),
+                value
+            ),
+        })
+    }
+
+    /// Returns the value of `key`, in a keyword list or a map with atom keys.
+    fn lookup(&self, key: &str) -> Result<Option<Term<'a>>, LoadError> {
+        let env = self.term.get_env();
+        let key = Atom::from_str(env, key).map_err(|_| LoadError {
+            message: format!("invalid load data key {:?}", key),
+        })?;
+
+        if self.term.is_map() {
+            return Ok(self.term.map_get(key.to_term(env)).ok());
+        }
+
+        let iter = self
+            .term
+            .into_list_iterator()
+            .map_err(|_| self.not_a_container())?;
+        let mut found = None;
+        for item in iter {
+            match get_tuple(item).as_deref() {
+                Ok([item_key, value]) if item_key.is_atom() => {
+                    if found.is_none() && *item_key == key.to_term(env) {
+                        found = Some(*value);
+                    }
+                }
+                _ => return Err(self.not_a_container()),
+            }
+        }
+        Ok(found)
+    }
+
+    fn not_a_container(&self) -> LoadError {
+        LoadError {
+            message: format!(
+                "load data must be a keyword list or a map, got: {:?}",
+                self.term
+            ),
+        }
+    }
+}
+
+/// An error decoding load data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoadError {
+    message: String,
+}
+
+impl LoadError {
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Logs the error with `rustler::log`. The `load` callback can then return `false`.
+    ///
+    /// Returns `false` if the `Rustler.Logger` helper is not running and the error was dropped.
+    pub fn report(&self, env: Env) -> bool {
+        log::log(env, Level::Error, &self.to_string())
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NIF library failed to load: {}", self.message)
+    }
+}
+
+impl std::error::Error for LoadError {}
@@ -0,0 +1,96 @@
+//! Logging to the Elixir `Logger` from NIFs.
+//!
+//! Like `persistent_term`, the NIF API has no access to `Logger`, so log messages are sent to the
+//! `Rustler.Logger` helper process from the `rustler` Mix package, which logs them. It must be
+//! running under the supervision tree of the application, and handles one message:
+//!
+//! * `{:log, level, message}`, where `level` is a `Logger` level and `message` a binary.
+//!
+//! When the helper is not running, messages are dropped, and `log` returns `false`.
+//!
+//! NIFs declared with `#[rustler::nif(deprecated = "...")]` log a warning through this module
+//! the first `DEPRECATION_WARNING_LIMIT` times they are called.
+
+use crate::types::atom::Atom;
+use crate::types::LocalPid;
+use crate::wrapper::pid;
+use crate::{Encoder, Env};
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Name under which the helper process is registered.
+pub const HELPER_NAME: &str = "Elixir.Rustler.Logger";
+
+/// Number of calls of a deprecated NIF that log a warning.
+pub const DEPRECATION_WARNING_LIMIT: usize = 3;
+
+mod atoms {
+    crate::atoms! {
+        log,
+        debug,
+        info,
+        warning,
+        error,
+    }
+}
+
+/// The severity of a log message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Encoder for Level {
+    fn encode<'a>(&self, env: Env<'a>) -> crate::Term<'a> {
+        match self {
+            Level::Debug => atoms::debug(),
+            Level::Info => atoms::info(),
+            Level::Warning => atoms::warning(),
+            Level::Error => atoms::error(),
+        }
+        .encode(env)
+    }
+}
+
+/// Logs `message` at `level`.
+///
+/// `env` must be the environment of the calling process, or the environment of an `OwnedEnv`
+/// on a thread that is not managed by the Erlang VM.
+///
+/// Returns `false` if the helper is not running and the message was dropped.
+pub fn log(env: Env, level: Level, message: &str) -> bool {
+    match helper(env) {
+        Some(helper) => {
+            env.send(&helper, (atoms::log(), level, message).encode(env));
+            true
+        }
+        None => false,
+    }
+}
+
+/// Logs a deprecation warning for the NIF `nif`, unless `calls` has reached
+/// `DEPRECATION_WARNING_LIMIT`. Used by `#[rustler::nif(deprecated = "...")]`.
+#[doc(hidden)]
+pub fn deprecated_call(env: Env, calls: &AtomicUsize, nif: &str, note: &str) {
+    if calls.fetch_add(1, Ordering::Relaxed) < DEPRECATION_WARNING_LIMIT {
+        let message = format!("NIF {} is deprecated: {}", nif, note);
+        log(env, Level::Warning, &message);
+    }
+}
+
+/// Looks up the helper process.
+fn helper(env: Env) -> Option<LocalPid> {
+    let name = Atom::from_str(env, HELPER_NAME).ok()?;
+    // Threads that are not managed by the VM must not pass an environment.
+    let c_env = if unsafe { rustler_sys::enif_thread_type() } == rustler_sys::ERL_NIF_THR_UNDEFINED
+    {
+        ptr::null_mut()
+    } else {
+        env.as_c_arg()
+    };
+
+    unsafe { pid::whereis_pid(c_env, name.to_term(env).as_c_arg()) }.map(LocalPid::from_c_arg)
+}
@@ -0,0 +1,113 @@
+//! Sending messages to a process from threads that are not managed by the VM.
+//!
+//! A `Messenger` is created with the pid of the recipient, usually in the NIF starting the
+//! work, and moved or cloned to the threads doing it. Each send builds the message in an
+//! environment kept by the sending thread, so threads don't have to manage `OwnedEnv`s and
+//! `SavedTerm`s themselves:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn start_download(env: Env, url: String) -> Atom {
+//!     let messenger = Messenger::new(env.pid());
+//!     thread::spawn(move || {
+//!         for chunk in download(&url) {
+//!             if messenger.send_encoded(&(atoms::chunk(), chunk)).is_err() {
+//!                 // The process is gone, stop downloading.
+//!                 return;
+//!             }
+//!         }
+//!         let _ = messenger.send_encoded(&atoms::done());
+//!     });
+//!     atoms::ok()
+//! }
+//! ```
+
+use crate::env::OwnedEnv;
+use crate::{Encoder, Env, LocalPid, Term};
+use std::cell::RefCell;
+use std::fmt;
+
+/// The error returned when a message can't be sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SendError {
+    /// The recipient is not alive.
+    NotAlive,
+    /// The calling thread is managed by the VM. NIFs send with `Env::send` instead.
+    ManagedThread,
+    /// The environment of the message could not be allocated.
+    Alloc,
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SendError::NotAlive => write!(f, "the recipient is not alive"),
+            SendError::ManagedThread => write!(f, "the calling thread is managed by the VM"),
+            SendError::Alloc => write!(f, "could not allocate the environment of the message"),
+        }
+    }
+}
+
+impl std::error::Error for SendError {}
+
+thread_local! {
+    /// The environment messages are built in, cleared after each send.
+    static MESSAGE_ENV: RefCell<Option<OwnedEnv>> = const { RefCell::new(None) };
+}
+
+/// Sends messages to a process. See the module documentation.
+#[derive(Clone)]
+pub struct Messenger {
+    pid: LocalPid,
+}
+
+impl Messenger {
+    pub fn new(pid: LocalPid) -> Self {
+        Messenger { pid }
+    }
+
+    /// The recipient of the messages.
+    pub fn pid(&self) -> &LocalPid {
+        &self.pid
+    }
+
+    /// Sends the message returned by `closure`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SendError::NotAlive` if the recipient has exited, and
+    /// `SendError::ManagedThread` if it is called from a thread managed by the VM.
+    pub fn send<F>(&self, closure: F) -> Result<(), SendError>
+    where
+        F: for<'a> FnOnce(Env<'a>) -> Term<'a>,
+    {
+        if unsafe { rustler_sys::enif_thread_type() } != rustler_sys::ERL_NIF_THR_UNDEFINED {
+            return Err(SendError::ManagedThread);
+        }
+
+        let sent = MESSAGE_ENV.with(|cell| match cell.try_borrow_mut() {
+            Ok(mut cached) => {
+                if cached.is_none() {
+                    *cached = Some(OwnedEnv::try_new().map_err(|_| SendError::Alloc)?);
+                }
+                let owned_env = cached.as_mut().unwrap();
+                Ok(owned_env.send_unchecked(&self.pid, closure))
+            }
+            // A message sent while building another one gets its own environment.
+            Err(_) => {
+                let mut owned_env = OwnedEnv::try_new().map_err(|_| SendError::Alloc)?;
+                Ok(owned_env.send_unchecked(&self.pid, closure))
+            }
+        })?;
+
+        if !sent {
+            return Err(SendError::NotAlive);
+        }
+        Ok(())
+    }
+
+    /// Sends `message`. See `send`.
+    pub fn send_encoded<T: Encoder + ?Sized>(&self, message: &T) -> Result<(), SendError> {
+        self.send(|env| message.encode(env))
+    }
+}
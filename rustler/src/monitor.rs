@@ -0,0 +1,132 @@
+//! Monitoring processes from resources, with `enif_monitor_process`.
+//!
+//! A NIF library handing out stateful handles can release what a process holds when the process
+//! exits, instead of waiting for the handle to be garbage collected. The resource type must be
+//! registered with the `monitor` option of `resource!` and implement `MonitorDown`:
+//!
+//! ```ignore
+//! struct Lock {
+//!     owner: Mutex<Option<(LocalPid, Monitor)>>,
+//! }
+//!
+//! impl MonitorDown for Lock {
+//!     fn down(&self, _env: Env, _pid: LocalPid, _monitor: Monitor) {
+//!         // The owner exited without releasing the lock.
+//!         *self.owner.lock().unwrap() = None;
+//!     }
+//! }
+//!
+//! #[rustler::nif]
+//! fn acquire(env: Env, lock: ResourceArc<Lock>) -> bool {
+//!     let mut owner = lock.owner.lock().unwrap();
+//!     if owner.is_some() {
+//!         return false;
+//!     }
+//!     let pid = env.pid();
+//!     match lock.monitor(env, &pid) {
+//!         Some(monitor) => {
+//!             *owner = Some((pid, monitor));
+//!             true
+//!         }
+//!         None => false,
+//!     }
+//! }
+//! ```
+
+use crate::resource::{resource_data, ResourceArc, ResourceTypeProvider};
+use crate::wrapper::{c_void, ErlNifPid};
+use crate::{Env, LocalPid};
+use rustler_sys::{ErlNifEnv, ErlNifMonitor};
+use std::fmt;
+use std::mem::MaybeUninit;
+use std::ptr;
+
+/// A monitor of a process by a resource.
+#[derive(Clone, Copy)]
+pub struct Monitor {
+    inner: ErlNifMonitor,
+}
+
+impl Monitor {
+    pub fn as_c_arg(&self) -> &ErlNifMonitor {
+        &self.inner
+    }
+}
+
+impl PartialEq for Monitor {
+    fn eq(&self, other: &Monitor) -> bool {
+        unsafe { rustler_sys::enif_compare_monitors(&self.inner, &other.inner) == 0 }
+    }
+}
+
+impl Eq for Monitor {}
+
+impl fmt::Debug for Monitor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Monitor")
+    }
+}
+
+/// The callback of resources monitoring processes.
+pub trait MonitorDown: ResourceTypeProvider {
+    /// Called when the process `pid`, monitored by this resource with `monitor`, exits.
+    fn down(&self, env: Env, pid: LocalPid, monitor: Monitor);
+}
+
+pub(crate) unsafe extern "C" fn down<T: MonitorDown>(
+    env: *mut ErlNifEnv,
+    obj: *mut c_void,
+    pid: *const ErlNifPid,
+    monitor: *const ErlNifMonitor,
+) {
+    let resource: &T = resource_data(obj);
+    let lifetime = ();
+    resource.down(
+        Env::new(&lifetime, env),
+        LocalPid::from_c_arg(*pid),
+        Monitor { inner: *monitor },
+    );
+}
+
+/// Returns the environment to pass to the monitoring functions: they must not be given one on
+/// threads that are not managed by the VM.
+pub(crate) fn caller_env(env: Env) -> *mut ErlNifEnv {
+    if unsafe { rustler_sys::enif_thread_type() } == rustler_sys::ERL_NIF_THR_UNDEFINED {
+        ptr::null_mut()
+    } else {
+        env.as_c_arg()
+    }
+}
+
+impl<T: MonitorDown> ResourceArc<T> {
+    /// Starts monitoring `pid`, so that `MonitorDown::down` is called when it exits.
+    ///
+    /// Returns `None` if the process is not alive. The monitor does not keep the resource alive:
+    /// it is removed when the resource is garbage collected.
+    pub fn monitor(&self, env: Env, pid: &LocalPid) -> Option<Monitor> {
+        let mut monitor = MaybeUninit::uninit();
+        let result = unsafe {
+            rustler_sys::enif_monitor_process(
+                caller_env(env),
+                self.handle(),
+                pid.as_c_arg(),
+                monitor.as_mut_ptr(),
+            )
+        };
+        if result != 0 {
+            return None;
+        }
+        Some(Monitor {
+            inner: unsafe { monitor.assume_init() },
+        })
+    }
+
+    /// Removes `monitor`. Returns `false` if it was not active, because the process has exited
+    /// or it was already removed.
+    pub fn demonitor(&self, env: Env, monitor: &Monitor) -> bool {
+        unsafe {
+            rustler_sys::enif_demonitor_process(caller_env(env), self.handle(), monitor.as_c_arg())
+                == 0
+        }
+    }
+}
@@ -1,7 +1,13 @@
-use crate::codegen_runtime::{c_int, DEF_NIF_FUNC, NIF_ENV, NIF_TERM};
+use crate::codegen_runtime::{
+    c_int, DEF_NIF_FUNC, NIF_ENV, NIF_MAJOR_VERSION, NIF_MINOR_VERSION, NIF_TERM,
+};
+use crate::schedule::SchedulerFlags;
+use std::ffi::CString;
 
 pub trait Nif {
     const NAME: *const u8;
+    /// `NAME` without the trailing NUL byte, for comparisons in constant expressions.
+    const NAME_STR: &'static str;
     const ARITY: u32;
     const FLAGS: u32;
     const FUNC: DEF_NIF_FUNC;
@@ -11,3 +17,89 @@ pub trait Nif {
         argv: *const NIF_TERM,
     ) -> NIF_TERM;
 }
+
+/// Additional NIFs registered by a library at load time, next to the functions listed in
+/// `rustler::init!`.
+///
+/// This allows registering families of NIFs that are generated at compile time, like one
+/// instantiation of a generic function per SIMD variant, without declaring a `#[rustler::nif]`
+/// function for each. The builder is returned by the function given as the `registry` option of
+/// `rustler::init!`, which is called once, when the library is loaded.
+///
+/// ```ignore
+/// unsafe extern "C" fn sum<V: Variant>(
+///     env: NIF_ENV,
+///     argc: c_int,
+///     argv: *const NIF_TERM,
+/// ) -> NIF_TERM {
+///     ...
+/// }
+///
+/// fn registry() -> NifRegistration {
+///     NifRegistration::new()
+///         .add("sum_sse2", 1, SchedulerFlags::Normal, sum::<Sse2>)
+///         .add("sum_avx2", 1, SchedulerFlags::Normal, sum::<Avx2>)
+/// }
+///
+/// rustler::init!("Elixir.Vectors", [], registry = registry);
+/// ```
+#[derive(Default)]
+pub struct NifRegistration {
+    funcs: Vec<DEF_NIF_FUNC>,
+}
+
+impl NifRegistration {
+    pub fn new() -> Self {
+        NifRegistration { funcs: Vec::new() }
+    }
+
+    /// Registers `function` as the NIF `name/arity`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` contains a NUL byte.
+    pub fn add(
+        mut self,
+        name: &str,
+        arity: u32,
+        flags: SchedulerFlags,
+        function: unsafe extern "C" fn(NIF_ENV, c_int, *const NIF_TERM) -> NIF_TERM,
+    ) -> Self {
+        let name = CString::new(name).expect("NIF names can't contain NUL bytes");
+        self.funcs.push(DEF_NIF_FUNC {
+            // Leaked on purpose: the VM refers to the name as long as the library is loaded.
+            name: CString::into_raw(name) as *const u8,
+            arity,
+            function,
+            flags: flags as u32,
+        });
+        self
+    }
+
+    /// Registers a function declared with `#[rustler::nif]`.
+    pub fn add_nif<N: Nif>(mut self) -> Self {
+        self.funcs.push(N::FUNC);
+        self
+    }
+
+    /// Returns the registered NIFs. Used by `rustler::init!`.
+    #[doc(hidden)]
+    pub fn into_funcs(self) -> Vec<DEF_NIF_FUNC> {
+        self.funcs
+    }
+}
+
+/// Returns whether the NIF API the library is built against is at least `major.minor`.
+///
+/// This is a constant expression, so code behind it is removed when building against older
+/// versions, like with a `cfg`:
+///
+/// ```ignore
+/// if rustler::nif_version_at_least(2, 15) {
+///     ...
+/// }
+/// ```
+pub const fn nif_version_at_least(major: u32, minor: u32) -> bool {
+    let (built_major, built_minor) = (NIF_MAJOR_VERSION as u32, NIF_MINOR_VERSION as u32);
+    built_major > major || (built_major == major && built_minor >= minor)
+}
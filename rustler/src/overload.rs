@@ -0,0 +1,202 @@
+//! Load shedding, to refuse native work quickly when the system is overloaded.
+//!
+//! When NIFs are called faster than they complete, calls pile up on dirty schedulers and in the
+//! threads they spawn, and every caller waits longer. An `Overload` limiter makes NIFs fail fast
+//! instead, with `{:error, :overloaded}`, once one of its thresholds is exceeded:
+//!
+//! * `max_in_flight` bounds the number of guarded calls running at the same time.
+//! * `max_load` bounds a load signal. NIFs can't read the run queues of the VM, so the load is
+//!   either reported from Elixir with `Overload::report_load`, for instance from a process
+//!   polling `:erlang.statistics(:total_run_queue_lengths)`, or computed by a `LoadSignal`.
+//!
+//! Limiters are resources, so that the same limiter can guard several NIFs and be updated from
+//! Elixir. The resource type must be registered by calling `rustler::overload::load(env)` from the
+//! `load` callback of the NIF library.
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn new_limiter(max_in_flight: usize, max_run_queue: u64) -> ResourceArc<Overload> {
+//!     ResourceArc::new(Overload::new().max_in_flight(max_in_flight).max_load(max_run_queue))
+//! }
+//!
+//! #[rustler::nif]
+//! fn report_run_queue(limiter: ResourceArc<Overload>, length: u64) {
+//!     limiter.report_load(length)
+//! }
+//!
+//! #[rustler::nif(schedule = "DirtyCpu")]
+//! fn render(limiter: ResourceArc<Overload>, page: Binary) -> NifResult<OwnedBinary> {
+//!     let _guard = limiter.enter()?;
+//!     ...
+//! }
+//! ```
+//!
+//! The call counts as in flight until the `Guard` returned by `Overload::enter` is dropped.
+
+use crate::{Encoder, Env, Error, Term};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+mod atoms {
+    crate::atoms! {
+        overloaded,
+    }
+}
+
+/// A source of load, compared with the `max_load` of a limiter.
+///
+/// Implemented by closures returning the current load.
+pub trait LoadSignal: Send + Sync {
+    fn load(&self) -> u64;
+}
+
+impl<F: Fn() -> u64 + Send + Sync> LoadSignal for F {
+    fn load(&self) -> u64 {
+        self()
+    }
+}
+
+/// A load shedding limiter. See the module documentation.
+pub struct Overload {
+    max_in_flight: Option<usize>,
+    max_load: Option<u64>,
+    signal: Option<Box<dyn LoadSignal>>,
+    in_flight: AtomicUsize,
+    reported_load: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl Overload {
+    /// A limiter without thresholds, which never sheds load until configured.
+    pub fn new() -> Self {
+        Overload {
+            max_in_flight: None,
+            max_load: None,
+            signal: None,
+            in_flight: AtomicUsize::new(0),
+            reported_load: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    /// Sheds calls once `max_in_flight` guarded calls are running.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_in_flight` is 0.
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        assert!(max_in_flight > 0, "max_in_flight must be positive");
+        self.max_in_flight = Some(max_in_flight);
+        self
+    }
+
+    /// Sheds calls while the load is above `max_load`.
+    pub fn max_load(mut self, max_load: u64) -> Self {
+        self.max_load = Some(max_load);
+        self
+    }
+
+    /// Reads the load from `signal`, instead of the load reported with `report_load`.
+    pub fn signal<S: LoadSignal + 'static>(mut self, signal: S) -> Self {
+        self.signal = Some(Box::new(signal));
+        self
+    }
+
+    /// Sets the load, compared with `max_load` by the following calls.
+    pub fn report_load(&self, load: u64) {
+        self.reported_load.store(load, Ordering::Relaxed);
+    }
+
+    /// Returns the current load.
+    pub fn load(&self) -> u64 {
+        match self.signal {
+            Some(ref signal) => signal.load(),
+            None => self.reported_load.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns the number of guarded calls running.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of calls shed so far.
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    /// Counts the calling NIF as in flight until the returned guard is dropped, or returns
+    /// `Overloaded` right away if a threshold is exceeded.
+    pub fn enter(&self) -> Result<Guard<'_>, Overloaded> {
+        if let Some(max_load) = self.max_load {
+            if self.load() > max_load {
+                return Err(self.reject());
+            }
+        }
+
+        let in_flight = self.in_flight.fetch_add(1, Ordering::AcqRel);
+        let guard = Guard { overload: self };
+        match self.max_in_flight {
+            Some(max_in_flight) if in_flight >= max_in_flight => {
+                drop(guard);
+                Err(self.reject())
+            }
+            _ => Ok(guard),
+        }
+    }
+
+    fn reject(&self) -> Overloaded {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+        Overloaded
+    }
+}
+
+impl Default for Overload {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A call counted as in flight by an `Overload` limiter, until it is dropped.
+pub struct Guard<'a> {
+    overload: &'a Overload,
+}
+
+impl<'a> Drop for Guard<'a> {
+    fn drop(&mut self) {
+        self.overload.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// The error of a call shed by an `Overload` limiter.
+///
+/// Encodes as `:overloaded`, and converts into an `Error` returning `{:error, :overloaded}`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Overloaded;
+
+impl fmt::Display for Overloaded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("overloaded")
+    }
+}
+
+impl std::error::Error for Overloaded {}
+
+impl Encoder for Overloaded {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        atoms::overloaded().encode(env)
+    }
+}
+
+impl From<Overloaded> for Error {
+    fn from(err: Overloaded) -> Error {
+        Error::Term(Box::new(err))
+    }
+}
+
+crate::resource_type!(Overload);
+
+/// Registers the resource type of `Overload`. Call this from the `load` callback.
+pub fn load(env: Env) -> bool {
+    crate::resource::register::<Overload>(env)
+}
@@ -0,0 +1,151 @@
+//! What a NIF raises when it panics.
+//!
+//! By default, a panic in a NIF is caught and raises the `:nif_panicked` atom, while the panic
+//! message is printed to standard error. The `on_panic` option of `rustler::init!` chooses
+//! another behavior for the whole library:
+//!
+//! * `on_panic = "atom"`, the default, raises `:nif_panicked`.
+//! * `on_panic = "exception"` raises a `Rustler.NifPanicError` exception, whose `message` is the
+//!   panic message, and whose `backtrace` is the backtrace of the panic, as a string.
+//! * `on_panic = "abort"` aborts the VM on any panic of the library, for code that can't be
+//!   trusted to leave its state consistent when unwinding.
+//!
+//! ```ignore
+//! rustler::init!("Elixir.Math", [add, sub, mul, div], on_panic = "exception");
+//! ```
+//!
+//! ```elixir
+//! try do
+//!   Math.div(1, 0)
+//! rescue
+//!   error in Rustler.NifPanicError -> Logger.error(error.message <> "\n" <> error.backtrace)
+//! end
+//! ```
+//!
+//! The mode can also be changed at runtime with `set_mode`. Modes other than the default install
+//! a panic hook, which captures the backtrace of panics or aborts, and then calls the hook that
+//! was installed before, so that panics are still printed.
+
+use crate::codegen_runtime::NifReturned;
+use crate::types::atom;
+use crate::types::string::encode_binary;
+use crate::{Encoder, Env, Term};
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::panic;
+use std::process;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Once;
+
+mod atoms {
+    crate::atoms! {
+        __exception__,
+        message,
+        backtrace,
+        nif_panic_error = "Elixir.Rustler.NifPanicError",
+    }
+}
+
+/// What a NIF raises when it panics. See the module documentation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanicMode {
+    /// Raises `:nif_panicked`.
+    Atom = 0,
+    /// Raises a `Rustler.NifPanicError` exception with the message and backtrace of the panic.
+    Exception = 1,
+    /// Aborts the VM.
+    Abort = 2,
+}
+
+static MODE: AtomicU8 = AtomicU8::new(PanicMode::Atom as u8);
+static HOOK: Once = Once::new();
+
+thread_local! {
+    /// The backtrace of the last panic of the thread, captured by the hook.
+    static BACKTRACE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Sets what NIFs raise when they panic, from now on.
+pub fn set_mode(mode: PanicMode) {
+    MODE.store(mode as u8, Ordering::Release);
+    if mode != PanicMode::Atom {
+        HOOK.call_once(install_hook);
+    }
+}
+
+/// Returns what NIFs raise when they panic.
+pub fn mode() -> PanicMode {
+    match MODE.load(Ordering::Acquire) {
+        1 => PanicMode::Exception,
+        2 => PanicMode::Abort,
+        _ => PanicMode::Atom,
+    }
+}
+
+fn install_hook() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        match mode() {
+            PanicMode::Atom => {}
+            PanicMode::Exception => {
+                let backtrace = Backtrace::force_capture().to_string();
+                BACKTRACE.with(|cell| *cell.borrow_mut() = Some(backtrace));
+            }
+            PanicMode::Abort => {
+                previous(info);
+                process::abort();
+            }
+        }
+        previous(info);
+    }));
+}
+
+/// Returns what a NIF raises for the panic with payload `err`, caught on the current thread.
+pub(crate) fn panicked(env: Env, err: Box<dyn Any + Send>) -> NifReturned {
+    NifReturned::Raise(reason(env, err).as_c_arg())
+}
+
+/// Returns the reason of the panic with payload `err`, caught on the current thread: the
+/// `:nif_panicked` atom or a `Rustler.NifPanicError`, depending on the mode. Async NIFs reply
+/// with it in an error tuple, as they can't raise.
+pub(crate) fn reason(env: Env, err: Box<dyn Any + Send>) -> Term {
+    match mode() {
+        PanicMode::Atom => atom::nif_panicked().encode(env),
+        PanicMode::Exception => {
+            let backtrace = BACKTRACE.with(|cell| cell.borrow_mut().take());
+            exception(env, &err, backtrace)
+        }
+        // Only reached if the hook was replaced since.
+        PanicMode::Abort => process::abort(),
+    }
+}
+
+/// Returns a `Rustler.NifPanicError` exception.
+fn exception<'a>(env: Env<'a>, err: &Box<dyn Any + Send>, backtrace: Option<String>) -> Term<'a> {
+    let message = if let Some(message) = err.downcast_ref::<String>() {
+        message.as_str()
+    } else if let Some(&message) = err.downcast_ref::<&'static str>() {
+        message
+    } else {
+        "NIF panicked"
+    };
+    let backtrace = match backtrace {
+        Some(backtrace) => encode_binary(env, &backtrace),
+        None => atom::nil().encode(env),
+    };
+
+    let keys = [
+        atom::__struct__().encode(env),
+        atoms::__exception__().encode(env),
+        atoms::message().encode(env),
+        atoms::backtrace().encode(env),
+    ];
+    let values = [
+        atoms::nif_panic_error().encode(env),
+        true.encode(env),
+        encode_binary(env, message),
+        backtrace,
+    ];
+    Term::map_from_arrays(env, &keys, &values).unwrap()
+}
@@ -0,0 +1,213 @@
+//! Data-parallel maps for NIFs running on dirty CPU schedulers.
+//!
+//! A dirty NIF that processes a large vector one item at a time uses a single core, while
+//! spawning one thread per item oversubscribes the host and competes with the schedulers of the
+//! VM. `map_dirty` shards the items across a bounded number of threads, and returns the results
+//! in the order of the items:
+//!
+//! ```ignore
+//! #[rustler::nif(schedule = "DirtyCpu")]
+//! fn hash_all(paths: Vec<String>) -> Vec<String> {
+//!     rustler::parallel::map_dirty(paths, |path| hash_file(&path))
+//! }
+//! ```
+//!
+//! The calling thread blocks until all items are mapped, so this should only be called from a
+//! dirty NIF. `ParallelMap` configures the number of threads and the size of the shards, and can
+//! report progress to the calling thread between shards. The progress callback can stop the map
+//! early by returning an error, for instance when a deadline has passed:
+//!
+//! ```ignore
+//! #[rustler::nif(schedule = "DirtyCpu")]
+//! fn score_all(docs: Vec<String>, ctx: CallContext) -> NifResult<Vec<f64>> {
+//!     ParallelMap::new()
+//!         .threads(4)
+//!         .progress(|_done, _total| ctx.check())
+//!         .run(docs, |doc| score(&doc))
+//! }
+//! ```
+//!
+//! `ParallelMap::hints` sizes the pool and pins its threads following `affinity::ThreadHints`.
+//!
+//! If the mapping function panics, the panic is resumed on the calling thread once all threads
+//! have stopped, so that the NIF raises an error.
+
+use crate::affinity::ThreadHints;
+use crate::NifResult;
+use std::num::NonZeroUsize;
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+/// The number of shards per thread, unless the shard size is configured.
+const SHARDS_PER_THREAD: usize = 4;
+
+type ProgressFn<'p> = Box<dyn FnMut(usize, usize) -> NifResult<()> + 'p>;
+
+/// A configurable parallel map. See the module documentation.
+pub struct ParallelMap<'p> {
+    threads: usize,
+    shard_size: Option<usize>,
+    hints: ThreadHints,
+    progress: Option<ProgressFn<'p>>,
+}
+
+impl<'p> ParallelMap<'p> {
+    /// A map using as many threads as the host has cores.
+    pub fn new() -> Self {
+        ParallelMap {
+            threads: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            shard_size: None,
+            hints: ThreadHints::new(),
+            progress: None,
+        }
+    }
+
+    /// Sets the maximum number of threads mapping items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threads` is 0.
+    pub fn threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "threads must be positive");
+        self.threads = threads;
+        self
+    }
+
+    /// Sets the number of threads from `hints`, and pins them to the cores of `hints`.
+    ///
+    /// Threads that can't be pinned run unpinned.
+    pub fn hints(mut self, hints: ThreadHints) -> Self {
+        self.threads = hints.thread_count();
+        self.hints = hints;
+        self
+    }
+
+    /// Sets the number of items a thread maps between two reports of progress.
+    ///
+    /// By default, the items are split into four shards per thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shard_size` is 0.
+    pub fn shard_size(mut self, shard_size: usize) -> Self {
+        assert!(shard_size > 0, "shard_size must be positive");
+        self.shard_size = Some(shard_size);
+        self
+    }
+
+    /// Sets a callback called on the calling thread each time a shard is mapped, with the number
+    /// of items mapped so far and the total number of items.
+    ///
+    /// If the callback returns an error, no more shards are started and `run` returns the error
+    /// once the shards in progress are done.
+    pub fn progress<F>(mut self, progress: F) -> Self
+    where
+        F: FnMut(usize, usize) -> NifResult<()> + 'p,
+    {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Maps `items` with `f`, returning the results in the order of the items.
+    pub fn run<T, R, F>(mut self, items: Vec<T>, f: F) -> NifResult<Vec<R>>
+    where
+        T: Send,
+        R: Send,
+        F: Fn(T) -> R + Sync,
+    {
+        let total = items.len();
+        let shard_size = self.shard_size.unwrap_or_else(|| {
+            let shards = self.threads * SHARDS_PER_THREAD;
+            total.div_ceil(shards).max(1)
+        });
+
+        let mut shards = Vec::with_capacity(total.div_ceil(shard_size));
+        let mut items = items.into_iter();
+        loop {
+            let shard: Vec<T> = items.by_ref().take(shard_size).collect();
+            if shard.is_empty() {
+                break;
+            }
+            shards.push(shard);
+        }
+        let shard_count = shards.len();
+        let threads = self.threads.min(shard_count);
+
+        let queue = Mutex::new(shards.into_iter().enumerate());
+        let stopped = AtomicBool::new(false);
+        let (sender, receiver) = mpsc::channel();
+        let mut results: Vec<Option<Vec<R>>> = (0..shard_count).map(|_| None).collect();
+        let mut outcome = Ok(());
+        let (hints, progress) = (&self.hints, &mut self.progress);
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|_| {
+                    let sender = sender.clone();
+                    let (queue, stopped, f, hints) = (&queue, &stopped, &f, hints);
+                    scope.spawn(move || {
+                        let _ = hints.apply();
+                        while !stopped.load(Ordering::Relaxed) {
+                            // The lock is only held to take a shard, so it can't be poisoned.
+                            let next = queue.lock().unwrap().next();
+                            let (index, shard) = match next {
+                                Some(next) => next,
+                                None => break,
+                            };
+                            let mapped: Vec<R> = shard.into_iter().map(f).collect();
+                            if sender.send((index, mapped)).is_err() {
+                                break;
+                            }
+                        }
+                    })
+                })
+                .collect();
+            drop(sender);
+
+            let mut done = 0;
+            for (index, mapped) in receiver.iter() {
+                done += mapped.len();
+                results[index] = Some(mapped);
+                if let (Some(progress), Ok(())) = (progress.as_mut(), &outcome) {
+                    outcome = progress(done, total);
+                    if outcome.is_err() {
+                        stopped.store(true, Ordering::Relaxed);
+                    }
+                }
+            }
+
+            for handle in handles {
+                if let Err(payload) = handle.join() {
+                    panic::resume_unwind(payload);
+                }
+            }
+        });
+
+        outcome?;
+        Ok(results.into_iter().flatten().flatten().collect())
+    }
+}
+
+impl<'p> Default for ParallelMap<'p> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Maps `items` with `f` on as many threads as the host has cores, returning the results in the
+/// order of the items.
+///
+/// See the module documentation.
+pub fn map_dirty<T, R, F>(items: Vec<T>, f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    match ParallelMap::new().run(items, f) {
+        Ok(results) => results,
+        Err(_) => unreachable!("a map without progress callback can't fail"),
+    }
+}
@@ -0,0 +1,95 @@
+//! Publishing terms to `persistent_term`.
+//!
+//! Terms that rarely change, like configuration or descriptors of compiled automata, can be
+//! published to `persistent_term`, so that Elixir and Erlang code can read them without calling
+//! into the NIF library every time.
+//!
+//! The NIF API can't access `persistent_term` directly. Instead, updates are sent to the
+//! `Rustler.PersistentTerm` helper process from the `rustler` Mix package, which must be running
+//! under the supervision tree of the application. It handles two messages:
+//!
+//! * `{:put, key, value}` calls `:persistent_term.put(key, value)`.
+//! * `{:erase, key}` calls `:persistent_term.erase(key)`.
+//!
+//! Updates are asynchronous: they are applied in order, but possibly after the NIF returns.
+//! Since every update of `persistent_term` triggers a global garbage collection, they should be
+//! rare. Keys should be namespaced, for example `{MyLib, :config}`.
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn reload_rules(env: Env, source: String) -> NifResult<Atom> {
+//!     let rules = compile(&source)?;
+//!     persistent_term::put(env, &(atoms::my_lib(), atoms::rules()), &rules)?;
+//!     Ok(atoms::ok())
+//! }
+//! ```
+
+use crate::types::atom::Atom;
+use crate::types::LocalPid;
+use crate::wrapper::pid;
+use crate::{Encoder, Env, Error, NifResult};
+use std::ptr;
+
+/// Name under which the helper process is registered.
+pub const HELPER_NAME: &str = "Elixir.Rustler.PersistentTerm";
+
+mod atoms {
+    crate::atoms! {
+        put,
+        erase,
+        persistent_term_helper_not_running,
+    }
+}
+
+/// Publishes `value` under `key`.
+///
+/// `env` must be the environment of the calling process, or the environment of an `OwnedEnv`
+/// on a thread that is not managed by the Erlang VM.
+///
+/// # Errors
+///
+/// `{:error, :persistent_term_helper_not_running}` if the `Rustler.PersistentTerm` process is not running.
+pub fn put<K, V>(env: Env, key: &K, value: &V) -> NifResult<()>
+where
+    K: Encoder + ?Sized,
+    V: Encoder + ?Sized,
+{
+    let helper = helper(env)?;
+    let message = (atoms::put(), key.encode(env), value.encode(env)).encode(env);
+    env.send(&helper, message);
+    Ok(())
+}
+
+/// Removes the term published under `key`, if any.
+///
+/// Readers still holding the old value keep it; the term is only freed once it is no longer
+/// referenced.
+///
+/// # Errors
+///
+/// `{:error, :persistent_term_helper_not_running}` if the `Rustler.PersistentTerm` process is not running.
+pub fn erase<K>(env: Env, key: &K) -> NifResult<()>
+where
+    K: Encoder + ?Sized,
+{
+    let helper = helper(env)?;
+    let message = (atoms::erase(), key.encode(env)).encode(env);
+    env.send(&helper, message);
+    Ok(())
+}
+
+/// Looks up the helper process.
+fn helper(env: Env) -> NifResult<LocalPid> {
+    let name = Atom::from_str(env, HELPER_NAME)?;
+    // Threads that are not managed by the VM must not pass an environment.
+    let c_env = if unsafe { rustler_sys::enif_thread_type() } == rustler_sys::ERL_NIF_THR_UNDEFINED
+    {
+        ptr::null_mut()
+    } else {
+        env.as_c_arg()
+    };
+
+    unsafe { pid::whereis_pid(c_env, name.to_term(env).as_c_arg()) }
+        .map(LocalPid::from_c_arg)
+        .ok_or_else(|| Error::Term(Box::new(atoms::persistent_term_helper_not_running())))
+}
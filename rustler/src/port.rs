@@ -0,0 +1,147 @@
+//! Port programs, talking to the VM over stdin and stdout like `Port.open/2` with
+//! `{:packet, 4}` expects.
+//!
+//! A crash of a port program doesn't take the VM down, unlike a crash in a NIF. Logic written
+//! against `EtfTerm`, which is both an `Encoder` and a `Decoder`, can be compiled as either:
+//!
+//! ```ignore
+//! fn handle(request: EtfTerm) -> EtfTerm {
+//!     ...
+//! }
+//!
+//! // In the NIF library.
+//! #[rustler::nif]
+//! fn call(request: EtfTerm) -> EtfTerm {
+//!     handle(request)
+//! }
+//!
+//! // In the port program.
+//! fn main() -> std::io::Result<()> {
+//!     rustler::port::Port::stdio().serve(handle)
+//! }
+//! ```
+//!
+//! On the Elixir side, terms are sent and received with `:erlang.term_to_binary/1` and
+//! `:erlang.binary_to_term/1`:
+//!
+//! ```elixir
+//! port = Port.open({:spawn_executable, path}, [:binary, packet: 4])
+//! send(port, {self(), {:command, :erlang.term_to_binary(request)}})
+//! receive do
+//!   {^port, {:data, data}} -> :erlang.binary_to_term(data)
+//! end
+//! ```
+//!
+//! Replies can also be built from Rust values deriving `NifTuple`, `NifMap` or `NifUnitEnum` with
+//! `#[rustler(build_term)]`, with `BuildTerm::build(EtfBackend)`.
+//!
+//! Packets larger than `DEFAULT_MAX_PACKET_SIZE` are refused, so that a corrupted header can't
+//! make the program allocate an arbitrary amount of memory. The limit can be changed with
+//! `set_max_packet_size`.
+//!
+//! This module is only available with the `port` feature.
+
+use crate::etf::EtfTerm;
+use std::convert::TryFrom;
+use std::io::{self, Read, Stdin, Stdout, Write};
+
+/// The size of the largest packet accepted by a new `Port`, in bytes.
+pub const DEFAULT_MAX_PACKET_SIZE: usize = 64 * 1024 * 1024;
+
+/// The two ends of a port, exchanging terms in packets prefixed with their length in 4 bytes.
+pub struct Port<R, W> {
+    input: R,
+    output: W,
+    max_packet_size: usize,
+}
+
+impl Port<Stdin, Stdout> {
+    /// The port of a port program, talking to the VM that spawned it.
+    pub fn stdio() -> Self {
+        Port::new(io::stdin(), io::stdout())
+    }
+}
+
+impl<R: Read, W: Write> Port<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Port {
+            input,
+            output,
+            max_packet_size: DEFAULT_MAX_PACKET_SIZE,
+        }
+    }
+
+    /// Sets the size of the largest packet `read_packet` accepts, in bytes.
+    pub fn set_max_packet_size(&mut self, size: usize) {
+        self.max_packet_size = size;
+    }
+
+    /// Reads the next packet. Returns `None` when the input is closed, which happens when the
+    /// port is closed on the Elixir side. Fails with `InvalidData` if the packet is larger than
+    /// the maximum.
+    pub fn read_packet(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut header = [0; 4];
+        let mut read = 0;
+        while read < header.len() {
+            match self.input.read(&mut header[read..]) {
+                Ok(0) if read == 0 => return Ok(None),
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => read += n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        let len = u32::from_be_bytes(header) as usize;
+        if len > self.max_packet_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("packet of {} bytes is too large", len),
+            ));
+        }
+        let mut packet = vec![0; len];
+        self.input.read_exact(&mut packet)?;
+        Ok(Some(packet))
+    }
+
+    pub fn write_packet(&mut self, data: &[u8]) -> io::Result<()> {
+        let len = u32::try_from(data.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "packet too large"))?;
+        self.output.write_all(&len.to_be_bytes())?;
+        self.output.write_all(data)?;
+        self.output.flush()
+    }
+
+    /// Reads the next term. Returns `None` when the input is closed.
+    pub fn read(&mut self) -> io::Result<Option<EtfTerm>> {
+        match self.read_packet()? {
+            Some(packet) => EtfTerm::from_bytes(&packet)
+                .map(|(term, _)| Some(term))
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn write(&mut self, term: &EtfTerm) -> io::Result<()> {
+        let bytes = term
+            .to_bytes()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        self.write_packet(&bytes)
+    }
+
+    /// Answers every term read with the term returned by `handler`, until the input is closed.
+    pub fn serve<F>(&mut self, mut handler: F) -> io::Result<()>
+    where
+        F: FnMut(EtfTerm) -> EtfTerm,
+    {
+        while let Some(request) = self.read()? {
+            self.write(&handler(request))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the input and output of the port.
+    pub fn into_inner(self) -> (R, W) {
+        (self.input, self.output)
+    }
+}
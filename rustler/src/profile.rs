@@ -0,0 +1,228 @@
+//! Encoding profiles select between Elixir- and Erlang-idiomatic term shapes.
+//!
+//! The same Rust type is often consumed from both Elixir and Erlang code, which disagree on a
+//! couple of conventions:
+//!
+//! | Convention     | Elixir          | Erlang               |
+//! |----------------|-----------------|----------------------|
+//! | Absent values  | `nil`           | `undefined`          |
+//! | Strings        | binaries        | charlists            |
+//!
+//! Additionally, maps produced by `NifMap` can use either atom keys or binary keys, and maps
+//! produced from `HashMap`s can be built in a deterministic order.
+//!
+//! A profile is never implicit: `Encoder::encode` and `Decoder::decode` always use
+//! `EncodingProfile::ELIXIR`. Another profile is passed explicitly, to `Encoder::encode_with` and
+//! `Decoder::decode_with`, which hand it down to the elements of containers and to the fields of
+//! derived types. A derived type can also fix its profile with `#[rustler(profile = "erlang")]`.
+//!
+//! ```ignore
+//! let term = EncodingProfile::ERLANG.encode(env, &Some("text"));
+//! let value: Option<String> = EncodingProfile::ERLANG.decode(term)?;
+//! ```
+
+use crate::types::atom::{self, Atom};
+use crate::types::string;
+use crate::{Decoder, Encoder, Env, NifResult, Term};
+
+/// How the keys of maps generated by `NifMap` are encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyStyle {
+    Atom,
+    Binary,
+}
+
+/// How `Option::None` is encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoneStyle {
+    Nil,
+    Undefined,
+}
+
+/// How Rust strings are encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StringStyle {
+    Binary,
+    Charlist,
+}
+
+/// In which order the entries of `HashMap`s are inserted into maps.
+///
+/// `Any` follows the iteration order of the `HashMap`, which differs between two equal `HashMap`s.
+/// `Sorted` inserts the entries sorted by key in term order, so that equal `HashMap`s build maps
+/// the same way. Maps derived with `NifMap` always insert their fields in declaration order. To
+/// hash or sign a term, encode it with `Term::to_canonical_binary`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapOrder {
+    Any,
+    Sorted,
+}
+
+impl MapOrder {
+    /// Orders `entries` the way the encoders of `HashMap`s insert them, for maps built by hand
+    /// with `Term::map_from_arrays`.
+    pub fn sort_entries(self, entries: &mut [(Term, Term)]) {
+        if self == MapOrder::Sorted {
+            entries.sort_by_key(|(key, _)| *key);
+        }
+    }
+}
+
+/// How binaries are allocated by `Binary::from_bytes`, and by the encoders of strings.
+///
+/// `Adaptive` builds binaries of up to `HEAP_BINARY_LIMIT` bytes on the heap of the process, and
+/// reference-counts larger ones. `Refc` allocates a reference-counted binary whatever the size,
+/// e.g. for binaries that are sent to many processes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryAllocation {
+    Adaptive,
+    Refc,
+}
+
+/// Whether ranges are encoded with a `step`.
+///
+/// `Stepped` builds the `%Range{}` structs of Elixir 1.12 and later. `Unstepped` leaves the `step`
+/// out, for earlier versions of Elixir, whose ranges don't have one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RangeStyle {
+    Stepped,
+    Unstepped,
+}
+
+/// A set of encoding conventions, passed explicitly to `Encoder::encode_with` and
+/// `Decoder::decode_with`.
+///
+/// Start from `EncodingProfile::ELIXIR` or `EncodingProfile::ERLANG` and adjust it with the
+/// builder methods; more conventions may be added in later versions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EncodingProfile {
+    keys: KeyStyle,
+    none: NoneStyle,
+    strings: StringStyle,
+    maps: MapOrder,
+    binaries: BinaryAllocation,
+    ranges: RangeStyle,
+}
+
+impl EncodingProfile {
+    /// Atom keys, `nil` and binaries.
+    pub const ELIXIR: EncodingProfile = EncodingProfile {
+        keys: KeyStyle::Atom,
+        none: NoneStyle::Nil,
+        strings: StringStyle::Binary,
+        maps: MapOrder::Any,
+        binaries: BinaryAllocation::Adaptive,
+        ranges: RangeStyle::Stepped,
+    };
+
+    /// Atom keys, `undefined` and charlists.
+    pub const ERLANG: EncodingProfile = EncodingProfile {
+        keys: KeyStyle::Atom,
+        none: NoneStyle::Undefined,
+        strings: StringStyle::Charlist,
+        maps: MapOrder::Any,
+        binaries: BinaryAllocation::Adaptive,
+        ranges: RangeStyle::Stepped,
+    };
+
+    pub const fn keys(self) -> KeyStyle {
+        self.keys
+    }
+
+    pub const fn none(self) -> NoneStyle {
+        self.none
+    }
+
+    pub const fn strings(self) -> StringStyle {
+        self.strings
+    }
+
+    pub const fn maps(self) -> MapOrder {
+        self.maps
+    }
+
+    pub const fn binaries(self) -> BinaryAllocation {
+        self.binaries
+    }
+
+    pub const fn ranges(self) -> RangeStyle {
+        self.ranges
+    }
+
+    /// Returns `self`, with the keys of `NifMap` maps encoded as `keys`.
+    pub const fn with_keys(self, keys: KeyStyle) -> EncodingProfile {
+        EncodingProfile { keys, ..self }
+    }
+
+    /// Returns `self`, with `Option::None` encoded as `none`.
+    pub const fn with_none(self, none: NoneStyle) -> EncodingProfile {
+        EncodingProfile { none, ..self }
+    }
+
+    /// Returns `self`, with strings encoded as `strings`.
+    pub const fn with_strings(self, strings: StringStyle) -> EncodingProfile {
+        EncodingProfile { strings, ..self }
+    }
+
+    /// Returns `self`, with the entries of `HashMap`s sorted by key.
+    pub const fn sorted_maps(self) -> EncodingProfile {
+        EncodingProfile {
+            maps: MapOrder::Sorted,
+            ..self
+        }
+    }
+
+    /// Returns `self`, with binaries always allocated as reference-counted binaries.
+    pub const fn force_refc(self) -> EncodingProfile {
+        EncodingProfile {
+            binaries: BinaryAllocation::Refc,
+            ..self
+        }
+    }
+
+    /// Returns `self`, with ranges encoded without a `step`, for Elixir versions before 1.12.
+    pub const fn unstepped_ranges(self) -> EncodingProfile {
+        EncodingProfile {
+            ranges: RangeStyle::Unstepped,
+            ..self
+        }
+    }
+
+    /// Encodes `value` with `self`. Shorthand for `value.encode_with(env, self)`.
+    pub fn encode<'a, T>(self, env: Env<'a>, value: &T) -> Term<'a>
+    where
+        T: Encoder + ?Sized,
+    {
+        value.encode_with(env, self)
+    }
+
+    /// Decodes `term` with `self`. Shorthand for `T::decode_with(term, self)`.
+    pub fn decode<'a, T>(self, term: Term<'a>) -> NifResult<T>
+    where
+        T: Decoder<'a>,
+    {
+        T::decode_with(term, self)
+    }
+
+    /// Returns the atom used to represent `Option::None`.
+    pub fn none_atom(self) -> Atom {
+        match self.none {
+            NoneStyle::Nil => atom::nil(),
+            NoneStyle::Undefined => atom::undefined(),
+        }
+    }
+
+    /// Returns the map key for a field named `name`, whose atom is `atom`.
+    pub fn key<'a>(self, env: Env<'a>, name: &str, atom: Atom) -> Term<'a> {
+        match self.keys {
+            KeyStyle::Atom => atom.to_term(env),
+            KeyStyle::Binary => string::encode_binary(env, name),
+        }
+    }
+}
+
+impl Default for EncodingProfile {
+    fn default() -> Self {
+        EncodingProfile::ELIXIR
+    }
+}
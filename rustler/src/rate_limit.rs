@@ -0,0 +1,136 @@
+//! Token buckets, to limit the throughput of native work.
+//!
+//! A `TokenBucket` holds up to `capacity` tokens, and is refilled continuously at `rate` tokens
+//! per second, following the monotonic clock of the VM (`enif_monotonic_time`) with nanosecond
+//! precision. Work is allowed when enough tokens can be taken from the bucket.
+//!
+//! Buckets are resources, so that the same bucket can be checked by several NIFs, from several
+//! processes, and be kept on the Elixir side between calls. The resource type must be
+//! registered by calling `rustler::rate_limit::load(env)` from the `load` callback of the NIF
+//! library.
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn new_limiter(capacity: u64, rate: u64) -> ResourceArc<TokenBucket> {
+//!     ResourceArc::new(TokenBucket::new(capacity, rate))
+//! }
+//!
+//! #[rustler::nif]
+//! fn compress(limiter: ResourceArc<TokenBucket>, data: Binary) -> NifResult<OwnedBinary> {
+//!     if !limiter.try_acquire(data.len() as u64) {
+//!         return Err(Error::Atom("rate_limited"));
+//!     }
+//!     ...
+//! }
+//! ```
+
+use crate::Env;
+use rustler_sys::ErlNifTimeUnit;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// A token bucket. See the module documentation.
+pub struct TokenBucket {
+    capacity: u64,
+    rate: u64,
+    state: Mutex<State>,
+}
+
+struct State {
+    /// The tokens in the bucket, in billionths of a token.
+    nano_tokens: u128,
+    /// The monotonic time of the last refill, in nanoseconds.
+    refilled_at: i64,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket of `capacity` tokens, refilled at `rate` tokens per second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` or `rate` is 0.
+    pub fn new(capacity: u64, rate: u64) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        assert!(rate > 0, "rate must be positive");
+
+        TokenBucket {
+            capacity,
+            rate,
+            state: Mutex::new(State {
+                nano_tokens: capacity as u128 * NANOS_PER_SEC,
+                refilled_at: now(),
+            }),
+        }
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// Returns the number of tokens added to the bucket per second.
+    pub fn rate(&self) -> u64 {
+        self.rate
+    }
+
+    /// Takes `tokens` tokens from the bucket if it holds enough of them, and returns whether it
+    /// did. Never blocks.
+    pub fn try_acquire(&self, tokens: u64) -> bool {
+        let mut state = self.refill();
+        let needed = tokens as u128 * NANOS_PER_SEC;
+        if state.nano_tokens >= needed {
+            state.nano_tokens -= needed;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the number of whole tokens in the bucket.
+    pub fn available(&self) -> u64 {
+        (self.refill().nano_tokens / NANOS_PER_SEC) as u64
+    }
+
+    /// Returns how long to wait until `tokens` tokens can be acquired, or `None` if they never
+    /// can, because `tokens` is larger than the capacity of the bucket.
+    pub fn wait_time(&self, tokens: u64) -> Option<Duration> {
+        if tokens > self.capacity {
+            return None;
+        }
+
+        let state = self.refill();
+        let missing = (tokens as u128 * NANOS_PER_SEC).saturating_sub(state.nano_tokens);
+        // Every nanosecond adds `rate` billionths of a token.
+        let nanos = missing.div_ceil(self.rate as u128);
+        Some(Duration::from_nanos(nanos as u64))
+    }
+
+    /// Adds the tokens accumulated since the last refill, and returns the locked state.
+    fn refill(&self) -> MutexGuard<'_, State> {
+        // The state is consistent between statements, so a poisoned lock can be reused.
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let now = now();
+        let elapsed = now.saturating_sub(state.refilled_at).max(0) as u128;
+        let capacity = self.capacity as u128 * NANOS_PER_SEC;
+        state.nano_tokens = (state.nano_tokens + elapsed * self.rate as u128).min(capacity);
+        state.refilled_at = now;
+        state
+    }
+}
+
+/// Returns the monotonic time of the VM, in nanoseconds.
+fn now() -> i64 {
+    unsafe { rustler_sys::enif_monotonic_time(ErlNifTimeUnit::ERL_NIF_NSEC) }
+}
+
+crate::resource_type!(TokenBucket);
+
+/// Registers the resource type of `TokenBucket`. Call this from the `load` callback.
+pub fn load(env: Env) -> bool {
+    crate::resource::register::<TokenBucket>(env)
+}
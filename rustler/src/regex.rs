@@ -0,0 +1,111 @@
+//! Compiled regular expressions, kept in a resource between calls.
+//!
+//! `RegexResource` wraps a `Regex` of the `regex` crate, so that a pattern can be compiled once
+//! and used by many NIF calls. The resource type must be registered by calling
+//! `rustler::regex::load(env)` from the `load` callback of the NIF library:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn compile(source: RegexSource) -> Result<ResourceArc<RegexResource>, String> {
+//!     RegexResource::from_source(&source)
+//!         .map(ResourceArc::new)
+//!         .map_err(|err| err.to_string())
+//! }
+//!
+//! #[rustler::nif]
+//! fn scan(regex: ResourceArc<RegexResource>, text: &str) -> Vec<(usize, usize)> {
+//!     regex.positions(text)
+//! }
+//! ```
+//!
+//! `RegexSource` decodes Elixir `Regex` structs, whose options are translated with
+//! `RegexResource::from_source`. The syntax of the `regex` crate is close to the one of PCRE,
+//! used by Elixir, but has no look-around or backreferences: such patterns fail to compile.
+//!
+//! This module is only available with the `regex` feature.
+
+use crate::types::elixir_std::RegexSource;
+use crate::Env;
+use ::regex::{Regex, RegexBuilder};
+use std::collections::HashMap;
+
+/// A compiled regular expression. See the module documentation.
+pub struct RegexResource {
+    regex: Regex,
+}
+
+impl RegexResource {
+    /// Compiles `pattern` with the default options of the `regex` crate.
+    pub fn compile(pattern: &str) -> Result<Self, ::regex::Error> {
+        Ok(RegexResource {
+            regex: Regex::new(pattern)?,
+        })
+    }
+
+    /// Compiles the source of an Elixir `Regex`, translating its options: `i`, `m`, `s`, `x` and
+    /// `U` set the corresponding flags, and `u` is always on. Other options can't be translated
+    /// and make the compilation fail.
+    pub fn from_source(source: &RegexSource) -> Result<Self, ::regex::Error> {
+        let mut builder = RegexBuilder::new(&source.source);
+        for option in source.opts.chars() {
+            match option {
+                'i' => builder.case_insensitive(true),
+                'm' => builder.multi_line(true),
+                's' => builder.dot_matches_new_line(true),
+                'x' => builder.ignore_whitespace(true),
+                'U' => builder.swap_greed(true),
+                'u' => builder.unicode(true),
+                other => {
+                    return Err(::regex::Error::Syntax(format!(
+                        "unsupported regex option {:?}",
+                        other
+                    )))
+                }
+            };
+        }
+        Ok(RegexResource {
+            regex: builder.build()?,
+        })
+    }
+
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+
+    /// Returns the `{start, length}` of every match in `text`, in bytes, like
+    /// `Regex.scan(regex, text, return: :index)` returns for whole matches.
+    pub fn positions(&self, text: &str) -> Vec<(usize, usize)> {
+        self.regex
+            .find_iter(text)
+            .map(|found| (found.start(), found.len()))
+            .collect()
+    }
+
+    /// Returns the named captures of the first match in `text`, or `None` if there is no match.
+    /// Groups that didn't participate in the match are empty, like with
+    /// `Regex.named_captures/2`.
+    pub fn named_captures<'t>(&self, text: &'t str) -> Option<HashMap<String, &'t str>> {
+        let captures = self.regex.captures(text)?;
+        Some(
+            self.regex
+                .capture_names()
+                .flatten()
+                .map(|name| {
+                    let value = captures.name(name).map_or("", |found| found.as_str());
+                    (name.to_string(), value)
+                })
+                .collect(),
+        )
+    }
+}
+
+crate::resource_type!(RegexResource);
+
+/// Registers the `RegexResource` resource type. Call this from the `load` callback.
+pub fn load(env: Env) -> bool {
+    crate::resource::register::<RegexResource>(env)
+}
@@ -0,0 +1,117 @@
+//! Streaming replies to the caller of a NIF.
+//!
+//! A NIF can only return a single term, but long computations (large exports, token streams of
+//! an inference model) often want to hand out partial results as they go. The streaming reply
+//! protocol standardizes this using plain messages:
+//!
+//! * `{:chunk, ref, data}` carries one partial result.
+//! * `{:done, ref}` marks the end of the stream.
+//!
+//! `ref` is a term chosen by the caller, typically a reference made with `make_ref()`, so that
+//! the messages of concurrent streams can be told apart.
+//!
+//! ```ignore
+//! #[rustler::nif(schedule = "DirtyCpu")]
+//! fn export(env: Env, reference: Term, rows: Vec<Row>) -> Atom {
+//!     let stream = ReplyStream::new(env, reference);
+//!     for batch in rows.chunks(1000) {
+//!         stream.chunk(&render(batch));
+//!     }
+//!     stream.done();
+//!     atoms::ok()
+//! }
+//! ```
+//!
+//! Messages sent to the calling process are only received once the NIF has returned, since the
+//! process is busy running it. They are delivered in order, and before any message sent after
+//! the NIF returns. On the Elixir side, the stream can be consumed with:
+//!
+//! ```elixir
+//! ref = make_ref()
+//! :ok = MyNif.export(ref, rows)
+//!
+//! Stream.repeatedly(fn ->
+//!   receive do
+//!     {:chunk, ^ref, data} -> data
+//!     {:done, ^ref} -> :done
+//!   end
+//! end)
+//! |> Enum.take_while(&(&1 != :done))
+//! ```
+
+use crate::types::atom;
+use crate::types::LocalPid;
+use crate::{Encoder, Env, Term};
+
+/// A stream of replies to a process, following the protocol described in the module
+/// documentation.
+pub struct ReplyStream<'a> {
+    env: Env<'a>,
+    pid: LocalPid,
+    reference: Term<'a>,
+}
+
+impl<'a> ReplyStream<'a> {
+    /// Creates a stream of replies to the calling process, tagged with `reference`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `env` is process-independent.
+    pub fn new(env: Env<'a>, reference: Term<'a>) -> Self {
+        Self::to(env, env.pid(), reference)
+    }
+
+    /// Creates a stream of replies to `pid`, tagged with `reference`.
+    pub fn to(env: Env<'a>, pid: LocalPid, reference: Term<'a>) -> Self {
+        ReplyStream {
+            env,
+            pid,
+            reference,
+        }
+    }
+
+    /// Returns the term that tags the messages of this stream.
+    pub fn reference(&self) -> Term<'a> {
+        self.reference
+    }
+
+    /// Sends `{:chunk, ref, data}`.
+    pub fn chunk<T>(&self, data: &T)
+    where
+        T: Encoder + ?Sized,
+    {
+        let message = (atom::chunk(), self.reference, data.encode(self.env)).encode(self.env);
+        self.env.send(&self.pid, message);
+    }
+
+    /// Sends `{:done, ref}`, ending the stream.
+    pub fn done(self) {
+        let message = (atom::done(), self.reference).encode(self.env);
+        self.env.send(&self.pid, message);
+    }
+}
+
+impl<'a> Env<'a> {
+    /// Sends `{:chunk, reference, data}` to the calling process.
+    ///
+    /// See the `reply` module for the protocol, and `ReplyStream` to send several chunks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this environment is process-independent.
+    pub fn reply_chunk<T>(self, reference: Term<'a>, data: T)
+    where
+        T: Encoder,
+    {
+        ReplyStream::new(self, reference).chunk(&data);
+    }
+
+    /// Sends `{:done, reference}` to the calling process.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this environment is process-independent.
+    pub fn reply_done(self, reference: Term<'a>) {
+        ReplyStream::new(self, reference).done();
+    }
+}
@@ -4,12 +4,17 @@
 //! NIF calls. The struct will be automatically dropped when the BEAM GC decides that there are no
 //! more references to the resource.
 
+use std::any;
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::mem;
 use std::ops::Deref;
 use std::ptr;
+use std::sync::Mutex;
 
 use super::{Decoder, Encoder, Env, Error, NifResult, Term};
+use crate::monitor::MonitorDown;
+use crate::select::SelectStop;
 use crate::wrapper::{
     c_void, NifResourceFlags, MUTABLE_NIF_RESOURCE_HANDLE, NIF_ENV, NIF_RESOURCE_TYPE,
 };
@@ -58,6 +63,10 @@ where
 /// Drop a T that lives in an Erlang resource. (erlang_nif-sys requires us to declare this
 /// function safe, but it is of course thoroughly unsafe!)
 extern "C" fn resource_destructor<T>(_env: NIF_ENV, handle: MUTABLE_NIF_RESOURCE_HANDLE) {
+    #[cfg(feature = "resource-tracking")]
+    crate::resource_tracking::destroyed::<T>(handle);
+    crate::resource_hooks::destroyed::<T>(handle);
+
     unsafe {
         let aligned = align_alloced_mem_for_struct::<T>(handle);
         let res = aligned as *mut T;
@@ -86,10 +95,160 @@ pub fn open_struct_resource_type<T: ResourceTypeProvider>(
         )
     };
 
-    res.map(|r| ResourceType {
-        res: r,
+    res.map(|r| opened(r, name))
+}
+
+/// The callbacks of a resource type besides its destructor, chosen with the options of
+/// `resource!`. Any combination of them can be set.
+#[doc(hidden)]
+pub struct ResourceTypeInit<T> {
+    stop: Option<rustler_sys::ErlNifResourceStop>,
+    down: Option<rustler_sys::ErlNifResourceDown>,
+    #[cfg(nif_version_2_16)]
+    dyncall: Option<rustler_sys::ErlNifResourceDynCall>,
+    struct_type: PhantomData<T>,
+}
+
+impl<T> Default for ResourceTypeInit<T> {
+    fn default() -> Self {
+        ResourceTypeInit {
+            stop: None,
+            down: None,
+            #[cfg(nif_version_2_16)]
+            dyncall: None,
+            struct_type: PhantomData,
+        }
+    }
+}
+
+impl<T: ResourceTypeProvider> ResourceTypeInit<T> {
+    /// For resources passed to `enif_select`: `SelectStop::stop` is called when the VM stops
+    /// selecting on an event of a resource of this type.
+    pub fn select(mut self) -> Self
+    where
+        T: SelectStop,
+    {
+        self.stop = Some(crate::select::stop::<T>);
+        self
+    }
+
+    /// For resources monitoring processes with `ResourceArc::monitor`: `MonitorDown::down` is
+    /// called when a monitored process exits.
+    pub fn monitor(mut self) -> Self
+    where
+        T: MonitorDown,
+    {
+        self.down = Some(crate::monitor::down::<T>);
+        self
+    }
+
+    /// For resources that other NIF libraries can call with `Env::call_dynamic`:
+    /// `DynamicCall::dynamic_call` is called with the data they pass.
+    #[cfg(nif_version_2_16)]
+    pub fn dyncall(mut self) -> Self
+    where
+        T: crate::dyncall::DynamicCall,
+    {
+        self.dyncall = Some(crate::dyncall::dyncall::<T>);
+        self
+    }
+
+    /// Like `open_struct_resource_type`, with the callbacks that are set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` isn't null-terminated.
+    pub fn open(self, env: Env, name: &str, flags: NifResourceFlags) -> Option<ResourceType<T>> {
+        #[cfg(nif_version_2_16)]
+        {
+            if self.dyncall.is_some() {
+                let init = rustler_sys::ErlNifResourceTypeInit2_16 {
+                    dtor: Some(resource_destructor::<T>),
+                    stop: self.stop,
+                    down: self.down,
+                    members: 4,
+                    dyncall: self.dyncall,
+                };
+                let res = unsafe {
+                    crate::wrapper::resource::init_resource_type(
+                        env.as_c_arg(),
+                        name.as_bytes(),
+                        &init,
+                        flags,
+                    )
+                };
+                return res.map(|r| opened(r, name));
+            }
+        }
+
+        if self.stop.is_none() && self.down.is_none() {
+            return open_struct_resource_type(env, name, flags);
+        }
+
+        let init = rustler_sys::ErlNifResourceTypeInit {
+            dtor: Some(resource_destructor::<T>),
+            stop: self.stop,
+            down: self.down,
+        };
+        let res = unsafe {
+            crate::wrapper::resource::open_resource_type_x(
+                env.as_c_arg(),
+                name.as_bytes(),
+                &init,
+                flags,
+            )
+        };
+        res.map(|r| opened(r, name))
+    }
+}
+
+fn opened<T>(res: NIF_RESOURCE_TYPE, name: &str) -> ResourceType<T> {
+    register_name::<T>(name);
+    ResourceType {
+        res,
         struct_type: PhantomData,
-    })
+    }
+}
+
+lazy_static::lazy_static! {
+    /// The names of the opened resource types, by Rust type name.
+    static ref NAMES: Mutex<HashMap<&'static str, String>> = Mutex::new(HashMap::new());
+}
+
+fn register_name<T>(name: &str) {
+    let name = name.trim_end_matches('\0').to_string();
+    NAMES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(any::type_name::<T>(), name);
+}
+
+/// Implemented by `resource_type!` to register the resource type of a struct.
+#[doc(hidden)]
+pub trait ResourceTypeRegistration: ResourceTypeProvider {
+    fn register(env: Env) -> bool;
+}
+
+/// Registers the resource type of `T`, declared with `resource_type!`. Call it from the `load`
+/// callback of the NIF library, which should fail if it returns `false`.
+pub fn register<T: ResourceTypeRegistration>(env: Env) -> bool {
+    T::register(env)
+}
+
+/// Returns the name the resource type of `T` was registered with, the one shown in crash dumps,
+/// or `None` if it has not been registered.
+pub fn registered_name<T: ResourceTypeProvider>() -> Option<String> {
+    NAMES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(any::type_name::<T>())
+        .cloned()
+}
+
+/// Returns the `T` stored in the resource `handle`, as passed to resource callbacks.
+/// Unsafe: `handle` must be a live resource of the type of `T`.
+pub(crate) unsafe fn resource_data<'a, T>(handle: *const c_void) -> &'a T {
+    &*(align_alloced_mem_for_struct::<T>(handle) as *const T)
 }
 
 fn get_alloc_size_struct<T>() -> usize {
@@ -139,6 +298,9 @@ where
 
         unsafe { ptr::write(aligned_mem, data) };
 
+        #[cfg(feature = "resource-tracking")]
+        crate::resource_tracking::created::<T>(mem_raw);
+
         ResourceArc {
             raw: mem_raw,
             inner: aligned_mem,
@@ -179,6 +341,11 @@ where
         self.raw
     }
 
+    /// Returns the resource handle, as expected by `enif_monitor_process`.
+    pub(crate) fn handle(&self) -> *const c_void {
+        self.raw
+    }
+
     fn inner(&self) -> &T {
         unsafe { &*self.inner }
     }
@@ -235,17 +402,54 @@ macro_rules! resource_struct_init {
     };
 }
 
+/// Registers the resource type of `$struct_name`. Call it from the `load` callback of the NIF
+/// library:
+///
+/// ```ignore
+/// fn load(env: Env, _info: Term) -> bool {
+///     rustler::resource!(Connection, env);
+///     rustler::resource!(Session, env, name = "MyApp.Session");
+///     true
+/// }
+/// ```
+///
+/// The name of the resource type is the one shown for its resources by `crashdump_viewer` and
+/// `:erlang.system_info/1`. It defaults to the name of the struct, and can be set with `name`.
+/// It must stay the same across upgrades of the library, since resource types are matched by
+/// name when a library is reloaded.
+///
+/// `resource!` implements traits for the struct inside the `load` callback, which the
+/// `non_local_definitions` lint warns about. `resource_type!` takes the same options at module
+/// scope instead.
+///
+/// Options after the name set the callbacks of the resource type, in any combination:
+///
+/// * With `select`, the struct must implement `rustler::select::SelectStop`, and its resources
+///   can be passed to `Env::select_read` and `Env::select_write`.
+/// * With `monitor`, the struct must implement `rustler::monitor::MonitorDown`, and its resources
+///   can monitor processes with `ResourceArc::monitor`.
+/// * With `dyncall`, which requires NIF 2.16, the struct must implement
+///   `rustler::dyncall::DynamicCall`, and other NIF libraries can call its resources with
+///   `Env::call_dynamic`, using the name of the resource type.
+///
+/// ```ignore
+/// rustler::resource!(Socket, env, select);
+/// rustler::resource!(Lock, env, name = "MyApp.Lock", monitor);
+/// rustler::resource!(Pipe, env, name = "MyApp.Pipe", select, monitor, dyncall);
+/// ```
 #[macro_export]
 macro_rules! resource {
-    ($struct_name:ty, $env: ident) => {
+    (@open $struct_name:ty, $env: ident, $name:expr $(, $callback:ident)*) => {
         {
             static mut STRUCT_TYPE: Option<$crate::resource::ResourceType<$struct_name>> = None;
 
             let temp_struct_type =
-                match $crate::resource::open_struct_resource_type::<$struct_name>(
-                    $env,
-                    concat!(stringify!($struct_name), "\x00"),
-                    $crate::resource::NIF_RESOURCE_FLAGS::ERL_NIF_RT_CREATE
+                match $crate::resource::ResourceTypeInit::<$struct_name>::default()
+                    $(.$callback())*
+                    .open(
+                        $env,
+                        concat!($name, "\x00"),
+                        $crate::resource::NIF_RESOURCE_FLAGS::ERL_NIF_RT_CREATE
                     ) {
                     Some(inner) => inner,
                     None => {
@@ -262,5 +466,65 @@ macro_rules! resource {
                 }
             }
         }
-    }
+    };
+    ($struct_name:ty, $env: ident, name = $name:expr $(, $callback:ident)*) => {
+        $crate::resource!(@open $struct_name, $env, $name $(, $callback)*)
+    };
+    ($struct_name:ty, $env: ident $(, $callback:ident)*) => {
+        $crate::resource!(@open $struct_name, $env, stringify!($struct_name) $(, $callback)*)
+    };
+}
+
+/// Declares the resource type of `$struct_name`, with the same options as `resource!`. Unlike
+/// `resource!`, it is used at module scope, and the resource type is registered by calling
+/// `rustler::resource::register` from the `load` callback:
+///
+/// ```ignore
+/// rustler::resource_type!(Connection);
+/// rustler::resource_type!(Session, name = "MyApp.Session", monitor);
+///
+/// fn load(env: Env, _info: Term) -> bool {
+///     rustler::resource::register::<Connection>(env)
+///         && rustler::resource::register::<Session>(env)
+/// }
+/// ```
+#[macro_export]
+macro_rules! resource_type {
+    (@declare $struct_name:ty, $name:expr $(, $callback:ident)*) => {
+        const _: () = {
+            static mut STRUCT_TYPE: Option<$crate::resource::ResourceType<$struct_name>> = None;
+
+            impl $crate::resource::ResourceTypeProvider for $struct_name {
+                fn get_type() -> &'static $crate::resource::ResourceType<Self> {
+                    unsafe { (*::std::ptr::addr_of!(STRUCT_TYPE)).as_ref() }
+                        .expect("The resource type hasn't been registered. Did you remember to call `rustler::resource::register` from the `load` callback?")
+                }
+            }
+
+            impl $crate::resource::ResourceTypeRegistration for $struct_name {
+                fn register(env: $crate::Env) -> bool {
+                    let struct_type = $crate::resource::ResourceTypeInit::<$struct_name>::default()
+                        $(.$callback())*
+                        .open(
+                            env,
+                            concat!($name, "\x00"),
+                            $crate::resource::NIF_RESOURCE_FLAGS::ERL_NIF_RT_CREATE,
+                        );
+                    match struct_type {
+                        Some(struct_type) => {
+                            unsafe { STRUCT_TYPE = Some(struct_type) };
+                            true
+                        }
+                        None => false,
+                    }
+                }
+            }
+        };
+    };
+    ($struct_name:ty, name = $name:expr $(, $callback:ident)*) => {
+        $crate::resource_type!(@declare $struct_name, $name $(, $callback)*);
+    };
+    ($struct_name:ty $(, $callback:ident)*) => {
+        $crate::resource_type!(@declare $struct_name, stringify!($struct_name) $(, $callback)*);
+    };
 }
@@ -0,0 +1,100 @@
+//! Callbacks observing the destruction of resources.
+//!
+//! Caches and secondary indexes are often keyed by the resource they describe, and must forget
+//! it when the VM destroys it. Instead of a `Drop` implementation for every resource type, a
+//! library can register a callback with `on_destroy`, which is called with the `ResourceId` of
+//! every destroyed resource, whatever its type:
+//!
+//! ```ignore
+//! lazy_static! {
+//!     static ref SIZES: Mutex<HashMap<ResourceId, usize>> = Mutex::new(HashMap::new());
+//! }
+//!
+//! fn load(env: Env, _info: Term) -> bool {
+//!     rustler::resource!(Document, env);
+//!     rustler::resource_hooks::on_destroy(|id| {
+//!         SIZES.lock().unwrap().remove(&id);
+//!     });
+//!     true
+//! }
+//! ```
+//!
+//! Callbacks run on the thread destroying the resource, before its value is dropped, and must
+//! not block or call `on_destroy`. A panic in a callback is caught and ignored.
+
+use crate::resource::{ResourceArc, ResourceTypeProvider};
+use crate::wrapper::c_void;
+use std::any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+/// The identity of a resource, stable for as long as the resource lives. The handle of a
+/// destroyed resource can be reused by a later one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ResourceId {
+    handle: usize,
+    type_name: &'static str,
+}
+
+impl ResourceId {
+    /// Returns the identity of `resource`.
+    pub fn of<T: ResourceTypeProvider>(resource: &ResourceArc<T>) -> Self {
+        ResourceId::new::<T>(resource.handle())
+    }
+
+    fn new<T>(handle: *const c_void) -> Self {
+        ResourceId {
+            handle: handle as usize,
+            type_name: any::type_name::<T>(),
+        }
+    }
+
+    /// Returns the address of the resource.
+    pub fn handle(&self) -> usize {
+        self.handle
+    }
+
+    /// Returns the name of the Rust type stored in the resource.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+type Callback = Box<dyn Fn(ResourceId) + Send + Sync>;
+
+/// Whether any callback is registered, checked before taking the lock on every destruction.
+static HAS_CALLBACKS: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref CALLBACKS: RwLock<Vec<Callback>> = RwLock::new(Vec::new());
+}
+
+/// Registers `callback` to be called with the identity of every resource destroyed from now on.
+/// Callbacks are never unregistered, and are called in the order they were registered.
+pub fn on_destroy<F>(callback: F)
+where
+    F: Fn(ResourceId) + Send + Sync + 'static,
+{
+    CALLBACKS
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .push(Box::new(callback));
+    HAS_CALLBACKS.store(true, Ordering::Release);
+}
+
+/// Calls the callbacks with the resource `handle` of type `T`, which is being destroyed.
+pub(crate) fn destroyed<T>(handle: *const c_void) {
+    if !HAS_CALLBACKS.load(Ordering::Acquire) {
+        return;
+    }
+
+    let id = ResourceId::new::<T>(handle);
+    let callbacks = CALLBACKS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    for callback in callbacks.iter() {
+        // Unwinding out of the destructor, which is called by the VM, would abort.
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| callback(id)));
+    }
+}
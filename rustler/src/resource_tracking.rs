@@ -0,0 +1,126 @@
+//! Counting live resources, to diagnose leaks.
+//!
+//! With the `resource-tracking` feature, every resource created by `ResourceArc::new` is counted
+//! until the VM destroys it, and `rustler::init!` registers the `__rustler_resources__/0` NIF,
+//! which returns the live resources of every type:
+//!
+//! ```elixir
+//! iex> MyNif.__rustler_resources__()
+//! [{"my_nif::Connection", 12, []}]
+//! ```
+//!
+//! `use Rustler` defines the function in the module. A count that keeps growing under a steady
+//! load usually means that resources are kept alive by a term stored somewhere, or by a
+//! `ResourceArc` that is never dropped.
+//!
+//! With the `resource-backtraces` feature, the backtrace of the creation of every live resource
+//! is captured and returned as the third element of each tuple. Capturing backtraces is slow, so
+//! this is meant for staging environments.
+//!
+//! This module is only available with the `resource-tracking` feature.
+
+use crate::wrapper::{c_int, c_void, DEF_NIF_FUNC, NIF_ENV, NIF_TERM};
+use crate::{Encoder, Env};
+use std::any;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+/// The live resources of a type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LiveResources {
+    pub type_name: &'static str,
+    pub count: usize,
+    /// The backtraces of the creation of the resources, with the `resource-backtraces` feature.
+    pub backtraces: Vec<String>,
+}
+
+#[derive(Default)]
+struct Registry {
+    counts: HashMap<&'static str, usize>,
+    /// The backtraces of the live resources, by type and handle.
+    #[cfg(feature = "resource-backtraces")]
+    backtraces: HashMap<&'static str, HashMap<usize, String>>,
+}
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: Mutex<Registry> = Mutex::new(Registry::default());
+}
+
+fn registry() -> MutexGuard<'static, Registry> {
+    // The registry is consistent between statements, so a poisoned lock can be reused.
+    REGISTRY
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Records the creation of the resource `handle` of type `T`.
+pub(crate) fn created<T>(handle: *const c_void) {
+    let type_name = any::type_name::<T>();
+    let mut registry = registry();
+    *registry.counts.entry(type_name).or_insert(0) += 1;
+
+    #[cfg(feature = "resource-backtraces")]
+    registry.backtraces.entry(type_name).or_default().insert(
+        handle as usize,
+        std::backtrace::Backtrace::force_capture().to_string(),
+    );
+    #[cfg(not(feature = "resource-backtraces"))]
+    let _ = handle;
+}
+
+/// Records the destruction of the resource `handle` of type `T`.
+pub(crate) fn destroyed<T>(handle: *const c_void) {
+    let type_name = any::type_name::<T>();
+    let mut registry = registry();
+    if let Some(count) = registry.counts.get_mut(type_name) {
+        *count = count.saturating_sub(1);
+    }
+
+    #[cfg(feature = "resource-backtraces")]
+    if let Some(backtraces) = registry.backtraces.get_mut(type_name) {
+        backtraces.remove(&(handle as usize));
+    }
+    #[cfg(not(feature = "resource-backtraces"))]
+    let _ = handle;
+}
+
+/// Returns the live resources of every type that had resources, sorted by type name.
+pub fn live_resources() -> Vec<LiveResources> {
+    let registry = registry();
+    let mut live: Vec<LiveResources> = registry
+        .counts
+        .iter()
+        .map(|(&type_name, &count)| LiveResources {
+            type_name,
+            count,
+            #[cfg(feature = "resource-backtraces")]
+            backtraces: registry
+                .backtraces
+                .get(type_name)
+                .map(|backtraces| backtraces.values().cloned().collect())
+                .unwrap_or_default(),
+            #[cfg(not(feature = "resource-backtraces"))]
+            backtraces: Vec::new(),
+        })
+        .collect();
+    live.sort_by_key(|resources| resources.type_name);
+    live
+}
+
+unsafe extern "C" fn resources_nif(env: NIF_ENV, _argc: c_int, _argv: *const NIF_TERM) -> NIF_TERM {
+    let lifetime = ();
+    let env = Env::new(&lifetime, env);
+    let live: Vec<(&str, usize, Vec<String>)> = live_resources()
+        .into_iter()
+        .map(|resources| (resources.type_name, resources.count, resources.backtraces))
+        .collect();
+    live.encode(env).as_c_arg()
+}
+
+/// The `__rustler_resources__/0` NIF.
+pub(crate) const RESOURCES_NIF: DEF_NIF_FUNC = DEF_NIF_FUNC {
+    name: b"__rustler_resources__\0".as_ptr(),
+    arity: 0,
+    function: resources_nif,
+    flags: 0,
+};
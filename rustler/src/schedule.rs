@@ -1,5 +1,6 @@
 use crate::wrapper::ErlNifTaskFlags;
 use crate::Env;
+use std::time::{Duration, Instant};
 
 pub enum SchedulerFlags {
     Normal = ErlNifTaskFlags::ERL_NIF_NORMAL_JOB as isize,
@@ -11,3 +12,64 @@ pub fn consume_timeslice(env: Env, percent: i32) -> bool {
     let success = unsafe { rustler_sys::enif_consume_timeslice(env.as_c_arg(), percent) };
     success == 1
 }
+
+/// The time spent between two reports to `enif_consume_timeslice` by `Budgeted`: a tenth of the
+/// timeslice of about a millisecond.
+const REPORT_INTERVAL: Duration = Duration::from_micros(100);
+
+/// An iterator that stops once the timeslice of the NIF is used up, returned by `Env::budgeted`.
+///
+/// The time spent between items, processing them, is reported to the VM with
+/// `enif_consume_timeslice`. Once the VM reports that the timeslice is used up, `next` returns
+/// `None` without pulling more items from the inner iterator, and the remaining items can be
+/// taken back with `into_inner` to continue later, e.g. after rescheduling the NIF.
+pub struct Budgeted<'a, I> {
+    env: Env<'a>,
+    iter: I,
+    started: Instant,
+    exhausted: bool,
+}
+
+impl<'a, I: Iterator> Budgeted<'a, I> {
+    pub(crate) fn new(env: Env<'a>, iter: I) -> Self {
+        Budgeted {
+            env,
+            iter,
+            started: Instant::now(),
+            exhausted: false,
+        }
+    }
+
+    /// Returns whether the iteration stopped because the timeslice was used up.
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    /// Returns the inner iterator, with the items that were not processed.
+    pub fn into_inner(self) -> I {
+        self.iter
+    }
+}
+
+impl<'a, I: Iterator> Iterator for Budgeted<'a, I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        if self.exhausted {
+            return None;
+        }
+
+        let elapsed = self.started.elapsed();
+        if elapsed >= REPORT_INTERVAL {
+            // Every 10µs spent is 1% of the timeslice.
+            let percent = (elapsed.as_micros() / 10).min(100) as i32;
+            if self.env.consume_timeslice(percent) {
+                self.exhausted = true;
+                return None;
+            }
+            self.started = Instant::now();
+        }
+
+        self.iter.next()
+    }
+}
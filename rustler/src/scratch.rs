@@ -0,0 +1,49 @@
+//! Reusable scratch buffers for temporaries that only live during a call.
+//!
+//! NIFs that build bytes before copying them into a binary, or encode a value into an
+//! intermediate buffer, often allocate and free a `Vec<u8>` on every call. `with_buffer` lends a
+//! buffer kept by the current thread instead. Since NIFs run on scheduler threads, every
+//! scheduler ends up with its own buffer, and the allocation is reused by all the calls it runs:
+//!
+//! ```
+//! let len = rustler::scratch::with_buffer(|buf| {
+//!     buf.extend_from_slice(b"hello");
+//!     buf.len()
+//! });
+//! assert_eq!(len, 5);
+//!
+//! // The buffer is cleared between calls.
+//! rustler::scratch::with_buffer(|buf| assert!(buf.is_empty()));
+//! ```
+//!
+//! Nested calls get a new buffer, which is freed when they return.
+
+use std::cell::RefCell;
+
+/// The capacity above which the buffer of a thread is freed after a call instead of being kept,
+/// so that a single large call doesn't hold its memory forever.
+pub const MAX_RETAINED_CAPACITY: usize = 1 << 20;
+
+thread_local! {
+    static BUFFER: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Calls `f` with the empty scratch buffer of the current thread. See the module documentation.
+pub fn with_buffer<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut Vec<u8>) -> R,
+{
+    BUFFER.with(|buffer| match buffer.try_borrow_mut() {
+        Ok(mut buf) => {
+            buf.clear();
+            let result = f(&mut buf);
+            buf.clear();
+            if buf.capacity() > MAX_RETAINED_CAPACITY {
+                *buf = Vec::new();
+            }
+            result
+        }
+        // Already lent to an outer call.
+        Err(_) => f(&mut Vec::new()),
+    })
+}
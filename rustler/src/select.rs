@@ -0,0 +1,183 @@
+//! Waiting for file descriptors to become ready, with `enif_select`.
+//!
+//! Instead of blocking a dirty scheduler on a socket or a pipe, a NIF can ask the VM to send a
+//! message to the calling process when the descriptor becomes readable or writable. The message
+//! is `{:select, resource, reference, :ready_input}` or `{:select, resource, reference,
+//! :ready_output}`, and the process then calls the NIF again to read or write without blocking.
+//!
+//! Selection is tied to a resource, whose type must be registered with the `select` option of
+//! `resource!` and implement `SelectStop`:
+//!
+//! ```ignore
+//! struct Socket {
+//!     stream: Mutex<UnixStream>,
+//! }
+//!
+//! impl SelectStop for Socket {
+//!     fn stop(&self, _event: Event, _is_direct_call: bool) {
+//!         // The VM no longer uses the descriptor, it can be closed.
+//!     }
+//! }
+//!
+//! #[rustler::nif]
+//! fn recv_ready<'a>(env: Env<'a>, socket: ResourceArc<Socket>, reference: Term<'a>) -> NifResult<()> {
+//!     let fd = socket.stream.lock().unwrap().as_raw_fd();
+//!     Ok(env.select_read(fd, &socket, reference)?)
+//! }
+//! ```
+//!
+//! A descriptor must not be closed while it is selected: `Env::select_stop` must be called
+//! first, and the descriptor closed once `SelectStop::stop` has been called.
+
+use crate::resource::{resource_data, ResourceArc, ResourceTypeProvider};
+use crate::types::atom;
+use crate::wrapper::{c_int, c_void};
+use crate::{Atom, Encoder, Env, Error, Term};
+use rustler_sys::{ErlNifEnv, ErlNifSelectFlags};
+use std::fmt;
+use std::ptr;
+
+/// A file descriptor on Unix, or an event handle on Windows.
+pub type Event = rustler_sys::ErlNifEvent;
+
+/// The callback of resources passed to `enif_select`.
+pub trait SelectStop: ResourceTypeProvider {
+    /// Called when the VM has stopped selecting on `event` after `Env::select_stop`, so that it
+    /// can be closed. `is_direct_call` is `true` when it is called from `select_stop` itself, and
+    /// `false` when it is called later from another thread.
+    fn stop(&self, event: Event, is_direct_call: bool);
+}
+
+pub(crate) unsafe extern "C" fn stop<T: SelectStop>(
+    _env: *mut ErlNifEnv,
+    obj: *mut c_void,
+    event: Event,
+    is_direct_call: c_int,
+) {
+    let resource: &T = resource_data(obj);
+    resource.stop(event, is_direct_call != 0);
+}
+
+/// The error returned when `enif_select` fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectError {
+    /// The event is not a valid descriptor.
+    InvalidEvent,
+    /// The descriptor could not be added to the VM's poll set.
+    Failed,
+}
+
+impl fmt::Display for SelectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SelectError::InvalidEvent => write!(f, "invalid event"),
+            SelectError::Failed => write!(f, "select failed"),
+        }
+    }
+}
+
+impl std::error::Error for SelectError {}
+
+impl Encoder for SelectError {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        let name = match self {
+            SelectError::InvalidEvent => "invalid_event",
+            SelectError::Failed => "select_failed",
+        };
+        Atom::from_str(env, name).unwrap().encode(env)
+    }
+}
+
+/// An invalid event raises `ArgumentError`, a failure returns `{:error, :select_failed}`.
+impl From<SelectError> for Error {
+    fn from(err: SelectError) -> Error {
+        match err {
+            SelectError::InvalidEvent => Error::BadArg,
+            SelectError::Failed => Error::Term(Box::new(err)),
+        }
+    }
+}
+
+/// How `Env::select_stop` stopped the selection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectStopped {
+    /// `SelectStop::stop` was called before `select_stop` returned.
+    Called,
+    /// `SelectStop::stop` will be called from another thread.
+    Scheduled,
+}
+
+/// ## Selecting descriptors
+impl<'a> Env<'a> {
+    /// Asks the VM to send `{:select, resource, reference, :ready_input}` to the calling process
+    /// once `event` is readable. The selection is one-shot: it must be renewed after each
+    /// message. `reference` must be a reference or the atom `undefined`.
+    pub fn select_read<T: SelectStop>(
+        self,
+        event: Event,
+        resource: &ResourceArc<T>,
+        reference: Term<'a>,
+    ) -> Result<(), SelectError> {
+        self.select(event, rustler_sys::ERL_NIF_SELECT_READ, resource, reference)
+            .map(|_| ())
+    }
+
+    /// Like `select_read`, for `{:select, resource, reference, :ready_output}` once `event` is
+    /// writable.
+    pub fn select_write<T: SelectStop>(
+        self,
+        event: Event,
+        resource: &ResourceArc<T>,
+        reference: Term<'a>,
+    ) -> Result<(), SelectError> {
+        self.select(
+            event,
+            rustler_sys::ERL_NIF_SELECT_WRITE,
+            resource,
+            reference,
+        )
+        .map(|_| ())
+    }
+
+    /// Stops selecting on `event`. `SelectStop::stop` is called once the VM no longer uses the
+    /// descriptor, either right away or later from another thread.
+    pub fn select_stop<T: SelectStop>(
+        self,
+        event: Event,
+        resource: &ResourceArc<T>,
+    ) -> Result<SelectStopped, SelectError> {
+        let undefined = atom::undefined().to_term(self);
+        let result = self.select(event, rustler_sys::ERL_NIF_SELECT_STOP, resource, undefined)?;
+        if result & rustler_sys::ERL_NIF_SELECT_STOP_CALLED != 0 {
+            Ok(SelectStopped::Called)
+        } else {
+            Ok(SelectStopped::Scheduled)
+        }
+    }
+
+    fn select<T: SelectStop>(
+        self,
+        event: Event,
+        mode: ErlNifSelectFlags,
+        resource: &ResourceArc<T>,
+        reference: Term<'a>,
+    ) -> Result<c_int, SelectError> {
+        let result = unsafe {
+            rustler_sys::enif_select(
+                self.as_c_arg(),
+                event,
+                mode,
+                resource.handle(),
+                ptr::null(),
+                reference.as_c_arg(),
+            )
+        };
+        if result < 0 {
+            if result & rustler_sys::ERL_NIF_SELECT_INVALID_EVENT != 0 {
+                return Err(SelectError::InvalidEvent);
+            }
+            return Err(SelectError::Failed);
+        }
+        Ok(result)
+    }
+}
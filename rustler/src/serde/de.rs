@@ -0,0 +1,344 @@
+use super::Error;
+use crate::dynamic::TermType;
+use crate::types::atom;
+use crate::types::tuple::get_tuple;
+use crate::{Binary, ListIterator, MapIterator, Term};
+use ::serde::de::{
+    self, Deserialize, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
+use std::vec;
+
+/// Deserializes a value from `term`. See the module documentation for the representation.
+pub fn from_term<'a, T>(term: Term<'a>) -> Result<T, Error>
+where
+    T: Deserialize<'a>,
+{
+    T::deserialize(Deserializer::new(term))
+}
+
+/// A serde `Deserializer` reading a term.
+///
+/// Strings and bytes are borrowed from binaries, without copying them.
+#[derive(Clone, Copy)]
+pub struct Deserializer<'a> {
+    term: Term<'a>,
+}
+
+impl<'a> Deserializer<'a> {
+    pub fn new(term: Term<'a>) -> Self {
+        Deserializer { term }
+    }
+
+    fn is_nil(&self) -> bool {
+        self.term.as_c_arg() == atom::nil().as_c_arg()
+    }
+
+    fn invalid(&self, expected: &str) -> Error {
+        Error::new(format_args!(
+            "expected {}, got a term of type {:?}",
+            expected,
+            self.term.get_type()
+        ))
+    }
+
+    /// Returns the string of an atom or a UTF-8 binary.
+    fn identifier<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        if let Ok(name) = self.term.atom_to_string() {
+            return visitor.visit_string(name);
+        }
+        match self.term.decode::<&'a str>() {
+            Ok(name) => visitor.visit_borrowed_str(name),
+            Err(_) => Err(self.invalid("an atom or a string")),
+        }
+    }
+
+    fn number<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        if let Ok(value) = self.term.decode::<i64>() {
+            visitor.visit_i64(value)
+        } else if let Ok(value) = self.term.decode::<u64>() {
+            visitor.visit_u64(value)
+        } else if let Ok(value) = self.term.decode::<f64>() {
+            visitor.visit_f64(value)
+        } else if let Ok(value) = self.term.decode::<i128>() {
+            visitor.visit_i128(value)
+        } else if let Ok(value) = self.term.decode::<u128>() {
+            visitor.visit_u128(value)
+        } else {
+            Err(Error::new("integer too large"))
+        }
+    }
+}
+
+impl<'a> de::Deserializer<'a> for Deserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.term.get_type() {
+            TermType::Atom => {
+                if let Ok(value) = self.term.decode::<bool>() {
+                    visitor.visit_bool(value)
+                } else if self.is_nil() {
+                    visitor.visit_unit()
+                } else {
+                    self.identifier(visitor)
+                }
+            }
+            TermType::Binary => {
+                let binary: Binary<'a> =
+                    self.term.decode().map_err(|_| self.invalid("a binary"))?;
+                match std::str::from_utf8(binary.as_slice()) {
+                    Ok(string) => visitor.visit_borrowed_str(string),
+                    Err(_) => visitor.visit_borrowed_bytes(binary.as_slice()),
+                }
+            }
+            TermType::EmptyList | TermType::List => {
+                let iter = self
+                    .term
+                    .decode()
+                    .map_err(|_| self.invalid("a proper list"))?;
+                visitor.visit_seq(ListAccess { iter })
+            }
+            TermType::Tuple => self.deserialize_tuple(0, visitor),
+            TermType::Map => self.deserialize_map(visitor),
+            TermType::Number => self.number(visitor),
+            _ => Err(self.invalid("a term representing a Rust value")),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.term.decode::<Binary<'a>>() {
+            Ok(binary) => visitor.visit_borrowed_bytes(binary.as_slice()),
+            Err(_) => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.is_nil() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.is_nil() {
+            visitor.visit_unit()
+        } else {
+            Err(self.invalid("nil"))
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V: Visitor<'a>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        match get_tuple(self.term) {
+            Ok(items) => visitor.visit_seq(TupleAccess {
+                items: items.into_iter(),
+            }),
+            Err(_) => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        match MapIterator::new(self.term) {
+            Some(iter) => visitor.visit_map(MapEntries {
+                len: self.term.map_size().unwrap_or(0),
+                iter,
+                value: None,
+            }),
+            None => Err(self.invalid("a map")),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match get_tuple(self.term) {
+            Ok(items) if !items.is_empty() => {
+                let mut items = items.into_iter();
+                let tag = Deserializer::new(items.next().unwrap());
+                visitor.visit_enum(Variant { tag, items })
+            }
+            Ok(_) => Err(self.invalid("a tagged tuple")),
+            Err(_) => visitor.visit_enum(Variant {
+                tag: self,
+                items: Vec::new().into_iter(),
+            }),
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.identifier(visitor)
+    }
+
+    ::serde::forward_to_deserialize_any! {
+        <W: Visitor<'a>>
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        seq ignored_any
+    }
+}
+
+struct ListAccess<'a> {
+    iter: ListIterator<'a>,
+}
+
+impl<'a> SeqAccess<'a> for ListAccess<'a> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'a>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        self.iter
+            .next()
+            .map(|term| seed.deserialize(Deserializer::new(term)))
+            .transpose()
+    }
+}
+
+struct TupleAccess<'a> {
+    items: vec::IntoIter<Term<'a>>,
+}
+
+impl<'a> SeqAccess<'a> for TupleAccess<'a> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'a>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        self.items
+            .next()
+            .map(|term| seed.deserialize(Deserializer::new(term)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct MapEntries<'a> {
+    len: usize,
+    iter: MapIterator<'a>,
+    value: Option<Term<'a>>,
+}
+
+impl<'a> MapAccess<'a> for MapEntries<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'a>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.len -= 1;
+                self.value = Some(value);
+                seed.deserialize(Deserializer::new(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'a>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(Deserializer::new(value)),
+            None => Err(Error::new("map value requested before its key")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+/// An enum variant: a tag, followed by the values of the variant if it was a tuple.
+struct Variant<'a> {
+    tag: Deserializer<'a>,
+    items: vec::IntoIter<Term<'a>>,
+}
+
+impl<'a> EnumAccess<'a> for Variant<'a> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'a>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+        let tag = seed.deserialize(self.tag)?;
+        Ok((tag, self))
+    }
+}
+
+impl<'a> VariantAccess<'a> for Variant<'a> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.items.len() {
+            0 => Ok(()),
+            _ => Err(Error::new("expected a unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'a>>(mut self, seed: T) -> Result<T::Value, Error> {
+        match (self.items.next(), self.items.len()) {
+            (Some(value), 0) => seed.deserialize(Deserializer::new(value)),
+            _ => Err(Error::new("expected a newtype variant")),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'a>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(TupleAccess { items: self.items })
+    }
+
+    fn struct_variant<V: Visitor<'a>>(
+        mut self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match (self.items.next(), self.items.len()) {
+            (Some(fields), 0) => {
+                de::Deserializer::deserialize_map(Deserializer::new(fields), visitor)
+            }
+            _ => Err(Error::new("expected a struct variant")),
+        }
+    }
+}
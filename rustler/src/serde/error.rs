@@ -0,0 +1,50 @@
+use crate::{Encoder, Env, Term};
+use std::fmt;
+
+/// An error of serialization or deserialization.
+///
+/// Converted into a `rustler::Error`, it is a `badarg` error, like the failures of other decoders.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    message: String,
+}
+
+impl Error {
+    pub(crate) fn new<T: fmt::Display>(message: T) -> Self {
+        Error {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ::serde::ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::new(msg)
+    }
+}
+
+impl ::serde::de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::new(msg)
+    }
+}
+
+impl Encoder for Error {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        self.message.encode(env)
+    }
+}
+
+impl From<Error> for crate::Error {
+    fn from(_err: Error) -> crate::Error {
+        crate::Error::BadArg
+    }
+}
@@ -0,0 +1,79 @@
+//! Encoding and decoding of types implementing `Serialize` and `Deserialize` of serde.
+//!
+//! Types already annotated for serde can be passed to and returned from NIFs without deriving
+//! `NifMap` or `NifStruct` for them, by wrapping them in `SerdeTerm`:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Order {
+//!     id: u64,
+//!     lines: Vec<(String, u32)>,
+//!     status: Status,
+//! }
+//!
+//! #[rustler::nif]
+//! fn total(order: SerdeTerm<Order>) -> SerdeTerm<Order> {
+//!     SerdeTerm(recompute(order.0))
+//! }
+//! ```
+//!
+//! Rust values are represented as follows:
+//!
+//! | Rust                              | Erlang                                |
+//! |-----------------------------------|---------------------------------------|
+//! | `bool`                            | `true` or `false`                     |
+//! | integers and floats               | integers and floats                   |
+//! | `char`, `str` and `String`        | UTF-8 binaries                        |
+//! | bytes, with `serde_bytes`         | binaries                              |
+//! | `None` and `()`                   | `nil`                                 |
+//! | `Some(value)` and newtype structs | `value`                               |
+//! | sequences                         | lists                                 |
+//! | tuples and tuple structs          | tuples                                |
+//! | maps                              | maps                                  |
+//! | unit structs                      | `nil`                                 |
+//! | structs                           | maps with atom keys                   |
+//! | unit variants                     | atoms                                 |
+//! | newtype variants                  | `{:variant, value}`                   |
+//! | tuple variants                    | `{:variant, value1, value2, ...}`     |
+//! | struct variants                   | `{:variant, %{field: value, ...}}`    |
+//!
+//! Decoding is more lenient: sequences and tuples decode from lists and tuples alike, strings and
+//! struct fields from atoms and binaries, and unit variants from atoms and binaries.
+//!
+//! `to_term` and `from_term` convert values directly, returning a `serde::Error` on failure.
+//!
+//! This module is only available with the `serde` feature.
+
+mod de;
+mod error;
+mod ser;
+
+pub use self::de::{from_term, Deserializer};
+pub use self::error::Error;
+pub use self::ser::{to_term, Serializer};
+
+use crate::{Decoder, Encoder, Env, NifResult, Term};
+use ::serde::{Deserialize, Serialize};
+
+/// A value encoded and decoded with serde. See the module documentation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SerdeTerm<T>(pub T);
+
+impl<T: Serialize> Encoder for SerdeTerm<T> {
+    /// # Panics
+    ///
+    /// Panics if the value can't be serialized, for instance if its `Serialize` implementation
+    /// fails. Use `to_term` to handle the error.
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match to_term(env, &self.0) {
+            Ok(term) => term,
+            Err(err) => panic!("failed to serialize a term: {}", err),
+        }
+    }
+}
+
+impl<'a, T: Deserialize<'a> + 'a> Decoder<'a> for SerdeTerm<T> {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        Ok(SerdeTerm(from_term(term)?))
+    }
+}
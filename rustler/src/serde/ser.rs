@@ -0,0 +1,378 @@
+use super::Error;
+use crate::types::atom::{self, Atom};
+use crate::types::list::make_list;
+use crate::types::tuple::make_tuple;
+use crate::{Encoder, Env, NewBinary, Term};
+use ::serde::ser::{self, Serialize};
+
+/// Serializes `value` into a term. See the module documentation for the representation.
+pub fn to_term<'a, T>(env: Env<'a>, value: &T) -> Result<Term<'a>, Error>
+where
+    T: Serialize + ?Sized,
+{
+    value.serialize(Serializer::new(env))
+}
+
+/// A serde `Serializer` producing terms in `env`.
+#[derive(Clone, Copy)]
+pub struct Serializer<'a> {
+    env: Env<'a>,
+}
+
+impl<'a> Serializer<'a> {
+    pub fn new(env: Env<'a>) -> Self {
+        Serializer { env }
+    }
+
+    fn atom(&self, name: &str) -> Result<Term<'a>, Error> {
+        Atom::from_str(self.env, name)
+            .map(|atom| atom.to_term(self.env))
+            .map_err(|_| Error::new(format_args!("`{}` can't be an atom", name)))
+    }
+}
+
+impl<'a> ser::Serializer for Serializer<'a> {
+    type Ok = Term<'a>;
+    type Error = Error;
+
+    type SerializeSeq = SequenceSerializer<'a>;
+    type SerializeTuple = SequenceSerializer<'a>;
+    type SerializeTupleStruct = SequenceSerializer<'a>;
+    type SerializeTupleVariant = SequenceSerializer<'a>;
+    type SerializeMap = MapSerializer<'a>;
+    type SerializeStruct = MapSerializer<'a>;
+    type SerializeStructVariant = MapSerializer<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<Term<'a>, Error> {
+        Ok(v.encode(self.env))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Term<'a>, Error> {
+        Ok(v.encode(self.env))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Term<'a>, Error> {
+        Ok(v.encode(self.env))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Term<'a>, Error> {
+        Ok(v.encode(self.env))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Term<'a>, Error> {
+        Ok(v.encode(self.env))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Term<'a>, Error> {
+        Ok(v.encode(self.env))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Term<'a>, Error> {
+        Ok(v.encode(self.env))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Term<'a>, Error> {
+        Ok(v.encode(self.env))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Term<'a>, Error> {
+        Ok(v.encode(self.env))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Term<'a>, Error> {
+        Ok(v.encode(self.env))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Term<'a>, Error> {
+        Ok(v.encode(self.env))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Term<'a>, Error> {
+        Ok(f64::from(v).encode(self.env))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Term<'a>, Error> {
+        Ok(v.encode(self.env))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Term<'a>, Error> {
+        Ok(v.encode_utf8(&mut [0; 4]).encode(self.env))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Term<'a>, Error> {
+        Ok(v.encode(self.env))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Term<'a>, Error> {
+        let mut binary = NewBinary::new(self.env, v.len());
+        binary.as_mut_slice().copy_from_slice(v);
+        Ok(binary.into())
+    }
+
+    fn serialize_none(self) -> Result<Term<'a>, Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Term<'a>, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Term<'a>, Error> {
+        Ok(atom::nil().to_term(self.env))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Term<'a>, Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Term<'a>, Error> {
+        self.atom(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Term<'a>, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Term<'a>, Error> {
+        let tag = self.atom(variant)?;
+        let value = value.serialize(self)?;
+        Ok(make_tuple(self.env, &[tag, value]))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SequenceSerializer<'a>, Error> {
+        Ok(SequenceSerializer::new(self, len, false))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SequenceSerializer<'a>, Error> {
+        Ok(SequenceSerializer::new(self, Some(len), true))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SequenceSerializer<'a>, Error> {
+        self.serialize_tuple(len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SequenceSerializer<'a>, Error> {
+        let mut tuple = SequenceSerializer::new(self, Some(len + 1), true);
+        tuple.items.push(self.atom(variant)?);
+        Ok(tuple)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapSerializer<'a>, Error> {
+        Ok(MapSerializer::new(self, len, None))
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapSerializer<'a>, Error> {
+        Ok(MapSerializer::new(self, Some(len), None))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<MapSerializer<'a>, Error> {
+        let tag = self.atom(variant)?;
+        Ok(MapSerializer::new(self, Some(len), Some(tag)))
+    }
+}
+
+/// Serializes sequences into lists, and tuples and tuple variants into tuples.
+pub struct SequenceSerializer<'a> {
+    ser: Serializer<'a>,
+    items: Vec<Term<'a>>,
+    tuple: bool,
+}
+
+impl<'a> SequenceSerializer<'a> {
+    fn new(ser: Serializer<'a>, len: Option<usize>, tuple: bool) -> Self {
+        SequenceSerializer {
+            ser,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+            tuple,
+        }
+    }
+
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.items.push(value.serialize(self.ser)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Term<'a>, Error> {
+        if self.tuple {
+            Ok(make_tuple(self.ser.env, &self.items))
+        } else {
+            Ok(make_list(self.ser.env, &self.items))
+        }
+    }
+}
+
+impl<'a> ser::SerializeSeq for SequenceSerializer<'a> {
+    type Ok = Term<'a>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Term<'a>, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTuple for SequenceSerializer<'a> {
+    type Ok = Term<'a>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Term<'a>, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for SequenceSerializer<'a> {
+    type Ok = Term<'a>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Term<'a>, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for SequenceSerializer<'a> {
+    type Ok = Term<'a>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Term<'a>, Error> {
+        self.finish()
+    }
+}
+
+/// Serializes maps and structs into maps, and struct variants into `{variant, map}` tuples.
+pub struct MapSerializer<'a> {
+    ser: Serializer<'a>,
+    keys: Vec<Term<'a>>,
+    values: Vec<Term<'a>>,
+    tag: Option<Term<'a>>,
+}
+
+impl<'a> MapSerializer<'a> {
+    fn new(ser: Serializer<'a>, len: Option<usize>, tag: Option<Term<'a>>) -> Self {
+        let len = len.unwrap_or(0);
+        MapSerializer {
+            ser,
+            keys: Vec::with_capacity(len),
+            values: Vec::with_capacity(len),
+            tag,
+        }
+    }
+
+    fn push_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.keys.push(self.ser.atom(key)?);
+        self.values.push(value.serialize(self.ser)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Term<'a>, Error> {
+        let env = self.ser.env;
+        let map = Term::map_from_arrays(env, &self.keys, &self.values)
+            .map_err(|_| Error::new("duplicate key in map"))?;
+        match self.tag {
+            Some(tag) => Ok(make_tuple(env, &[tag, map])),
+            None => Ok(map),
+        }
+    }
+}
+
+impl<'a> ser::SerializeMap for MapSerializer<'a> {
+    type Ok = Term<'a>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.keys.push(key.serialize(self.ser)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.values.push(value.serialize(self.ser)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Term<'a>, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStruct for MapSerializer<'a> {
+    type Ok = Term<'a>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.push_field(key, value)
+    }
+
+    fn end(self) -> Result<Term<'a>, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for MapSerializer<'a> {
+    type Ok = Term<'a>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.push_field(key, value)
+    }
+
+    fn end(self) -> Result<Term<'a>, Error> {
+        self.finish()
+    }
+}
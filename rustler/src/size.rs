@@ -0,0 +1,134 @@
+//! Estimation of the memory taken by a term, to reject or chunk oversized inputs before doing any
+//! expensive work on them.
+//!
+//! The NIF API has no equivalent of `:erts_debug.flat_size/1`, so the size is computed by
+//! traversing the term, using the heap layout of a 64-bit VM. The result is an estimate: it is
+//! exact for lists and tuples of small values, but only approximate for maps, funs and references.
+//! Unlike `flat_size`, it includes the data of binaries, even when stored off heap, since that is
+//! what large inputs usually consist of. Shared subterms are counted every time they are
+//! referenced.
+
+use crate::dynamic::TermType;
+use crate::types::binary::HEAP_BINARY_LIMIT;
+use crate::types::map::MapIterator;
+use crate::wrapper::{list, tuple};
+use crate::Term;
+
+const WORD_SIZE: usize = 8;
+
+/// Maps up to this size are flat maps, stored as a keys tuple and an array of values.
+const FLAT_MAP_LIMIT: usize = 32;
+
+/// Integers in this range are immediates.
+const SMALL_INTEGER_MIN: i64 = -(1 << 59);
+const SMALL_INTEGER_MAX: i64 = (1 << 59) - 1;
+
+/// ## Size estimation
+impl<'a> Term<'a> {
+    /// Returns an estimate of the number of bytes taken by `self` on a 64-bit VM, including the
+    /// data of binaries stored off heap. Shared subterms are counted every time they are
+    /// referenced.
+    pub fn byte_size_estimate(self) -> usize {
+        estimate(self, usize::MAX).unwrap_or(usize::MAX)
+    }
+
+    /// Returns whether the estimated size of `self` is over `limit` bytes.
+    ///
+    /// The traversal stops as soon as `limit` is exceeded, so this is cheap even for huge terms.
+    pub fn byte_size_exceeds(self, limit: usize) -> bool {
+        estimate(self, limit).is_none()
+    }
+}
+
+/// Returns the estimated size of `term`, or `None` once it exceeds `limit`.
+fn estimate(term: Term, limit: usize) -> Option<usize> {
+    let env = term.get_env();
+    let mut total: usize = 0;
+    // Terms left to visit. Using a stack instead of recursion allows arbitrarily deep terms.
+    let mut pending = vec![term];
+
+    while let Some(term) = pending.pop() {
+        let words = match term.get_type() {
+            TermType::Atom | TermType::EmptyList | TermType::Pid | TermType::Port => 0,
+            TermType::Number => number_words(term),
+            TermType::Binary => {
+                let size = term.decode_as_binary().map_or(0, |binary| binary.len());
+                if size <= HEAP_BINARY_LIMIT {
+                    2 + size.div_ceil(WORD_SIZE)
+                } else {
+                    // A reference to off-heap data, plus the data itself.
+                    total = total.checked_add(size)?;
+                    6
+                }
+            }
+            TermType::List => {
+                // Lists are walked here rather than pushed cell by cell, to keep the stack small.
+                // The limit is checked on every cell, so that a huge list is never fully walked.
+                let mut tail = term.as_c_arg();
+                while let Some((head, rest)) = unsafe { list::get_list_cell(env.as_c_arg(), tail) }
+                {
+                    total = total.checked_add(2 * WORD_SIZE)?;
+                    if total > limit {
+                        return None;
+                    }
+                    pending.push(unsafe { Term::new(env, head) });
+                    tail = rest;
+                }
+                pending.push(unsafe { Term::new(env, tail) });
+                0
+            }
+            TermType::Tuple => {
+                let elements =
+                    unsafe { tuple::get_tuple(env.as_c_arg(), term.as_c_arg()) }.unwrap_or(&[]);
+                pending.extend(
+                    elements
+                        .iter()
+                        .map(|&element| unsafe { Term::new(env, element) }),
+                );
+                1 + elements.len()
+            }
+            TermType::Map => {
+                let size = term.map_size().unwrap_or(0);
+                if let Some(iter) = MapIterator::new(term) {
+                    for (key, value) in iter {
+                        pending.push(key);
+                        pending.push(value);
+                    }
+                }
+                if size <= FLAT_MAP_LIMIT {
+                    // Header, size, keys tuple and values.
+                    3 + 2 * size
+                } else {
+                    // Hash array mapped trie nodes, roughly one word per entry on top of the
+                    // key-value cells.
+                    3 * size
+                }
+            }
+            TermType::Ref => 4,
+            TermType::Fun => 5,
+            TermType::Exception | TermType::Unknown => 1,
+        };
+
+        total = total.checked_add(words * WORD_SIZE)?;
+        if total > limit {
+            return None;
+        }
+    }
+
+    Some(total)
+}
+
+fn number_words(term: Term) -> usize {
+    if let Ok(integer) = term.decode::<i64>() {
+        if (SMALL_INTEGER_MIN..=SMALL_INTEGER_MAX).contains(&integer) {
+            0
+        } else {
+            2
+        }
+    } else if term.decode::<u64>().is_ok() || term.decode::<f64>().is_ok() {
+        2
+    } else {
+        // A bignum larger than 64 bits. The NIF API doesn't expose its size.
+        3
+    }
+}
@@ -0,0 +1,331 @@
+//! Helpers for golden tests of encoders.
+//!
+//! `snapshot` renders a term as stable text, close to the output of `inspect/1` in Elixir, so that
+//! the output of an encoder can be compared with a string checked in next to the test:
+//!
+//! * maps are rendered with their keys sorted in term order, whatever their internal layout,
+//! * binaries longer than `SnapshotOptions::max_binary` bytes are cut, and followed by their size,
+//! * pids, ports, references and funs are rendered without their identifiers, which differ between
+//!   runs.
+//!
+//! `assert_snapshot!` panics with both renderings when a term doesn't match the expected one:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn check_user_encoding(env: Env) {
+//!     let user = User { id: 1, tags: vec!["admin".to_string()] };
+//!     rustler::assert_snapshot!(user.encode(env), r#"%{id: 1, tags: ["admin"]}"#);
+//! }
+//! ```
+
+use crate::dynamic::{get_type, TermType};
+use crate::types::map::MapIterator;
+use crate::types::primitive::get_big_integer;
+use crate::types::tuple::get_tuple;
+use crate::Term;
+use std::fmt::Write;
+
+/// How `snapshot_with` renders terms.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SnapshotOptions {
+    /// The number of bytes rendered from binaries.
+    pub max_binary: usize,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        SnapshotOptions { max_binary: 64 }
+    }
+}
+
+/// Renders `term` as stable text, with the default options. See the module documentation.
+pub fn snapshot(term: Term) -> String {
+    snapshot_with(term, &SnapshotOptions::default())
+}
+
+/// Renders `term` as stable text. See the module documentation.
+pub fn snapshot_with(term: Term, options: &SnapshotOptions) -> String {
+    let mut out = String::new();
+    render(&mut out, term, options);
+    out
+}
+
+/// Panics if the snapshot of a term differs from the expected text.
+///
+/// Takes the term, the expected text and, optionally, the `SnapshotOptions` of the snapshot.
+#[macro_export]
+macro_rules! assert_snapshot {
+    ($term:expr, $expected:expr $(,)?) => {
+        $crate::assert_snapshot!(
+            $term,
+            $expected,
+            &$crate::snapshot::SnapshotOptions::default()
+        )
+    };
+    ($term:expr, $expected:expr, $options:expr $(,)?) => {{
+        let actual = $crate::snapshot::snapshot_with($term, $options);
+        let expected: &str = $expected;
+        if actual != expected {
+            panic!(
+                "snapshot mismatch\n  actual: {}\nexpected: {}",
+                actual, expected
+            );
+        }
+    }};
+}
+
+fn render(out: &mut String, term: Term, options: &SnapshotOptions) {
+    match get_type(term) {
+        TermType::Atom => render_atom(out, &term.atom_to_string().unwrap_or_default()),
+        TermType::Binary => render_binary(out, term, options),
+        TermType::EmptyList => out.push_str("[]"),
+        TermType::List => render_list(out, term, options),
+        TermType::Tuple => {
+            out.push('{');
+            let items = get_tuple(term).unwrap_or_default();
+            render_items(out, &items, options);
+            out.push('}');
+        }
+        TermType::Map => render_map(out, term, options),
+        TermType::Number => render_number(out, term),
+        TermType::Pid => out.push_str("#PID"),
+        TermType::Port => out.push_str("#Port"),
+        TermType::Ref => out.push_str("#Reference"),
+        TermType::Fun => out.push_str("#Function"),
+        TermType::Exception | TermType::Unknown => out.push_str("#Term"),
+    }
+}
+
+fn render_items(out: &mut String, items: &[Term], options: &SnapshotOptions) {
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            out.push_str(", ");
+        }
+        render(out, *item, options);
+    }
+}
+
+fn render_list(out: &mut String, list: Term, options: &SnapshotOptions) {
+    out.push('[');
+    let mut tail = list;
+    let mut first = true;
+    while let Ok((head, rest)) = tail.list_get_cell() {
+        if !first {
+            out.push_str(", ");
+        }
+        first = false;
+        render(out, head, options);
+        tail = rest;
+    }
+    if !tail.is_empty_list() {
+        out.push_str(" | ");
+        render(out, tail, options);
+    }
+    out.push(']');
+}
+
+fn render_map(out: &mut String, map: Term, options: &SnapshotOptions) {
+    let mut entries: Vec<(Term, Term)> = match MapIterator::new(map) {
+        Some(iter) => iter.collect(),
+        None => Vec::new(),
+    };
+    entries.sort_by_key(|(key, _)| *key);
+
+    let module = entries
+        .iter()
+        .position(|(key, _)| key.atom_to_string().ok().as_deref() == Some("__struct__"))
+        .and_then(|index| {
+            let module = entries[index].1.atom_to_string().ok()?;
+            entries.remove(index);
+            Some(module)
+        });
+    match module {
+        Some(ref module) => {
+            out.push('%');
+            out.push_str(module.strip_prefix("Elixir.").unwrap_or(module));
+            out.push('{');
+        }
+        None => out.push_str("%{"),
+    }
+
+    let shorthand = entries.iter().all(|(key, _)| key.is_atom());
+    for (index, (key, value)) in entries.into_iter().enumerate() {
+        if index > 0 {
+            out.push_str(", ");
+        }
+        if shorthand {
+            let name = key.atom_to_string().unwrap_or_default();
+            if is_identifier(&name) {
+                out.push_str(&name);
+            } else {
+                render_string(out, &name);
+            }
+            out.push_str(": ");
+        } else {
+            render(out, key, options);
+            out.push_str(" => ");
+        }
+        render(out, value, options);
+    }
+    out.push('}');
+}
+
+fn render_number(out: &mut String, number: Term) {
+    if let Ok(value) = number.decode::<i64>() {
+        let _ = write!(out, "{}", value);
+    } else if let Ok(value) = number.decode::<f64>() {
+        render_float(out, value);
+    } else if let Ok((negative, magnitude)) = get_big_integer(number) {
+        if negative {
+            out.push('-');
+        }
+        out.push_str(&to_decimal(magnitude));
+    } else {
+        out.push_str("#Number");
+    }
+}
+
+/// Renders `value` like Erlang and Elixir do: with the shortest digits that read back as `value`,
+/// in scientific notation when that is shorter, e.g. `1.0e20`, `1.0e3` or `0.001`.
+fn render_float(out: &mut String, value: f64) {
+    if value.is_sign_negative() {
+        out.push('-');
+    }
+    if value == 0.0 {
+        out.push_str("0.0");
+        return;
+    }
+
+    // `{:e}` writes the shortest digits, as `d.ddde<exp>`.
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    // The position of the decimal point, relative to the first digit.
+    let place = exponent.parse::<i32>().unwrap() + 1;
+    let len = digits.len() as i32;
+
+    if place > 0 && place < len {
+        let (integer, fraction) = digits.split_at(place as usize);
+        let _ = write!(out, "{}.{}", integer, fraction);
+        return;
+    }
+    if place == 0 {
+        let _ = write!(out, "0.{}", digits);
+        return;
+    }
+
+    // As `io_lib_format:fwrite_g/1`, use the exponent when it is shorter than the zeros.
+    let exponent = (place - 1).to_string();
+    let exponent_cost = exponent.len() as i32 + if len == 1 { 3 } else { 2 };
+    if place < 0 && 2 - place <= exponent_cost {
+        let _ = write!(out, "0.{}{}", "0".repeat(-place as usize), digits);
+    } else if place >= len && place - len + 2 <= exponent_cost {
+        let _ = write!(out, "{}{}.0", digits, "0".repeat((place - len) as usize));
+    } else {
+        let (first, rest) = digits.split_at(1);
+        let rest = if rest.is_empty() { "0" } else { rest };
+        let _ = write!(out, "{}.{}e{}", first, rest, exponent);
+    }
+}
+
+/// Converts a little-endian magnitude to decimal digits.
+fn to_decimal(mut magnitude: Vec<u8>) -> String {
+    let mut digits = Vec::new();
+    while magnitude.iter().any(|&byte| byte != 0) {
+        let mut remainder = 0u32;
+        for byte in magnitude.iter_mut().rev() {
+            let value = (remainder << 8) | u32::from(*byte);
+            *byte = (value / 10) as u8;
+            remainder = value % 10;
+        }
+        digits.push(b'0' + remainder as u8);
+    }
+    if digits.is_empty() {
+        digits.push(b'0');
+    }
+    digits.reverse();
+    String::from_utf8(digits).expect("digits are ASCII")
+}
+
+fn render_atom(out: &mut String, name: &str) {
+    match name {
+        "nil" | "true" | "false" => out.push_str(name),
+        _ if name.starts_with("Elixir.") => out.push_str(&name["Elixir.".len()..]),
+        _ if is_identifier(name) => {
+            out.push(':');
+            out.push_str(name);
+        }
+        _ => {
+            out.push(':');
+            render_string(out, name);
+        }
+    }
+}
+
+/// Returns whether an atom can be written without quotes.
+fn is_identifier(name: &str) -> bool {
+    let body = name.strip_suffix(|c| c == '?' || c == '!').unwrap_or(name);
+    let mut chars = body.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@')
+        }
+        _ => false,
+    }
+}
+
+fn render_binary(out: &mut String, term: Term, options: &SnapshotOptions) {
+    let binary = match term.decode_as_binary() {
+        Ok(binary) => binary,
+        Err(_) => return out.push_str("#Bitstring"),
+    };
+    let bytes = binary.as_slice();
+    let mut end = bytes.len().min(options.max_binary);
+
+    match std::str::from_utf8(bytes) {
+        Ok(string) if !string.chars().any(is_unprintable) => {
+            while !string.is_char_boundary(end) {
+                end -= 1;
+            }
+            render_string(out, &string[..end]);
+            if end < bytes.len() {
+                let _ = write!(out, "... ({} bytes)", bytes.len());
+            }
+        }
+        _ => {
+            out.push_str("<<");
+            for (index, byte) in bytes[..end].iter().enumerate() {
+                if index > 0 {
+                    out.push_str(", ");
+                }
+                let _ = write!(out, "{}", byte);
+            }
+            if end < bytes.len() {
+                let _ = write!(out, ", ... ({} bytes)", bytes.len());
+            }
+            out.push_str(">>");
+        }
+    }
+}
+
+fn is_unprintable(c: char) -> bool {
+    c.is_control() && !matches!(c, '\n' | '\t' | '\r')
+}
+
+fn render_string(out: &mut String, string: &str) {
+    out.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\x{{{:X}}}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
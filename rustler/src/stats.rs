@@ -0,0 +1,143 @@
+//! Per-NIF statistics, collected by NIFs that opt in.
+//!
+//! NIFs declared with `#[rustler::nif(cpu_time)]` measure the CPU time of the scheduler thread
+//! running each of their calls, and record it in a registry keyed by NIF name. This helps to
+//! analyze how work is distributed across dirty NIFs, and to find calls that should yield or be
+//! moved to a dirty scheduler.
+//!
+//! CPU time is read from the thread CPU clock, which is only available on Unix. On other
+//! platforms, only the call count and wall-clock time are recorded.
+//!
+//! ```ignore
+//! #[rustler::nif(schedule = "DirtyCpu", cpu_time)]
+//! fn resize(image: Binary) -> OwnedBinary { ... }
+//!
+//! #[rustler::nif]
+//! fn resize_stats() -> Option<NifStats> {
+//!     rustler::stats::get("resize")
+//! }
+//! ```
+
+use crate::types::map::map_new;
+use crate::{Encoder, Env, Term};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+mod atoms {
+    crate::atoms! {
+        calls,
+        cpu_time_us,
+        max_cpu_time_us,
+        wall_time_us,
+    }
+}
+
+/// Statistics of a single NIF, accumulated over all its calls.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NifStats {
+    /// Number of calls.
+    pub calls: u64,
+    /// Total CPU time.
+    pub cpu_time: Duration,
+    /// Longest CPU time of a single call.
+    pub max_cpu_time: Duration,
+    /// Total wall-clock time.
+    pub wall_time: Duration,
+}
+
+/// Encodes as a map with the `calls` count, and durations in microseconds under the
+/// `cpu_time_us`, `max_cpu_time_us` and `wall_time_us` keys.
+impl Encoder for NifStats {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        let entries = [
+            (atoms::calls(), self.calls),
+            (atoms::cpu_time_us(), self.cpu_time.as_micros() as u64),
+            (
+                atoms::max_cpu_time_us(),
+                self.max_cpu_time.as_micros() as u64,
+            ),
+            (atoms::wall_time_us(), self.wall_time.as_micros() as u64),
+        ];
+
+        entries.iter().fold(map_new(env), |map, (key, value)| {
+            map.map_put(key.encode(env), value.encode(env)).unwrap()
+        })
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: Mutex<HashMap<&'static str, NifStats>> = Mutex::new(HashMap::new());
+}
+
+/// Returns the statistics of the NIF named `name`, if it was called at least once.
+pub fn get(name: &str) -> Option<NifStats> {
+    REGISTRY.lock().unwrap().get(name).copied()
+}
+
+/// Returns the statistics of all NIFs that were called at least once, sorted by name.
+pub fn snapshot() -> Vec<(&'static str, NifStats)> {
+    let mut stats: Vec<_> = REGISTRY
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, stats)| (*name, *stats))
+        .collect();
+    stats.sort_by_key(|(name, _)| *name);
+    stats
+}
+
+/// Forgets all statistics.
+pub fn reset() {
+    REGISTRY.lock().unwrap().clear();
+}
+
+/// Measures a single NIF call. Used by generated code.
+#[doc(hidden)]
+pub struct CallTimer {
+    cpu_start: Option<Duration>,
+    wall_start: Instant,
+}
+
+impl CallTimer {
+    pub fn start() -> Self {
+        CallTimer {
+            cpu_start: thread_cpu_time(),
+            wall_start: Instant::now(),
+        }
+    }
+
+    /// Records the call in the statistics of the NIF named `name`.
+    pub fn stop(self, name: &'static str) {
+        let wall_time = self.wall_start.elapsed();
+        let cpu_time = match (self.cpu_start, thread_cpu_time()) {
+            (Some(start), Some(end)) => end.checked_sub(start).unwrap_or_default(),
+            _ => Duration::default(),
+        };
+
+        let mut registry = REGISTRY.lock().unwrap();
+        let stats = registry.entry(name).or_default();
+        stats.calls += 1;
+        stats.cpu_time += cpu_time;
+        stats.max_cpu_time = stats.max_cpu_time.max(cpu_time);
+        stats.wall_time += wall_time;
+    }
+}
+
+/// Returns the CPU time consumed by the current thread.
+#[cfg(unix)]
+fn thread_cpu_time() -> Option<Duration> {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    if unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) } != 0 {
+        return None;
+    }
+    Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+}
+
+#[cfg(not(unix))]
+fn thread_cpu_time() -> Option<Duration> {
+    None
+}
@@ -0,0 +1,175 @@
+//! Long-lived OS subprocesses owned by an Erlang process.
+//!
+//! `SubprocessResource` gives NIF libraries Port-like semantics: a child OS process is spawned,
+//! and its output is pumped to an owner process by a managed thread, as messages:
+//!
+//! * `{:stdout, os_pid, data}` and `{:stderr, os_pid, data}`, where `data` is a binary;
+//! * `{:exit_status, os_pid, status}` once both output streams are closed and the child has
+//!   exited. `status` is `nil` if the child was killed by a signal.
+//!
+//! Data is written to the child's standard input with `write()`. The child is killed when the
+//! resource is garbage collected, so it can't outlive the terms referring to it.
+//!
+//! The resource type must be registered by calling `rustler::subprocess::load(env)` from the
+//! `load` callback of the NIF library.
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn start(env: Env, program: String) -> NifResult<ResourceArc<SubprocessResource>> {
+//!     SubprocessResource::spawn(env.pid(), Command::new(program))
+//!         .map_err(|err| Error::Term(Box::new(err.to_string())))
+//! }
+//! ```
+
+use crate::env::OwnedEnv;
+use crate::types::LocalPid;
+use crate::{Atom, Binary, Encoder, Env, ResourceArc};
+use std::io::{self, Read, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+mod atoms {
+    crate::atoms! {
+        stdout,
+        stderr,
+        exit_status,
+    }
+}
+
+/// How often the pump thread checks whether the child has exited, after its output is closed.
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A child OS process, whose output is sent to an owner process.
+///
+/// See the module documentation for the messages sent to the owner.
+pub struct SubprocessResource {
+    os_pid: u32,
+    child: Arc<Mutex<Child>>,
+    stdin: Mutex<Option<ChildStdin>>,
+}
+
+crate::resource_type!(SubprocessResource);
+
+/// Registers the `SubprocessResource` resource type. Call this from the `load` callback.
+pub fn load(env: Env) -> bool {
+    crate::resource::register::<SubprocessResource>(env)
+}
+
+impl SubprocessResource {
+    /// Spawns `command`, sending its output to `owner`.
+    ///
+    /// The standard streams of `command` are replaced by pipes.
+    ///
+    /// Fails with `OutOfMemory` if the environments used to send messages can't be allocated.
+    pub fn spawn(owner: LocalPid, mut command: Command) -> io::Result<ResourceArc<Self>> {
+        // Allocated before the child is started, so that a failure doesn't leave it running.
+        let alloc_env = || OwnedEnv::try_new().map_err(|_| io::ErrorKind::OutOfMemory);
+        let mut stdout_env = alloc_env()?;
+        let mut stderr_env = alloc_env()?;
+
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let os_pid = child.id();
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let child = Arc::new(Mutex::new(child));
+
+        let stderr_pump = stderr.map(|stderr| {
+            let owner = owner.clone();
+            thread::spawn(move || pump(stderr, &mut stderr_env, owner, atoms::stderr(), os_pid))
+        });
+
+        let pump_child = child.clone();
+        thread::spawn(move || {
+            if let Some(stdout) = stdout {
+                pump(
+                    stdout,
+                    &mut stdout_env,
+                    owner.clone(),
+                    atoms::stdout(),
+                    os_pid,
+                );
+            }
+            if let Some(stderr_pump) = stderr_pump {
+                let _ = stderr_pump.join();
+            }
+
+            let status = loop {
+                match pump_child.lock().unwrap().try_wait() {
+                    Ok(Some(status)) => break status.code(),
+                    Ok(None) => (),
+                    Err(_) => break None,
+                }
+                thread::sleep(EXIT_POLL_INTERVAL);
+            };
+
+            stdout_env.send_and_clear(&owner, |env| {
+                (atoms::exit_status(), os_pid, status).encode(env)
+            });
+        });
+
+        Ok(ResourceArc::new(SubprocessResource {
+            os_pid,
+            child,
+            stdin: Mutex::new(stdin),
+        }))
+    }
+
+    /// Returns the OS process id of the child.
+    pub fn os_pid(&self) -> u32 {
+        self.os_pid
+    }
+
+    /// Writes `data` to the standard input of the child.
+    ///
+    /// Fails with `BrokenPipe` if standard input was closed.
+    pub fn write(&self, data: &[u8]) -> io::Result<()> {
+        match *self.stdin.lock().unwrap() {
+            Some(ref mut stdin) => stdin.write_all(data),
+            None => Err(io::ErrorKind::BrokenPipe.into()),
+        }
+    }
+
+    /// Closes the standard input of the child, signalling the end of its input.
+    pub fn close_stdin(&self) {
+        self.stdin.lock().unwrap().take();
+    }
+
+    /// Kills the child. The owner still receives `{:exit_status, os_pid, status}`.
+    pub fn kill(&self) -> io::Result<()> {
+        self.child.lock().unwrap().kill()
+    }
+}
+
+impl Drop for SubprocessResource {
+    fn drop(&mut self) {
+        // The pump thread reaps the child once its output is closed.
+        let _ = self.kill();
+    }
+}
+
+/// Sends everything read from `source` to `owner`, until the end of the stream.
+fn pump<R: Read>(mut source: R, env: &mut OwnedEnv, owner: LocalPid, tag: Atom, os_pid: u32) {
+    let mut buffer = [0; 4096];
+
+    loop {
+        let len = match source.read(&mut buffer) {
+            Ok(0) => return,
+            Ok(len) => len,
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => return,
+        };
+
+        env.send_and_clear(&owner, |env| {
+            let data = Binary::from_bytes(env, &buffer[..len]);
+            (tag, os_pid, data).encode(env)
+        });
+    }
+}
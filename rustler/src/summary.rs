@@ -0,0 +1,148 @@
+//! Truncated representations of terms, to include in errors and logs.
+//!
+//! `summarize` returns a copy of a term where long strings are cut, binaries that are not
+//! strings are replaced with `"<<... N bytes>>"`, and long lists, tuples and maps only keep their
+//! first elements. This keeps error terms and log lines readable when they refer to large
+//! values.
+//!
+//! The derives of structs generate a `summary_term` method with `#[rustler(summary)]`:
+//!
+//! ```ignore
+//! #[derive(NifMap)]
+//! #[rustler(summary)]
+//! struct Upload {
+//!     name: String,
+//!     data: Vec<u8>,
+//! }
+//!
+//! #[rustler::nif]
+//! fn store(env: Env, upload: Upload) -> NifResult<()> {
+//!     if !valid(&upload) {
+//!         return Err(Error::RaiseTerm(Box::new((atoms::invalid_upload(), upload.summary_term(env)))));
+//!     }
+//!     ...
+//! }
+//! ```
+
+use crate::dynamic::{get_type, TermType};
+use crate::types::map::MapIterator;
+use crate::types::tuple::{get_tuple, make_tuple};
+use crate::{Encoder, Env, Term};
+
+/// How much of a term `summarize` keeps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SummaryLimits {
+    /// The number of bytes kept from strings.
+    pub max_string: usize,
+    /// The number of elements kept from lists, tuples and maps.
+    pub max_items: usize,
+    /// The number of nested lists, tuples and maps kept.
+    pub max_depth: usize,
+}
+
+impl Default for SummaryLimits {
+    fn default() -> Self {
+        SummaryLimits {
+            max_string: 64,
+            max_items: 16,
+            max_depth: 4,
+        }
+    }
+}
+
+/// Returns a truncated copy of `term`. See the module documentation.
+pub fn summarize<'a>(term: Term<'a>, limits: &SummaryLimits) -> Term<'a> {
+    summarize_at(term, limits, 0)
+}
+
+fn summarize_at<'a>(term: Term<'a>, limits: &SummaryLimits, depth: usize) -> Term<'a> {
+    let env = term.get_env();
+    match get_type(term) {
+        TermType::Binary => summarize_binary(term, limits),
+        TermType::List | TermType::Tuple | TermType::Map if depth >= limits.max_depth => {
+            "...".encode(env)
+        }
+        TermType::List => match term.into_list_iterator() {
+            Ok(iter) => {
+                let elements: Vec<Term<'a>> = iter.collect();
+                let mut summarized = summarize_items(env, &elements, limits, depth);
+                if elements.len() > limits.max_items {
+                    let more = format!("... {} more", elements.len() - limits.max_items);
+                    summarized.push(more.encode(env));
+                }
+                summarized.encode(env)
+            }
+            // Improper lists are kept as they are.
+            Err(_) => term,
+        },
+        TermType::Tuple => match get_tuple(term) {
+            Ok(elements) => {
+                let mut summarized = summarize_items(env, &elements, limits, depth);
+                if elements.len() > limits.max_items {
+                    let more = format!("... {} more", elements.len() - limits.max_items);
+                    summarized.push(more.encode(env));
+                }
+                make_tuple(env, &summarized)
+            }
+            Err(_) => term,
+        },
+        TermType::Map => {
+            let size = term.map_size().unwrap_or(0);
+            let iter = match MapIterator::new(term) {
+                Some(iter) => iter,
+                None => return term,
+            };
+            let mut map = Term::map_new(env);
+            for (key, value) in iter.take(limits.max_items) {
+                let key = summarize_at(key, limits, depth + 1);
+                let value = summarize_at(value, limits, depth + 1);
+                map = map.map_put(key, value).unwrap_or(map);
+            }
+            if size > limits.max_items {
+                let more = format!("{} more", size - limits.max_items);
+                map = map
+                    .map_put("...".encode(env), more.encode(env))
+                    .unwrap_or(map);
+            }
+            map
+        }
+        _ => term,
+    }
+}
+
+fn summarize_items<'a>(
+    env: Env<'a>,
+    elements: &[Term<'a>],
+    limits: &SummaryLimits,
+    depth: usize,
+) -> Vec<Term<'a>> {
+    elements
+        .iter()
+        .take(limits.max_items)
+        .map(|element| summarize_at(element.in_env(env), limits, depth + 1))
+        .collect()
+}
+
+fn summarize_binary<'a>(term: Term<'a>, limits: &SummaryLimits) -> Term<'a> {
+    let env = term.get_env();
+    let binary = match term.decode_as_binary() {
+        Ok(binary) => binary,
+        // Bitstrings are kept as they are.
+        Err(_) => return term,
+    };
+    let bytes = binary.as_slice();
+    if bytes.len() <= limits.max_string {
+        return term;
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(string) => {
+            let mut end = limits.max_string;
+            while !string.is_char_boundary(end) {
+                end -= 1;
+            }
+            format!("{}...", &string[..end]).encode(env)
+        }
+        Err(_) => format!("<<... {} bytes>>", bytes.len()).encode(env),
+    }
+}
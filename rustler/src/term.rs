@@ -1,7 +1,8 @@
-use crate::types::binary::OwnedBinary;
+use crate::types::batch::argument_error;
+use crate::types::binary::{AllocError, OwnedBinary};
 use crate::wrapper::env::term_to_binary;
 use crate::wrapper::NIF_TERM;
-use crate::{Binary, Decoder, Env, NifResult};
+use crate::{Binary, Decoder, Env, Error, NifResult};
 use std::cmp::Ordering;
 use std::fmt::{self, Debug};
 
@@ -78,6 +79,25 @@ impl<'a> Term<'a> {
         Decoder::decode(self)
     }
 
+    /// Decodes the Term into type T, raising an `ArgumentError` if it can't be decoded.
+    ///
+    /// The message of the exception names `T` and the type of the term, like
+    /// `term could not be decoded as u32, got a binary`. Errors other than `BadArg` returned by
+    /// the decoder are passed through unchanged.
+    ///
+    /// ```ignore
+    /// let options: Options = term.decode_or_raise()?;
+    /// ```
+    pub fn decode_or_raise<T>(self) -> NifResult<T>
+    where
+        T: Decoder<'a>,
+    {
+        self.decode().map_err(|err| match err {
+            Error::BadArg => argument_error::<T>("term", self),
+            err => err,
+        })
+    }
+
     /// Decodes the Term into Binary
     ///
     /// This could be used as a replacement for [`decode`] when decoding Binary from an iolist
@@ -91,9 +111,41 @@ impl<'a> Term<'a> {
         Binary::from_iolist(self)
     }
 
+    /// Encodes the term in the
+    /// [External Term Format](http://erlang.org/doc/apps/erts/erl_ext_dist.html), like
+    /// `:erlang.term_to_binary/1`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the binary can't be allocated. See `try_to_binary`.
     pub fn to_binary(self) -> OwnedBinary {
-        let raw_binary = unsafe { term_to_binary(self.env.as_c_arg(), self.as_c_arg()) }.unwrap();
-        unsafe { OwnedBinary::from_raw(raw_binary) }
+        self.try_to_binary()
+            .expect("failed to allocate the binary of enif_term_to_binary")
+    }
+
+    /// Like `to_binary`, but returns an `AllocError` when the binary can't be allocated.
+    pub fn try_to_binary(self) -> Result<OwnedBinary, AllocError> {
+        match unsafe { term_to_binary(self.env.as_c_arg(), self.as_c_arg()) } {
+            Some(raw_binary) => Ok(unsafe { OwnedBinary::from_raw(raw_binary) }),
+            // The size of the encoding is not known, the size of the term is close to it.
+            None => Err(AllocError {
+                size: self.byte_size_estimate(),
+            }),
+        }
+    }
+
+    /// Decodes a term encoded in the External Term Format, like `:erlang.binary_to_term/2` with
+    /// the `:safe` option: atoms that don't exist yet and external functions are rejected.
+    ///
+    /// # Errors
+    ///
+    /// If `data` is not a whole encoded term, `Error::BadArg` is returned. `Env::binary_to_term`
+    /// accepts trailing data.
+    pub fn from_binary(env: Env<'a>, data: &[u8]) -> NifResult<Term<'a>> {
+        match env.binary_to_term(data) {
+            Some((term, size)) if size == data.len() => Ok(term),
+            _ => Err(Error::BadArg),
+        }
     }
 }
 
@@ -0,0 +1,141 @@
+//! Text helpers working directly on binaries.
+//!
+//! The functions of this module read the bytes of a `Binary` in place instead of decoding it to
+//! a `String`, and only allocate an `OwnedBinary` when they produce new text. A binary that is
+//! already normalized is returned as it is, and the graphemes of a binary are returned as
+//! sub-binaries of it:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn canonical<'a>(env: Env<'a>, name: Binary<'a>) -> NifResult<Binary<'a>> {
+//!     rustler::text::nfc(env, name)
+//! }
+//!
+//! #[rustler::nif]
+//! fn initial(name: Binary) -> NifResult<Option<Binary>> {
+//!     Ok(rustler::text::graphemes(name)?.into_iter().next())
+//! }
+//! ```
+//!
+//! Graphemes are extended grapheme clusters, like the ones of Elixir's `String` module.
+//!
+//! This module is only available with the `text` feature.
+
+use crate::types::binary::{Binary, OwnedBinary};
+use crate::{Env, Error, NifResult};
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Returns the text of `binary`, or `Error::BadArg` if it is not valid UTF-8.
+pub fn as_str<'a>(binary: &Binary<'a>) -> NifResult<&'a str> {
+    std::str::from_utf8(binary.as_slice()).map_err(|_| Error::BadArg)
+}
+
+/// Returns the offset of the first byte of `bytes` that is not part of valid UTF-8, or `None`
+/// if all of it is valid. A truncated sequence at the end is invalid.
+pub fn invalid_utf8_offset(bytes: &[u8]) -> Option<usize> {
+    std::str::from_utf8(bytes)
+        .err()
+        .map(|err| err.valid_up_to())
+}
+
+/// Returns `binary` in Normalization Form C. It is returned as it is when it already is in NFC,
+/// which is the case of most text.
+///
+/// # Errors
+///
+/// If `binary` is not valid UTF-8, `Error::BadArg` is returned.
+pub fn nfc<'a>(env: Env<'a>, binary: Binary<'a>) -> NifResult<Binary<'a>> {
+    let text = as_str(&binary)?;
+    if unicode_normalization::is_nfc(text) {
+        return Ok(binary);
+    }
+    Ok(collect_chars(text.nfc())?.release(env))
+}
+
+/// Returns `binary` in Normalization Form KC, as it is when it already is in NFKC.
+///
+/// # Errors
+///
+/// If `binary` is not valid UTF-8, `Error::BadArg` is returned.
+pub fn nfkc<'a>(env: Env<'a>, binary: Binary<'a>) -> NifResult<Binary<'a>> {
+    let text = as_str(&binary)?;
+    if unicode_normalization::is_nfkc(text) {
+        return Ok(binary);
+    }
+    Ok(collect_chars(text.nfkc())?.release(env))
+}
+
+/// Returns `binary` in Normalization Form D, as it is when it already is in NFD.
+///
+/// # Errors
+///
+/// If `binary` is not valid UTF-8, `Error::BadArg` is returned.
+pub fn nfd<'a>(env: Env<'a>, binary: Binary<'a>) -> NifResult<Binary<'a>> {
+    let text = as_str(&binary)?;
+    if unicode_normalization::is_nfd(text) {
+        return Ok(binary);
+    }
+    Ok(collect_chars(text.nfd())?.release(env))
+}
+
+fn collect_chars<I>(chars: I) -> NifResult<OwnedBinary>
+where
+    I: Iterator<Item = char>,
+{
+    crate::scratch::with_buffer(|buf| {
+        let mut utf8 = [0; 4];
+        for c in chars {
+            buf.extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+        }
+        let mut owned = OwnedBinary::try_new(buf.len())?;
+        owned.as_mut_slice().copy_from_slice(buf);
+        Ok(owned)
+    })
+}
+
+/// Returns the graphemes of `binary`, as sub-binaries of it.
+///
+/// # Errors
+///
+/// If `binary` is not valid UTF-8, `Error::BadArg` is returned.
+pub fn graphemes(binary: Binary) -> NifResult<Vec<Binary>> {
+    let text = as_str(&binary)?;
+    text.grapheme_indices(true)
+        .map(|(offset, grapheme)| binary.make_subbinary(offset, grapheme.len()))
+        .collect()
+}
+
+/// Returns the number of graphemes of `text`, like `String.length/1`.
+pub fn grapheme_count(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// Returns the Levenshtein distance between `a` and `b`, counting graphemes: the number of
+/// graphemes to insert, delete or replace to turn `a` into `b`.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<&str> = a.graphemes(true).collect();
+    let b: Vec<&str> = b.graphemes(true).collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ga) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, gb) in b.iter().enumerate() {
+            let replace = previous[j] + if ga == gb { 0 } else { 1 };
+            current[j + 1] = replace.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Returns the similarity of `a` and `b` between 0.0 and 1.0, as 1 minus their Levenshtein
+/// distance divided by the number of graphemes of the longest one. Two empty strings are
+/// similar.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let longest = grapheme_count(a).max(grapheme_count(b));
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / longest as f64
+}
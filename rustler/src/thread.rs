@@ -1,6 +1,8 @@
-use crate::env::OwnedEnv;
+use crate::affinity::ThreadHints;
+use crate::env::{EnvAllocError, OwnedEnv};
 use crate::types::atom::Atom;
 use crate::{Encoder, Env, Term};
+use std::any::Any;
 use std::panic;
 use std::thread;
 
@@ -34,31 +36,61 @@ impl JobSpawner for ThreadSpawner {
 /// Note that the thread creates a new `Env` and passes it to the closure, so the closure
 /// runs under a separate environment, not under `env`.
 ///
+/// # Panics
+///
+/// Panics if the environment of the thread can't be allocated. This happens before the thread
+/// is spawned, so that the calling NIF fails instead of never getting a reply. Use `try_spawn()`
+/// to handle that case.
 pub fn spawn<'a, S, F>(env: Env<'a>, thread_fn: F)
+where
+    F: for<'b> FnOnce(Env<'b>) -> Term<'b> + Send + panic::UnwindSafe + 'static,
+    S: JobSpawner,
+{
+    try_spawn::<S, F>(env, thread_fn).expect("enif_alloc_env failed")
+}
+
+/// Like `spawn`, but fails with `EnvAllocError` if the environment of the thread can't be
+/// allocated, without spawning the thread.
+pub fn try_spawn<'a, S, F>(env: Env<'a>, thread_fn: F) -> Result<(), EnvAllocError>
 where
     F: for<'b> FnOnce(Env<'b>) -> Term<'b> + Send + panic::UnwindSafe + 'static,
     S: JobSpawner,
 {
     let pid = env.pid();
+    let mut owned_env = OwnedEnv::try_new()?;
     S::spawn(move || {
-        OwnedEnv::new().send_and_clear(&pid, |env| {
-            match panic::catch_unwind(|| thread_fn(env)) {
-                Ok(term) => term,
-                Err(err) => {
-                    // Try to get an error message from Rust.
-                    let reason = if let Some(string) = err.downcast_ref::<String>() {
-                        string.encode(env)
-                    } else if let Some(&s) = err.downcast_ref::<&'static str>() {
-                        s.encode(env)
-                    } else {
-                        Atom::from_bytes(env, b"nif_panic")
-                            .ok()
-                            .unwrap()
-                            .to_term(env)
-                    };
-                    env.error_tuple(reason)
-                }
-            }
+        owned_env.send_and_clear(&pid, |env| match panic::catch_unwind(|| thread_fn(env)) {
+            Ok(term) => term,
+            Err(err) => env.error_tuple(panic_reason(env, &err)),
         });
     });
+    Ok(())
+}
+
+/// Returns the message of a panic, or `:nif_panic` if it has none.
+pub(crate) fn panic_reason<'a>(env: Env<'a>, err: &Box<dyn Any + Send>) -> Term<'a> {
+    if let Some(string) = err.downcast_ref::<String>() {
+        string.encode(env)
+    } else if let Some(&s) = err.downcast_ref::<&'static str>() {
+        s.encode(env)
+    } else {
+        Atom::from_bytes(env, b"nif_panic")
+            .ok()
+            .unwrap()
+            .to_term(env)
+    }
+}
+
+/// Like `spawn`, but pins the thread to the cores of `hints` before calling `thread_fn`.
+///
+/// The thread runs unpinned if it can't be pinned.
+pub fn spawn_with_hints<'a, S, F>(env: Env<'a>, hints: ThreadHints, thread_fn: F)
+where
+    F: for<'b> FnOnce(Env<'b>) -> Term<'b> + Send + panic::UnwindSafe + 'static,
+    S: JobSpawner,
+{
+    spawn::<S, _>(env, move |env| {
+        let _ = hints.apply();
+        thread_fn(env)
+    });
 }
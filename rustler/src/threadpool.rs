@@ -0,0 +1,123 @@
+//! A pool of native threads running jobs for NIFs, and replying to their callers by message.
+//!
+//! `rustler::thread::spawn` starts a thread for every call, which is wasteful for frequent short
+//! jobs and unbounded under load. A `ThreadPool` runs the jobs on a fixed set of threads instead,
+//! with a bounded queue of pending jobs. The value returned by a job is sent to the process given
+//! to `spawn`, usually the caller, and a panicking job sends `{:error, reason}` instead:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn checksum(env: Env, data: Binary) -> NifResult<Atom> {
+//!     let data = data.to_owned().ok_or(Error::BadArg)?;
+//!     rustler::threadpool::global().spawn(env.pid(), move |env| {
+//!         (atoms::checksum(), crc32(data.as_slice())).encode(env)
+//!     })?;
+//!     Ok(atoms::ok())
+//! }
+//! ```
+//!
+//! The global pool has as many threads as the machine has cores, and is created by the first
+//! call to `global`, unless the `load` callback sets another with `set_global`. Pools can also
+//! be created with `ThreadPool::new` and stored in resources.
+
+use crate::env::OwnedEnv;
+use crate::thread::panic_reason;
+use crate::types::LocalPid;
+use crate::work_queue::{PushError, QueueStats, WorkQueue};
+use crate::{Env, NifResult, Term};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+/// The number of jobs the global pool holds before refusing new ones.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// A job queued in a `ThreadPool`.
+pub type Job = Box<dyn FnOnce() + Send>;
+
+/// A fixed set of threads running jobs. See the module documentation.
+pub struct ThreadPool {
+    queue: WorkQueue<Job>,
+}
+
+impl ThreadPool {
+    /// Starts `threads` threads, running the jobs of a queue holding at most `capacity` jobs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threads` or `capacity` is zero, or if a thread can't be spawned.
+    pub fn new(threads: usize, capacity: usize) -> Self {
+        ThreadPool {
+            queue: WorkQueue::new(capacity, threads, |job: Job| job()),
+        }
+    }
+
+    /// Queues `job`, whose result is sent to `pid`. If the job panics, `{:error, reason}` is sent
+    /// instead, where `reason` is the message of the panic.
+    ///
+    /// # Errors
+    ///
+    /// Returns `{:error, :insufficient_memory}` if the environment of the reply can't be
+    /// allocated, and `{:error, :full}` if the queue of the pool is full. The environment is
+    /// allocated before the job is queued, so that a queued job always replies.
+    pub fn spawn<F>(&self, pid: LocalPid, job: F) -> NifResult<()>
+    where
+        F: for<'a> FnOnce(Env<'a>) -> Term<'a> + Send + 'static,
+    {
+        let mut owned_env = OwnedEnv::try_new()?;
+        self.execute(move || {
+            owned_env.send_and_clear(&pid, |env| {
+                match panic::catch_unwind(AssertUnwindSafe(|| job(env))) {
+                    Ok(term) => term,
+                    Err(err) => env.error_tuple(panic_reason(env, &err)),
+                }
+            });
+        })?;
+        Ok(())
+    }
+
+    /// Queues `job`, which replies by itself if needed.
+    pub fn execute<F>(&self, job: F) -> Result<(), PushError<Job>>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.queue.try_push(Box::new(job))
+    }
+
+    /// Returns the statistics of the queue of the pool.
+    pub fn stats(&self) -> QueueStats {
+        self.queue.stats()
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL: RwLock<Option<Arc<ThreadPool>>> = RwLock::new(None);
+}
+
+/// Sets the pool returned by `global`. Pools set before are dropped once their queued jobs are
+/// done.
+pub fn set_global(pool: ThreadPool) {
+    *GLOBAL
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Arc::new(pool));
+}
+
+/// Returns the global pool, creating it if needed.
+pub fn global() -> Arc<ThreadPool> {
+    if let Some(pool) = &*GLOBAL
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+    {
+        return pool.clone();
+    }
+
+    let mut global = GLOBAL
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    global
+        .get_or_insert_with(|| {
+            let threads = thread::available_parallelism().map_or(4, |threads| threads.get());
+            Arc::new(ThreadPool::new(threads, DEFAULT_CAPACITY))
+        })
+        .clone()
+}
@@ -0,0 +1,179 @@
+//! Propagation of distributed tracing contexts across NIF calls.
+//!
+//! A trace started in Elixir is lost at the native boundary unless the NIF carries its identity
+//! along. `TraceContext` holds the identity of the current span, as in the W3C Trace Context
+//! specification, and decodes from the map of tracing metadata passed by the caller:
+//!
+//! * `:trace_id`, a binary of 16 bytes, or of 32 hexadecimal digits,
+//! * `:span_id`, a binary of 8 bytes, or of 16 hexadecimal digits,
+//! * `:trace_flags`, an optional integer, 0 by default.
+//!
+//! It encodes back to the same map, with raw binaries, and can be put into the metadata of
+//! telemetry events or the messages sent by the NIF, so that their handlers can link them to the
+//! trace:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn import<'a>(env: Env<'a>, rows: Vec<Row>, metadata: Term<'a>) -> NifResult<Term<'a>> {
+//!     let context = TraceContext::extract(metadata)?;
+//!     let stats = load(rows);
+//!     let measurements = map_new(env).map_put(atoms::rows().encode(env), stats.rows.encode(env))?;
+//!     let metadata = match context {
+//!         Some(context) => context.inject(map_new(env))?,
+//!         None => map_new(env),
+//!     };
+//!     Ok((measurements, metadata).encode(env))
+//! }
+//! ```
+//!
+//! Outgoing requests made by the NIF can carry the context as a `traceparent` header, with
+//! `to_traceparent`.
+
+use crate::{Binary, Decoder, Encoder, Env, Error, NifResult, Term};
+use std::fmt::Write;
+
+mod atoms {
+    crate::atoms! {
+        trace_id,
+        span_id,
+        trace_flags,
+    }
+}
+
+/// The identity of a span of a distributed trace. See the module documentation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TraceContext {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+    pub trace_flags: u8,
+}
+
+impl TraceContext {
+    /// The flag marking a sampled trace.
+    pub const SAMPLED: u8 = 0x01;
+
+    /// Returns whether the trace is sampled, i.e. recorded by the caller.
+    pub fn is_sampled(&self) -> bool {
+        self.trace_flags & TraceContext::SAMPLED != 0
+    }
+
+    /// Decodes the context from a map of tracing metadata, or returns `None` if the map has no
+    /// `:trace_id`, so that tracing is optional for the caller.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::BadArg` if `metadata` is not a map, or has invalid tracing metadata.
+    pub fn extract(metadata: Term) -> NifResult<Option<TraceContext>> {
+        let env = metadata.get_env();
+        if !metadata.is_map() {
+            return Err(Error::BadArg);
+        }
+        if metadata.map_get(atoms::trace_id().encode(env)).is_err() {
+            return Ok(None);
+        }
+        metadata.decode().map(Some)
+    }
+
+    /// Puts the context into `map`, e.g. the metadata of a telemetry event.
+    pub fn inject<'a>(&self, map: Term<'a>) -> NifResult<Term<'a>> {
+        let env = map.get_env();
+        let trace_id = Binary::from_bytes(env, &self.trace_id);
+        let span_id = Binary::from_bytes(env, &self.span_id);
+        map.map_put(atoms::trace_id().encode(env), trace_id.encode(env))?
+            .map_put(atoms::span_id().encode(env), span_id.encode(env))?
+            .map_put(
+                atoms::trace_flags().encode(env),
+                self.trace_flags.encode(env),
+            )
+    }
+
+    /// Returns the context as the value of a W3C `traceparent` header.
+    pub fn to_traceparent(&self) -> String {
+        let mut header = String::with_capacity(55);
+        header.push_str("00-");
+        write_hex(&mut header, &self.trace_id);
+        header.push('-');
+        write_hex(&mut header, &self.span_id);
+        header.push('-');
+        write_hex(&mut header, &[self.trace_flags]);
+        header
+    }
+
+    /// Parses the value of a W3C `traceparent` header of version 0.
+    pub fn from_traceparent(header: &str) -> Option<TraceContext> {
+        let mut parts = header.trim().split('-');
+        if parts.next()? != "00" {
+            return None;
+        }
+        let trace_id = parse_hex(parts.next()?)?;
+        let span_id = parse_hex(parts.next()?)?;
+        let [trace_flags] = parse_hex(parts.next()?)?;
+        if parts.next().is_some() {
+            return None;
+        }
+        TraceContext::new(trace_id, span_id, trace_flags)
+    }
+
+    /// Returns the context, unless the trace or span id is all zeros, which the specification
+    /// reserves for invalid contexts.
+    fn new(trace_id: [u8; 16], span_id: [u8; 8], trace_flags: u8) -> Option<TraceContext> {
+        if trace_id == [0; 16] || span_id == [0; 8] {
+            return None;
+        }
+        Some(TraceContext {
+            trace_id,
+            span_id,
+            trace_flags,
+        })
+    }
+}
+
+impl<'a> Decoder<'a> for TraceContext {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let env = term.get_env();
+        let trace_id = decode_id(term.map_get(atoms::trace_id().encode(env))?)?;
+        let span_id = decode_id(term.map_get(atoms::span_id().encode(env))?)?;
+        let trace_flags = match term.map_get(atoms::trace_flags().encode(env)) {
+            Ok(flags) => flags.decode()?,
+            Err(_) => 0,
+        };
+        TraceContext::new(trace_id, span_id, trace_flags).ok_or(Error::BadArg)
+    }
+}
+
+impl Encoder for TraceContext {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        self.inject(Term::map_new(env)).unwrap()
+    }
+}
+
+/// Decodes an id given as raw bytes, or as hexadecimal digits.
+fn decode_id<const N: usize>(term: Term) -> NifResult<[u8; N]> {
+    let bytes = term.decode_as_binary()?.as_slice();
+    if bytes.len() == N {
+        let mut id = [0; N];
+        id.copy_from_slice(bytes);
+        return Ok(id);
+    }
+    std::str::from_utf8(bytes)
+        .ok()
+        .and_then(parse_hex)
+        .ok_or(Error::BadArg)
+}
+
+fn write_hex(out: &mut String, bytes: &[u8]) {
+    for byte in bytes {
+        write!(out, "{:02x}", byte).unwrap();
+    }
+}
+
+fn parse_hex<const N: usize>(digits: &str) -> Option<[u8; N]> {
+    if digits.len() != 2 * N || !digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut bytes = [0; N];
+    for (index, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[2 * index..2 * index + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
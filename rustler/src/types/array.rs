@@ -0,0 +1,193 @@
+//! Encoding and decoding of OTP's `:array`.
+//!
+//! An array is a record `{array, size, max, default, elements}`, where `elements` is a tree of
+//! tuples: leaves are tuples of 10 entries, inner nodes are tuples of 10 subtrees followed by the
+//! number of entries each subtree spans, and an integer stands for a subtree where every entry
+//! has the default value.
+//!
+//! `ErlArray<T>` decodes such a record into a `Vec<Option<T>>` with `size` entries, where the
+//! entries that have the default value of the array are `None`, and encodes it back into an
+//! array with the default value `undefined`, like `:array.new/0` creates:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn count_set(array: ErlArray<Term>) -> usize {
+//!     array.iter().filter(|entry| entry.is_some()).count()
+//! }
+//! ```
+
+use super::tuple::{get_tuple, make_tuple};
+use crate::{Decoder, Encoder, Env, Error, NifResult, Term};
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut};
+
+/// The number of entries in a leaf, and of subtrees in an inner node.
+const NODE_SIZE: usize = 10;
+
+mod atoms {
+    crate::atoms! {
+        array,
+        undefined,
+    }
+}
+
+/// The entries of an `:array`, where `None` is the default value. See the module documentation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ErlArray<T>(pub Vec<Option<T>>);
+
+impl<T> ErlArray<T> {
+    pub fn into_inner(self) -> Vec<Option<T>> {
+        self.0
+    }
+}
+
+impl<T> From<Vec<Option<T>>> for ErlArray<T> {
+    fn from(entries: Vec<Option<T>>) -> Self {
+        ErlArray(entries)
+    }
+}
+
+impl<T> FromIterator<Option<T>> for ErlArray<T> {
+    fn from_iter<I: IntoIterator<Item = Option<T>>>(iter: I) -> Self {
+        ErlArray(iter.into_iter().collect())
+    }
+}
+
+impl<T> Deref for ErlArray<T> {
+    type Target = Vec<Option<T>>;
+    fn deref(&self) -> &Vec<Option<T>> {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for ErlArray<T> {
+    fn deref_mut(&mut self) -> &mut Vec<Option<T>> {
+        &mut self.0
+    }
+}
+
+impl<T> Encoder for ErlArray<T>
+where
+    T: Encoder,
+{
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        let mut max = NODE_SIZE;
+        while max < self.0.len() {
+            max *= NODE_SIZE;
+        }
+        let default = atoms::undefined().encode(env);
+        let elements = self.encode_tree(env, 0, max, default);
+        make_tuple(
+            env,
+            &[
+                atoms::array().encode(env),
+                self.0.len().encode(env),
+                max.encode(env),
+                default,
+                elements,
+            ],
+        )
+    }
+}
+
+impl<T> ErlArray<T>
+where
+    T: Encoder,
+{
+    /// Encodes the subtree of the `span` entries starting at `start`.
+    fn encode_tree<'a>(
+        &self,
+        env: Env<'a>,
+        start: usize,
+        span: usize,
+        default: Term<'a>,
+    ) -> Term<'a> {
+        if start >= self.0.len() {
+            return span.encode(env);
+        }
+
+        if span == NODE_SIZE {
+            let entries: Vec<Term<'a>> = (start..start + NODE_SIZE)
+                .map(|index| match self.0.get(index) {
+                    Some(Some(value)) => value.encode(env),
+                    _ => default,
+                })
+                .collect();
+            return make_tuple(env, &entries);
+        }
+
+        let child_span = span / NODE_SIZE;
+        let mut node: Vec<Term<'a>> = (0..NODE_SIZE)
+            .map(|i| self.encode_tree(env, start + i * child_span, child_span, default))
+            .collect();
+        node.push(child_span.encode(env));
+        make_tuple(env, &node)
+    }
+}
+
+/// Calls `visit` with the entries of `tree` in order, `None` standing for the default value,
+/// until `remaining` entries were visited.
+fn visit_tree<'a, F>(tree: Term<'a>, remaining: &mut usize, visit: &mut F) -> NifResult<()>
+where
+    F: FnMut(Option<Term<'a>>) -> NifResult<()>,
+{
+    if *remaining == 0 {
+        return Ok(());
+    }
+
+    if let Ok(span) = tree.decode::<usize>() {
+        let count = span.min(*remaining);
+        for _ in 0..count {
+            visit(None)?;
+        }
+        *remaining -= count;
+        return Ok(());
+    }
+
+    let node = get_tuple(tree)?;
+    match node.len() {
+        NODE_SIZE => {
+            for entry in node.into_iter().take(*remaining) {
+                visit(Some(entry))?;
+                *remaining -= 1;
+            }
+            Ok(())
+        }
+        len if len == NODE_SIZE + 1 => {
+            for child in &node[..NODE_SIZE] {
+                visit_tree(*child, remaining, visit)?;
+            }
+            Ok(())
+        }
+        _ => Err(Error::BadArg),
+    }
+}
+
+impl<'a, T> Decoder<'a> for ErlArray<T>
+where
+    T: Decoder<'a>,
+{
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let record = get_tuple(term)?;
+        if record.len() != 5 || atoms::array() != record[0] {
+            return Err(Error::BadArg);
+        }
+        let size: usize = record[1].decode()?;
+        let default = record[3];
+
+        let mut entries = Vec::new();
+        let mut remaining = size;
+        visit_tree(record[4], &mut remaining, &mut |entry| {
+            entries.push(match entry {
+                Some(value) if value != default => Some(value.decode()?),
+                _ => None,
+            });
+            Ok(())
+        })?;
+
+        if entries.len() != size {
+            return Err(Error::BadArg);
+        }
+        Ok(ErlArray(entries))
+    }
+}
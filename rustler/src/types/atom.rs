@@ -58,6 +58,23 @@ impl Atom {
         }
     }
 
+    /// Returns the length of the text of the atom, in Latin-1 bytes, without allocating.
+    ///
+    /// Atoms with characters outside Latin-1 have no Latin-1 text, and a length of 0.
+    pub fn text_len(self) -> usize {
+        unsafe { atom::get_atom_length(std::ptr::null_mut(), self.term).unwrap_or(0) }
+    }
+
+    /// Copies the text of the atom, in Latin-1 bytes, into `buf` without allocating, and returns
+    /// the number of bytes copied.
+    ///
+    /// When `buf` is shorter than `text_len()`, the text is truncated to the first `buf.len()`
+    /// bytes, so a return value smaller than `text_len()` means the text was truncated. Atoms with
+    /// characters outside Latin-1 copy nothing.
+    pub fn read_into(self, buf: &mut [u8]) -> usize {
+        unsafe { atom::get_atom_into(std::ptr::null_mut(), self.term, buf) }
+    }
+
     /// Return the atom whose text representation is the given `string`, like `erlang:list_to_atom/2`.
     ///
     /// # Errors
@@ -275,6 +292,9 @@ atoms! {
     /// The `nil` atom.
     nil,
 
+    /// The `undefined` atom, commonly used in Erlang to represent an absent value.
+    undefined,
+
     /// The `ok` atom, commonly used in success tuples.
     ok,
 
@@ -297,6 +317,18 @@ atoms! {
     /// values, use `Encoder` and `Decoder` instead.
     true_ = "true",
 
+    /// The `chunk` atom, used to tag partial results of streaming replies.
+    chunk,
+
+    /// The `done` atom, used to mark the end of streaming replies.
+    done,
+
+    /// The `insufficient_memory` atom, returned when an allocation fails.
+    insufficient_memory,
+
+    /// The `not_implemented` atom, raised by NIFs whose feature is disabled.
+    not_implemented,
+
     /// The `__struct__` atom used by Elixir.
     __struct__,
 
@@ -0,0 +1,131 @@
+//! Decoding of all the arguments of a NIF call, with errors naming the failing argument.
+//!
+//! The `#[rustler::nif]` macro decodes arguments through a `DecodeBatch`. NIFs written by hand,
+//! taking raw terms, can use it as well to report failures consistently:
+//!
+//! ```ignore
+//! let mut batch = DecodeBatch::new(args).nif("insert/5");
+//! let table: ResourceArc<Table> = batch.next()?;
+//! let key: String = batch.next()?;
+//! ```
+//!
+//! When an argument can't be decoded, an `ArgumentError` is raised, with a message like
+//! `argument 2 of insert/5 could not be decoded as alloc::string::String, got a number`. Errors
+//! other than `BadArg` returned by decoders, like raised terms, are passed through unchanged.
+//! `Term::decode_or_raise` raises the same errors for a single term.
+
+use crate::types::atom;
+use crate::types::string::encode_binary;
+use crate::{Decoder, Encoder, Env, Error, NifResult, Term, TermType};
+use std::any;
+
+mod atoms {
+    crate::atoms! {
+        __exception__,
+        message,
+        argument_error = "Elixir.ArgumentError",
+    }
+}
+
+/// Decodes the arguments of a NIF call. See the module documentation.
+pub struct DecodeBatch<'a, 'b> {
+    args: &'b [Term<'a>],
+    nif: Option<&'static str>,
+    next: usize,
+}
+
+impl<'a, 'b> DecodeBatch<'a, 'b> {
+    pub fn new(args: &'b [Term<'a>]) -> Self {
+        DecodeBatch {
+            args,
+            nif: None,
+            next: 0,
+        }
+    }
+
+    /// Sets the name of the NIF, as `name/arity`, to mention in error messages.
+    pub fn nif(mut self, nif: &'static str) -> Self {
+        self.nif = Some(nif);
+        self
+    }
+
+    /// Decodes the argument at `index`, counting from 0.
+    pub fn arg<T: Decoder<'a>>(&self, index: usize) -> NifResult<T> {
+        let term = match self.args.get(index) {
+            Some(term) => *term,
+            None => return Err(Error::BadArg),
+        };
+
+        term.decode().map_err(|err| match err {
+            Error::BadArg => argument_error::<T>(&self.position(index), term),
+            err => err,
+        })
+    }
+
+    /// Decodes the argument following the last one decoded with `next()`.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next<T: Decoder<'a>>(&mut self) -> NifResult<T> {
+        let index = self.next;
+        self.next += 1;
+        self.arg(index)
+    }
+
+    fn position(&self, index: usize) -> String {
+        match self.nif {
+            Some(nif) => format!("argument {} of {}", index + 1, nif),
+            None => format!("argument {}", index + 1),
+        }
+    }
+}
+
+/// Returns the `ArgumentError` raised when `term` can't be decoded as a `T`, naming the term
+/// `subject` in the message.
+pub(crate) fn argument_error<T>(subject: &str, term: Term) -> Error {
+    Error::RaiseTerm(Box::new(ArgumentError {
+        message: format!(
+            "{} could not be decoded as {}, got {}",
+            subject,
+            any::type_name::<T>(),
+            describe(term.get_type())
+        ),
+    }))
+}
+
+fn describe(term_type: TermType) -> &'static str {
+    match term_type {
+        TermType::Atom => "an atom",
+        TermType::Binary => "a binary",
+        TermType::EmptyList => "an empty list",
+        TermType::Exception => "an exception",
+        TermType::Fun => "a fun",
+        TermType::List => "a list",
+        TermType::Map => "a map",
+        TermType::Number => "a number",
+        TermType::Pid => "a pid",
+        TermType::Port => "a port",
+        TermType::Ref => "a reference",
+        TermType::Tuple => "a tuple",
+        TermType::Unknown => "a term of unknown type",
+    }
+}
+
+/// An Elixir `ArgumentError` exception.
+struct ArgumentError {
+    message: String,
+}
+
+impl Encoder for ArgumentError {
+    fn encode<'c>(&self, env: Env<'c>) -> Term<'c> {
+        let keys = [
+            atom::__struct__().encode(env),
+            atoms::__exception__().encode(env),
+            atoms::message().encode(env),
+        ];
+        let values = [
+            atoms::argument_error().encode(env),
+            true.encode(env),
+            encode_binary(env, &self.message),
+        ];
+        Term::map_from_arrays(env, &keys, &values).unwrap()
+    }
+}
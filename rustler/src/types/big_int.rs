@@ -0,0 +1,57 @@
+//! Encoding and decoding of `num_bigint::BigInt`, for integers of any size.
+//!
+//! Erlang integers are unbounded, while the NIF API can only make and read 64-bit ones. Larger
+//! integers are converted through the External Term Format, from and to their magnitude in
+//! little-endian bytes:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn factorial(n: u32) -> BigInt {
+//!     (1..=n).map(BigInt::from).product()
+//! }
+//! ```
+//!
+//! This module is only available with the `big_integer` feature.
+
+use crate::types::primitive::{get_big_integer, make_big_integer};
+use crate::{Decoder, Encoder, Env, Error, NifResult, Term};
+use num_bigint::{BigInt, BigUint, Sign};
+use std::convert::TryFrom;
+
+pub use num_bigint;
+
+impl Encoder for BigInt {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        if let Ok(value) = i64::try_from(self) {
+            return value.encode(env);
+        }
+        let (sign, magnitude) = self.to_bytes_le();
+        make_big_integer(env, sign == Sign::Minus, &magnitude)
+    }
+}
+
+impl<'a> Decoder<'a> for BigInt {
+    fn decode(term: Term<'a>) -> NifResult<BigInt> {
+        let (negative, magnitude) = get_big_integer(term)?;
+        let sign = if negative { Sign::Minus } else { Sign::Plus };
+        Ok(BigInt::from_bytes_le(sign, &magnitude))
+    }
+}
+
+impl Encoder for BigUint {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        if let Ok(value) = u64::try_from(self) {
+            return value.encode(env);
+        }
+        make_big_integer(env, false, &self.to_bytes_le())
+    }
+}
+
+impl<'a> Decoder<'a> for BigUint {
+    fn decode(term: Term<'a>) -> NifResult<BigUint> {
+        match get_big_integer(term)? {
+            (false, magnitude) => Ok(BigUint::from_bytes_le(&magnitude)),
+            (true, _) => Err(Error::BadArg),
+        }
+    }
+}
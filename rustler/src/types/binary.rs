@@ -2,7 +2,8 @@
 //!
 //! Rustler provides two binary types: [`Binary`] and [`OwnedBinary`].  Both
 //! represent a contiguous region `u8`s, and they both use the Erlang allocator. The
-//! primary difference between the two is their ownership semantics.
+//! primary difference between the two is their ownership semantics. A third type,
+//! [`NewBinary`], is a binary being built directly in an [`Env`].
 //!
 //! The _owned_ in `OwnedBinary` refers to the fact that it owns the binary it
 //! wraps. The _owner_ of an `OwnedBinary` is free to modify its contents. Ownership
@@ -76,19 +77,54 @@
 //!
 //! [`Binary`]: struct.Binary.html
 //! [`Env`]: ../../env/struct.Env.html
+//! [`NewBinary`]: struct.NewBinary.html
 //! [`OwnedBinary`]: struct.OwnedBinary.html
 
 use crate::{
+    profile::BinaryAllocation,
+    types::atom,
     wrapper::binary::{alloc, realloc, ErlNifBinary},
+    wrapper::{list, NIF_TERM},
     Decoder, Encoder, Env, Error, NifResult, Term,
 };
 use std::{
     borrow::{Borrow, BorrowMut},
+    fmt,
     io::Write,
     mem::MaybeUninit,
     ops::{Deref, DerefMut},
 };
 
+/// The error returned when a binary can't be allocated.
+///
+/// It converts to an `Error` returning `{:error, :insufficient_memory}` from the NIF, so it can be
+/// propagated with `?` from NIFs returning a `NifResult`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AllocError {
+    /// The number of bytes that were requested.
+    pub size: usize,
+}
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "could not allocate a binary of {} bytes", self.size)
+    }
+}
+
+impl std::error::Error for AllocError {}
+
+impl Encoder for AllocError {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        atom::insufficient_memory().encode(env)
+    }
+}
+
+impl From<AllocError> for Error {
+    fn from(err: AllocError) -> Error {
+        Error::Term(Box::new(err))
+    }
+}
+
 /// An mutable smart-pointer to an Erlang binary.
 ///
 /// See [module-level doc](index.html) for more information.
@@ -99,6 +135,12 @@ impl<'a> OwnedBinary {
         OwnedBinary(inner)
     }
 
+    /// Returns the inner binary without releasing it, handing over its ownership.
+    pub(crate) fn into_raw(self) -> ErlNifBinary {
+        let owned = std::mem::ManuallyDrop::new(self);
+        owned.0
+    }
+
     /// Allocates a new `OwnedBinary` with size `size`.
     ///
     /// Memory is not initialized. If uninitialized memory is undesirable, set it
@@ -111,6 +153,12 @@ impl<'a> OwnedBinary {
         unsafe { alloc(size) }.map(OwnedBinary)
     }
 
+    /// Allocates a new `OwnedBinary` with size `size`, like `new()`, but returns an `AllocError`
+    /// on failure.
+    pub fn try_new(size: usize) -> Result<OwnedBinary, AllocError> {
+        OwnedBinary::new(size).ok_or(AllocError { size })
+    }
+
     /// Copies `src`'s data into a new `OwnedBinary`.
     ///
     /// # Errors
@@ -207,6 +255,80 @@ impl Drop for OwnedBinary {
 
 unsafe impl Send for OwnedBinary {}
 
+/// A binary allocated directly in an `Env`, writable until it is converted into a `Binary` or a
+/// `Term`.
+///
+/// Unlike an `OwnedBinary`, which is allocated on its own and then handed over to an `Env` by
+/// `Binary::from_owned`, a `NewBinary` is created with `enif_make_new_binary` and belongs to the
+/// `Env` from the start, so there is nothing to release and no ownership to transfer. It is the
+/// cheapest way to return a binary built by a NIF.
+///
+/// Like for `OwnedBinary`, the content of a new binary is not initialized.
+///
+/// ```no_run
+/// # use rustler::{Binary, Env, NewBinary};
+/// #[rustler::nif]
+/// fn zeros<'a>(env: Env<'a>, size: usize) -> Binary<'a> {
+///     let mut binary = NewBinary::new(env, size);
+///     binary.as_mut_slice().fill(0);
+///     binary.into()
+/// }
+/// ```
+pub struct NewBinary<'a> {
+    data: *mut u8,
+    size: usize,
+    term: Term<'a>,
+}
+
+impl<'a> NewBinary<'a> {
+    /// Allocates a binary of `size` bytes in `env`.
+    pub fn new(env: Env<'a>, size: usize) -> Self {
+        let mut term = 0;
+        let data = unsafe { rustler_sys::enif_make_new_binary(env.as_c_arg(), size, &mut term) };
+        NewBinary {
+            data,
+            size,
+            term: unsafe { Term::new(env, term) },
+        }
+    }
+
+    /// Extracts a slice containing the entire binary.
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { ::std::slice::from_raw_parts(self.data, self.size) }
+    }
+
+    /// Extracts a mutable slice of the entire binary.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { ::std::slice::from_raw_parts_mut(self.data, self.size) }
+    }
+}
+
+impl<'a> Deref for NewBinary<'a> {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl<'a> DerefMut for NewBinary<'a> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
+impl<'a> From<NewBinary<'a>> for Binary<'a> {
+    fn from(binary: NewBinary<'a>) -> Self {
+        // This should never fail, as the term was created as a binary.
+        Binary::from_term(binary.term).ok().unwrap()
+    }
+}
+
+impl<'a> From<NewBinary<'a>> for Term<'a> {
+    fn from(binary: NewBinary<'a>) -> Self {
+        binary.term
+    }
+}
+
 /// An immutable smart-pointer to an Erlang binary.
 ///
 /// See [module-level doc](index.html) for more information.
@@ -214,9 +336,53 @@ unsafe impl Send for OwnedBinary {}
 pub struct Binary<'a> {
     inner: ErlNifBinary,
     term: Term<'a>,
+    /// The binary this one was cut from with `make_subbinary`, and the offset into it.
+    parent: Option<(Term<'a>, usize)>,
 }
 
+/// The size in bytes up to which the VM stores binaries on the heap of the process, instead of
+/// reference-counting them (`ERL_ONHEAP_BIN_LIMIT`).
+///
+/// `Binary::from_bytes` builds binaries up to this size directly on the heap. See
+/// `Binary::from_bytes_with` to force reference-counted binaries.
+pub const HEAP_BINARY_LIMIT: usize = 64;
+
 impl<'a> Binary<'a> {
+    /// Copies `bytes` into a new binary in `env`.
+    ///
+    /// Binaries of up to `HEAP_BINARY_LIMIT` bytes are built in place with `enif_make_new_binary`,
+    /// which avoids allocating and freeing a reference-counted binary for each of them. Larger
+    /// binaries are allocated as an `OwnedBinary` and handed over to `env`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the binary can't be allocated.
+    pub fn from_bytes(env: Env<'a>, bytes: &[u8]) -> Self {
+        Self::from_bytes_with(env, bytes, BinaryAllocation::Adaptive)
+    }
+
+    /// Copies `bytes` into a new binary in `env`, allocated as set by `allocation`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the binary can't be allocated.
+    pub fn from_bytes_with(env: Env<'a>, bytes: &[u8], allocation: BinaryAllocation) -> Self {
+        let inline = match allocation {
+            BinaryAllocation::Adaptive => bytes.len() <= HEAP_BINARY_LIMIT,
+            BinaryAllocation::Refc => false,
+        };
+
+        if inline {
+            let mut binary = NewBinary::new(env, bytes.len());
+            binary.as_mut_slice().copy_from_slice(bytes);
+            binary.into()
+        } else {
+            let mut owned = OwnedBinary::new(bytes.len()).expect("binary allocation failed");
+            owned.as_mut_slice().copy_from_slice(bytes);
+            Binary::from_owned(owned, env)
+        }
+    }
+
     /// Consumes `owned` and returns an immutable `Binary`.
     pub fn from_owned(owned: OwnedBinary, env: Env<'a>) -> Self {
         // We are transferring ownership of `owned`'s data to the
@@ -234,6 +400,7 @@ impl<'a> Binary<'a> {
         Binary {
             inner: owned.0,
             term,
+            parent: None,
         }
     }
 
@@ -246,6 +413,12 @@ impl<'a> Binary<'a> {
         OwnedBinary::from_unowned(self)
     }
 
+    /// Copies `self`'s data into a new `OwnedBinary`, like `to_owned()`, but returns an
+    /// `AllocError` on failure.
+    pub fn try_to_owned(&self) -> Result<OwnedBinary, AllocError> {
+        self.to_owned().ok_or(AllocError { size: self.len() })
+    }
+
     /// Creates a `Binary` from `term`.
     ///
     /// # Errors
@@ -266,6 +439,7 @@ impl<'a> Binary<'a> {
         Ok(Binary {
             inner: unsafe { binary.assume_init() },
             term,
+            parent: None,
         })
     }
 
@@ -289,6 +463,7 @@ impl<'a> Binary<'a> {
         Ok(Binary {
             inner: unsafe { binary.assume_init() },
             term,
+            parent: None,
         })
     }
 
@@ -326,7 +501,44 @@ impl<'a> Binary<'a> {
         };
         let term = unsafe { Term::new(self.term.get_env(), raw_term) };
         // This should never fail, as we are always passing in a binary term.
-        Ok(Binary::from_term(term).ok().unwrap())
+        let mut binary = Binary::from_term(term).ok().unwrap();
+        binary.parent = Some((self.term, offset));
+        Ok(binary)
+    }
+
+    /// Returns whether `self` was created with `make_subbinary`.
+    ///
+    /// Binaries decoded from terms are never considered sub-binaries, even if the VM shares their
+    /// data with a larger binary.
+    pub fn is_subbinary(&self) -> bool {
+        self.parent.is_some()
+    }
+
+    /// Returns the offset of `self` into its parent, or 0 if `self` isn't a sub-binary.
+    pub fn offset(&self) -> usize {
+        self.parent.map_or(0, |(_, offset)| offset)
+    }
+
+    /// Returns the binary `self` was cut from with `make_subbinary`.
+    pub fn parent(&self) -> Option<Binary<'a>> {
+        self.parent.map(|(term, _)| {
+            // This should never fail, as the parent was a binary when `self` was created.
+            Binary::from_term(term).ok().unwrap()
+        })
+    }
+
+    /// Copies `self`'s data into a new, independent binary.
+    ///
+    /// A sub-binary keeps its whole parent alive, so returning a few bytes cut from a huge
+    /// binary pins the huge binary in memory for as long as the result is referenced. Returning
+    /// the realized binary instead lets the parent be garbage collected.
+    ///
+    /// # Errors
+    ///
+    /// If allocation fails, `None` is returned.
+    pub fn realize(&self) -> Option<Binary<'a>> {
+        let owned = self.to_owned()?;
+        Some(Binary::from_owned(owned, self.term.get_env()))
     }
 }
 
@@ -347,10 +559,30 @@ impl<'a> Decoder<'a> for Binary<'a> {
         Binary::from_term(term)
     }
 }
+/// Borrows the bytes of a binary term, without copying them. The slice lives as long as the
+/// term.
+///
+/// Note that a `&[u8]` is encoded as a list of integers, like any other slice. Use `Binary` in
+/// types that are encoded too.
+impl<'a> Decoder<'a> for &'a [u8] {
+    fn decode(term: Term<'a>) -> Result<Self, Error> {
+        Ok(Binary::from_term(term)?.as_slice())
+    }
+}
+
 impl<'a> Encoder for Binary<'a> {
     fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
         self.to_term(env)
     }
+
+    /// Encodes a list of binaries, which can be written as iodata, e.g. with `:gen_tcp.send/2`.
+    fn encode_slice<'b>(values: &[Self], env: Env<'b>) -> Term<'b> {
+        let terms: Vec<NIF_TERM> = values
+            .iter()
+            .map(|binary| binary.to_term(env).as_c_arg())
+            .collect();
+        unsafe { Term::new(env, list::make_list(env.as_c_arg(), &terms)) }
+    }
 }
 
 /// ## Binary terms
@@ -0,0 +1,191 @@
+//! A cursor reading protocol fields from a `Binary`.
+//!
+//! `BinaryReader` reads fixed-width integers in both byte orders, LEB128 varints as used by
+//! Protocol Buffers, zig-zag encoded signed varints, and length-prefixed fields. Byte fields are
+//! returned as sub-binaries of the binary being read, so they are not copied:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn parse_frame(data: Binary) -> NifResult<(u16, u64, Binary)> {
+//!     let mut reader = BinaryReader::new(data);
+//!     let kind = reader.read_u16_be()?;
+//!     let id = reader.read_varint()?;
+//!     let payload = reader.read_varint_prefixed()?;
+//!     Ok((kind, id, payload))
+//! }
+//! ```
+//!
+//! Reads past the end of the binary, and varints longer than 64 bits, fail with
+//! `Error::BadArg` and leave the position of the reader unchanged.
+
+use super::binary::Binary;
+use crate::{Error, NifResult};
+use std::convert::{TryFrom, TryInto};
+
+/// A cursor over a `Binary`. See the module documentation.
+#[derive(Clone, Copy)]
+pub struct BinaryReader<'a> {
+    binary: Binary<'a>,
+    position: usize,
+}
+
+macro_rules! read_int {
+    ($($name:ident => $ty:ty, $from_bytes:ident, $order:literal;)*) => {
+        $(
+            #[doc = concat!("Reads a `", stringify!($ty), "` in ", $order, " byte order.")]
+            pub fn $name(&mut self) -> NifResult<$ty> {
+                let bytes = self.read_slice(std::mem::size_of::<$ty>())?;
+                Ok(<$ty>::$from_bytes(bytes.try_into().unwrap()))
+            }
+        )*
+    };
+}
+
+impl<'a> BinaryReader<'a> {
+    /// Returns a reader at the start of `binary`.
+    pub fn new(binary: Binary<'a>) -> Self {
+        BinaryReader {
+            binary,
+            position: 0,
+        }
+    }
+
+    /// Returns the number of bytes read so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the number of bytes left to read.
+    pub fn remaining(&self) -> usize {
+        self.binary.len() - self.position
+    }
+
+    /// Returns whether all the bytes have been read.
+    pub fn is_empty(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Skips `len` bytes.
+    pub fn skip(&mut self, len: usize) -> NifResult<()> {
+        self.read_slice(len).map(|_| ())
+    }
+
+    /// Reads `len` bytes, borrowed from the binary.
+    pub fn read_slice(&mut self, len: usize) -> NifResult<&'a [u8]> {
+        if len > self.remaining() {
+            return Err(Error::BadArg);
+        }
+        let bytes = &self.binary.as_slice()[self.position..self.position + len];
+        self.position += len;
+        Ok(bytes)
+    }
+
+    /// Reads `len` bytes, as a sub-binary.
+    pub fn read_binary(&mut self, len: usize) -> NifResult<Binary<'a>> {
+        let binary = self.binary.make_subbinary(self.position, len)?;
+        self.position += len;
+        Ok(binary)
+    }
+
+    /// Reads the remaining bytes, as a sub-binary.
+    pub fn read_rest(&mut self) -> NifResult<Binary<'a>> {
+        self.read_binary(self.remaining())
+    }
+
+    /// Reads a `u8`.
+    pub fn read_u8(&mut self) -> NifResult<u8> {
+        Ok(self.read_slice(1)?[0])
+    }
+
+    /// Reads an `i8`.
+    pub fn read_i8(&mut self) -> NifResult<i8> {
+        Ok(self.read_u8()? as i8)
+    }
+
+    read_int! {
+        read_u16_be => u16, from_be_bytes, "big-endian";
+        read_u16_le => u16, from_le_bytes, "little-endian";
+        read_u32_be => u32, from_be_bytes, "big-endian";
+        read_u32_le => u32, from_le_bytes, "little-endian";
+        read_u64_be => u64, from_be_bytes, "big-endian";
+        read_u64_le => u64, from_le_bytes, "little-endian";
+        read_i16_be => i16, from_be_bytes, "big-endian";
+        read_i16_le => i16, from_le_bytes, "little-endian";
+        read_i32_be => i32, from_be_bytes, "big-endian";
+        read_i32_le => i32, from_le_bytes, "little-endian";
+        read_i64_be => i64, from_be_bytes, "big-endian";
+        read_i64_le => i64, from_le_bytes, "little-endian";
+    }
+
+    /// Reads an unsigned LEB128 varint: 7 bits per byte, least significant group first, with the
+    /// high bit set on all bytes but the last.
+    pub fn read_varint(&mut self) -> NifResult<u64> {
+        let bytes = &self.binary.as_slice()[self.position..];
+        let mut value: u64 = 0;
+
+        for (index, byte) in bytes.iter().enumerate().take(10) {
+            let group = u64::from(byte & 0x7f);
+            // The tenth byte holds the 64th bit only.
+            if index == 9 && group > 1 {
+                return Err(Error::BadArg);
+            }
+            value |= group << (7 * index);
+            if byte & 0x80 == 0 {
+                self.position += index + 1;
+                return Ok(value);
+            }
+        }
+
+        Err(Error::BadArg)
+    }
+
+    /// Reads a signed varint in zig-zag encoding, where 0, -1, 1, -2... are encoded as 0, 1, 2,
+    /// 3..., like the `sint64` fields of Protocol Buffers.
+    pub fn read_zigzag(&mut self) -> NifResult<i64> {
+        let value = self.read_varint()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    /// Reads a field prefixed with its length as a `u8`.
+    pub fn read_u8_prefixed(&mut self) -> NifResult<Binary<'a>> {
+        self.read_prefixed(|reader| reader.read_u8().map(u64::from))
+    }
+
+    /// Reads a field prefixed with its length as a big-endian `u16`.
+    pub fn read_u16_be_prefixed(&mut self) -> NifResult<Binary<'a>> {
+        self.read_prefixed(|reader| reader.read_u16_be().map(u64::from))
+    }
+
+    /// Reads a field prefixed with its length as a big-endian `u32`.
+    pub fn read_u32_be_prefixed(&mut self) -> NifResult<Binary<'a>> {
+        self.read_prefixed(|reader| reader.read_u32_be().map(u64::from))
+    }
+
+    /// Reads a field prefixed with its length as a varint, like the `bytes` fields of Protocol
+    /// Buffers.
+    pub fn read_varint_prefixed(&mut self) -> NifResult<Binary<'a>> {
+        self.read_prefixed(Self::read_varint)
+    }
+
+    /// Reads a length with `read_len`, then as many bytes, restoring the position if either fails.
+    fn read_prefixed<F>(&mut self, read_len: F) -> NifResult<Binary<'a>>
+    where
+        F: FnOnce(&mut Self) -> NifResult<u64>,
+    {
+        let start = self.position;
+        let result = read_len(self).and_then(|len| {
+            let len = usize::try_from(len).map_err(|_| Error::BadArg)?;
+            self.read_binary(len)
+        });
+        if result.is_err() {
+            self.position = start;
+        }
+        result
+    }
+}
+
+impl<'a> From<Binary<'a>> for BinaryReader<'a> {
+    fn from(binary: Binary<'a>) -> Self {
+        BinaryReader::new(binary)
+    }
+}
@@ -0,0 +1,215 @@
+//! A buffer writing protocol fields into an `OwnedBinary`.
+//!
+//! `BinaryWriter` is the counterpart of `BinaryReader`: it writes integers and floats in both
+//! byte orders, varints, zig-zag varints and length-prefixed fields. The bytes are written
+//! directly into an `OwnedBinary`, which grows as needed and is handed over to the `Env` when
+//! done, so the result isn't copied:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn encode_frame<'a>(env: Env<'a>, kind: u16, id: u64, payload: Binary) -> NifResult<Binary<'a>> {
+//!     let mut writer = BinaryWriter::with_capacity(payload.len() + 12)?;
+//!     writer.write_u16_be(kind)?;
+//!     writer.write_varint(id)?;
+//!     writer.write_varint_prefixed(&payload)?;
+//!     Ok(writer.finish(env)?)
+//! }
+//! ```
+
+use super::binary::{AllocError, Binary, OwnedBinary};
+use crate::{Env, Error, NifResult};
+use std::convert::TryFrom;
+use std::io;
+
+/// A growable buffer backed by an `OwnedBinary`. See the module documentation.
+pub struct BinaryWriter {
+    binary: OwnedBinary,
+    len: usize,
+}
+
+macro_rules! write_num {
+    ($($name:ident => $ty:ty, $to_bytes:ident, $order:literal;)*) => {
+        $(
+            #[doc = concat!("Writes a `", stringify!($ty), "` in ", $order, " byte order.")]
+            pub fn $name(&mut self, value: $ty) -> Result<(), AllocError> {
+                self.write_bytes(&value.$to_bytes())
+            }
+        )*
+    };
+}
+
+impl BinaryWriter {
+    /// Returns an empty writer, with room for 64 bytes.
+    pub fn new() -> Result<Self, AllocError> {
+        BinaryWriter::with_capacity(64)
+    }
+
+    /// Returns an empty writer, with room for `capacity` bytes.
+    pub fn with_capacity(capacity: usize) -> Result<Self, AllocError> {
+        Ok(BinaryWriter {
+            binary: OwnedBinary::try_new(capacity)?,
+            len: 0,
+        })
+    }
+
+    /// Returns the number of bytes written.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether no bytes have been written.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bytes that can be written without growing the binary.
+    pub fn capacity(&self) -> usize {
+        self.binary.len()
+    }
+
+    /// Returns the bytes written.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.binary.as_slice()[..self.len]
+    }
+
+    /// Makes room for at least `additional` more bytes, doubling the capacity at least.
+    pub fn reserve(&mut self, additional: usize) -> Result<(), AllocError> {
+        let needed = self
+            .len
+            .checked_add(additional)
+            .ok_or(AllocError { size: usize::MAX })?;
+        if needed <= self.capacity() {
+            return Ok(());
+        }
+        let size = needed.max(self.capacity().saturating_mul(2));
+        if self.binary.realloc(size) {
+            Ok(())
+        } else {
+            Err(AllocError { size })
+        }
+    }
+
+    /// Writes `bytes`.
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), AllocError> {
+        self.reserve(bytes.len())?;
+        self.binary.as_mut_slice()[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+        Ok(())
+    }
+
+    /// Writes a `u8`.
+    pub fn write_u8(&mut self, value: u8) -> Result<(), AllocError> {
+        self.write_bytes(&[value])
+    }
+
+    /// Writes an `i8`.
+    pub fn write_i8(&mut self, value: i8) -> Result<(), AllocError> {
+        self.write_bytes(&value.to_be_bytes())
+    }
+
+    write_num! {
+        write_u16_be => u16, to_be_bytes, "big-endian";
+        write_u16_le => u16, to_le_bytes, "little-endian";
+        write_u32_be => u32, to_be_bytes, "big-endian";
+        write_u32_le => u32, to_le_bytes, "little-endian";
+        write_u64_be => u64, to_be_bytes, "big-endian";
+        write_u64_le => u64, to_le_bytes, "little-endian";
+        write_i16_be => i16, to_be_bytes, "big-endian";
+        write_i16_le => i16, to_le_bytes, "little-endian";
+        write_i32_be => i32, to_be_bytes, "big-endian";
+        write_i32_le => i32, to_le_bytes, "little-endian";
+        write_i64_be => i64, to_be_bytes, "big-endian";
+        write_i64_le => i64, to_le_bytes, "little-endian";
+        write_f32_be => f32, to_be_bytes, "big-endian";
+        write_f32_le => f32, to_le_bytes, "little-endian";
+        write_f64_be => f64, to_be_bytes, "big-endian";
+        write_f64_le => f64, to_le_bytes, "little-endian";
+    }
+
+    /// Writes an unsigned LEB128 varint, as read by `BinaryReader::read_varint`.
+    pub fn write_varint(&mut self, mut value: u64) -> Result<(), AllocError> {
+        let mut bytes = [0; 10];
+        let mut len = 0;
+        loop {
+            let group = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                bytes[len] = group;
+                len += 1;
+                break;
+            }
+            bytes[len] = group | 0x80;
+            len += 1;
+        }
+        self.write_bytes(&bytes[..len])
+    }
+
+    /// Writes a signed varint in zig-zag encoding, as read by `BinaryReader::read_zigzag`.
+    pub fn write_zigzag(&mut self, value: i64) -> Result<(), AllocError> {
+        self.write_varint(((value << 1) ^ (value >> 63)) as u64)
+    }
+
+    /// Writes `bytes` prefixed with their length as a `u8`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::BadArg` if `bytes` is longer than 255 bytes.
+    pub fn write_u8_prefixed(&mut self, bytes: &[u8]) -> NifResult<()> {
+        let len = u8::try_from(bytes.len()).map_err(|_| Error::BadArg)?;
+        self.write_u8(len)?;
+        Ok(self.write_bytes(bytes)?)
+    }
+
+    /// Writes `bytes` prefixed with their length as a big-endian `u16`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::BadArg` if `bytes` is longer than 65535 bytes.
+    pub fn write_u16_be_prefixed(&mut self, bytes: &[u8]) -> NifResult<()> {
+        let len = u16::try_from(bytes.len()).map_err(|_| Error::BadArg)?;
+        self.write_u16_be(len)?;
+        Ok(self.write_bytes(bytes)?)
+    }
+
+    /// Writes `bytes` prefixed with their length as a big-endian `u32`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::BadArg` if `bytes` is 4 GiB or longer.
+    pub fn write_u32_be_prefixed(&mut self, bytes: &[u8]) -> NifResult<()> {
+        let len = u32::try_from(bytes.len()).map_err(|_| Error::BadArg)?;
+        self.write_u32_be(len)?;
+        Ok(self.write_bytes(bytes)?)
+    }
+
+    /// Writes `bytes` prefixed with their length as a varint.
+    pub fn write_varint_prefixed(&mut self, bytes: &[u8]) -> Result<(), AllocError> {
+        self.write_varint(bytes.len() as u64)?;
+        self.write_bytes(bytes)
+    }
+
+    /// Shrinks the binary to the bytes written, and returns it.
+    pub fn into_owned(mut self) -> Result<OwnedBinary, AllocError> {
+        if self.len < self.capacity() && !self.binary.realloc(self.len) {
+            return Err(AllocError { size: self.len });
+        }
+        Ok(self.binary)
+    }
+
+    /// Shrinks the binary to the bytes written, and hands it over to `env`.
+    pub fn finish(self, env: Env) -> Result<Binary, AllocError> {
+        Ok(self.into_owned()?.release(env))
+    }
+}
+
+impl io::Write for BinaryWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_bytes(buf)
+            .map_err(|err| io::Error::new(io::ErrorKind::OutOfMemory, err))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
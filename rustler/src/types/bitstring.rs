@@ -0,0 +1,195 @@
+//! Bitstrings, whose size in bits is not necessarily a multiple of 8.
+//!
+//! `Binary` can only hold byte-aligned binaries, since `enif_inspect_binary` rejects other
+//! bitstrings. `Bitstring` accepts both: byte-aligned binaries are borrowed, and other bitstrings
+//! are copied out of the term through the External Term Format, as the NIF API has no other way
+//! to read them.
+//!
+//! Bits are numbered from the most significant bit of the first byte, like in Erlang's bit
+//! syntax, so the bits of `<<0b101::3>>` are `true`, `false`, `true`.
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn flags(bits: Bitstring) -> Vec<bool> {
+//!     (0..bits.bit_len()).map(|i| bits.get(i).unwrap()).collect()
+//! }
+//! ```
+
+use crate::dynamic::{get_type, TermType};
+use crate::types::binary::{Binary, NewBinary};
+use crate::{Decoder, Encoder, Env, Error, NifResult, Term};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+/// The `VERSION_MAGIC` and `BIT_BINARY_EXT` tags of the External Term Format.
+const VERSION_MAGIC: u8 = 131;
+const BIT_BINARY_EXT: u8 = 77;
+
+/// A sequence of bits, stored as `bit_len` bits starting `bit_offset` bits into `data`.
+#[derive(Clone, Debug)]
+pub struct Bitstring<'a> {
+    data: Cow<'a, [u8]>,
+    bit_offset: usize,
+    bit_len: usize,
+}
+
+impl<'a> Bitstring<'a> {
+    /// Creates a `Bitstring` from `term`.
+    ///
+    /// # Errors
+    ///
+    /// If `term` is not a bitstring, an error will be returned.
+    pub fn from_term(term: Term<'a>) -> NifResult<Self> {
+        if let Ok(binary) = Binary::from_term(term) {
+            let data = binary.as_slice();
+            return Ok(Bitstring {
+                data: Cow::Borrowed(data),
+                bit_offset: 0,
+                bit_len: data.len() * 8,
+            });
+        }
+
+        // Bitstrings are the only terms with no `TermType`, so other terms are rejected without
+        // encoding them.
+        if get_type(term) != TermType::Unknown {
+            return Err(Error::BadArg);
+        }
+        let etf = term.to_binary();
+        match etf.as_slice() {
+            [VERSION_MAGIC, BIT_BINARY_EXT, l0, l1, l2, l3, bits, data @ ..] => {
+                let len = u32::from_be_bytes([*l0, *l1, *l2, *l3]) as usize;
+                if data.len() != len || *bits == 0 || *bits > 8 || len == 0 {
+                    return Err(Error::BadArg);
+                }
+                Ok(Bitstring {
+                    data: Cow::Owned(data.to_vec()),
+                    bit_offset: 0,
+                    bit_len: (len - 1) * 8 + *bits as usize,
+                })
+            }
+            _ => Err(Error::BadArg),
+        }
+    }
+
+    /// Returns a bitstring of the `bit_len` bits of `bytes` starting `bit_offset` bits in, or
+    /// `None` if they are out of bounds.
+    pub fn from_parts(bytes: &'a [u8], bit_offset: usize, bit_len: usize) -> Option<Self> {
+        let end = bit_offset.checked_add(bit_len)?;
+        if end > bytes.len() * 8 {
+            return None;
+        }
+        Some(Bitstring {
+            data: Cow::Borrowed(bytes),
+            bit_offset,
+            bit_len,
+        })
+    }
+
+    /// Returns the bytes the bits are stored in, the offset of the first bit in them, and the
+    /// number of bits.
+    pub fn as_parts(&self) -> (&[u8], usize, usize) {
+        (&self.data, self.bit_offset, self.bit_len)
+    }
+
+    /// Returns the number of bits.
+    pub fn bit_len(&self) -> usize {
+        self.bit_len
+    }
+
+    /// Returns whether the number of bits is a multiple of 8, making this a binary.
+    pub fn is_binary(&self) -> bool {
+        self.bit_len.is_multiple_of(8)
+    }
+
+    /// Returns the bit at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<bool> {
+        if index >= self.bit_len {
+            return None;
+        }
+        let bit = self.bit_offset + index;
+        Some(self.data[bit / 8] & (0x80 >> (bit % 8)) != 0)
+    }
+
+    /// Returns the `bit_len` bits starting at `bit_start`, without copying them, or `None` if
+    /// they are out of bounds.
+    pub fn slice(&self, bit_start: usize, bit_len: usize) -> Option<Bitstring<'_>> {
+        let end = bit_start.checked_add(bit_len)?;
+        if end > self.bit_len {
+            return None;
+        }
+        Bitstring::from_parts(&self.data, self.bit_offset + bit_start, bit_len)
+    }
+
+    /// Returns the bits packed from the most significant bit of the first byte, with the unused
+    /// bits of the last byte set to 0.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let shift = self.bit_offset % 8;
+        let start = self.bit_offset / 8;
+        let len = self.bit_len.div_ceil(8);
+        let mut bytes: Vec<u8> = (0..len)
+            .map(|i| {
+                let high = self.data[start + i] << shift;
+                let low = match self.data.get(start + i + 1) {
+                    Some(next) if shift > 0 => next >> (8 - shift),
+                    _ => 0,
+                };
+                high | low
+            })
+            .collect();
+        if let Some(last) = bytes.last_mut() {
+            let used = self.bit_len % 8;
+            if used > 0 {
+                *last &= 0xff << (8 - used);
+            }
+        }
+        bytes
+    }
+
+    /// Copies the bits, to keep them beyond the lifetime of the term they were decoded from.
+    pub fn into_owned(self) -> Bitstring<'static> {
+        Bitstring {
+            data: Cow::Owned(self.to_bytes()),
+            bit_offset: 0,
+            bit_len: self.bit_len,
+        }
+    }
+}
+
+impl<'a> PartialEq for Bitstring<'a> {
+    fn eq(&self, other: &Bitstring) -> bool {
+        self.bit_len == other.bit_len && self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl<'a> Eq for Bitstring<'a> {}
+
+impl<'a> Decoder<'a> for Bitstring<'a> {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        Bitstring::from_term(term)
+    }
+}
+
+/// Byte-aligned bitstrings are encoded as binaries. Others are built by decoding them from the
+/// External Term Format.
+impl<'a> Encoder for Bitstring<'a> {
+    fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
+        let bytes = self.to_bytes();
+        if self.is_binary() {
+            let mut binary = NewBinary::new(env, bytes.len());
+            binary.as_mut_slice().copy_from_slice(&bytes);
+            return binary.into();
+        }
+
+        let len = u32::try_from(bytes.len()).expect("bitstring too large for the term format");
+        let mut etf = Vec::with_capacity(bytes.len() + 7);
+        etf.push(VERSION_MAGIC);
+        etf.push(BIT_BINARY_EXT);
+        etf.extend_from_slice(&len.to_be_bytes());
+        etf.push((self.bit_len % 8) as u8);
+        etf.extend_from_slice(&bytes);
+        let (term, _) = env
+            .binary_to_term(&etf)
+            .expect("the VM rejected a bitstring in the term format");
+        term
+    }
+}
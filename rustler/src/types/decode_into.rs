@@ -0,0 +1,77 @@
+//! Decoding into existing buffers.
+//!
+//! `Decoder` returns new values, so decoding a list or a string allocates on every call.
+//! `DecodeInto` fills a buffer provided by the caller instead, keeping its capacity, which lets
+//! hot NIFs reuse their allocations between calls:
+//!
+//! ```ignore
+//! thread_local! {
+//!     static SCRATCH: RefCell<Vec<f64>> = const { RefCell::new(Vec::new()) };
+//! }
+//!
+//! #[rustler::nif]
+//! fn mean(samples: Term) -> NifResult<f64> {
+//!     SCRATCH.with(|scratch| {
+//!         let mut scratch = scratch.borrow_mut();
+//!         samples.decode_into(&mut *scratch)?;
+//!         Ok(scratch.iter().sum::<f64>() / scratch.len() as f64)
+//!     })
+//! }
+//! ```
+//!
+//! When decoding fails, the content of the buffer is unspecified, but it stays valid and can be
+//! reused.
+
+use crate::types::binary::OwnedBinary;
+use crate::{Decoder, ListIterator, NifResult, Term};
+
+/// A type that can be decoded into an existing value, replacing its content.
+pub trait DecodeInto<'a> {
+    fn decode_into(&mut self, term: Term<'a>) -> NifResult<()>;
+}
+
+impl<'a, T> DecodeInto<'a> for Vec<T>
+where
+    T: Decoder<'a>,
+{
+    fn decode_into(&mut self, term: Term<'a>) -> NifResult<()> {
+        let iter: ListIterator = term.decode()?;
+        self.clear();
+        for element in iter {
+            self.push(element.decode()?);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> DecodeInto<'a> for String {
+    fn decode_into(&mut self, term: Term<'a>) -> NifResult<()> {
+        let string = <&str as Decoder>::decode(term)?;
+        self.clear();
+        self.push_str(string);
+        Ok(())
+    }
+}
+
+/// Binaries are resized with `OwnedBinary::realloc_or_copy`, which keeps the allocation when the
+/// size doesn't change.
+impl<'a> DecodeInto<'a> for OwnedBinary {
+    fn decode_into(&mut self, term: Term<'a>) -> NifResult<()> {
+        let binary = term.decode_as_binary()?;
+        if self.len() != binary.len() {
+            self.realloc_or_copy(binary.len());
+        }
+        self.as_mut_slice().copy_from_slice(binary.as_slice());
+        Ok(())
+    }
+}
+
+impl<'a> Term<'a> {
+    /// Decodes the term into `target`, replacing its content. See `DecodeInto`.
+    pub fn decode_into<T>(self, target: &mut T) -> NifResult<()>
+    where
+        T: DecodeInto<'a>,
+    {
+        target.decode_into(self)
+    }
+}
@@ -0,0 +1,462 @@
+//! Adapters for structs of the Elixir standard library.
+//!
+//! The fields of these structs are internal to Elixir and changed between versions: `Range`
+//! gained a `step` in Elixir 1.12, and the layout of `MapSet` and `Regex` is not documented. The
+//! types of this module decode every known layout and encode the current one, so NIFs don't have
+//! to depend on these details:
+//!
+//! * `MapSet<T>` decodes a `MapSet` into its elements, in map order. `HashSet<T>` and
+//!   `BTreeSet<T>` are encoded and decoded as `MapSet`s too.
+//! * `Range` decodes a range with or without a step. Ranges without a step are decreasing when
+//!   `first > last`, like they were before Elixir 1.12. `RangeInclusive<T>` decodes the `first`
+//!   and `last` of a range, whatever its step, and is encoded with a `step` of 1, unless it is
+//!   encoded with an `EncodingProfile` that has `RangeStyle::Unstepped`.
+//! * `Uri` decodes a `URI` struct. The deprecated `authority` field is ignored, and set to `nil`
+//!   when encoding.
+//! * `RegexSource` decodes the source and options of a `Regex`. A compiled regex can't be built
+//!   by a NIF, so it is encoded as a `{source, options}` tuple, which `Regex.compile!/2` accepts:
+//!
+//! ```elixir
+//! {source, opts} = MyNif.simplify(~r/ab+c/i)
+//! Regex.compile!(source, opts)
+//! ```
+
+use super::atom;
+use super::elixir_struct::{get_ex_struct_name, make_ex_struct};
+use crate::profile::RangeStyle;
+use crate::{Atom, Decoder, Encoder, EncodingProfile, Env, Error, MapIterator, NifResult, Term};
+use std::collections::{BTreeSet, HashSet};
+use std::convert::TryFrom;
+use std::hash::Hash;
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut, RangeInclusive};
+
+mod atoms {
+    crate::atoms! {
+        map_set = "Elixir.MapSet",
+        range = "Elixir.Range",
+        uri = "Elixir.URI",
+        regex = "Elixir.Regex",
+        map,
+        version,
+        first,
+        last,
+        step,
+        scheme,
+        userinfo,
+        host,
+        port,
+        path,
+        query,
+        fragment,
+        authority,
+        source,
+        opts,
+        caseless,
+        unicode,
+        ucp,
+        dotall,
+        multiline,
+        extended,
+        firstline,
+        ungreedy,
+        export,
+    }
+}
+
+/// Checks that `term` is a struct of `module`.
+pub(super) fn check_struct(term: Term, module: Atom) -> NifResult<()> {
+    if get_ex_struct_name(term)? == module {
+        Ok(())
+    } else {
+        Err(Error::BadArg)
+    }
+}
+
+/// Returns the value of the `field` of a struct, or `None` if it is missing or `nil`.
+fn optional_field<'a, T: Decoder<'a>>(term: Term<'a>, field: Atom) -> NifResult<Option<T>> {
+    match term.map_get(field.encode(term.get_env())) {
+        Ok(value) if atom::nil() == value => Ok(None),
+        Ok(value) => value.decode().map(Some),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Builds a struct of `module` with `fields`.
+pub(super) fn make_struct<'a>(env: Env<'a>, module: &str, fields: &[(Atom, Term<'a>)]) -> Term<'a> {
+    let mut map = make_ex_struct(env, module).expect("struct modules are valid atoms");
+    for (field, value) in fields {
+        map = map.map_put(field.encode(env), *value).unwrap();
+    }
+    map
+}
+
+/// The elements of a `MapSet`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MapSet<T>(pub Vec<T>);
+
+impl<T> MapSet<T> {
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<T> From<Vec<T>> for MapSet<T> {
+    fn from(elements: Vec<T>) -> Self {
+        MapSet(elements)
+    }
+}
+
+impl<T> FromIterator<T> for MapSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        MapSet(iter.into_iter().collect())
+    }
+}
+
+impl<T> Deref for MapSet<T> {
+    type Target = Vec<T>;
+    fn deref(&self) -> &Vec<T> {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for MapSet<T> {
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        &mut self.0
+    }
+}
+
+/// The elements are the keys of the `map` field, whatever their values.
+impl<'a, T> Decoder<'a> for MapSet<T>
+where
+    T: Decoder<'a>,
+{
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let mut elements = Vec::new();
+        decode_map_set(term, |element| elements.push(element))?;
+        Ok(MapSet(elements))
+    }
+}
+
+/// Encodes a version 2 `MapSet`, whose elements are mapped to `[]`. Duplicate elements are
+/// merged.
+impl<T> Encoder for MapSet<T>
+where
+    T: Encoder,
+{
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        encode_map_set(env, self.0.iter())
+    }
+}
+
+/// Decodes a `MapSet`, like `MapSet<T>`.
+impl<'a, T> Decoder<'a> for HashSet<T>
+where
+    T: Decoder<'a> + Eq + Hash,
+{
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let mut elements = HashSet::new();
+        decode_map_set(term, |element| {
+            elements.insert(element);
+        })?;
+        Ok(elements)
+    }
+}
+
+/// Encodes a `MapSet`, like `MapSet<T>`.
+impl<T> Encoder for HashSet<T>
+where
+    T: Encoder + Eq + Hash,
+{
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        encode_map_set(env, self.iter())
+    }
+}
+
+/// Decodes a `MapSet`, like `MapSet<T>`.
+impl<'a, T> Decoder<'a> for BTreeSet<T>
+where
+    T: Decoder<'a> + Ord,
+{
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let mut elements = BTreeSet::new();
+        decode_map_set(term, |element| {
+            elements.insert(element);
+        })?;
+        Ok(elements)
+    }
+}
+
+/// Encodes a `MapSet`, like `MapSet<T>`.
+impl<T> Encoder for BTreeSet<T>
+where
+    T: Encoder + Ord,
+{
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        encode_map_set(env, self.iter())
+    }
+}
+
+/// Decodes the elements of a `MapSet`, passing each of them to `insert`.
+fn decode_map_set<'a, T, F>(term: Term<'a>, mut insert: F) -> NifResult<()>
+where
+    T: Decoder<'a>,
+    F: FnMut(T),
+{
+    check_struct(term, atoms::map_set())?;
+    let map = term.map_get(atoms::map().encode(term.get_env()))?;
+    let iter = MapIterator::new(map).ok_or(Error::BadArg)?;
+    for (element, _) in iter {
+        insert(element.decode()?);
+    }
+    Ok(())
+}
+
+fn encode_map_set<'a, 'b, T, I>(env: Env<'a>, elements: I) -> Term<'a>
+where
+    T: Encoder + 'b,
+    I: Iterator<Item = &'b T>,
+{
+    let empty: Vec<Term<'a>> = Vec::new();
+    let empty = empty.encode(env);
+    let map = elements.fold(Term::map_new(env), |map, element| {
+        map.map_put(element.encode(env), empty).unwrap()
+    });
+    make_struct(
+        env,
+        "Elixir.MapSet",
+        &[(atoms::map(), map), (atoms::version(), 2.encode(env))],
+    )
+}
+
+/// A `Range` of integers, from `first` to `last` by `step`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Range {
+    pub first: i64,
+    pub last: i64,
+    pub step: i64,
+}
+
+impl Range {
+    /// Returns the integers of the range, like `Enum.to_list/1`.
+    pub fn iter(&self) -> impl Iterator<Item = i64> {
+        let Range { first, last, step } = *self;
+        let mut next = Some(first);
+        std::iter::from_fn(move || {
+            let current = next?;
+            let in_range = (step > 0 && current <= last) || (step < 0 && current >= last);
+            if !in_range {
+                return None;
+            }
+            next = current.checked_add(step);
+            Some(current)
+        })
+    }
+}
+
+impl<'a> Decoder<'a> for Range {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        check_struct(term, atoms::range())?;
+        let env = term.get_env();
+        let first: i64 = term.map_get(atoms::first().encode(env))?.decode()?;
+        let last: i64 = term.map_get(atoms::last().encode(env))?.decode()?;
+        let step = match optional_field(term, atoms::step())? {
+            Some(step) => step,
+            None if first <= last => 1,
+            None => -1,
+        };
+        Ok(Range { first, last, step })
+    }
+}
+
+impl Encoder for Range {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        make_struct(
+            env,
+            "Elixir.Range",
+            &[
+                (atoms::first(), self.first.encode(env)),
+                (atoms::last(), self.last.encode(env)),
+                (atoms::step(), self.step.encode(env)),
+            ],
+        )
+    }
+}
+
+impl From<RangeInclusive<i64>> for Range {
+    fn from(range: RangeInclusive<i64>) -> Self {
+        Range {
+            first: *range.start(),
+            last: *range.end(),
+            step: 1,
+        }
+    }
+}
+
+/// Fails for ranges with a step other than 1.
+impl TryFrom<Range> for RangeInclusive<i64> {
+    type Error = Error;
+
+    fn try_from(range: Range) -> Result<Self, Self::Error> {
+        if range.step == 1 {
+            Ok(range.first..=range.last)
+        } else {
+            Err(Error::BadArg)
+        }
+    }
+}
+
+/// Decodes `first..=last`, ignoring the step, so a decreasing range decodes to an empty
+/// `RangeInclusive`. Decode a `Range` to take the step into account.
+impl<'a, T> Decoder<'a> for RangeInclusive<T>
+where
+    T: Decoder<'a>,
+{
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        check_struct(term, atoms::range())?;
+        let env = term.get_env();
+        let first = term.map_get(atoms::first().encode(env))?.decode::<T>()?;
+        let last = term.map_get(atoms::last().encode(env))?.decode::<T>()?;
+        Ok(first..=last)
+    }
+}
+
+impl<T> Encoder for RangeInclusive<T>
+where
+    T: Encoder,
+{
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        self.encode_with(env, EncodingProfile::ELIXIR)
+    }
+
+    fn encode_with<'a>(&self, env: Env<'a>, profile: EncodingProfile) -> Term<'a> {
+        let first = (atoms::first(), self.start().encode_with(env, profile));
+        let last = (atoms::last(), self.end().encode_with(env, profile));
+        match profile.ranges() {
+            RangeStyle::Stepped => make_struct(
+                env,
+                "Elixir.Range",
+                &[first, last, (atoms::step(), 1.encode(env))],
+            ),
+            RangeStyle::Unstepped => make_struct(env, "Elixir.Range", &[first, last]),
+        }
+    }
+}
+
+/// The components of a `URI`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Uri {
+    pub scheme: Option<String>,
+    pub userinfo: Option<String>,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub path: Option<String>,
+    pub query: Option<String>,
+    pub fragment: Option<String>,
+}
+
+impl<'a> Decoder<'a> for Uri {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        check_struct(term, atoms::uri())?;
+        Ok(Uri {
+            scheme: optional_field(term, atoms::scheme())?,
+            userinfo: optional_field(term, atoms::userinfo())?,
+            host: optional_field(term, atoms::host())?,
+            port: optional_field(term, atoms::port())?,
+            path: optional_field(term, atoms::path())?,
+            query: optional_field(term, atoms::query())?,
+            fragment: optional_field(term, atoms::fragment())?,
+        })
+    }
+}
+
+impl Encoder for Uri {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        let nil = atom::nil().encode(env);
+        let field = |value: &Option<String>| match value {
+            Some(value) => value.encode(env),
+            None => nil,
+        };
+        make_struct(
+            env,
+            "Elixir.URI",
+            &[
+                (atoms::scheme(), field(&self.scheme)),
+                (atoms::userinfo(), field(&self.userinfo)),
+                (atoms::host(), field(&self.host)),
+                (
+                    atoms::port(),
+                    self.port.map_or(nil, |port| port.encode(env)),
+                ),
+                (atoms::path(), field(&self.path)),
+                (atoms::query(), field(&self.query)),
+                (atoms::fragment(), field(&self.fragment)),
+                (atoms::authority(), nil),
+            ],
+        )
+    }
+}
+
+/// The source and options of a `Regex`, with options as the letters `Regex.compile/2` accepts,
+/// like `"iu"`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegexSource {
+    pub source: String,
+    pub opts: String,
+}
+
+/// Returns the letter of a regex option, for versions that keep options as atoms.
+fn option_letter(option: Atom) -> Option<char> {
+    let letters = [
+        (atoms::caseless(), 'i'),
+        (atoms::unicode(), 'u'),
+        (atoms::ucp(), 'u'),
+        (atoms::dotall(), 's'),
+        (atoms::multiline(), 'm'),
+        (atoms::extended(), 'x'),
+        (atoms::firstline(), 'f'),
+        (atoms::ungreedy(), 'U'),
+        (atoms::export(), 'E'),
+    ];
+    letters
+        .iter()
+        .find(|(atom, _)| *atom == option)
+        .map(|(_, letter)| *letter)
+}
+
+/// Decodes a `Regex`, with options as a string or a list of atoms, or a `{source, options}`
+/// tuple.
+impl<'a> Decoder<'a> for RegexSource {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        if let Ok((source, opts)) = term.decode::<(String, String)>() {
+            return Ok(RegexSource { source, opts });
+        }
+
+        check_struct(term, atoms::regex())?;
+        let env = term.get_env();
+        let source: String = term.map_get(atoms::source().encode(env))?.decode()?;
+        let opts_term = term.map_get(atoms::opts().encode(env))?;
+        let opts = match opts_term.decode::<String>() {
+            Ok(opts) => opts,
+            Err(_) => {
+                let mut opts = String::new();
+                for option in opts_term.decode::<Vec<Atom>>()? {
+                    match option_letter(option) {
+                        Some(letter) if !opts.contains(letter) => opts.push(letter),
+                        Some(_) => {}
+                        None => return Err(Error::BadArg),
+                    }
+                }
+                opts
+            }
+        };
+        Ok(RegexSource { source, opts })
+    }
+}
+
+/// Encodes a `{source, options}` tuple, to compile with `Regex.compile!/2`.
+impl Encoder for RegexSource {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        (self.source.as_str(), self.opts.as_str()).encode(env)
+    }
+}
@@ -0,0 +1,256 @@
+//! Encoding and decoding of OTP's `gb_trees` and `gb_sets`.
+//!
+//! Both are represented as a tuple `{size, tree}`, where `tree` is either `nil` or a node:
+//! `{key, value, smaller, bigger}` for `gb_trees`, and `{key, smaller, bigger}` for `gb_sets`.
+//!
+//! `GbTreeIterator` and `GbSetIterator` decode these terms into iterators over their items, in
+//! key order, without decoding the items themselves. `GbTree<K, V>` and `GbSet<T>` decode and
+//! encode the items, building balanced trees like `:gb_trees.from_orddict/1` and
+//! `:gb_sets.from_ordset/1` do:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn prices(tree: GbTreeIterator) -> NifResult<GbTree<String, u64>> {
+//!     let mut prices = Vec::new();
+//!     for (key, value) in tree {
+//!         prices.push((key.decode()?, value.decode()?));
+//!     }
+//!     Ok(GbTree(prices))
+//! }
+//! ```
+//!
+//! The items of a `GbTree` or `GbSet` must be sorted in Erlang term order, without duplicate
+//! keys, when it is encoded. This is not checked, and the `gb_trees` and `gb_sets` functions
+//! will misbehave on a tree built from unsorted items.
+
+use super::atom;
+use super::tuple::{get_tuple, make_tuple};
+use crate::{Decoder, Encoder, Env, Error, NifResult, Term};
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut};
+
+/// Collects the nodes of `{size, tree}` in key order. Nodes are tuples of `arity` elements, the
+/// last two being the smaller and bigger subtrees.
+fn collect_nodes(term: Term, arity: usize) -> NifResult<Vec<Vec<Term>>> {
+    let (size, tree): (usize, Term) = term.decode()?;
+    let nil = atom::nil().to_term(term.get_env());
+
+    let mut nodes = Vec::new();
+    let mut stack: Vec<Vec<Term>> = Vec::new();
+    let mut current = tree;
+    loop {
+        while current != nil {
+            let node = get_tuple(current)?;
+            if node.len() != arity {
+                return Err(Error::BadArg);
+            }
+            current = node[arity - 2];
+            stack.push(node);
+        }
+        match stack.pop() {
+            Some(node) => {
+                current = node[arity - 1];
+                nodes.push(node);
+                if nodes.len() > size {
+                    return Err(Error::BadArg);
+                }
+            }
+            None => break,
+        }
+    }
+
+    if nodes.len() != size {
+        return Err(Error::BadArg);
+    }
+    Ok(nodes)
+}
+
+/// Builds a balanced tree of `len` nodes, built by `node` from their index and subtrees.
+fn build_tree<'a, F>(env: Env<'a>, start: usize, len: usize, node: &F) -> Term<'a>
+where
+    F: Fn(usize, Term<'a>, Term<'a>) -> Term<'a>,
+{
+    if len == 0 {
+        return atom::nil().to_term(env);
+    }
+    let bigger_len = (len - 1) / 2;
+    let smaller_len = len - 1 - bigger_len;
+    let smaller = build_tree(env, start, smaller_len, node);
+    let bigger = build_tree(env, start + smaller_len + 1, bigger_len, node);
+    node(start + smaller_len, smaller, bigger)
+}
+
+/// The key and value terms of a `gb_trees` term, in key order.
+pub struct GbTreeIterator<'a> {
+    nodes: std::vec::IntoIter<Vec<Term<'a>>>,
+}
+
+impl<'a> Iterator for GbTreeIterator<'a> {
+    type Item = (Term<'a>, Term<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.nodes.next().map(|node| (node[0], node[1]))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.nodes.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for GbTreeIterator<'a> {}
+
+impl<'a> Decoder<'a> for GbTreeIterator<'a> {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        Ok(GbTreeIterator {
+            nodes: collect_nodes(term, 4)?.into_iter(),
+        })
+    }
+}
+
+/// The element terms of a `gb_sets` term, in order.
+pub struct GbSetIterator<'a> {
+    nodes: std::vec::IntoIter<Vec<Term<'a>>>,
+}
+
+impl<'a> Iterator for GbSetIterator<'a> {
+    type Item = Term<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.nodes.next().map(|node| node[0])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.nodes.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for GbSetIterator<'a> {}
+
+impl<'a> Decoder<'a> for GbSetIterator<'a> {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        Ok(GbSetIterator {
+            nodes: collect_nodes(term, 3)?.into_iter(),
+        })
+    }
+}
+
+/// The entries of a `gb_trees` term, sorted by key.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GbTree<K, V>(pub Vec<(K, V)>);
+
+impl<K, V> GbTree<K, V> {
+    pub fn into_inner(self) -> Vec<(K, V)> {
+        self.0
+    }
+}
+
+impl<K, V> From<Vec<(K, V)>> for GbTree<K, V> {
+    fn from(entries: Vec<(K, V)>) -> Self {
+        GbTree(entries)
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for GbTree<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        GbTree(iter.into_iter().collect())
+    }
+}
+
+impl<K, V> Deref for GbTree<K, V> {
+    type Target = Vec<(K, V)>;
+    fn deref(&self) -> &Vec<(K, V)> {
+        &self.0
+    }
+}
+
+impl<K, V> DerefMut for GbTree<K, V> {
+    fn deref_mut(&mut self) -> &mut Vec<(K, V)> {
+        &mut self.0
+    }
+}
+
+impl<K, V> Encoder for GbTree<K, V>
+where
+    K: Encoder,
+    V: Encoder,
+{
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        let tree = build_tree(env, 0, self.0.len(), &|index, smaller, bigger| {
+            let (key, value) = &self.0[index];
+            make_tuple(env, &[key.encode(env), value.encode(env), smaller, bigger])
+        });
+        (self.0.len(), tree).encode(env)
+    }
+}
+
+impl<'a, K, V> Decoder<'a> for GbTree<K, V>
+where
+    K: Decoder<'a>,
+    V: Decoder<'a>,
+{
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let iter: GbTreeIterator<'a> = term.decode()?;
+        let entries: NifResult<Vec<(K, V)>> = iter
+            .map(|(key, value)| Ok((key.decode()?, value.decode()?)))
+            .collect();
+        Ok(GbTree(entries?))
+    }
+}
+
+/// The elements of a `gb_sets` term, sorted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GbSet<T>(pub Vec<T>);
+
+impl<T> GbSet<T> {
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<T> From<Vec<T>> for GbSet<T> {
+    fn from(elements: Vec<T>) -> Self {
+        GbSet(elements)
+    }
+}
+
+impl<T> FromIterator<T> for GbSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        GbSet(iter.into_iter().collect())
+    }
+}
+
+impl<T> Deref for GbSet<T> {
+    type Target = Vec<T>;
+    fn deref(&self) -> &Vec<T> {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for GbSet<T> {
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        &mut self.0
+    }
+}
+
+impl<T> Encoder for GbSet<T>
+where
+    T: Encoder,
+{
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        let tree = build_tree(env, 0, self.0.len(), &|index, smaller, bigger| {
+            make_tuple(env, &[self.0[index].encode(env), smaller, bigger])
+        });
+        (self.0.len(), tree).encode(env)
+    }
+}
+
+impl<'a, T> Decoder<'a> for GbSet<T>
+where
+    T: Decoder<'a>,
+{
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let iter: GbSetIterator<'a> = term.decode()?;
+        let elements: NifResult<Vec<T>> = iter.map(|element| element.decode()).collect();
+        Ok(GbSet(elements?))
+    }
+}
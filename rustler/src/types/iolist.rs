@@ -0,0 +1,127 @@
+//! Building iodata out of binaries.
+//!
+//! A `Vec<Binary>` is encoded as a flat list of binaries, which is already valid iodata, and so
+//! is a `Vec<OwnedBinary>` returned from a NIF, whose binaries are released without copying.
+//! `Iolist` builds nested iodata, so that framed output can be put together from existing
+//! binaries without copying them, and written as is with `:gen_tcp.send/2` or
+//! `IO.binwrite/2`:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn frame<'a>(env: Env<'a>, payloads: Vec<Binary<'a>>) -> Iolist<'a> {
+//!     let mut frame = Iolist::new();
+//!     for payload in payloads {
+//!         frame.push_bytes(env, &(payload.len() as u32).to_be_bytes());
+//!         frame.push_binary(payload);
+//!     }
+//!     frame
+//! }
+//! ```
+
+use crate::types::binary::{Binary, NewBinary, OwnedBinary};
+use crate::wrapper::{list, NIF_TERM};
+use crate::{Encoder, Env, Term};
+
+/// Nested iodata. See the module documentation.
+#[derive(Clone, Default)]
+pub struct Iolist<'a> {
+    elements: Vec<Element<'a>>,
+    size: usize,
+}
+
+#[derive(Clone)]
+enum Element<'a> {
+    Binary(Binary<'a>),
+    Byte(u8),
+    List(Iolist<'a>),
+}
+
+impl<'a> Iolist<'a> {
+    pub fn new() -> Self {
+        Iolist::default()
+    }
+
+    /// An empty `Iolist` with room for `capacity` elements.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Iolist {
+            elements: Vec::with_capacity(capacity),
+            size: 0,
+        }
+    }
+
+    /// An `Iolist` of the binaries `binaries`, released in `env`.
+    pub fn from_owned(env: Env<'a>, binaries: Vec<OwnedBinary>) -> Self {
+        let mut iolist = Iolist::with_capacity(binaries.len());
+        for binary in binaries {
+            iolist.push_owned(env, binary);
+        }
+        iolist
+    }
+
+    /// Appends `binary`, without copying it.
+    pub fn push_binary(&mut self, binary: Binary<'a>) {
+        self.size += binary.len();
+        self.elements.push(Element::Binary(binary));
+    }
+
+    /// Releases `binary` in `env` and appends it, without copying it.
+    pub fn push_owned(&mut self, env: Env<'a>, binary: OwnedBinary) {
+        self.push_binary(binary.release(env));
+    }
+
+    /// Appends a copy of `bytes`, as a single binary.
+    pub fn push_bytes(&mut self, env: Env<'a>, bytes: &[u8]) {
+        let mut binary = NewBinary::new(env, bytes.len());
+        binary.as_mut_slice().copy_from_slice(bytes);
+        self.push_binary(binary.into());
+    }
+
+    /// Appends a single byte, encoded as an integer.
+    pub fn push_byte(&mut self, byte: u8) {
+        self.size += 1;
+        self.elements.push(Element::Byte(byte));
+    }
+
+    /// Appends `iolist` as a nested list.
+    pub fn push_iolist(&mut self, iolist: Iolist<'a>) {
+        self.size += iolist.size;
+        self.elements.push(Element::List(iolist));
+    }
+
+    /// The number of bytes of the iodata, as returned by `:erlang.iolist_size/1`.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The number of elements of the outer list.
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+}
+
+impl<'a> From<Vec<Binary<'a>>> for Iolist<'a> {
+    fn from(binaries: Vec<Binary<'a>>) -> Self {
+        let size = binaries.iter().map(|binary| binary.len()).sum();
+        let elements = binaries.into_iter().map(Element::Binary).collect();
+        Iolist { elements, size }
+    }
+}
+
+impl<'a> Encoder for Iolist<'a> {
+    fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
+        let terms: Vec<NIF_TERM> = self
+            .elements
+            .iter()
+            .map(|element| match element {
+                Element::Binary(binary) => binary.to_term(env).as_c_arg(),
+                Element::Byte(byte) => byte.encode(env).as_c_arg(),
+                Element::List(iolist) => iolist.encode(env).as_c_arg(),
+            })
+            .collect();
+        unsafe { Term::new(env, list::make_list(env.as_c_arg(), &terms)) }
+    }
+}
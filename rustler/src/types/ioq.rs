@@ -0,0 +1,162 @@
+//! I/O queues, buffering bytes from binaries without copying them.
+//!
+//! `IoQueue` wraps an `ErlNifIOQueue`. Enqueued binaries are kept alive by reference counts, so
+//! data read from a socket can be buffered until a whole frame has arrived, and queued output
+//! can be written with `write_vectored` as the socket accepts it:
+//!
+//! ```ignore
+//! struct Connection {
+//!     output: Mutex<IoQueue>,
+//! }
+//!
+//! #[rustler::nif]
+//! fn send(env: Env, conn: ResourceArc<Connection>, data: Binary) -> NifResult<usize> {
+//!     let mut output = conn.output.lock().unwrap();
+//!     output.enqueue_binary(env, data)?;
+//!     Ok(output.len())
+//! }
+//! ```
+//!
+//! The queue is not synchronized, so a queue shared between processes must be behind a lock.
+
+use crate::types::binary::{Binary, OwnedBinary};
+use crate::types::iovec::IoVec;
+use crate::{Env, Error, NifResult, Term};
+use rustler_sys::{ErlNifIOQueue, ErlNifIOQueueOpts};
+use std::io::{self, IoSlice, Read, Write};
+use std::os::raw::c_int;
+use std::slice;
+
+/// A queue of bytes, stored as the segments of the binaries that were enqueued.
+pub struct IoQueue {
+    queue: *mut ErlNifIOQueue,
+}
+
+unsafe impl Send for IoQueue {}
+
+impl IoQueue {
+    pub fn new() -> Self {
+        let queue = unsafe { rustler_sys::enif_ioq_create(ErlNifIOQueueOpts::ERL_NIF_IOQ_NORMAL) };
+        // `enif_ioq_create` uses the VM allocator, which aborts instead of failing.
+        assert!(!queue.is_null(), "enif_ioq_create failed");
+        IoQueue { queue }
+    }
+
+    /// The number of bytes in the queue.
+    pub fn len(&self) -> usize {
+        unsafe { rustler_sys::enif_ioq_size(self.queue) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Enqueues `binary`, whose ownership is handed over to the queue.
+    pub fn enqueue_owned(&mut self, binary: OwnedBinary) {
+        let mut inner = binary.into_raw();
+        // Enqueueing a whole binary can't fail.
+        unsafe { rustler_sys::enif_ioq_enq_binary(self.queue, &mut inner, 0) };
+    }
+
+    /// Enqueues `binary`. Large binaries are shared with the term, small ones are copied.
+    pub fn enqueue_binary(&mut self, env: Env, binary: Binary) -> NifResult<()> {
+        let list = Term::list_new_empty(env).list_prepend(binary.to_term(env));
+        self.enqueue_iovec(&IoVec::from_term(list)?, 0)
+    }
+
+    /// Enqueues the segments of `iovec`, skipping their first `skip` bytes.
+    ///
+    /// # Errors
+    ///
+    /// If `skip` is larger than the size of `iovec`, an error will be returned.
+    pub fn enqueue_iovec(&mut self, iovec: &IoVec, skip: usize) -> NifResult<()> {
+        let success = unsafe { rustler_sys::enif_ioq_enqv(self.queue, iovec.as_c_arg(), skip) };
+        if success == 0 {
+            return Err(Error::BadArg);
+        }
+        Ok(())
+    }
+
+    /// Removes the first `count` bytes. Returns `false`, removing nothing, if the queue holds
+    /// fewer bytes.
+    pub fn dequeue(&mut self, count: usize) -> bool {
+        let mut size = 0;
+        unsafe { rustler_sys::enif_ioq_deq(self.queue, count, &mut size) != 0 }
+    }
+
+    /// Returns the segments of the queue, from the first one.
+    // `iov_len` is a `c_ulong` on Windows.
+    #[allow(clippy::unnecessary_cast)]
+    pub fn peek(&self) -> Vec<&[u8]> {
+        let mut len: c_int = 0;
+        let iov = unsafe { rustler_sys::enif_ioq_peek(self.queue, &mut len) };
+        if iov.is_null() || len <= 0 {
+            return Vec::new();
+        }
+        let iov = unsafe { slice::from_raw_parts(iov, len as usize) };
+        iov.iter()
+            .filter(|segment| segment.iov_len > 0)
+            .map(|segment| unsafe {
+                slice::from_raw_parts(segment.iov_base as *const u8, segment.iov_len as usize)
+            })
+            .collect()
+    }
+
+    /// Returns the segments of the queue as `IoSlice`s, for `Write::write_vectored`.
+    pub fn io_slices(&self) -> Vec<IoSlice<'_>> {
+        self.peek().into_iter().map(IoSlice::new).collect()
+    }
+
+    /// Returns the first segment of the queue as a binary, without removing it.
+    #[cfg(nif_version_2_14)]
+    pub fn peek_head<'a>(&self, env: Env<'a>) -> Option<Binary<'a>> {
+        let mut size = 0;
+        let mut head = 0;
+        let success = unsafe {
+            rustler_sys::enif_ioq_peek_head(env.as_c_arg(), self.queue, &mut size, &mut head)
+        };
+        if success == 0 {
+            return None;
+        }
+        Binary::from_term(unsafe { Term::new(env, head) }).ok()
+    }
+
+    /// Writes the queue to `writer` with one call to `write_vectored`, and removes the bytes that
+    /// were written. Returns their number.
+    pub fn write_to<W: Write + ?Sized>(&mut self, writer: &mut W) -> io::Result<usize> {
+        if self.is_empty() {
+            return Ok(0);
+        }
+        let written = writer.write_vectored(&self.io_slices())?;
+        self.dequeue(written);
+        Ok(written)
+    }
+}
+
+impl Default for IoQueue {
+    fn default() -> Self {
+        IoQueue::new()
+    }
+}
+
+impl Read for IoQueue {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut read = 0;
+        for segment in self.peek() {
+            let n = segment.len().min(buf.len() - read);
+            buf[read..read + n].copy_from_slice(&segment[..n]);
+            read += n;
+            if read == buf.len() {
+                break;
+            }
+        }
+        self.dequeue(read);
+        Ok(read)
+    }
+}
+
+impl Drop for IoQueue {
+    fn drop(&mut self) {
+        unsafe { rustler_sys::enif_ioq_destroy(self.queue) };
+    }
+}
@@ -0,0 +1,177 @@
+//! I/O vectors, reading a list of binaries without copying them into one binary.
+//!
+//! `IoVec` is backed by `enif_inspect_iovec`, which accepts a flat list of binaries. Unlike
+//! `Term::decode_as_binary`, which flattens an iolist into a new binary, the segments stay where
+//! they are, so they can be passed to vectored writes:
+//!
+//! ```ignore
+//! #[rustler::nif(schedule = "DirtyIo")]
+//! fn send(socket: ResourceArc<Socket>, data: IoVec) -> NifResult<()> {
+//!     let mut stream = socket.stream.lock().unwrap();
+//!     data.write_to(&mut *stream).map_err(|_| Error::BadArg)
+//! }
+//! ```
+//!
+//! Nested iolists are rejected. They can be converted with `:erlang.iolist_to_iovec/1` first,
+//! which only copies the small binaries.
+
+use crate::{Decoder, Error, NifResult, Term};
+use rustler_sys::ErlNifIOVec;
+use std::io::{self, IoSlice, Write};
+use std::{ptr, slice};
+
+/// The segments of a list of binaries.
+///
+/// The segments are borrowed from the binaries of the list and are valid as long as the term.
+pub struct IoVec<'a> {
+    iovec: *mut ErlNifIOVec,
+    tail: Term<'a>,
+}
+
+impl<'a> IoVec<'a> {
+    /// Creates an `IoVec` from a list of binaries.
+    ///
+    /// # Errors
+    ///
+    /// If `term` is not a proper list of binaries, an error will be returned.
+    pub fn from_term(term: Term<'a>) -> NifResult<Self> {
+        let iovec = Self::from_term_bounded(term, usize::MAX)?;
+        if !matches!(iovec.tail.list_length(), Ok(0)) {
+            return Err(Error::BadArg);
+        }
+        Ok(iovec)
+    }
+
+    /// Creates an `IoVec` from the first `max_elements` binaries of a list. The rest of the list
+    /// is returned by `tail`, so a long list can be processed in chunks.
+    ///
+    /// Some platforms may return more than `max_elements` segments.
+    ///
+    /// # Errors
+    ///
+    /// If `term` is not a list of binaries, an error will be returned.
+    pub fn from_term_bounded(term: Term<'a>, max_elements: usize) -> NifResult<Self> {
+        let env = term.get_env();
+        let mut tail = term.as_c_arg();
+        // With a null vector, the VM allocates one that is freed with the environment.
+        let mut iovec: *mut ErlNifIOVec = ptr::null_mut();
+        let success = unsafe {
+            rustler_sys::enif_inspect_iovec(
+                env.as_c_arg(),
+                max_elements,
+                term.as_c_arg(),
+                &mut tail,
+                &mut iovec,
+            )
+        };
+        if success == 0 || iovec.is_null() {
+            return Err(Error::BadArg);
+        }
+        Ok(IoVec {
+            iovec,
+            tail: unsafe { Term::new(env, tail) },
+        })
+    }
+
+    pub(crate) fn as_c_arg(&self) -> *mut ErlNifIOVec {
+        self.iovec
+    }
+
+    /// The part of the list that was not inspected, the empty list when all of it was.
+    pub fn tail(&self) -> Term<'a> {
+        self.tail
+    }
+
+    /// The number of segments.
+    pub fn len(&self) -> usize {
+        unsafe { (*self.iovec).iovcnt as usize }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The total size of the segments in bytes.
+    pub fn total_size(&self) -> usize {
+        unsafe { (*self.iovec).size }
+    }
+
+    /// Returns the segments.
+    // `iov_len` is a `c_ulong` on Windows.
+    #[allow(clippy::unnecessary_cast)]
+    pub fn slices(&self) -> Vec<&'a [u8]> {
+        let len = self.len();
+        if len == 0 {
+            return Vec::new();
+        }
+        let iov = unsafe { slice::from_raw_parts((*self.iovec).iov, len) };
+        iov.iter()
+            .map(|segment| {
+                if segment.iov_len == 0 {
+                    &[][..]
+                } else {
+                    unsafe {
+                        slice::from_raw_parts(
+                            segment.iov_base as *const u8,
+                            segment.iov_len as usize,
+                        )
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the segments as `IoSlice`s, for `Write::write_vectored`.
+    pub fn io_slices(&self) -> Vec<IoSlice<'a>> {
+        self.slices().into_iter().map(IoSlice::new).collect()
+    }
+
+    /// Writes all the segments to `writer`, with as few calls to `write_vectored` as it allows.
+    pub fn write_to<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        let mut segments: Vec<&[u8]> = self
+            .slices()
+            .into_iter()
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        let mut start = 0;
+        while start < segments.len() {
+            let io_slices: Vec<IoSlice> = segments[start..]
+                .iter()
+                .map(|segment| IoSlice::new(segment))
+                .collect();
+            match writer.write_vectored(&io_slices) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(mut written) => {
+                    while written > 0 {
+                        let segment = segments[start];
+                        if written >= segment.len() {
+                            written -= segment.len();
+                            start += 1;
+                        } else {
+                            segments[start] = &segment[written..];
+                            written = 0;
+                        }
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies the segments into one vector.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.total_size());
+        for segment in self.slices() {
+            bytes.extend_from_slice(segment);
+        }
+        bytes
+    }
+}
+
+impl<'a> Decoder<'a> for IoVec<'a> {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        IoVec::from_term(term)
+    }
+}
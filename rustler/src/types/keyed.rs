@@ -0,0 +1,91 @@
+//! Encoding collections of structs as maps keyed by one of their fields.
+//!
+//! A very common pattern in Elixir is to keep a collection of records in a map, indexed by
+//! one of the record's fields, e.g. `%{1 => %User{id: 1, ...}, 2 => %User{id: 2, ...}}`.
+//! `KeyedVec<T>` encodes a `Vec<T>` in this shape, and decodes such a map back into a `Vec<T>`.
+//!
+//! The key is provided by the `Keyed` trait, which is implemented by the `NifMap` and
+//! `NifStruct` derives when the struct is annotated with `#[rustler(key = "field")]`:
+//!
+//! ```ignore
+//! #[derive(NifMap)]
+//! #[rustler(key = "id")]
+//! struct User {
+//!     id: u32,
+//!     name: String,
+//! }
+//!
+//! #[rustler::nif]
+//! fn users() -> KeyedVec<User> {
+//!     KeyedVec(vec![User { id: 1, name: "Joe".to_string() }])
+//! }
+//! ```
+
+use super::map::map_new;
+use crate::{Decoder, Encoder, Env, Error, MapIterator, NifResult, Term};
+use std::ops::{Deref, DerefMut};
+
+/// A type that can be stored in a map under a key derived from its own value.
+pub trait Keyed {
+    /// Returns the term the value is stored under when encoded in a `KeyedVec`.
+    fn key<'a>(&self, env: Env<'a>) -> Term<'a>;
+}
+
+/// A `Vec<T>` that is encoded as a map from `T::key()` to `T`.
+///
+/// When decoding, the keys of the map are ignored and only the values are decoded, in map
+/// iteration order. If several elements share a key when encoding, the last one wins.
+pub struct KeyedVec<T>(pub Vec<T>);
+
+impl<T> KeyedVec<T> {
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<T> From<Vec<T>> for KeyedVec<T> {
+    fn from(vec: Vec<T>) -> Self {
+        KeyedVec(vec)
+    }
+}
+
+impl<T> Deref for KeyedVec<T> {
+    type Target = Vec<T>;
+    fn deref(&self) -> &Vec<T> {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for KeyedVec<T> {
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        &mut self.0
+    }
+}
+
+impl<T> Encoder for KeyedVec<T>
+where
+    T: Encoder + Keyed,
+{
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        self.0.iter().fold(map_new(env), |map, value| {
+            map.map_put(value.key(env), value.encode(env)).unwrap()
+        })
+    }
+}
+
+impl<'a, T> Decoder<'a> for KeyedVec<T>
+where
+    T: Decoder<'a> + Keyed,
+{
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let size = term.map_size()?;
+        let iter = MapIterator::new(term).ok_or(Error::BadArg)?;
+
+        let mut vec = Vec::with_capacity(size);
+        for (_key, value) in iter {
+            vec.push(value.decode()?);
+        }
+
+        Ok(KeyedVec(vec))
+    }
+}
@@ -0,0 +1,123 @@
+//! Keyword lists and proplists.
+//!
+//! Options are passed from Elixir as keyword lists, `[timeout: 5000, mode: :fast]`, and from
+//! Erlang as proplists, which may also hold bare atoms standing for `{atom, true}`, like
+//! `[verbose, {timeout, 5000}]`. `KeywordList` decodes both forms, keeping duplicate keys in
+//! order, and gives access to the values by key:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn connect(host: String, opts: KeywordList) -> NifResult<Connection> {
+//!     let timeout: u64 = opts.get_as(atoms::timeout())?.unwrap_or(5000);
+//!     let verbose = opts.has_key(atoms::verbose());
+//!     ...
+//! }
+//! ```
+//!
+//! `KeywordList` is encoded as a keyword list of `{key, value}` tuples. When every value has
+//! the same type and bare atoms are not expected, a `Vec<(Atom, T)>` decodes and encodes
+//! keyword lists as well.
+
+use super::atom::{self, Atom};
+use super::tuple::{get_tuple, make_tuple};
+use crate::{Decoder, Encoder, Env, Error, ListIterator, NifResult, Term};
+
+/// The entries of a keyword list or proplist, in order. See the module documentation.
+#[derive(Clone, Default)]
+pub struct KeywordList<'a>(pub Vec<(Atom, Term<'a>)>);
+
+impl<'a> KeywordList<'a> {
+    pub fn new() -> Self {
+        KeywordList(Vec::new())
+    }
+
+    pub fn into_inner(self) -> Vec<(Atom, Term<'a>)> {
+        self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterates over the entries, in order, including duplicate keys.
+    pub fn iter(&self) -> impl Iterator<Item = (Atom, Term<'a>)> + '_ {
+        self.0.iter().copied()
+    }
+
+    /// Returns whether `key` is in the list.
+    pub fn has_key(&self, key: Atom) -> bool {
+        self.0.iter().any(|(k, _)| *k == key)
+    }
+
+    /// Returns the value of the first entry for `key`, like `Keyword.get/2` and
+    /// `proplists:get_value/2`.
+    pub fn get(&self, key: Atom) -> Option<Term<'a>> {
+        self.0
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| *value)
+    }
+
+    /// Decodes the value of the first entry for `key`, or returns `None` when `key` is missing.
+    pub fn get_as<T>(&self, key: Atom) -> NifResult<Option<T>>
+    where
+        T: Decoder<'a>,
+    {
+        self.get(key).map(Term::decode).transpose()
+    }
+
+    /// Returns the values of all the entries for `key`, in order, like `Keyword.get_values/2`.
+    pub fn get_values(&self, key: Atom) -> Vec<Term<'a>> {
+        self.0
+            .iter()
+            .filter(|(k, _)| *k == key)
+            .map(|(_, value)| *value)
+            .collect()
+    }
+
+    /// Appends an entry, keeping any previous entry for `key`.
+    pub fn push<T: Encoder>(&mut self, env: Env<'a>, key: Atom, value: T) {
+        self.0.push((key, value.encode(env)));
+    }
+}
+
+impl<'a> From<Vec<(Atom, Term<'a>)>> for KeywordList<'a> {
+    fn from(entries: Vec<(Atom, Term<'a>)>) -> Self {
+        KeywordList(entries)
+    }
+}
+
+impl<'a> Encoder for KeywordList<'a> {
+    fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
+        let entries: Vec<Term<'b>> = self
+            .0
+            .iter()
+            .map(|(key, value)| make_tuple(env, &[key.encode(env), value.in_env(env)]))
+            .collect();
+        entries.encode(env)
+    }
+}
+
+impl<'a> Decoder<'a> for KeywordList<'a> {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let iter: ListIterator = term.decode()?;
+        let mut entries = Vec::with_capacity(term.list_length()?);
+
+        for item in iter {
+            if let Ok(key) = Atom::from_term(item) {
+                entries.push((key, atom::true_().encode(term.get_env())));
+                continue;
+            }
+            match get_tuple(item)?[..] {
+                [key, value] => entries.push((Atom::from_term(key)?, value)),
+                _ => return Err(Error::BadArg),
+            }
+        }
+
+        Ok(KeywordList(entries))
+    }
+}
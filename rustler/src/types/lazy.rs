@@ -0,0 +1,87 @@
+//! Decoding of arguments on first use.
+//!
+//! `Lazy<T>` keeps the term it was decoded from, and only decodes it as a `T` when the value is
+//! accessed. NIFs taking large arguments that are only used on some paths don't pay for decoding
+//! them on the others:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn insert(key: String, options: Lazy<Options>) -> NifResult<()> {
+//!     if cache::contains(&key) {
+//!         return Ok(());
+//!     }
+//!     store(key, options.get()?)
+//! }
+//! ```
+//!
+//! Since the decoding is deferred, an argument that can't be decoded as a `T` only results in an
+//! error when it is accessed.
+
+use crate::{Decoder, Encoder, Env, NifResult, Term};
+use std::cell::OnceCell;
+use std::fmt;
+
+/// A term decoded as a `T` on first access. See the module documentation.
+pub struct Lazy<'a, T> {
+    term: Term<'a>,
+    value: OnceCell<T>,
+}
+
+impl<'a, T: Decoder<'a>> Lazy<'a, T> {
+    pub fn new(term: Term<'a>) -> Self {
+        Lazy {
+            term,
+            value: OnceCell::new(),
+        }
+    }
+
+    /// Returns the value, decoding the term on the first call. Failures are not cached, so
+    /// every call decodes the term again until it succeeds.
+    pub fn get(&self) -> NifResult<&T> {
+        if let Some(value) = self.value.get() {
+            return Ok(value);
+        }
+        let value = self.term.decode()?;
+        Ok(self.value.get_or_init(|| value))
+    }
+
+    /// Returns the value, decoding the term if it wasn't accessed yet.
+    pub fn into_inner(self) -> NifResult<T> {
+        match self.value.into_inner() {
+            Some(value) => Ok(value),
+            None => self.term.decode(),
+        }
+    }
+
+    /// Returns whether the term was decoded.
+    pub fn is_decoded(&self) -> bool {
+        self.value.get().is_some()
+    }
+
+    /// Returns the term, without decoding it.
+    pub fn term(&self) -> Term<'a> {
+        self.term
+    }
+}
+
+impl<'a, T: Decoder<'a>> Decoder<'a> for Lazy<'a, T> {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        Ok(Lazy::new(term))
+    }
+}
+
+/// Encodes the original term, whether or not it was decoded.
+impl<'a, T> Encoder for Lazy<'a, T> {
+    fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
+        self.term.encode(env)
+    }
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for Lazy<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Lazy")
+            .field("term", &self.term)
+            .field("value", &self.value.get())
+            .finish()
+    }
+}
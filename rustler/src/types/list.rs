@@ -3,7 +3,7 @@
 //! Right now the only supported way to read lists are through the ListIterator.
 
 use crate::wrapper::{list, NIF_TERM};
-use crate::{Decoder, Encoder, Env, Error, NifResult, Term};
+use crate::{Decoder, Encoder, EncodingProfile, Env, Error, NifResult, Term};
 
 /// Enables iteration over the items in the list.
 ///
@@ -100,6 +100,10 @@ where
     fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
         self.as_slice().encode(env)
     }
+
+    fn encode_with<'b>(&self, env: Env<'b>, profile: EncodingProfile) -> Term<'b> {
+        self.as_slice().encode_with(env, profile)
+    }
 }
 
 impl<'a, T> Decoder<'a> for Vec<T>
@@ -107,9 +111,12 @@ where
     T: Decoder<'a>,
 {
     fn decode(term: Term<'a>) -> NifResult<Self> {
+        Self::decode_with(term, EncodingProfile::ELIXIR)
+    }
+
+    fn decode_with(term: Term<'a>, profile: EncodingProfile) -> NifResult<Self> {
         let iter: ListIterator = term.decode()?;
-        let res: NifResult<Self> = iter.map(|x| x.decode::<T>()).collect();
-        res
+        iter.map(|x| T::decode_with(x, profile)).collect()
     }
 }
 
@@ -118,8 +125,11 @@ where
     T: Encoder,
 {
     fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
-        let term_array: Vec<NIF_TERM> = self.iter().map(|x| x.encode(env).as_c_arg()).collect();
-        unsafe { Term::new(env, list::make_list(env.as_c_arg(), &term_array)) }
+        T::encode_slice(self, env)
+    }
+
+    fn encode_with<'b>(&self, env: Env<'b>, profile: EncodingProfile) -> Term<'b> {
+        T::encode_slice_with(self, env, profile)
     }
 }
 impl<'a, T> Encoder for &'a [T]
@@ -127,11 +137,21 @@ where
     T: Encoder,
 {
     fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
-        let term_array: Vec<NIF_TERM> = self.iter().map(|x| x.encode(env).as_c_arg()).collect();
-        unsafe { Term::new(env, list::make_list(env.as_c_arg(), &term_array)) }
+        T::encode_slice(self, env)
+    }
+
+    fn encode_with<'b>(&self, env: Env<'b>, profile: EncodingProfile) -> Term<'b> {
+        T::encode_slice_with(self, env, profile)
     }
 }
 
+/// Convert a slice of terms to an Erlang list. (To convert from a Rust slice or vector, use
+/// `Encoder` instead.)
+pub fn make_list<'a>(env: Env<'a>, terms: &[Term]) -> Term<'a> {
+    let c_terms: Vec<NIF_TERM> = terms.iter().map(|term| term.as_c_arg()).collect();
+    unsafe { Term::new(env, list::make_list(env.as_c_arg(), &c_terms)) }
+}
+
 /// ## List terms
 impl<'a> Term<'a> {
     /// Returns a new empty list.
@@ -1,8 +1,9 @@
+use crate::types::atom::Atom;
 use crate::wrapper::{pid, ErlNifPid};
 use crate::{Decoder, Encoder, Env, Error, NifResult, Term};
 use std::mem::MaybeUninit;
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq)]
 pub struct LocalPid {
     c: ErlNifPid,
 }
@@ -11,6 +12,10 @@ impl LocalPid {
     pub fn as_c_arg(&self) -> &ErlNifPid {
         &self.c
     }
+
+    pub(crate) fn from_c_arg(c: ErlNifPid) -> LocalPid {
+        LocalPid { c }
+    }
 }
 
 impl<'a> Decoder<'a> for LocalPid {
@@ -44,4 +49,27 @@ impl<'a> Env<'a> {
             c: unsafe { pid.assume_init() },
         }
     }
+
+    /// Returns the process registered as `name`, or `None` if there is none.
+    ///
+    /// Like `send`, this can be called with the environment of the calling process, or from a
+    /// thread that is not managed by the VM, with the environment of an `OwnedEnv`.
+    pub fn whereis_pid(self, name: &str) -> Option<LocalPid> {
+        // No process can be registered under an atom that does not exist.
+        let name = Atom::try_from_bytes(self, name.as_bytes()).ok()??;
+        let mut pid = MaybeUninit::uninit();
+        let found = unsafe {
+            rustler_sys::enif_whereis_pid(
+                crate::monitor::caller_env(self),
+                name.as_c_arg(),
+                pid.as_mut_ptr(),
+            )
+        };
+        if found == 0 {
+            return None;
+        }
+        Some(LocalPid {
+            c: unsafe { pid.assume_init() },
+        })
+    }
 }
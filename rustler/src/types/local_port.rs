@@ -0,0 +1,75 @@
+use crate::types::atom::Atom;
+use crate::{Decoder, Env, Error, NifResult, Term};
+use rustler_sys::ErlNifPort;
+use std::mem::MaybeUninit;
+
+/// A port of the local node.
+#[derive(Clone, Copy)]
+pub struct LocalPort {
+    c: ErlNifPort,
+}
+
+impl LocalPort {
+    pub fn as_c_arg(&self) -> &ErlNifPort {
+        &self.c
+    }
+
+    /// Whether the port is open.
+    pub fn is_alive(&self, env: Env) -> bool {
+        unsafe { rustler_sys::enif_is_port_alive(env.as_c_arg(), &self.c) != 0 }
+    }
+
+    /// Sends `message` to the port, like `Port.command/2`. `env` must be the environment of the
+    /// calling process. Returns `false` if the port is closed or `message` is not an iolist.
+    pub fn command(&self, env: Env, message: Term) -> bool {
+        unsafe {
+            rustler_sys::enif_port_command(
+                env.as_c_arg(),
+                &self.c,
+                std::ptr::null_mut(),
+                message.as_c_arg(),
+            ) != 0
+        }
+    }
+}
+
+impl<'a> Decoder<'a> for LocalPort {
+    fn decode(term: Term<'a>) -> NifResult<LocalPort> {
+        let mut port = MaybeUninit::uninit();
+        let success = unsafe {
+            rustler_sys::enif_get_local_port(
+                term.get_env().as_c_arg(),
+                term.as_c_arg(),
+                port.as_mut_ptr(),
+            )
+        };
+        if success == 0 {
+            return Err(Error::BadArg);
+        }
+        Ok(LocalPort {
+            c: unsafe { port.assume_init() },
+        })
+    }
+}
+
+impl<'a> Env<'a> {
+    /// Returns the port registered as `name`, or `None` if there is none. Like `whereis_pid`,
+    /// this can be called from a thread that is not managed by the VM.
+    pub fn whereis_port(self, name: &str) -> Option<LocalPort> {
+        let name = Atom::try_from_bytes(self, name.as_bytes()).ok()??;
+        let mut port = MaybeUninit::uninit();
+        let found = unsafe {
+            rustler_sys::enif_whereis_port(
+                crate::monitor::caller_env(self),
+                name.as_c_arg(),
+                port.as_mut_ptr(),
+            )
+        };
+        if found == 0 {
+            return None;
+        }
+        Some(LocalPort {
+            c: unsafe { port.assume_init() },
+        })
+    }
+}
@@ -1,14 +1,27 @@
 //! Utilities used to access and create Erlang maps.
 
-use super::atom;
 use crate::wrapper::map;
 use crate::{Decoder, Env, Error, NifResult, Term};
-use std::ops::RangeInclusive;
 
 pub fn map_new(env: Env) -> Term {
     unsafe { Term::new(env, map::map_new(env.as_c_arg())) }
 }
 
+/// Builds a map from the pairs of `keys` and `values`, like `Term::map_from_arrays`, except that
+/// a repeated key keeps its last value, like with `Term::map_put`, instead of failing. Used by the
+/// encoders of maps, which can't fail. Extra keys or values are ignored.
+pub fn map_from_entries<'a>(env: Env<'a>, keys: &[Term<'a>], values: &[Term<'a>]) -> Term<'a> {
+    let len = keys.len().min(values.len());
+    let (keys, values) = (&keys[..len], &values[..len]);
+    Term::map_from_arrays(env, keys, values).unwrap_or_else(|_| {
+        keys.iter()
+            .zip(values)
+            .fold(map_new(env), |map, (key, value)| {
+                map.map_put(*key, *value).unwrap_or(map)
+            })
+    })
+}
+
 /// ## Map terms
 impl<'a> Term<'a> {
     /// Constructs a new, empty map term.
@@ -222,23 +235,3 @@ impl<'a> Decoder<'a> for MapIterator<'a> {
         }
     }
 }
-
-impl<'a, T> Decoder<'a> for RangeInclusive<T>
-where
-    T: Decoder<'a>,
-{
-    fn decode(term: Term<'a>) -> NifResult<Self> {
-        let env = term.get_env();
-        let name = term.map_get(atom::__struct__().to_term(env))?;
-
-        match name.atom_to_string()?.as_ref() {
-            "Elixir.Range" => (),
-            _ => return Err(Error::BadArg),
-        }
-
-        let first = term.map_get(atom::first().to_term(env))?.decode::<T>()?;
-        let last = term.map_get(atom::last().to_term(env))?.decode::<T>()?;
-
-        Ok(first..=last)
-    }
-}
@@ -0,0 +1,98 @@
+//! Decoding a few fields out of a large map.
+//!
+//! NIFs often receive big context maps of which they only need a handful of keys. A
+//! `MapSubset<T>` looks up the keys of `T` in the map, one by one, and decodes `T` from them,
+//! without iterating over the rest of the map. The keys that were found are kept, so that the
+//! NIF can tell a missing key from one explicitly set to `nil`.
+//!
+//! The keys are provided by the `MapFields` trait, which is implemented by the `NifMap` derive:
+//!
+//! ```ignore
+//! #[derive(NifMap)]
+//! struct Options {
+//!     timeout: Option<u64>,
+//!     retries: Option<u32>,
+//! }
+//!
+//! #[rustler::nif]
+//! fn connect(options: MapSubset<Options>) -> u64 {
+//!     options.timeout.unwrap_or(5000)
+//! }
+//! ```
+//!
+//! Keys missing from the map are decoded as `nil`, or as the `Option::None` atom of the profile
+//! passed to `Decoder::decode_with`, so `Option` fields are `None` when absent, while other
+//! fields fail to decode.
+
+use super::map::map_new;
+use crate::{Decoder, EncodingProfile, Env, NifResult, Term};
+use std::ops::{Deref, DerefMut};
+
+/// A type decoded from a map, with a known set of keys.
+pub trait MapFields {
+    /// Returns the keys of the map the value is decoded from, following `profile`.
+    fn field_keys<'a>(env: Env<'a>, profile: EncodingProfile) -> Vec<Term<'a>>;
+}
+
+/// A `T` decoded from the keys of `T::field_keys()` in a map, ignoring any other key.
+pub struct MapSubset<'a, T> {
+    value: T,
+    found: Vec<Term<'a>>,
+}
+
+impl<'a, T> MapSubset<'a, T> {
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// Returns the keys of `T` that were present in the map.
+    pub fn found_keys(&self) -> &[Term<'a>] {
+        &self.found
+    }
+}
+
+impl<'a, T> Deref for MapSubset<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<'a, T> DerefMut for MapSubset<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<'a, T> Decoder<'a> for MapSubset<'a, T>
+where
+    T: Decoder<'a> + MapFields,
+{
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        Self::decode_with(term, EncodingProfile::ELIXIR)
+    }
+
+    fn decode_with(term: Term<'a>, profile: EncodingProfile) -> NifResult<Self> {
+        let env = term.get_env();
+        // Fails with `BadArg` if `term` is not a map.
+        term.map_size()?;
+
+        let mut found = Vec::new();
+        let mut subset = map_new(env);
+        for key in T::field_keys(env, profile) {
+            let value = match term.map_get(key) {
+                Ok(value) => {
+                    found.push(key);
+                    value
+                }
+                Err(_) => profile.none_atom().to_term(env),
+            };
+            subset = subset.map_put(key, value)?;
+        }
+
+        Ok(MapSubset {
+            value: T::decode_with(subset, profile)?,
+            found,
+        })
+    }
+}
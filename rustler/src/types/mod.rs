@@ -1,11 +1,25 @@
+use crate::backend::Backend;
+use crate::profile::EncodingProfile;
 use crate::{Env, Error, NifResult, Term};
 
 #[macro_use]
 pub mod atom;
 pub use crate::types::atom::Atom;
 
+pub mod array;
+pub use crate::types::array::ErlArray;
+
 pub mod binary;
-pub use crate::types::binary::{Binary, OwnedBinary};
+pub use crate::types::binary::{AllocError, Binary, NewBinary, OwnedBinary};
+
+pub mod binary_reader;
+pub use crate::types::binary_reader::BinaryReader;
+
+pub mod binary_writer;
+pub use crate::types::binary_writer::BinaryWriter;
+
+pub mod bitstring;
+pub use crate::types::bitstring::Bitstring;
 
 #[doc(hidden)]
 pub mod list;
@@ -19,12 +33,16 @@ pub use self::map::MapIterator;
 pub mod primitive;
 #[doc(hidden)]
 pub mod string;
+pub mod time;
 pub mod tuple;
 
 #[doc(hidden)]
 pub mod local_pid;
 pub use self::local_pid::LocalPid;
 
+pub mod local_port;
+pub use self::local_port::LocalPort;
+
 #[deprecated(since = "0.22.0", note = "Please use local_pid instead")]
 pub mod pid {
     #[deprecated(since = "0.22.0", note = "Please use LocalPid instead")]
@@ -35,13 +53,101 @@ pub use self::LocalPid as Pid;
 
 pub mod truthy;
 
+pub mod elixir_std;
 pub mod elixir_struct;
 
+pub mod gb_trees;
+pub use self::gb_trees::{GbSet, GbSetIterator, GbTree, GbTreeIterator};
+
+#[cfg(feature = "big_integer")]
+pub mod big_int;
+
+pub mod iolist;
+pub use self::iolist::Iolist;
+
+#[cfg(nif_version_2_13)]
+pub mod ioq;
+#[cfg(nif_version_2_13)]
+pub use self::ioq::IoQueue;
+
+#[cfg(nif_version_2_13)]
+pub mod iovec;
+#[cfg(nif_version_2_13)]
+pub use self::iovec::IoVec;
+
+pub mod keyed;
+pub use self::keyed::KeyedVec;
+
+pub mod keyword_list;
+pub use self::keyword_list::KeywordList;
+
+pub mod lazy;
+pub use self::lazy::Lazy;
+
+pub mod map_subset;
+pub use self::map_subset::MapSubset;
+
+pub mod queue;
+pub use self::queue::ErlQueue;
+
+pub mod rows;
+pub use self::rows::RowDecoder;
+
+pub mod batch;
+pub use self::batch::DecodeBatch;
+
+pub mod decode_into;
+pub use self::decode_into::DecodeInto;
+
 pub trait Encoder {
     fn encode<'a>(&self, env: Env<'a>) -> Term<'a>;
+
+    /// Encodes the value following `profile`. See `EncodingProfile`.
+    ///
+    /// Types whose encoding doesn't depend on a profile can rely on the default, which calls
+    /// `encode`. Containers pass `profile` on to their elements.
+    fn encode_with<'a>(&self, env: Env<'a>, profile: EncodingProfile) -> Term<'a> {
+        let _ = profile;
+        self.encode(env)
+    }
+
+    /// Encodes a slice of values as a list.
+    ///
+    /// This is used by the `Vec<T>` and `[T]` encoders, and calls `encode_slice_with` with
+    /// `EncodingProfile::ELIXIR`.
+    fn encode_slice<'a>(values: &[Self], env: Env<'a>) -> Term<'a>
+    where
+        Self: Sized,
+    {
+        Self::encode_slice_with(values, env, EncodingProfile::ELIXIR)
+    }
+
+    /// Encodes a slice of values as a list, following `profile`.
+    ///
+    /// Types can override it to share work between the elements, like the `NifMap` and
+    /// `NifStruct` derives do for their keys.
+    fn encode_slice_with<'a>(values: &[Self], env: Env<'a>, profile: EncodingProfile) -> Term<'a>
+    where
+        Self: Sized,
+    {
+        env.list(
+            values
+                .iter()
+                .map(|value| value.encode_with(env, profile))
+                .collect(),
+        )
+    }
 }
 pub trait Decoder<'a>: Sized + 'a {
     fn decode(term: Term<'a>) -> NifResult<Self>;
+
+    /// Decodes a value encoded following `profile`. See `EncodingProfile`.
+    ///
+    /// The default calls `decode`. Containers pass `profile` on to their elements.
+    fn decode_with(term: Term<'a>, profile: EncodingProfile) -> NifResult<Self> {
+        let _ = profile;
+        Self::decode(term)
+    }
 }
 
 impl<'a> Encoder for Term<'a> {
@@ -62,6 +168,10 @@ where
     fn encode<'c>(&self, env: Env<'c>) -> Term<'c> {
         <T as Encoder>::encode(self, env)
     }
+
+    fn encode_with<'c>(&self, env: Env<'c>, profile: EncodingProfile) -> Term<'c> {
+        <T as Encoder>::encode_with(self, env, profile)
+    }
 }
 
 impl<T> Encoder for Option<T>
@@ -69,9 +179,13 @@ where
     T: Encoder,
 {
     fn encode<'c>(&self, env: Env<'c>) -> Term<'c> {
+        self.encode_with(env, EncodingProfile::ELIXIR)
+    }
+
+    fn encode_with<'c>(&self, env: Env<'c>, profile: EncodingProfile) -> Term<'c> {
         match *self {
-            Some(ref value) => value.encode(env),
-            None => atom::nil().encode(env),
+            Some(ref value) => value.encode_with(env, profile),
+            None => profile.none_atom().encode(env),
         }
     }
 }
@@ -81,11 +195,15 @@ where
     T: Decoder<'a>,
 {
     fn decode(term: Term<'a>) -> NifResult<Self> {
-        if let Ok(term) = term.decode::<T>() {
+        Self::decode_with(term, EncodingProfile::ELIXIR)
+    }
+
+    fn decode_with(term: Term<'a>, profile: EncodingProfile) -> NifResult<Self> {
+        if let Ok(term) = T::decode_with(term, profile) {
             Ok(Some(term))
         } else {
             let decoded_atom: atom::Atom = term.decode()?;
-            if decoded_atom == atom::nil() {
+            if decoded_atom == profile.none_atom() {
                 Ok(None)
             } else {
                 Err(Error::BadArg)
@@ -100,9 +218,13 @@ where
     E: Encoder,
 {
     fn encode<'c>(&self, env: Env<'c>) -> Term<'c> {
+        self.encode_with(env, EncodingProfile::ELIXIR)
+    }
+
+    fn encode_with<'c>(&self, env: Env<'c>, profile: EncodingProfile) -> Term<'c> {
         match *self {
-            Ok(ref value) => (atom::ok().encode(env), value.encode(env)).encode(env),
-            Err(ref err) => (atom::error().encode(env), err.encode(env)).encode(env),
+            Ok(ref value) => (atom::ok().encode(env), value.encode_with(env, profile)).encode(env),
+            Err(ref err) => (atom::error().encode(env), err.encode_with(env, profile)).encode(env),
         }
     }
 }
@@ -113,12 +235,16 @@ where
     E: Decoder<'a>,
 {
     fn decode(term: Term<'a>) -> NifResult<Self> {
+        Self::decode_with(term, EncodingProfile::ELIXIR)
+    }
+
+    fn decode_with(term: Term<'a>, profile: EncodingProfile) -> NifResult<Self> {
         let (decoded_atom, inner_term): (atom::Atom, Term) = term.decode()?;
         if decoded_atom == atom::ok() {
-            let ok_value: T = inner_term.decode()?;
+            let ok_value = T::decode_with(inner_term, profile)?;
             Ok(Ok(ok_value))
         } else if decoded_atom == atom::error() {
-            let err_value: E = inner_term.decode()?;
+            let err_value = E::decode_with(inner_term, profile)?;
             Ok(Err(err_value))
         } else {
             Err(Error::BadArg)
@@ -132,32 +258,137 @@ where
     V: Decoder<'a>,
 {
     fn decode(term: Term<'a>) -> NifResult<Self> {
+        Self::decode_with(term, EncodingProfile::ELIXIR)
+    }
+
+    fn decode_with(term: Term<'a>, profile: EncodingProfile) -> NifResult<Self> {
         let size = term.map_size()?;
+        let mut map = std::collections::HashMap::with_capacity(size);
+        decode_map_entries(term, profile, |k, v| {
+            map.insert(k, v);
+        })?;
+        Ok(map)
+    }
+}
+
+impl<K, V> Encoder for std::collections::HashMap<K, V>
+where
+    K: Encoder + Eq + std::hash::Hash,
+    V: Encoder,
+{
+    fn encode<'c>(&self, env: Env<'c>) -> Term<'c> {
+        self.encode_with(env, EncodingProfile::ELIXIR)
+    }
 
-        let it = MapIterator::new(term).ok_or(Error::BadArg)?;
+    fn encode_with<'c>(&self, env: Env<'c>, profile: EncodingProfile) -> Term<'c> {
+        encode_map_entries(env, profile, self.iter())
+    }
+}
 
-        let mut map = std::collections::HashMap::with_capacity(size);
+impl<'a, K, V> Decoder<'a> for std::collections::BTreeMap<K, V>
+where
+    K: Decoder<'a> + Ord,
+    V: Decoder<'a>,
+{
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        Self::decode_with(term, EncodingProfile::ELIXIR)
+    }
 
-        for (k, v) in it {
-            let k = k.decode()?;
-            let v = v.decode()?;
+    fn decode_with(term: Term<'a>, profile: EncodingProfile) -> NifResult<Self> {
+        let mut map = std::collections::BTreeMap::new();
+        decode_map_entries(term, profile, |k, v| {
             map.insert(k, v);
-        }
+        })?;
+        Ok(map)
+    }
+}
+
+impl<K, V> Encoder for std::collections::BTreeMap<K, V>
+where
+    K: Encoder + Ord,
+    V: Encoder,
+{
+    fn encode<'c>(&self, env: Env<'c>) -> Term<'c> {
+        self.encode_with(env, EncodingProfile::ELIXIR)
+    }
 
+    fn encode_with<'c>(&self, env: Env<'c>, profile: EncodingProfile) -> Term<'c> {
+        encode_map_entries(env, profile, self.iter())
+    }
+}
+
+/// Decodes an `IndexMap` from a map. Entries are inserted in the iteration order of the map,
+/// which is the term order of the keys for maps of up to 32 entries, and unspecified otherwise.
+#[cfg(feature = "indexmap")]
+impl<'a, K, V> Decoder<'a> for indexmap::IndexMap<K, V>
+where
+    K: Decoder<'a> + Eq + std::hash::Hash,
+    V: Decoder<'a>,
+{
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        Self::decode_with(term, EncodingProfile::ELIXIR)
+    }
+
+    fn decode_with(term: Term<'a>, profile: EncodingProfile) -> NifResult<Self> {
+        let size = term.map_size()?;
+        let mut map = indexmap::IndexMap::with_capacity(size);
+        decode_map_entries(term, profile, |k, v| {
+            map.insert(k, v);
+        })?;
         Ok(map)
     }
 }
 
-impl<K, V> Encoder for std::collections::HashMap<K, V>
+/// Encodes an `IndexMap` as a map. Maps don't keep insertion order, so the order of the entries
+/// is lost.
+#[cfg(feature = "indexmap")]
+impl<K, V> Encoder for indexmap::IndexMap<K, V>
 where
     K: Encoder + Eq + std::hash::Hash,
     V: Encoder,
 {
     fn encode<'c>(&self, env: Env<'c>) -> Term<'c> {
-        let (keys, values): (Vec<_>, Vec<_>) = self
-            .iter()
-            .map(|(k, v)| (k.encode(env), v.encode(env)))
-            .unzip();
-        Term::map_from_arrays(env, &keys, &values).unwrap()
+        self.encode_with(env, EncodingProfile::ELIXIR)
     }
+
+    fn encode_with<'c>(&self, env: Env<'c>, profile: EncodingProfile) -> Term<'c> {
+        encode_map_entries(env, profile, self.iter())
+    }
+}
+
+/// Decodes the entries of the map `term`, passing each of them to `insert`.
+fn decode_map_entries<'a, K, V, F>(
+    term: Term<'a>,
+    profile: EncodingProfile,
+    mut insert: F,
+) -> NifResult<()>
+where
+    K: Decoder<'a>,
+    V: Decoder<'a>,
+    F: FnMut(K, V),
+{
+    let it = MapIterator::new(term).ok_or(Error::BadArg)?;
+    for (k, v) in it {
+        insert(K::decode_with(k, profile)?, V::decode_with(v, profile)?);
+    }
+    Ok(())
+}
+
+/// Builds a map from `entries` in one go, sorted by key when `profile` asks for it.
+fn encode_map_entries<'a, 'c, K, V, I>(
+    env: Env<'c>,
+    profile: EncodingProfile,
+    entries: I,
+) -> Term<'c>
+where
+    K: Encoder + 'a,
+    V: Encoder + 'a,
+    I: Iterator<Item = (&'a K, &'a V)>,
+{
+    let mut entries: Vec<_> = entries
+        .map(|(k, v)| (k.encode_with(env, profile), v.encode_with(env, profile)))
+        .collect();
+    profile.maps().sort_entries(&mut entries);
+    let (keys, values): (Vec<_>, Vec<_>) = entries.into_iter().unzip();
+    map::map_from_entries(env, &keys, &values)
 }
@@ -1,57 +1,59 @@
+use crate::backend::Backend;
 use crate::types::atom;
 use crate::{Decoder, Encoder, Env, Error, NifResult, Term};
+use std::convert::TryFrom;
 
-macro_rules! impl_number_transcoder {
-    ($dec_type:ty, $nif_type:ty, $encode_fun:ident, $decode_fun:ident) => {
+// Integers are decoded as `$nif_type`, failing when they don't fit, and then cast, like with
+// `enif_get_int` and `enif_get_uint`.
+macro_rules! impl_integer_transcoder {
+    ($dec_type:ty, $nif_type:ty, $make:ident, $get:ident) => {
         impl Encoder for $dec_type {
             fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
                 #[allow(clippy::cast_lossless)]
-                unsafe {
-                    Term::new(
-                        env,
-                        rustler_sys::$encode_fun(env.as_c_arg(), *self as $nif_type),
-                    )
-                }
+                env.$make(*self as _)
             }
         }
         impl<'a> Decoder<'a> for $dec_type {
             fn decode(term: Term) -> NifResult<$dec_type> {
-                #![allow(unused_unsafe)]
-                let mut res: $nif_type = Default::default();
-                if unsafe {
-                    rustler_sys::$decode_fun(term.get_env().as_c_arg(), term.as_c_arg(), &mut res)
-                } == 0
-                {
-                    return Err(Error::BadArg);
+                #[allow(clippy::useless_conversion)]
+                let value = term.get_env().$get(&term).map(<$nif_type>::try_from);
+                match value {
+                    Some(Ok(value)) => Ok(value as $dec_type),
+                    _ => Err(Error::BadArg),
                 }
-                Ok(res as $dec_type)
             }
         }
     };
 }
 
 // Base number types
-impl_number_transcoder!(i32, i32, enif_make_int, enif_get_int);
-impl_number_transcoder!(u32, u32, enif_make_uint, enif_get_uint);
-impl_number_transcoder!(i64, i64, enif_make_int64, enif_get_int64);
-impl_number_transcoder!(u64, u64, enif_make_uint64, enif_get_uint64);
-impl_number_transcoder!(f64, f64, enif_make_double, enif_get_double);
+impl_integer_transcoder!(i32, i32, int, get_int);
+impl_integer_transcoder!(u32, u32, uint, get_uint);
+impl_integer_transcoder!(i64, i64, int, get_int);
+impl_integer_transcoder!(u64, u64, uint, get_uint);
 
 // Casted number types
-impl_number_transcoder!(i8, i32, enif_make_int, enif_get_int);
-impl_number_transcoder!(u8, u32, enif_make_uint, enif_get_uint);
-impl_number_transcoder!(i16, i32, enif_make_int, enif_get_int);
-impl_number_transcoder!(u16, u32, enif_make_uint, enif_get_uint);
-impl_number_transcoder!(usize, u64, enif_make_uint64, enif_get_uint64);
-impl_number_transcoder!(isize, i64, enif_make_int64, enif_get_int64);
+impl_integer_transcoder!(i8, i32, int, get_int);
+impl_integer_transcoder!(u8, u32, uint, get_uint);
+impl_integer_transcoder!(i16, i32, int, get_int);
+impl_integer_transcoder!(u16, u32, uint, get_uint);
+impl_integer_transcoder!(usize, u64, uint, get_uint);
+impl_integer_transcoder!(isize, i64, int, get_int);
+
+impl Encoder for f64 {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        env.float(*self)
+    }
+}
+impl<'a> Decoder<'a> for f64 {
+    fn decode(term: Term) -> NifResult<f64> {
+        term.get_env().get_float(&term).ok_or(Error::BadArg)
+    }
+}
 
 impl Encoder for bool {
     fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
-        if *self {
-            atom::true_().to_term(env)
-        } else {
-            atom::false_().to_term(env)
-        }
+        env.bool(*self)
     }
 }
 impl<'a> Decoder<'a> for bool {
@@ -77,3 +79,114 @@ impl<'a> Decoder<'a> for f32 {
         }
     }
 }
+
+/// The tags of the External Term Format used for integers that don't fit in 64 bits.
+const VERSION_MAGIC: u8 = 131;
+const SMALL_BIG_EXT: u8 = 110;
+const LARGE_BIG_EXT: u8 = 111;
+
+/// Makes an integer from its sign and its magnitude, in little-endian bytes.
+///
+/// The NIF API can only make 64-bit integers, so larger ones are decoded from the External Term
+/// Format.
+pub(crate) fn make_big_integer<'a>(env: Env<'a>, negative: bool, magnitude: &[u8]) -> Term<'a> {
+    let len = magnitude
+        .iter()
+        .rposition(|&digit| digit != 0)
+        .map_or(0, |i| i + 1);
+    let magnitude = &magnitude[..len];
+
+    let mut etf = Vec::with_capacity(len + 7);
+    etf.push(VERSION_MAGIC);
+    if len <= u8::MAX as usize {
+        etf.push(SMALL_BIG_EXT);
+        etf.push(len as u8);
+    } else {
+        etf.push(LARGE_BIG_EXT);
+        etf.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+    etf.push((negative && len > 0) as u8);
+    etf.extend_from_slice(magnitude);
+    let (term, _) = env
+        .binary_to_term(&etf)
+        .expect("the VM rejected an integer in the term format");
+    term
+}
+
+/// Returns the sign of the integer `term` and its magnitude, in little-endian bytes.
+pub(crate) fn get_big_integer(term: Term) -> NifResult<(bool, Vec<u8>)> {
+    if let Ok(value) = term.decode::<i64>() {
+        return Ok((value < 0, value.unsigned_abs().to_le_bytes().to_vec()));
+    }
+    if !term.is_number() {
+        return Err(Error::BadArg);
+    }
+
+    let etf = term.try_to_binary()?;
+    let (len, rest) = match etf.as_slice() {
+        [VERSION_MAGIC, SMALL_BIG_EXT, len, rest @ ..] => (*len as usize, rest),
+        [VERSION_MAGIC, LARGE_BIG_EXT, a, b, c, d, rest @ ..] => {
+            (u32::from_be_bytes([*a, *b, *c, *d]) as usize, rest)
+        }
+        // Floats.
+        _ => return Err(Error::BadArg),
+    };
+    match rest {
+        [sign, magnitude @ ..] if magnitude.len() == len => Ok((*sign != 0, magnitude.to_vec())),
+        _ => Err(Error::BadArg),
+    }
+}
+
+/// Returns `magnitude`, in little-endian bytes, if it fits in 128 bits.
+fn magnitude_u128(magnitude: &[u8]) -> NifResult<u128> {
+    if magnitude.iter().skip(16).any(|&digit| digit != 0) {
+        return Err(Error::BadArg);
+    }
+    let mut bytes = [0; 16];
+    let len = magnitude.len().min(16);
+    bytes[..len].copy_from_slice(&magnitude[..len]);
+    Ok(u128::from_le_bytes(bytes))
+}
+
+impl Encoder for i128 {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        if let Ok(value) = i64::try_from(*self) {
+            return value.encode(env);
+        }
+        make_big_integer(env, *self < 0, &self.unsigned_abs().to_le_bytes())
+    }
+}
+impl<'a> Decoder<'a> for i128 {
+    fn decode(term: Term<'a>) -> NifResult<i128> {
+        if let Ok(value) = term.decode::<i64>() {
+            return Ok(value.into());
+        }
+        let (negative, magnitude) = get_big_integer(term)?;
+        let magnitude = magnitude_u128(&magnitude)?;
+        if negative {
+            0i128.checked_sub_unsigned(magnitude).ok_or(Error::BadArg)
+        } else {
+            i128::try_from(magnitude).map_err(|_| Error::BadArg)
+        }
+    }
+}
+
+impl Encoder for u128 {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        if let Ok(value) = u64::try_from(*self) {
+            return value.encode(env);
+        }
+        make_big_integer(env, false, &self.to_le_bytes())
+    }
+}
+impl<'a> Decoder<'a> for u128 {
+    fn decode(term: Term<'a>) -> NifResult<u128> {
+        if let Ok(value) = term.decode::<u64>() {
+            return Ok(value.into());
+        }
+        match get_big_integer(term)? {
+            (false, magnitude) => magnitude_u128(&magnitude),
+            (true, _) => Err(Error::BadArg),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Encoding and decoding of OTP's `:queue`.
+//!
+//! A queue is represented as a tuple `{rear, front}` of two lists: the items of the queue are the
+//! items of `front`, followed by the items of `rear` in reverse order. `ErlQueue<T>` decodes
+//! such a tuple into a `VecDeque<T>` in queue order, and encodes a `VecDeque<T>` back into one,
+//! so that NIFs can take and return queues without converting them to lists on the Elixir side:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn drain_even(queue: ErlQueue<i64>) -> ErlQueue<i64> {
+//!     queue.into_inner().into_iter().filter(|n| n % 2 != 0).collect()
+//! }
+//! ```
+
+use super::list::ListIterator;
+use super::tuple::get_tuple;
+use crate::{Decoder, Encoder, Env, Error, NifResult, Term};
+use std::collections::VecDeque;
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut};
+
+/// A `VecDeque<T>`, in queue order, that is encoded as an OTP `:queue`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ErlQueue<T>(pub VecDeque<T>);
+
+impl<T> ErlQueue<T> {
+    pub fn new() -> Self {
+        ErlQueue(VecDeque::new())
+    }
+
+    pub fn into_inner(self) -> VecDeque<T> {
+        self.0
+    }
+}
+
+impl<T> From<VecDeque<T>> for ErlQueue<T> {
+    fn from(items: VecDeque<T>) -> Self {
+        ErlQueue(items)
+    }
+}
+
+impl<T> From<Vec<T>> for ErlQueue<T> {
+    fn from(items: Vec<T>) -> Self {
+        ErlQueue(items.into())
+    }
+}
+
+impl<T> FromIterator<T> for ErlQueue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        ErlQueue(iter.into_iter().collect())
+    }
+}
+
+impl<T> Deref for ErlQueue<T> {
+    type Target = VecDeque<T>;
+    fn deref(&self) -> &VecDeque<T> {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for ErlQueue<T> {
+    fn deref_mut(&mut self) -> &mut VecDeque<T> {
+        &mut self.0
+    }
+}
+
+/// Splits the items between the two lists, so that both ends of the queue can be taken without
+/// reversing a list: the first half goes in the front list, and the rest in the rear list,
+/// reversed. A single item goes in the front list.
+impl<T> Encoder for ErlQueue<T>
+where
+    T: Encoder,
+{
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        let split = match self.0.len() {
+            1 => 1,
+            len => len / 2,
+        };
+        let front: Vec<Term<'a>> = self
+            .0
+            .iter()
+            .take(split)
+            .map(|item| item.encode(env))
+            .collect();
+        let rear: Vec<Term<'a>> = self
+            .0
+            .iter()
+            .skip(split)
+            .rev()
+            .map(|item| item.encode(env))
+            .collect();
+        (rear, front).encode(env)
+    }
+}
+
+impl<'a, T> Decoder<'a> for ErlQueue<T>
+where
+    T: Decoder<'a>,
+{
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let elements = get_tuple(term)?;
+        if elements.len() != 2 {
+            return Err(Error::BadArg);
+        }
+        let rear: ListIterator<'a> = elements[0].decode()?;
+        let front: ListIterator<'a> = elements[1].decode()?;
+
+        let mut items: VecDeque<T> = front.map(|item| item.decode()).collect::<NifResult<_>>()?;
+        let rear: Vec<Term<'a>> = rear.collect();
+        for item in rear.into_iter().rev() {
+            items.push_back(item.decode()?);
+        }
+        Ok(ErlQueue(items))
+    }
+}
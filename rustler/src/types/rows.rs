@@ -0,0 +1,188 @@
+//! Column-oriented decoding of tabular terms.
+//!
+//! NIFs that ingest large tables, like the result set of a database query, receive lists of
+//! homogeneous rows. Decoding each row into a struct works, but is slow for millions of rows and
+//! produces a row-oriented layout, while many consumers (statistics, columnar storage) want one
+//! vector per column.
+//!
+//! A `RowDecoder` is declared once with the type of each column, as a tuple type, and decodes a
+//! list of rows into a tuple of vectors. Rows can be tuples or lists.
+//!
+//! ```ignore
+//! #[rustler::nif(schedule = "DirtyCpu")]
+//! fn ingest(rows: Term) -> NifResult<usize> {
+//!     let (ids, names, scores): (Vec<i64>, Vec<String>, Vec<f64>) =
+//!         RowDecoder::<(i64, String, f64)>::new().decode(rows)?;
+//!     ...
+//! }
+//! ```
+//!
+//! The whole list is validated before any value is returned. A row of the wrong shape, or a value
+//! that can't be decoded, raises an error naming the row and column.
+
+use crate::wrapper::{list, tuple, NIF_TERM};
+use crate::{Decoder, Env, Error, NifResult, Term};
+use std::marker::PhantomData;
+
+/// A set of column types, implemented for tuples of up to 12 `Decoder` types.
+pub trait Columns<'a> {
+    /// The decoded columns, a tuple of vectors.
+    type Output;
+
+    /// Number of columns.
+    const WIDTH: usize;
+
+    /// Returns empty columns, with room for `capacity` rows.
+    fn with_capacity(capacity: usize) -> Self::Output;
+
+    /// Decodes a row of `WIDTH` values and appends it to `columns`. On failure, returns the
+    /// index of the column that could not be decoded, and leaves `columns` unchanged.
+    ///
+    /// # Safety
+    ///
+    /// The terms in `row` must be valid in `env`.
+    unsafe fn push_row(
+        columns: &mut Self::Output,
+        env: Env<'a>,
+        row: &[NIF_TERM],
+    ) -> Result<(), usize>;
+}
+
+macro_rules! impl_columns {
+    ( $width:expr; $( $index:tt : $ty:ident ),* ) => {
+        impl<'a, $( $ty: Decoder<'a> ),*> Columns<'a> for ( $( $ty, )* ) {
+            type Output = ( $( Vec<$ty>, )* );
+
+            const WIDTH: usize = $width;
+
+            fn with_capacity(capacity: usize) -> Self::Output {
+                ( $( Vec::<$ty>::with_capacity(capacity), )* )
+            }
+
+            unsafe fn push_row(
+                columns: &mut Self::Output,
+                env: Env<'a>,
+                row: &[NIF_TERM],
+            ) -> Result<(), usize> {
+                // Decode the whole row first, so that the columns stay aligned on failure.
+                let values = ( $(
+                    <$ty as Decoder>::decode(Term::new(env, row[$index])).map_err(|_| $index as usize)?,
+                )* );
+                $( columns.$index.push(values.$index); )*
+                Ok(())
+            }
+        }
+    }
+}
+
+impl_columns!(1; 0: A);
+impl_columns!(2; 0: A, 1: B);
+impl_columns!(3; 0: A, 1: B, 2: C);
+impl_columns!(4; 0: A, 1: B, 2: C, 3: D);
+impl_columns!(5; 0: A, 1: B, 2: C, 3: D, 4: E);
+impl_columns!(6; 0: A, 1: B, 2: C, 3: D, 4: E, 5: F);
+impl_columns!(7; 0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G);
+impl_columns!(8; 0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H);
+impl_columns!(9; 0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I);
+impl_columns!(10; 0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I, 9: J);
+impl_columns!(11; 0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I, 9: J, 10: K);
+impl_columns!(12; 0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I, 9: J, 10: K, 11: L);
+
+/// Decodes lists of rows into one vector per column. See the module documentation.
+pub struct RowDecoder<C> {
+    columns: PhantomData<fn() -> C>,
+}
+
+impl<C> RowDecoder<C> {
+    pub fn new() -> Self {
+        RowDecoder {
+            columns: PhantomData,
+        }
+    }
+
+    /// Decodes `rows`, a list of tuples or lists, into columns.
+    pub fn decode<'a>(&self, rows: Term<'a>) -> NifResult<C::Output>
+    where
+        C: Columns<'a>,
+    {
+        let len = rows.list_length()?;
+        let mut columns = C::with_capacity(len);
+        self.decode_into(rows, &mut columns)?;
+        Ok(columns)
+    }
+
+    /// Decodes `rows`, a list of tuples or lists, and appends them to `columns`. This allows to
+    /// decode a table that arrives in several batches.
+    ///
+    /// On failure, the rows preceding the failing one have been appended.
+    pub fn decode_into<'a>(&self, rows: Term<'a>, columns: &mut C::Output) -> NifResult<()>
+    where
+        C: Columns<'a>,
+    {
+        let env = rows.get_env();
+        // Reused for rows that are lists, to avoid an allocation per row.
+        let mut buffer: Vec<NIF_TERM> = Vec::with_capacity(C::WIDTH);
+        let mut tail = rows.as_c_arg();
+        let mut index = 0;
+
+        while let Some((row, rest)) = unsafe { list::get_list_cell(env.as_c_arg(), tail) } {
+            let values = match unsafe { tuple::get_tuple(env.as_c_arg(), row) } {
+                Ok(values) => values,
+                Err(_) => {
+                    buffer.clear();
+                    list_values(unsafe { Term::new(env, row) }, &mut buffer)
+                        .ok_or_else(|| shape_error(index))?;
+                    &buffer[..]
+                }
+            };
+
+            if values.len() != C::WIDTH {
+                return Err(shape_error(index));
+            }
+
+            unsafe { C::push_row(columns, env, values) }.map_err(|column| {
+                Error::RaiseTerm(Box::new(format!(
+                    "Could not decode column {} of row {}",
+                    column, index
+                )))
+            })?;
+
+            tail = rest;
+            index += 1;
+        }
+
+        if unsafe { Term::new(env, tail) }.is_empty_list() {
+            Ok(())
+        } else {
+            Err(Error::BadArg)
+        }
+    }
+}
+
+impl<C> Default for RowDecoder<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Appends the elements of the proper list `term` to `buffer`.
+fn list_values(term: Term, buffer: &mut Vec<NIF_TERM>) -> Option<()> {
+    let env = term.get_env().as_c_arg();
+    let mut tail = term.as_c_arg();
+    while let Some((head, rest)) = unsafe { list::get_list_cell(env, tail) } {
+        buffer.push(head);
+        tail = rest;
+    }
+    if unsafe { Term::new(term.get_env(), tail) }.is_empty_list() {
+        Some(())
+    } else {
+        None
+    }
+}
+
+fn shape_error(index: usize) -> Error {
+    Error::RaiseTerm(Box::new(format!(
+        "Row {} is not a tuple or list of the expected size",
+        index
+    )))
+}
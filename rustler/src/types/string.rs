@@ -1,10 +1,22 @@
-use super::binary::{Binary, OwnedBinary};
+use super::binary::Binary;
+use crate::backend::Backend;
+use crate::profile::{EncodingProfile, StringStyle};
 use crate::{Decoder, Encoder, Env, Error, NifResult, Term};
 
 impl<'a> Decoder<'a> for String {
     fn decode(term: Term<'a>) -> NifResult<Self> {
-        let string: &str = Decoder::decode(term)?;
-        Ok(string.to_string())
+        Self::decode_with(term, EncodingProfile::ELIXIR)
+    }
+
+    fn decode_with(term: Term<'a>, profile: EncodingProfile) -> NifResult<Self> {
+        if let Ok(string) = <&str as Decoder>::decode(term) {
+            return Ok(string.to_string());
+        }
+
+        match profile.strings() {
+            StringStyle::Binary => Err(Error::BadArg),
+            StringStyle::Charlist => decode_charlist(term),
+        }
     }
 }
 impl<'a> Decoder<'a> for &'a str {
@@ -17,30 +29,55 @@ impl<'a> Decoder<'a> for &'a str {
     }
 }
 
-use std::io::Write;
-
 impl<'a> Encoder for &'a str {
     fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
         (*self).encode(env)
     }
+
+    fn encode_with<'b>(&self, env: Env<'b>, profile: EncodingProfile) -> Term<'b> {
+        (*self).encode_with(env, profile)
+    }
 }
 
 impl Encoder for str {
     fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
-        let str_len = self.len();
-        let mut bin = match OwnedBinary::new(str_len) {
-            Some(bin) => bin,
-            None => panic!("binary term allocation fail"),
-        };
-        bin.as_mut_slice()
-            .write_all(self.as_bytes())
-            .expect("memory copy of string failed");
-        bin.release(env).to_term(env)
+        encode_binary(env, self)
     }
+
+    fn encode_with<'b>(&self, env: Env<'b>, profile: EncodingProfile) -> Term<'b> {
+        match profile.strings() {
+            StringStyle::Binary => {
+                Binary::from_bytes_with(env, self.as_bytes(), profile.binaries()).to_term(env)
+            }
+            StringStyle::Charlist => encode_charlist(env, self),
+        }
+    }
+}
+
+/// Encodes `string` as a binary, regardless of the string style of any `EncodingProfile`.
+pub(crate) fn encode_binary<'a>(env: Env<'a>, string: &str) -> Term<'a> {
+    env.binary(string.as_bytes())
+}
+
+fn encode_charlist<'a>(env: Env<'a>, string: &str) -> Term<'a> {
+    let chars: Vec<u32> = string.chars().map(|c| c as u32).collect();
+    chars.encode(env)
+}
+
+fn decode_charlist(term: Term) -> NifResult<String> {
+    let chars: Vec<u32> = term.decode()?;
+    chars
+        .into_iter()
+        .map(|c| std::char::from_u32(c).ok_or(Error::BadArg))
+        .collect()
 }
 
 impl Encoder for String {
     fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
         self.as_str().encode(env)
     }
+
+    fn encode_with<'b>(&self, env: Env<'b>, profile: EncodingProfile) -> Term<'b> {
+        self.as_str().encode_with(env, profile)
+    }
 }
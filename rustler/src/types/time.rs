@@ -0,0 +1,359 @@
+//! Conversions between Rust times and the calendar types of Elixir.
+//!
+//! * `SystemTime` is encoded as a `DateTime` in UTC. It is decoded from a `DateTime` in any time
+//!   zone, or from an Erlang timestamp `{mega_secs, secs, micro_secs}`, like the ones returned by
+//!   `:os.timestamp/0`. Times that are out of the range of `DateTime` can be encoded with
+//!   `try_encode_system_time`, which returns an error instead of panicking.
+//! * `Duration` is encoded and decoded as an Erlang timestamp, the shape Erlang uses for the time
+//!   elapsed since an epoch.
+//!
+//! With the `chrono` feature:
+//!
+//! * `chrono::DateTime<Utc>` is encoded and decoded like `SystemTime`.
+//! * `chrono::NaiveDateTime` is encoded and decoded as a `NaiveDateTime`.
+//! * `chrono::NaiveDate` is encoded and decoded as a `Date`.
+//!
+//! Times are encoded with a precision of microseconds, the finest one of the calendar types of
+//! Elixir, and nanoseconds are truncated.
+
+use super::elixir_std::{check_struct, make_struct};
+use super::tuple::get_tuple;
+use crate::{Atom, Decoder, Encoder, Env, Error, NifResult, Term};
+use std::convert::TryFrom;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+mod atoms {
+    crate::atoms! {
+        date_time = "Elixir.DateTime",
+        iso = "Elixir.Calendar.ISO",
+        calendar,
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        microsecond,
+        std_offset,
+        utc_offset,
+        time_zone,
+        zone_abbr,
+    }
+}
+
+const MICROS_PER_SEC: i64 = 1_000_000;
+const SECS_PER_DAY: i64 = 86_400;
+
+/// The fields of a `NaiveDateTime`, or of a `DateTime` before applying its offsets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Fields {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    microsecond: u32,
+}
+
+impl Fields {
+    fn from_unix_micros(micros: i64) -> Self {
+        let secs = micros.div_euclid(MICROS_PER_SEC);
+        let day_secs = secs.rem_euclid(SECS_PER_DAY);
+        let (year, month, day) = civil_from_days(secs.div_euclid(SECS_PER_DAY));
+        Fields {
+            year,
+            month,
+            day,
+            hour: (day_secs / 3600) as u32,
+            minute: (day_secs / 60 % 60) as u32,
+            second: (day_secs % 60) as u32,
+            microsecond: micros.rem_euclid(MICROS_PER_SEC) as u32,
+        }
+    }
+
+    fn to_unix_micros(self) -> NifResult<i64> {
+        let secs = days_from_civil(self.year, self.month, self.day)
+            .checked_mul(SECS_PER_DAY)
+            .and_then(|secs| {
+                secs.checked_add(
+                    i64::from(self.hour) * 3600
+                        + i64::from(self.minute) * 60
+                        + i64::from(self.second),
+                )
+            })
+            .ok_or(Error::BadArg)?;
+        secs.checked_mul(MICROS_PER_SEC)
+            .and_then(|micros| micros.checked_add(i64::from(self.microsecond)))
+            .ok_or(Error::BadArg)
+    }
+
+    /// Decodes the date and time fields of a struct, checking that they form a valid date.
+    fn decode(term: Term) -> NifResult<Self> {
+        let (year, month, day) = decode_date(term)?;
+        let (microsecond, _precision): (u32, u32) = field(term, atoms::microsecond())?;
+        let fields = Fields {
+            year,
+            month,
+            day,
+            hour: field(term, atoms::hour())?,
+            minute: field(term, atoms::minute())?,
+            second: field(term, atoms::second())?,
+            microsecond,
+        };
+        if fields.hour > 23 || fields.minute > 59 || fields.second > 59 || microsecond > 999_999 {
+            return Err(Error::BadArg);
+        }
+        Ok(fields)
+    }
+
+    /// Returns the fields of the struct, with the `Calendar.ISO` calendar.
+    fn encode<'a>(self, env: Env<'a>) -> Vec<(Atom, Term<'a>)> {
+        vec![
+            (atoms::calendar(), atoms::iso().encode(env)),
+            (atoms::year(), self.year.encode(env)),
+            (atoms::month(), self.month.encode(env)),
+            (atoms::day(), self.day.encode(env)),
+            (atoms::hour(), self.hour.encode(env)),
+            (atoms::minute(), self.minute.encode(env)),
+            (atoms::second(), self.second.encode(env)),
+            (atoms::microsecond(), (self.microsecond, 6).encode(env)),
+        ]
+    }
+}
+
+fn field<'a, T: Decoder<'a>>(term: Term<'a>, field: Atom) -> NifResult<T> {
+    term.map_get(field.encode(term.get_env()))?.decode()
+}
+
+/// Decodes the `year`, `month` and `day` fields of a struct, checking that they form a valid
+/// date.
+fn decode_date(term: Term) -> NifResult<(i64, u32, u32)> {
+    let year: i64 = field(term, atoms::year())?;
+    let month: u32 = field(term, atoms::month())?;
+    let day: u32 = field(term, atoms::day())?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(Error::BadArg);
+    }
+    // Days past the end of the month roll over to the next one.
+    if civil_from_days(days_from_civil(year, month, day)) != (year, month, day) {
+        return Err(Error::BadArg);
+    }
+    Ok((year, month, day))
+}
+
+/// Returns the number of days from 1970-01-01 to a date of the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let (month, day) = (i64::from(month), i64::from(day));
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Returns the date of the proleptic Gregorian calendar that is `days` days after 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = (shifted_month + 2) % 12 + 1;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month as u32, day as u32)
+}
+
+/// Encodes a `DateTime` in UTC.
+fn encode_date_time(env: Env, unix_micros: i64) -> Term {
+    let zero = 0.encode(env);
+    let mut fields = Fields::from_unix_micros(unix_micros).encode(env);
+    fields.extend_from_slice(&[
+        (atoms::std_offset(), zero),
+        (atoms::utc_offset(), zero),
+        (atoms::time_zone(), "Etc/UTC".encode(env)),
+        (atoms::zone_abbr(), "UTC".encode(env)),
+    ]);
+    make_struct(env, "Elixir.DateTime", &fields)
+}
+
+/// Decodes a `DateTime` or an Erlang timestamp into microseconds since the Unix epoch.
+fn decode_unix_micros(term: Term) -> NifResult<i64> {
+    if let Ok(elements) = get_tuple(term) {
+        let (mega_secs, secs, micro_secs): (i64, i64, i64) = match elements[..] {
+            [mega_secs, secs, micro_secs] => {
+                (mega_secs.decode()?, secs.decode()?, micro_secs.decode()?)
+            }
+            _ => return Err(Error::BadArg),
+        };
+        return mega_secs
+            .checked_mul(MICROS_PER_SEC)
+            .and_then(|secs_total| secs_total.checked_add(secs))
+            .and_then(|secs_total| secs_total.checked_mul(MICROS_PER_SEC))
+            .and_then(|micros| micros.checked_add(micro_secs))
+            .ok_or(Error::BadArg);
+    }
+
+    check_struct(term, atoms::date_time())?;
+    let utc_offset: i64 = field(term, atoms::utc_offset())?;
+    let std_offset: i64 = field(term, atoms::std_offset())?;
+    let offset = (utc_offset + std_offset)
+        .checked_mul(MICROS_PER_SEC)
+        .ok_or(Error::BadArg)?;
+    Fields::decode(term)?
+        .to_unix_micros()?
+        .checked_sub(offset)
+        .ok_or(Error::BadArg)
+}
+
+fn system_time_to_unix_micros(time: SystemTime) -> NifResult<i64> {
+    let micros = match time.duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => i128::try_from(elapsed.as_micros()).unwrap_or(i128::MAX),
+        // Rounded down, like times after the epoch.
+        Err(err) => -i128::try_from(err.duration().as_nanos().div_ceil(1000)).unwrap_or(i128::MAX),
+    };
+    i64::try_from(micros).map_err(|_| Error::BadArg)
+}
+
+fn system_time_from_unix_micros(micros: i64) -> NifResult<SystemTime> {
+    let offset = Duration::from_micros(micros.unsigned_abs());
+    let time = if micros >= 0 {
+        UNIX_EPOCH.checked_add(offset)
+    } else {
+        UNIX_EPOCH.checked_sub(offset)
+    };
+    time.ok_or(Error::BadArg)
+}
+
+/// Encodes `time` as a `DateTime` in UTC, or returns `Error::BadArg` when it is out of the range
+/// of microseconds that fit in an `i64`.
+pub fn try_encode_system_time(env: Env, time: SystemTime) -> NifResult<Term> {
+    Ok(encode_date_time(env, system_time_to_unix_micros(time)?))
+}
+
+/// # Panics
+///
+/// Panics if the time is out of the range of `DateTime`. Use `try_encode_system_time` to get an
+/// error instead.
+impl Encoder for SystemTime {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        try_encode_system_time(env, *self).expect("SystemTime out of the range of DateTime")
+    }
+}
+
+impl<'a> Decoder<'a> for SystemTime {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        system_time_from_unix_micros(decode_unix_micros(term)?)
+    }
+}
+
+/// Encodes an Erlang timestamp, `{mega_secs, secs, micro_secs}`.
+impl Encoder for Duration {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        let secs = self.as_secs();
+        (secs / 1_000_000, secs % 1_000_000, self.subsec_micros()).encode(env)
+    }
+}
+
+/// Decodes an Erlang timestamp, `{mega_secs, secs, micro_secs}`.
+impl<'a> Decoder<'a> for Duration {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let (mega_secs, secs, micro_secs): (u64, u64, u64) = term.decode()?;
+        let micros = u128::from(mega_secs) * 1_000_000_000_000
+            + u128::from(secs) * 1_000_000
+            + u128::from(micro_secs);
+        let secs = u64::try_from(micros / 1_000_000).map_err(|_| Error::BadArg)?;
+        Ok(Duration::new(secs, (micros % 1_000_000) as u32 * 1000))
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_impls {
+    use super::{
+        atoms, check_struct, decode_date, decode_unix_micros, encode_date_time, make_struct, Fields,
+    };
+    use crate::{Decoder, Encoder, Env, Error, NifResult, Term};
+    use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Timelike, Utc};
+    use std::convert::TryFrom;
+
+    mod chrono_atoms {
+        crate::atoms! {
+            naive_date_time = "Elixir.NaiveDateTime",
+            date = "Elixir.Date",
+        }
+    }
+
+    impl Encoder for DateTime<Utc> {
+        fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+            encode_date_time(env, self.timestamp_micros())
+        }
+    }
+
+    impl<'a> Decoder<'a> for DateTime<Utc> {
+        fn decode(term: Term<'a>) -> NifResult<Self> {
+            DateTime::from_timestamp_micros(decode_unix_micros(term)?).ok_or(Error::BadArg)
+        }
+    }
+
+    impl Encoder for NaiveDateTime {
+        fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+            let fields = Fields {
+                year: i64::from(self.year()),
+                month: self.month(),
+                day: self.day(),
+                hour: self.hour(),
+                minute: self.minute(),
+                second: self.second(),
+                // Leap seconds are represented by nanoseconds past one second.
+                microsecond: (self.nanosecond() / 1000).min(999_999),
+            };
+            make_struct(env, "Elixir.NaiveDateTime", &fields.encode(env))
+        }
+    }
+
+    impl<'a> Decoder<'a> for NaiveDateTime {
+        fn decode(term: Term<'a>) -> NifResult<Self> {
+            check_struct(term, chrono_atoms::naive_date_time())?;
+            let fields = Fields::decode(term)?;
+            let year = i32::try_from(fields.year).map_err(|_| Error::BadArg)?;
+            NaiveDate::from_ymd_opt(year, fields.month, fields.day)
+                .and_then(|date| {
+                    date.and_hms_micro_opt(
+                        fields.hour,
+                        fields.minute,
+                        fields.second,
+                        fields.microsecond,
+                    )
+                })
+                .ok_or(Error::BadArg)
+        }
+    }
+
+    impl Encoder for NaiveDate {
+        fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+            make_struct(
+                env,
+                "Elixir.Date",
+                &[
+                    (atoms::calendar(), atoms::iso().encode(env)),
+                    (atoms::year(), self.year().encode(env)),
+                    (atoms::month(), self.month().encode(env)),
+                    (atoms::day(), self.day().encode(env)),
+                ],
+            )
+        }
+    }
+
+    impl<'a> Decoder<'a> for NaiveDate {
+        fn decode(term: Term<'a>) -> NifResult<Self> {
+            check_struct(term, chrono_atoms::date())?;
+            let (year, month, day) = decode_date(term)?;
+            let year = i32::try_from(year).map_err(|_| Error::BadArg)?;
+            NaiveDate::from_ymd_opt(year, month, day).ok_or(Error::BadArg)
+        }
+    }
+}
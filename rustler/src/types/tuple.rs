@@ -1,14 +1,42 @@
+use crate::backend::Backend;
 use crate::wrapper::{tuple, NIF_TERM};
-use crate::{Decoder, Encoder, Env, Error, NifResult, Term};
+use crate::{Decoder, Encoder, EncodingProfile, Env, Error, NifResult, Term};
 
 /// ## Tuple terms
-//impl<'a> Term<'a> {
-//
-//    pub fn tuple_to_vec(self) -> NifResult<Term<'a>> {
-//
-//    }
-//
-//}
+impl<'a> Term<'a> {
+    /// Returns the number of elements of a tuple term.
+    ///
+    /// Returns `badarg` if the term is not a tuple.
+    ///
+    /// ### Elixir equivalent
+    /// ```elixir
+    /// tuple_size(self_term)
+    /// ```
+    pub fn tuple_size(self) -> NifResult<usize> {
+        let env = self.get_env();
+        unsafe { tuple::get_tuple(env.as_c_arg(), self.as_c_arg()) }
+            .map(|terms| terms.len())
+            .map_err(|_| Error::BadArg)
+    }
+
+    /// Returns the element at `index` of a tuple term, without copying the other elements.
+    ///
+    /// Returns `badarg` if the term is not a tuple, or if `index` is out of bounds.
+    ///
+    /// ### Elixir equivalent
+    /// ```elixir
+    /// elem(self_term, index)
+    /// ```
+    pub fn tuple_get(self, index: usize) -> NifResult<Term<'a>> {
+        let env = self.get_env();
+        let terms = unsafe { tuple::get_tuple(env.as_c_arg(), self.as_c_arg()) }
+            .map_err(|_| Error::BadArg)?;
+        match terms.get(index) {
+            Some(term) => Ok(unsafe { Term::new(env, *term) }),
+            None => Err(Error::BadArg),
+        }
+    }
+}
 
 /// Convert an Erlang tuple to a Rust vector. (To convert to a Rust tuple, use `term.decode()`
 /// instead.)
@@ -58,10 +86,12 @@ macro_rules! impl_nifencoder_nifdecoder_for_tuple {
             Encoder for tuple!( $( $tyvar ),* )
         {
             fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
-                let arr = [ $( Encoder::encode(&self.$index, env).as_c_arg() ),* ];
-                unsafe {
-                    Term::new(env, tuple::make_tuple(env.as_c_arg(), &arr))
-                }
+                env.tuple(vec![ $( Encoder::encode(&self.$index, env) ),* ])
+            }
+
+            fn encode_with<'a>(&self, env: Env<'a>, profile: EncodingProfile) -> Term<'a> {
+                let _ = profile;
+                env.tuple(vec![ $( Encoder::encode_with(&self.$index, env, profile) ),* ])
             }
         }
 
@@ -70,11 +100,17 @@ macro_rules! impl_nifencoder_nifdecoder_for_tuple {
         {
             fn decode(term: Term<'a>) -> NifResult<tuple!( $( $tyvar ),* )>
             {
-                match unsafe { tuple::get_tuple(term.get_env().as_c_arg(), term.as_c_arg()) } {
-                    Ok(elements) if elements.len() == count!( $( $index ),* ) =>
+                Self::decode_with(term, EncodingProfile::ELIXIR)
+            }
+
+            fn decode_with(term: Term<'a>, profile: EncodingProfile)
+                -> NifResult<tuple!( $( $tyvar ),* )>
+            {
+                let _ = profile;
+                match term.get_env().get_tuple(&term) {
+                    Some(elements) if elements.len() == count!( $( $index ),* ) =>
                         Ok(tuple!( $(
-                            (<$tyvar as Decoder>::decode(
-                                unsafe { Term::new(term.get_env(), elements[$index]) })?)
+                            (<$tyvar as Decoder>::decode_with(elements[$index], profile)?)
                         ),* )),
                     _ =>
                         Err(Error::BadArg),
@@ -92,3 +128,138 @@ impl_nifencoder_nifdecoder_for_tuple!(0: A, 1: B, 2: C, 3: D);
 impl_nifencoder_nifdecoder_for_tuple!(0: A, 1: B, 2: C, 3: D, 4: E);
 impl_nifencoder_nifdecoder_for_tuple!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F);
 impl_nifencoder_nifdecoder_for_tuple!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G);
+
+/// Matches a tuple term against a list of patterns, in the spirit of Erlang's pattern matching.
+///
+/// Each arm is a tuple pattern in curly braces followed by `=>` and an expression. Arms are
+/// tried in order. The first matching arm binds its variables and evaluates to `Ok(expression)`.
+/// If no arm matches, `Err(Error::BadArg)` is returned. Arms must be separated by commas.
+///
+/// The elements of a pattern can be:
+///
+/// * `atom("name")` matches exactly the atom `name`.
+/// * A literal, like `1` or `"text"`, matches an element that decodes to that value.
+/// * `_` matches any element.
+/// * `name: Type` decodes the element to `Type` and binds it to `name`.
+/// * `name @ ..`, as the last element, binds the remaining elements to `name` as a
+///   `Vec<Term>`.
+/// * `..`, as the last element, matches any number of remaining elements.
+///
+/// Elements are checked from left to right, and only the elements up to the first mismatch are
+/// decoded, so dispatching on a tag element is cheap even for large tuples.
+///
+/// ```ignore
+/// #[rustler::nif]
+/// fn handle(term: Term) -> NifResult<i64> {
+///     rustler::term_match!(term,
+///         {atom("add"), a: i64, b: i64} => a + b,
+///         {atom("neg"), a: i64} => -a,
+///         {atom("sum"), rest @ ..} => rest.len() as i64,
+///     )
+/// }
+/// ```
+#[macro_export]
+macro_rules! term_match {
+    ($term:expr, $($arms:tt)+) => {{
+        let term: $crate::Term = $term;
+        $crate::term_match!(@arms term size; $($arms)+)
+    }};
+
+    // Arms.
+    (@arms $term:ident $size:ident; $(,)?) => {
+        ::std::result::Result::Err($crate::Error::BadArg)
+    };
+    (@arms $term:ident $size:ident; { $($pat:tt)* } => $body:expr $(, $($arms:tt)*)?) => {
+        $crate::term_match!(
+            @elem $term $size [] [] (0usize); [$($pat)*]; $body; $($($arms)*)?
+        )
+    };
+
+    // Pattern elements. Each rule appends the checks for one element, and remembers the
+    // variables it binds.
+    (@elem $term:ident $size:ident [$($binds:ident)*] [$($checks:tt)*] ($($idx:tt)*);
+        [atom($name:expr) $(, $($pat:tt)*)?]; $body:expr; $($arms:tt)*) => {
+        $crate::term_match!(
+            @elem $term $size [$($binds)*] [$($checks)* {
+                let element = $term.tuple_get($($idx)*).ok()?;
+                let env = element.get_env();
+                let expected = $crate::types::atom::Atom::from_str(env, $name).ok()?;
+                if element != expected.to_term(env) {
+                    return ::std::option::Option::None;
+                }
+            }] ($($idx)* + 1); [$($($pat)*)?]; $body; $($arms)*
+        )
+    };
+    (@elem $term:ident $size:ident [$($binds:ident)*] [$($checks:tt)*] ($($idx:tt)*);
+        [_ $(, $($pat:tt)*)?]; $body:expr; $($arms:tt)*) => {
+        $crate::term_match!(
+            @elem $term $size [$($binds)*] [$($checks)*] ($($idx)* + 1);
+            [$($($pat)*)?]; $body; $($arms)*
+        )
+    };
+    (@elem $term:ident $size:ident [$($binds:ident)*] [$($checks:tt)*] ($($idx:tt)*);
+        [$lit:literal $(, $($pat:tt)*)?]; $body:expr; $($arms:tt)*) => {
+        $crate::term_match!(
+            @elem $term $size [$($binds)*] [$($checks)* {
+                if $term.tuple_get($($idx)*).ok()?.decode().ok() != ::std::option::Option::Some($lit) {
+                    return ::std::option::Option::None;
+                }
+            }] ($($idx)* + 1); [$($($pat)*)?]; $body; $($arms)*
+        )
+    };
+    (@elem $term:ident $size:ident [$($binds:ident)*] [$($checks:tt)*] ($($idx:tt)*);
+        [$var:ident @ ..]; $body:expr; $($arms:tt)*) => {
+        $crate::term_match!(
+            @done $term $size [$($binds)* $var] [$($checks)*
+                if $size < $($idx)* {
+                    return ::std::option::Option::None;
+                }
+                let $var: ::std::vec::Vec<$crate::Term> = ($($idx)*..$size)
+                    .map(|index| $term.tuple_get(index))
+                    .collect::<$crate::NifResult<_>>()
+                    .ok()?;
+            ]; $body; $($arms)*
+        )
+    };
+    (@elem $term:ident $size:ident [$($binds:ident)*] [$($checks:tt)*] ($($idx:tt)*);
+        [..]; $body:expr; $($arms:tt)*) => {
+        $crate::term_match!(
+            @done $term $size [$($binds)*] [$($checks)*
+                if $size < $($idx)* {
+                    return ::std::option::Option::None;
+                }
+            ]; $body; $($arms)*
+        )
+    };
+    (@elem $term:ident $size:ident [$($binds:ident)*] [$($checks:tt)*] ($($idx:tt)*);
+        [$var:ident : $ty:ty $(, $($pat:tt)*)?]; $body:expr; $($arms:tt)*) => {
+        $crate::term_match!(
+            @elem $term $size [$($binds)* $var] [$($checks)*
+                let $var: $ty = $term.tuple_get($($idx)*).ok()?.decode().ok()?;
+            ] ($($idx)* + 1); [$($($pat)*)?]; $body; $($arms)*
+        )
+    };
+    (@elem $term:ident $size:ident [$($binds:ident)*] [$($checks:tt)*] ($($idx:tt)*);
+        []; $body:expr; $($arms:tt)*) => {
+        $crate::term_match!(
+            @done $term $size [$($binds)*] [$($checks)*
+                if $size != $($idx)* {
+                    return ::std::option::Option::None;
+                }
+            ]; $body; $($arms)*
+        )
+    };
+
+    // All elements of an arm are known: try it, and fall back to the next arm.
+    (@done $term:ident $size:ident [$($binds:ident)*] [$($checks:tt)*]; $body:expr; $($arms:tt)*) => {{
+        let matched = (|| {
+            let $size = $term.tuple_size().ok()?;
+            $($checks)*
+            ::std::option::Option::Some(($($binds,)*))
+        })();
+        match matched {
+            ::std::option::Option::Some(($($binds,)*)) => ::std::result::Result::Ok($body),
+            ::std::option::Option::None => $crate::term_match!(@arms $term $size; $($arms)*),
+        }
+    }};
+}
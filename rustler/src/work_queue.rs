@@ -0,0 +1,312 @@
+//! Bounded queues of jobs submitted from Elixir and run by native worker threads.
+//!
+//! A `WorkQueue` owns a fixed set of worker threads, which run a handler on the jobs pushed into
+//! the queue, in order. The queue holds at most `capacity` jobs waiting for a worker: past that,
+//! `try_push` fails with `PushError::Full`, which a NIF returns as `{:error, :full}`, so that
+//! callers are pushed back instead of piling up work. Workers are not managed by the VM, so the
+//! handler can reply with an `OwnedEnv`:
+//!
+//! ```ignore
+//! struct Resizer {
+//!     queue: WorkQueue<(LocalPid, OwnedBinary)>,
+//! }
+//!
+//! #[rustler::nif]
+//! fn resizer_new(capacity: usize, workers: usize) -> ResourceArc<Resizer> {
+//!     ResourceArc::new(Resizer {
+//!         queue: WorkQueue::new(capacity, workers, |(pid, image): (LocalPid, OwnedBinary)| {
+//!             let thumbnail = resize(image.as_slice());
+//!             if let Ok(mut owned_env) = OwnedEnv::try_new() {
+//!                 owned_env.send_and_clear(&pid, |env| thumbnail.encode(env));
+//!             }
+//!         }),
+//!     })
+//! }
+//!
+//! #[rustler::nif]
+//! fn resizer_submit(env: Env, resizer: ResourceArc<Resizer>, image: Binary) -> NifResult<Atom> {
+//!     resizer.queue.try_push((env.pid(), image.to_owned().unwrap()))?;
+//!     Ok(atoms::ok())
+//! }
+//! ```
+//!
+//! The queue is usually stored in a resource. When it is dropped, or closed with `close`, it
+//! stops accepting jobs, and the workers exit once they have run the jobs already queued.
+
+use crate::types::map::map_new;
+use crate::{Encoder, Env, Error, Term};
+use std::collections::VecDeque;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+mod atoms {
+    crate::atoms! {
+        full,
+        closed,
+        depth,
+        capacity,
+        workers,
+        busy,
+        enqueued,
+        rejected,
+        completed,
+        panicked,
+    }
+}
+
+/// A bounded queue of jobs run by worker threads. See the module documentation.
+pub struct WorkQueue<J> {
+    shared: Arc<Shared<J>>,
+    workers: usize,
+}
+
+struct Shared<J> {
+    state: Mutex<State<J>>,
+    available: Condvar,
+    capacity: usize,
+    busy: AtomicUsize,
+    enqueued: AtomicU64,
+    rejected: AtomicU64,
+    completed: AtomicU64,
+    panicked: AtomicU64,
+}
+
+struct State<J> {
+    jobs: VecDeque<J>,
+    closed: bool,
+}
+
+impl<J: Send + 'static> WorkQueue<J> {
+    /// Starts `workers` threads running `handler` on the jobs of a queue holding at most
+    /// `capacity` jobs. A panic in `handler` is caught, and counted in the statistics.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` or `workers` is zero, or if a thread can't be spawned.
+    pub fn new<F>(capacity: usize, workers: usize, handler: F) -> Self
+    where
+        F: Fn(J) + Send + Sync + 'static,
+    {
+        assert!(capacity > 0, "a work queue needs a capacity");
+        assert!(workers > 0, "a work queue needs workers");
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                jobs: VecDeque::with_capacity(capacity),
+                closed: false,
+            }),
+            available: Condvar::new(),
+            capacity,
+            busy: AtomicUsize::new(0),
+            enqueued: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            completed: AtomicU64::new(0),
+            panicked: AtomicU64::new(0),
+        });
+        let handler = Arc::new(handler);
+
+        for index in 0..workers {
+            let shared = shared.clone();
+            let handler = handler.clone();
+            thread::Builder::new()
+                .name(format!("rustler-work-queue-{}", index))
+                .spawn(move || shared.work(&*handler))
+                .expect("failed to spawn a work queue thread");
+        }
+
+        WorkQueue { shared, workers }
+    }
+}
+
+impl<J> WorkQueue<J> {
+    /// Queues `job`, or gives it back if the queue is full or closed.
+    pub fn try_push(&self, job: J) -> Result<(), PushError<J>> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.closed {
+            return Err(PushError::Closed(job));
+        }
+        if state.jobs.len() >= self.shared.capacity {
+            drop(state);
+            self.shared.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(PushError::Full(job));
+        }
+        state.jobs.push_back(job);
+        drop(state);
+
+        self.shared.enqueued.fetch_add(1, Ordering::Relaxed);
+        self.shared.available.notify_one();
+        Ok(())
+    }
+
+    /// Returns the number of jobs waiting for a worker.
+    pub fn depth(&self) -> usize {
+        self.shared.state.lock().unwrap().jobs.len()
+    }
+
+    /// Returns the maximum number of jobs waiting for a worker.
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+
+    /// Returns whether the queue was closed.
+    pub fn is_closed(&self) -> bool {
+        self.shared.state.lock().unwrap().closed
+    }
+
+    /// Stops accepting jobs. The workers exit once they have run the jobs already queued.
+    pub fn close(&self) {
+        self.shared.state.lock().unwrap().closed = true;
+        self.shared.available.notify_all();
+    }
+
+    /// Returns the statistics of the queue.
+    pub fn stats(&self) -> QueueStats {
+        QueueStats {
+            depth: self.depth(),
+            capacity: self.shared.capacity,
+            workers: self.workers,
+            busy: self.shared.busy.load(Ordering::Relaxed),
+            enqueued: self.shared.enqueued.load(Ordering::Relaxed),
+            rejected: self.shared.rejected.load(Ordering::Relaxed),
+            completed: self.shared.completed.load(Ordering::Relaxed),
+            panicked: self.shared.panicked.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl<J> Drop for WorkQueue<J> {
+    fn drop(&mut self) {
+        // The workers are not joined: the queue is usually dropped by the destructor of a
+        // resource, on a scheduler thread that must not wait for jobs to complete.
+        self.close();
+    }
+}
+
+impl<J> Shared<J> {
+    /// Runs the jobs of the queue, until it is closed and empty.
+    fn work(&self, handler: &dyn Fn(J)) {
+        loop {
+            let job = {
+                let mut state = self.state.lock().unwrap();
+                loop {
+                    if let Some(job) = state.jobs.pop_front() {
+                        break job;
+                    }
+                    if state.closed {
+                        return;
+                    }
+                    state = self.available.wait(state).unwrap();
+                }
+            };
+
+            self.busy.fetch_add(1, Ordering::Relaxed);
+            let result = panic::catch_unwind(AssertUnwindSafe(|| handler(job)));
+            self.busy.fetch_sub(1, Ordering::Relaxed);
+
+            match result {
+                Ok(()) => self.completed.fetch_add(1, Ordering::Relaxed),
+                Err(_) => self.panicked.fetch_add(1, Ordering::Relaxed),
+            };
+        }
+    }
+}
+
+/// The statistics of a `WorkQueue`, encoded as a map.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueueStats {
+    /// The number of jobs waiting for a worker.
+    pub depth: usize,
+    pub capacity: usize,
+    pub workers: usize,
+    /// The number of workers running a job.
+    pub busy: usize,
+    /// The number of jobs queued since the queue was created.
+    pub enqueued: u64,
+    /// The number of jobs refused because the queue was full.
+    pub rejected: u64,
+    /// The number of jobs run to completion.
+    pub completed: u64,
+    /// The number of jobs whose handler panicked.
+    pub panicked: u64,
+}
+
+impl Encoder for QueueStats {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        let entries = [
+            (atoms::depth(), self.depth as u64),
+            (atoms::capacity(), self.capacity as u64),
+            (atoms::workers(), self.workers as u64),
+            (atoms::busy(), self.busy as u64),
+            (atoms::enqueued(), self.enqueued),
+            (atoms::rejected(), self.rejected),
+            (atoms::completed(), self.completed),
+            (atoms::panicked(), self.panicked),
+        ];
+
+        entries.iter().fold(map_new(env), |map, (key, value)| {
+            map.map_put(key.encode(env), value.encode(env)).unwrap()
+        })
+    }
+}
+
+/// The error of a job refused by a `WorkQueue`, which holds the job.
+///
+/// Encodes as `:full` or `:closed`, and converts into an `Error` returning `{:error, :full}` or
+/// `{:error, :closed}`.
+pub enum PushError<J> {
+    /// The queue holds `capacity` jobs already.
+    Full(J),
+    /// The queue was closed.
+    Closed(J),
+}
+
+impl<J> PushError<J> {
+    /// Returns the refused job.
+    pub fn into_inner(self) -> J {
+        match self {
+            PushError::Full(job) | PushError::Closed(job) => job,
+        }
+    }
+}
+
+impl<J> fmt::Debug for PushError<J> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PushError::Full(_) => f.write_str("Full(..)"),
+            PushError::Closed(_) => f.write_str("Closed(..)"),
+        }
+    }
+}
+
+impl<J> fmt::Display for PushError<J> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PushError::Full(_) => f.write_str("work queue is full"),
+            PushError::Closed(_) => f.write_str("work queue is closed"),
+        }
+    }
+}
+
+impl<J> std::error::Error for PushError<J> {}
+
+impl<J> Encoder for PushError<J> {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match self {
+            PushError::Full(_) => atoms::full().encode(env),
+            PushError::Closed(_) => atoms::closed().encode(env),
+        }
+    }
+}
+
+impl<J> From<PushError<J>> for Error {
+    fn from(err: PushError<J>) -> Error {
+        let reason = match err {
+            PushError::Full(_) => atoms::full(),
+            PushError::Closed(_) => atoms::closed(),
+        };
+        Error::Term(Box::new(reason))
+    }
+}
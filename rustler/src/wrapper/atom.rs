@@ -21,6 +21,42 @@ pub unsafe fn make_existing_atom(env: NIF_ENV, name: &[u8]) -> Option<NIF_TERM>
     Some(atom_out)
 }
 
+/// Returns the length of the Latin-1 text of an atom, or `None` if `term` is not an atom or has
+/// characters outside Latin-1.
+///
+/// The VM doesn't use the environment to read atoms, so `env` can be null.
+pub unsafe fn get_atom_length(env: NIF_ENV, term: NIF_TERM) -> Option<usize> {
+    let mut len = 0;
+    match rustler_sys::enif_get_atom_length(env, term, &mut len, ERL_NIF_LATIN1) {
+        0 => None,
+        _ => Some(len as usize),
+    }
+}
+
+/// Copies the Latin-1 text of an atom into `buf`, truncated to the length of `buf`, and returns
+/// the number of bytes copied. Returns 0 if `term` is not an atom or has characters outside
+/// Latin-1.
+///
+/// The VM doesn't use the environment to read atoms, so `env` can be null.
+pub unsafe fn get_atom_into(env: NIF_ENV, term: NIF_TERM, buf: &mut [u8]) -> usize {
+    // enif_get_atom() fails unless the whole text and its null byte fit, and atoms are at most
+    // 255 characters long.
+    let mut text = [0u8; 256];
+    let nbytes = rustler_sys::enif_get_atom(
+        env,
+        term,
+        text.as_mut_ptr(),
+        text.len() as c_uint,
+        ERL_NIF_LATIN1,
+    );
+    if nbytes <= 0 {
+        return 0;
+    }
+    let len = (nbytes as usize - 1).min(buf.len());
+    buf[..len].copy_from_slice(&text[..len]);
+    len
+}
+
 /// Get the contents of this atom as a string.
 ///
 /// If you only need to test for equality, comparing the terms directly
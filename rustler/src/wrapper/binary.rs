@@ -1,5 +1,5 @@
 use crate::wrapper::size_t;
-pub(in crate) use rustler_sys::ErlNifBinary;
+pub(crate) use rustler_sys::ErlNifBinary;
 use std::mem::MaybeUninit;
 
 pub unsafe fn alloc(size: size_t) -> Option<ErlNifBinary> {
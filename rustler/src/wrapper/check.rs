@@ -20,3 +20,8 @@ impl_check_fun!(is_pid, rustler_sys::enif_is_pid);
 impl_check_fun!(is_port, rustler_sys::enif_is_port);
 impl_check_fun!(is_ref, rustler_sys::enif_is_ref);
 impl_check_fun!(is_tuple, rustler_sys::enif_is_tuple);
+
+#[cfg(nif_version_2_15)]
+pub unsafe fn term_type(env: NIF_ENV, term: NIF_TERM) -> rustler_sys::ErlNifTermType {
+    rustler_sys::enif_term_type(env, term)
+}
@@ -16,3 +16,11 @@ pub unsafe fn get_local_pid(env: NIF_ENV, term: NIF_TERM) -> Option<ErlNifPid> {
 pub unsafe fn make_pid(env: NIF_ENV, pid: ErlNifPid) -> NIF_TERM {
     rustler_sys::enif_make_pid(env, pid)
 }
+
+pub unsafe fn whereis_pid(env: NIF_ENV, name: NIF_TERM) -> Option<ErlNifPid> {
+    let mut pid = MaybeUninit::uninit();
+    if rustler_sys::enif_whereis_pid(env, name, pid.as_mut_ptr()) == 0 {
+        return None;
+    }
+    Some(pid.assume_init())
+}
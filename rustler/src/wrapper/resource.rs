@@ -36,6 +36,51 @@ pub unsafe fn open_resource_type(
     }
 }
 
+/// Like `open_resource_type`, with all the callbacks of `init`.
+pub unsafe fn open_resource_type_x(
+    env: NIF_ENV,
+    name: &[u8],
+    init: &rustler_sys::ErlNifResourceTypeInit,
+    flags: NifResourceFlags,
+) -> Option<NIF_RESOURCE_TYPE> {
+    // Panic if name is not null-terminated.
+    assert_eq!(name.last().cloned(), Some(0u8));
+
+    let res = {
+        let mut tried = MaybeUninit::uninit();
+        rustler_sys::enif_open_resource_type_x(env, name.as_ptr(), init, flags, tried.as_mut_ptr())
+    };
+
+    if res.is_null() {
+        None
+    } else {
+        Some(res)
+    }
+}
+
+/// Like `open_resource_type_x`, reading all the members of `init`.
+#[cfg(nif_version_2_16)]
+pub unsafe fn init_resource_type(
+    env: NIF_ENV,
+    name: &[u8],
+    init: &rustler_sys::ErlNifResourceTypeInit2_16,
+    flags: NifResourceFlags,
+) -> Option<NIF_RESOURCE_TYPE> {
+    // Panic if name is not null-terminated.
+    assert_eq!(name.last().cloned(), Some(0u8));
+
+    let res = {
+        let mut tried = MaybeUninit::uninit();
+        rustler_sys::enif_init_resource_type(env, name.as_ptr(), init, flags, tried.as_mut_ptr())
+    };
+
+    if res.is_null() {
+        None
+    } else {
+        Some(res)
+    }
+}
+
 // Functionally incomplete
 pub unsafe fn get_resource(
     env: NIF_ENV,
@@ -0,0 +1,169 @@
+//! Long computations on normal schedulers, split into slices with `enif_schedule_nif`.
+//!
+//! A NIF should return within about a millisecond, or run on a dirty scheduler. `Yielding` is a
+//! third option for computations that can be done in steps: returned from a NIF, it runs the
+//! steps of a `Resumable` computation until the timeslice of the NIF is used up, then yields back
+//! to the VM with `enif_schedule_nif`, and carries on where it stopped when the process is
+//! scheduled again. The value the computation ends with is returned to the caller, which sees a
+//! regular call.
+//!
+//! A computation is a type implementing `Resumable`, or a closure over its state returning a
+//! `Step`:
+//!
+//! ```ignore
+//! #[rustler::nif]
+//! fn checksum(data: Vec<u8>) -> Yielding<impl Resumable> {
+//!     let mut offset = 0;
+//!     let mut sum = 0u64;
+//!     Yielding::new(move || {
+//!         let end = (offset + 4096).min(data.len());
+//!         sum = data[offset..end].iter().fold(sum, |sum, &b| sum.wrapping_mul(31) ^ u64::from(b));
+//!         offset = end;
+//!         if offset == data.len() { Step::Done(sum) } else { Step::Yield }
+//!     })
+//! }
+//! ```
+//!
+//! The computation is kept in a resource between slices, whose type must be registered by
+//! calling `rustler::yielding::load(env)` from the `load` callback of the NIF library. Otherwise,
+//! returning a `Yielding` raises an error saying so. A step should take well under a millisecond:
+//! the timeslice is only checked between steps.
+
+use crate::codegen_runtime::{NifReturnable, NifReturned, NIF_ENV, NIF_TERM};
+use crate::schedule::SchedulerFlags;
+use crate::{Encoder, Env, ResourceArc, Term};
+use std::ffi::CString;
+use std::iter;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// The outcome of a step of a `Resumable` computation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step<T> {
+    /// The computation isn't done, and is resumed with another step.
+    Yield,
+    /// The computation is done, and the NIF returns the value.
+    Done(T),
+}
+
+/// A computation done in steps. See the module documentation.
+pub trait Resumable: Send + 'static {
+    /// The value returned by the NIF, like the return type of a `#[rustler::nif]` function.
+    type Output: NifReturnable;
+
+    /// Does the next step of the computation.
+    fn step(&mut self) -> Step<Self::Output>;
+}
+
+impl<F, T> Resumable for F
+where
+    F: FnMut() -> Step<T> + Send + 'static,
+    T: NifReturnable,
+{
+    type Output = T;
+
+    fn step(&mut self) -> Step<T> {
+        self()
+    }
+}
+
+/// A `Resumable` computation returned from a NIF, which runs it across as many timeslices as
+/// needed. See the module documentation.
+pub struct Yielding<R> {
+    task: R,
+}
+
+impl<R: Resumable> Yielding<R> {
+    pub fn new(task: R) -> Self {
+        Yielding { task }
+    }
+}
+
+/// A computation in progress, with its type erased so that a single resource type and a single
+/// continuation function serve all computations.
+pub(crate) trait PendingTask: Send {
+    /// Does the next step, and returns the result of the NIF when the computation is done.
+    fn step(&mut self, env: Env) -> Option<NifReturned>;
+}
+
+impl<R: Resumable> PendingTask for Yielding<R> {
+    fn step(&mut self, env: Env) -> Option<NifReturned> {
+        match self.task.step() {
+            Step::Yield => None,
+            Step::Done(output) => Some(unsafe { output.into_returned(env) }),
+        }
+    }
+}
+
+struct YieldState {
+    task: Mutex<Box<dyn PendingTask>>,
+}
+
+/// Whether `load` registered the resource type of `YieldState`.
+static LOADED: AtomicBool = AtomicBool::new(false);
+
+crate::resource_type!(YieldState, name = "YieldingState");
+
+/// Registers the resource type holding yielding computations between slices. Call this from the
+/// `load` callback.
+pub fn load(env: Env) -> bool {
+    if !crate::resource::register::<YieldState>(env) {
+        return false;
+    }
+    LOADED.store(true, Ordering::Release);
+    true
+}
+
+unsafe impl<R: Resumable> NifReturnable for Yielding<R> {
+    unsafe fn into_returned(self, env: Env) -> NifReturned {
+        start(env, Box::new(self))
+    }
+}
+
+/// Runs `task` until it is done, in as many slices as needed.
+pub(crate) fn start(env: Env, task: Box<dyn PendingTask>) -> NifReturned {
+    if !LOADED.load(Ordering::Acquire) {
+        let reason = "rustler::yielding::load(env) must be called from the load callback";
+        return NifReturned::Raise(reason.encode(env).as_c_arg());
+    }
+
+    let state = ResourceArc::new(YieldState {
+        task: Mutex::new(task),
+    });
+    run_slice(env, &state)
+}
+
+/// Runs steps until the computation is done, or the timeslice is used up.
+fn run_slice(env: Env, state: &ResourceArc<YieldState>) -> NifReturned {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut task = state.task.lock().unwrap();
+        // The steps stop once the timeslice is used up, as `Budgeted` reports the time they take.
+        for () in env.budgeted(iter::repeat(())) {
+            if let Some(returned) = task.step(env) {
+                return returned;
+            }
+        }
+
+        NifReturned::Reschedule {
+            fun_name: CString::new("rustler_yielding").unwrap(),
+            flags: SchedulerFlags::Normal,
+            fun: resume,
+            args: vec![state.encode(env).as_c_arg()],
+        }
+    }));
+
+    result.unwrap_or_else(|err| crate::panic_handler::panicked(env, err))
+}
+
+unsafe extern "C" fn resume(nif_env: NIF_ENV, argc: i32, argv: *const NIF_TERM) -> NIF_TERM {
+    let lifetime = ();
+    let env = Env::new(&lifetime, nif_env);
+    let args = std::slice::from_raw_parts(argv, argc as usize);
+
+    let returned = match Term::new(env, args[0]).decode::<ResourceArc<YieldState>>() {
+        Ok(state) => run_slice(env, &state),
+        Err(_) => NifReturned::BadArg,
+    };
+    returned.apply(env)
+}
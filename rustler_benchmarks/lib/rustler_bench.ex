@@ -0,0 +1,20 @@
+defmodule RustlerBench do
+  @moduledoc """
+  Runs the encoder and decoder benchmarks of `native/rustler_bench` inside the VM.
+
+      mix run -e "RustlerBench.run()"
+      mix run -e 'RustlerBench.run("decode")'
+
+  The optional argument only runs the benchmarks whose name contains it.
+  """
+
+  use Rustler,
+    otp_app: :rustler_bench,
+    crate: :rustler_bench,
+    mode: :release
+
+  def run(filter \\ nil), do: run_benchmarks(filter)
+
+  @doc false
+  def run_benchmarks(_filter), do: :erlang.nif_error(:nif_not_loaded)
+end
@@ -0,0 +1,22 @@
+defmodule RustlerBench.Mixfile do
+  use Mix.Project
+
+  def project do
+    [
+      app: :rustler_bench,
+      version: "0.0.1",
+      elixir: "~> 1.2",
+      compilers: [:rustler] ++ Mix.compilers(),
+      start_permanent: false,
+      deps: deps()
+    ]
+  end
+
+  def application do
+    [applications: [:logger]]
+  end
+
+  defp deps do
+    [{:rustler, path: "../rustler_mix", runtime: false}]
+  end
+end
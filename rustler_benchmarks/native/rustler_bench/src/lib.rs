@@ -0,0 +1,105 @@
+use criterion::{black_box, Criterion};
+use rustler::bench::BenchEnv;
+use rustler::types::atom;
+use rustler::{Atom, Encoder, NifMap, NifStruct, NifTuple, Term};
+
+#[derive(Clone, NifTuple)]
+pub struct Point {
+    x: i64,
+    y: i64,
+}
+
+#[derive(Clone, NifMap)]
+pub struct Config {
+    name: String,
+    retries: u32,
+    verbose: bool,
+}
+
+#[derive(Clone, NifStruct)]
+#[module = "User"]
+pub struct User {
+    id: u64,
+    name: String,
+    email: Option<String>,
+    tags: Vec<String>,
+}
+
+fn user() -> User {
+    User {
+        id: 42,
+        name: "Joe".to_string(),
+        email: Some("joe@example.com".to_string()),
+        tags: vec!["admin".to_string(), "ops".to_string()],
+    }
+}
+
+fn config() -> Config {
+    Config {
+        name: "default".to_string(),
+        retries: 3,
+        verbose: false,
+    }
+}
+
+/// Benchmarks encoding `value` into a term.
+fn bench_encode<T: Encoder>(c: &mut Criterion, name: &str, value: T) {
+    let mut env = BenchEnv::new();
+    c.bench_function(&format!("encode {}", name), |b| {
+        b.iter(|| {
+            env.run(|env| {
+                black_box(value.encode(env));
+            })
+        })
+    });
+}
+
+/// Benchmarks decoding a term created by encoding `value`.
+fn bench_decode<T>(c: &mut Criterion, name: &str, value: T)
+where
+    T: Encoder + for<'a> rustler::Decoder<'a>,
+{
+    let mut env = BenchEnv::new();
+    c.bench_function(&format!("decode {}", name), |b| {
+        b.iter(|| {
+            env.run(|env| {
+                let term: Term = value.encode(env);
+                black_box(term.decode::<T>().unwrap());
+            })
+        })
+    });
+}
+
+fn benchmarks(c: &mut Criterion) {
+    let numbers: Vec<i64> = (0..1000).collect();
+    let text = "lorem ipsum ".repeat(100);
+
+    bench_encode(c, "i64", 123_456_789i64);
+    bench_encode(c, "list of 1000 i64", numbers.clone());
+    bench_encode(c, "string", text.clone());
+    bench_encode(c, "NifTuple", Point { x: 1, y: 2 });
+    bench_encode(c, "NifMap", config());
+    bench_encode(c, "NifStruct", user());
+
+    bench_decode(c, "i64", 123_456_789i64);
+    bench_decode(c, "list of 1000 i64", numbers);
+    bench_decode(c, "string", text);
+    bench_decode(c, "NifTuple", Point { x: 1, y: 2 });
+    bench_decode(c, "NifMap", config());
+    bench_decode(c, "NifStruct", user());
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn run_benchmarks(filter: Option<String>) -> Atom {
+    let mut criterion = Criterion::default();
+    if let Some(filter) = filter {
+        criterion = criterion.with_filter(filter);
+    }
+
+    benchmarks(&mut criterion);
+    criterion.final_summary();
+
+    atom::ok()
+}
+
+rustler::init!("Elixir.RustlerBench", [run_benchmarks]);
@@ -1,8 +1,11 @@
 // TODO When we settle for a minimum version of Rust >= 1.42, remove this.
 #![allow(clippy::match_like_matches_macro)]
 
+use heck::{CamelCase, KebabCase, MixedCase, ShoutySnakeCase, SnakeCase};
 use proc_macro2::{Span, TokenStream};
-use syn::{Data, Field, Fields, Ident, Lit, Meta, NestedMeta, Variant};
+use quote::ToTokens;
+use std::collections::HashSet;
+use syn::{Data, Field, Fields, GenericParam, Generics, Ident, Lit, Meta, NestedMeta, Variant};
 
 use super::RustlerAttr;
 
@@ -15,8 +18,10 @@ pub(crate) struct Context<'a> {
     pub attrs: Vec<RustlerAttr>,
     pub ident: &'a proc_macro2::Ident,
     pub ident_with_lifetime: proc_macro2::TokenStream,
+    pub generics: &'a Generics,
     pub variants: Option<Vec<&'a Variant>>,
     pub struct_fields: Option<Vec<&'a Field>>,
+    pub skipped_fields: Vec<&'a Field>,
     pub is_tuple_struct: bool,
 }
 
@@ -29,6 +34,16 @@ impl<'a> Context<'a> {
             .flatten()
             .collect();
 
+        if attrs.iter().any(|attr| match attr {
+            RustlerAttr::Rename(_) => true,
+            _ => false,
+        }) {
+            panic!("`rename` is only allowed on fields and variants, use `rename_all` instead");
+        }
+        if attrs.iter().any(Context::is_field_attr) {
+            panic!("`default` and `skip` are only allowed on fields");
+        }
+
         //
         // Default: generate encoder and decoder
         //
@@ -44,10 +59,19 @@ impl<'a> Context<'a> {
         };
 
         let ident = &ast.ident;
-        let ident_with_lifetime = if has_lifetime {
-            quote! { #ident <'a> }
-        } else {
+        let mut params: Vec<TokenStream> = Vec::new();
+        if has_lifetime {
+            params.push(quote! { 'a });
+        }
+        params.extend(ast.generics.params.iter().filter_map(|param| match param {
+            GenericParam::Type(param) => Some(param.ident.to_token_stream()),
+            GenericParam::Const(param) => Some(param.ident.to_token_stream()),
+            GenericParam::Lifetime(_) => None,
+        }));
+        let ident_with_lifetime = if params.is_empty() {
             quote! { #ident }
+        } else {
+            quote! { #ident <#(#params),*> }
         };
 
         let variants = match ast.data {
@@ -55,9 +79,15 @@ impl<'a> Context<'a> {
             _ => None,
         };
 
-        let struct_fields = match ast.data {
-            Data::Struct(ref data_struct) => Some(data_struct.fields.iter().collect()),
-            _ => None,
+        let (struct_fields, skipped_fields) = match ast.data {
+            Data::Struct(ref data_struct) => {
+                let (skipped, fields) = data_struct
+                    .fields
+                    .iter()
+                    .partition(|field| Self::is_skipped(field));
+                (Some(fields), skipped)
+            }
+            _ => (None, Vec::new()),
         };
 
         let is_tuple_struct = match ast.data {
@@ -68,16 +98,50 @@ impl<'a> Context<'a> {
             _ => false,
         };
 
+        if is_tuple_struct && !skipped_fields.is_empty() {
+            panic!("`skip` is only allowed on named fields");
+        }
+
         Self {
             attrs,
             ident,
             ident_with_lifetime,
+            generics: &ast.generics,
             variants,
             struct_fields,
+            skipped_fields,
             is_tuple_struct,
         }
     }
 
+    /// Returns the generics of an impl for the type: `lifetime`, followed by the type and const
+    /// parameters of the type. Type parameters keep their own bounds, and are also bounded by
+    /// `bound`, if any.
+    pub fn impl_generics(&self, lifetime: TokenStream, bound: Option<TokenStream>) -> TokenStream {
+        let params = self.generics.params.iter().filter_map(|param| match param {
+            GenericParam::Type(param) => {
+                let ident = &param.ident;
+                let bounds = &param.bounds;
+                Some(match bound {
+                    Some(ref bound) if bounds.is_empty() => quote! { #ident: #bound },
+                    Some(ref bound) => quote! { #ident: #bounds + #bound },
+                    None => quote! { #ident: #bounds },
+                })
+            }
+            GenericParam::Const(param) => {
+                let ident = &param.ident;
+                let ty = &param.ty;
+                Some(quote! { const #ident: #ty })
+            }
+            GenericParam::Lifetime(_) => None,
+        });
+        quote! { <#lifetime #(, #params)*> }
+    }
+
+    pub fn where_clause(&self) -> Option<&syn::WhereClause> {
+        self.generics.where_clause.as_ref()
+    }
+
     pub fn atoms_module_name(&self, span: Span) -> Ident {
         Ident::new(&format!("RUSTLER_ATOMS_{}", self.ident), span)
     }
@@ -96,25 +160,312 @@ impl<'a> Context<'a> {
         })
     }
 
+    /// Whether `BuildTerm` is implemented too, with `#[rustler(build_term)]`.
+    pub fn build_term(&self) -> bool {
+        self.attrs.iter().any(|attr| match attr {
+            RustlerAttr::BuildTerm => true,
+            _ => false,
+        })
+    }
+
+    pub fn key_field(&self) -> Option<&'a Field> {
+        let key = self.attrs.iter().find_map(|attr| match attr {
+            RustlerAttr::Key(ref key) => Some(key),
+            _ => None,
+        })?;
+
+        let field = self
+            .struct_fields
+            .as_ref()
+            .and_then(|fields| {
+                fields.iter().find(|field| match field.ident {
+                    Some(ref ident) => Self::remove_raw(&ident.to_string()) == key,
+                    None => false,
+                })
+            })
+            .unwrap_or_else(|| panic!("Key field `{}` does not exist", key));
+
+        Some(field)
+    }
+
+    pub fn gen_keyed(&self) -> TokenStream {
+        let struct_type = &self.ident_with_lifetime;
+
+        match self.key_field() {
+            Some(field) => {
+                let field_ident = field.ident.as_ref().unwrap();
+                let impl_generics =
+                    self.impl_generics(quote! { 'b }, Some(quote! { ::rustler::Encoder }));
+                let where_clause = self.where_clause();
+
+                quote! {
+                    impl #impl_generics ::rustler::types::keyed::Keyed for #struct_type #where_clause {
+                        fn key<'a>(&self, env: ::rustler::Env<'a>) -> ::rustler::Term<'a> {
+                            use ::rustler::Encoder;
+                            self.#field_ident.encode(env)
+                        }
+                    }
+                }
+            }
+            None => quote! {},
+        }
+    }
+
+    /// Generates `summary_term` with `#[rustler(summary)]`, for structs that have an encoder.
+    pub fn gen_summary(&self) -> TokenStream {
+        let struct_type = &self.ident_with_lifetime;
+        let summary = self.attrs.iter().any(|attr| match attr {
+            RustlerAttr::Summary => true,
+            _ => false,
+        });
+
+        if !summary || !self.encode() {
+            return quote! {};
+        }
+
+        let impl_generics = self.impl_generics(quote! { 'a }, Some(quote! { ::rustler::Encoder }));
+        let where_clause = self.where_clause();
+
+        quote! {
+            impl #impl_generics #struct_type #where_clause {
+                /// Encodes `self` and truncates the term with `rustler::summary::summarize`,
+                /// to include it in errors and logs.
+                pub fn summary_term<'b>(&self, env: ::rustler::Env<'b>) -> ::rustler::Term<'b> {
+                    ::rustler::summary::summarize(
+                        ::rustler::Encoder::encode(self, env),
+                        &::rustler::summary::SummaryLimits::default(),
+                    )
+                }
+            }
+        }
+    }
+
+    /// Whether the struct is an exception, with `#[rustler(exception)]` or an `__exception__`
+    /// field.
+    pub fn exception(&self) -> bool {
+        self.exception_attr() || self.exception_field()
+    }
+
+    /// Whether `__exception__` has to be added to the encoded struct, because there is no
+    /// field for it.
+    pub fn exception_attr(&self) -> bool {
+        self.attrs.iter().any(|attr| match attr {
+            RustlerAttr::Exception => true,
+            _ => false,
+        }) && !self.exception_field()
+    }
+
+    fn exception_field(&self) -> bool {
+        match self.struct_fields {
+            Some(ref fields) => fields
+                .iter()
+                .any(|field| field.ident.is_some() && self.field_name(field) == "__exception__"),
+            None => false,
+        }
+    }
+
+    pub fn profile(&self) -> Option<TokenStream> {
+        self.attrs.iter().find_map(|attr| match attr {
+            RustlerAttr::Profile(ref profile) => match profile.as_ref() {
+                "elixir" => Some(quote! { ::rustler::EncodingProfile::ELIXIR }),
+                "erlang" => Some(quote! { ::rustler::EncodingProfile::ERLANG }),
+                other => panic!(
+                    "Unknown profile `{}`. Allowed profiles: [\"elixir\", \"erlang\"]",
+                    other
+                ),
+            },
+            _ => None,
+        })
+    }
+
+    /// Prefixes `body`, the body of a generated `encode_with` or `decode_with`, so that the
+    /// profile given by `#[rustler(profile = "...")]`, if any, replaces its `profile` argument.
+    pub fn with_profile(&self, body: TokenStream) -> TokenStream {
+        match self.profile() {
+            Some(profile) => quote! {
+                let _ = profile;
+                let profile = #profile;
+                #body
+            },
+            None => quote! {
+                let _ = profile;
+                #body
+            },
+        }
+    }
+
+    /// Returns the definitions of the atoms of the fields, for `rustler::atoms!`.
+    ///
+    /// Panics if two fields have the same name once renamed, as they would map to the same key.
     pub fn field_atoms(&self) -> Option<Vec<TokenStream>> {
         self.struct_fields.as_ref().map(|struct_fields| {
+            let mut names = HashSet::new();
             struct_fields
                 .iter()
                 .map(|field| {
                     let atom_fun = Self::field_to_atom_fun(field);
-
-                    let ident = field.ident.as_ref().unwrap();
-                    let ident_str = ident.to_string();
-                    let ident_str = Self::remove_raw(&ident_str);
+                    let name = self.field_name(field);
+                    if !names.insert(name.clone()) {
+                        panic!(
+                            "Field `{}` is named `{}` like another field, use `rename` to tell them apart",
+                            field.ident.as_ref().unwrap(),
+                            name
+                        );
+                    }
 
                     quote! {
-                        #atom_fun = #ident_str,
+                        #atom_fun = #name,
                     }
                 })
                 .collect()
         })
     }
 
+    /// Returns the name of `field` in Elixir: the one given by `#[rustler(rename = "...")]`, or
+    /// the identifier of the field, converted by `#[rustler(rename_all = "...")]` if any.
+    pub fn field_name(&self, field: &Field) -> String {
+        if let Some(name) = Self::rename_attr(&field.attrs) {
+            return name;
+        }
+
+        let ident = field.ident.as_ref().unwrap();
+        let name = Self::remove_raw(&ident.to_string()).to_string();
+        match self.rename_all() {
+            Some(rule) => Self::apply_rename_rule(&name, rule),
+            None => name,
+        }
+    }
+
+    /// Returns the name of a field of a struct variant: the one given by
+    /// `#[rustler(rename = "...")]`, or the identifier of the field. `rename_all` only applies to
+    /// the variants of an enum.
+    pub fn variant_field_name(field: &Field) -> String {
+        if let Some(name) = Self::rename_attr(&field.attrs) {
+            return name;
+        }
+
+        let ident = field.ident.as_ref().unwrap();
+        Self::remove_raw(&ident.to_string()).to_string()
+    }
+
+    /// Returns the atom of `variant`: the one given by `#[rustler(rename = "...")]`, or the
+    /// identifier of the variant, converted by `#[rustler(rename_all = "...")]` if any, and to
+    /// snake case otherwise.
+    pub fn variant_name(&self, variant: &Variant) -> String {
+        if Self::member_attrs(&variant.attrs).any(|attr| Context::is_field_attr(&attr)) {
+            panic!("`default` and `skip` are only allowed on fields");
+        }
+        if let Some(name) = Self::rename_attr(&variant.attrs) {
+            return name;
+        }
+
+        let name = Self::remove_raw(&variant.ident.to_string()).to_string();
+        let rule = self.rename_all().unwrap_or("snake_case");
+        Self::apply_rename_rule(&name, rule)
+    }
+
+    fn rename_all(&self) -> Option<&str> {
+        self.attrs.iter().find_map(|attr| match attr {
+            RustlerAttr::RenameAll(ref rule) => Some(rule.as_ref()),
+            _ => None,
+        })
+    }
+
+    fn apply_rename_rule(name: &str, rule: &str) -> String {
+        match rule {
+            "lowercase" => name.to_lowercase(),
+            "UPPERCASE" => name.to_uppercase(),
+            "PascalCase" => name.to_camel_case(),
+            "camelCase" => name.to_mixed_case(),
+            "snake_case" => name.to_snake_case(),
+            "SCREAMING_SNAKE_CASE" => name.to_shouty_snake_case(),
+            "kebab-case" => name.to_kebab_case(),
+            "SCREAMING-KEBAB-CASE" => name.to_kebab_case().to_uppercase(),
+            other => panic!(
+                "Unknown rename_all rule `{}`. Allowed rules: [\"lowercase\", \"UPPERCASE\", \
+                 \"PascalCase\", \"camelCase\", \"snake_case\", \"SCREAMING_SNAKE_CASE\", \
+                 \"kebab-case\", \"SCREAMING-KEBAB-CASE\"]",
+                other
+            ),
+        }
+    }
+
+    /// Returns the expression a missing field decodes to: `Default::default()` for
+    /// `#[rustler(default)]`, the expression of `#[rustler(default = "...")]`, or `None` if the
+    /// field is required.
+    pub fn field_default(field: &Field) -> Option<TokenStream> {
+        let mut defaults: Vec<Option<String>> = Self::member_attrs(&field.attrs)
+            .filter_map(|attr| match attr {
+                RustlerAttr::Default(expr) => Some(expr),
+                _ => None,
+            })
+            .collect();
+        match defaults.pop()? {
+            None => Some(quote! { ::std::default::Default::default() }),
+            Some(expr) => {
+                let expr: syn::Expr = syn::parse_str(&expr)
+                    .unwrap_or_else(|_| panic!("Invalid default expression `{}`", expr));
+                Some(quote! { #expr })
+            }
+        }
+    }
+
+    /// Returns the initializers of the fields marked with `#[rustler(skip)]`, which are neither
+    /// encoded nor decoded: `Default::default()`, or the expression of `#[rustler(default = "...")]`.
+    pub fn skipped_field_defs(&self) -> Vec<TokenStream> {
+        self.skipped_fields
+            .iter()
+            .map(|field| {
+                let ident = field.ident.as_ref().unwrap();
+                let default = Self::field_default(field)
+                    .unwrap_or_else(|| quote! { ::std::default::Default::default() });
+                quote! { #ident: #default }
+            })
+            .collect()
+    }
+
+    /// Returns the name given by `#[rustler(rename = "...")]` in the attributes of a field or a
+    /// variant.
+    fn rename_attr(attrs: &[syn::Attribute]) -> Option<String> {
+        let mut names: Vec<String> = Self::member_attrs(attrs)
+            .filter_map(|attr| match attr {
+                RustlerAttr::Rename(name) => Some(name),
+                _ => None,
+            })
+            .collect();
+        names.pop()
+    }
+
+    /// Returns the rustler attributes of a field or a variant.
+    fn member_attrs(attrs: &[syn::Attribute]) -> impl Iterator<Item = RustlerAttr> + '_ {
+        attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("rustler"))
+            .flat_map(Context::get_rustler_attrs)
+            .inspect(|attr| match attr {
+                RustlerAttr::Rename(_) | RustlerAttr::Default(_) | RustlerAttr::Skip => (),
+                _ => panic!(
+                    "Only `rename`, `default` and `skip` are allowed in rustler attributes of \
+                     fields and variants"
+                ),
+            })
+    }
+
+    /// Whether a field has `#[rustler(skip)]`.
+    pub fn is_skipped(field: &Field) -> bool {
+        Self::member_attrs(&field.attrs).any(|attr| match attr {
+            RustlerAttr::Skip => true,
+            _ => false,
+        })
+    }
+
+    fn is_field_attr(attr: &RustlerAttr) -> bool {
+        match attr {
+            RustlerAttr::Default(_) | RustlerAttr::Skip => true,
+            _ => false,
+        }
+    }
+
     pub fn field_to_atom_fun(field: &Field) -> Ident {
         let ident = field.ident.as_ref().unwrap();
         let ident_str = ident.to_string();
@@ -158,6 +509,11 @@ impl<'a> Context<'a> {
     }
 
     fn get_rustler_attrs(attr: &syn::Attribute) -> Vec<RustlerAttr> {
+        // Paths like `rustler::dispatch` are attribute macros, not options of the derives.
+        if attr.path.segments.len() != 1 {
+            return Vec::new();
+        }
+
         attr.path
             .segments
             .iter()
@@ -187,12 +543,32 @@ impl<'a> Context<'a> {
     }
 
     fn parse_nested_rustler(nested: &NestedMeta) -> RustlerAttr {
-        if let NestedMeta::Meta(Meta::Path(ref path)) = nested {
-            match path.segments[0].ident.to_string().as_ref() {
-                "encode" => return RustlerAttr::Encode,
-                "decode" => return RustlerAttr::Decode,
-                other => panic!("Unexpected literal {}", other),
+        match nested {
+            NestedMeta::Meta(Meta::Path(ref path)) => {
+                match path.segments[0].ident.to_string().as_ref() {
+                    "encode" => return RustlerAttr::Encode,
+                    "decode" => return RustlerAttr::Decode,
+                    "summary" => return RustlerAttr::Summary,
+                    "build_term" => return RustlerAttr::BuildTerm,
+                    "exception" => return RustlerAttr::Exception,
+                    "default" => return RustlerAttr::Default(None),
+                    "skip" => return RustlerAttr::Skip,
+                    other => panic!("Unexpected literal {}", other),
+                }
+            }
+            NestedMeta::Meta(Meta::NameValue(ref name_value)) => {
+                if let Lit::Str(ref value) = name_value.lit {
+                    match name_value.path.segments[0].ident.to_string().as_ref() {
+                        "key" => return RustlerAttr::Key(value.value()),
+                        "profile" => return RustlerAttr::Profile(value.value()),
+                        "rename" => return RustlerAttr::Rename(value.value()),
+                        "rename_all" => return RustlerAttr::RenameAll(value.value()),
+                        "default" => return RustlerAttr::Default(Some(value.value())),
+                        other => panic!("Unexpected literal {}", other),
+                    }
+                }
             }
+            _ => (),
         }
 
         panic!("Expected encode and/or decode in rustler attribute");
@@ -0,0 +1,100 @@
+use heck::SnakeCase;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{Fields, Ident, Lit, Meta, NestedMeta};
+
+/// Handles `#[rustler::dispatch]` on an enum of commands: the enum is kept as it is, and a NIF
+/// decoding a command and calling the handler of its variant is added next to it.
+pub fn dispatch_decorator(args: syn::AttributeArgs, item: syn::ItemEnum) -> TokenStream {
+    let mut name = "call".to_string();
+    let mut handler = None;
+    let mut schedule = None;
+
+    for arg in args {
+        match arg {
+            NestedMeta::Meta(Meta::NameValue(ref name_value)) => {
+                let value = match name_value.lit {
+                    Lit::Str(ref value) => value.value(),
+                    _ => panic!("Expected a string in #[rustler::dispatch] arguments"),
+                };
+                match name_value.path.get_ident().map(Ident::to_string).as_deref() {
+                    Some("name") => name = value,
+                    Some("handler") => handler = Some(value),
+                    Some("schedule") => schedule = Some(value),
+                    _ => panic!("Unexpected argument in #[rustler::dispatch]"),
+                }
+            }
+            _ => panic!("Unexpected argument in #[rustler::dispatch]"),
+        }
+    }
+
+    if item.generics.type_params().next().is_some() {
+        panic!("#[rustler::dispatch] is not supported on generic enums");
+    }
+
+    let enum_ident = &item.ident;
+    let enum_type = match item.generics.lifetimes().count() {
+        0 => quote! { #enum_ident },
+        1 => quote! { #enum_ident<'a> },
+        _ => panic!("Enum can only have one lifetime argument"),
+    };
+    let handler = match handler {
+        Some(handler) => {
+            let path: syn::Path = syn::parse_str(&handler)
+                .unwrap_or_else(|_| panic!("Invalid handler `{}`", handler));
+            quote! { #path }
+        }
+        None => quote! { #enum_ident },
+    };
+
+    let arms: Vec<TokenStream> = item
+        .variants
+        .iter()
+        .map(|variant| {
+            let variant_ident = &variant.ident;
+            let method = Ident::new(
+                &variant_ident.to_string().to_snake_case(),
+                variant_ident.span(),
+            );
+            let bindings: Vec<Ident> = (0..variant.fields.len())
+                .map(|index| Ident::new(&format!("field_{}", index), Span::call_site()))
+                .collect();
+
+            let pattern = match variant.fields {
+                Fields::Unit => quote! { #enum_ident::#variant_ident },
+                Fields::Unnamed(_) => quote! { #enum_ident::#variant_ident(#(#bindings),*) },
+                Fields::Named(ref fields) => {
+                    let names = fields.named.iter().map(|field| &field.ident);
+                    quote! { #enum_ident::#variant_ident { #(#names: #bindings),* } }
+                }
+            };
+
+            quote! {
+                #pattern => ::rustler::codegen_runtime::Dispatched::new(
+                    env,
+                    #handler::#method(env #(, #bindings)*),
+                )
+            }
+        })
+        .collect();
+
+    let fun = Ident::new(&name, Span::call_site());
+    let nif_args = match schedule {
+        Some(schedule) => quote! { (schedule = #schedule) },
+        None => quote! {},
+    };
+
+    quote! {
+        #item
+
+        #[rustler::nif #nif_args]
+        fn #fun<'a>(
+            env: ::rustler::Env<'a>,
+            command: #enum_type,
+        ) -> ::rustler::codegen_runtime::Dispatched {
+            match command {
+                #(#arms),*
+            }
+        }
+    }
+}
@@ -3,6 +3,7 @@ use proc_macro2::{Span, TokenStream};
 use syn::{self, spanned::Spanned, Field, Ident};
 
 use super::context::Context;
+use super::map::field_values;
 use super::RustlerAttr;
 
 pub fn transcoder_decorator(ast: &syn::DeriveInput) -> TokenStream {
@@ -18,10 +19,17 @@ pub fn transcoder_decorator(ast: &syn::DeriveInput) -> TokenStream {
     // Unwrap is ok here, as we already determined that struct_fields is not None
     let field_atoms = ctx.field_atoms().unwrap();
 
+    let exception_atom = if ctx.exception_attr() {
+        quote! { atom_exception = "__exception__", }
+    } else {
+        quote! {}
+    };
+
     let atom_defs = quote! {
         rustler::atoms! {
             atom_struct = "__struct__",
             atom_module = #elixir_module,
+            #exception_atom
             #(#field_atoms)*
         }
     };
@@ -40,6 +48,15 @@ pub fn transcoder_decorator(ast: &syn::DeriveInput) -> TokenStream {
         quote! {}
     };
 
+    let exception = if ctx.exception() && ctx.encode() {
+        gen_exception(ast, &ctx)
+    } else {
+        quote! {}
+    };
+
+    let keyed = ctx.gen_keyed();
+    let summary = ctx.gen_summary();
+
     let gen = quote! {
         mod #atoms_module_name {
             #atom_defs
@@ -47,6 +64,9 @@ pub fn transcoder_decorator(ast: &syn::DeriveInput) -> TokenStream {
 
         #decoder
         #encoder
+        #exception
+        #keyed
+        #summary
     };
 
     gen
@@ -62,16 +82,25 @@ fn gen_decoder(ctx: &Context, fields: &[&Field], atoms_module_name: &Ident) -> T
         .map(|field| field.ident.as_ref().unwrap())
         .collect();
 
-    let (assignments, field_defs): (Vec<TokenStream>, Vec<TokenStream>) = fields
+    let (assignments, mut field_defs): (Vec<TokenStream>, Vec<TokenStream>) = fields
         .iter()
         .zip(idents.iter())
         .enumerate()
         .map(|(index, (field, ident))| {
             let atom_fun = Context::field_to_atom_fun(field);
+            let field_name = ctx.field_name(field);
             let variable = Context::escape_ident_with_index(&ident.to_string(), index, "struct");
 
-            let assignment = quote_spanned! { field.span() =>
-                let #variable = try_decode_field(env, term, #atom_fun())?;
+            let assignment = match Context::field_default(field) {
+                Some(default) => quote_spanned! { field.span() =>
+                    let #variable = match try_decode_optional_field(env, term, profile, #field_name, #atom_fun())? {
+                        Some(value) => value,
+                        None => #default,
+                    };
+                },
+                None => quote_spanned! { field.span() =>
+                    let #variable = try_decode_field(env, term, profile, #field_name, #atom_fun())?;
+                },
             };
 
             let field_def = quote! {
@@ -81,41 +110,87 @@ fn gen_decoder(ctx: &Context, fields: &[&Field], atoms_module_name: &Ident) -> T
             (assignment, field_def)
         })
         .unzip();
+    field_defs.extend(ctx.skipped_field_defs());
 
-    let gen = quote! {
-        impl<'a> ::rustler::Decoder<'a> for #struct_type {
-            fn decode(term: ::rustler::Term<'a>) -> Result<Self, ::rustler::Error> {
-                use #atoms_module_name::*;
-                use ::rustler::Encoder;
-
-                let env = term.get_env();
-
-                fn try_decode_field<'a, T>(
-                    env: rustler::Env<'a>,
-                    term: rustler::Term<'a>,
-                    field: rustler::Atom,
-                    ) -> Result<T, rustler::Error>
-                    where
-                        T: rustler::Decoder<'a>,
-                    {
-                        use rustler::Encoder;
-                        match ::rustler::Decoder::decode(term.map_get(field.encode(env))?) {
-                            Err(_) => Err(::rustler::Error::RaiseTerm(Box::new(format!(
-                                            "Could not decode field :{:?} on %{}{{}}",
-                                            field, #struct_name_str
-                            )))),
-                            Ok(value) => Ok(value),
-                        }
-                    };
+    let body = ctx.with_profile(quote! {
+        use #atoms_module_name::*;
+        use ::rustler::Encoder;
+
+        let env = term.get_env();
 
-                let module: ::rustler::types::atom::Atom = term.map_get(atom_struct().to_term(env))?.decode()?;
-                if module != atom_module() {
-                    return Err(::rustler::Error::Atom("invalid_struct"));
+        fn try_decode_optional_field<'a, T>(
+            env: rustler::Env<'a>,
+            term: rustler::Term<'a>,
+            profile: ::rustler::EncodingProfile,
+            field_name: &'static str,
+            field: rustler::Atom,
+            ) -> Result<Option<T>, rustler::Error>
+            where
+                T: rustler::Decoder<'a>,
+            {
+                use rustler::Encoder;
+                let value = match term.map_get(field.encode(env)) {
+                    Ok(value) => value,
+                    Err(_) => return Ok(None),
+                };
+                match ::rustler::Decoder::decode_with(value, profile) {
+                    Err(_) => {
+                        ::rustler::decode_trace::record(#struct_name_str, Some(field_name), value);
+                        Err(::rustler::Error::RaiseTerm(Box::new(format!(
+                                    "Could not decode field :{:?} on %{}{{}}",
+                                    field, #struct_name_str
+                        ))))
+                    }
+                    Ok(value) => Ok(Some(value)),
                 }
+            }
+
+        #[allow(dead_code)]
+        fn try_decode_field<'a, T>(
+            env: rustler::Env<'a>,
+            term: rustler::Term<'a>,
+            profile: ::rustler::EncodingProfile,
+            field_name: &'static str,
+            field: rustler::Atom,
+            ) -> Result<T, rustler::Error>
+            where
+                T: rustler::Decoder<'a>,
+            {
+                try_decode_optional_field(env, term, profile, field_name, field)?.ok_or_else(|| {
+                    ::rustler::decode_trace::record(#struct_name_str, Some(field_name), term);
+                    ::rustler::Error::BadArg
+                })
+            };
 
-                #(#assignments);*
+        let module: ::rustler::types::atom::Atom = term.map_get(atom_struct().to_term(env))
+            .and_then(|module| module.decode())
+            .map_err(|err| {
+                ::rustler::decode_trace::record(#struct_name_str, Some("__struct__"), term);
+                err
+            })?;
+        if module != atom_module() {
+            ::rustler::decode_trace::record(#struct_name_str, Some("__struct__"), term);
+            return Err(::rustler::Error::Atom("invalid_struct"));
+        }
+
+        #(#assignments);*
+
+        Ok(#struct_name { #(#field_defs),* })
+    });
+
+    let impl_generics = ctx.impl_generics(quote! { 'a }, Some(quote! { ::rustler::Decoder<'a> }));
+    let where_clause = ctx.where_clause();
+    let gen = quote! {
+        impl #impl_generics ::rustler::Decoder<'a> for #struct_type #where_clause {
+            fn decode(term: ::rustler::Term<'a>) -> Result<Self, ::rustler::Error> {
+                <Self as ::rustler::Decoder<'a>>::decode_with(term, ::rustler::EncodingProfile::ELIXIR)
+            }
 
-                Ok(#struct_name { #(#field_defs),* })
+            fn decode_with(
+                term: ::rustler::Term<'a>,
+                profile: ::rustler::EncodingProfile,
+            ) -> Result<Self, ::rustler::Error> {
+                #body
             }
         }
     };
@@ -132,19 +207,77 @@ fn gen_encoder(ctx: &Context, fields: &[&Field], atoms_module_name: &Ident) -> T
             let field_ident = field.ident.as_ref().unwrap();
             let atom_fun = Context::field_to_atom_fun(field);
             quote_spanned! { field.span() =>
-                map = map.map_put(#atom_fun().encode(env), self.#field_ident.encode(env)).unwrap();
+                map = map.map_put(#atom_fun().encode(env), self.#field_ident.encode_with(env, profile)).unwrap();
             }
         })
         .collect();
 
+    let (exception_def, exception_key, exception_value) = if ctx.exception_attr() {
+        (
+            quote! {
+                map = map.map_put(atom_exception().encode(env), true.encode(env)).unwrap();
+            },
+            quote! { atom_exception().encode(env), },
+            quote! { true.encode(env), },
+        )
+    } else {
+        (quote! {}, quote! {}, quote! {})
+    };
+
+    let body = ctx.with_profile(quote! {
+        use #atoms_module_name::*;
+        let mut map = ::rustler::types::map::map_new(env);
+        map = map.map_put(atom_struct().encode(env), atom_module().encode(env)).unwrap();
+        #exception_def
+        #(#field_defs)*
+        map
+    });
+
+    // Batch encoder for slices, building the keys once for all elements.
+    let atom_funs: Vec<Ident> = fields
+        .iter()
+        .map(|f| Context::field_to_atom_fun(f))
+        .collect();
+    let values = field_values(fields);
+
+    let slice_body = ctx.with_profile(quote! {
+        use #atoms_module_name::*;
+        use ::rustler::Encoder;
+
+        let keys = [atom_struct().encode(env), #exception_key #(#atom_funs().encode(env)),*];
+        let module = atom_module().encode(env);
+        let terms: Vec<::rustler::Term<'a>> = values
+            .iter()
+            .map(|value| {
+                let values = [module, #exception_value #(#values),*];
+                ::rustler::types::map::map_from_entries(env, &keys, &values)
+            })
+            .collect();
+        ::rustler::types::list::make_list(env, &terms)
+    });
+
+    let impl_generics = ctx.impl_generics(quote! { 'b }, Some(quote! { ::rustler::Encoder }));
+    let where_clause = ctx.where_clause();
     let gen = quote! {
-        impl<'b> ::rustler::Encoder for #struct_type {
+        impl #impl_generics ::rustler::Encoder for #struct_type #where_clause {
             fn encode<'a>(&self, env: ::rustler::Env<'a>) -> ::rustler::Term<'a> {
-                use #atoms_module_name::*;
-                let mut map = ::rustler::types::map::map_new(env);
-                map = map.map_put(atom_struct().encode(env), atom_module().encode(env)).unwrap();
-                #(#field_defs)*
-                map
+                ::rustler::Encoder::encode_with(self, env, ::rustler::EncodingProfile::ELIXIR)
+            }
+
+            fn encode_with<'a>(
+                &self,
+                env: ::rustler::Env<'a>,
+                profile: ::rustler::EncodingProfile,
+            ) -> ::rustler::Term<'a> {
+                #body
+            }
+
+            fn encode_slice_with<'a>(
+                values: &[Self],
+                env: ::rustler::Env<'a>,
+                profile: ::rustler::EncodingProfile,
+            ) -> ::rustler::Term<'a> {
+                #slice_body
             }
         }
     };
@@ -152,6 +285,23 @@ fn gen_encoder(ctx: &Context, fields: &[&Field], atoms_module_name: &Ident) -> T
     gen
 }
 
+/// Converts the struct into an error raising it, so that `Err(value)?` raises the exception.
+fn gen_exception(ast: &syn::DeriveInput, ctx: &Context) -> TokenStream {
+    if ast.generics.lifetimes().count() > 0 {
+        panic!("An exception struct can't have a lifetime argument");
+    }
+
+    let struct_name = ctx.ident;
+
+    quote! {
+        impl From<#struct_name> for ::rustler::Error {
+            fn from(exception: #struct_name) -> Self {
+                ::rustler::Error::RaiseTerm(Box::new(exception))
+            }
+        }
+    }
+}
+
 fn get_module(ctx: &Context) -> String {
     ctx.attrs
         .iter()
@@ -1,7 +1,8 @@
 use proc_macro2::{Span, TokenStream};
-use quote::quote;
+use quote::{quote, quote_spanned};
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
 use syn::token::Comma;
 use syn::{Expr, Ident, Result, Token};
 
@@ -9,7 +10,13 @@ use syn::{Expr, Ident, Result, Token};
 pub struct InitMacroInput {
     name: syn::Lit,
     funcs: syn::ExprArray,
-    load: TokenStream,
+    load: Option<Expr>,
+    upgrade: Option<Expr>,
+    registry: TokenStream,
+    prefix: TokenStream,
+    min_nif_version: Option<(u32, u32)>,
+    features: Option<Vec<syn::LitStr>>,
+    on_panic: TokenStream,
 }
 
 impl Parse for InitMacroInput {
@@ -18,9 +25,25 @@ impl Parse for InitMacroInput {
         let _comma = <syn::Token![,]>::parse(input)?;
         let funcs = syn::ExprArray::parse(input)?;
         let options = parse_expr_assigns(input);
-        let load = extract_option(options, "load");
+        let load = extract_option_expr(options.clone(), "load");
+        let upgrade = extract_option_expr(options.clone(), "upgrade");
+        let registry = extract_option(options.clone(), "registry");
+        let prefix = extract_option(options.clone(), "prefix");
+        let features = extract_features(options.clone());
+        let on_panic = extract_on_panic(options.clone());
+        let min_nif_version = extract_min_nif_version(options);
 
-        Ok(InitMacroInput { name, funcs, load })
+        Ok(InitMacroInput {
+            name,
+            funcs,
+            load,
+            upgrade,
+            registry,
+            prefix,
+            min_nif_version,
+            features,
+            on_panic,
+        })
     }
 }
 
@@ -37,38 +60,215 @@ fn parse_expr_assigns(input: ParseStream) -> Vec<syn::ExprAssign> {
 }
 
 fn extract_option(args: Vec<syn::ExprAssign>, name: &str) -> TokenStream {
+    match extract_option_expr(args, name) {
+        Some(value) => quote!(Some(#value)),
+        None => {
+            let none = Ident::new("None", Span::call_site());
+            quote!(#none)
+        }
+    }
+}
+
+fn extract_option_expr(args: Vec<syn::ExprAssign>, name: &str) -> Option<Expr> {
     for syn::ExprAssign { left, right, .. } in args.into_iter() {
         if let syn::Expr::Path(syn::ExprPath { path, .. }) = &*left {
             if let Some(ident) = path.get_ident() {
                 if *ident == name {
-                    let value = *right;
-                    return quote!(Some(#value));
+                    return Some(*right);
                 }
             }
         }
     }
 
-    let none = Ident::new("None", Span::call_site());
-    quote!(#none)
+    None
+}
+
+/// Calls the `load` or `upgrade` callback, if any, from the corresponding function of the NIF
+/// entry, where the environment and the load data are `env` and `load_info`.
+fn load_call(callback: Option<Expr>) -> TokenStream {
+    match callback {
+        Some(callback) => quote! {
+            rustler::codegen_runtime::handle_load_call(#callback, env, load_info)
+        },
+        None => quote!(0),
+    }
+}
+
+/// Extracts the `min_nif_version = (major, minor)` option.
+fn extract_min_nif_version(args: Vec<syn::ExprAssign>) -> Option<(u32, u32)> {
+    let usage =
+        "min_nif_version must be a tuple of two integers (i.e. `min_nif_version = (2, 15)`)";
+
+    for syn::ExprAssign { left, right, .. } in args.into_iter() {
+        let is_option = matches!(
+            &*left,
+            syn::Expr::Path(syn::ExprPath { path, .. }) if path.is_ident("min_nif_version")
+        );
+        if !is_option {
+            continue;
+        }
+
+        let parts: Vec<u32> = match &*right {
+            syn::Expr::Tuple(tuple) => tuple
+                .elems
+                .iter()
+                .map(|elem| match elem {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Int(int),
+                        ..
+                    }) => int.base10_parse().expect(usage),
+                    _ => panic!("{}", usage),
+                })
+                .collect(),
+            _ => panic!("{}", usage),
+        };
+
+        match parts[..] {
+            [major, minor] => return Some((major, minor)),
+            _ => panic!("{}", usage),
+        }
+    }
+
+    None
+}
+
+/// Extracts the `on_panic = "..."` option, as a call setting the panic mode of the library.
+fn extract_on_panic(args: Vec<syn::ExprAssign>) -> TokenStream {
+    let usage = "on_panic must be \"atom\", \"exception\" or \"abort\"";
+
+    let mode = match extract_option_expr(args, "on_panic") {
+        Some(syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(mode),
+            ..
+        })) => mode.value(),
+        Some(_) => panic!("{}", usage),
+        None => return TokenStream::new(),
+    };
+    let mode = match mode.as_str() {
+        "atom" => quote!(Atom),
+        "exception" => quote!(Exception),
+        "abort" => quote!(Abort),
+        _ => panic!("{}", usage),
+    };
+
+    quote! {
+        rustler::panic_handler::set_mode(rustler::panic_handler::PanicMode::#mode);
+    }
+}
+
+/// Extracts the `features = ["feature", ...]` option.
+fn extract_features(args: Vec<syn::ExprAssign>) -> Option<Vec<syn::LitStr>> {
+    let usage = "features must be an array of strings (i.e. `features = [\"serde\"]`)";
+
+    match extract_option_expr(args, "features") {
+        Some(Expr::Array(array)) => Some(
+            array
+                .elems
+                .iter()
+                .map(|elem| match elem {
+                    Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(feature),
+                        ..
+                    }) => feature.clone(),
+                    _ => panic!("{}", usage),
+                })
+                .collect(),
+        ),
+        Some(_) => panic!("{}", usage),
+        None => None,
+    }
 }
 
 impl From<InitMacroInput> for proc_macro2::TokenStream {
     fn from(input: InitMacroInput) -> Self {
         let name = input.name;
-        let num_of_funcs = input.funcs.elems.len();
+        let duplicate_checks = duplicate_checks(&input.funcs.elems);
         let funcs = nif_funcs(input.funcs.elems);
-        let load = input.load;
+        let has_upgrade = input.upgrade.is_some();
+        let load = load_call(input.load);
+        let upgrade = load_call(input.upgrade);
+        let registry = input.registry;
+        let prefix = input.prefix;
+        // `__rustler_features__/0` is only registered with the `features` option, as loading fails
+        // for modules that don't define it.
+        let features_nif = input.features.map(|features| {
+            // `cfg!` is expanded in the crate of the library, so it sees the features of the
+            // library.
+            let features = features.iter().map(|feature| {
+                quote! { (#feature, cfg!(feature = #feature)) }
+            });
+
+            quote! {
+                unsafe extern "C" fn rustler_features(
+                    env: rustler::codegen_runtime::NIF_ENV,
+                    _argc: rustler::codegen_runtime::c_int,
+                    _argv: *const rustler::codegen_runtime::NIF_TERM
+                ) -> rustler::codegen_runtime::NIF_TERM {
+                    let lifetime = ();
+                    let env = rustler::Env::new(&lifetime, env);
+                    rustler::codegen_runtime::features_term(env, &[#(#features),*])
+                }
+                funcs.push(rustler::codegen_runtime::DEF_NIF_FUNC {
+                    name: b"__rustler_features__\0".as_ptr(),
+                    arity: 0,
+                    function: rustler_features,
+                    flags: 0,
+                });
+            }
+        });
+        let (version_assert, version_check) = match input.min_nif_version {
+            Some((major, minor)) => {
+                let message = format!(
+                    "the NIF library requires NIF version {}.{}, but is built against an older \
+                     version. Build with a newer OTP release or set RUSTLER_NIF_VERSION.",
+                    major, minor
+                );
+                let assert = quote! {
+                    const _: () = {
+                        if !rustler::nif_version_at_least(#major, #minor) {
+                            panic!(#message);
+                        }
+                    };
+                };
+                let check = quote! {
+                    if !rustler::codegen_runtime::check_nif_version(env, #major, #minor) {
+                        return 1;
+                    }
+                };
+                (assert, check)
+            }
+            None => (TokenStream::new(), TokenStream::new()),
+        };
+
+        let on_panic = input.on_panic;
 
         let inner = quote! {
             static mut NIF_ENTRY: Option<rustler::codegen_runtime::DEF_NIF_ENTRY> = None;
             use rustler::Nif;
 
+            #on_panic
+
+            let registry: Option<fn() -> rustler::NifRegistration> = #registry;
+            let mut funcs = vec![#funcs];
+            if let Some(registry) = registry {
+                funcs.extend(registry().into_funcs());
+            }
+            let prefix: Option<&str> = #prefix;
+            if let Some(prefix) = prefix {
+                rustler::codegen_runtime::prefix_nif_names(prefix, &mut funcs);
+            }
+            funcs.extend(rustler::codegen_runtime::builtin_nifs());
+            #features_nif
+            // Leaked on purpose: the VM refers to the functions as long as the library is loaded.
+            let funcs: &'static [rustler::codegen_runtime::DEF_NIF_FUNC] =
+                Box::leak(funcs.into_boxed_slice());
+
             let entry = rustler::codegen_runtime::DEF_NIF_ENTRY {
                 major: rustler::codegen_runtime::NIF_MAJOR_VERSION,
                 minor: rustler::codegen_runtime::NIF_MINOR_VERSION,
                 name: concat!(#name, "\0").as_ptr() as *const u8,
-                num_of_funcs: #num_of_funcs as rustler::codegen_runtime::c_int,
-                funcs: [#funcs].as_ptr(),
+                num_of_funcs: funcs.len() as rustler::codegen_runtime::c_int,
+                funcs: funcs.as_ptr(),
                 load: {
                     extern "C" fn nif_load(
                         env: rustler::codegen_runtime::NIF_ENV,
@@ -76,14 +276,32 @@ impl From<InitMacroInput> for proc_macro2::TokenStream {
                         load_info: rustler::codegen_runtime::NIF_TERM
                     ) -> rustler::codegen_runtime::c_int {
                         unsafe {
+                            #version_check
                             // TODO: If an unwrap ever happens, we will unwind right into C! Fix this!
-                            rustler::codegen_runtime::handle_nif_init_call(#load, env, load_info)
+                            #load
                         }
                     }
                     Some(nif_load)
                 },
                 reload: None,
-                upgrade: None,
+                upgrade: {
+                    extern "C" fn nif_upgrade(
+                        env: rustler::codegen_runtime::NIF_ENV,
+                        _priv_data: *mut *mut rustler::codegen_runtime::c_void,
+                        _old_priv_data: *mut *mut rustler::codegen_runtime::c_void,
+                        load_info: rustler::codegen_runtime::NIF_TERM
+                    ) -> rustler::codegen_runtime::c_int {
+                        unsafe {
+                            #version_check
+                            #upgrade
+                        }
+                    }
+                    if #has_upgrade {
+                        Some(nif_upgrade)
+                    } else {
+                        None
+                    }
+                },
                 unload: None,
                 vm_variant: b"beam.vanilla\0".as_ptr(),
                 options: 0,
@@ -97,6 +315,9 @@ impl From<InitMacroInput> for proc_macro2::TokenStream {
         };
 
         quote! {
+            #duplicate_checks
+            #version_assert
+
             #[cfg(unix)]
             #[no_mangle]
             extern "C" fn nif_init() -> *const rustler::codegen_runtime::DEF_NIF_ENTRY {
@@ -129,3 +350,42 @@ fn nif_funcs(funcs: Punctuated<Expr, Comma>) -> TokenStream {
 
     tokens
 }
+
+/// Emits a constant that fails to evaluate if two of `funcs` register the same name and arity.
+///
+/// Names can be changed with `#[rustler::nif(name = "...")]`, so they are only known once the
+/// `Nif` implementations are resolved, and are compared in a constant expression.
+fn duplicate_checks(funcs: &Punctuated<Expr, Comma>) -> TokenStream {
+    let funcs: Vec<&Expr> = funcs.iter().collect();
+    let mut checks = TokenStream::new();
+
+    for (i, first) in funcs.iter().enumerate() {
+        for second in &funcs[i + 1..] {
+            let message = format!(
+                "`{}` and `{}` register a NIF with the same name and arity",
+                path_string(first),
+                path_string(second)
+            );
+            checks.extend(quote_spanned! {second.span()=>
+                if rustler::codegen_runtime::nif_conflicts(
+                    <#first as rustler::Nif>::NAME_STR,
+                    <#first as rustler::Nif>::ARITY,
+                    <#second as rustler::Nif>::NAME_STR,
+                    <#second as rustler::Nif>::ARITY,
+                ) {
+                    panic!(#message);
+                }
+            });
+        }
+    }
+
+    quote! {
+        const _: () = {
+            #checks
+        };
+    }
+}
+
+fn path_string(expr: &Expr) -> String {
+    quote!(#expr).to_string().replace(' ', "")
+}
@@ -11,11 +11,13 @@ extern crate syn;
 extern crate quote;
 
 mod context;
+mod dispatch;
 mod ex_struct;
 mod init;
 mod map;
 mod nif;
 mod record;
+mod tagged_enum;
 mod tuple;
 mod unit_enum;
 mod untagged_enum;
@@ -26,6 +28,15 @@ enum RustlerAttr {
     Decode,
     Module(String),
     Tag(String),
+    Key(String),
+    Profile(String),
+    Summary,
+    BuildTerm,
+    Exception,
+    Rename(String),
+    RenameAll(String),
+    Default(Option<String>),
+    Skip,
 }
 
 /// Implementation of a Native Implementated Function (NIF) macro that lets the user annotate
@@ -54,6 +65,68 @@ enum RustlerAttr {
 ///
 /// rustler::init!("Elixir.Math", [add, sub, mul, div], Some(load));
 /// ```
+///
+/// The `load` callback can take the load data as any type implementing `Decoder`, instead of a
+/// `Term`. When the load data can't be decoded, the error is logged and loading fails:
+///
+/// ```ignore
+/// #[derive(NifMap)]
+/// struct Config {
+///     precision: u32,
+/// }
+///
+/// fn load(env: Env, config: Config) -> bool {
+///     ...
+/// }
+///
+/// rustler::init!("Elixir.Math", [add, sub, mul, div], load = load);
+/// ```
+///
+/// The `upgrade` option takes a function with the same signature as `load`, called instead of it
+/// when the module is upgraded while an older version of the library is still loaded. It
+/// receives the load data of the new version:
+///
+/// ```ignore
+/// rustler::init!("Elixir.Math", [add, sub, mul, div], load = load, upgrade = load);
+/// ```
+///
+/// The `prefix` option prepends a string to the names of all NIFs of the library, including the
+/// ones added by a `registry`. Here, the NIFs are registered as `math_add`, `math_sub`, etc.:
+///
+/// ```ignore
+/// rustler::init!("Elixir.Math", [add, sub, mul, div], prefix = "math_");
+/// ```
+///
+/// The `min_nif_version` option fails the build when it is built against an older version of
+/// the NIF API, and fails loading the library with a logged error when the VM provides an older
+/// one. Code depending on newer versions can be guarded with `rustler::nif_version_at_least`:
+///
+/// ```ignore
+/// rustler::init!("Elixir.Math", [add, sub, mul, div], min_nif_version = (2, 15));
+/// ```
+///
+/// The `on_panic` option chooses what NIFs raise when they panic: `"atom"`, the default, raises
+/// `:nif_panicked`, `"exception"` raises a `Rustler.NifPanicError` with the message and backtrace
+/// of the panic, and `"abort"` aborts the VM. See `rustler::panic_handler`:
+///
+/// ```ignore
+/// rustler::init!("Elixir.Math", [add, sub, mul, div], on_panic = "exception");
+/// ```
+///
+/// The `features` option adds a `__rustler_features__/0` NIF, which returns the names of the
+/// listed features of the library that are enabled, followed by the enabled features of
+/// `rustler`, prefixed with `rustler/`, so that Elixir code can check for optional capabilities.
+/// The module must define a `__rustler_features__/0` stub, like `use Rustler` does:
+///
+/// ```ignore
+/// rustler::init!("Elixir.Math", [add, sub, mul, div], features = ["simd", "bigint"]);
+/// ```
+///
+/// ```elixir
+/// unless "bigint" in Math.__rustler_features__() do
+///   raise "Math was built without the bigint feature"
+/// end
+/// ```
 #[proc_macro]
 pub fn init(input: TokenStream) -> TokenStream {
     let input = syn::parse_macro_input!(input as init::InitMacroInput);
@@ -70,12 +143,110 @@ pub fn init(input: TokenStream) -> TokenStream {
 ///     a + b
 /// }
 /// ```
+///
+/// With the `cpu_time` flag, the CPU time of each call is recorded in `rustler::stats`:
+///
+/// ```ignore
+/// #[nif(schedule = "DirtyCpu", cpu_time)]
+/// fn checksum(data: Binary) -> u64 {
+///     ...
+/// }
+/// ```
+///
+/// With the `unit_ok` flag, a NIF returning `()` or `NifResult<()>` returns `:ok` on success:
+///
+/// ```ignore
+/// #[nif(unit_ok)]
+/// fn flush(queue: ResourceArc<Queue>) -> NifResult<()> {
+///     ...
+/// }
+/// ```
+///
+/// With the `unsafe_crash_guard` flag and the `experimental-crash-guard` feature of `rustler`,
+/// segmentation faults in the function raise an exception instead of crashing the VM. This is
+/// unsafe and meant for development only: see `rustler::crash_guard`.
+///
+/// With `feature = "..."`, the function is only compiled when the feature of the library is
+/// enabled. Otherwise, the NIF is registered all the same and raises `{:error, :not_implemented}`,
+/// so that the module exports the same functions whatever the features of the build:
+///
+/// ```ignore
+/// #[nif(feature = "gpu")]
+/// fn render(scene: Scene) -> Binary {
+///     ...
+/// }
+/// ```
+///
+/// An `async fn` NIF returns a new reference, and its future is spawned on the runtime of
+/// `rustler::async_nif`. Its output is sent to the caller as `{ref, output}` when it completes:
+///
+/// ```ignore
+/// #[nif]
+/// async fn fetch(url: String) -> Result<String, String> {
+///     ...
+/// }
+/// ```
+///
+/// Associated functions can be NIFs too, when their `impl` block is annotated as well. They are
+/// named after the type and the function, `counter_read` here:
+///
+/// ```ignore
+/// #[nif]
+/// impl Counter {
+///     #[nif]
+///     fn read(counter: ResourceArc<Counter>) -> u64 {
+///         ...
+///     }
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn nif(args: TokenStream, input: TokenStream) -> TokenStream {
     let args = syn::parse_macro_input!(args as syn::AttributeArgs);
-    let input = syn::parse_macro_input!(input as syn::ItemFn);
+    let input = syn::parse_macro_input!(input as syn::Item);
 
-    nif::transcoder_decorator(args, input).into()
+    match input {
+        syn::Item::Fn(fun) => nif::transcoder_decorator(args, fun).into(),
+        syn::Item::Impl(item) => nif::impl_decorator(args, item).into(),
+        _ => panic!("#[rustler::nif] can only be used on functions and impl blocks"),
+    }
+}
+
+/// Adds a NIF dispatching the commands of an enum to handlers, so that a family of operations is
+/// exported as a single NIF of arity 1.
+///
+/// The enum must implement `Decoder`, usually through a derive. The NIF decodes its argument as
+/// the enum, and calls the handler of the variant: the associated function of the enum named
+/// after the variant in snake case, with the `Env` followed by the fields of the variant. The
+/// value returned by the handler is returned by the NIF, and can be any type a NIF can return.
+///
+/// ```ignore
+/// #[derive(NifUntaggedEnum)]
+/// #[rustler::dispatch(name = "store_call")]
+/// enum StoreCommand {
+///     Get(GetArgs),
+///     Put(PutArgs),
+/// }
+///
+/// impl StoreCommand {
+///     fn get(env: Env, args: GetArgs) -> NifResult<Term> { ... }
+///     fn put(env: Env, args: PutArgs) -> Atom { ... }
+/// }
+///
+/// rustler::init!("Elixir.Store", [store_call]);
+/// ```
+///
+/// The options are `name`, the name of the NIF, `call` by default, `handler`, a type whose
+/// associated functions handle the commands instead of the enum, and `schedule`, like
+/// `#[rustler::nif]`.
+#[proc_macro_attribute]
+pub fn dispatch(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = syn::parse_macro_input!(args as syn::AttributeArgs);
+    let input = syn::parse_macro_input!(input as syn::Item);
+
+    match input {
+        syn::Item::Enum(item) => dispatch::dispatch_decorator(args, item).into(),
+        _ => panic!("#[rustler::dispatch] can only be used on enums"),
+    }
 }
 
 /// Implementation of the `NifStruct` macro that lets the user annotate a struct that will
@@ -98,6 +269,36 @@ pub fn nif(args: TokenStream, input: TokenStream) -> TokenStream {
 ///   defstruct lhs: 0, rhs: 0
 /// end
 /// ```
+///
+/// With `#[rustler(exception)]`, the struct is encoded with `__exception__: true`, so it
+/// matches an exception defined with `defexception`, and it converts into a `rustler::Error`
+/// raising it. A struct with an `__exception__` field is handled the same way. The struct can
+/// then be returned as data, or raised with `Err(...)?`:
+///
+/// ```ignore
+/// #[derive(NifStruct)]
+/// #[module = "Parser.SyntaxError"]
+/// #[rustler(exception)]
+/// struct SyntaxError {
+///     message: String,
+///     line: u32,
+/// }
+///
+/// #[rustler::nif]
+/// fn parse(source: String) -> NifResult<Ast> {
+///     let ast = parser::parse(&source).map_err(|err| SyntaxError {
+///         message: err.to_string(),
+///         line: err.line,
+///     })?;
+///     Ok(ast)
+/// }
+/// ```
+///
+/// ```elixir
+/// defmodule Parser.SyntaxError do
+///   defexception [:message, :line]
+/// end
+/// ```
 #[proc_macro_derive(NifStruct, attributes(module, rustler))]
 pub fn nif_struct(input: TokenStream) -> TokenStream {
     let ast = syn::parse(input).unwrap();
@@ -122,6 +323,83 @@ pub fn nif_struct(input: TokenStream) -> TokenStream {
 /// ```elixir
 /// %{lhs: 33, rhs: 21}
 /// ```
+///
+/// With `#[rustler(summary)]`, the struct also gets a `summary_term(env)` method, returning the
+/// encoded struct with long strings, binaries and collections truncated by
+/// `rustler::summary::summarize`. This is also available on `NifStruct`, `NifTuple` and
+/// `NifRecord`.
+///
+/// Keys are named after the fields. `#[rustler(rename = "...")]` on a field gives it another
+/// name, and `#[rustler(rename_all = "...")]` on the struct converts the names of all fields, with
+/// the same rules as serde: `"lowercase"`, `"UPPERCASE"`, `"PascalCase"`, `"camelCase"`,
+/// `"snake_case"`, `"SCREAMING_SNAKE_CASE"`, `"kebab-case"` or `"SCREAMING-KEBAB-CASE"`. Both are
+/// also available on `NifStruct`.
+///
+/// ```ignore
+/// #[derive(NifMap)]
+/// #[rustler(rename_all = "camelCase")]
+/// struct User {
+///     user_id: i32,
+///     #[rustler(rename = "e-mail")]
+///     email: String,
+/// }
+/// ```
+///
+/// A key missing from the map makes decoding fail, unless its field is marked with
+/// `#[rustler(default)]`, which decodes it as `Default::default()`, or with
+/// `#[rustler(default = "...")]`, which decodes it as the given expression. This is also available
+/// on `NifStruct`.
+///
+/// ```ignore
+/// #[derive(NifMap)]
+/// struct Settings {
+///     name: String,
+///     #[rustler(default)]
+///     tags: Vec<String>,
+///     #[rustler(default = "Settings::DEFAULT_RETRIES")]
+///     retries: u32,
+/// }
+/// ```
+///
+/// Fields marked with `#[rustler(skip)]` are neither encoded nor decoded, and are initialized with
+/// `Default::default()`, or the expression of `#[rustler(default = "...")]`, by the decoder. This
+/// is meant for fields without a term representation, like caches or `PhantomData`, and is also
+/// available on the named fields of `NifStruct`, `NifTuple` and `NifRecord`.
+///
+/// ```ignore
+/// #[derive(NifMap)]
+/// struct Document {
+///     text: String,
+///     #[rustler(skip)]
+///     tokens: Option<Vec<String>>,
+/// }
+/// ```
+///
+/// With `#[rustler(build_term)]`, the struct also implements `rustler::backend::BuildTerm`, so that
+/// it can be built without a running VM, like in the messages of `rustler::dist` and
+/// `rustler::port`. Keys are always atoms there, whatever the `EncodingProfile`. This is also
+/// available on `NifTuple` and `NifUnitEnum`.
+///
+/// ```ignore
+/// #[derive(NifMap)]
+/// #[rustler(build_term)]
+/// struct Event {
+///     kind: String,
+///     count: u32,
+/// }
+/// ```
+///
+/// Generic types can be derived too. The decoder requires the type parameters to implement
+/// `Decoder`, and the encoder requires them to implement `Encoder`, as do the derives of
+/// `NifStruct`, `NifTuple`, `NifRecord` and `NifUntaggedEnum`.
+///
+/// ```ignore
+/// #[derive(NifMap)]
+/// struct Pair<T> {
+///     first: T,
+///     second: T,
+/// }
+/// ```
 #[proc_macro_derive(NifMap, attributes(rustler))]
 pub fn nif_map(input: TokenStream) -> TokenStream {
     let ast = syn::parse(input).unwrap();
@@ -148,6 +426,21 @@ pub fn nif_map(input: TokenStream) -> TokenStream {
 /// ```
 ///
 /// The size of the tuple will depend on the number of elements in the struct.
+///
+/// Elements are decoded in place, so a struct whose fields borrow from the term, like `Binary`
+/// and `&[u8]`, is decoded without allocating. Such a struct is bound to the lifetime of the
+/// NIF call, and can't outlive its arguments:
+///
+/// ```ignore
+/// #[derive(NifTuple)]
+/// #[rustler(decode)]
+/// struct Frame<'a> {
+///     header: Binary<'a>,
+///     payload: &'a [u8],
+/// }
+/// ```
+///
+/// As a `&[u8]` is encoded as a list of integers, such structs are usually only decoded.
 #[proc_macro_derive(NifTuple, attributes(rustler))]
 pub fn nif_tuple(input: TokenStream) -> TokenStream {
     let ast = syn::parse(input).unwrap();
@@ -204,6 +497,10 @@ pub fn nif_record(input: TokenStream) -> TokenStream {
 ///
 /// Note that the `:invalid_variant` atom is returned if the user tries to encode something
 /// that isn't in the Rust enum.
+///
+/// Atoms are the names of the variants in snake case, unless the enum has a
+/// `#[rustler(rename_all = "...")]` attribute, taking the same rules as `NifMap`. A variant can
+/// also be given any atom with `#[rustler(rename = "...")]`.
 #[proc_macro_derive(NifUnitEnum, attributes(rustler))]
 pub fn nif_unit_enum(input: TokenStream) -> TokenStream {
     let ast = syn::parse(input).unwrap();
@@ -246,3 +543,35 @@ pub fn nif_untagged_enum(input: TokenStream) -> TokenStream {
     let ast = syn::parse(input).unwrap();
     untagged_enum::transcoder_decorator(&ast).into()
 }
+
+/// Implementation of the `NifTaggedEnum` macro that lets the user annotate an enum with data
+/// carrying variants, encoded as tagged tuples:
+///
+/// ```ignore
+/// #[derive(NifTaggedEnum)]
+/// enum Shape {
+///     Empty,
+///     Circle(f64),
+///     Rect { width: f64, height: f64 },
+/// }
+/// ```
+///
+/// Unit variants are encoded as atoms, tuple variants as a tuple of the atom of the variant
+/// followed by the fields, and struct variants as a tuple of the atom and a map of the fields:
+///
+/// ```elixir
+/// :empty
+/// {:circle, 1.5}
+/// {:rect, %{width: 2.0, height: 3.0}}
+/// ```
+///
+/// Atoms of variants are named like in `NifUnitEnum`, and can be changed with
+/// `#[rustler(rename_all = "...")]` and `#[rustler(rename = "...")]`. The keys of struct variants
+/// are the names of the fields, or the names given by `#[rustler(rename = "...")]`, and missing
+/// keys can be allowed with `#[rustler(default)]`, like in `NifMap`. As with `NifUnitEnum`,
+/// decoding anything else returns the `:invalid_variant` atom.
+#[proc_macro_derive(NifTaggedEnum, attributes(rustler))]
+pub fn nif_tagged_enum(input: TokenStream) -> TokenStream {
+    let ast = syn::parse(input).unwrap();
+    tagged_enum::transcoder_decorator(&ast).into()
+}
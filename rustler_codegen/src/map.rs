@@ -24,7 +24,12 @@ pub fn transcoder_decorator(ast: &syn::DeriveInput) -> TokenStream {
     let atoms_module_name = ctx.atoms_module_name(Span::call_site());
 
     let decoder = if ctx.decode() {
-        gen_decoder(&ctx, &struct_fields, &atoms_module_name)
+        let decoder = gen_decoder(&ctx, &struct_fields, &atoms_module_name);
+        let map_fields = gen_map_fields(&ctx, struct_fields, &atoms_module_name);
+        quote! {
+            #decoder
+            #map_fields
+        }
     } else {
         quote! {}
     };
@@ -35,6 +40,15 @@ pub fn transcoder_decorator(ast: &syn::DeriveInput) -> TokenStream {
         quote! {}
     };
 
+    let build_term = if ctx.build_term() {
+        gen_build_term(&ctx, struct_fields)
+    } else {
+        quote! {}
+    };
+
+    let keyed = ctx.gen_keyed();
+    let summary = ctx.gen_summary();
+
     let gen = quote! {
         mod #atoms_module_name {
             #atom_defs
@@ -42,6 +56,9 @@ pub fn transcoder_decorator(ast: &syn::DeriveInput) -> TokenStream {
 
         #decoder
         #encoder
+        #build_term
+        #keyed
+        #summary
     };
 
     gen
@@ -50,22 +67,32 @@ pub fn transcoder_decorator(ast: &syn::DeriveInput) -> TokenStream {
 fn gen_decoder(ctx: &Context, fields: &[&Field], atoms_module_name: &Ident) -> TokenStream {
     let struct_type = &ctx.ident_with_lifetime;
     let struct_name = ctx.ident;
+    let struct_name_str = struct_name.to_string();
 
     let idents: Vec<_> = fields
         .iter()
         .map(|field| field.ident.as_ref().unwrap())
         .collect();
 
-    let (assignments, field_defs): (Vec<TokenStream>, Vec<TokenStream>) = fields
+    let (assignments, mut field_defs): (Vec<TokenStream>, Vec<TokenStream>) = fields
         .iter()
         .zip(idents.iter())
         .enumerate()
         .map(|(index, (field, ident))| {
             let atom_fun = Context::field_to_atom_fun(field);
+            let field_name = ctx.field_name(field);
             let variable = Context::escape_ident_with_index(&ident.to_string(), index, "map");
 
-            let assignment = quote_spanned! { field.span() =>
-            let #variable = try_decode_field(env, term, #atom_fun())?;
+            let assignment = match Context::field_default(field) {
+                Some(default) => quote_spanned! { field.span() =>
+                    let #variable = match try_decode_optional_field(env, term, profile, #field_name, #atom_fun())? {
+                        Some(value) => value,
+                        None => #default,
+                    };
+                },
+                None => quote_spanned! { field.span() =>
+                    let #variable = try_decode_field(env, term, profile, #field_name, #atom_fun())?;
+                },
             };
 
             let field_def = quote! {
@@ -74,35 +101,75 @@ fn gen_decoder(ctx: &Context, fields: &[&Field], atoms_module_name: &Ident) -> T
             (assignment, field_def)
         })
         .unzip();
+    field_defs.extend(ctx.skipped_field_defs());
+
+    let body = ctx.with_profile(quote! {
+        use #atoms_module_name::*;
 
+        let env = term.get_env();
+
+        fn try_decode_optional_field<'a, T>(
+            env: rustler::Env<'a>,
+            term: rustler::Term<'a>,
+            profile: ::rustler::EncodingProfile,
+            field_name: &'static str,
+            field: rustler::Atom,
+            ) -> Result<Option<T>, rustler::Error>
+            where
+                T: rustler::Decoder<'a>,
+            {
+                let key = profile.key(env, field_name, field);
+                let value = match term.map_get(key) {
+                    Ok(value) => value,
+                    Err(_) => return Ok(None),
+                };
+                match ::rustler::Decoder::decode_with(value, profile) {
+                    Err(_) => {
+                        ::rustler::decode_trace::record(#struct_name_str, Some(field_name), value);
+                        Err(::rustler::Error::RaiseTerm(Box::new(format!(
+                                    "Could not decode field :{:?} on %{{}}",
+                                    field
+                        ))))
+                    }
+                    Ok(value) => Ok(Some(value)),
+                }
+            }
+
+        #[allow(dead_code)]
+        fn try_decode_field<'a, T>(
+            env: rustler::Env<'a>,
+            term: rustler::Term<'a>,
+            profile: ::rustler::EncodingProfile,
+            field_name: &'static str,
+            field: rustler::Atom,
+            ) -> Result<T, rustler::Error>
+            where
+                T: rustler::Decoder<'a>,
+            {
+                try_decode_optional_field(env, term, profile, field_name, field)?.ok_or_else(|| {
+                    ::rustler::decode_trace::record(#struct_name_str, Some(field_name), term);
+                    ::rustler::Error::BadArg
+                })
+            };
+
+        #(#assignments);*
+
+        Ok(#struct_name { #(#field_defs),* })
+    });
+
+    let impl_generics = ctx.impl_generics(quote! { 'a }, Some(quote! { ::rustler::Decoder<'a> }));
+    let where_clause = ctx.where_clause();
     let gen = quote! {
-        impl<'a> ::rustler::Decoder<'a> for #struct_type {
+        impl #impl_generics ::rustler::Decoder<'a> for #struct_type #where_clause {
             fn decode(term: ::rustler::Term<'a>) -> Result<Self, ::rustler::Error> {
-                use #atoms_module_name::*;
-
-                let env = term.get_env();
-
-                fn try_decode_field<'a, T>(
-                    env: rustler::Env<'a>,
-                    term: rustler::Term<'a>,
-                    field: rustler::Atom,
-                    ) -> Result<T, rustler::Error>
-                    where
-                        T: rustler::Decoder<'a>,
-                    {
-                        use rustler::Encoder;
-                        match ::rustler::Decoder::decode(term.map_get(field.encode(env))?) {
-                            Err(_) => Err(::rustler::Error::RaiseTerm(Box::new(format!(
-                                            "Could not decode field :{:?} on %{{}}",
-                                            field
-                            )))),
-                            Ok(value) => Ok(value),
-                        }
-                    };
-
-                #(#assignments);*
+                <Self as ::rustler::Decoder<'a>>::decode_with(term, ::rustler::EncodingProfile::ELIXIR)
+            }
 
-                Ok(#struct_name { #(#field_defs),* })
+            fn decode_with(
+                term: ::rustler::Term<'a>,
+                profile: ::rustler::EncodingProfile,
+            ) -> Result<Self, ::rustler::Error> {
+                #body
             }
         }
     };
@@ -110,32 +177,147 @@ fn gen_decoder(ctx: &Context, fields: &[&Field], atoms_module_name: &Ident) -> T
     gen
 }
 
-fn gen_encoder(ctx: &Context, fields: &[&Field], atoms_module_name: &Ident) -> TokenStream {
+/// Implements `MapFields`, for decoding the struct with `MapSubset`.
+fn gen_map_fields(ctx: &Context, fields: &[&Field], atoms_module_name: &Ident) -> TokenStream {
     let struct_type = &ctx.ident_with_lifetime;
 
-    let field_defs: Vec<TokenStream> = fields
+    let keys: Vec<TokenStream> = fields
         .iter()
         .map(|field| {
-            let field_ident = field.ident.as_ref().unwrap();
+            let field_name = ctx.field_name(field);
             let atom_fun = Context::field_to_atom_fun(field);
+            quote! { profile.key(env, #field_name, #atom_fun()) }
+        })
+        .collect();
 
-            quote_spanned! { field.span() =>
-                map = map.map_put(#atom_fun().encode(env), self.#field_ident.encode(env)).unwrap();
+    let body = ctx.with_profile(quote! {
+        use #atoms_module_name::*;
+
+        vec![#(#keys),*]
+    });
+
+    let impl_generics = ctx.impl_generics(quote! { 'a }, None);
+    let where_clause = ctx.where_clause();
+    quote! {
+        impl #impl_generics ::rustler::types::map_subset::MapFields for #struct_type #where_clause {
+            fn field_keys<'b>(
+                env: ::rustler::Env<'b>,
+                profile: ::rustler::EncodingProfile,
+            ) -> Vec<::rustler::Term<'b>> {
+                #body
             }
+        }
+    }
+}
+
+fn gen_encoder(ctx: &Context, fields: &[&Field], atoms_module_name: &Ident) -> TokenStream {
+    let struct_type = &ctx.ident_with_lifetime;
+
+    // Both encoders build maps with `map_from_entries`, so that they treat repeated keys alike.
+    let keys: Vec<TokenStream> = fields
+        .iter()
+        .map(|field| {
+            let field_name = ctx.field_name(field);
+            let atom_fun = Context::field_to_atom_fun(field);
+            quote! { profile.key(env, #field_name, #atom_fun()) }
         })
         .collect();
+    let values = field_values(fields);
+
+    let body = ctx.with_profile(quote! {
+        use #atoms_module_name::*;
+        use ::rustler::Encoder;
 
+        let value = self;
+        let keys: &[::rustler::Term<'a>] = &[#(#keys),*];
+        let values: &[::rustler::Term<'a>] = &[#(#values),*];
+        ::rustler::types::map::map_from_entries(env, keys, values)
+    });
+
+    // Batch encoder for slices, building the keys once for all elements.
+    let slice_body = ctx.with_profile(quote! {
+        use #atoms_module_name::*;
+        use ::rustler::Encoder;
+
+        let keys: &[::rustler::Term<'a>] = &[#(#keys),*];
+        let terms: Vec<::rustler::Term<'a>> = values
+            .iter()
+            .map(|value| {
+                let values: &[::rustler::Term<'a>] = &[#(#values),*];
+                ::rustler::types::map::map_from_entries(env, keys, values)
+            })
+            .collect();
+        ::rustler::types::list::make_list(env, &terms)
+    });
+
+    let impl_generics = ctx.impl_generics(quote! { 'b }, Some(quote! { ::rustler::Encoder }));
+    let where_clause = ctx.where_clause();
     let gen = quote! {
-        impl<'b> ::rustler::Encoder for #struct_type {
+        impl #impl_generics ::rustler::Encoder for #struct_type #where_clause {
             fn encode<'a>(&self, env: ::rustler::Env<'a>) -> ::rustler::Term<'a> {
-                use #atoms_module_name::*;
+                ::rustler::Encoder::encode_with(self, env, ::rustler::EncodingProfile::ELIXIR)
+            }
 
-                let mut map = ::rustler::types::map::map_new(env);
-                #(#field_defs)*
-                map
+            fn encode_with<'a>(
+                &self,
+                env: ::rustler::Env<'a>,
+                profile: ::rustler::EncodingProfile,
+            ) -> ::rustler::Term<'a> {
+                #body
+            }
+
+            fn encode_slice_with<'a>(
+                values: &[Self],
+                env: ::rustler::Env<'a>,
+                profile: ::rustler::EncodingProfile,
+            ) -> ::rustler::Term<'a> {
+                #slice_body
             }
         }
     };
 
     gen
 }
+
+fn gen_build_term(ctx: &Context, fields: &[&Field]) -> TokenStream {
+    let struct_type = &ctx.ident_with_lifetime;
+
+    let entries: Vec<TokenStream> = fields
+        .iter()
+        .map(|field| {
+            let field_ident = field.ident.as_ref().unwrap();
+            let field_name = ctx.field_name(field);
+
+            quote_spanned! { field.span() =>
+                (
+                    backend.atom(#field_name),
+                    ::rustler::backend::BuildTerm::build(&self.#field_ident, backend),
+                )
+            }
+        })
+        .collect();
+
+    let impl_generics = ctx.impl_generics(
+        quote! { 'b },
+        Some(quote! { ::rustler::backend::BuildTerm }),
+    );
+    let where_clause = ctx.where_clause();
+    quote! {
+        impl #impl_generics ::rustler::backend::BuildTerm for #struct_type #where_clause {
+            fn build<B: ::rustler::backend::Backend>(&self, backend: B) -> B::Term {
+                backend.map(vec![#(#entries),*])
+            }
+        }
+    }
+}
+
+/// Expressions encoding each field of `value`, in order.
+pub(crate) fn field_values(fields: &[&Field]) -> Vec<TokenStream> {
+    fields
+        .iter()
+        .map(|field| {
+            let field_ident = field.ident.as_ref().unwrap();
+            quote_spanned! { field.span() => value.#field_ident.encode_with(env, profile) }
+        })
+        .collect()
+}
@@ -1,32 +1,190 @@
+use heck::SnakeCase;
 use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens};
 use syn::punctuated::Punctuated;
 use syn::token::Comma;
 
 pub fn transcoder_decorator(args: syn::AttributeArgs, fun: syn::ItemFn) -> TokenStream {
-    let sig = &fun.sig;
-    let name = &sig.ident;
+    let name = &fun.sig.ident;
+    let function = fun.to_owned().into_token_stream();
+
+    nif_struct(args, &fun.sig, name, function, quote!(#name))
+}
+
+/// Handles `#[rustler::nif]` on an `impl` block: every associated function annotated with
+/// `#[rustler::nif]` becomes a NIF named after the type and the function, e.g. `counter_add` for
+/// `Counter::add`, unless renamed with `name = "..."`.
+pub fn impl_decorator(args: syn::AttributeArgs, mut item: syn::ItemImpl) -> TokenStream {
+    if !args.is_empty() {
+        panic!("#[rustler::nif] on an impl block doesn't take any arguments");
+    }
+    if !item.generics.params.is_empty() {
+        panic!("#[rustler::nif] is not supported on generic impl blocks");
+    }
+
+    let self_ty = &item.self_ty;
+    let type_name = match &**self_ty {
+        syn::Type::Path(syn::TypePath { path, .. }) => {
+            path.segments.last().unwrap().ident.to_string()
+        }
+        _ => panic!("#[rustler::nif] is only supported on impl blocks of named types"),
+    };
+
+    let mut nifs = TokenStream::new();
+
+    for impl_item in item.items.iter_mut() {
+        let method = match impl_item {
+            syn::ImplItem::Method(method) => method,
+            _ => continue,
+        };
+        let position = match method.attrs.iter().position(is_nif_attribute) {
+            Some(position) => position,
+            None => continue,
+        };
+        let attr = method.attrs.remove(position);
+        let args = match attr.parse_meta() {
+            Ok(syn::Meta::Path(_)) => Vec::new(),
+            Ok(syn::Meta::List(list)) => list.nested.into_iter().collect(),
+            _ => panic!("Invalid #[rustler::nif] attribute"),
+        };
+
+        if let Some(feature) = extract_attr_value(args.clone(), "feature") {
+            method
+                .attrs
+                .push(syn::parse_quote!(#[cfg(feature = #feature)]));
+        }
+
+        let method_name = &method.sig.ident;
+        let name = syn::Ident::new(
+            &format!("{}_{}", type_name.to_snake_case(), method_name),
+            method_name.span(),
+        );
+        nifs.extend(nif_struct(
+            args,
+            &method.sig,
+            &name,
+            TokenStream::new(),
+            quote!(<#self_ty>::#method_name),
+        ));
+    }
+
+    quote! {
+        #item
+        #nifs
+    }
+}
+
+fn is_nif_attribute(attr: &syn::Attribute) -> bool {
+    let segments: Vec<String> = attr
+        .path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect();
+    segments == ["nif"] || segments == ["rustler", "nif"]
+}
+
+/// Generates the `Nif` implementation named `name`, which decodes the arguments and calls
+/// `callee`. `function` is emitted next to the call, for NIFs defined by a free function.
+fn nif_struct(
+    args: syn::AttributeArgs,
+    sig: &syn::Signature,
+    name: &syn::Ident,
+    function: TokenStream,
+    callee: TokenStream,
+) -> TokenStream {
     let inputs = &sig.inputs;
 
     validate_attributes(args.clone());
 
     let flags = schedule_flag(args.to_owned());
-    let function = fun.to_owned().into_token_stream();
     let arity = arity(inputs.clone());
     let decoded_terms = extract_inputs(inputs.clone());
     let argument_names = create_function_params(inputs.clone());
-    let erl_func_name = extract_attr_value(args, "name")
+    let cpu_time = has_flag(&args, "cpu_time");
+    let unit_ok = has_flag(&args, "unit_ok");
+    let crash_guard = has_flag(&args, "unsafe_crash_guard");
+    let erl_func_name = extract_attr_value(args.clone(), "name")
         .map(|ref n| syn::Ident::new(n, Span::call_site()))
         .unwrap_or_else(|| name.clone());
 
-    quote! {
-        #[allow(non_camel_case_types)]
-        pub struct #name;
+    let nif_name = format!("{}/{}", erl_func_name, arity);
+    let feature = extract_attr_value(args.clone(), "feature");
+
+    let deprecation = match extract_attr_value(args, "deprecated") {
+        Some(note) => {
+            let nif = &nif_name;
+            quote! {
+                static DEPRECATED_CALLS: std::sync::atomic::AtomicUsize =
+                    std::sync::atomic::AtomicUsize::new(0);
+                rustler::log::deprecated_call(env, &DEPRECATED_CALLS, #nif, #note);
+            }
+        }
+        None => TokenStream::new(),
+    };
+
+    let is_async = sig.asyncness.is_some();
+    if is_async {
+        if crash_guard || unit_ok {
+            panic!("The unsafe_crash_guard and unit_ok flags are not supported on async NIFs");
+        }
+        if takes_env(inputs) {
+            panic!("Async NIFs can't take an Env, which doesn't outlive the call");
+        }
+    }
 
+    let invocation = if is_async {
+        quote!(rustler::async_nif::spawn(env, #callee(#argument_names))?)
+    } else if crash_guard {
+        quote!({
+            let guarded = move || #callee(#argument_names);
+            unsafe { rustler::crash_guard::guard(#nif_name, guarded) }?
+        })
+    } else {
+        quote!(#callee(#argument_names))
+    };
+    let returned = if unit_ok {
+        quote!(rustler::codegen_runtime::UnitAsOk::unit_as_ok(#invocation))
+    } else {
+        invocation
+    };
+
+    // The generated `Ok(spawn(..)?)` converts the error of an async NIF.
+    let allow_question_mark = if is_async {
+        quote!(#[allow(clippy::needless_question_mark)])
+    } else {
+        quote!()
+    };
+
+    let call = if cpu_time {
+        quote! {
+            let timer = rustler::stats::CallTimer::start();
+            let returned = wrapper(env, &terms);
+            timer.stop(stringify!(#erl_func_name));
+            returned.apply(env)
+        }
+    } else {
+        quote! {
+            wrapper(env, &terms).apply(env)
+        }
+    };
+
+    let consts = quote! {
+        const NAME: *const u8 = concat!(stringify!(#erl_func_name), "\0").as_ptr() as *const u8;
+        const NAME_STR: &'static str = stringify!(#erl_func_name);
+        const ARITY: u32 = #arity;
+        const FLAGS: u32 = #flags as u32;
+        const FUNC: rustler::codegen_runtime::DEF_NIF_FUNC = rustler::codegen_runtime::DEF_NIF_FUNC {
+            arity: Self::ARITY,
+            flags: Self::FLAGS,
+            function: Self::RAW_FUNC,
+            name: Self::NAME
+        };
+    };
+
+    let nif_impl = quote! {
         impl rustler::Nif for #name {
-            const NAME: *const u8 = concat!(stringify!(#erl_func_name), "\0").as_ptr() as *const u8;
-            const ARITY: u32 = #arity;
-            const FLAGS: u32 = #flags as u32;
+            #consts
             const RAW_FUNC: unsafe extern "C" fn(
                 nif_env: rustler::codegen_runtime::NIF_ENV,
                 argc: rustler::codegen_runtime::c_int,
@@ -40,6 +198,9 @@ pub fn transcoder_decorator(args: syn::AttributeArgs, fun: syn::ItemFn) -> Token
                     let lifetime = ();
                     let env = rustler::Env::new(&lifetime, nif_env);
 
+                    rustler::decode_trace::clear();
+                    #deprecation
+
                     let terms = std::slice::from_raw_parts(argv, argc as usize)
                         .iter()
                         .map(|term| rustler::Term::new(env, *term))
@@ -49,25 +210,59 @@ pub fn transcoder_decorator(args: syn::AttributeArgs, fun: syn::ItemFn) -> Token
                         env: rustler::Env<'a>,
                         args: &[rustler::Term<'a>]
                     ) -> rustler::codegen_runtime::NifReturned {
+                        #allow_question_mark
                         let result: std::thread::Result<_> = std::panic::catch_unwind(move || {
+                            #[allow(unused_variables)]
+                            let decode_batch = rustler::types::DecodeBatch::new(args).nif(#nif_name);
                             #decoded_terms
                             #function
-                            Ok(#name(#argument_names))
+                            Ok(#returned)
                         });
 
                         rustler::codegen_runtime::handle_nif_result(result, env)
                     }
-                    wrapper(env, &terms).apply(env)
+                    #call
                 }
                 nif_func
             };
-            const FUNC: rustler::codegen_runtime::DEF_NIF_FUNC = rustler::codegen_runtime::DEF_NIF_FUNC {
-                arity: Self::ARITY,
-                flags: Self::FLAGS,
-                function: Self::RAW_FUNC,
-                name: Self::NAME
-            };
         }
+    };
+
+    // Without its feature, the NIF is still registered, so that the functions of the module are
+    // the same in every build, and raises `{:error, :not_implemented}`.
+    let nif_impl = match feature {
+        Some(feature) => quote! {
+            #[cfg(feature = #feature)]
+            #nif_impl
+
+            #[cfg(not(feature = #feature))]
+            impl rustler::Nif for #name {
+                #consts
+                const RAW_FUNC: unsafe extern "C" fn(
+                    nif_env: rustler::codegen_runtime::NIF_ENV,
+                    argc: rustler::codegen_runtime::c_int,
+                    argv: *const rustler::codegen_runtime::NIF_TERM
+                ) -> rustler::codegen_runtime::NIF_TERM = {
+                    unsafe extern "C" fn nif_func(
+                        nif_env: rustler::codegen_runtime::NIF_ENV,
+                        _argc: rustler::codegen_runtime::c_int,
+                        _argv: *const rustler::codegen_runtime::NIF_TERM
+                    ) -> rustler::codegen_runtime::NIF_TERM {
+                        let lifetime = ();
+                        rustler::codegen_runtime::not_implemented(rustler::Env::new(&lifetime, nif_env))
+                    }
+                    nif_func
+                };
+            }
+        },
+        None => nif_impl,
+    };
+
+    quote! {
+        #[allow(non_camel_case_types)]
+        pub struct #name;
+
+        #nif_impl
     }
 }
 
@@ -107,6 +302,15 @@ fn extract_attr_value(args: syn::AttributeArgs, name: &str) -> Option<String> {
     None
 }
 
+fn has_flag(args: &[syn::NestedMeta], name: &str) -> bool {
+    use syn::{Meta, NestedMeta};
+
+    args.iter().any(|arg| match arg {
+        NestedMeta::Meta(Meta::Path(path)) => path.is_ident(name),
+        _ => false,
+    })
+}
+
 fn extract_inputs(inputs: Punctuated<syn::FnArg, Comma>) -> TokenStream {
     let mut tokens = TokenStream::new();
     let mut idx: usize = 0;
@@ -118,10 +322,7 @@ fn extract_inputs(inputs: Punctuated<syn::FnArg, Comma>) -> TokenStream {
             match &*typed.ty {
                 syn::Type::Reference(typ) => {
                     let decoder = quote! {
-                        let #name: #typ = match args[#idx].decode() {
-                            Ok(value) => value,
-                            Err(err) => return Err(err)
-                        };
+                        let #name: #typ = decode_batch.arg(#idx)?;
                     };
 
                     tokens.extend(decoder);
@@ -143,10 +344,7 @@ fn extract_inputs(inputs: Punctuated<syn::FnArg, Comma>) -> TokenStream {
                         }
                         _ => {
                             let decoder = quote! {
-                                let #name: #typ = match args[#idx].decode() {
-                                    Ok(value) => value,
-                                    Err(err) => return Err(err)
-                                };
+                                let #name: #typ = decode_batch.arg(#idx)?;
                             };
 
                             tokens.extend(decoder);
@@ -182,6 +380,18 @@ fn create_function_params(inputs: Punctuated<syn::FnArg, Comma>) -> TokenStream
     tokens
 }
 
+fn takes_env(inputs: &Punctuated<syn::FnArg, Comma>) -> bool {
+    match inputs.first() {
+        Some(syn::FnArg::Typed(typed)) => match &*typed.ty {
+            syn::Type::Path(syn::TypePath { path, .. }) => {
+                path.segments.last().unwrap().ident == "Env"
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
 fn arity(inputs: Punctuated<syn::FnArg, Comma>) -> u32 {
     let mut arity: u32 = 0;
 
@@ -209,9 +419,20 @@ fn arity(inputs: Punctuated<syn::FnArg, Comma>) -> u32 {
 
 fn validate_attributes(args: syn::AttributeArgs) {
     use syn::{Meta, MetaNameValue, NestedMeta};
-    let known_attrs = ["schedule", "name"];
+    let known_attrs = ["schedule", "name", "deprecated", "feature"];
+    let known_flags = ["cpu_time", "unit_ok", "unsafe_crash_guard"];
 
     for arg in args.iter() {
+        if let NestedMeta::Meta(Meta::Path(path)) = arg {
+            if known_flags.iter().all(|known| !path.is_ident(known)) {
+                panic!(
+                    "Unknown flag '{}'. Allowed flags: {:?}",
+                    path.to_token_stream(),
+                    known_flags
+                );
+            }
+        }
+
         if let NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, .. })) = arg {
             if known_attrs.iter().all(|known| !path.is_ident(known)) {
                 match path.get_ident() {
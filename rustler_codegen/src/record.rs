@@ -35,6 +35,8 @@ pub fn transcoder_decorator(ast: &syn::DeriveInput) -> TokenStream {
         quote! {}
     };
 
+    let summary = ctx.gen_summary();
+
     let gen = quote! {
         mod #atoms_module_name {
             #atom_defs
@@ -42,6 +44,7 @@ pub fn transcoder_decorator(ast: &syn::DeriveInput) -> TokenStream {
 
         #decoder
         #encoder
+        #summary
     };
 
     gen
@@ -52,7 +55,7 @@ fn gen_decoder(ctx: &Context, fields: &[&Field], atoms_module_name: &Ident) -> T
     let struct_name = ctx.ident;
 
     // Make a decoder for each of the fields in the struct.
-    let (assignments, field_defs): (Vec<TokenStream>, Vec<TokenStream>) = fields
+    let (assignments, mut field_defs): (Vec<TokenStream>, Vec<TokenStream>) = fields
         .iter()
         .enumerate()
         .map(|(index, field)| {
@@ -67,7 +70,7 @@ fn gen_decoder(ctx: &Context, fields: &[&Field], atoms_module_name: &Ident) -> T
             let variable = Context::escape_ident(&pos_in_struct, "record");
 
             let assignment = quote_spanned! { field.span() =>
-                let #variable = try_decode_index(&terms, #pos_in_struct, #actual_index)?;
+                let #variable = try_decode_index(term, profile, #pos_in_struct, #actual_index)?;
             };
 
             let field_def = match ident {
@@ -82,6 +85,7 @@ fn gen_decoder(ctx: &Context, fields: &[&Field], atoms_module_name: &Ident) -> T
         .unzip();
 
     let field_num = field_defs.len();
+    field_defs.extend(ctx.skipped_field_defs());
     let struct_name_str = struct_name.to_string();
 
     // The implementation itself
@@ -96,39 +100,71 @@ fn gen_decoder(ctx: &Context, fields: &[&Field], atoms_module_name: &Ident) -> T
             Ok(#struct_name { #(#field_defs),* })
         }
     };
-    let gen = quote! {
-        impl<'a> ::rustler::Decoder<'a> for #struct_type {
-            fn decode(term: ::rustler::Term<'a>) -> Result<Self, ::rustler::Error> {
-                use #atoms_module_name::*;
+    let body = ctx.with_profile(quote! {
+        use #atoms_module_name::*;
+
+        // Elements are read in place, like in `NifTuple` decoders.
+        let arity = match term.tuple_size() {
+            Err(_) => {
+                ::rustler::decode_trace::record(#struct_name_str, None, term);
+                return Err(::rustler::Error::RaiseTerm(
+                    Box::new(format!("Invalid Record structure for {}", #struct_name_str))));
+            }
+            Ok(value) => value,
+        };
 
-                let terms = match ::rustler::types::tuple::get_tuple(term) {
-                    Err(_) => return Err(::rustler::Error::RaiseTerm(
-                            Box::new(format!("Invalid Record structure for {}", #struct_name_str)))),
-                    Ok(value) => value,
-                };
+        if arity != #field_num + 1 {
+            ::rustler::decode_trace::record(#struct_name_str, None, term);
+            return Err(::rustler::Error::Atom("invalid_record"));
+        }
 
-                if terms.len() != #field_num + 1 {
-                    return Err(::rustler::Error::Atom("invalid_record"));
-                }
+        let tag_term = term.tuple_get(0)?;
+        let tag : ::rustler::types::atom::Atom = tag_term.decode().map_err(|err| {
+            ::rustler::decode_trace::record(#struct_name_str, Some("tag"), tag_term);
+            err
+        })?;
 
-                let tag : ::rustler::types::atom::Atom = terms[0].decode()?;
+        if tag != atom_tag() {
+            ::rustler::decode_trace::record(#struct_name_str, Some("tag"), tag_term);
+            return Err(::rustler::Error::Atom("invalid_record"));
+        }
 
-                if tag != atom_tag() {
-                    return Err(::rustler::Error::Atom("invalid_record"));
+        fn try_decode_index<'a, T>(
+            term: ::rustler::Term<'a>,
+            profile: ::rustler::EncodingProfile,
+            pos_in_struct: &'static str,
+            index: usize,
+            ) -> Result<T, rustler::Error>
+            where
+                T: rustler::Decoder<'a>,
+        {
+            let item = term.tuple_get(index)?;
+            match ::rustler::Decoder::decode_with(item, profile) {
+                Err(_) => {
+                    ::rustler::decode_trace::record(#struct_name_str, Some(pos_in_struct), item);
+                    Err(::rustler::Error::RaiseTerm(Box::new(
+                            format!("Could not decode field {} on Record {}", pos_in_struct, #struct_name_str))))
                 }
+                Ok(value) => Ok(value)
+            }
+        }
 
-                fn try_decode_index<'a, T>(terms: &[::rustler::Term<'a>], pos_in_struct: &str, index: usize) -> Result<T, rustler::Error>
-                    where
-                        T: rustler::Decoder<'a>,
-                {
-                    match ::rustler::Decoder::decode(terms[index]) {
-                        Err(_) => Err(::rustler::Error::RaiseTerm(Box::new(
-                                    format!("Could not decode field {} on Record {}", pos_in_struct, #struct_name_str)))),
-                        Ok(value) => Ok(value)
-                    }
-                }
+        #construct
+    });
+
+    let impl_generics = ctx.impl_generics(quote! { 'a }, Some(quote! { ::rustler::Decoder<'a> }));
+    let where_clause = ctx.where_clause();
+    let gen = quote! {
+        impl #impl_generics ::rustler::Decoder<'a> for #struct_type #where_clause {
+            fn decode(term: ::rustler::Term<'a>) -> Result<Self, ::rustler::Error> {
+                <Self as ::rustler::Decoder<'a>>::decode_with(term, ::rustler::EncodingProfile::ELIXIR)
+            }
 
-                #construct
+            fn decode_with(
+                term: ::rustler::Term<'a>,
+                profile: ::rustler::EncodingProfile,
+            ) -> Result<Self, ::rustler::Error> {
+                #body
             }
         }
     };
@@ -150,7 +186,7 @@ fn gen_encoder(ctx: &Context, fields: &[&Field], atoms_module_name: &Ident) -> T
                 Some(ident) => quote! { self.#ident },
             };
 
-            quote_spanned! { field.span() => #field_source.encode(env) }
+            quote_spanned! { field.span() => #field_source.encode_with(env, profile) }
         })
         .collect();
 
@@ -163,14 +199,28 @@ fn gen_encoder(ctx: &Context, fields: &[&Field], atoms_module_name: &Ident) -> T
     };
 
     // The implementation itself
+    let body = ctx.with_profile(quote! {
+        use #atoms_module_name::*;
+
+        use ::rustler::Encoder;
+        let arr = #field_list_ast;
+        ::rustler::types::tuple::make_tuple(env, &arr)
+    });
+
+    let impl_generics = ctx.impl_generics(quote! { 'b }, Some(quote! { ::rustler::Encoder }));
+    let where_clause = ctx.where_clause();
     let gen = quote! {
-        impl<'b> ::rustler::Encoder for #struct_type {
+        impl #impl_generics ::rustler::Encoder for #struct_type #where_clause {
             fn encode<'a>(&self, env: ::rustler::Env<'a>) -> ::rustler::Term<'a> {
-                use #atoms_module_name::*;
+                ::rustler::Encoder::encode_with(self, env, ::rustler::EncodingProfile::ELIXIR)
+            }
 
-                use ::rustler::Encoder;
-                let arr = #field_list_ast;
-                ::rustler::types::tuple::make_tuple(env, &arr)
+            fn encode_with<'a>(
+                &self,
+                env: ::rustler::Env<'a>,
+                profile: ::rustler::EncodingProfile,
+            ) -> ::rustler::Term<'a> {
+                #body
             }
         }
     };
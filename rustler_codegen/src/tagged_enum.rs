@@ -0,0 +1,319 @@
+use proc_macro2::{Span, TokenStream};
+
+use heck::SnakeCase;
+use syn::{self, Field, Fields, Ident, Variant};
+
+use super::context::Context;
+
+pub fn transcoder_decorator(ast: &syn::DeriveInput) -> TokenStream {
+    let ctx = Context::from_ast(ast);
+
+    let variants = ctx
+        .variants
+        .as_ref()
+        .expect("NifTaggedEnum can only be used with enums");
+
+    for variant in variants {
+        if variant.fields.iter().any(Context::is_skipped) {
+            panic!("`skip` is not allowed on fields of variants");
+        }
+    }
+
+    let mut atoms: Vec<TokenStream> = Vec::new();
+    for (index, variant) in variants.iter().enumerate() {
+        let atom_str = ctx.variant_name(variant);
+        let atom_fn = atom_fn(variant);
+        atoms.push(quote! { #atom_fn = #atom_str, });
+
+        if let Fields::Named(ref fields) = variant.fields {
+            for (field_index, field) in fields.named.iter().enumerate() {
+                let atom_str = Context::variant_field_name(field);
+                let atom_fn = field_atom_fn(index, field_index);
+                atoms.push(quote! { #atom_fn = #atom_str, });
+            }
+        }
+    }
+
+    let atom_defs = quote! {
+        rustler::atoms! {
+            #(#atoms)*
+        }
+    };
+
+    let atoms_module_name = ctx.atoms_module_name(Span::call_site());
+
+    let decoder = if ctx.decode() {
+        gen_decoder(&ctx, variants, &atoms_module_name)
+    } else {
+        quote! {}
+    };
+
+    let encoder = if ctx.encode() {
+        gen_encoder(&ctx, variants, &atoms_module_name)
+    } else {
+        quote! {}
+    };
+
+    let gen = quote! {
+        mod #atoms_module_name {
+            #atom_defs
+        }
+
+        #decoder
+        #encoder
+    };
+
+    gen
+}
+
+fn gen_decoder(ctx: &Context, variants: &[&Variant], atoms_module_name: &Ident) -> TokenStream {
+    let enum_type = &ctx.ident_with_lifetime;
+    let enum_name = ctx.ident;
+    let enum_name_str = enum_name.to_string();
+
+    let unit_defs: Vec<TokenStream> = variants
+        .iter()
+        .filter(|variant| matches!(variant.fields, Fields::Unit))
+        .map(|variant| {
+            let variant_ident = &variant.ident;
+            let atom_fn = atom_fn(variant);
+
+            quote! {
+                if value == #atom_fn() {
+                    return Ok(#enum_name::#variant_ident);
+                }
+            }
+        })
+        .collect();
+
+    let tagged_defs: Vec<TokenStream> = variants
+        .iter()
+        .enumerate()
+        .filter_map(|(index, variant)| {
+            let variant_ident = &variant.ident;
+            let variant_str = variant_ident.to_string();
+            let atom_fn = atom_fn(variant);
+
+            match variant.fields {
+                Fields::Unit => None,
+                Fields::Unnamed(ref fields) => {
+                    let arity = fields.unnamed.len();
+                    let decoded = (0..arity).map(|field_index| {
+                        quote! { decode_payload(profile, #variant_str, payload[#field_index])? }
+                    });
+
+                    Some(quote! {
+                        if tag == #atom_fn() && payload.len() == #arity {
+                            return Ok(#enum_name::#variant_ident(#(#decoded),*));
+                        }
+                    })
+                }
+                Fields::Named(ref fields) => {
+                    let field_defs = fields.named.iter().enumerate().map(|(field_index, field)| {
+                        gen_field_decoder(ctx, index, field_index, field)
+                    });
+
+                    Some(quote! {
+                        if tag == #atom_fn() && payload.len() == 1 && payload[0].is_map() {
+                            let map = payload[0];
+                            return Ok(#enum_name::#variant_ident { #(#field_defs),* });
+                        }
+                    })
+                }
+            }
+        })
+        .collect();
+
+    let unit_body = if unit_defs.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            if let Ok(value) = ::rustler::types::atom::Atom::from_term(term) {
+                #(#unit_defs)*
+            }
+        }
+    };
+
+    let tagged_body = if tagged_defs.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            fn decode_payload<'a, T>(
+                profile: ::rustler::EncodingProfile,
+                variant: &'static str,
+                term: ::rustler::Term<'a>,
+            ) -> Result<T, ::rustler::Error>
+            where
+                T: ::rustler::Decoder<'a>,
+            {
+                ::rustler::Decoder::decode_with(term, profile).map_err(|err| {
+                    ::rustler::decode_trace::record(#enum_name_str, Some(variant), term);
+                    err
+                })
+            }
+
+            if let Ok(items) = ::rustler::types::tuple::get_tuple(term) {
+                if let Some((tag, payload)) = items.split_first() {
+                    if let Ok(tag) = ::rustler::types::atom::Atom::from_term(*tag) {
+                        #(#tagged_defs)*
+                    }
+                }
+            }
+        }
+    };
+
+    let body = ctx.with_profile(quote! {
+        use #atoms_module_name::*;
+
+        #unit_body
+        #tagged_body
+
+        ::rustler::decode_trace::record(#enum_name_str, None, term);
+        Err(::rustler::Error::Atom("invalid_variant"))
+    });
+
+    let impl_generics = ctx.impl_generics(quote! { 'a }, Some(quote! { ::rustler::Decoder<'a> }));
+    let where_clause = ctx.where_clause();
+    let gen = quote! {
+        impl #impl_generics ::rustler::Decoder<'a> for #enum_type #where_clause {
+            fn decode(term: ::rustler::Term<'a>) -> Result<Self, ::rustler::Error> {
+                <Self as ::rustler::Decoder<'a>>::decode_with(term, ::rustler::EncodingProfile::ELIXIR)
+            }
+
+            fn decode_with(
+                term: ::rustler::Term<'a>,
+                profile: ::rustler::EncodingProfile,
+            ) -> Result<Self, ::rustler::Error> {
+                #body
+            }
+        }
+    };
+
+    gen
+}
+
+/// Decodes a field of a struct variant from `map`, falling back to the default of the field, if
+/// any, when the key is missing.
+fn gen_field_decoder(
+    ctx: &Context,
+    variant_index: usize,
+    field_index: usize,
+    field: &Field,
+) -> TokenStream {
+    let enum_name_str = ctx.ident.to_string();
+    let ident = field.ident.as_ref().unwrap();
+    let field_name = Context::variant_field_name(field);
+    let atom_fn = field_atom_fn(variant_index, field_index);
+
+    let missing = match Context::field_default(field) {
+        Some(default) => quote! { #default },
+        None => quote! {
+            {
+                ::rustler::decode_trace::record(#enum_name_str, Some(#field_name), map);
+                return Err(::rustler::Error::BadArg);
+            }
+        },
+    };
+
+    quote! {
+        #ident: {
+            let key = profile.key(map.get_env(), #field_name, #atom_fn());
+            match map.map_get(key) {
+                Ok(value) => decode_payload(profile, #field_name, value)?,
+                Err(_) => #missing,
+            }
+        }
+    }
+}
+
+fn gen_encoder(ctx: &Context, variants: &[&Variant], atoms_module_name: &Ident) -> TokenStream {
+    let enum_type = &ctx.ident_with_lifetime;
+    let enum_name = ctx.ident;
+
+    let variant_defs: Vec<TokenStream> = variants
+        .iter()
+        .enumerate()
+        .map(|(index, variant)| {
+            let variant_ident = &variant.ident;
+            let atom_fn = atom_fn(variant);
+            let bindings: Vec<Ident> = (0..variant.fields.len())
+                .map(|field_index| Ident::new(&format!("field_{}", field_index), Span::call_site()))
+                .collect();
+
+            match variant.fields {
+                Fields::Unit => quote! {
+                    #enum_name::#variant_ident => #atom_fn().encode(env),
+                },
+                Fields::Unnamed(_) => quote! {
+                    #enum_name::#variant_ident(#(ref #bindings),*) => {
+                        ::rustler::types::tuple::make_tuple(
+                            env,
+                            &[#atom_fn().encode(env) #(, #bindings.encode_with(env, profile))*],
+                        )
+                    }
+                },
+                Fields::Named(ref fields) => {
+                    let idents = fields.named.iter().map(|field| &field.ident);
+                    let keys = fields.named.iter().enumerate().map(|(field_index, field)| {
+                        let field_name = Context::variant_field_name(field);
+                        let atom_fn = field_atom_fn(index, field_index);
+                        quote! { profile.key(env, #field_name, #atom_fn()) }
+                    });
+
+                    quote! {
+                        #enum_name::#variant_ident { #(#idents: ref #bindings),* } => {
+                            let keys = [#(#keys),*];
+                            let values = [#(#bindings.encode_with(env, profile)),*];
+                            let map = ::rustler::types::map::map_from_entries(env, &keys, &values);
+                            ::rustler::types::tuple::make_tuple(env, &[#atom_fn().encode(env), map])
+                        }
+                    }
+                }
+            }
+        })
+        .collect();
+
+    let body = ctx.with_profile(quote! {
+        use #atoms_module_name::*;
+        use ::rustler::Encoder;
+
+        match *self {
+            #(#variant_defs)*
+        }
+    });
+
+    let impl_generics = ctx.impl_generics(quote! { 'b }, Some(quote! { ::rustler::Encoder }));
+    let where_clause = ctx.where_clause();
+    let gen = quote! {
+        impl #impl_generics ::rustler::Encoder for #enum_type #where_clause {
+            fn encode<'a>(&self, env: ::rustler::Env<'a>) -> ::rustler::Term<'a> {
+                ::rustler::Encoder::encode_with(self, env, ::rustler::EncodingProfile::ELIXIR)
+            }
+
+            fn encode_with<'a>(
+                &self,
+                env: ::rustler::Env<'a>,
+                profile: ::rustler::EncodingProfile,
+            ) -> ::rustler::Term<'a> {
+                #body
+            }
+        }
+    };
+
+    gen
+}
+
+/// The function of the atom of `variant`, named after its identifier whatever the atom is.
+fn atom_fn(variant: &Variant) -> Ident {
+    let ident_str = variant.ident.to_string().to_snake_case();
+    Ident::new(&format!("atom_{}", ident_str), Span::call_site())
+}
+
+/// The function of the atom of a field of a struct variant, named after the indices of the
+/// variant and the field, as fields of different variants can share a name.
+fn field_atom_fn(variant_index: usize, field_index: usize) -> Ident {
+    Ident::new(
+        &format!("field_{}_{}", variant_index, field_index),
+        Span::call_site(),
+    )
+}
@@ -24,9 +24,19 @@ pub fn transcoder_decorator(ast: &syn::DeriveInput) -> TokenStream {
         quote! {}
     };
 
+    let build_term = if ctx.build_term() {
+        gen_build_term(&ctx, struct_fields)
+    } else {
+        quote! {}
+    };
+
+    let summary = ctx.gen_summary();
+
     let gen = quote! {
         #decoder
         #encoder
+        #build_term
+        #summary
     };
 
     gen
@@ -38,7 +48,7 @@ fn gen_decoder(ctx: &Context, fields: &[&Field]) -> TokenStream {
     let struct_name_str = struct_name.to_string();
 
     // Make a decoder for each of the fields in the struct.
-    let (assignments, field_defs): (Vec<TokenStream>, Vec<TokenStream>) = fields
+    let (assignments, mut field_defs): (Vec<TokenStream>, Vec<TokenStream>) = fields
         .iter()
         .enumerate()
         .map(|(index, field)| {
@@ -52,7 +62,7 @@ fn gen_decoder(ctx: &Context, fields: &[&Field]) -> TokenStream {
             let variable = Context::escape_ident(&pos_in_struct, "struct");
 
             let assignment = quote_spanned! { field.span() =>
-                let #variable = try_decode_index(&terms, #pos_in_struct, #index)?;
+                let #variable = try_decode_index(term, profile, #pos_in_struct, #index)?;
             };
 
             let field_def = match ident {
@@ -67,6 +77,7 @@ fn gen_decoder(ctx: &Context, fields: &[&Field]) -> TokenStream {
         .unzip();
 
     let field_num = field_defs.len();
+    field_defs.extend(ctx.skipped_field_defs());
 
     // The implementation itself
     let construct = if ctx.is_tuple_struct {
@@ -80,25 +91,53 @@ fn gen_decoder(ctx: &Context, fields: &[&Field]) -> TokenStream {
             Ok(#struct_name { #(#field_defs),* })
         }
     };
+    let body = ctx.with_profile(quote! {
+        // Elements are read in place, so that decoding fields that borrow from the term, like
+        // `Binary` and `&[u8]`, doesn't allocate.
+        let arity = term.tuple_size().map_err(|err| {
+            ::rustler::decode_trace::record(#struct_name_str, None, term);
+            err
+        })?;
+        if arity != #field_num {
+            ::rustler::decode_trace::record(#struct_name_str, None, term);
+            return Err(::rustler::Error::BadArg);
+        }
+
+        fn try_decode_index<'a, T>(
+            term: ::rustler::Term<'a>,
+            profile: ::rustler::EncodingProfile,
+            pos_in_struct: &'static str,
+            index: usize,
+            ) -> Result<T, rustler::Error>
+            where
+                T: rustler::Decoder<'a>,
+        {
+            let item = term.tuple_get(index)?;
+            match ::rustler::Decoder::decode_with(item, profile) {
+                Err(_) => {
+                    ::rustler::decode_trace::record(#struct_name_str, Some(pos_in_struct), item);
+                    Err(::rustler::Error::RaiseTerm(Box::new(
+                            format!("Could not decode field {} on {}", pos_in_struct, #struct_name_str))))
+                }
+                Ok(value) => Ok(value)
+            }
+        }
+        #construct
+    });
+
+    let impl_generics = ctx.impl_generics(quote! { 'a }, Some(quote! { ::rustler::Decoder<'a> }));
+    let where_clause = ctx.where_clause();
     let gen = quote! {
-        impl<'a> ::rustler::Decoder<'a> for #struct_type {
+        impl #impl_generics ::rustler::Decoder<'a> for #struct_type #where_clause {
             fn decode(term: ::rustler::Term<'a>) -> Result<Self, ::rustler::Error> {
-                let terms = ::rustler::types::tuple::get_tuple(term)?;
-                if terms.len() != #field_num {
-                    return Err(::rustler::Error::BadArg);
-                }
+                <Self as ::rustler::Decoder<'a>>::decode_with(term, ::rustler::EncodingProfile::ELIXIR)
+            }
 
-                fn try_decode_index<'a, T>(terms: &[::rustler::Term<'a>], pos_in_struct: &str, index: usize) -> Result<T, rustler::Error>
-                    where
-                        T: rustler::Decoder<'a>,
-                {
-                    match ::rustler::Decoder::decode(terms[index]) {
-                        Err(_) => Err(::rustler::Error::RaiseTerm(Box::new(
-                                    format!("Could not decode field {} on {}", pos_in_struct, #struct_name_str)))),
-                        Ok(value) => Ok(value)
-                    }
-                }
-                #construct
+            fn decode_with(
+                term: ::rustler::Term<'a>,
+                profile: ::rustler::EncodingProfile,
+            ) -> Result<Self, ::rustler::Error> {
+                #body
             }
         }
     };
@@ -120,7 +159,7 @@ fn gen_encoder(ctx: &Context, fields: &[&Field]) -> TokenStream {
                 Some(ident) => quote! { self.#ident },
             };
 
-            quote_spanned! { field.span() => #field_source.encode(env) }
+            quote_spanned! { field.span() => #field_source.encode_with(env, profile) }
         })
         .collect();
 
@@ -130,15 +169,62 @@ fn gen_encoder(ctx: &Context, fields: &[&Field]) -> TokenStream {
     };
 
     // The implementation itself
+    let body = ctx.with_profile(quote! {
+        use ::rustler::Encoder;
+        let arr = #field_list_ast;
+        ::rustler::types::tuple::make_tuple(env, &arr)
+    });
+
+    let impl_generics = ctx.impl_generics(quote! { 'b }, Some(quote! { ::rustler::Encoder }));
+    let where_clause = ctx.where_clause();
     let gen = quote! {
-        impl<'b> ::rustler::Encoder for #struct_type {
+        impl #impl_generics ::rustler::Encoder for #struct_type #where_clause {
             fn encode<'a>(&self, env: ::rustler::Env<'a>) -> ::rustler::Term<'a> {
-                use ::rustler::Encoder;
-                let arr = #field_list_ast;
-                ::rustler::types::tuple::make_tuple(env, &arr)
+                ::rustler::Encoder::encode_with(self, env, ::rustler::EncodingProfile::ELIXIR)
+            }
+
+            fn encode_with<'a>(
+                &self,
+                env: ::rustler::Env<'a>,
+                profile: ::rustler::EncodingProfile,
+            ) -> ::rustler::Term<'a> {
+                #body
             }
         }
     };
 
     gen
 }
+
+fn gen_build_term(ctx: &Context, fields: &[&Field]) -> TokenStream {
+    let struct_type = &ctx.ident_with_lifetime;
+
+    let elements: Vec<TokenStream> = fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let literal_index = Index::from(index);
+            let field_source = match field.ident.as_ref() {
+                None => quote! { self.#literal_index },
+                Some(ident) => quote! { self.#ident },
+            };
+
+            quote_spanned! { field.span() =>
+                ::rustler::backend::BuildTerm::build(&#field_source, backend)
+            }
+        })
+        .collect();
+
+    let impl_generics = ctx.impl_generics(
+        quote! { 'b },
+        Some(quote! { ::rustler::backend::BuildTerm }),
+    );
+    let where_clause = ctx.where_clause();
+    quote! {
+        impl #impl_generics ::rustler::backend::BuildTerm for #struct_type #where_clause {
+            fn build<B: ::rustler::backend::Backend>(&self, backend: B) -> B::Term {
+                backend.tuple(vec![#(#elements),*])
+            }
+        }
+    }
+}
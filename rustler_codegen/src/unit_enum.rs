@@ -25,8 +25,8 @@ pub fn transcoder_decorator(ast: &syn::DeriveInput) -> TokenStream {
     let atoms: Vec<TokenStream> = variants
         .iter()
         .map(|variant| {
-            let atom_str = variant.ident.to_string().to_snake_case();
-            let atom_fn = Ident::new(&format!("atom_{}", atom_str), Span::call_site());
+            let atom_str = ctx.variant_name(variant);
+            let atom_fn = atom_fn(variant);
             quote! {
                 #atom_fn = #atom_str,
             }
@@ -53,6 +53,12 @@ pub fn transcoder_decorator(ast: &syn::DeriveInput) -> TokenStream {
         quote! {}
     };
 
+    let build_term = if ctx.build_term() {
+        gen_build_term(&ctx, variants)
+    } else {
+        quote! {}
+    };
+
     let gen = quote! {
         mod #atoms_module_name {
             #atom_defs
@@ -60,6 +66,7 @@ pub fn transcoder_decorator(ast: &syn::DeriveInput) -> TokenStream {
 
         #decoder
         #encoder
+        #build_term
     };
 
     gen
@@ -68,13 +75,13 @@ pub fn transcoder_decorator(ast: &syn::DeriveInput) -> TokenStream {
 fn gen_decoder(ctx: &Context, variants: &[&Variant], atoms_module_name: &Ident) -> TokenStream {
     let enum_type = &ctx.ident_with_lifetime;
     let enum_name = ctx.ident;
+    let enum_name_str = enum_name.to_string();
 
     let variant_defs: Vec<TokenStream> = variants
         .iter()
         .map(|variant| {
             let variant_ident = &variant.ident;
-            let atom_str = variant_ident.to_string().to_snake_case();
-            let atom_fn = Ident::new(&format!("atom_{}", atom_str), Span::call_site());
+            let atom_fn = atom_fn(variant);
 
             quote! {
                 if value == #atom_fn() {
@@ -84,15 +91,21 @@ fn gen_decoder(ctx: &Context, variants: &[&Variant], atoms_module_name: &Ident)
         })
         .collect();
 
+    let impl_generics = ctx.impl_generics(quote! { 'a }, Some(quote! { ::rustler::Decoder<'a> }));
+    let where_clause = ctx.where_clause();
     let gen = quote! {
-        impl<'a> ::rustler::Decoder<'a> for #enum_type {
+        impl #impl_generics ::rustler::Decoder<'a> for #enum_type #where_clause {
             fn decode(term: ::rustler::Term<'a>) -> Result<Self, ::rustler::Error> {
                 use #atoms_module_name::*;
 
-                let value = ::rustler::types::atom::Atom::from_term(term)?;
+                let value = ::rustler::types::atom::Atom::from_term(term).map_err(|err| {
+                    ::rustler::decode_trace::record(#enum_name_str, None, term);
+                    err
+                })?;
 
                 #(#variant_defs)*
 
+                ::rustler::decode_trace::record(#enum_name_str, None, term);
                 Err(::rustler::Error::Atom("invalid_variant"))
             }
         }
@@ -109,8 +122,7 @@ fn gen_encoder(ctx: &Context, variants: &[&Variant], atoms_module_name: &Ident)
         .iter()
         .map(|variant| {
             let variant_ident = &variant.ident;
-            let atom_str = variant_ident.to_string().to_snake_case();
-            let atom_fn = Ident::new(&format!("atom_{}", atom_str), Span::call_site());
+            let atom_fn = atom_fn(variant);
 
             quote! {
                 #enum_name :: #variant_ident => #atom_fn().encode(env),
@@ -118,8 +130,10 @@ fn gen_encoder(ctx: &Context, variants: &[&Variant], atoms_module_name: &Ident)
         })
         .collect();
 
+    let impl_generics = ctx.impl_generics(quote! { 'b }, Some(quote! { ::rustler::Encoder }));
+    let where_clause = ctx.where_clause();
     let gen = quote! {
-        impl<'b> ::rustler::Encoder for #enum_type {
+        impl #impl_generics ::rustler::Encoder for #enum_type #where_clause {
             fn encode<'a>(&self, env: ::rustler::Env<'a>) -> ::rustler::Term<'a> {
                 use #atoms_module_name::*;
 
@@ -132,3 +146,38 @@ fn gen_encoder(ctx: &Context, variants: &[&Variant], atoms_module_name: &Ident)
 
     gen
 }
+
+fn gen_build_term(ctx: &Context, variants: &[&Variant]) -> TokenStream {
+    let enum_type = &ctx.ident_with_lifetime;
+    let enum_name = ctx.ident;
+
+    let variant_defs: Vec<TokenStream> = variants
+        .iter()
+        .map(|variant| {
+            let variant_ident = &variant.ident;
+            let atom_str = ctx.variant_name(variant);
+
+            quote! {
+                #enum_name :: #variant_ident => backend.atom(#atom_str),
+            }
+        })
+        .collect();
+
+    let impl_generics = ctx.impl_generics(quote! { 'b }, None);
+    let where_clause = ctx.where_clause();
+    quote! {
+        impl #impl_generics ::rustler::backend::BuildTerm for #enum_type #where_clause {
+            fn build<B: ::rustler::backend::Backend>(&self, backend: B) -> B::Term {
+                match *self {
+                    #(#variant_defs)*
+                }
+            }
+        }
+    }
+}
+
+/// The function of the atom of `variant`, named after its identifier whatever the atom is.
+fn atom_fn(variant: &Variant) -> Ident {
+    let ident_str = variant.ident.to_string().to_snake_case();
+    Ident::new(&format!("atom_{}", ident_str), Span::call_site())
+}
@@ -49,6 +49,7 @@ pub fn transcoder_decorator(ast: &syn::DeriveInput) -> TokenStream {
 fn gen_decoder(ctx: &Context, variants: &[&Variant]) -> TokenStream {
     let enum_type = &ctx.ident_with_lifetime;
     let enum_name = ctx.ident;
+    let enum_name_str = enum_name.to_string();
 
     let variant_defs: Vec<_> = variants
         .iter()
@@ -64,11 +65,14 @@ fn gen_decoder(ctx: &Context, variants: &[&Variant]) -> TokenStream {
         })
         .collect();
 
+    let impl_generics = ctx.impl_generics(quote! { 'a }, Some(quote! { ::rustler::Decoder<'a> }));
+    let where_clause = ctx.where_clause();
     let gen = quote! {
-        impl<'a> ::rustler::Decoder<'a> for #enum_type {
+        impl #impl_generics ::rustler::Decoder<'a> for #enum_type #where_clause {
             fn decode(term: ::rustler::Term<'a>) -> Result<Self, ::rustler::Error> {
                 #(#variant_defs)*
 
+                ::rustler::decode_trace::record(#enum_name_str, None, term);
                 Err(::rustler::Error::Atom("invalid_variant"))
             }
         }
@@ -92,8 +96,10 @@ fn gen_encoder(ctx: &Context, variants: &[&Variant]) -> TokenStream {
         })
         .collect();
 
+    let impl_generics = ctx.impl_generics(quote! { 'b }, Some(quote! { ::rustler::Encoder }));
+    let where_clause = ctx.where_clause();
     let gen = quote! {
-        impl<'b> ::rustler::Encoder for #enum_type {
+        impl #impl_generics ::rustler::Encoder for #enum_type #where_clause {
             fn encode<'a>(&self, env: ::rustler::Env<'a>) -> ::rustler::Term<'a> {
                 match *self {
                     #(#variant_defs)*
@@ -39,6 +39,11 @@ defmodule Rustler do
     * `:load_data` - Any valid term. This value is passed into the NIF when it is
       loaded (default: `0`)
 
+    * `:load_data_fun` - A `{module, function}` tuple. The function is called without
+      arguments when the NIF is loaded, and its result is passed into the NIF instead of
+      `:load_data`. This allows passing environment variables or application configuration,
+      which is read again when the module is reloaded.
+
     * `:load_from` - This option allows control over where the final artifact should be
       loaded from at runtime. By default the compiled artifact is loaded from the
       owning `:otp_app`'s `priv/native` directory. This option comes in handy in
@@ -85,6 +90,7 @@ defmodule Rustler do
       if config.lib do
         @load_from config.load_from
         @load_data config.load_data
+        @load_data_fun config.load_data_fun
 
         @before_compile Rustler
       end
@@ -95,6 +101,14 @@ defmodule Rustler do
     quote do
       @on_load :rustler_init
 
+      @doc false
+      # Replaced by the NIF library when built with the `resource-tracking` feature.
+      def __rustler_resources__, do: :erlang.nif_error(:nif_not_loaded)
+
+      @doc false
+      # Replaced by the NIF library when it is initialized with the `features` option.
+      def __rustler_features__, do: :erlang.nif_error(:nif_not_loaded)
+
       @doc false
       def rustler_init do
         # Remove any old modules that may be loaded so we don't get
@@ -108,7 +122,13 @@ defmodule Rustler do
           |> Application.app_dir(path)
           |> to_charlist()
 
-        :erlang.load_nif(load_path, @load_data)
+        load_data =
+          case @load_data_fun do
+            {module, function} -> apply(module, function, [])
+            nil -> @load_data
+          end
+
+        :erlang.load_nif(load_path, load_data)
       end
     end
   end
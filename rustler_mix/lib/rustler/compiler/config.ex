@@ -9,6 +9,7 @@ defmodule Rustler.Compiler.Config do
   @type features :: [binary()]
   @type mode :: :debug | :release
   @type load_data :: term()
+  @type load_data_fun :: {module(), atom()} | nil
   @type path :: Path.t()
 
   defstruct cargo: :system,
@@ -19,6 +20,7 @@ defmodule Rustler.Compiler.Config do
             features: [],
             lib: true,
             load_data: 0,
+            load_data_fun: nil,
             load_from: nil,
             mode: :release,
             otp_app: nil,
@@ -0,0 +1,56 @@
+defmodule Rustler.Ets do
+  @moduledoc """
+  Inserts rows produced by NIFs into ETS tables.
+
+  The NIF API has no access to ETS, so NIFs using `rustler::ets` send their
+  rows to this process instead, which inserts them. Add it to the supervision
+  tree of your application:
+
+      children = [
+        Rustler.Ets,
+        ...
+      ]
+
+  The tables must be public, or owned by this process. Rows are inserted
+  asynchronously, in the order in which they were sent: call `sync/1` after
+  the NIF returns to wait until its rows are inserted. Rows that can't be
+  inserted, e.g. into a protected or deleted table, are dropped and logged.
+  """
+
+  use GenServer
+  require Logger
+
+  def start_link(opts \\ []) do
+    GenServer.start_link(__MODULE__, nil, Keyword.put_new(opts, :name, __MODULE__))
+  end
+
+  @doc """
+  Waits until the rows sent by the calling process are inserted.
+  """
+  def sync(timeout \\ 5000) do
+    GenServer.call(__MODULE__, :sync, timeout)
+  end
+
+  @impl true
+  def init(nil), do: {:ok, nil}
+
+  @impl true
+  def handle_info({:insert, table, rows}, state) do
+    try do
+      :ets.insert(table, rows)
+    rescue
+      error in ArgumentError ->
+        Logger.error(
+          "Rustler.Ets could not insert #{length(rows)} rows into #{inspect(table)}: " <>
+            Exception.message(error)
+        )
+    end
+
+    {:noreply, state}
+  end
+
+  @impl true
+  def handle_call(:sync, _from, state) do
+    {:reply, :ok, state}
+  end
+end
@@ -0,0 +1,34 @@
+defmodule Rustler.Logger do
+  @moduledoc """
+  Logs messages produced by NIFs with `Logger`.
+
+  The NIF API has no access to `Logger`, so NIFs using `rustler::log` send
+  their messages to this process instead, which logs them. This includes the
+  warnings of NIFs declared with `#[rustler::nif(deprecated = "...")]`. Add it
+  to the supervision tree of your application:
+
+      children = [
+        Rustler.Logger,
+        ...
+      ]
+
+  Without it, the messages are dropped.
+  """
+
+  use GenServer
+
+  require Logger
+
+  def start_link(opts \\ []) do
+    GenServer.start_link(__MODULE__, nil, Keyword.put_new(opts, :name, __MODULE__))
+  end
+
+  @impl true
+  def init(nil), do: {:ok, nil}
+
+  @impl true
+  def handle_info({:log, level, message}, state) do
+    Logger.log(level, message)
+    {:noreply, state}
+  end
+end
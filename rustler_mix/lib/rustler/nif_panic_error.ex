@@ -0,0 +1,11 @@
+defmodule Rustler.NifPanicError do
+  @moduledoc """
+  Raised by a NIF that panicked, when its library is initialized with
+  `on_panic = "exception"`.
+
+  `message` is the panic message, and `backtrace` the backtrace of the panic
+  in the native code, as a string.
+  """
+
+  defexception [:message, :backtrace]
+end
@@ -0,0 +1,36 @@
+defmodule Rustler.PersistentTerm do
+  @moduledoc """
+  Publishes terms produced by NIFs to `:persistent_term`.
+
+  The NIF API has no access to `:persistent_term`, so NIFs using
+  `rustler::persistent_term` send their updates to this process instead, which
+  applies them. Add it to the supervision tree of your application:
+
+      children = [
+        Rustler.PersistentTerm,
+        ...
+      ]
+
+  Updates are applied asynchronously, in the order in which they were sent.
+  """
+
+  use GenServer
+
+  def start_link(opts \\ []) do
+    GenServer.start_link(__MODULE__, nil, Keyword.put_new(opts, :name, __MODULE__))
+  end
+
+  @impl true
+  def init(nil), do: {:ok, nil}
+
+  @impl true
+  def handle_info({:put, key, value}, state) do
+    :persistent_term.put(key, value)
+    {:noreply, state}
+  end
+
+  def handle_info({:erase, key}, state) do
+    :persistent_term.erase(key)
+    {:noreply, state}
+  end
+end
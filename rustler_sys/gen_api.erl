@@ -36,6 +36,9 @@ version_opts("2.14") -> [{major,2}, {minor,14}, exception, getenv, time,   % erl
 version_opts("2.15") -> [{major,2}, {minor,15}, exception, getenv, time,   % erlang 22.0
                         dirty_scheduler_opt, nif_2_11, nif_2_12, nif_2_13,
                         nif_2_14, nif_2_15];
+version_opts("2.16") -> [{major,2}, {minor,16}, exception, getenv, time,   % erlang 24.0
+                        dirty_scheduler_opt, nif_2_11, nif_2_12, nif_2_13,
+                        nif_2_14, nif_2_15, nif_2_16];
 version_opts(Ver) ->
     io:format(
         "This OTP release uses the unsupported Erlang NIF version ~p.\n\n"
@@ -304,41 +307,28 @@ api_list(Opts) -> [
 
     case proplists:get_bool(nif_2_13, Opts) of
         true -> [
-            %% Skip iovec API for now (perhaps forever).
-            %% Consider safer Rust iovec crates like https://crates.io/crates/iovec instead of this API.
-            %% If anybody really does need this API in Rust, please file a bug.
-            %% {"ErlNifIOQueue *",  "enif_ioq_create",     "ErlNifIOQueueOpts opts"},
-            %% {"void",             "enif_ioq_destroy",    "ErlNifIOQueue *q"},
-            %% {"int",              "enif_ioq_enq_binary", "ErlNifIOQueue *q, ErlNifBinary *bin, size_t skip"},
-            %% {"int",              "enif_ioq_enqv",       "ErlNifIOQueue *q, ErlNifIOVec *iov, size_t skip"},
-            %% {"size_t",           "enif_ioq_size",       "ErlNifIOQueue *q"},
-            %% {"int",              "enif_ioq_deq",        "ErlNifIOQueue *q, size_t count, size_t *size"},
-            %% {"SysIOVec*",        "enif_ioq_peek",       "ErlNifIOQueue *q, int *iovlen"},
-            %% {"int",              "enif_inspect_iovec",  "ErlNifEnv *env, size_t max_length, ERL_NIF_TERM iovec_term, ERL_NIF_TERM *tail, ErlNifIOVec **iovec"},
-            %% {"void",             "enif_free_iovec",     "ErlNifIOVec *iov"}
-            {"", "dummy_enif_ioq_create",     ""},
-            {"", "dummy_enif_ioq_destroy",    ""},
-            {"", "dummy_enif_ioq_enq_binary", ""},
-            {"", "dummy_enif_ioq_enqv",       ""},
-            {"", "dummy_enif_ioq_size",       ""},
-            {"", "dummy_enif_ioq_deq",        ""},
-            {"", "dummy_enif_ioq_peek",       ""},
-            {"", "dummy_enif_inspect_iovec",  ""},
-            {"", "dummy_enif_free_iovec",     ""}
+            {"*mut ErlNifIOQueue", "enif_ioq_create",     "opts: ErlNifIOQueueOpts"},
+            {"",                   "enif_ioq_destroy",    "q: *mut ErlNifIOQueue"},
+            {"c_int",              "enif_ioq_enq_binary", "q: *mut ErlNifIOQueue, bin: *mut ErlNifBinary, skip: size_t"},
+            {"c_int",              "enif_ioq_enqv",       "q: *mut ErlNifIOQueue, iov: *mut ErlNifIOVec, skip: size_t"},
+            {"size_t",             "enif_ioq_size",       "q: *mut ErlNifIOQueue"},
+            {"c_int",              "enif_ioq_deq",        "q: *mut ErlNifIOQueue, count: size_t, size: *mut size_t"},
+            {"*mut SysIOVec",      "enif_ioq_peek",       "q: *mut ErlNifIOQueue, iovlen: *mut c_int"},
+            {"c_int",              "enif_inspect_iovec",  "env: *mut ErlNifEnv, max_length: size_t, iovec_term: ERL_NIF_TERM, tail: *mut ERL_NIF_TERM, iovec: *mut *mut ErlNifIOVec"},
+            {"",                   "enif_free_iovec",     "iov: *mut ErlNifIOVec"}
         ];
         false -> []
     end ++
     case proplists:get_bool(nif_2_14, Opts) of
         true -> [
-            %% Skip iovec and synchronization APIs for now (perhaps forever).
-            %% Consider safer Rust iovec crates like https://crates.io/crates/iovec instead of this API.
+            {"c_int", "enif_ioq_peek_head", "env: *mut ErlNifEnv, q: *mut ErlNifIOQueue, size: *mut size_t, head: *mut ERL_NIF_TERM"},
+
+            %% Skip synchronization APIs for now (perhaps forever).
             %% If anybody really does need this API in Rust, please file a bug.
-            % {"int",  "enif_ioq_peek_head",        "ErlNifEnv *env, ErlNifIOQueue *q, size_t *size, ERL_NIF_TERM *head"},
             % {"char*, "enif_mutex_name",           "ErlNifMutex*"},
             % {"char*, "enif_cond_name",            "ErlNifCond*"},
             % {"char*, "enif_rwlock_name",          "ErlNifRWLock*"},
             % {"char*, "enif_thread_name",          "ErlNifTid"},
-            {"", "dummy_enif_ioq_peek_head", ""},
             {"", "dummy_enif_mutex_name",    ""},
             {"", "dummy_enif_cond_name",     ""},
             {"", "dummy_enif_rwlock_name",   ""},
@@ -357,13 +347,20 @@ api_list(Opts) -> [
     end ++
     case proplists:get_bool(nif_2_15, Opts) of
         true -> [
-            {"ErlNifTermType", "enif_term_type", "env: *mut ErlNifEnv, term: *const ERL_NIF_TERM"},
+            {"ErlNifTermType", "enif_term_type", "env: *mut ErlNifEnv, term: ERL_NIF_TERM"},
 
             {"c_int", "enif_is_pid_undefined", "pid: *const ErlNifPid"},
             {"", "enif_set_pid_undefined", "pid: *mut ErlNifPid"},
             {"ERL_NIF_TERM", "enif_make_monitor_term", "env: *mut ErlNifEnv, mon: *const ErlNifMonitor"}
         ];
         false -> []
+    end ++
+    case proplists:get_bool(nif_2_16, Opts) of
+        true -> [
+            {"*const ErlNifResourceType", "enif_init_resource_type", "env: *mut ErlNifEnv, name_str: *const c_uchar, init: *const ErlNifResourceTypeInit2_16, flags: ErlNifResourceFlags, tried: *mut ErlNifResourceFlags"},
+            {"c_int", "enif_dynamic_resource_call", "env: *mut ErlNifEnv, rt_module: ERL_NIF_TERM, rt_name: ERL_NIF_TERM, resource: ERL_NIF_TERM, call_data: *mut c_void"}
+        ];
+        false -> []
     end.
 
 
@@ -138,13 +138,32 @@ pub type ErlNifResourceDown = unsafe extern "C" fn(
     mon: *const ErlNifMonitor,
 ) -> ();
 
+/// See [ErlNifResourceDynCall](http://www.erlang.org/doc/man/erl_nif.html#ErlNifResourceDynCall) in the Erlang docs.
+#[allow(missing_copy_implementations)]
+pub type ErlNifResourceDynCall =
+    unsafe extern "C" fn(env: *mut ErlNifEnv, obj: *mut c_void, call_data: *mut c_void) -> ();
+
 /// See [ErlNifResourceTypeInit](http://www.erlang.org/doc/man/erl_nif.html#ErlNifResourceTypeInit) in the Erlang docs.
 #[derive(Debug, Copy, Clone)]
 #[repr(C)]
 pub struct ErlNifResourceTypeInit {
-    dtor: *const ErlNifResourceDtor,
-    stop: *const ErlNifResourceStop, // at ERL_NIF_SELECT_STOP event
-    down: *const ErlNifResourceDown, // enif_monitor_process
+    pub dtor: Option<ErlNifResourceDtor>,
+    pub stop: Option<ErlNifResourceStop>, // at ERL_NIF_SELECT_STOP event
+    pub down: Option<ErlNifResourceDown>, // enif_monitor_process
+}
+
+/// The [ErlNifResourceTypeInit](http://www.erlang.org/doc/man/erl_nif.html#ErlNifResourceTypeInit)
+/// of NIF 2.16 and later, read by `enif_init_resource_type`. `members` is the number of the
+/// callbacks that are set, counting from `dtor`.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct ErlNifResourceTypeInit2_16 {
+    pub dtor: Option<ErlNifResourceDtor>,
+    pub stop: Option<ErlNifResourceStop>, // at ERL_NIF_SELECT_STOP event
+    pub down: Option<ErlNifResourceDown>, // enif_monitor_process
+    pub members: c_int,
+    pub dyncall: Option<ErlNifResourceDynCall>, // enif_dynamic_resource_call
 }
 
 /// See [ErlNifSelectFlags](http://erlang.org/doc/man/erl_nif.html#ErlNifSelectFlags) in the Erlang docs.
@@ -153,6 +172,11 @@ pub type ErlNifSelectFlags = c_int;
 pub const ERL_NIF_SELECT_READ: ErlNifSelectFlags = 1 << 0;
 pub const ERL_NIF_SELECT_WRITE: ErlNifSelectFlags = 1 << 1;
 pub const ERL_NIF_SELECT_STOP: ErlNifSelectFlags = 1 << 2;
+// Return flags of `enif_select`.
+#[allow(clippy::identity_op)]
+pub const ERL_NIF_SELECT_STOP_CALLED: c_int = 1 << 0;
+pub const ERL_NIF_SELECT_STOP_SCHEDULED: c_int = 1 << 1;
+pub const ERL_NIF_SELECT_INVALID_EVENT: c_int = 1 << 2;
 pub const ERL_NIF_SELECT_FAILED: ErlNifSelectFlags = 1 << 3;
 pub const ERL_NIF_SELECT_READ_CANCELLED: ErlNifSelectFlags = 1 << 4;
 pub const ERL_NIF_SELECT_WRITE_CANCELLED: ErlNifSelectFlags = 1 << 5;
@@ -183,7 +207,7 @@ pub enum ErlNifCharEncoding {
 }
 
 /// See [ErlNifPid](http://www.erlang.org/doc/man/erl_nif.html#ErlNifPid) in the Erlang docs.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(C)]
 pub struct ErlNifPid {
     pid: ERL_NIF_TERM,
@@ -279,6 +303,55 @@ pub struct ErlNifPort {
 }
 // ref https://github.com/erlang/otp/blob/maint/erts/emulator/beam/erl_nif.h#L155
 
+/// See [SysIOVec](http://erlang.org/doc/man/erl_driver.html#SysIOVec) in the Erlang docs.
+#[cfg(unix)]
+#[derive(Copy, Clone, Debug)]
+#[repr(C)]
+pub struct SysIOVec {
+    pub iov_base: *mut c_char,
+    pub iov_len: size_t,
+}
+
+/// See [SysIOVec](http://erlang.org/doc/man/erl_driver.html#SysIOVec) in the Erlang docs.
+#[cfg(windows)]
+#[derive(Copy, Clone, Debug)]
+#[repr(C)]
+pub struct SysIOVec {
+    pub iov_len: c_ulong,
+    pub iov_base: *mut c_char,
+}
+
+/// See [ErlNifIOQueue](http://erlang.org/doc/man/erl_nif.html#ErlNifIOQueue) in the Erlang docs.
+#[allow(missing_copy_implementations)]
+#[repr(C)]
+pub struct ErlNifIOQueue {
+    dummy: c_int,
+}
+
+/// See [ErlNifIOQueueOpts](http://erlang.org/doc/man/erl_nif.html#ErlNifIOQueueOpts) in the Erlang docs.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub enum ErlNifIOQueueOpts {
+    ERL_NIF_IOQ_NORMAL = 1,
+}
+
+pub const ERL_NIF_IOVEC_SIZE: usize = 16;
+
+/// See [ErlNifIOVec](http://erlang.org/doc/man/erl_nif.html#ErlNifIOVec) in the Erlang docs.
+// ref https://github.com/erlang/otp/blob/maint/erts/emulator/beam/erl_nif.h#L325
+#[allow(missing_copy_implementations)]
+#[repr(C)]
+pub struct ErlNifIOVec {
+    pub iovcnt: c_int,
+    pub size: size_t,
+    pub iov: *mut SysIOVec,
+    // internal fields
+    ref_bins: *mut *mut c_void,
+    flags: c_int,
+    small_iov: [SysIOVec; ERL_NIF_IOVEC_SIZE],
+    small_ref_bin: [*mut c_void; ERL_NIF_IOVEC_SIZE],
+}
+
 /// See [ErlNifBinaryToTerm](http://erlang.org/doc/man/erl_nif.html#ErlNifBinaryToTerm) in the Erlang docs.
 pub type ErlNifBinaryToTerm = c_int;
 pub const ERL_NIF_BIN2TERM_SAFE: ErlNifBinaryToTerm = 0x2000_0000;
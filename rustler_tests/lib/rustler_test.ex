@@ -2,10 +2,15 @@ defmodule NifNotLoadedError do
   defexception message: "nif not loaded"
 end
 
+defmodule RustlerTest.LoadData do
+  def nif_data, do: %{pool_size: String.to_integer(System.get_env("RUSTLER_TEST_POOL_SIZE", "4"))}
+end
+
 defmodule RustlerTest do
   use Rustler,
     otp_app: :rustler_test,
-    crate: :rustler_test
+    crate: :rustler_test,
+    load_data_fun: {RustlerTest.LoadData, :nif_data}
 
   defp err do
     throw(NifNotLoadedError)
@@ -14,54 +19,174 @@ defmodule RustlerTest do
   def add_u32(_, _), do: err()
   def add_i32(_, _), do: err()
   def echo_u8(_), do: err()
+  def add_i128(_, _), do: err()
+  def echo_u128(_), do: err()
+  def mul_bigint(_, _), do: err()
   def option_inc(_), do: err()
   def result_to_int(_), do: err()
 
   def sum_list(_), do: err()
+  def sum_list_scratch(_), do: err()
   def make_list(), do: err()
+  def make_chunked_list(_, _), do: err()
+  def queue_reverse(_), do: err()
+  def array_double(_), do: err()
+  def keyword_list_echo(_), do: err()
+  def keyword_list_get(_, _), do: err()
 
   def term_debug(_), do: err()
   def term_eq(_, _), do: err()
   def term_cmp(_, _), do: err()
+  def term_type(_), do: err()
+  def term_decode_or_raise(_, _), do: err()
+  def term_tuple_size(_), do: err()
+  def term_tuple_get(_, _), do: err()
+  def term_match_tuple(_), do: err()
+  def etf_decode(_), do: err()
+  def etf_encode(_), do: err()
+  def term_to_binary(_), do: err()
+  def term_from_binary(_), do: err()
+  def term_to_canonical_binary(_), do: err()
+  def sorted_map_encode(_), do: err()
+  def sorted_map_entries(_), do: err()
+  def term_snapshot(_, _), do: err()
+  def term_assert_snapshot(_, _), do: err()
+  def rows_decode(_), do: err()
+  def term_byte_size_estimate(_), do: err()
+  def term_byte_size_exceeds(_, _), do: err()
+  def lazy_sum(_, _), do: err()
 
   def sum_map_values(_), do: err()
   def map_entries_sorted(_), do: err()
   def map_from_arrays(_keys, _values), do: err()
   def map_generic(_), do: err()
+  def btree_map_echo(_), do: err()
+  def index_map_echo(_), do: err()
+  def gb_tree_double(_), do: err()
+  def gb_tree_keys(_), do: err()
+  def gb_set_echo(_), do: err()
+
+  def ets_export(_, _, _), do: err()
+
+  def persistent_term_put(_, _), do: err()
+  def persistent_term_erase(_), do: err()
 
   def resource_make(), do: err()
   def resource_set_integer_field(_, _), do: err()
   def resource_get_integer_field(_), do: err()
   def resource_make_immutable(_), do: err()
   def resource_immutable_count(), do: err()
+  def resource_type_names(), do: err()
+  def resource_index_make(), do: err()
+  def resource_index_contains(_), do: err()
+  def ioq_new(), do: err()
+  def ioq_push(_, _), do: err()
+  def ioq_push_owned(_, _), do: err()
+  def ioq_read(_, _), do: err()
+  def ioq_segments(_), do: err()
+  def ioq_head(_), do: err()
+
+  def dyncall_adder(_), do: err()
+  def dyncall_add(_, _, _, _), do: err()
+  def dyncall_adder_watch(_, _), do: err()
+  def dyncall_adder_downs(_), do: err()
+
+  def subprocess_spawn(_, _), do: err()
+  def subprocess_os_pid(_), do: err()
+  def subprocess_write(_, _), do: err()
+  def subprocess_close_stdin(_), do: err()
+  def subprocess_kill(_), do: err()
 
   def make_shorter_subbinary(_), do: err()
+  def realize_subbinary(_, _, _), do: err()
   def parse_integer(_), do: err()
   def binary_new(), do: err()
+  def new_binary_new(_), do: err()
+  def binary_from_bytes(_, _, _), do: err()
   def owned_binary_new(), do: err()
+  def owned_binary_try_new(_), do: err()
+  def alloc_error(_), do: err()
   def unowned_to_owned(_), do: err()
   def realloc_shrink(), do: err()
   def realloc_grow(), do: err()
   def encode_string(), do: err()
   def decode_iolist(_), do: err()
+  def decode_into_owned(_), do: err()
+  def scratch_join(_, _), do: err()
+  def bitstring_bits(_), do: err()
+  def bitstring_slice(_, _, _), do: err()
+  def iovec_segments(_), do: err()
+  def iovec_concat(_), do: err()
+  def secure_compare(_, _), do: err()
+  def compress_binary(_, _), do: err()
+  def decompress_binary(_, _, _), do: err()
+  def owned_binaries(_), do: err()
+  def frame_iolist(_), do: err()
+  def binary_reader_parse(_), do: err()
+  def binary_reader_prefixed(_), do: err()
+  def binary_writer_frame(_, _, _, _, _), do: err()
 
   def atom_to_string(_), do: err()
   def atom_equals_ok(_), do: err()
   def binary_to_atom(_), do: err()
   def binary_to_existing_atom(_), do: err()
+  def atom_read_into(_, _), do: err()
 
   def threaded_fac(_), do: err()
   def threaded_sleep(_), do: err()
+  def pool_fac(_), do: err()
+  def pool_sleep(_, _, _, _), do: err()
+  def async_add(_, _), do: err()
+  def async_sleep(_), do: err()
+  def async_panic(), do: err()
 
   def send_all(_, _), do: err()
   def sublists(_), do: err()
+  def reply_chunks(_, _), do: err()
+  def intern_strs(_), do: err()
+  def deadline_remaining(_), do: err()
+  def deadline_steps(_, _), do: err()
+  def whereis_send(_, _, _), do: err()
+  def whereis_port_command(_, _), do: err()
+  def messenger_send(_, _, _), do: err()
+  def messenger_send_from_nif(_), do: err()
+  def trace_context_extract(_), do: err()
+  def trace_context_traceparent(_), do: err()
+  def trace_context_send(_, _, _), do: err()
 
   def tuple_echo(_), do: err()
   def record_echo(_), do: err()
   def map_echo(_), do: err()
+  def map_list_echo(_), do: err()
   def struct_echo(_), do: err()
+  def struct_list_echo(_), do: err()
+  def struct_decode_trace(_), do: err()
+  def exception_struct_echo(_), do: err()
+  def exception_struct_raise(_), do: err()
+  def keyed_map_echo(_), do: err()
+  def erlang_profile_map_echo(_), do: err()
+  def map_subset_decode(_), do: err()
+  def binary_keys_map_encode(), do: err()
+  def summary_map_summary(_), do: err()
+  def summarize_term(_), do: err()
   def unit_enum_echo(_), do: err()
+  def renamed_map_echo(_), do: err()
+  def renamed_struct_echo(_), do: err()
+  def renamed_unit_enum_echo(_), do: err()
+  def defaulted_map_echo(_), do: err()
+  def defaulted_struct_echo(_), do: err()
+  def skipped_map_decode(_), do: err()
+  def skipped_tuple_echo(_), do: err()
+  def generic_pair_swap(_), do: err()
+  def generic_wrapper_echo(_), do: err()
+  def generic_tuple_echo(_), do: err()
+  def generic_borrowed_len(_), do: err()
+  def borrowed_frame_split(_), do: err()
+  def borrowed_frame_map_split(_), do: err()
   def untagged_enum_echo(_), do: err()
+  def command_call(_), do: err()
+  def tagged_enum_echo(_), do: err()
+  def counter_call(_), do: err()
   def untagged_enum_with_truthy(_), do: err()
   def newtype_echo(_), do: err()
   def tuplestruct_echo(_), do: err()
@@ -70,9 +195,22 @@ defmodule RustlerTest do
   def reserved_keywords_type_echo(_), do: err()
 
   def dirty_io(), do: err()
+  def dirty_cpu_timed(_), do: err()
+  def nif_stats(_), do: err()
   def dirty_cpu(), do: err()
+  def parallel_squares(_, _), do: err()
+  def parallel_until(_, _), do: err()
+  def thread_hints(_, _), do: err()
+  def parallel_squares_hinted(_, _), do: err()
+  def yielding_sum(_, _), do: err()
+  def yielding_collatz(_), do: err()
+  def env_consume_timeslice(_), do: err()
+  def budgeted_sum(_, _), do: err()
 
   def sum_range(_), do: err()
+  def range_inclusive_echo(_, _), do: err()
+  def range_slice(_, _), do: err()
+  def range_to_inclusive(_), do: err()
 
   def bad_arg_error(), do: err()
   def atom_str_error(), do: err()
@@ -80,6 +218,94 @@ defmodule RustlerTest do
   def raise_term_with_string_error(), do: err()
   def raise_term_with_atom_error(), do: err()
   def term_with_tuple_error(), do: err()
+  def panic_mode_set(_), do: err()
+  def panic_with(_), do: err()
 
   def nif_attrs_can_rename(), do: err()
+  def deprecated_add(_, _), do: err()
+  def unit_ok_check(_), do: err()
+  def crash_guard_read(_), do: err()
+  def feature_enabled_add(_, _), do: err()
+  def feature_disabled_add(_, _), do: err()
+  def counter_double(_), do: err()
+  def nif_attrs_triple(_), do: err()
+  def nif_attrs_quadruple(_), do: err()
+  def registered_one(), do: err()
+  def registered_two(), do: err()
+
+  def pid_set_new(), do: err()
+  def pid_set_subscribe(_, _), do: err()
+  def pid_set_unsubscribe(_, _), do: err()
+  def pid_set_size(_), do: err()
+  def pid_set_broadcast(_, _), do: err()
+
+  def token_bucket_new(_, _), do: err()
+  def token_bucket_try_acquire(_, _), do: err()
+  def token_bucket_available(_), do: err()
+  def token_bucket_wait_time(_, _), do: err()
+
+  def overload_new(_, _), do: err()
+  def overload_report_load(_, _), do: err()
+  def overload_run(_, _), do: err()
+  def overload_stats(_), do: err()
+
+  def load_data_pool_size(), do: err()
+  def load_data_get_u32(_, _), do: err()
+  def load_data_check_nif_version(_, _), do: err()
+
+  def fuzz_decode_config(_), do: err()
+
+  def backend_build(_), do: err()
+
+  def port_serve(_, _), do: err()
+  def dist_call(_, _, _, _, _), do: err()
+
+  def map_set_echo(_), do: err()
+  def hash_set_echo(_), do: err()
+  def btree_set_to_list(_), do: err()
+  def range_to_list(_), do: err()
+  def uri_echo(_), do: err()
+  def regex_source(_), do: err()
+
+  def system_time_echo(_), do: err()
+  def system_time_unix_micros(_), do: err()
+  def system_time_after_epoch(_), do: err()
+  def duration_echo(_), do: err()
+  def utc_date_time_add_seconds(_, _), do: err()
+  def naive_date_time_echo(_), do: err()
+  def naive_date_echo(_), do: err()
+
+  def regex_compile(_), do: err()
+  def regex_is_match(_, _), do: err()
+  def regex_positions(_, _), do: err()
+  def regex_named_captures(_, _), do: err()
+
+  def text_nfc(_), do: err()
+  def text_nfd(_), do: err()
+  def text_graphemes(_), do: err()
+  def text_invalid_utf8_offset(_), do: err()
+  def text_levenshtein(_, _), do: err()
+  def text_similarity(_, _), do: err()
+
+  def serde_echo_order(_), do: err()
+  def serde_longest(_), do: err()
+  def serde_order_to_term(_), do: err()
+
+  def watcher_new(), do: err()
+  def watcher_watch(_, _), do: err()
+  def watcher_unwatch(_, _), do: err()
+  def watcher_watched(_), do: err()
+  def watcher_downs(_), do: err()
+
+  def select_pipe_new(), do: err()
+  def select_pipe_wait(_, _), do: err()
+  def select_pipe_write(_, _), do: err()
+  def select_pipe_read(_), do: err()
+  def select_pipe_stop(_), do: err()
+  def select_pipe_stopped(_), do: err()
+
+  def work_queue_new(_, _), do: err()
+  def work_queue_push(_, _, _), do: err()
+  def work_queue_stats(_), do: err()
+  def work_queue_close(_), do: err()
 end
@@ -0,0 +1,42 @@
+/// Emits the same `nif_version_*` cfgs as rustler, to gate the tests of version-dependent APIs.
+use std::env;
+use std::process::Command;
+
+// keep this in sync with rustler/build.rs
+const NIF_VERSION: &[&str] = &[
+    "2.7", "2.8", "2.9", "2.10", "2.11", "2.12", "2.13", "2.14", "2.15", "2.16",
+];
+const PRECOMPILED_NIF_VERSION: &str = "2.15";
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=RUSTLER_NIF_VERSION");
+
+    let version = env::var("RUSTLER_NIF_VERSION").unwrap_or_else(|_| {
+        get_version_from_erl().unwrap_or_else(|| PRECOMPILED_NIF_VERSION.to_string())
+    });
+
+    let index = NIF_VERSION
+        .iter()
+        .position(|&v| v == version)
+        .unwrap_or_else(|| panic!("Erlang version {} not handled", version));
+
+    for (i, version) in NIF_VERSION.iter().enumerate() {
+        let cfg = format!("nif_version_{}", version.replace(".", "_"));
+        println!("cargo:rustc-check-cfg=cfg({})", cfg);
+        if i <= index {
+            println!("cargo:rustc-cfg={}", cfg);
+        }
+    }
+}
+
+fn get_version_from_erl() -> Option<String> {
+    let args = vec![
+        "-noshell",
+        "-eval",
+        r#"io:format("~s~n", [erlang:system_info(nif_version)]), init:stop()."#,
+    ];
+
+    let version = Command::new("erl").args(&args).output().ok()?.stdout;
+
+    Some(String::from_utf8(version).ok()?.trim().into())
+}
@@ -1,17 +1,42 @@
+mod test_async;
 mod test_atom;
+mod test_backend;
 mod test_binary;
+mod test_broadcast;
 mod test_codegen;
 mod test_dirty;
+mod test_dist;
+#[cfg(nif_version_2_16)]
+mod test_dyncall;
+mod test_elixir_std;
 mod test_env;
 mod test_error;
+mod test_ets;
+mod test_fuzz;
+#[cfg(nif_version_2_13)]
+mod test_ioq;
 mod test_list;
+mod test_load_data;
 mod test_map;
+mod test_monitor;
 mod test_nif_attrs;
+mod test_overload;
+mod test_persistent_term;
+mod test_port;
 mod test_primitives;
 mod test_range;
+mod test_rate_limit;
+mod test_regex;
 mod test_resource;
+#[cfg(unix)]
+mod test_select;
+mod test_serde;
+mod test_subprocess;
 mod test_term;
+mod test_text;
 mod test_thread;
+mod test_time;
+mod test_work_queue;
 
 rustler::init!(
     "Elixir.RustlerTest",
@@ -19,45 +44,156 @@ rustler::init!(
         test_primitives::add_u32,
         test_primitives::add_i32,
         test_primitives::echo_u8,
+        test_primitives::add_i128,
+        test_primitives::echo_u128,
+        test_primitives::mul_bigint,
         test_primitives::option_inc,
         test_primitives::result_to_int,
         test_list::sum_list,
+        test_list::sum_list_scratch,
         test_list::make_list,
+        test_list::make_chunked_list,
+        test_list::queue_reverse,
+        test_list::array_double,
+        test_list::keyword_list_echo,
+        test_list::keyword_list_get,
         test_term::term_debug,
         test_term::term_eq,
         test_term::term_cmp,
+        test_term::term_type,
+        test_term::term_decode_or_raise,
+        test_term::term_tuple_size,
+        test_term::term_tuple_get,
+        test_term::term_match_tuple,
+        test_term::etf_decode,
+        test_term::etf_encode,
+        test_term::term_to_binary,
+        test_term::term_from_binary,
+        test_term::term_to_canonical_binary,
+        test_term::sorted_map_encode,
+        test_term::sorted_map_entries,
+        test_term::term_snapshot,
+        test_term::term_assert_snapshot,
+        test_term::rows_decode,
+        test_term::term_byte_size_estimate,
+        test_term::term_byte_size_exceeds,
+        test_term::lazy_sum,
         test_map::sum_map_values,
         test_map::map_entries_sorted,
         test_map::map_from_arrays,
         test_map::map_generic,
+        test_map::btree_map_echo,
+        test_map::index_map_echo,
+        test_map::gb_tree_double,
+        test_map::gb_tree_keys,
+        test_map::gb_set_echo,
+        test_ets::ets_export,
+        test_persistent_term::persistent_term_put,
+        test_persistent_term::persistent_term_erase,
         test_resource::resource_make,
         test_resource::resource_set_integer_field,
         test_resource::resource_get_integer_field,
         test_resource::resource_make_immutable,
         test_resource::resource_immutable_count,
+        test_resource::resource_index_make,
+        test_resource::resource_index_contains,
+        test_subprocess::subprocess_spawn,
+        test_subprocess::subprocess_os_pid,
+        test_subprocess::subprocess_write,
+        test_subprocess::subprocess_close_stdin,
+        test_subprocess::subprocess_kill,
         test_atom::atom_to_string,
         test_atom::atom_equals_ok,
         test_atom::binary_to_atom,
         test_atom::binary_to_existing_atom,
+        test_atom::atom_read_into,
         test_binary::make_shorter_subbinary,
+        test_binary::realize_subbinary,
         test_binary::parse_integer,
         test_binary::binary_new,
+        test_binary::new_binary_new,
+        test_binary::binary_from_bytes,
         test_binary::owned_binary_new,
+        test_binary::owned_binary_try_new,
+        test_binary::alloc_error,
         test_binary::unowned_to_owned,
         test_binary::realloc_shrink,
         test_binary::realloc_grow,
         test_binary::encode_string,
         test_binary::decode_iolist,
+        test_binary::decode_into_owned,
+        test_binary::scratch_join,
+        test_binary::bitstring_bits,
+        test_binary::bitstring_slice,
+        test_binary::secure_compare,
+        test_binary::compress_binary,
+        test_binary::decompress_binary,
+        test_binary::owned_binaries,
+        test_binary::frame_iolist,
+        test_binary::binary_reader_parse,
+        test_binary::binary_reader_prefixed,
+        test_binary::binary_writer_frame,
+        test_elixir_std::map_set_echo,
+        test_elixir_std::range_to_list,
+        test_elixir_std::uri_echo,
+        test_elixir_std::regex_source,
+        test_regex::regex_compile,
+        test_regex::regex_is_match,
+        test_regex::regex_positions,
+        test_regex::regex_named_captures,
         test_thread::threaded_fac,
         test_thread::threaded_sleep,
+        test_thread::pool_fac,
+        test_thread::pool_sleep,
+        test_async::async_add,
+        test_async::async_sleep,
+        test_async::async_panic,
         test_env::send_all,
         test_env::sublists,
+        test_env::reply_chunks,
+        test_env::intern_strs,
+        test_env::deadline_remaining,
+        test_env::deadline_steps,
+        test_env::whereis_send,
+        test_env::whereis_port_command,
+        test_env::messenger_send,
+        test_env::messenger_send_from_nif,
+        test_env::trace_context_extract,
+        test_env::trace_context_traceparent,
+        test_env::trace_context_send,
         test_codegen::tuple_echo,
         test_codegen::record_echo,
         test_codegen::map_echo,
+        test_codegen::map_list_echo,
         test_codegen::struct_echo,
+        test_codegen::struct_list_echo,
+        test_codegen::struct_decode_trace,
+        test_codegen::exception_struct_echo,
+        test_codegen::exception_struct_raise,
+        test_codegen::keyed_map_echo,
+        test_codegen::erlang_profile_map_echo,
+        test_codegen::map_subset_decode,
+        test_codegen::binary_keys_map_encode,
+        test_codegen::summary_map_summary,
+        test_codegen::summarize_term,
         test_codegen::unit_enum_echo,
+        test_codegen::renamed_map_echo,
+        test_codegen::renamed_struct_echo,
+        test_codegen::renamed_unit_enum_echo,
+        test_codegen::defaulted_map_echo,
+        test_codegen::defaulted_struct_echo,
+        test_codegen::skipped_map_decode,
+        test_codegen::skipped_tuple_echo,
+        test_codegen::generic_pair_swap,
+        test_codegen::generic_wrapper_echo,
+        test_codegen::generic_tuple_echo,
+        test_codegen::generic_borrowed_len,
+        test_codegen::borrowed_frame_split,
+        test_codegen::borrowed_frame_map_split,
         test_codegen::untagged_enum_echo,
+        test_codegen::command_call,
+        test_codegen::tagged_enum_echo,
+        test_codegen::counter_call,
         test_codegen::untagged_enum_with_truthy,
         test_codegen::newtype_echo,
         test_codegen::tuplestruct_echo,
@@ -65,20 +201,146 @@ rustler::init!(
         test_codegen::tuplestruct_record_echo,
         test_dirty::dirty_cpu,
         test_dirty::dirty_io,
+        test_dirty::dirty_cpu_timed,
+        test_dirty::nif_stats,
+        test_dirty::parallel_squares,
+        test_dirty::parallel_until,
+        test_dirty::thread_hints,
+        test_dirty::parallel_squares_hinted,
+        test_dirty::yielding_sum,
+        test_dirty::yielding_collatz,
+        test_dirty::env_consume_timeslice,
+        test_dirty::budgeted_sum,
         test_range::sum_range,
+        test_range::range_inclusive_echo,
+        test_range::range_slice,
+        test_range::range_to_inclusive,
         test_error::bad_arg_error,
         test_error::atom_str_error,
         test_error::raise_atom_error,
         test_error::raise_term_with_string_error,
         test_error::raise_term_with_atom_error,
         test_error::term_with_tuple_error,
+        test_error::panic_mode_set,
+        test_error::panic_with,
         test_nif_attrs::can_rename,
-        test_codegen::reserved_keywords::reserved_keywords_type_echo
+        test_nif_attrs::deprecated_add,
+        test_nif_attrs::unit_ok_check,
+        test_nif_attrs::crash_guard_read,
+        test_nif_attrs::feature_enabled_add,
+        test_nif_attrs::feature_disabled_add,
+        test_nif_attrs::counter_double,
+        test_nif_attrs::counter_triple,
+        test_nif_attrs::counter_quadruple,
+        test_codegen::reserved_keywords::reserved_keywords_type_echo,
+        test_broadcast::pid_set_new,
+        test_broadcast::pid_set_subscribe,
+        test_broadcast::pid_set_unsubscribe,
+        test_broadcast::pid_set_size,
+        test_broadcast::pid_set_broadcast,
+        test_rate_limit::token_bucket_new,
+        test_rate_limit::token_bucket_try_acquire,
+        test_rate_limit::token_bucket_available,
+        test_rate_limit::token_bucket_wait_time,
+        test_overload::overload_new,
+        test_overload::overload_report_load,
+        test_overload::overload_run,
+        test_overload::overload_stats,
+        test_load_data::load_data_pool_size,
+        test_load_data::load_data_get_u32,
+        test_load_data::load_data_check_nif_version,
+        test_fuzz::fuzz_decode_config,
+        test_backend::backend_build,
+        test_port::port_serve,
+        test_dist::dist_call,
+        test_elixir_std::hash_set_echo,
+        test_elixir_std::btree_set_to_list,
+        test_time::system_time_echo,
+        test_time::system_time_unix_micros,
+        test_time::system_time_after_epoch,
+        test_time::duration_echo,
+        test_time::utc_date_time_add_seconds,
+        test_time::naive_date_time_echo,
+        test_time::naive_date_echo,
+        test_text::text_nfc,
+        test_text::text_nfd,
+        test_text::text_graphemes,
+        test_text::text_invalid_utf8_offset,
+        test_text::text_levenshtein,
+        test_text::text_similarity,
+        test_monitor::watcher_new,
+        test_monitor::watcher_watch,
+        test_monitor::watcher_unwatch,
+        test_monitor::watcher_watched,
+        test_monitor::watcher_downs,
+        test_serde::serde_echo_order,
+        test_serde::serde_longest,
+        test_serde::serde_order_to_term,
+        test_work_queue::work_queue_new,
+        test_work_queue::work_queue_push,
+        test_work_queue::work_queue_stats,
+        test_work_queue::work_queue_close
     ],
-    load = load
+    load = load,
+    registry = registry,
+    min_nif_version = (2, 12),
+    features = ["default-feature", "optional-feature"]
 );
 
-fn load(env: rustler::Env, _: rustler::Term) -> bool {
+fn load(env: rustler::Env, config: test_load_data::LoadConfig) -> bool {
     test_resource::on_load(env);
-    true
+    #[cfg(nif_version_2_13)]
+    test_ioq::on_load(env);
+    #[cfg(nif_version_2_16)]
+    test_dyncall::on_load(env);
+    #[cfg(unix)]
+    test_select::on_load(env);
+    test_monitor::on_load(env);
+    test_work_queue::on_load(env);
+    test_load_data::on_load(config);
+    rustler::subprocess::load(env)
+        && rustler::chunked::load(env)
+        && rustler::yielding::load(env)
+        && rustler::broadcast::load(env)
+        && rustler::rate_limit::load(env)
+        && rustler::overload::load(env)
+        && rustler::regex::load(env)
+}
+
+/// Adds the NIFs that need a newer NIF API than `min_nif_version`, or Unix, to
+/// `test_nif_attrs::registry`.
+fn registry() -> rustler::NifRegistration {
+    let registration = test_nif_attrs::registry();
+
+    #[cfg(nif_version_2_13)]
+    let registration = registration
+        .add_nif::<test_resource::resource_type_names>()
+        .add_nif::<test_ioq::ioq_new>()
+        .add_nif::<test_ioq::ioq_push>()
+        .add_nif::<test_ioq::ioq_push_owned>()
+        .add_nif::<test_ioq::ioq_read>()
+        .add_nif::<test_ioq::ioq_segments>()
+        .add_nif::<test_binary::iovec_segments>()
+        .add_nif::<test_binary::iovec_concat>();
+
+    #[cfg(nif_version_2_14)]
+    let registration = registration.add_nif::<test_ioq::ioq_head>();
+
+    #[cfg(nif_version_2_16)]
+    let registration = registration
+        .add_nif::<test_dyncall::dyncall_adder>()
+        .add_nif::<test_dyncall::dyncall_add>()
+        .add_nif::<test_dyncall::dyncall_adder_watch>()
+        .add_nif::<test_dyncall::dyncall_adder_downs>();
+
+    #[cfg(unix)]
+    let registration = registration
+        .add_nif::<test_select::select_pipe_new>()
+        .add_nif::<test_select::select_pipe_wait>()
+        .add_nif::<test_select::select_pipe_write>()
+        .add_nif::<test_select::select_pipe_read>()
+        .add_nif::<test_select::select_pipe_stop>()
+        .add_nif::<test_select::select_pipe_stopped>();
+
+    registration
 }
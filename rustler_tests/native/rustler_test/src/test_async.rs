@@ -0,0 +1,55 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A future completing after a delay, woken by a thread sleeping until then.
+struct Sleep {
+    until: Instant,
+    waking: bool,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let now = Instant::now();
+        if now >= self.until {
+            return Poll::Ready(());
+        }
+        if !self.waking {
+            self.waking = true;
+            let waker = cx.waker().clone();
+            let delay = self.until - now;
+            thread::spawn(move || {
+                thread::sleep(delay);
+                waker.wake();
+            });
+        }
+        Poll::Pending
+    }
+}
+
+fn sleep(duration: Duration) -> Sleep {
+    Sleep {
+        until: Instant::now() + duration,
+        waking: false,
+    }
+}
+
+#[rustler::nif]
+pub async fn async_add(a: i64, b: i64) -> i64 {
+    a + b
+}
+
+#[rustler::nif]
+pub async fn async_sleep(ms: u64) -> Result<u64, String> {
+    sleep(Duration::from_millis(ms)).await;
+    Ok(ms)
+}
+
+#[rustler::nif]
+pub async fn async_panic() -> i64 {
+    panic!("async NIF panicked")
+}
@@ -1,4 +1,4 @@
-use rustler::{Atom, Binary, Env, NifResult, Term};
+use rustler::{Atom, Binary, Env, NifResult, OwnedBinary, Term};
 
 mod atoms {
     rustler::atoms! { ok }
@@ -25,3 +25,12 @@ pub fn binary_to_existing_atom(env: Env, binary: Binary) -> NifResult<Option<Ato
     let atom = Atom::try_from_bytes(env, binary.as_slice())?;
     Ok(atom)
 }
+
+#[rustler::nif]
+pub fn atom_read_into(env: Env, atom: Atom, capacity: usize) -> (usize, Binary) {
+    let mut buf = [0u8; 256];
+    let len = atom.read_into(&mut buf[..capacity.min(256)]);
+    let mut binary = OwnedBinary::new(len).unwrap();
+    binary.as_mut_slice().copy_from_slice(&buf[..len]);
+    (atom.text_len(), binary.release(env))
+}
@@ -0,0 +1,10 @@
+use rustler::backend::BuildTerm;
+use rustler::{Env, Term};
+use std::collections::HashMap;
+
+#[rustler::nif]
+pub fn backend_build(env: Env, count: u32) -> Term {
+    let mut sizes = HashMap::new();
+    sizes.insert("count".to_string(), count);
+    ("ok", vec![1i64, -2], Some(1.5), None::<u8>, sizes).build(env)
+}
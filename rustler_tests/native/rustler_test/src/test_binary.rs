@@ -1,7 +1,12 @@
 use std::io::Write;
 
-use rustler::types::binary::{Binary, OwnedBinary};
-use rustler::{Env, Error, NifResult, Term};
+use rustler::compress::{Codec, Gzip, Zstd};
+use rustler::profile::BinaryAllocation;
+use rustler::types::binary::{AllocError, Binary, NewBinary, OwnedBinary};
+#[cfg(nif_version_2_13)]
+use rustler::types::IoVec;
+use rustler::types::{BinaryReader, BinaryWriter, Bitstring, Iolist};
+use rustler::{Env, Error, NifResult, NifUnitEnum, Term};
 
 #[rustler::nif]
 pub fn make_shorter_subbinary(binary: Binary) -> NifResult<Binary> {
@@ -9,6 +14,18 @@ pub fn make_shorter_subbinary(binary: Binary) -> NifResult<Binary> {
     binary.make_subbinary(1, length - 2)
 }
 
+#[rustler::nif]
+pub fn realize_subbinary(
+    binary: Binary,
+    offset: usize,
+    length: usize,
+) -> NifResult<(Binary, usize, usize)> {
+    let sub = binary.make_subbinary(offset, length)?;
+    let parent_size = sub.parent().map_or(0, |parent| parent.len());
+    let realized = sub.realize().ok_or(Error::BadArg)?;
+    Ok((realized, sub.offset(), parent_size))
+}
+
 #[rustler::nif]
 pub fn parse_integer(string: &str) -> NifResult<i64> {
     std::str::FromStr::from_str(string).map_err(|_| Error::BadArg)
@@ -21,6 +38,26 @@ pub fn binary_new(env: Env) -> Binary {
     binary.release(env)
 }
 
+#[rustler::nif]
+pub fn new_binary_new(env: Env, size: usize) -> Binary {
+    let mut binary = NewBinary::new(env, size);
+    for (i, byte) in binary.as_mut_slice().iter_mut().enumerate() {
+        *byte = (i % 256) as u8;
+    }
+    binary.into()
+}
+
+#[rustler::nif]
+pub fn binary_from_bytes(env: Env, size: usize, force_refc: bool) -> Binary {
+    let bytes: Vec<u8> = (0..size).map(|i| (i % 256) as u8).collect();
+    let allocation = if force_refc {
+        BinaryAllocation::Refc
+    } else {
+        BinaryAllocation::Adaptive
+    };
+    Binary::from_bytes_with(env, &bytes, allocation)
+}
+
 #[rustler::nif]
 pub fn owned_binary_new() -> OwnedBinary {
     let mut binary = OwnedBinary::new(4).unwrap();
@@ -28,6 +65,18 @@ pub fn owned_binary_new() -> OwnedBinary {
     binary
 }
 
+#[rustler::nif]
+pub fn owned_binary_try_new(size: usize) -> NifResult<OwnedBinary> {
+    let mut binary = OwnedBinary::try_new(size)?;
+    binary.as_mut_slice().iter_mut().for_each(|byte| *byte = 0);
+    Ok(binary)
+}
+
+#[rustler::nif]
+pub fn alloc_error(size: usize) -> NifResult<()> {
+    Err(AllocError { size }.into())
+}
+
 #[rustler::nif]
 pub fn unowned_to_owned<'a>(env: Env<'a>, binary: Binary<'a>) -> NifResult<Binary<'a>> {
     let mut copied = binary.to_owned().unwrap();
@@ -66,3 +115,160 @@ pub fn encode_string() -> (String, &'static str) {
 pub fn decode_iolist(binary: Term) -> NifResult<Binary> {
     binary.decode_as_binary()
 }
+
+#[rustler::nif]
+pub fn decode_into_owned<'a>(env: Env<'a>, term: Term<'a>) -> NifResult<Binary<'a>> {
+    let mut owned = OwnedBinary::new(4).unwrap();
+    term.decode_into(&mut owned)?;
+    Ok(owned.release(env))
+}
+
+#[rustler::nif]
+pub fn scratch_join<'a>(env: Env<'a>, parts: Vec<Binary>, separator: Binary) -> Binary<'a> {
+    rustler::scratch::with_buffer(|buf| {
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                buf.extend_from_slice(&separator);
+            }
+            buf.extend_from_slice(part);
+        }
+        let mut binary = OwnedBinary::new(buf.len()).unwrap();
+        binary.as_mut_slice().copy_from_slice(buf);
+        binary.release(env)
+    })
+}
+
+#[rustler::nif]
+pub fn bitstring_bits(bits: Bitstring) -> Vec<bool> {
+    (0..bits.bit_len()).map(|i| bits.get(i).unwrap()).collect()
+}
+
+#[rustler::nif]
+pub fn bitstring_slice(bits: Bitstring, start: usize, len: usize) -> NifResult<Bitstring> {
+    match bits.slice(start, len) {
+        Some(slice) => Ok(slice.into_owned()),
+        None => Err(Error::BadArg),
+    }
+}
+
+#[cfg(nif_version_2_13)]
+#[rustler::nif]
+pub fn iovec_segments(iovec: IoVec) -> (usize, Vec<usize>) {
+    let lengths = iovec.slices().iter().map(|segment| segment.len()).collect();
+    (iovec.total_size(), lengths)
+}
+
+#[cfg(nif_version_2_13)]
+#[rustler::nif]
+pub fn iovec_concat<'a>(env: Env<'a>, iovec: IoVec) -> Binary<'a> {
+    let mut buf = Vec::new();
+    iovec.write_to(&mut buf).unwrap();
+    let mut binary = OwnedBinary::new(buf.len()).unwrap();
+    binary.as_mut_slice().copy_from_slice(&buf);
+    binary.release(env)
+}
+
+#[rustler::nif]
+pub fn secure_compare(a: Binary, b: Binary) -> bool {
+    rustler::crypto::secure_compare(a, b)
+}
+
+#[derive(NifUnitEnum)]
+pub enum CompressFormat {
+    Gzip,
+    Zstd,
+}
+
+fn codec(format: CompressFormat) -> Box<dyn Codec> {
+    match format {
+        CompressFormat::Gzip => Box::new(Gzip { level: 9 }),
+        CompressFormat::Zstd => Box::new(Zstd::default()),
+    }
+}
+
+#[rustler::nif]
+pub fn compress_binary<'a>(
+    env: Env<'a>,
+    format: CompressFormat,
+    data: Binary<'a>,
+) -> NifResult<Binary<'a>> {
+    let compressed = rustler::compress::compress(&*codec(format), &data)?;
+    Ok(compressed.release(env))
+}
+
+#[rustler::nif]
+pub fn decompress_binary<'a>(
+    env: Env<'a>,
+    format: CompressFormat,
+    data: Binary<'a>,
+    max_size: usize,
+) -> NifResult<Binary<'a>> {
+    let decompressed = rustler::compress::decompress(&*codec(format), &data, max_size)?;
+    Ok(decompressed.release(env))
+}
+
+#[rustler::nif]
+pub fn owned_binaries(chunks: Vec<Binary>) -> Vec<OwnedBinary> {
+    chunks
+        .iter()
+        .map(|chunk| {
+            let mut owned = OwnedBinary::new(chunk.len()).unwrap();
+            owned.as_mut_slice().copy_from_slice(chunk);
+            owned
+        })
+        .collect()
+}
+
+#[rustler::nif]
+pub fn frame_iolist<'a>(env: Env<'a>, payloads: Vec<Binary<'a>>) -> (Iolist<'a>, usize) {
+    let mut frames = Iolist::with_capacity(payloads.len());
+    for payload in payloads {
+        let mut frame = Iolist::new();
+        frame.push_bytes(env, &(payload.len() as u16).to_be_bytes());
+        frame.push_binary(payload);
+        frame.push_byte(b'\n');
+        frames.push_iolist(frame);
+    }
+    let size = frames.size();
+    (frames, size)
+}
+
+#[rustler::nif]
+pub fn binary_reader_parse(data: Binary) -> NifResult<(u16, u32, u64, i64, Binary, Binary, usize)> {
+    let mut reader = BinaryReader::new(data);
+    let kind = reader.read_u16_be()?;
+    let flags = reader.read_u32_le()?;
+    let id = reader.read_varint()?;
+    let delta = reader.read_zigzag()?;
+    let payload = reader.read_varint_prefixed()?;
+    let position = reader.position();
+    let rest = reader.read_rest()?;
+    Ok((kind, flags, id, delta, payload, rest, position))
+}
+
+/// Reads a `u16_be`-prefixed field, and returns the position of the reader after a failure.
+#[rustler::nif]
+pub fn binary_reader_prefixed(data: Binary) -> Result<Binary, usize> {
+    let mut reader = BinaryReader::new(data);
+    reader.read_u16_be_prefixed().map_err(|_| reader.position())
+}
+
+/// Writes the frames read by `binary_reader_parse`, growing from a single byte.
+#[rustler::nif]
+pub fn binary_writer_frame<'a>(
+    env: Env<'a>,
+    kind: u16,
+    flags: u32,
+    id: u64,
+    delta: i64,
+    payload: Binary,
+) -> NifResult<Binary<'a>> {
+    let mut writer = BinaryWriter::with_capacity(1)?;
+    writer.write_u16_be(kind)?;
+    writer.write_u32_le(flags)?;
+    writer.write_varint(id)?;
+    writer.write_zigzag(delta)?;
+    writer.write_varint_prefixed(&payload)?;
+    writer.write_f64_be(1.5)?;
+    Ok(writer.finish(env)?)
+}
@@ -0,0 +1,27 @@
+use rustler::broadcast::PidSet;
+use rustler::{Env, LocalPid, Term};
+
+#[rustler::nif]
+pub fn pid_set_new() -> PidSet {
+    PidSet::new()
+}
+
+#[rustler::nif]
+pub fn pid_set_subscribe(env: Env, set: PidSet, pid: LocalPid) -> bool {
+    set.subscribe(env, &pid)
+}
+
+#[rustler::nif]
+pub fn pid_set_unsubscribe(env: Env, set: PidSet, pid: LocalPid) -> bool {
+    set.unsubscribe(env, &pid)
+}
+
+#[rustler::nif]
+pub fn pid_set_size(set: PidSet) -> usize {
+    set.len()
+}
+
+#[rustler::nif]
+pub fn pid_set_broadcast<'a>(env: Env<'a>, set: PidSet, message: Term<'a>) -> usize {
+    set.broadcast(env, message)
+}
@@ -1,5 +1,13 @@
+use rustler::profile::{EncodingProfile, KeyStyle};
+use rustler::summary::SummaryLimits;
+use rustler::types::keyed::KeyedVec;
 use rustler::types::truthy::Truthy;
-use rustler::{NifMap, NifRecord, NifStruct, NifTuple, NifUnitEnum, NifUntaggedEnum};
+use rustler::types::MapSubset;
+use rustler::{Binary, Encoder, Env, NifResult, Term};
+use rustler::{
+    NifMap, NifRecord, NifStruct, NifTaggedEnum, NifTuple, NifUnitEnum, NifUntaggedEnum,
+};
+use std::marker::PhantomData;
 
 #[derive(NifTuple)]
 pub struct AddTuple {
@@ -37,6 +45,24 @@ pub fn map_echo(map: AddMap) -> AddMap {
     map
 }
 
+#[rustler::nif]
+pub fn map_list_echo(maps: Vec<AddMap>) -> Vec<AddMap> {
+    maps
+}
+
+#[derive(NifMap)]
+pub struct SubsetMap {
+    lhs: i32,
+    nickname: Option<String>,
+}
+
+#[rustler::nif]
+pub fn map_subset_decode(subset: MapSubset<SubsetMap>) -> (i32, Option<String>, usize) {
+    let found = subset.found_keys().len();
+    let subset = subset.into_inner();
+    (subset.lhs, subset.nickname, found)
+}
+
 #[derive(Debug, NifStruct)]
 #[must_use] // Added to test Issue #152
 #[module = "AddStruct"]
@@ -50,6 +76,93 @@ pub fn struct_echo(add_struct: AddStruct) -> AddStruct {
     add_struct
 }
 
+#[rustler::nif]
+pub fn struct_list_echo(add_structs: Vec<AddStruct>) -> Vec<AddStruct> {
+    add_structs
+}
+
+#[rustler::nif]
+pub fn struct_decode_trace(term: Term) -> Vec<String> {
+    let _ = term.decode::<AddStruct>();
+    rustler::decode_trace::take_trace()
+        .iter()
+        .map(|failure| failure.to_string())
+        .collect()
+}
+
+#[derive(Debug, NifStruct)]
+#[module = "RustlerTest.ParseError"]
+#[rustler(exception)]
+pub struct ParseError {
+    message: String,
+    line: u32,
+}
+
+#[rustler::nif]
+pub fn exception_struct_echo(error: ParseError) -> ParseError {
+    error
+}
+
+#[rustler::nif]
+pub fn exception_struct_raise(line: u32) -> NifResult<u32> {
+    if line == 0 {
+        return Ok(0);
+    }
+    Err(ParseError {
+        message: "unexpected token".to_string(),
+        line,
+    }
+    .into())
+}
+
+#[derive(NifMap)]
+#[rustler(key = "id")]
+pub struct KeyedMap {
+    id: i32,
+    name: String,
+}
+
+#[rustler::nif]
+pub fn keyed_map_echo(keyed: KeyedVec<KeyedMap>) -> KeyedVec<KeyedMap> {
+    keyed
+}
+
+#[derive(NifMap)]
+#[rustler(profile = "erlang")]
+pub struct ErlangProfileMap {
+    name: String,
+    nickname: Option<String>,
+}
+
+#[rustler::nif]
+pub fn erlang_profile_map_echo(map: ErlangProfileMap) -> ErlangProfileMap {
+    map
+}
+
+#[derive(NifMap)]
+#[rustler(summary)]
+pub struct SummaryMap {
+    name: String,
+    values: Vec<u32>,
+}
+
+#[rustler::nif]
+pub fn summary_map_summary(env: Env, map: SummaryMap) -> Term {
+    map.summary_term(env)
+}
+
+#[rustler::nif]
+pub fn summarize_term(term: Term) -> Term {
+    rustler::summary::summarize(term, &SummaryLimits::default())
+}
+
+#[rustler::nif]
+pub fn binary_keys_map_encode(env: Env) -> Term {
+    let profile = EncodingProfile::ELIXIR.with_keys(KeyStyle::Binary);
+    let map = AddMap { lhs: 1, rhs: 2 };
+    profile.encode(env, &map)
+}
+
 #[derive(NifUnitEnum)]
 pub enum UnitEnum {
     FooBar,
@@ -61,6 +174,190 @@ pub fn unit_enum_echo(unit_enum: UnitEnum) -> UnitEnum {
     unit_enum
 }
 
+#[derive(NifMap)]
+#[rustler(rename_all = "camelCase")]
+pub struct RenamedMap {
+    user_id: i32,
+    display_name: String,
+    #[rustler(rename = "e-mail")]
+    email: String,
+}
+
+#[rustler::nif]
+pub fn renamed_map_echo(map: RenamedMap) -> RenamedMap {
+    map
+}
+
+#[derive(NifStruct)]
+#[module = "RenamedStruct"]
+pub struct RenamedStruct {
+    #[rustler(rename = "type")]
+    kind: String,
+    r#ref: i32,
+}
+
+#[rustler::nif]
+pub fn renamed_struct_echo(renamed: RenamedStruct) -> RenamedStruct {
+    renamed
+}
+
+#[derive(NifUnitEnum)]
+#[rustler(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RenamedUnitEnum {
+    FooBar,
+    #[rustler(rename = "baz")]
+    Baz,
+}
+
+#[rustler::nif]
+pub fn renamed_unit_enum_echo(unit_enum: RenamedUnitEnum) -> RenamedUnitEnum {
+    unit_enum
+}
+
+#[derive(NifMap)]
+pub struct DefaultedMap {
+    name: String,
+    #[rustler(default)]
+    tags: Vec<String>,
+    #[rustler(default = "DefaultedMap::DEFAULT_RETRIES")]
+    retries: u32,
+}
+
+impl DefaultedMap {
+    const DEFAULT_RETRIES: u32 = 3;
+}
+
+#[rustler::nif]
+pub fn defaulted_map_echo(map: DefaultedMap) -> DefaultedMap {
+    map
+}
+
+#[derive(NifStruct)]
+#[module = "DefaultedStruct"]
+pub struct DefaultedStruct {
+    name: String,
+    #[rustler(default = "1")]
+    version: i32,
+}
+
+#[rustler::nif]
+pub fn defaulted_struct_echo(defaulted: DefaultedStruct) -> DefaultedStruct {
+    defaulted
+}
+
+#[derive(NifMap)]
+pub struct SkippedMap {
+    name: String,
+    #[rustler(skip)]
+    cache: Option<String>,
+    #[rustler(skip, default = "1")]
+    generation: u32,
+    #[rustler(skip)]
+    marker: PhantomData<u8>,
+}
+
+#[rustler::nif]
+pub fn skipped_map_decode(map: SkippedMap) -> (SkippedMap, bool, u32) {
+    let decoded = (map.cache.is_none(), map.generation);
+    let map = SkippedMap {
+        cache: Some(map.name.clone()),
+        generation: 2,
+        ..map
+    };
+    (map, decoded.0, decoded.1)
+}
+
+#[derive(NifTuple)]
+pub struct SkippedTuple {
+    lhs: i32,
+    #[rustler(skip)]
+    scratch: Vec<i32>,
+    rhs: i32,
+}
+
+#[rustler::nif]
+pub fn skipped_tuple_echo(tuple: SkippedTuple) -> SkippedTuple {
+    assert!(tuple.scratch.is_empty());
+    tuple
+}
+
+#[derive(NifMap)]
+pub struct GenericPair<T> {
+    first: T,
+    second: T,
+}
+
+#[rustler::nif]
+pub fn generic_pair_swap(pair: GenericPair<String>) -> GenericPair<String> {
+    GenericPair {
+        first: pair.second,
+        second: pair.first,
+    }
+}
+
+#[derive(NifStruct)]
+#[module = "GenericWrapper"]
+pub struct GenericWrapper<T: Clone, U>
+where
+    U: Default,
+{
+    value: T,
+    #[rustler(skip)]
+    extra: U,
+}
+
+#[rustler::nif]
+pub fn generic_wrapper_echo(wrapper: GenericWrapper<Vec<i64>, u8>) -> GenericWrapper<Vec<i64>, u8> {
+    assert_eq!(wrapper.extra, 0);
+    wrapper
+}
+
+#[derive(NifTuple)]
+pub struct GenericTuple<A, B>(A, B);
+
+#[rustler::nif]
+pub fn generic_tuple_echo(
+    tuple: GenericTuple<i64, Option<String>>,
+) -> GenericTuple<i64, Option<String>> {
+    tuple
+}
+
+#[derive(NifMap)]
+#[rustler(decode)]
+pub struct GenericBorrowed<'a, T> {
+    name: &'a str,
+    values: Vec<T>,
+}
+
+#[rustler::nif]
+pub fn generic_borrowed_len(borrowed: GenericBorrowed<f64>) -> (String, usize) {
+    (borrowed.name.to_string(), borrowed.values.len())
+}
+
+#[derive(NifTuple)]
+#[rustler(decode)]
+pub struct BorrowedFrame<'a> {
+    header: Binary<'a>,
+    payload: &'a [u8],
+}
+
+#[rustler::nif]
+pub fn borrowed_frame_split(frame: BorrowedFrame) -> (Binary, usize) {
+    (frame.header, frame.payload.len())
+}
+
+#[derive(NifMap)]
+#[rustler(decode)]
+pub struct BorrowedFrameMap<'a> {
+    header: &'a [u8],
+    payload: Binary<'a>,
+}
+
+#[rustler::nif]
+pub fn borrowed_frame_map_split(frame: BorrowedFrameMap) -> (usize, Binary) {
+    (frame.header.len(), frame.payload)
+}
+
 #[derive(NifUntaggedEnum)]
 pub enum UntaggedEnum {
     Foo(u32),
@@ -74,6 +371,75 @@ pub fn untagged_enum_echo(untagged_enum: UntaggedEnum) -> UntaggedEnum {
     untagged_enum
 }
 
+#[derive(NifUntaggedEnum)]
+#[rustler::dispatch(name = "command_call", handler = "CommandHandlers")]
+pub enum Command {
+    Add(AddTuple),
+    Echo(String),
+}
+
+pub struct CommandHandlers;
+
+impl CommandHandlers {
+    fn add(_env: Env, tuple: AddTuple) -> i32 {
+        tuple.lhs + tuple.rhs
+    }
+
+    fn echo(env: Env, text: String) -> NifResult<Term> {
+        if text.is_empty() {
+            return Err(rustler::Error::Atom("empty"));
+        }
+        Ok(text.encode(env))
+    }
+}
+
+#[derive(NifTaggedEnum)]
+pub enum TaggedEnum {
+    Empty,
+    Circle(f64),
+    Pair(i32, String),
+    Rect {
+        width: f64,
+        height: f64,
+        #[rustler(rename = "label", default)]
+        name: String,
+    },
+    #[rustler(rename = "none")]
+    Nothing,
+}
+
+#[rustler::nif]
+pub fn tagged_enum_echo(tagged_enum: TaggedEnum) -> TaggedEnum {
+    tagged_enum
+}
+
+#[derive(NifTaggedEnum)]
+#[rustler::dispatch(name = "counter_call")]
+pub enum CounterCommand {
+    Reset,
+    Add(i64, i64),
+    Scale { value: i64, factor: i64 },
+    Sub { value: i64, by: i64 },
+}
+
+impl CounterCommand {
+    fn reset(_env: Env) -> i64 {
+        0
+    }
+
+    fn add(_env: Env, lhs: i64, rhs: i64) -> i64 {
+        lhs + rhs
+    }
+
+    fn scale(_env: Env, value: i64, factor: i64) -> i64 {
+        value * factor
+    }
+
+    fn sub(_env: Env, value: i64, by: i64) -> i64 {
+        value - by
+    }
+}
+
 #[derive(NifUntaggedEnum)]
 pub enum UntaggedEnumWithTruthy {
     Baz(AddStruct),
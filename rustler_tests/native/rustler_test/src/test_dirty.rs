@@ -1,5 +1,8 @@
-use rustler::Atom;
-use std::time::Duration;
+use rustler::affinity::{SchedulerBindings, ThreadHints};
+use rustler::parallel::ParallelMap;
+use rustler::yielding::{Resumable, Step, Yielding};
+use rustler::{Atom, Env, Error, NifResult, NifStats};
+use std::time::{Duration, Instant};
 
 mod atoms {
     rustler::atoms! { ok }
@@ -22,3 +25,147 @@ pub fn dirty_io() -> Atom {
 
     atoms::ok()
 }
+
+#[rustler::nif(schedule = "DirtyCpu", cpu_time)]
+pub fn dirty_cpu_timed(iterations: u64) -> u64 {
+    (0..iterations).fold(0u64, |acc, i| acc.wrapping_mul(31).wrapping_add(i))
+}
+
+#[rustler::nif]
+pub fn nif_stats(name: String) -> Option<NifStats> {
+    rustler::stats::get(&name)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn parallel_squares(items: Vec<i64>, threads: usize) -> Vec<i64> {
+    if threads == 0 {
+        return rustler::parallel::map_dirty(items, |n| n * n);
+    }
+    ParallelMap::new()
+        .threads(threads)
+        .run(items, |n| n * n)
+        .unwrap()
+}
+
+// Maps shards of one item, stopping once `limit` items are mapped.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn parallel_until(items: Vec<i64>, limit: usize) -> NifResult<Vec<i64>> {
+    let mut reports = 0;
+    ParallelMap::new()
+        .threads(2)
+        .shard_size(1)
+        .progress(|done, _total| {
+            reports += 1;
+            if done >= limit {
+                Err(Error::Term(Box::new(reports)))
+            } else {
+                Ok(())
+            }
+        })
+        .run(items, |n| {
+            if n < 0 {
+                panic!("negative item");
+            }
+            n
+        })
+}
+
+// Returns the thread count and the pinned cores of hints avoiding the schedulers.
+#[rustler::nif]
+pub fn thread_hints(
+    bindings: SchedulerBindings,
+    cores: Option<Vec<usize>>,
+) -> (usize, Option<Vec<usize>>) {
+    let mut hints = ThreadHints::new();
+    if let Some(cores) = cores {
+        hints = hints.cores(cores);
+    }
+    let hints = hints.avoid_schedulers(&bindings);
+    (
+        hints.thread_count(),
+        hints.pinned_cores().map(<[usize]>::to_vec),
+    )
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn parallel_squares_hinted(items: Vec<i64>, bindings: SchedulerBindings) -> Vec<i64> {
+    ParallelMap::new()
+        .hints(
+            ThreadHints::new()
+                .max_threads(2)
+                .avoid_schedulers(&bindings),
+        )
+        .run(items, |n| n * n)
+        .unwrap()
+}
+
+/// Sums the integers below `n`, `chunk` at a time, and returns the sum and the number of steps.
+#[rustler::nif]
+pub fn yielding_sum(n: u64, chunk: u64) -> Yielding<impl Resumable> {
+    let mut next = 0u64;
+    let mut sum = 0u64;
+    let mut steps = 0u64;
+    Yielding::new(move || {
+        let end = n.min(next.saturating_add(chunk));
+        sum = (next..end).fold(sum, u64::wrapping_add);
+        next = end;
+        steps += 1;
+        if next == n {
+            Step::Done((sum, steps))
+        } else {
+            Step::Yield
+        }
+    })
+}
+
+/// Counts the steps of the Collatz sequence from `n` down to 1, one step at a time.
+pub struct Collatz {
+    n: u64,
+    steps: u64,
+}
+
+impl Resumable for Collatz {
+    type Output = NifResult<u64>;
+
+    fn step(&mut self) -> Step<NifResult<u64>> {
+        match self.n {
+            0 => Step::Done(Err(Error::BadArg)),
+            1 => Step::Done(Ok(self.steps)),
+            n if n % 2 == 0 => {
+                self.n = n / 2;
+                self.steps += 1;
+                Step::Yield
+            }
+            n => {
+                self.n = 3 * n + 1;
+                self.steps += 1;
+                Step::Yield
+            }
+        }
+    }
+}
+
+#[rustler::nif]
+pub fn yielding_collatz(n: u64) -> Yielding<Collatz> {
+    Yielding::new(Collatz { n, steps: 0 })
+}
+
+#[rustler::nif]
+pub fn env_consume_timeslice(env: Env, percent: i32) -> bool {
+    env.consume_timeslice(percent)
+}
+
+/// Sums `items` until the timeslice is used up, spending `spin_us` on each, and returns the sum
+/// and the items left.
+#[rustler::nif]
+pub fn budgeted_sum(env: Env, items: Vec<u64>, spin_us: u64) -> (u64, Vec<u64>) {
+    let spin = Duration::from_micros(spin_us);
+    let mut budgeted = env.budgeted(items);
+    let mut sum = 0u64;
+    for item in budgeted.by_ref() {
+        let started = Instant::now();
+        while started.elapsed() < spin {}
+        sum = sum.wrapping_add(item);
+    }
+    (sum, budgeted.into_inner().collect())
+}
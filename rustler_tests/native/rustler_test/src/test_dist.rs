@@ -0,0 +1,58 @@
+use rustler::backend::{BuildTerm, EtfBackend};
+use rustler::dist::{Connection, DistError, Recipient};
+use rustler::etf::EtfTerm;
+use rustler::{Error, NifResult, NifTuple, NifUnitEnum};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+
+#[derive(NifUnitEnum)]
+#[rustler(build_term)]
+pub enum Greeting {
+    Hello,
+    Goodbye,
+}
+
+#[derive(NifTuple)]
+#[rustler(build_term)]
+pub struct Request {
+    greeting: Greeting,
+    count: u32,
+}
+
+fn dist_error(err: DistError) -> Error {
+    Error::Term(Box::new(err.to_string()))
+}
+
+/// Connects to `remote` as a hidden node, sends `{pid, {greeting, count}}` to the process
+/// registered there as `name`, and returns the reply sent to `pid`.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn dist_call(
+    remote: String,
+    cookie: String,
+    name: String,
+    goodbye: bool,
+    count: u32,
+) -> NifResult<EtfTerm> {
+    // A new name for each connection, so that it doesn't race with the teardown of the last one.
+    let (_, host) = remote.split_once('@').unwrap_or(("", "localhost"));
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let local = format!("rustler_dist_test_{}@{}", id, host);
+    let mut conn = Connection::connect(&local, &remote, &cookie).map_err(dist_error)?;
+    let me = conn.make_pid();
+
+    let greeting = if goodbye {
+        Greeting::Goodbye
+    } else {
+        Greeting::Hello
+    };
+    let request = Request { greeting, count }.build(EtfBackend);
+    let message = EtfTerm::Tuple(vec![me.clone(), request]);
+    conn.send_named(&me, &name, &message).map_err(dist_error)?;
+
+    let reply = conn.receive().map_err(dist_error)?;
+    if reply.to != Recipient::Pid(me) {
+        return Err(Error::Term(Box::new("reply sent to another process")));
+    }
+    Ok(reply.payload)
+}
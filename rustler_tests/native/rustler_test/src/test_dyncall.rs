@@ -0,0 +1,68 @@
+use rustler::dyncall::{CallData, DynamicCall};
+use rustler::monitor::{Monitor, MonitorDown};
+use rustler::{Atom, Env, LocalPid, NifResult, ResourceArc, Term};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The data passed to `Adder`, which adds its amount to `value`.
+#[repr(C)]
+pub struct AddCall {
+    value: i64,
+}
+
+unsafe impl CallData for AddCall {}
+
+pub struct Adder {
+    amount: i64,
+    downs: AtomicUsize,
+}
+
+impl DynamicCall for Adder {
+    type CallData = AddCall;
+
+    fn dynamic_call(&self, _env: Env, data: &mut AddCall) {
+        data.value += self.amount;
+    }
+}
+
+impl MonitorDown for Adder {
+    fn down(&self, _env: Env, _pid: LocalPid, _monitor: Monitor) {
+        self.downs.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+rustler::resource_type!(Adder, name = "RustlerTest.Adder", monitor, dyncall);
+
+pub fn on_load(env: Env) -> bool {
+    rustler::resource::register::<Adder>(env)
+}
+
+#[rustler::nif]
+pub fn dyncall_adder(amount: i64) -> ResourceArc<Adder> {
+    ResourceArc::new(Adder {
+        amount,
+        downs: AtomicUsize::new(0),
+    })
+}
+
+#[rustler::nif]
+pub fn dyncall_adder_watch(env: Env, adder: ResourceArc<Adder>, pid: LocalPid) -> bool {
+    adder.monitor(env, &pid).is_some()
+}
+
+#[rustler::nif]
+pub fn dyncall_adder_downs(adder: ResourceArc<Adder>) -> usize {
+    adder.downs.load(Ordering::SeqCst)
+}
+
+#[rustler::nif]
+pub fn dyncall_add(
+    env: Env,
+    module: Atom,
+    name: &str,
+    resource: Term,
+    value: i64,
+) -> NifResult<i64> {
+    let mut data = AddCall { value };
+    env.call_dynamic(module, name, resource, &mut data)?;
+    Ok(data.value)
+}
@@ -0,0 +1,33 @@
+use rustler::types::elixir_std::{MapSet, Range, RegexSource, Uri};
+use std::collections::{BTreeSet, HashSet};
+
+#[rustler::nif]
+pub fn map_set_echo(set: MapSet<i64>) -> MapSet<i64> {
+    set
+}
+
+#[rustler::nif]
+pub fn hash_set_echo(set: HashSet<String>) -> HashSet<String> {
+    set
+}
+
+#[rustler::nif]
+pub fn btree_set_to_list(set: BTreeSet<i64>) -> (BTreeSet<i64>, Vec<i64>) {
+    let elements = set.iter().copied().collect();
+    (set, elements)
+}
+
+#[rustler::nif]
+pub fn range_to_list(range: Range) -> (Range, Vec<i64>) {
+    (range, range.iter().collect())
+}
+
+#[rustler::nif]
+pub fn uri_echo(uri: Uri) -> Uri {
+    uri
+}
+
+#[rustler::nif]
+pub fn regex_source(regex: RegexSource) -> RegexSource {
+    regex
+}
@@ -1,9 +1,12 @@
+use rustler::deadline::CallContext;
 use rustler::env::{OwnedEnv, SavedTerm};
+use rustler::messenger::SendError;
 use rustler::types::atom;
 use rustler::types::list::ListIterator;
-use rustler::types::LocalPid;
-use rustler::{Atom, Encoder, Env, NifResult, Term};
+use rustler::types::{LocalPid, LocalPort};
+use rustler::{Atom, Encoder, Env, Messenger, NifResult, ReplyStream, Term, TraceContext};
 use std::thread;
+use std::time::Duration;
 
 // Send a message to several PIDs.
 #[rustler::nif]
@@ -66,3 +69,140 @@ pub fn sublists<'a>(env: Env<'a>, list: Term<'a>) -> NifResult<Atom> {
 
     Ok(atom::ok())
 }
+
+#[rustler::nif]
+pub fn reply_chunks<'a>(env: Env<'a>, reference: Term<'a>, count: u32) -> Atom {
+    let stream = ReplyStream::new(env, reference);
+    for i in 0..count {
+        stream.chunk(&i);
+    }
+    stream.done();
+
+    atom::ok()
+}
+
+#[rustler::nif]
+pub fn intern_strs<'a>(env: Env<'a>, strings: Vec<String>) -> Vec<Term<'a>> {
+    let mut interner = env.interner();
+    strings
+        .iter()
+        .map(|string| interner.intern(string))
+        .collect()
+}
+
+#[rustler::nif]
+pub fn deadline_remaining(ctx: CallContext) -> Option<u64> {
+    ctx.remaining()
+        .map(|remaining| remaining.as_millis() as u64)
+}
+
+// Runs `steps` steps of 1ms, checking the deadline before each of them.
+#[rustler::nif]
+pub fn deadline_steps(ctx: CallContext, steps: u32) -> NifResult<u32> {
+    for _ in 0..steps {
+        ctx.check()?;
+        thread::sleep(Duration::from_millis(1));
+    }
+    Ok(steps)
+}
+
+// Sends `msg` to the process registered as `name`, from the NIF or from a thread.
+#[rustler::nif]
+pub fn whereis_send<'a>(env: Env<'a>, name: String, msg: Term<'a>, from_thread: bool) -> bool {
+    if !from_thread {
+        return match env.whereis_pid(&name) {
+            Some(pid) => {
+                env.send(&pid, msg);
+                true
+            }
+            None => false,
+        };
+    }
+
+    let mut owned_env = OwnedEnv::new();
+    let saved_msg = owned_env.save(msg);
+    thread::spawn(move || {
+        let mut found = None;
+        owned_env.run(|env| found = env.whereis_pid(&name));
+        if let Some(pid) = found {
+            owned_env.send_and_clear(&pid, |env| saved_msg.load(env));
+        }
+    })
+    .join()
+    .is_ok()
+}
+
+#[rustler::nif]
+pub fn whereis_port_command<'a>(env: Env<'a>, name: String, data: Term<'a>) -> Option<bool> {
+    let port: LocalPort = env.whereis_port(&name)?;
+    Some(port.is_alive(env) && port.command(env, data))
+}
+
+mod messenger_atoms {
+    rustler::atoms! {
+        not_alive,
+        managed_thread,
+        alloc,
+    }
+}
+
+fn send_result_atom(result: Result<(), SendError>) -> Atom {
+    match result {
+        Ok(()) => atom::ok(),
+        Err(SendError::NotAlive) => messenger_atoms::not_alive(),
+        Err(SendError::ManagedThread) => messenger_atoms::managed_thread(),
+        Err(SendError::Alloc) => messenger_atoms::alloc(),
+    }
+}
+
+// Sends `{i, thread}` for `i` in `0..count` from each of `threads` threads, and returns the
+// result of the last send of each thread.
+#[rustler::nif]
+pub fn messenger_send(pid: LocalPid, threads: u32, count: u32) -> Vec<Atom> {
+    let messenger = Messenger::new(pid);
+    let handles: Vec<_> = (0..threads)
+        .map(|thread| {
+            let messenger = messenger.clone();
+            thread::spawn(move || {
+                let mut result = Ok(());
+                for i in 0..count {
+                    result = messenger.send(|env| (i, thread).encode(env));
+                }
+                send_result_atom(result)
+            })
+        })
+        .collect();
+    handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect()
+}
+
+#[rustler::nif]
+pub fn messenger_send_from_nif(pid: LocalPid) -> Atom {
+    send_result_atom(Messenger::new(pid).send_encoded(&atom::ok()))
+}
+
+#[rustler::nif]
+pub fn trace_context_extract(metadata: Term) -> NifResult<Option<TraceContext>> {
+    TraceContext::extract(metadata)
+}
+
+#[rustler::nif]
+pub fn trace_context_traceparent(context: TraceContext) -> String {
+    context.to_traceparent()
+}
+
+/// Sends `message` to `pid` tagged with the trace context of the `traceparent` header.
+#[rustler::nif]
+pub fn trace_context_send<'a>(
+    env: Env<'a>,
+    pid: LocalPid,
+    header: String,
+    message: Term<'a>,
+) -> NifResult<Atom> {
+    let context = TraceContext::from_traceparent(&header).ok_or(rustler::Error::BadArg)?;
+    let metadata = context.inject(Term::map_new(env))?;
+    env.send(&pid, (metadata, message).encode(env));
+    Ok(atom::ok())
+}
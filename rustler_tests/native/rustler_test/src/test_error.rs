@@ -1,3 +1,4 @@
+use rustler::panic_handler::{self, PanicMode};
 use rustler::{Error, NifResult};
 
 mod atoms {
@@ -43,3 +44,19 @@ pub fn term_with_tuple_error() -> NifResult<()> {
     let reason = atoms::should_be_an_atom_wrapped_in_an_error_tuple();
     Err(Error::Term(Box::new(reason)))
 }
+
+#[rustler::nif(unit_ok)]
+pub fn panic_mode_set(mode: String) -> NifResult<()> {
+    let mode = match mode.as_str() {
+        "atom" => PanicMode::Atom,
+        "exception" => PanicMode::Exception,
+        _ => return Err(Error::BadArg),
+    };
+    panic_handler::set_mode(mode);
+    Ok(())
+}
+
+#[rustler::nif]
+pub fn panic_with(message: String) -> u32 {
+    panic!("{}", message)
+}
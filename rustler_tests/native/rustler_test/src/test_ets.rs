@@ -0,0 +1,11 @@
+use rustler::ets::EtsInsert;
+use rustler::Term;
+
+#[rustler::nif]
+pub fn ets_export(
+    table: Term,
+    count: u64,
+    chunk_size: usize,
+) -> EtsInsert<impl Iterator<Item = (u64, u64)>> {
+    EtsInsert::new(table, (0..count).map(|id| (id, id * 2))).chunk_size(chunk_size)
+}
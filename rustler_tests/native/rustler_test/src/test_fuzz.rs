@@ -0,0 +1,26 @@
+use rustler::fuzz::{decode_arbitrary, DecodeOutcome};
+use rustler::{Atom, Binary, NifMap};
+
+mod atoms {
+    rustler::atoms! {
+        not_etf,
+        decoded,
+        rejected,
+    }
+}
+
+#[derive(NifMap)]
+pub struct FuzzConfig {
+    name: String,
+    sizes: Vec<u32>,
+    ratio: f64,
+}
+
+#[rustler::nif]
+pub fn fuzz_decode_config(data: Binary) -> Atom {
+    match decode_arbitrary::<FuzzConfig>(data.as_slice()) {
+        DecodeOutcome::NotEtf => atoms::not_etf(),
+        DecodeOutcome::Decoded => atoms::decoded(),
+        DecodeOutcome::Rejected => atoms::rejected(),
+    }
+}
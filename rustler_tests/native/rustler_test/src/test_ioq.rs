@@ -0,0 +1,55 @@
+use rustler::types::{Binary, IoQueue, OwnedBinary};
+use rustler::{Env, NifResult, ResourceArc};
+use std::io::Read;
+use std::sync::Mutex;
+
+pub struct QueueResource {
+    queue: Mutex<IoQueue>,
+}
+
+rustler::resource_type!(QueueResource, name = "RustlerTest.IoQueue");
+
+pub fn on_load(env: Env) -> bool {
+    rustler::resource::register::<QueueResource>(env)
+}
+
+#[rustler::nif]
+pub fn ioq_new() -> ResourceArc<QueueResource> {
+    ResourceArc::new(QueueResource {
+        queue: Mutex::new(IoQueue::new()),
+    })
+}
+
+#[rustler::nif]
+pub fn ioq_push(env: Env, resource: ResourceArc<QueueResource>, data: Binary) -> NifResult<usize> {
+    let mut queue = resource.queue.lock().unwrap();
+    queue.enqueue_binary(env, data)?;
+    Ok(queue.len())
+}
+
+#[rustler::nif]
+pub fn ioq_push_owned(resource: ResourceArc<QueueResource>, data: Binary) -> usize {
+    let mut queue = resource.queue.lock().unwrap();
+    queue.enqueue_owned(data.to_owned().unwrap());
+    queue.len()
+}
+
+#[rustler::nif]
+pub fn ioq_read(env: Env, resource: ResourceArc<QueueResource>, count: usize) -> Binary {
+    let mut queue = resource.queue.lock().unwrap();
+    let mut binary = OwnedBinary::new(count.min(queue.len())).unwrap();
+    queue.read_exact(binary.as_mut_slice()).unwrap();
+    binary.release(env)
+}
+
+#[rustler::nif]
+pub fn ioq_segments(resource: ResourceArc<QueueResource>) -> Vec<usize> {
+    let queue = resource.queue.lock().unwrap();
+    queue.peek().iter().map(|segment| segment.len()).collect()
+}
+
+#[cfg(nif_version_2_14)]
+#[rustler::nif]
+pub fn ioq_head(env: Env, resource: ResourceArc<QueueResource>) -> Option<Binary> {
+    resource.queue.lock().unwrap().peek_head(env)
+}
@@ -1,4 +1,6 @@
-use rustler::{Error, ListIterator, NifResult};
+use rustler::types::{ErlArray, ErlQueue, KeywordList};
+use rustler::{Atom, ChunkedList, Error, ListIterator, NifResult, Term};
+use std::cell::RefCell;
 
 #[rustler::nif]
 pub fn sum_list(iter: ListIterator) -> NifResult<i64> {
@@ -10,7 +12,49 @@ pub fn sum_list(iter: ListIterator) -> NifResult<i64> {
     }
 }
 
+thread_local! {
+    static SCRATCH: RefCell<Vec<i64>> = const { RefCell::new(Vec::new()) };
+}
+
+#[rustler::nif]
+pub fn sum_list_scratch(list: Term) -> NifResult<i64> {
+    SCRATCH.with(|scratch| {
+        let mut scratch = scratch.borrow_mut();
+        list.decode_into(&mut *scratch)?;
+        Ok(scratch.iter().sum())
+    })
+}
+
 #[rustler::nif]
 pub fn make_list() -> Vec<usize> {
     vec![1, 2, 3]
 }
+
+#[rustler::nif]
+pub fn make_chunked_list(len: usize, chunk_size: usize) -> ChunkedList<usize> {
+    ChunkedList::new((0..len).collect()).chunk_size(chunk_size)
+}
+
+#[rustler::nif]
+pub fn queue_reverse(queue: ErlQueue<i64>) -> ErlQueue<i64> {
+    queue.into_inner().into_iter().rev().collect()
+}
+
+#[rustler::nif]
+pub fn array_double(array: ErlArray<i64>) -> ErlArray<i64> {
+    array
+        .into_inner()
+        .into_iter()
+        .map(|entry| entry.map(|value| value * 2))
+        .collect()
+}
+
+#[rustler::nif]
+pub fn keyword_list_echo(list: KeywordList) -> KeywordList {
+    list
+}
+
+#[rustler::nif]
+pub fn keyword_list_get(list: KeywordList, key: Atom) -> NifResult<(Option<i64>, usize)> {
+    Ok((list.get_as(key)?, list.get_values(key).len()))
+}
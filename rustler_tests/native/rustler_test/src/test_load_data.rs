@@ -0,0 +1,32 @@
+use rustler::load_data::LoadData;
+use rustler::{Env, Error, NifMap, NifResult, Term};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static POOL_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(NifMap)]
+pub struct LoadConfig {
+    pool_size: usize,
+}
+
+pub fn on_load(config: LoadConfig) {
+    POOL_SIZE.store(config.pool_size, Ordering::Relaxed);
+}
+
+#[rustler::nif]
+pub fn load_data_pool_size() -> usize {
+    POOL_SIZE.load(Ordering::Relaxed)
+}
+
+#[rustler::nif]
+pub fn load_data_get_u32(data: Term, key: String) -> NifResult<u32> {
+    LoadData::new(data)
+        .get(&key)
+        .map_err(|err| Error::Term(Box::new(err.message().to_string())))
+}
+
+/// Runs the check of `min_nif_version`, which fails loading when it returns `false`.
+#[rustler::nif]
+pub fn load_data_check_nif_version(env: Env, major: u32, minor: u32) -> bool {
+    unsafe { rustler::codegen_runtime::check_nif_version(env.as_c_arg(), major, minor) }
+}
@@ -1,6 +1,9 @@
+use indexmap::IndexMap;
 use rustler::types::map::MapIterator;
 use rustler::types::tuple::make_tuple;
+use rustler::types::{GbSet, GbTree, GbTreeIterator};
 use rustler::{Encoder, Env, NifResult, Term};
+use std::collections::BTreeMap;
 
 #[rustler::nif]
 pub fn sum_map_values(iter: MapIterator) -> NifResult<i64> {
@@ -41,3 +44,31 @@ pub fn map_generic(
 ) -> std::collections::HashMap<i64, String> {
     map
 }
+
+#[rustler::nif]
+pub fn btree_map_echo(map: BTreeMap<String, i64>) -> (Vec<String>, BTreeMap<String, i64>) {
+    (map.keys().cloned().collect(), map)
+}
+
+#[rustler::nif]
+pub fn index_map_echo(map: IndexMap<i64, String>) -> (Vec<i64>, IndexMap<i64, String>) {
+    (map.keys().copied().collect(), map)
+}
+
+#[rustler::nif]
+pub fn gb_tree_double(tree: GbTree<i64, i64>) -> GbTree<i64, i64> {
+    tree.into_inner()
+        .into_iter()
+        .map(|(key, value)| (key, value * 2))
+        .collect()
+}
+
+#[rustler::nif]
+pub fn gb_tree_keys(tree: GbTreeIterator) -> Vec<Term> {
+    tree.map(|(key, _value)| key).collect()
+}
+
+#[rustler::nif]
+pub fn gb_set_echo(set: GbSet<String>) -> GbSet<String> {
+    set
+}
@@ -0,0 +1,66 @@
+use rustler::monitor::{Monitor, MonitorDown};
+use rustler::{Env, LocalPid, ResourceArc};
+use std::sync::Mutex;
+
+pub struct Watcher {
+    monitors: Mutex<Vec<(LocalPid, Monitor)>>,
+    downs: Mutex<Vec<LocalPid>>,
+}
+
+impl MonitorDown for Watcher {
+    fn down(&self, _env: Env, pid: LocalPid, monitor: Monitor) {
+        self.monitors
+            .lock()
+            .unwrap()
+            .retain(|(_, active)| *active != monitor);
+        self.downs.lock().unwrap().push(pid);
+    }
+}
+
+rustler::resource_type!(Watcher, name = "RustlerTest.Watcher", monitor);
+
+pub fn on_load(env: Env) -> bool {
+    rustler::resource::register::<Watcher>(env)
+}
+
+#[rustler::nif]
+pub fn watcher_new() -> ResourceArc<Watcher> {
+    ResourceArc::new(Watcher {
+        monitors: Mutex::new(Vec::new()),
+        downs: Mutex::new(Vec::new()),
+    })
+}
+
+#[rustler::nif]
+pub fn watcher_watch(env: Env, watcher: ResourceArc<Watcher>, pid: LocalPid) -> bool {
+    match watcher.monitor(env, &pid) {
+        Some(monitor) => {
+            watcher.monitors.lock().unwrap().push((pid, monitor));
+            true
+        }
+        None => false,
+    }
+}
+
+#[rustler::nif]
+pub fn watcher_unwatch(env: Env, watcher: ResourceArc<Watcher>, pid: LocalPid) -> bool {
+    let mut monitors = watcher.monitors.lock().unwrap();
+    match monitors.iter().position(|(watched, _)| *watched == pid) {
+        Some(index) => {
+            let (_, monitor) = monitors.swap_remove(index);
+            watcher.demonitor(env, &monitor)
+        }
+        None => false,
+    }
+}
+
+#[rustler::nif]
+pub fn watcher_watched(watcher: ResourceArc<Watcher>) -> Vec<LocalPid> {
+    let monitors = watcher.monitors.lock().unwrap();
+    monitors.iter().map(|(pid, _)| pid.clone()).collect()
+}
+
+#[rustler::nif]
+pub fn watcher_downs(watcher: ResourceArc<Watcher>) -> Vec<LocalPid> {
+    watcher.downs.lock().unwrap().clone()
+}
@@ -1,4 +1,75 @@
+use rustler::codegen_runtime::{c_int, NIF_ENV, NIF_TERM};
+use rustler::{Encoder, Env, Error, NifRegistration, NifResult, SchedulerFlags};
+
 #[rustler::nif(name = "nif_attrs_can_rename")]
 pub fn can_rename() -> bool {
     true
 }
+
+#[rustler::nif(deprecated = "use add_i32/2 instead")]
+pub fn deprecated_add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[rustler::nif(unit_ok)]
+pub fn unit_ok_check(n: i64) -> NifResult<()> {
+    if n < 0 {
+        return Err(Error::BadArg);
+    }
+    Ok(())
+}
+
+#[rustler::nif(unsafe_crash_guard)]
+pub fn crash_guard_read(address: usize) -> i32 {
+    unsafe { std::ptr::read_volatile(address as *const i32) }
+}
+
+#[rustler::nif(feature = "default-feature")]
+pub fn feature_enabled_add(a: i64, b: i64) -> i64 {
+    a + b
+}
+
+#[rustler::nif(feature = "optional-feature")]
+pub fn feature_disabled_add(a: i64, b: i64) -> i64 {
+    a + b
+}
+
+unsafe extern "C" fn constant<const N: i64>(
+    nif_env: NIF_ENV,
+    _argc: c_int,
+    _argv: *const NIF_TERM,
+) -> NIF_TERM {
+    let lifetime = ();
+    let env = Env::new(&lifetime, nif_env);
+    N.encode(env).as_c_arg()
+}
+
+pub fn registry() -> NifRegistration {
+    NifRegistration::new()
+        .add("registered_one", 0, SchedulerFlags::Normal, constant::<1>)
+        .add("registered_two", 0, SchedulerFlags::DirtyCpu, constant::<2>)
+}
+
+pub struct Counter;
+
+#[rustler::nif]
+impl Counter {
+    #[rustler::nif]
+    pub fn double(n: i64) -> i64 {
+        Counter::scale(n, 2)
+    }
+
+    #[rustler::nif(name = "nif_attrs_triple")]
+    pub fn triple(n: i64) -> i64 {
+        Counter::scale(n, 3)
+    }
+
+    #[rustler::nif(name = "nif_attrs_quadruple", feature = "optional-feature")]
+    pub fn quadruple(n: i64) -> i64 {
+        Counter::scale(n, 4)
+    }
+
+    fn scale(n: i64, factor: i64) -> i64 {
+        n * factor
+    }
+}
@@ -0,0 +1,35 @@
+use rustler::overload::Overload;
+use rustler::{Atom, NifResult, ResourceArc};
+use std::thread;
+use std::time::Duration;
+
+mod atoms {
+    rustler::atoms! { ok }
+}
+
+#[rustler::nif]
+pub fn overload_new(max_in_flight: usize, max_load: u64) -> ResourceArc<Overload> {
+    ResourceArc::new(
+        Overload::new()
+            .max_in_flight(max_in_flight)
+            .max_load(max_load),
+    )
+}
+
+#[rustler::nif]
+pub fn overload_report_load(overload: ResourceArc<Overload>, load: u64) {
+    overload.report_load(load)
+}
+
+// Holds a guard for `millis` milliseconds.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn overload_run(overload: ResourceArc<Overload>, millis: u64) -> NifResult<Atom> {
+    let _guard = overload.enter()?;
+    thread::sleep(Duration::from_millis(millis));
+    Ok(atoms::ok())
+}
+
+#[rustler::nif]
+pub fn overload_stats(overload: ResourceArc<Overload>) -> (usize, u64) {
+    (overload.in_flight(), overload.rejected())
+}
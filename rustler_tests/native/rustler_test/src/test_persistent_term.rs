@@ -0,0 +1,14 @@
+use rustler::types::atom;
+use rustler::{persistent_term, Atom, Env, NifResult, Term};
+
+#[rustler::nif]
+pub fn persistent_term_put<'a>(env: Env<'a>, key: Term<'a>, value: Term<'a>) -> NifResult<Atom> {
+    persistent_term::put(env, &key, &value)?;
+    Ok(atom::ok())
+}
+
+#[rustler::nif]
+pub fn persistent_term_erase<'a>(env: Env<'a>, key: Term<'a>) -> NifResult<Atom> {
+    persistent_term::erase(env, &key)?;
+    Ok(atom::ok())
+}
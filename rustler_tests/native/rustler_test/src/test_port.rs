@@ -0,0 +1,54 @@
+use rustler::backend::{BuildTerm, EtfBackend};
+use rustler::etf::EtfTerm;
+use rustler::port::Port;
+use rustler::{Binary, Error, NifResult, NifTuple, NifUnitEnum, OwnedBinary};
+
+#[derive(NifUnitEnum)]
+#[rustler(build_term)]
+pub enum Status {
+    Ok,
+    Error,
+}
+
+#[derive(NifUnitEnum)]
+#[rustler(build_term)]
+pub enum Failure {
+    Unknown,
+}
+
+#[derive(NifTuple)]
+#[rustler(build_term)]
+pub struct Sum {
+    status: Status,
+    value: i64,
+}
+
+fn handle(request: EtfTerm) -> EtfTerm {
+    if let EtfTerm::Tuple(ref elements) = request {
+        if let [EtfTerm::Atom(op), EtfTerm::Integer(a), EtfTerm::Integer(b)] = elements.as_slice() {
+            if op == "add" {
+                return Sum {
+                    status: Status::Ok,
+                    value: a + b,
+                }
+                .build(EtfBackend);
+            }
+        }
+    }
+    (Status::Error, Failure::Unknown).build(EtfBackend)
+}
+
+/// Runs a port over `input`, accepting packets of at most `max_packet_size` bytes, and returns
+/// what it wrote.
+#[rustler::nif]
+pub fn port_serve(input: Binary, max_packet_size: usize) -> NifResult<OwnedBinary> {
+    let mut port = Port::new(input.as_slice(), Vec::new());
+    port.set_max_packet_size(max_packet_size);
+    port.serve(handle)
+        .map_err(|err| Error::Term(Box::new(err.to_string())))?;
+
+    let (_, output) = port.into_inner();
+    let mut binary = OwnedBinary::new(output.len()).unwrap();
+    binary.as_mut_slice().copy_from_slice(&output);
+    Ok(binary)
+}
@@ -1,3 +1,5 @@
+use rustler::types::big_int::num_bigint::BigInt;
+
 #[rustler::nif]
 pub fn add_u32(a: u32, b: u32) -> u32 {
     a + b
@@ -13,6 +15,21 @@ pub fn echo_u8(n: u8) -> u8 {
     n
 }
 
+#[rustler::nif]
+pub fn add_i128(a: i128, b: i128) -> Option<i128> {
+    a.checked_add(b)
+}
+
+#[rustler::nif]
+pub fn echo_u128(n: u128) -> u128 {
+    n
+}
+
+#[rustler::nif]
+pub fn mul_bigint(a: BigInt, b: BigInt) -> BigInt {
+    a * b
+}
+
 #[rustler::nif]
 pub fn option_inc(opt: Option<f64>) -> Option<f64> {
     opt.map(|num| num + 1.0)
@@ -1,6 +1,29 @@
+use rustler::types::elixir_std::Range;
+use rustler::{Binary, EncodingProfile, Env, Error, NifResult, Term};
+use std::convert::TryFrom;
 use std::ops::RangeInclusive;
 
 #[rustler::nif]
 pub fn sum_range(range: RangeInclusive<i64>) -> i64 {
     range.sum()
 }
+
+#[rustler::nif]
+pub fn range_inclusive_echo(env: Env, range: RangeInclusive<i64>, stepped: bool) -> Term {
+    let profile = if stepped {
+        EncodingProfile::ELIXIR
+    } else {
+        EncodingProfile::ELIXIR.unstepped_ranges()
+    };
+    profile.encode(env, &range)
+}
+
+#[rustler::nif]
+pub fn range_slice(data: Binary, range: RangeInclusive<usize>) -> NifResult<Vec<u8>> {
+    data.get(range).map(<[u8]>::to_vec).ok_or(Error::BadArg)
+}
+
+#[rustler::nif]
+pub fn range_to_inclusive(range: Range) -> NifResult<RangeInclusive<i64>> {
+    RangeInclusive::try_from(range)
+}
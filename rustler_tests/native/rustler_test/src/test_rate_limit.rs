@@ -0,0 +1,24 @@
+use rustler::rate_limit::TokenBucket;
+use rustler::ResourceArc;
+
+#[rustler::nif]
+pub fn token_bucket_new(capacity: u64, rate: u64) -> ResourceArc<TokenBucket> {
+    ResourceArc::new(TokenBucket::new(capacity, rate))
+}
+
+#[rustler::nif]
+pub fn token_bucket_try_acquire(bucket: ResourceArc<TokenBucket>, tokens: u64) -> bool {
+    bucket.try_acquire(tokens)
+}
+
+#[rustler::nif]
+pub fn token_bucket_available(bucket: ResourceArc<TokenBucket>) -> u64 {
+    bucket.available()
+}
+
+#[rustler::nif]
+pub fn token_bucket_wait_time(bucket: ResourceArc<TokenBucket>, tokens: u64) -> Option<u64> {
+    bucket
+        .wait_time(tokens)
+        .map(|wait_time| wait_time.as_millis() as u64)
+}
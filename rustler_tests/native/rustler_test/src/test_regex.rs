@@ -0,0 +1,29 @@
+use rustler::regex::RegexResource;
+use rustler::types::elixir_std::RegexSource;
+use rustler::ResourceArc;
+use std::collections::HashMap;
+
+#[rustler::nif]
+pub fn regex_compile(source: RegexSource) -> Result<ResourceArc<RegexResource>, String> {
+    RegexResource::from_source(&source)
+        .map(ResourceArc::new)
+        .map_err(|err| err.to_string())
+}
+
+#[rustler::nif]
+pub fn regex_is_match(regex: ResourceArc<RegexResource>, text: &str) -> bool {
+    regex.is_match(text)
+}
+
+#[rustler::nif]
+pub fn regex_positions(regex: ResourceArc<RegexResource>, text: &str) -> Vec<(usize, usize)> {
+    regex.positions(text)
+}
+
+#[rustler::nif]
+pub fn regex_named_captures(
+    regex: ResourceArc<RegexResource>,
+    text: &str,
+) -> Option<HashMap<String, &str>> {
+    regex.named_captures(text)
+}
@@ -1,5 +1,7 @@
+use rustler::resource_hooks::{self, ResourceId};
 use rustler::{Env, ResourceArc};
-use std::sync::RwLock;
+use std::collections::HashSet;
+use std::sync::{Mutex, RwLock};
 
 pub struct TestResource {
     test_field: RwLock<i32>,
@@ -15,6 +17,9 @@ pub struct ImmutableResource {
 pub fn on_load(env: Env) -> bool {
     rustler::resource!(TestResource, env);
     rustler::resource!(ImmutableResource, env);
+    resource_hooks::on_destroy(|id| {
+        INDEXED.lock().unwrap().remove(&id);
+    });
     true
 }
 
@@ -69,3 +74,36 @@ pub fn resource_make_immutable(u: u32) -> ResourceArc<ImmutableResource> {
 pub fn resource_immutable_count() -> u32 {
     COUNT.load(Ordering::SeqCst) as u32
 }
+
+#[cfg(nif_version_2_13)]
+#[rustler::nif]
+pub fn resource_type_names() -> (Option<String>, Option<String>) {
+    (
+        rustler::resource::registered_name::<TestResource>(),
+        rustler::resource::registered_name::<crate::test_ioq::QueueResource>(),
+    )
+}
+
+lazy_static::lazy_static! {
+    /// The indexed resources, forgotten when the VM destroys them.
+    static ref INDEXED: Mutex<HashSet<ResourceId>> = Mutex::new(HashSet::new());
+}
+
+#[rustler::nif]
+pub fn resource_index_make() -> (ResourceArc<TestResource>, usize) {
+    let resource = ResourceArc::new(TestResource {
+        test_field: RwLock::new(0),
+    });
+    let id = ResourceId::of(&resource);
+    INDEXED.lock().unwrap().insert(id);
+    (resource, id.handle())
+}
+
+#[rustler::nif]
+pub fn resource_index_contains(handle: usize) -> bool {
+    INDEXED
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|id| id.handle() == handle)
+}
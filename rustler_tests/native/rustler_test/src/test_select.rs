@@ -0,0 +1,85 @@
+use rustler::select::{Event, SelectStop, SelectStopped};
+use rustler::{Atom, Env, NifResult, ResourceArc, Term};
+use std::io::{Read, Write};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+mod atoms {
+    rustler::atoms! {
+        called,
+        scheduled,
+    }
+}
+
+pub struct SelectPipe {
+    reader: Mutex<UnixStream>,
+    writer: Mutex<UnixStream>,
+    stopped: AtomicBool,
+}
+
+impl SelectStop for SelectPipe {
+    fn stop(&self, _event: Event, _is_direct_call: bool) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
+
+rustler::resource_type!(SelectPipe, select);
+
+pub fn on_load(env: Env) -> bool {
+    rustler::resource::register::<SelectPipe>(env)
+}
+
+#[rustler::nif]
+pub fn select_pipe_new() -> ResourceArc<SelectPipe> {
+    let (reader, writer) = UnixStream::pair().unwrap();
+    reader.set_nonblocking(true).unwrap();
+    ResourceArc::new(SelectPipe {
+        reader: Mutex::new(reader),
+        writer: Mutex::new(writer),
+        stopped: AtomicBool::new(false),
+    })
+}
+
+#[rustler::nif]
+pub fn select_pipe_wait<'a>(
+    env: Env<'a>,
+    pipe: ResourceArc<SelectPipe>,
+    reference: Term<'a>,
+) -> NifResult<()> {
+    let fd = pipe.reader.lock().unwrap().as_raw_fd();
+    Ok(env.select_read(fd, &pipe, reference)?)
+}
+
+#[rustler::nif]
+pub fn select_pipe_write(pipe: ResourceArc<SelectPipe>, data: &str) {
+    pipe.writer
+        .lock()
+        .unwrap()
+        .write_all(data.as_bytes())
+        .unwrap();
+}
+
+#[rustler::nif]
+pub fn select_pipe_read(pipe: ResourceArc<SelectPipe>) -> String {
+    let mut buf = [0; 64];
+    match pipe.reader.lock().unwrap().read(&mut buf) {
+        Ok(n) => String::from_utf8_lossy(&buf[..n]).into_owned(),
+        Err(_) => String::new(),
+    }
+}
+
+#[rustler::nif]
+pub fn select_pipe_stop(env: Env, pipe: ResourceArc<SelectPipe>) -> NifResult<Atom> {
+    let fd = pipe.reader.lock().unwrap().as_raw_fd();
+    match env.select_stop(fd, &pipe)? {
+        SelectStopped::Called => Ok(atoms::called()),
+        SelectStopped::Scheduled => Ok(atoms::scheduled()),
+    }
+}
+
+#[rustler::nif]
+pub fn select_pipe_stopped(pipe: ResourceArc<SelectPipe>) -> bool {
+    pipe.stopped.load(Ordering::SeqCst)
+}
@@ -0,0 +1,44 @@
+use rustler::serde::SerdeTerm;
+use rustler::{Env, Term};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub enum Status {
+    Pending,
+    Shipped(String),
+    Split(u32, u32),
+    Returned { reason: String, refund: bool },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Order {
+    id: u64,
+    customer: Option<String>,
+    lines: Vec<(String, u32)>,
+    tags: HashMap<String, f64>,
+    status: Status,
+}
+
+#[rustler::nif]
+pub fn serde_echo_order(order: SerdeTerm<Order>) -> SerdeTerm<Order> {
+    order
+}
+
+// Borrows the strings of the binaries of `names`.
+#[rustler::nif]
+pub fn serde_longest(names: SerdeTerm<Vec<&str>>) -> Option<&str> {
+    names.0.into_iter().max_by_key(|name| name.len())
+}
+
+#[rustler::nif]
+pub fn serde_order_to_term(env: Env, id: u64) -> Term {
+    let order = Order {
+        id,
+        customer: None,
+        lines: vec![("apple".to_string(), 3)],
+        tags: HashMap::new(),
+        status: Status::Split(1, 2),
+    };
+    rustler::serde::to_term(env, &order).unwrap()
+}
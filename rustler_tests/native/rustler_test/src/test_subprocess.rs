@@ -0,0 +1,45 @@
+use rustler::types::atom;
+use rustler::{Atom, Binary, Env, Error, NifResult, ResourceArc, SubprocessResource};
+use std::process::Command;
+
+#[rustler::nif]
+pub fn subprocess_spawn(
+    env: Env,
+    program: String,
+    args: Vec<String>,
+) -> NifResult<ResourceArc<SubprocessResource>> {
+    let mut command = Command::new(program);
+    command.args(args);
+    SubprocessResource::spawn(env.pid(), command)
+        .map_err(|err| Error::Term(Box::new(err.to_string())))
+}
+
+#[rustler::nif]
+pub fn subprocess_os_pid(subprocess: ResourceArc<SubprocessResource>) -> u32 {
+    subprocess.os_pid()
+}
+
+#[rustler::nif]
+pub fn subprocess_write(
+    subprocess: ResourceArc<SubprocessResource>,
+    data: Binary,
+) -> NifResult<Atom> {
+    subprocess
+        .write(data.as_slice())
+        .map(|()| atom::ok())
+        .map_err(|err| Error::Term(Box::new(err.to_string())))
+}
+
+#[rustler::nif]
+pub fn subprocess_close_stdin(subprocess: ResourceArc<SubprocessResource>) -> Atom {
+    subprocess.close_stdin();
+    atom::ok()
+}
+
+#[rustler::nif]
+pub fn subprocess_kill(subprocess: ResourceArc<SubprocessResource>) -> NifResult<Atom> {
+    subprocess
+        .kill()
+        .map(|()| atom::ok())
+        .map_err(|err| Error::Term(Box::new(err.to_string())))
+}
@@ -1,5 +1,9 @@
-use rustler::{Atom, Term};
+use rustler::etf::EtfTerm;
+use rustler::snapshot::SnapshotOptions;
+use rustler::types::{Lazy, RowDecoder};
+use rustler::{Atom, Binary, Encoder, EncodingProfile, Env, Error, NifResult, OwnedBinary, Term};
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::io::Write;
 
 mod atoms {
@@ -7,6 +11,9 @@ mod atoms {
         equal,
         less,
         greater,
+        ok,
+        error,
+        other,
     }
 }
 
@@ -30,3 +37,125 @@ pub fn term_cmp<'a>(a: Term<'a>, b: Term<'a>) -> Atom {
         Ordering::Greater => atoms::greater(),
     }
 }
+
+#[rustler::nif]
+pub fn term_type(term: Term) -> String {
+    format!("{:?}", term.get_type())
+}
+
+#[rustler::nif]
+pub fn term_decode_or_raise(term: Term, as_list: bool) -> NifResult<usize> {
+    if as_list {
+        term.decode_or_raise::<Vec<i64>>().map(|list| list.len())
+    } else {
+        term.decode_or_raise::<u32>().map(|number| number as usize)
+    }
+}
+
+#[rustler::nif]
+pub fn term_tuple_size(term: Term) -> NifResult<usize> {
+    term.tuple_size()
+}
+
+#[rustler::nif]
+pub fn term_tuple_get(term: Term, index: usize) -> NifResult<Term> {
+    term.tuple_get(index)
+}
+
+#[rustler::nif]
+pub fn term_match_tuple(term: Term) -> NifResult<(Atom, i64, usize)> {
+    rustler::term_match!(term,
+        {atom("ok"), value: i64, rest @ ..} => (atoms::ok(), value, rest.len()),
+        {atom("error"), "fatal", _} => (atoms::error(), -1, 0),
+        {atom("error"), code: i64, ..} => (atoms::error(), code, 0),
+        {_} => (atoms::other(), 0, 0),
+    )
+}
+
+#[rustler::nif]
+pub fn etf_decode(data: Binary) -> NifResult<EtfTerm> {
+    EtfTerm::from_bytes(data.as_slice())
+        .map(|(term, _)| term)
+        .map_err(|err| Error::Term(Box::new(err.to_string())))
+}
+
+#[rustler::nif]
+pub fn etf_encode(term: EtfTerm) -> NifResult<OwnedBinary> {
+    let bytes = term
+        .to_bytes()
+        .map_err(|err| Error::Term(Box::new(err.to_string())))?;
+    let mut binary = OwnedBinary::new(bytes.len()).unwrap();
+    binary.as_mut_slice().copy_from_slice(&bytes);
+    Ok(binary)
+}
+
+#[rustler::nif]
+pub fn term_to_binary(term: Term) -> NifResult<OwnedBinary> {
+    Ok(term.try_to_binary()?)
+}
+
+#[rustler::nif]
+pub fn term_from_binary<'a>(env: Env<'a>, data: Binary) -> NifResult<Term<'a>> {
+    Term::from_binary(env, &data)
+}
+
+#[rustler::nif]
+pub fn term_to_canonical_binary(term: Term) -> OwnedBinary {
+    term.to_canonical_binary()
+}
+
+#[rustler::nif]
+pub fn sorted_map_encode(env: Env, map: HashMap<i64, String>) -> Term {
+    EncodingProfile::ELIXIR.sorted_maps().encode(env, &map)
+}
+
+/// Returns the entries of `map` in the order the sorted map encoder inserts them.
+#[rustler::nif]
+pub fn sorted_map_entries<'a>(
+    env: Env<'a>,
+    map: HashMap<i64, String>,
+) -> Vec<(Term<'a>, Term<'a>)> {
+    let mut entries: Vec<_> = map
+        .iter()
+        .map(|(key, value)| (key.encode(env), value.encode(env)))
+        .collect();
+    EncodingProfile::ELIXIR
+        .sorted_maps()
+        .maps()
+        .sort_entries(&mut entries);
+    entries
+}
+
+#[rustler::nif]
+pub fn term_snapshot(term: Term, max_binary: usize) -> String {
+    rustler::snapshot::snapshot_with(term, &SnapshotOptions { max_binary })
+}
+
+#[rustler::nif(unit_ok)]
+pub fn term_assert_snapshot(term: Term, expected: String) {
+    rustler::assert_snapshot!(term, &expected);
+}
+
+#[rustler::nif]
+pub fn rows_decode(rows: Term) -> NifResult<(Vec<i64>, Vec<String>, Vec<f64>)> {
+    RowDecoder::<(i64, String, f64)>::new().decode(rows)
+}
+
+#[rustler::nif]
+pub fn term_byte_size_estimate(term: Term) -> usize {
+    term.byte_size_estimate()
+}
+
+#[rustler::nif]
+pub fn term_byte_size_exceeds(term: Term, limit: usize) -> bool {
+    term.byte_size_exceeds(limit)
+}
+
+#[rustler::nif]
+pub fn lazy_sum(enabled: bool, values: Lazy<Vec<i64>>) -> NifResult<(i64, bool)> {
+    if !enabled {
+        return Ok((0, values.is_decoded()));
+    }
+    let sum = values.get()?.iter().sum();
+    Ok((sum, values.is_decoded()))
+}
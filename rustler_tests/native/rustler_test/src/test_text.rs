@@ -0,0 +1,32 @@
+use rustler::types::Binary;
+use rustler::{Env, NifResult};
+
+#[rustler::nif]
+pub fn text_nfc<'a>(env: Env<'a>, text: Binary<'a>) -> NifResult<Binary<'a>> {
+    rustler::text::nfc(env, text)
+}
+
+#[rustler::nif]
+pub fn text_nfd<'a>(env: Env<'a>, text: Binary<'a>) -> NifResult<Binary<'a>> {
+    rustler::text::nfd(env, text)
+}
+
+#[rustler::nif]
+pub fn text_graphemes(text: Binary) -> NifResult<Vec<Binary>> {
+    rustler::text::graphemes(text)
+}
+
+#[rustler::nif]
+pub fn text_invalid_utf8_offset(bytes: Binary) -> Option<usize> {
+    rustler::text::invalid_utf8_offset(&bytes)
+}
+
+#[rustler::nif]
+pub fn text_levenshtein(a: &str, b: &str) -> usize {
+    rustler::text::levenshtein(a, b)
+}
+
+#[rustler::nif]
+pub fn text_similarity(a: &str, b: &str) -> f64 {
+    rustler::text::similarity(a, b)
+}
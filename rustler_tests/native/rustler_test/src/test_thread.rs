@@ -1,6 +1,11 @@
 use rustler::thread;
 use rustler::types::atom;
-use rustler::{Atom, Encoder, Env};
+use rustler::{Atom, Encoder, Env, NifResult, ThreadPool};
+use std::time::Duration;
+
+mod atoms {
+    rustler::atoms! { pool_fac }
+}
 
 #[rustler::nif]
 pub fn threaded_fac(env: Env, n: u64) -> Atom {
@@ -20,13 +25,52 @@ pub fn threaded_fac(env: Env, n: u64) -> Atom {
 }
 
 #[rustler::nif]
-pub fn threaded_sleep(env: Env, msec: u64) -> Atom {
+pub fn threaded_sleep(env: Env, msec: u64) -> NifResult<Atom> {
     let q = msec / 1000;
     let r = (msec % 1000) as u32;
-    thread::spawn::<thread::ThreadSpawner, _>(env, move |thread_env| {
+    thread::try_spawn::<thread::ThreadSpawner, _>(env, move |thread_env| {
         std::thread::sleep(std::time::Duration::new(q as u64, r * 1_000_000));
         msec.encode(thread_env)
-    });
+    })?;
 
-    atom::ok()
+    Ok(atom::ok())
+}
+
+/// Replies `{:pool_fac, n, n!}` from the global pool, panicking on overflow.
+#[rustler::nif]
+pub fn pool_fac(env: Env, n: u64) -> NifResult<Atom> {
+    rustler::threadpool::global().spawn(env.pid(), move |thread_env| {
+        let result = (1..=n).fold(1u64, |a, b| {
+            a.checked_mul(b).expect("pool_fac: integer overflow")
+        });
+        (atoms::pool_fac(), n, result).encode(thread_env)
+    })?;
+    Ok(atom::ok())
+}
+
+/// Sleeps `millis` milliseconds on a pool of `threads` threads holding `capacity` jobs, for each
+/// of `jobs`, replying with the job.
+#[rustler::nif]
+pub fn pool_sleep(
+    env: Env,
+    threads: usize,
+    capacity: usize,
+    jobs: Vec<u64>,
+    millis: u64,
+) -> NifResult<Vec<Atom>> {
+    let pool = ThreadPool::new(threads, capacity);
+    let pid = env.pid();
+    Ok(jobs
+        .into_iter()
+        .map(|job| {
+            let result = pool.spawn(pid.clone(), move |thread_env| {
+                std::thread::sleep(Duration::from_millis(millis));
+                job.encode(thread_env)
+            });
+            match result {
+                Ok(()) => atom::ok(),
+                Err(_) => atom::error(),
+            }
+        })
+        .collect())
 }
@@ -0,0 +1,42 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use rustler::types::time::try_encode_system_time;
+use rustler::{Env, NifResult, Term};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[rustler::nif]
+pub fn system_time_echo(time: SystemTime) -> SystemTime {
+    time
+}
+
+#[rustler::nif]
+pub fn system_time_unix_micros(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_micros() as i64,
+        Err(err) => -(err.duration().as_micros() as i64),
+    }
+}
+
+#[rustler::nif]
+pub fn system_time_after_epoch(env: Env, secs: u64) -> NifResult<Term> {
+    try_encode_system_time(env, UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+#[rustler::nif]
+pub fn duration_echo(duration: Duration) -> Duration {
+    duration
+}
+
+#[rustler::nif]
+pub fn utc_date_time_add_seconds(time: DateTime<Utc>, seconds: i64) -> DateTime<Utc> {
+    time + chrono::Duration::seconds(seconds)
+}
+
+#[rustler::nif]
+pub fn naive_date_time_echo(time: NaiveDateTime) -> NaiveDateTime {
+    time
+}
+
+#[rustler::nif]
+pub fn naive_date_echo(date: NaiveDate) -> NaiveDate {
+    date
+}
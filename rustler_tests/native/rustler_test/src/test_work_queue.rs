@@ -0,0 +1,65 @@
+use rustler::env::OwnedEnv;
+use rustler::types::LocalPid;
+use rustler::work_queue::QueueStats;
+use rustler::{Atom, Encoder, Env, NifResult, ResourceArc, WorkQueue};
+use std::thread;
+use std::time::Duration;
+
+mod atoms {
+    rustler::atoms! { ok, job_done }
+}
+
+/// A job sleeping for a number of milliseconds, then replying `{:job_done, id}`.
+struct Job {
+    pid: LocalPid,
+    id: u64,
+    millis: u64,
+}
+
+pub struct JobQueue {
+    queue: WorkQueue<Job>,
+}
+
+rustler::resource_type!(JobQueue);
+
+pub fn on_load(env: Env) -> bool {
+    rustler::resource::register::<JobQueue>(env)
+}
+
+#[rustler::nif]
+pub fn work_queue_new(capacity: usize, workers: usize) -> ResourceArc<JobQueue> {
+    ResourceArc::new(JobQueue {
+        queue: WorkQueue::new(capacity, workers, |job: Job| {
+            if job.millis == u64::MAX {
+                panic!("job {} panicked", job.id);
+            }
+            thread::sleep(Duration::from_millis(job.millis));
+            if let Ok(mut owned_env) = OwnedEnv::try_new() {
+                owned_env.send_and_clear(&job.pid, |env| (atoms::job_done(), job.id).encode(env));
+            }
+        }),
+    })
+}
+
+#[rustler::nif]
+pub fn work_queue_push(
+    env: Env,
+    queue: ResourceArc<JobQueue>,
+    id: u64,
+    millis: u64,
+) -> NifResult<Atom> {
+    let pid = env.pid();
+    queue.queue.try_push(Job { pid, id, millis })?;
+    Ok(atoms::ok())
+}
+
+#[rustler::nif]
+pub fn work_queue_stats(queue: ResourceArc<JobQueue>) -> QueueStats {
+    queue.queue.stats()
+}
+
+#[rustler::nif]
+pub fn work_queue_close(queue: ResourceArc<JobQueue>) -> Atom {
+    queue.queue.close();
+    atoms::ok()
+}
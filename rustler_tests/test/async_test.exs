@@ -0,0 +1,29 @@
+defmodule RustlerTest.AsyncTest do
+  use ExUnit.Case, async: true
+
+  test "async nif replies with its output" do
+    ref = RustlerTest.async_add(1, 2)
+    assert is_reference(ref)
+    assert_receive {^ref, 3}
+  end
+
+  test "async nif awaiting a future" do
+    ref = RustlerTest.async_sleep(100)
+    refute_receive {^ref, _}, 50
+    assert_receive {^ref, {:ok, 100}}, 1000
+  end
+
+  test "concurrent async nifs" do
+    refs = Enum.map(1..20, &RustlerTest.async_sleep(&1 * 5))
+
+    for {ref, n} <- Enum.zip(refs, 1..20) do
+      expected = n * 5
+      assert_receive {^ref, {:ok, ^expected}}, 1000
+    end
+  end
+
+  test "async nif panicking" do
+    ref = RustlerTest.async_panic()
+    assert_receive {^ref, {:error, :nif_panicked}}
+  end
+end
@@ -17,6 +17,16 @@ defmodule RustlerTest.AtomTest do
     assert RustlerTest.binary_to_existing_atom("test_atom_nonexisting") != nil
   end
 
+  test "atom text read into a buffer" do
+    assert RustlerTest.atom_read_into(:test_atom, 64) == {9, "test_atom"}
+    assert RustlerTest.atom_read_into(:test_atom, 4) == {9, "test"}
+    assert RustlerTest.atom_read_into(:test_atom, 0) == {9, ""}
+    assert RustlerTest.atom_read_into(:erlang.list_to_atom([197]), 8) == {1, <<197>>}
+
+    long = String.duplicate("a", 255) |> String.to_atom()
+    assert RustlerTest.atom_read_into(long, 256) == {255, Atom.to_string(long)}
+  end
+
   test "atom to string for non-atom should raise" do
     assert catch_error(RustlerTest.atom_to_string("already a string")) == :badarg
   end
@@ -24,6 +34,9 @@ defmodule RustlerTest.AtomTest do
   test "atom equals ok" do
     assert RustlerTest.atom_equals_ok(:ok)
     refute RustlerTest.atom_equals_ok(:fish)
-    assert catch_error(RustlerTest.atom_equals_ok("ok")) == :badarg
+
+    assert_raise ArgumentError, ~r"argument 1 of atom_equals_ok/1", fn ->
+      RustlerTest.atom_equals_ok("ok")
+    end
   end
 end
@@ -0,0 +1,7 @@
+defmodule RustlerTest.BackendTest do
+  use ExUnit.Case, async: true
+
+  test "terms built with the NIF backend" do
+    assert RustlerTest.backend_build(3) == {"ok", [1, -2], 1.5, nil, %{"count" => 3}}
+  end
+end
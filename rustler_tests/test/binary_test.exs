@@ -6,6 +6,17 @@ defmodule RustlerTest.BinaryTest do
     assert_raise ErlangError, fn -> RustlerTest.make_shorter_subbinary("t") end
   end
 
+  test "subbinary realization" do
+    parent = :binary.copy("x", 4096)
+    {realized, offset, parent_size} = RustlerTest.realize_subbinary(parent, 10, 3)
+
+    assert realized == "xxx"
+    assert :binary.referenced_byte_size(realized) == 3
+    assert offset == 10
+    assert parent_size == 4096
+    assert_raise ArgumentError, fn -> RustlerTest.realize_subbinary(parent, 4095, 2) end
+  end
+
   test "parse integer from binary" do
     assert RustlerTest.parse_integer("12") == 12
     assert RustlerTest.parse_integer("-254") == -254
@@ -17,10 +28,51 @@ defmodule RustlerTest.BinaryTest do
     assert RustlerTest.binary_new() == <<1, 2, 3, 4>>
   end
 
+  test "new binary creation" do
+    assert RustlerTest.new_binary_new(4) == <<0, 1, 2, 3>>
+    assert RustlerTest.new_binary_new(0) == ""
+
+    large = RustlerTest.new_binary_new(100_000)
+    assert byte_size(large) == 100_000
+    assert :binary.at(large, 99_999) == rem(99_999, 256)
+  end
+
+  test "binary from bytes" do
+    for size <- [0, 1, 63, 64, 65, 1000], force_refc <- [false, true] do
+      {binary, refc} = refc_binary(fn -> RustlerTest.binary_from_bytes(size, force_refc) end)
+      assert byte_size(binary) == size
+      assert binary == :binary.list_to_bin(Enum.map(Enum.take(0..size, size), &rem(&1, 256)))
+
+      # The VM may copy small refc binaries onto the heap, so only the heap path is checked
+      # for them.
+      if size > 64 or not force_refc do
+        assert refc == size > 64
+      end
+    end
+  end
+
+  # Calls `fun` and returns its binary, and whether it was allocated as a reference-counted
+  # binary instead of on the process heap. Only refc binaries are listed by
+  # `Process.info(pid, :binary)`.
+  defp refc_binary(fun) do
+    {:binary, before} = Process.info(self(), :binary)
+    binary = fun.()
+    {:binary, after_call} = Process.info(self(), :binary)
+
+    refc = Enum.any?(after_call -- before, fn {_id, size, _refs} -> size == byte_size(binary) end)
+
+    {binary, refc}
+  end
+
   test "owned binary creation" do
     assert RustlerTest.owned_binary_new() == <<1, 2, 3, 4>>
   end
 
+  test "fallible owned binary allocation" do
+    assert RustlerTest.owned_binary_try_new(3) == <<0, 0, 0>>
+    assert RustlerTest.alloc_error(3) == {:error, :insufficient_memory}
+  end
+
   test "unowned binary to owned" do
     assert RustlerTest.unowned_to_owned("test") == <<1, "est">>
     assert RustlerTest.unowned_to_owned("whatisgoingon") == <<1, "hatisgoingon">>
@@ -42,4 +94,136 @@ defmodule RustlerTest.BinaryTest do
   test "decode iolist as binary" do
     assert RustlerTest.decode_iolist(["hi", " ", "there"]) == ["hi", " ", "there"]
   end
+
+  test "decoding into an owned binary" do
+    assert RustlerTest.decode_into_owned("abcd") == "abcd"
+    assert RustlerTest.decode_into_owned("a longer binary") == "a longer binary"
+    assert RustlerTest.decode_into_owned(["a", ["b"]]) == "ab"
+    assert_raise ArgumentError, fn -> RustlerTest.decode_into_owned(:atom) end
+  end
+
+  test "joining binaries in a scratch buffer" do
+    assert RustlerTest.scratch_join(["a", "b", "c"], ", ") == "a, b, c"
+    assert RustlerTest.scratch_join(["x"], ", ") == "x"
+    assert RustlerTest.scratch_join([], ", ") == ""
+
+    large = :binary.copy("x", 2_000_000)
+    assert RustlerTest.scratch_join([large, large], "") == large <> large
+  end
+
+  test "bitstring decoding" do
+    assert RustlerTest.bitstring_bits(<<0b101::3>>) == [true, false, true]
+    assert RustlerTest.bitstring_bits(<<0x80, 1::1>>) ==
+             [true | List.duplicate(false, 7)] ++ [true]
+    assert RustlerTest.bitstring_bits(<<>>) == []
+    assert length(RustlerTest.bitstring_bits("ab")) == 16
+    assert_raise ArgumentError, fn -> RustlerTest.bitstring_bits(:atom) end
+  end
+
+  test "bitstring slicing" do
+    assert RustlerTest.bitstring_slice(<<0b10110011, 0b1::1>>, 2, 7) == <<0b1100111::7>>
+    assert RustlerTest.bitstring_slice(<<1, 2, 3>>, 8, 16) == <<2, 3>>
+    assert RustlerTest.bitstring_slice(<<1, 2, 3>>, 3, 0) == <<>>
+
+    bits = <<0::5, "hello", 1::3>>
+    assert RustlerTest.bitstring_slice(bits, 5, 40) == "hello"
+    assert_raise ArgumentError, fn -> RustlerTest.bitstring_slice(<<1::3>>, 2, 2) end
+  end
+
+  @tag nif_version: "2.13"
+  test "iovec inspection" do
+    large = :binary.copy("a", 1000)
+    assert RustlerTest.iovec_segments([large, large]) == {2000, [1000, 1000]}
+    assert RustlerTest.iovec_segments([]) == {0, []}
+    assert {5, _} = RustlerTest.iovec_segments(["he", "llo"])
+
+    assert RustlerTest.iovec_concat(["he", "llo", large]) == "hello" <> large
+    assert RustlerTest.iovec_concat(:erlang.iolist_to_iovec(["a", ["b", ?c]])) == "abc"
+    assert_raise ArgumentError, fn -> RustlerTest.iovec_concat(["a", ["b"]]) end
+    assert_raise ArgumentError, fn -> RustlerTest.iovec_concat(:atom) end
+  end
+
+  test "secure compare" do
+    assert RustlerTest.secure_compare("secret", "secret")
+    refute RustlerTest.secure_compare("secret", "secreT")
+    refute RustlerTest.secure_compare("secret", "secrets")
+    assert RustlerTest.secure_compare("", "")
+    assert_raise ArgumentError, fn -> RustlerTest.secure_compare("a", :a) end
+  end
+
+  test "gzip compression" do
+    data = String.duplicate("compressible ", 1000)
+    compressed = RustlerTest.compress_binary(:gzip, data)
+    assert byte_size(compressed) < byte_size(data)
+    assert :zlib.gunzip(compressed) == data
+    assert RustlerTest.decompress_binary(:gzip, compressed, byte_size(data)) == data
+    assert RustlerTest.decompress_binary(:gzip, :zlib.gzip("hello"), 5) == "hello"
+    assert RustlerTest.decompress_binary(:gzip, RustlerTest.compress_binary(:gzip, ""), 0) == ""
+  end
+
+  test "zstd compression" do
+    data = :crypto.strong_rand_bytes(100) |> String.duplicate(100)
+    compressed = RustlerTest.compress_binary(:zstd, data)
+    assert byte_size(compressed) < byte_size(data)
+    assert RustlerTest.decompress_binary(:zstd, compressed, byte_size(data)) == data
+  end
+
+  test "decompression limits and errors" do
+    data = String.duplicate("a", 10_000)
+
+    for format <- [:gzip, :zstd] do
+      compressed = RustlerTest.compress_binary(format, data)
+      assert {:error, :too_large} == RustlerTest.decompress_binary(format, compressed, 9_999)
+
+      truncated = binary_part(compressed, 0, div(byte_size(compressed), 2))
+      assert {:error, :invalid_data} == RustlerTest.decompress_binary(format, truncated, 10_000)
+      assert {:error, :invalid_data} == RustlerTest.decompress_binary(format, "garbage", 10_000)
+    end
+  end
+
+  test "lists of binaries are iodata" do
+    chunks = ["hello", "", " world"]
+    owned = RustlerTest.owned_binaries(chunks)
+    assert owned == chunks
+    assert Enum.all?(owned, &is_binary/1)
+    assert IO.iodata_to_binary(owned) == "hello world"
+  end
+
+  test "nested iolists" do
+    {frames, size} = RustlerTest.frame_iolist(["ab", "", "cde"])
+    assert [[<<0, 2>>, "ab", ?\n], [<<0, 0>>, "", ?\n], [<<0, 3>>, "cde", ?\n]] == frames
+    assert size == :erlang.iolist_size(frames)
+    assert IO.iodata_to_binary(frames) == <<0, 2, "ab\n", 0, 0, "\n", 0, 3, "cde\n">>
+    assert {[], 0} == RustlerTest.frame_iolist([])
+  end
+
+  test "binary reader" do
+    data = <<1, 2, 4, 3, 2, 1, 0xAC, 0x02, 5, 3, "abc", "xyz">>
+
+    assert {258, 0x01020304, 300, -3, "abc", "xyz", 13} == RustlerTest.binary_reader_parse(data)
+
+    # Varints are at most 64 bits.
+    overflow = <<1, 2, 4, 3, 2, 1, :binary.copy(<<0xFF>>, 10)::binary, 0x01>>
+    assert_raise ArgumentError, fn -> RustlerTest.binary_reader_parse(overflow) end
+    assert_raise ArgumentError, fn -> RustlerTest.binary_reader_parse(<<1, 2, 4, 3>>) end
+  end
+
+  test "binary reader keeps its position when a read fails" do
+    assert {:ok, "abc"} == RustlerTest.binary_reader_prefixed(<<0, 3, "abcd">>)
+    assert {:error, 0} == RustlerTest.binary_reader_prefixed(<<0, 5, "abc">>)
+    assert {:error, 0} == RustlerTest.binary_reader_prefixed(<<0>>)
+  end
+
+  test "binary writer" do
+    frame = RustlerTest.binary_writer_frame(258, 0x01020304, 300, -3, "abc")
+
+    assert <<1, 2, 4, 3, 2, 1, 0xAC, 0x02, 5, 3, "abc", 1.5::float-big>> == frame
+    assert {258, 0x01020304, 300, -3, "abc", <<1.5::float-big>>, 13} ==
+             RustlerTest.binary_reader_parse(frame)
+
+    max = 0xFFFFFFFFFFFFFFFF
+    min = -0x8000000000000000
+    frame = RustlerTest.binary_writer_frame(0, 0, max, min, "")
+    assert {0, 0, ^max, ^min, "", _, _} = RustlerTest.binary_reader_parse(frame)
+  end
 end
@@ -0,0 +1,49 @@
+defmodule RustlerTest.BroadcastTest do
+  use ExUnit.Case, async: true
+  import RustlerTest.Helper
+
+  test "subscribe and unsubscribe" do
+    set = RustlerTest.pid_set_new()
+    assert RustlerTest.pid_set_size(set) == 0
+
+    assert RustlerTest.pid_set_subscribe(set, self())
+    refute RustlerTest.pid_set_subscribe(set, self())
+    assert RustlerTest.pid_set_size(set) == 1
+
+    assert RustlerTest.pid_set_unsubscribe(set, self())
+    refute RustlerTest.pid_set_unsubscribe(set, self())
+    assert RustlerTest.pid_set_size(set) == 0
+  end
+
+  test "broadcast sends to all members" do
+    set = RustlerTest.pid_set_new()
+    parent = self()
+
+    pids =
+      for i <- 1..3 do
+        spawn_link(fn ->
+          receive do
+            message -> send(parent, {i, message})
+          end
+        end)
+      end
+
+    for pid <- pids, do: assert(RustlerTest.pid_set_subscribe(set, pid))
+    assert RustlerTest.pid_set_broadcast(set, {:event, 42}) == 3
+
+    for i <- 1..3, do: assert_receive({^i, {:event, 42}})
+  end
+
+  test "dead processes are removed" do
+    set = RustlerTest.pid_set_new()
+    pid = spawn(fn -> Process.sleep(:infinity) end)
+    assert RustlerTest.pid_set_subscribe(set, pid)
+
+    ref = Process.monitor(pid)
+    Process.exit(pid, :kill)
+    assert_receive {:DOWN, ^ref, :process, ^pid, :killed}
+
+    assert wait_until(fn -> RustlerTest.pid_set_size(set) == 0 end)
+    refute RustlerTest.pid_set_subscribe(set, pid)
+  end
+end
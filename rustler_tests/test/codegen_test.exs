@@ -2,6 +2,22 @@ defmodule AddStruct do
   defstruct lhs: 0, rhs: 0
 end
 
+defmodule RenamedStruct do
+  defstruct type: nil, ref: 0
+end
+
+defmodule DefaultedStruct do
+  defstruct name: nil, version: 2
+end
+
+defmodule GenericWrapper do
+  defstruct value: nil
+end
+
+defmodule RustlerTest.ParseError do
+  defexception [:message, :line]
+end
+
 defmodule AddRecord do
   import Record
   defrecord :record, lhs: 1, rhs: 2
@@ -41,6 +57,12 @@ defmodule RustlerTest.CodegenTest do
       assert value == RustlerTest.map_echo(value)
     end
 
+    test "list transcoder" do
+      value = for i <- 1..100, do: %{lhs: i, rhs: -i}
+      assert value == RustlerTest.map_list_echo(value)
+      assert [] == RustlerTest.map_list_echo([])
+    end
+
     test "with invalid map" do
       value = %{lhs: "invalid", rhs: 2}
 
@@ -48,6 +70,16 @@ defmodule RustlerTest.CodegenTest do
         assert value == RustlerTest.map_echo(value)
       end
     end
+
+    test "subset decoder" do
+      context = Map.new(1..1000, &{&1, &1})
+      assert {1, nil, 1} == RustlerTest.map_subset_decode(Map.put(context, :lhs, 1))
+
+      assert {1, "joe", 2} ==
+               RustlerTest.map_subset_decode(Map.merge(context, %{lhs: 1, nickname: "joe"}))
+
+      assert_raise ErlangError, fn -> RustlerTest.map_subset_decode(context) end
+    end
   end
 
   describe "struct" do
@@ -57,6 +89,27 @@ defmodule RustlerTest.CodegenTest do
       assert :invalid_struct == RustlerTest.struct_echo(DateTime.utc_now())
     end
 
+    test "list transcoder" do
+      value = for i <- 1..100, do: %AddStruct{lhs: i, rhs: -i}
+      assert value == RustlerTest.struct_list_echo(value)
+    end
+
+    test "exception" do
+      value = %RustlerTest.ParseError{message: "oops", line: 3}
+      assert value == RustlerTest.exception_struct_echo(value)
+      assert 0 == RustlerTest.exception_struct_raise(0)
+
+      assert_raise RustlerTest.ParseError, "unexpected token", fn ->
+        RustlerTest.exception_struct_raise(12)
+      end
+
+      try do
+        RustlerTest.exception_struct_raise(12)
+      rescue
+        error in RustlerTest.ParseError -> assert 12 == error.line
+      end
+    end
+
     test "with invalid struct" do
       value = %AddStruct{lhs: "lhs", rhs: 123}
 
@@ -66,6 +119,80 @@ defmodule RustlerTest.CodegenTest do
                      RustlerTest.struct_echo(value)
                    end
     end
+
+    test "decode trace" do
+      assert [] == RustlerTest.struct_decode_trace(%AddStruct{lhs: 1, rhs: 2})
+
+      assert ["could not decode field lhs of AddStruct from Binary term"] ==
+               RustlerTest.struct_decode_trace(%AddStruct{lhs: "lhs", rhs: 2})
+
+      assert ["could not decode field __struct__ of AddStruct from Map term"] ==
+               RustlerTest.struct_decode_trace(%{lhs: 1, rhs: 2})
+    end
+  end
+
+  describe "keyed map" do
+    test "transcoder" do
+      value = %{1 => %{id: 1, name: "one"}, 2 => %{id: 2, name: "two"}}
+      assert value == RustlerTest.keyed_map_echo(value)
+      assert %{} == RustlerTest.keyed_map_echo(%{})
+    end
+
+    test "with invalid value" do
+      assert_raise ArgumentError, fn ->
+        RustlerTest.keyed_map_echo(%{1 => %{id: 1}})
+      end
+    end
+  end
+
+  describe "encoding profiles" do
+    test "erlang profile transcoder" do
+      value = %{name: 'joe', nickname: :undefined}
+      assert value == RustlerTest.erlang_profile_map_echo(value)
+
+      value = %{name: 'joe', nickname: 'jo'}
+      assert value == RustlerTest.erlang_profile_map_echo(value)
+    end
+
+    test "erlang profile rejects nil" do
+      assert_raise ErlangError, fn ->
+        RustlerTest.erlang_profile_map_echo(%{name: 'joe', nickname: nil})
+      end
+    end
+
+    test "binary keys" do
+      assert %{"lhs" => 1, "rhs" => 2} == RustlerTest.binary_keys_map_encode()
+    end
+  end
+
+  describe "summary" do
+    test "truncates long fields" do
+      name = String.duplicate("é", 40)
+      summary = RustlerTest.summary_map_summary(%{name: name, values: Enum.to_list(1..20)})
+
+      assert summary.name == String.duplicate("é", 32) <> "..."
+      assert summary.values == Enum.to_list(1..16) ++ ["... 4 more"]
+    end
+
+    test "keeps small terms" do
+      value = %{name: "joe", values: [1, 2]}
+      assert value == RustlerTest.summary_map_summary(value)
+      assert {:ok, "joe", [1]} == RustlerTest.summarize_term({:ok, "joe", [1]})
+    end
+
+    test "replaces binaries that are not strings" do
+      binary = :binary.copy(<<255>>, 100)
+      assert "<<... 100 bytes>>" == RustlerTest.summarize_term(binary)
+    end
+
+    test "truncates maps and nested terms" do
+      map = Map.new(1..20, &{&1, &1})
+      summary = RustlerTest.summarize_term(map)
+      assert map_size(summary) == 17
+      assert summary["..."] == "4 more"
+
+      assert [[[["..."]]]] == RustlerTest.summarize_term([[[[[1]]]]])
+    end
   end
 
   describe "record" do
@@ -98,6 +225,74 @@ defmodule RustlerTest.CodegenTest do
     assert :invalid_variant == RustlerTest.unit_enum_echo(:somethingelse)
   end
 
+  test "renamed fields and variants" do
+    map = %{userId: 1, displayName: "Ada", "e-mail": "ada@example.com"}
+    assert map == RustlerTest.renamed_map_echo(map)
+    assert_raise ErlangError, fn -> RustlerTest.renamed_map_echo(%{map | userId: "1"}) end
+
+    assert_raise ArgumentError, fn ->
+      RustlerTest.renamed_map_echo(%{user_id: 1, display_name: "Ada", email: "ada@example.com"})
+    end
+
+    struct = %RenamedStruct{type: "admin", ref: 2}
+    assert struct == RustlerTest.renamed_struct_echo(struct)
+
+    assert :FOO_BAR == RustlerTest.renamed_unit_enum_echo(:FOO_BAR)
+    assert :baz == RustlerTest.renamed_unit_enum_echo(:baz)
+    assert :invalid_variant == RustlerTest.renamed_unit_enum_echo(:foo_bar)
+  end
+
+  test "defaulted fields" do
+    assert %{name: "pool", tags: [], retries: 3} ==
+             RustlerTest.defaulted_map_echo(%{name: "pool"})
+
+    map = %{name: "pool", tags: ["a"], retries: 5}
+    assert map == RustlerTest.defaulted_map_echo(map)
+    assert_raise ErlangError, fn -> RustlerTest.defaulted_map_echo(%{map | tags: nil}) end
+    assert_raise ArgumentError, fn -> RustlerTest.defaulted_map_echo(%{tags: []}) end
+
+    struct = %DefaultedStruct{name: "pool"}
+    assert struct == RustlerTest.defaulted_struct_echo(struct)
+
+    assert %DefaultedStruct{name: "pool", version: 1} ==
+             RustlerTest.defaulted_struct_echo(Map.delete(struct, :version))
+  end
+
+  test "skipped fields" do
+    assert {%{name: "doc"}, true, 1} ==
+             RustlerTest.skipped_map_decode(%{name: "doc", cache: "ignored", generation: 5})
+
+    assert {3, 4} == RustlerTest.skipped_tuple_echo({3, 4})
+    assert_raise ArgumentError, fn -> RustlerTest.skipped_tuple_echo({3, [], 4}) end
+  end
+
+  test "generic types" do
+    assert %{first: "b", second: "a"} == RustlerTest.generic_pair_swap(%{first: "a", second: "b"})
+    assert_raise ErlangError, fn -> RustlerTest.generic_pair_swap(%{first: 1, second: "b"}) end
+
+    wrapper = %GenericWrapper{value: [1, 2]}
+    assert wrapper == RustlerTest.generic_wrapper_echo(wrapper)
+
+    assert {1, nil} == RustlerTest.generic_tuple_echo({1, nil})
+    assert {1, "a"} == RustlerTest.generic_tuple_echo({1, "a"})
+
+    assert {"xs", 2} == RustlerTest.generic_borrowed_len(%{name: "xs", values: [1.0, 2.0]})
+  end
+
+  test "borrowed binary fields" do
+    assert {"head", 3} == RustlerTest.borrowed_frame_split({"head", <<1, 2, 3>>})
+
+    frame = %{header: "head", payload: <<1, 2, 3>>}
+    assert {4, <<1, 2, 3>>} == RustlerTest.borrowed_frame_map_split(frame)
+
+    assert_raise ErlangError, fn -> RustlerTest.borrowed_frame_split({"head", [1, 2, 3]}) end
+    assert_raise ArgumentError, fn -> RustlerTest.borrowed_frame_split({"head"}) end
+
+    assert_raise ErlangError, fn ->
+      RustlerTest.borrowed_frame_map_split(%{header: 1, payload: ""})
+    end
+  end
+
   test "untagged enum transcoder" do
     assert 123 == RustlerTest.untagged_enum_echo(123)
     assert "Hello" == RustlerTest.untagged_enum_echo("Hello")
@@ -110,6 +305,39 @@ defmodule RustlerTest.CodegenTest do
     assert :invalid_variant == RustlerTest.untagged_enum_echo([1, 2, 3, 4])
   end
 
+  test "command dispatch" do
+    assert 3 == RustlerTest.command_call({1, 2})
+    assert "hello" == RustlerTest.command_call("hello")
+    assert :empty == RustlerTest.command_call("")
+    assert_raise ArgumentError, fn -> RustlerTest.command_call(:unknown) end
+  end
+
+  test "tagged enum transcoder" do
+    assert :empty == RustlerTest.tagged_enum_echo(:empty)
+    assert :none == RustlerTest.tagged_enum_echo(:none)
+    assert {:circle, 1.5} == RustlerTest.tagged_enum_echo({:circle, 1.5})
+    assert {:pair, 1, "a"} == RustlerTest.tagged_enum_echo({:pair, 1, "a"})
+
+    rect = {:rect, %{width: 2.0, height: 3.0, label: "box"}}
+    assert rect == RustlerTest.tagged_enum_echo(rect)
+
+    assert {:rect, %{width: 2.0, height: 3.0, label: ""}} ==
+             RustlerTest.tagged_enum_echo({:rect, %{width: 2.0, height: 3.0}})
+
+    assert :invalid_variant == RustlerTest.tagged_enum_echo(:nothing)
+    assert :invalid_variant == RustlerTest.tagged_enum_echo({:pair, 1})
+    assert :invalid_variant == RustlerTest.tagged_enum_echo({:rect, [width: 2.0]})
+    assert_raise ArgumentError, fn -> RustlerTest.tagged_enum_echo({:circle, "a"}) end
+    assert_raise ArgumentError, fn -> RustlerTest.tagged_enum_echo({:rect, %{width: 2.0}}) end
+  end
+
+  test "tagged enum command dispatch" do
+    assert 0 == RustlerTest.counter_call(:reset)
+    assert 3 == RustlerTest.counter_call({:add, 1, 2})
+    assert 6 == RustlerTest.counter_call({:scale, %{value: 2, factor: 3}})
+    assert 7 == RustlerTest.counter_call({:sub, %{value: 10, by: 3}})
+  end
+
   test "untagged enum with truthy" do
     assert %AddStruct{lhs: 45, rhs: 123} =
              RustlerTest.untagged_enum_with_truthy(%AddStruct{lhs: 45, rhs: 123})
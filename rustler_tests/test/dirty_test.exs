@@ -8,4 +8,86 @@ defmodule RustlerTest.DirtyTest do
   test "dirty cpu" do
     RustlerTest.dirty_cpu()
   end
+
+  test "cpu time accounting" do
+    assert RustlerTest.nif_stats("dirty_cpu") == nil
+
+    RustlerTest.dirty_cpu_timed(1_000_000)
+    RustlerTest.dirty_cpu_timed(1_000_000)
+
+    stats = RustlerTest.nif_stats("dirty_cpu_timed")
+    assert stats.calls >= 2
+    assert stats.cpu_time_us >= stats.max_cpu_time_us
+    assert stats.wall_time_us > 0
+  end
+
+  test "parallel map" do
+    items = Enum.to_list(-500..500)
+    squares = Enum.map(items, &(&1 * &1))
+
+    assert RustlerTest.parallel_squares(items, 0) == squares
+    assert RustlerTest.parallel_squares(items, 1) == squares
+    assert RustlerTest.parallel_squares(items, 3) == squares
+    assert RustlerTest.parallel_squares([], 2) == []
+  end
+
+  test "parallel map progress" do
+    assert RustlerTest.parallel_until([1, 2, 3], 10) == [1, 2, 3]
+    assert {:error, 5} == RustlerTest.parallel_until(Enum.to_list(1..100), 5)
+    assert_raise ErlangError, fn -> RustlerTest.parallel_until([1, -1, 2], 10) end
+  end
+
+  test "thread hints" do
+    cores = :erlang.system_info(:logical_processors_available)
+    schedulers = :erlang.system_info(:schedulers)
+    unbound = Tuple.duplicate(:unbound, schedulers)
+
+    assert {max(cores - schedulers, 1), nil} == RustlerTest.thread_hints(unbound, nil)
+    assert {1, [0]} == RustlerTest.thread_hints({:unbound}, [0])
+    assert {1, [0]} == RustlerTest.thread_hints({0}, [0])
+
+    if cores > 1 do
+      assert {1, [1]} == RustlerTest.thread_hints({0}, [0, 1])
+    end
+
+    assert_raise ArgumentError, fn -> RustlerTest.thread_hints({:bound}, nil) end
+  end
+
+  test "parallel map with thread hints" do
+    items = Enum.to_list(1..100)
+    bindings = :erlang.system_info(:scheduler_bindings)
+
+    assert RustlerTest.parallel_squares_hinted(items, bindings) == Enum.map(items, &(&1 * &1))
+  end
+
+  test "yielding computations" do
+    assert {0, 1} == RustlerTest.yielding_sum(0, 10)
+    assert {45, 4} == RustlerTest.yielding_sum(10, 3)
+
+    n = 50_000_000
+    assert {div(n * (n - 1), 2), 5000} == RustlerTest.yielding_sum(n, 10_000)
+  end
+
+  test "resumable computations" do
+    assert 111 == RustlerTest.yielding_collatz(27)
+    assert 0 == RustlerTest.yielding_collatz(1)
+    assert_raise ArgumentError, fn -> RustlerTest.yielding_collatz(0) end
+  end
+
+  test "consume timeslice" do
+    assert RustlerTest.env_consume_timeslice(100)
+    assert RustlerTest.env_consume_timeslice(1_000)
+    assert is_boolean(RustlerTest.env_consume_timeslice(1))
+  end
+
+  test "budgeted iteration" do
+    assert {6, []} == RustlerTest.budgeted_sum([1, 2, 3], 0)
+
+    items = Enum.to_list(1..1_000)
+    {sum, rest} = RustlerTest.budgeted_sum(items, 50)
+    assert rest != []
+    assert length(rest) < 1_000
+    assert rest == Enum.drop(items, 1_000 - length(rest))
+    assert sum == Enum.sum(Enum.take(items, 1_000 - length(rest)))
+  end
 end
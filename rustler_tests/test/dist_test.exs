@@ -0,0 +1,52 @@
+defmodule RustlerTest.DistTest do
+  use ExUnit.Case
+
+  # The client only speaks the handshake of OTP 23 and later.
+  if :erlang.system_info(:otp_release) < '23' do
+    @moduletag :skip
+  end
+
+  setup_all do
+    unless Node.alive?() do
+      start_epmd()
+      {:ok, _} = Node.start(:"rustler_test@127.0.0.1")
+    end
+
+    :ok
+  end
+
+  defp start_epmd do
+    epmd =
+      System.find_executable("epmd") ||
+        List.first(Path.wildcard(Path.join([to_string(:code.root_dir()), "erts-*/bin/epmd*"])))
+
+    System.cmd(epmd, ["-daemon"])
+  end
+
+  setup do
+    name = "rustler_dist_echo_#{System.unique_integer([:positive])}"
+    Process.register(spawn_link(&echo/0), String.to_atom(name))
+    {:ok, name: name, remote: to_string(node()), cookie: to_string(Node.get_cookie())}
+  end
+
+  defp echo do
+    receive do
+      {from, request} ->
+        send(from, {:echo, request})
+        echo()
+    end
+  end
+
+  test "messages are sent to registered processes and replied to", ctx do
+    assert RustlerTest.dist_call(ctx.remote, ctx.cookie, ctx.name, false, 3) ==
+             {:echo, {:hello, 3}}
+
+    assert RustlerTest.dist_call(ctx.remote, ctx.cookie, ctx.name, true, 7) ==
+             {:echo, {:goodbye, 7}}
+  end
+
+  test "a wrong cookie fails the handshake", ctx do
+    assert {:error, "handshake failed: " <> _} =
+             RustlerTest.dist_call(ctx.remote, "wrong", ctx.name, false, 1)
+  end
+end
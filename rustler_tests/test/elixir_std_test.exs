@@ -0,0 +1,60 @@
+defmodule RustlerTest.ElixirStdTest do
+  use ExUnit.Case, async: true
+  import RustlerTest.Helper
+
+  test "map sets" do
+    set = MapSet.new([1, 2, 3])
+    assert set == RustlerTest.map_set_echo(set)
+    assert MapSet.new() == RustlerTest.map_set_echo(MapSet.new())
+
+    # Older layouts map the elements to other values.
+    old = %{__struct__: MapSet, map: %{1 => true, 2 => true}, version: 1}
+    assert MapSet.new([1, 2]) == RustlerTest.map_set_echo(old)
+
+    assert_raise ArgumentError, fn -> RustlerTest.map_set_echo(%{map: %{}}) end
+  end
+
+  test "rust sets as map sets" do
+    set = MapSet.new(["a", "b"])
+    assert set == RustlerTest.hash_set_echo(set)
+    assert MapSet.new() == RustlerTest.hash_set_echo(MapSet.new())
+
+    assert {MapSet.new([1, 2, 3]), [1, 2, 3]} ==
+             RustlerTest.btree_set_to_list(MapSet.new([3, 1, 2]))
+
+    assert_raise ArgumentError, fn -> RustlerTest.hash_set_echo(["a", "b"]) end
+    assert_raise ArgumentError, fn -> RustlerTest.btree_set_to_list(MapSet.new([:a])) end
+  end
+
+  test "ranges" do
+    assert {range(1, 5, 1), [1, 2, 3, 4, 5]} == RustlerTest.range_to_list(1..5)
+    assert {range(1, 10, 3), [1, 4, 7, 10]} == RustlerTest.range_to_list(range(1, 10, 3))
+    assert {_, []} = RustlerTest.range_to_list(range(5, 1, 1))
+
+    # Before Elixir 1.12, ranges had no step and decreased when first > last.
+    old = %{__struct__: Range, first: 3, last: 1}
+    assert {range(3, 1, -1), [3, 2, 1]} == RustlerTest.range_to_list(old)
+  end
+
+  test "URIs" do
+    uri = URI.parse("https://user@example.com:8443/path?query=1#top")
+    echoed = RustlerTest.uri_echo(uri)
+    assert URI.to_string(echoed) == URI.to_string(uri)
+    assert echoed.port == 8443
+    assert echoed.userinfo == "user"
+
+    assert %URI{path: "relative"} = RustlerTest.uri_echo(URI.parse("relative"))
+    assert RustlerTest.uri_echo(%{__struct__: URI, host: "example.com"}).host == "example.com"
+  end
+
+  test "regex sources" do
+    {source, opts} = RustlerTest.regex_source(~r/ab+c/i)
+    assert source == "ab+c"
+    assert Regex.match?(Regex.compile!(source, opts), "xABBCx")
+
+    assert {"a.b", "su"} == RustlerTest.regex_source({"a.b", "su"})
+
+    old = %{__struct__: Regex, source: "x", opts: [:caseless, :unicode, :ucp]}
+    assert {"x", "iu"} == RustlerTest.regex_source(old)
+  end
+end
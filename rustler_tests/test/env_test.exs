@@ -57,4 +57,118 @@ defmodule RustlerTest.EnvTest do
                ]
     end
   end
+
+  test "streaming replies" do
+    ref = make_ref()
+    assert :ok == RustlerTest.reply_chunks(ref, 3)
+
+    for i <- 0..2 do
+      assert_received {:chunk, ^ref, ^i}
+    end
+
+    assert_received {:done, ^ref}
+  end
+
+  test "interned strings" do
+    [a, b, c] = RustlerTest.intern_strs(["key", "value", "key"])
+
+    assert [a, b, c] == ["key", "value", "key"]
+    assert :erts_debug.same(a, c)
+    refute :erts_debug.same(a, b)
+  end
+
+  test "deadlines" do
+    assert nil == RustlerTest.deadline_remaining(:infinity)
+    assert 0 == RustlerTest.deadline_remaining(System.monotonic_time(:millisecond) - 10)
+
+    remaining = RustlerTest.deadline_remaining(System.monotonic_time(:millisecond) + 10_000)
+    assert remaining > 9_000 and remaining <= 10_000
+
+    assert 5 == RustlerTest.deadline_steps(:infinity, 5)
+    assert 5 == RustlerTest.deadline_steps(System.monotonic_time(:millisecond) + 10_000, 5)
+
+    assert {:error, :deadline_exceeded} ==
+             RustlerTest.deadline_steps(System.monotonic_time(:millisecond) + 20, 1_000)
+
+    assert_raise ArgumentError, fn -> RustlerTest.deadline_steps(:never, 1) end
+  end
+
+  test "sending to a registered name" do
+    Process.register(self(), :rustler_test_whereis)
+    assert RustlerTest.whereis_send("rustler_test_whereis", :from_nif, false)
+    assert_receive :from_nif
+    assert RustlerTest.whereis_send("rustler_test_whereis", :from_thread, true)
+    assert_receive :from_thread
+    Process.unregister(:rustler_test_whereis)
+
+    refute RustlerTest.whereis_send("rustler_test_whereis", :lost, false)
+    missing = "rustler_test_missing_#{System.unique_integer([:positive])}"
+    refute RustlerTest.whereis_send(missing, :lost, false)
+  end
+
+  # Spawns `cat`.
+  @tag :unix
+  test "sending to a registered port" do
+    port = Port.open({:spawn, "cat"}, [:binary])
+    Process.register(port, :rustler_test_cat)
+
+    assert RustlerTest.whereis_port_command("rustler_test_cat", ["he", "llo"])
+    assert_receive {^port, {:data, "hello"}}
+    assert nil == RustlerTest.whereis_port_command("rustler_test_no_port", "hello")
+
+    Port.close(port)
+  end
+
+  test "messenger" do
+    assert [:ok, :ok] == RustlerTest.messenger_send(self(), 2, 3)
+
+    for i <- 0..2, thread <- 0..1 do
+      assert_receive {^i, ^thread}
+    end
+
+    pid = spawn(fn -> :ok end)
+    ref = Process.monitor(pid)
+    assert_receive {:DOWN, ^ref, :process, ^pid, _}
+    assert [:not_alive] == RustlerTest.messenger_send(pid, 1, 1)
+
+    assert :managed_thread == RustlerTest.messenger_send_from_nif(self())
+  end
+
+  test "trace context" do
+    trace_id = Base.decode16!("4BF92F3577B34DA6A3CE929D0E0E4736")
+    span_id = Base.decode16!("00F067AA0BA902B7")
+    context = %{trace_id: trace_id, span_id: span_id, trace_flags: 1}
+
+    assert context == RustlerTest.trace_context_extract(Map.put(context, :user_id, 7))
+
+    assert context ==
+             RustlerTest.trace_context_extract(%{
+               trace_id: "4bf92f3577b34da6a3ce929d0e0e4736",
+               span_id: "00f067aa0ba902b7",
+               trace_flags: 1
+             })
+
+    assert %{context | trace_flags: 0} ==
+             RustlerTest.trace_context_extract(%{trace_id: trace_id, span_id: span_id})
+
+    assert nil == RustlerTest.trace_context_extract(%{user_id: 7})
+    assert_raise ArgumentError, fn -> RustlerTest.trace_context_extract([]) end
+
+    assert_raise ArgumentError, fn ->
+      RustlerTest.trace_context_extract(%{trace_id: "short", span_id: span_id})
+    end
+
+    assert_raise ArgumentError, fn ->
+      RustlerTest.trace_context_extract(%{trace_id: <<0::128>>, span_id: span_id})
+    end
+
+    header = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+    assert header == RustlerTest.trace_context_traceparent(context)
+
+    assert :ok == RustlerTest.trace_context_send(self(), header, :done)
+    assert_receive {^context, :done}
+    assert_raise ArgumentError, fn ->
+      RustlerTest.trace_context_send(self(), "00-1-2-3", :done)
+    end
+  end
 end
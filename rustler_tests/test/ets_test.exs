@@ -0,0 +1,57 @@
+defmodule RustlerTest.EtsTest do
+  use ExUnit.Case, async: false
+  import ExUnit.CaptureLog
+
+  test "inserts rows through the helper process" do
+    start_supervised!(Rustler.Ets)
+    table = :ets.new(:ets_test, [:public])
+
+    assert {:ok, 2500} == RustlerTest.ets_export(table, 2500, 1000)
+    assert :ok == Rustler.Ets.sync()
+    assert :ets.info(table, :size) == 2500
+    assert :ets.lookup(table, 0) == [{0, 0}]
+    assert :ets.lookup(table, 2499) == [{2499, 4998}]
+  end
+
+  test "inserts into named tables" do
+    start_supervised!(Rustler.Ets)
+    :ets.new(:ets_test_named, [:named_table, :public])
+
+    assert {:ok, 10} == RustlerTest.ets_export(:ets_test_named, 10, 3)
+    assert :ok == Rustler.Ets.sync()
+    assert :ets.info(:ets_test_named, :size) == 10
+  end
+
+  test "inserts nothing for no rows" do
+    start_supervised!(Rustler.Ets)
+    table = :ets.new(:ets_test, [:public])
+
+    assert {:ok, 0} == RustlerTest.ets_export(table, 0, 1000)
+    assert :ok == Rustler.Ets.sync()
+    assert :ets.info(table, :size) == 0
+  end
+
+  test "logs rows that can't be inserted and keeps running" do
+    start_supervised!(Rustler.Ets)
+    protected = :ets.new(:ets_test, [:protected])
+    table = :ets.new(:ets_test, [:public])
+
+    log =
+      capture_log(fn ->
+        assert {:ok, 10} == RustlerTest.ets_export(protected, 10, 3)
+        assert :ok == Rustler.Ets.sync()
+      end)
+
+    assert log =~ "could not insert"
+    assert :ets.info(protected, :size) == 0
+
+    assert {:ok, 10} == RustlerTest.ets_export(table, 10, 3)
+    assert :ok == Rustler.Ets.sync()
+    assert :ets.info(table, :size) == 10
+  end
+
+  test "fails without the helper process" do
+    table = :ets.new(:ets_test, [:public])
+    assert {:error, :ets_helper_not_running} == RustlerTest.ets_export(table, 10, 1000)
+  end
+end
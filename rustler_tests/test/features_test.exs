@@ -0,0 +1,14 @@
+defmodule RustlerTest.FeaturesTest do
+  use ExUnit.Case, async: true
+
+  test "enabled features are listed" do
+    features = RustlerTest.__rustler_features__()
+
+    assert "default-feature" in features
+    refute "optional-feature" in features
+    assert "rustler/big_integer" in features
+    assert "rustler/derive" in features
+    assert "rustler/dist" in features
+    refute "rustler/tokio" in features
+  end
+end
@@ -0,0 +1,21 @@
+defmodule RustlerTest.FuzzTest do
+  use ExUnit.Case, async: true
+
+  @valid :erlang.term_to_binary(%{name: "pool", sizes: [1, 2, 3], ratio: 0.5})
+
+  test "decode outcomes" do
+    assert RustlerTest.fuzz_decode_config(@valid) == :decoded
+    assert RustlerTest.fuzz_decode_config(<<1, 2, 3>>) == :not_etf
+    assert RustlerTest.fuzz_decode_config(:erlang.term_to_binary([1])) == :rejected
+  end
+
+  test "decoding mutated terms never crashes" do
+    for _ <- 1..1000 do
+      position = :rand.uniform(byte_size(@valid)) - 1
+      <<prefix::binary-size(position), _, suffix::binary>> = @valid
+      mutated = prefix <> <<:rand.uniform(256) - 1>> <> suffix
+
+      assert RustlerTest.fuzz_decode_config(mutated) in [:decoded, :rejected, :not_etf]
+    end
+  end
+end
@@ -13,6 +13,14 @@ defmodule RustlerTest.ListTest do
     assert_raise ArgumentError, fn -> RustlerTest.sum_list([1, 4, :invalid, 2]) end
   end
 
+  test "list decoding into a reused buffer" do
+    assert 5050 == RustlerTest.sum_list_scratch(Enum.to_list(1..100))
+    assert 8 == RustlerTest.sum_list_scratch([1, 2, 1, 4])
+    assert 0 == RustlerTest.sum_list_scratch([])
+    assert_raise ArgumentError, fn -> RustlerTest.sum_list_scratch([1, :invalid]) end
+    assert 3 == RustlerTest.sum_list_scratch([1, 2])
+  end
+
   test "simple list construction" do
     assert RustlerTest.make_list() == [1, 2, 3]
   end
@@ -20,4 +28,67 @@ defmodule RustlerTest.ListTest do
   test "simple list construction with sum" do
     assert RustlerTest.sum_list(RustlerTest.make_list()) == 6
   end
+
+  test "chunked list construction" do
+    assert RustlerTest.make_chunked_list(3, 10) == [0, 1, 2]
+    assert RustlerTest.make_chunked_list(10, 3) == Enum.to_list(0..9)
+    assert RustlerTest.make_chunked_list(1_000_000, 1000) == Enum.to_list(0..999_999)
+  end
+
+  test "queues" do
+    for n <- 0..5 do
+      items = Enum.take(1..5, n)
+      reversed = RustlerTest.queue_reverse(:queue.from_list(items))
+      assert :queue.is_queue(reversed)
+      assert :queue.to_list(reversed) == Enum.reverse(items)
+      assert :queue.len(reversed) == n
+    end
+
+    queue = Enum.reduce(1..10, :queue.new(), &:queue.in/2)
+    queue = :queue.in_r(0, queue)
+    reversed = RustlerTest.queue_reverse(queue)
+    assert :queue.is_queue(reversed)
+    assert :queue.to_list(reversed) == Enum.to_list(10..0)
+
+    assert_raise ArgumentError, fn -> RustlerTest.queue_reverse({[1], [:a]}) end
+    assert_raise ArgumentError, fn -> RustlerTest.queue_reverse([1, 2]) end
+  end
+
+  test "arrays" do
+    for n <- [0, 1, 9, 10, 11, 100, 101, 1234] do
+      items = Enum.to_list(1..(n + 1)) |> Enum.take(n)
+      doubled = RustlerTest.array_double(:array.from_list(items))
+
+      assert :array.is_array(doubled)
+      assert :array.size(doubled) == n
+      assert :array.to_list(doubled) == Enum.map(items, &(&1 * 2))
+    end
+
+    sparse = :array.set(250, 1, :array.set(3, 2, :array.new(default: 0)))
+    doubled = RustlerTest.array_double(sparse)
+    assert :array.size(doubled) == 251
+    assert :array.get(3, doubled) == 4
+    assert :array.get(250, doubled) == 2
+    assert :array.get(4, doubled) == :undefined
+    assert :array.sparse_to_orddict(doubled) == [{3, 4}, {250, 2}]
+
+    doubled = RustlerTest.array_double(:array.set(15, 3, :array.set(20, 1, :array.new())))
+    assert :array.get(20, doubled) == 2
+
+    assert_raise ArgumentError, fn -> RustlerTest.array_double(:array.from_list([:a])) end
+    assert_raise ArgumentError, fn -> RustlerTest.array_double({:array, 1, 10}) end
+  end
+
+  test "keyword lists" do
+    assert [a: 1, b: "x", a: 2] == RustlerTest.keyword_list_echo(a: 1, b: "x", a: 2)
+    assert [verbose: true, timeout: 5] == RustlerTest.keyword_list_echo([:verbose, {:timeout, 5}])
+    assert [] == RustlerTest.keyword_list_echo([])
+
+    assert {1, 2} == RustlerTest.keyword_list_get([a: 1, b: 3, a: 2], :a)
+    assert {nil, 0} == RustlerTest.keyword_list_get([b: 3], :a)
+
+    assert_raise ArgumentError, fn -> RustlerTest.keyword_list_echo([{"a", 1}]) end
+    assert_raise ArgumentError, fn -> RustlerTest.keyword_list_echo([{:a, 1, 2}]) end
+    assert_raise ArgumentError, fn -> RustlerTest.keyword_list_get([a: "x"], :a) end
+  end
 end
@@ -0,0 +1,38 @@
+defmodule RustlerTest.LoadDataTest do
+  use ExUnit.Case, async: true
+
+  test "load data is computed at load time" do
+    assert RustlerTest.load_data_pool_size() == 4
+  end
+
+  test "keyword lists and maps" do
+    assert RustlerTest.load_data_get_u32([pool_size: 2], "pool_size") == 2
+    assert RustlerTest.load_data_get_u32(%{pool_size: 2}, "pool_size") == 2
+  end
+
+  test "errors name the key" do
+    assert RustlerTest.load_data_get_u32([], "pool_size") ==
+             {:error, "missing key :pool_size in load data"}
+
+    assert RustlerTest.load_data_get_u32([pool_size: -1], "pool_size") ==
+             {:error, "could not decode :pool_size in load data as u32, got: -1"}
+
+    assert {:error, "load data must be a keyword list or a map, got: " <> _} =
+             RustlerTest.load_data_get_u32([1, 2], "pool_size")
+  end
+
+  test "min_nif_version fails loading with a logged error on older VMs" do
+    start_supervised!(Rustler.Logger)
+
+    assert RustlerTest.load_data_check_nif_version(2, 12)
+
+    log =
+      ExUnit.CaptureLog.capture_log(fn ->
+        refute RustlerTest.load_data_check_nif_version(99, 0)
+        # Messages are logged asynchronously by the helper process.
+        :sys.get_state(Rustler.Logger)
+      end)
+
+    assert log =~ "NIF library requires NIF version 99.0, but the VM provides 2."
+  end
+end
@@ -32,4 +32,44 @@ defmodule RustlerTest.MapTest do
       RustlerTest.map_generic(%{1 => "hello", not_a_number: "world"})
     end)
   end
+
+  test "btree maps" do
+    map = %{"b" => 2, "a" => 1, "c" => 3}
+    assert {["a", "b", "c"], map} == RustlerTest.btree_map_echo(map)
+    assert {[], %{}} == RustlerTest.btree_map_echo(%{})
+    assert_raise ArgumentError, fn -> RustlerTest.btree_map_echo(%{a: 1}) end
+  end
+
+  test "index maps" do
+    map = %{3 => "c", 1 => "a", 2 => "b"}
+    assert {[1, 2, 3], map} == RustlerTest.index_map_echo(map)
+    assert_raise ArgumentError, fn -> RustlerTest.index_map_echo([{1, "a"}]) end
+  end
+
+  test "gb_trees" do
+    for n <- [0, 1, 2, 3, 10, 100] do
+      keys = Enum.take(1..100, n)
+      tree = :gb_trees.from_orddict(for i <- keys, do: {i, i})
+      doubled = RustlerTest.gb_tree_double(tree)
+
+      assert :gb_trees.size(doubled) == n
+      assert :gb_trees.to_list(doubled) == for(i <- keys, do: {i, 2 * i})
+      assert n == 0 or :gb_trees.lookup(n, doubled) == {:value, 2 * n}
+    end
+
+    tree = Enum.reduce([:c, :a, :b], :gb_trees.empty(), &:gb_trees.insert(&1, "x", &2))
+    assert [:a, :b, :c] == RustlerTest.gb_tree_keys(tree)
+
+    assert_raise ArgumentError, fn -> RustlerTest.gb_tree_keys({2, {:a, 1, nil, nil}}) end
+    assert_raise ArgumentError, fn -> RustlerTest.gb_tree_keys({1, {:a, nil, nil}}) end
+  end
+
+  test "gb_sets" do
+    set = :gb_sets.from_list(["b", "a", "c"])
+    echoed = RustlerTest.gb_set_echo(set)
+    assert :gb_sets.to_list(echoed) == ["a", "b", "c"]
+    assert :gb_sets.is_member("b", echoed)
+
+    assert_raise ArgumentError, fn -> RustlerTest.gb_set_echo(:gb_sets.from_list([1])) end
+  end
 end
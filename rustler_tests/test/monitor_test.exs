@@ -0,0 +1,39 @@
+defmodule RustlerTest.MonitorTest do
+  use ExUnit.Case, async: true
+  import RustlerTest.Helper
+
+  test "down callback of a monitored process" do
+    watcher = RustlerTest.watcher_new()
+    pid = spawn(fn -> receive do: (:exit -> :ok) end)
+
+    assert RustlerTest.watcher_watch(watcher, pid)
+    assert RustlerTest.watcher_watched(watcher) == [pid]
+    assert RustlerTest.watcher_downs(watcher) == []
+
+    send(pid, :exit)
+    assert wait_until(fn -> RustlerTest.watcher_downs(watcher) == [pid] end)
+    assert RustlerTest.watcher_watched(watcher) == []
+  end
+
+  test "demonitoring a process" do
+    watcher = RustlerTest.watcher_new()
+    pid = spawn(fn -> receive do: (:exit -> :ok) end)
+
+    assert RustlerTest.watcher_watch(watcher, pid)
+    assert RustlerTest.watcher_unwatch(watcher, pid)
+    refute RustlerTest.watcher_unwatch(watcher, pid)
+
+    send(pid, :exit)
+    :timer.sleep(50)
+    assert RustlerTest.watcher_downs(watcher) == []
+  end
+
+  test "monitoring a dead process" do
+    watcher = RustlerTest.watcher_new()
+    pid = spawn(fn -> :ok end)
+    ref = Process.monitor(pid)
+    assert_receive {:DOWN, ^ref, :process, ^pid, _}
+
+    refute RustlerTest.watcher_watch(watcher, pid)
+  end
+end
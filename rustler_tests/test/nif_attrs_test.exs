@@ -4,4 +4,48 @@ defmodule NifAttrsTest do
   test "can rename a NIF with an attribute" do
     assert RustlerTest.nif_attrs_can_rename()
   end
+
+  test "deprecated NIFs log a warning on their first calls" do
+    start_supervised!(Rustler.Logger)
+
+    log =
+      ExUnit.CaptureLog.capture_log(fn ->
+        for _ <- 1..5, do: assert(RustlerTest.deprecated_add(1, 2) == 3)
+        # Messages are logged asynchronously by the helper process.
+        :sys.get_state(Rustler.Logger)
+      end)
+
+    assert log =~ "NIF deprecated_add/2 is deprecated: use add_i32/2 instead"
+    assert length(String.split(log, "is deprecated")) - 1 == 3
+  end
+
+  test "can return :ok for unit" do
+    assert RustlerTest.unit_ok_check(1) == :ok
+    assert_raise ArgumentError, fn -> RustlerTest.unit_ok_check(-1) end
+  end
+
+  test "native faults raise with crash_guard" do
+    assert {:native_crash, :sigsegv, 8, "crash_guard_read/1"} ==
+             catch_error(RustlerTest.crash_guard_read(8))
+
+    # The handlers stay usable after a fault.
+    assert {:native_crash, :sigsegv, 16, "crash_guard_read/1"} ==
+             catch_error(RustlerTest.crash_guard_read(16))
+  end
+
+  test "associated functions can be NIFs" do
+    assert RustlerTest.counter_double(21) == 42
+    assert RustlerTest.nif_attrs_triple(3) == 9
+  end
+
+  test "NIFs of disabled features raise :not_implemented" do
+    assert RustlerTest.feature_enabled_add(1, 2) == 3
+    assert {:error, :not_implemented} == catch_error(RustlerTest.feature_disabled_add(1, 2))
+    assert {:error, :not_implemented} == catch_error(RustlerTest.nif_attrs_quadruple(2))
+  end
+
+  test "can register NIFs through a registry" do
+    assert RustlerTest.registered_one() == 1
+    assert RustlerTest.registered_two() == 2
+  end
 end
@@ -0,0 +1,27 @@
+defmodule RustlerTest.OverloadTest do
+  use ExUnit.Case, async: true
+
+  test "calls are shed when too many are in flight" do
+    overload = RustlerTest.overload_new(1, 100)
+
+    task = Task.async(fn -> RustlerTest.overload_run(overload, 200) end)
+    Process.sleep(50)
+    assert {1, 0} == RustlerTest.overload_stats(overload)
+    assert {:error, :overloaded} == RustlerTest.overload_run(overload, 0)
+    assert :ok == Task.await(task)
+
+    assert {0, 1} == RustlerTest.overload_stats(overload)
+    assert :ok == RustlerTest.overload_run(overload, 0)
+  end
+
+  test "calls are shed while the reported load is too high" do
+    overload = RustlerTest.overload_new(10, 100)
+
+    RustlerTest.overload_report_load(overload, 101)
+    assert {:error, :overloaded} == RustlerTest.overload_run(overload, 0)
+
+    RustlerTest.overload_report_load(overload, 100)
+    assert :ok == RustlerTest.overload_run(overload, 0)
+    assert {0, 1} == RustlerTest.overload_stats(overload)
+  end
+end
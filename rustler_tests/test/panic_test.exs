@@ -0,0 +1,33 @@
+defmodule RustlerTest.PanicTest do
+  # The panic mode is global to the library.
+  use ExUnit.Case, async: false
+
+  setup do
+    on_exit(fn -> RustlerTest.panic_mode_set("atom") end)
+  end
+
+  test "panics raise :nif_panicked by default" do
+    assert catch_error(RustlerTest.panic_with("boom")) == :nif_panicked
+  end
+
+  test "panics raise an exception with the message and backtrace" do
+    assert :ok == RustlerTest.panic_mode_set("exception")
+
+    error = assert_raise Rustler.NifPanicError, "boom", fn -> RustlerTest.panic_with("boom") end
+    assert is_binary(error.backtrace)
+
+    assert :ok == RustlerTest.panic_mode_set("atom")
+    assert catch_error(RustlerTest.panic_with("boom")) == :nif_panicked
+  end
+
+  test "async NIFs reply with the exception of their panic" do
+    assert :ok == RustlerTest.panic_mode_set("exception")
+
+    ref = RustlerTest.async_panic()
+    assert_receive {^ref, {:error, %Rustler.NifPanicError{message: "async NIF panicked"}}}, 1000
+  end
+
+  test "invalid modes are refused" do
+    assert_raise ArgumentError, fn -> RustlerTest.panic_mode_set("ignore") end
+  end
+end
@@ -0,0 +1,23 @@
+defmodule RustlerTest.PersistentTermTest do
+  use ExUnit.Case, async: false
+
+  @key {RustlerTest, :persistent_term_test}
+
+  test "put and erase through the helper process" do
+    start_supervised!(Rustler.PersistentTerm)
+
+    assert :ok == RustlerTest.persistent_term_put(@key, %{rules: [1, 2, 3]})
+    # Wait until the helper has handled the message.
+    :sys.get_state(Rustler.PersistentTerm)
+    assert :persistent_term.get(@key) == %{rules: [1, 2, 3]}
+
+    assert :ok == RustlerTest.persistent_term_erase(@key)
+    :sys.get_state(Rustler.PersistentTerm)
+    assert :persistent_term.get(@key, :erased) == :erased
+  end
+
+  test "fails without the helper process" do
+    assert {:error, :persistent_term_helper_not_running} ==
+             RustlerTest.persistent_term_put(@key, :value)
+  end
+end
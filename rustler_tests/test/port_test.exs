@@ -0,0 +1,36 @@
+defmodule RustlerTest.PortTest do
+  use ExUnit.Case, async: true
+
+  defp packet(term) do
+    data = :erlang.term_to_binary(term)
+    <<byte_size(data)::32, data::binary>>
+  end
+
+  defp replies(<<>>), do: []
+
+  defp replies(<<size::32, data::binary-size(size), rest::binary>>),
+    do: [:erlang.binary_to_term(data) | replies(rest)]
+
+  @max_packet_size 1024
+
+  test "requests are answered in order" do
+    input = packet({:add, 1, 2}) <> packet(:other) <> packet({:add, 40, 2})
+    output = RustlerTest.port_serve(input, @max_packet_size)
+    assert replies(output) == [{:ok, 3}, {:error, :unknown}, {:ok, 42}]
+  end
+
+  test "truncated and invalid packets" do
+    assert RustlerTest.port_serve(<<>>, @max_packet_size) == <<>>
+    assert {:error, _} = RustlerTest.port_serve(<<0, 0, 0, 5, 131>>, @max_packet_size)
+    assert {:error, _} = RustlerTest.port_serve(<<0, 0, 0, 1, 1>>, @max_packet_size)
+  end
+
+  test "packets larger than the maximum are refused before they are read" do
+    assert RustlerTest.port_serve(<<0xFFFFFFFF::32>>, @max_packet_size) ==
+             {:error, "packet of 4294967295 bytes is too large"}
+
+    input = packet({:add, 1, 2})
+    assert {:error, _} = RustlerTest.port_serve(input, byte_size(input) - 5)
+    assert replies(RustlerTest.port_serve(input, byte_size(input) - 4)) == [{:ok, 3}]
+  end
+end
@@ -1,5 +1,6 @@
 defmodule RustlerTest.PrimitivesTest do
   use ExUnit.Case, async: true
+  use Bitwise
 
   test "number decoding and encoding" do
     assert 3 == RustlerTest.add_u32(1, 2)
@@ -14,6 +15,41 @@ defmodule RustlerTest.PrimitivesTest do
     assert_raise ArgumentError, fn -> RustlerTest.add_i32(2_147_483_648, 1) end
   end
 
+  test "argument decoding errors name the argument" do
+    assert_raise ArgumentError,
+                 "argument 2 of add_u32/2 could not be decoded as u32, got a number",
+                 fn -> RustlerTest.add_u32(1, -1) end
+
+    assert_raise ArgumentError,
+                 "argument 1 of add_u32/2 could not be decoded as u32, got a binary",
+                 fn -> RustlerTest.add_u32("1", 1) end
+  end
+
+  test "128-bit integers" do
+    assert 3 == RustlerTest.add_i128(1, 2)
+    assert -bsl(1, 100) == RustlerTest.add_i128(-bsl(1, 100) + 1, -1)
+    assert bsl(1, 64) == RustlerTest.add_i128(bsl(1, 63), bsl(1, 63))
+    assert bsl(1, 127) - 1 == RustlerTest.add_i128(bsl(1, 126), bsl(1, 126) - 1)
+    assert nil == RustlerTest.add_i128(bsl(1, 127) - 1, 1)
+    assert -bsl(1, 127) == RustlerTest.add_i128(-bsl(1, 127), 0)
+    assert_raise ArgumentError, fn -> RustlerTest.add_i128(bsl(1, 127), 0) end
+    assert_raise ArgumentError, fn -> RustlerTest.add_i128(1.0, 0) end
+
+    assert bsl(1, 128) - 1 == RustlerTest.echo_u128(bsl(1, 128) - 1)
+    assert 0 == RustlerTest.echo_u128(0)
+    assert_raise ArgumentError, fn -> RustlerTest.echo_u128(bsl(1, 128)) end
+    assert_raise ArgumentError, fn -> RustlerTest.echo_u128(-bsl(1, 64)) end
+  end
+
+  test "big integers" do
+    assert 6 == RustlerTest.mul_bigint(2, 3)
+    big = 100_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000
+    assert -(big * big) == RustlerTest.mul_bigint(big, -big)
+    assert bsl(1, 4000) == RustlerTest.mul_bigint(bsl(1, 2000), bsl(1, 2000))
+    assert 0 == RustlerTest.mul_bigint(0, -bsl(1, 200))
+    assert_raise ArgumentError, fn -> RustlerTest.mul_bigint(1.5, 2) end
+  end
+
   test "option decoding and encoding" do
     assert 33.0 == RustlerTest.option_inc(32.0)
     assert nil == RustlerTest.option_inc(nil)
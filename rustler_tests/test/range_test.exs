@@ -1,7 +1,34 @@
 defmodule RustlerTest.RangeTest do
   use ExUnit.Case, async: true
+  import RustlerTest.Helper
 
   test "range iteration" do
     assert 55 == RustlerTest.sum_range(1..10)
   end
+
+  test "RangeInclusive is encoded as a range" do
+    assert range(1, 10, 1) == RustlerTest.range_inclusive_echo(1..10, true)
+    assert range(3, 1, 1) == RustlerTest.range_inclusive_echo(range(3, 1, 1), true)
+  end
+
+  test "RangeInclusive is encoded without a step for older Elixir versions" do
+    assert %{__struct__: Range, first: 1, last: 10} ==
+             RustlerTest.range_inclusive_echo(1..10, false)
+  end
+
+  test "RangeInclusive ignores the step of ranges" do
+    assert range(1, 10, 1) == RustlerTest.range_inclusive_echo(range(1, 10, 2), true)
+    assert 0 == RustlerTest.sum_range(range(10, 1, -1))
+    assert 0 == RustlerTest.sum_range(%{__struct__: Range, first: 10, last: 1})
+  end
+
+  test "ranges as offsets" do
+    assert [?b, ?c, ?d] == RustlerTest.range_slice("abcdef", 1..3)
+    assert_raise ArgumentError, fn -> RustlerTest.range_slice("abc", 1..5) end
+  end
+
+  test "Range converts to RangeInclusive" do
+    assert range(2, 4, 1) == RustlerTest.range_to_inclusive(2..4)
+    assert_raise ArgumentError, fn -> RustlerTest.range_to_inclusive(range(4, 2, -1)) end
+  end
 end
@@ -0,0 +1,34 @@
+defmodule RustlerTest.RateLimitTest do
+  use ExUnit.Case, async: true
+
+  test "tokens are taken from a full bucket" do
+    bucket = RustlerTest.token_bucket_new(10, 1)
+    assert RustlerTest.token_bucket_available(bucket) == 10
+
+    assert RustlerTest.token_bucket_try_acquire(bucket, 7)
+    refute RustlerTest.token_bucket_try_acquire(bucket, 7)
+    assert RustlerTest.token_bucket_try_acquire(bucket, 3)
+    assert RustlerTest.token_bucket_available(bucket) == 0
+  end
+
+  test "buckets are refilled over time" do
+    bucket = RustlerTest.token_bucket_new(5, 100)
+    assert RustlerTest.token_bucket_try_acquire(bucket, 5)
+    refute RustlerTest.token_bucket_try_acquire(bucket, 1)
+
+    Process.sleep(50)
+    assert RustlerTest.token_bucket_try_acquire(bucket, 1)
+
+    Process.sleep(200)
+    assert RustlerTest.token_bucket_available(bucket) == 5
+  end
+
+  test "wait time" do
+    bucket = RustlerTest.token_bucket_new(10, 10)
+    assert RustlerTest.token_bucket_wait_time(bucket, 10) == 0
+    assert RustlerTest.token_bucket_wait_time(bucket, 11) == nil
+
+    assert RustlerTest.token_bucket_try_acquire(bucket, 10)
+    assert RustlerTest.token_bucket_wait_time(bucket, 5) in 400..500
+  end
+end
@@ -0,0 +1,35 @@
+defmodule RustlerTest.RegexTest do
+  use ExUnit.Case, async: true
+
+  test "compiling Elixir regexes" do
+    assert {:ok, regex} = RustlerTest.regex_compile(~r/ab+c/i)
+    assert RustlerTest.regex_is_match(regex, "xABBCx")
+    refute RustlerTest.regex_is_match(regex, "ac")
+
+    assert {:ok, _} = RustlerTest.regex_compile({"^a.b$", "ms"})
+    assert {:error, message} = RustlerTest.regex_compile({"(", ""})
+    assert is_binary(message)
+    assert {:error, _} = RustlerTest.regex_compile({"a", "f"})
+  end
+
+  test "match positions" do
+    {:ok, regex} = RustlerTest.regex_compile(~r/\d+/)
+    text = "a1 bb22 é333"
+
+    assert RustlerTest.regex_positions(regex, text) ==
+             Regex.scan(~r/\d+/, text, return: :index) |> Enum.map(&hd/1)
+
+    assert RustlerTest.regex_positions(regex, "none") == []
+  end
+
+  test "named captures" do
+    pattern = ~r/(?<year>\d{4})-(?<month>\d{2})(-(?<day>\d{2}))?/
+    {:ok, regex} = RustlerTest.regex_compile(pattern)
+
+    for text <- ["on 2021-03-14", "on 2021-03"] do
+      assert RustlerTest.regex_named_captures(regex, text) == Regex.named_captures(pattern, text)
+    end
+
+    assert RustlerTest.regex_named_captures(regex, "never") == nil
+  end
+end
@@ -35,4 +35,66 @@ defmodule RustlerTest.ResourceTest do
     # Erlang's exact GC should have cleaned all that up.
     assert RustlerTest.resource_immutable_count() == 0
   end
+
+  @tag nif_version: "2.13"
+  test "resource type names" do
+    assert RustlerTest.resource_type_names() == {"TestResource", "RustlerTest.IoQueue"}
+  end
+
+  test "destruction hooks" do
+    {resource, handle} = RustlerTest.resource_index_make()
+    assert RustlerTest.resource_index_contains(handle)
+    assert RustlerTest.resource_get_integer_field(resource) == 0
+
+    # The resource of a process is destroyed when the process exits.
+    handle =
+      Task.async(fn -> elem(RustlerTest.resource_index_make(), 1) end)
+      |> Task.await()
+
+    :timer.sleep(100)
+    refute RustlerTest.resource_index_contains(handle)
+  end
+
+  @tag nif_version: "2.14"
+  test "io queue" do
+    queue = RustlerTest.ioq_new()
+    large = :binary.copy("a", 1000)
+
+    assert RustlerTest.ioq_push(queue, "hello ") == 6
+    assert RustlerTest.ioq_push(queue, large) == 1006
+    assert RustlerTest.ioq_push_owned(queue, "world") == 1011
+    assert RustlerTest.ioq_segments(queue) == [6, 1000, 5]
+    assert RustlerTest.ioq_head(queue) == "hello "
+
+    assert RustlerTest.ioq_read(queue, 3) == "hel"
+    assert RustlerTest.ioq_segments(queue) == [3, 1000, 5]
+    assert RustlerTest.ioq_read(queue, 1000) == "lo " <> :binary.copy("a", 997)
+    assert RustlerTest.ioq_read(queue, 100) == "aaaworld"
+    assert RustlerTest.ioq_read(queue, 100) == ""
+    assert RustlerTest.ioq_segments(queue) == []
+    assert RustlerTest.ioq_head(queue) == nil
+  end
+
+  @tag nif_version: "2.16"
+  test "dynamic resource calls" do
+    adder = RustlerTest.dyncall_adder(5)
+    assert RustlerTest.dyncall_add(RustlerTest, "RustlerTest.Adder", adder, 2) == 7
+
+    assert_raise ArgumentError, fn -> RustlerTest.dyncall_add(RustlerTest, "Other", adder, 2) end
+
+    assert_raise ArgumentError, fn ->
+      RustlerTest.dyncall_add(:other_module, "RustlerTest.Adder", adder, 2)
+    end
+  end
+
+  @tag nif_version: "2.16"
+  test "resource type with several callbacks" do
+    adder = RustlerTest.dyncall_adder(1)
+    pid = spawn(fn -> receive do: (:exit -> :ok) end)
+
+    assert RustlerTest.dyncall_adder_watch(adder, pid)
+    send(pid, :exit)
+    assert RustlerTest.Helper.wait_until(fn -> RustlerTest.dyncall_adder_downs(adder) == 1 end)
+    assert RustlerTest.dyncall_add(RustlerTest, "RustlerTest.Adder", adder, 2) == 3
+  end
 end
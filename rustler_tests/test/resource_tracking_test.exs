@@ -0,0 +1,27 @@
+defmodule RustlerTest.ResourceTrackingTest do
+  # Not async: other tests create and release resources.
+  use ExUnit.Case, async: false
+
+  @type_name "rustler_test::test_resource::ImmutableResource"
+
+  defp live do
+    case List.keyfind(RustlerTest.__rustler_resources__(), @type_name, 0) do
+      {@type_name, count, backtraces} -> {count, length(backtraces)}
+      nil -> {0, 0}
+    end
+  end
+
+  test "live resources are counted until they are destroyed" do
+    {count, _} = live()
+
+    resources = for i <- 1..10, do: RustlerTest.resource_make_immutable(i)
+    assert live() == {count + 10, count + 10}
+    assert length(resources) == 10
+
+    resources = nil
+    :erlang.garbage_collect()
+    :timer.sleep(100)
+    assert resources == nil
+    assert live() == {count, count}
+  end
+end
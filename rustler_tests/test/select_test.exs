@@ -0,0 +1,24 @@
+defmodule RustlerTest.SelectTest do
+  use ExUnit.Case, async: true
+  import RustlerTest.Helper
+
+  @moduletag :unix
+
+  test "select on a readable descriptor" do
+    pipe = RustlerTest.select_pipe_new()
+    ref = make_ref()
+    assert RustlerTest.select_pipe_wait(pipe, ref) == :ok
+    refute_receive {:select, _, _, _}, 50
+
+    RustlerTest.select_pipe_write(pipe, "ping")
+    assert_receive {:select, ^pipe, ^ref, :ready_input}
+    assert RustlerTest.select_pipe_read(pipe) == "ping"
+  end
+
+  test "stopping a selection" do
+    pipe = RustlerTest.select_pipe_new()
+    assert RustlerTest.select_pipe_wait(pipe, :undefined) == :ok
+    assert RustlerTest.select_pipe_stop(pipe) in [:called, :scheduled]
+    assert wait_until(fn -> RustlerTest.select_pipe_stopped(pipe) end)
+  end
+end
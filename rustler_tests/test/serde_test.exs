@@ -0,0 +1,66 @@
+defmodule RustlerTest.SerdeTest do
+  use ExUnit.Case, async: true
+
+  defp order(status) do
+    %{
+      id: 42,
+      customer: "ada",
+      lines: [{"apple", 3}, {"pear", 1}],
+      tags: %{"priority" => 1.5},
+      status: status
+    }
+  end
+
+  test "structs and enums round trip" do
+    for status <- [
+          :Pending,
+          {:Shipped, "UPS"},
+          {:Split, 1, 2},
+          {:Returned, %{reason: "damaged", refund: true}}
+        ] do
+      assert order(status) == RustlerTest.serde_echo_order(order(status))
+    end
+
+    assert %{customer: nil} = RustlerTest.serde_echo_order(%{order(:Pending) | customer: nil})
+  end
+
+  test "lenient decoding" do
+    decoded =
+      RustlerTest.serde_echo_order(%{
+        "id" => 1,
+        "customer" => :ada,
+        "lines" => [["apple", 3]],
+        "tags" => %{},
+        "status" => "Pending"
+      })
+
+    assert decoded ==
+             %{id: 1, customer: "ada", lines: [{"apple", 3}], tags: %{}, status: :Pending}
+  end
+
+  test "invalid terms" do
+    assert_raise ArgumentError, fn ->
+      RustlerTest.serde_echo_order(%{order(:Pending) | id: -1})
+    end
+
+    assert_raise ArgumentError, fn ->
+      RustlerTest.serde_echo_order(%{order(:Pending) | status: :Lost})
+    end
+
+    assert_raise ArgumentError, fn ->
+      RustlerTest.serde_echo_order(Map.delete(order(:Pending), :lines))
+    end
+
+    assert_raise ArgumentError, fn -> RustlerTest.serde_echo_order({:Shipped, 1, 2}) end
+  end
+
+  test "borrowed strings" do
+    assert "banana" == RustlerTest.serde_longest(["fig", "banana", "kiwi"])
+    assert nil == RustlerTest.serde_longest([])
+  end
+
+  test "to_term" do
+    assert %{id: 7, customer: nil, lines: [{"apple", 3}], tags: %{}, status: {:Split, 1, 2}} ==
+             RustlerTest.serde_order_to_term(7)
+  end
+end
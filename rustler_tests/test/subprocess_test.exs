@@ -0,0 +1,39 @@
+defmodule RustlerTest.SubprocessTest do
+  use ExUnit.Case, async: true
+
+  test "output is sent to the owner" do
+    subprocess = RustlerTest.subprocess_spawn("echo", ["hello"])
+    os_pid = RustlerTest.subprocess_os_pid(subprocess)
+
+    assert_receive {:stdout, ^os_pid, "hello\n"}
+    assert_receive {:exit_status, ^os_pid, 0}
+  end
+
+  test "standard error is sent separately" do
+    subprocess = RustlerTest.subprocess_spawn("sh", ["-c", "echo oops >&2; exit 3"])
+    os_pid = RustlerTest.subprocess_os_pid(subprocess)
+
+    assert_receive {:stderr, ^os_pid, "oops\n"}
+    assert_receive {:exit_status, ^os_pid, 3}
+  end
+
+  test "write to standard input" do
+    subprocess = RustlerTest.subprocess_spawn("cat", [])
+    os_pid = RustlerTest.subprocess_os_pid(subprocess)
+
+    assert :ok == RustlerTest.subprocess_write(subprocess, "ping")
+    assert_receive {:stdout, ^os_pid, "ping"}
+
+    assert :ok == RustlerTest.subprocess_close_stdin(subprocess)
+    assert_receive {:exit_status, ^os_pid, 0}
+    assert {:error, _} = RustlerTest.subprocess_write(subprocess, "pong")
+  end
+
+  test "kill" do
+    subprocess = RustlerTest.subprocess_spawn("sleep", ["60"])
+    os_pid = RustlerTest.subprocess_os_pid(subprocess)
+
+    assert :ok == RustlerTest.subprocess_kill(subprocess)
+    assert_receive {:exit_status, ^os_pid, nil}
+  end
+end
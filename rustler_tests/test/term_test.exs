@@ -40,4 +40,209 @@ defmodule RustlerTest.TermTest do
     # Other term types
     assert RustlerTest.term_cmp(5, :test) == :less
   end
+
+  test "tuple size" do
+    assert RustlerTest.term_tuple_size({}) == 0
+    assert RustlerTest.term_tuple_size({:ok, 1, 2}) == 3
+    assert_raise ArgumentError, fn -> RustlerTest.term_tuple_size([:ok]) end
+  end
+
+  test "tuple element access" do
+    assert RustlerTest.term_tuple_get({:ok, "value"}, 0) == :ok
+    assert RustlerTest.term_tuple_get({:ok, "value"}, 1) == "value"
+    assert_raise ArgumentError, fn -> RustlerTest.term_tuple_get({:ok, "value"}, 2) end
+    assert_raise ArgumentError, fn -> RustlerTest.term_tuple_get([:ok], 0) end
+  end
+
+  test "term_match" do
+    assert RustlerTest.term_match_tuple({:ok, 1}) == {:ok, 1, 0}
+    assert RustlerTest.term_match_tuple({:ok, 2, :a, :b}) == {:ok, 2, 2}
+    assert RustlerTest.term_match_tuple({:error, "fatal", :reason}) == {:error, -1, 0}
+    assert RustlerTest.term_match_tuple({:error, 404, :not_found}) == {:error, 404, 0}
+    assert RustlerTest.term_match_tuple({:anything}) == {:other, 0, 0}
+    assert_raise ArgumentError, fn -> RustlerTest.term_match_tuple({:ok, "text"}) end
+    assert_raise ArgumentError, fn -> RustlerTest.term_match_tuple([:ok, 1]) end
+  end
+
+  test "etf reader and writer" do
+    terms = [
+      42,
+      -1,
+      100_000_000_000,
+      -1_267_650_600_228_229_401_496_703_205_376,
+      1.5,
+      :atom,
+      :"with spaces",
+      "binary",
+      <<1::3>>,
+      {},
+      {:ok, [1, 2, 3]},
+      [],
+      [1 | :improper],
+      'charlist',
+      %{a: 1, "b" => [2.0]},
+      self(),
+      make_ref()
+    ]
+
+    for term <- terms do
+      assert RustlerTest.etf_decode(:erlang.term_to_binary(term)) == term
+      assert :erlang.binary_to_term(RustlerTest.etf_encode(term)) == term
+    end
+  end
+
+  test "etf reader errors" do
+    assert {:error, "bad version byte 1"} == RustlerTest.etf_decode(<<1, 97, 1>>)
+    assert {:error, "unexpected end of data"} == RustlerTest.etf_decode(<<131, 98, 0>>)
+    assert {:error, _} = RustlerTest.etf_decode(:erlang.term_to_binary(fn -> :ok end))
+
+    nested = Enum.reduce(1..100_000, [], fn _, acc -> [acc] end)
+    assert {:error, "terms nested deeper than 512"} ==
+             RustlerTest.etf_decode(:erlang.term_to_binary(nested))
+  end
+
+  test "term to binary and back" do
+    terms = [:atom, 42, "binary", [1 | :improper], %{a: {1.5, 'x'}}, self(), make_ref()]
+
+    for term <- terms do
+      assert RustlerTest.term_to_binary(term) == :erlang.term_to_binary(term)
+      assert RustlerTest.term_from_binary(:erlang.term_to_binary(term)) == term
+    end
+
+    assert_raise ArgumentError, fn -> RustlerTest.term_from_binary(<<131, 98, 0>>) end
+    assert_raise ArgumentError, fn ->
+      RustlerTest.term_from_binary(:erlang.term_to_binary(1) <> "x")
+    end
+
+    unknown = <<131, 119, 20, "rustler_unknown_atom">>
+    assert_raise ArgumentError, fn -> RustlerTest.term_from_binary(unknown) end
+  end
+
+  test "canonical binary" do
+    assert RustlerTest.term_to_canonical_binary(%{2 => 20, 1 => 10}) ==
+             <<131, 116, 0, 0, 0, 2, 97, 1, 97, 10, 97, 2, 97, 20>>
+
+    assert RustlerTest.term_to_canonical_binary([1 | 2]) == <<131, 108, 0, 0, 0, 1, 97, 1, 97, 2>>
+
+    large = Map.new(1..100, &{&1, [%{&1 => {&1}}]})
+    built = Enum.reduce(100..1, %{}, &Map.put(&2, &1, [%{&1 => {&1}}]))
+    binary = RustlerTest.term_to_canonical_binary(large)
+
+    assert binary == RustlerTest.term_to_canonical_binary(built)
+    assert :erlang.binary_to_term(binary) == large
+
+    terms = [:atom, 42, "binary", [1 | :improper], %{a: {1.5, 'x'}}, self(), make_ref()]
+
+    for term <- terms do
+      assert :erlang.binary_to_term(RustlerTest.term_to_canonical_binary(term)) == term
+    end
+  end
+
+  test "sorted map encoding" do
+    map = Map.new(1..100, &{&1, Integer.to_string(&1)})
+    assert RustlerTest.sorted_map_encode(map) == map
+
+    # A map of more than 32 keys is a hash map, whose keys `:maps.to_list` doesn't sort.
+    refute Enum.map(:maps.to_list(map), &elem(&1, 0)) == Enum.to_list(1..100)
+    assert RustlerTest.sorted_map_entries(map) == Enum.map(1..100, &{&1, Integer.to_string(&1)})
+  end
+
+  test "snapshots" do
+    assert RustlerTest.term_snapshot(%{b: [1 | 2], a: {:ok, nil}}, 64) ==
+             "%{a: {:ok, nil}, b: [1 | 2]}"
+
+    assert RustlerTest.term_snapshot(%{"b" => 1, 2 => :"e-mail", 1 => 1.5}, 64) ==
+             ~s(%{1 => 1.5, 2 => :"e-mail", "b" => 1})
+
+    assert RustlerTest.term_snapshot(%{"e-mail": true}, 64) == ~s(%{"e-mail": true})
+
+    assert RustlerTest.term_snapshot(%{__struct__: Snapshot.Struct, type: "a\"b", ref: 0}, 64) ==
+             ~s(%Snapshot.Struct{ref: 0, type: "a\\"b"})
+
+    assert RustlerTest.term_snapshot(-1_267_650_600_228_229_401_496_703_205_376, 64) ==
+             "-1267650600228229401496703205376"
+
+    assert RustlerTest.term_snapshot({String, :foo?, self(), make_ref()}, 64) ==
+             "{String, :foo?, #PID, #Reference}"
+
+    assert RustlerTest.term_snapshot([1.0e20, 1.5e-10, 1000.0, 100.0, 0.001, -2.5], 64) ==
+             "[1.0e20, 1.5e-10, 1.0e3, 100.0, 0.001, -2.5]"
+
+    assert RustlerTest.term_snapshot(String.duplicate("a", 10), 4) == ~s("aaaa"... (10 bytes))
+    assert RustlerTest.term_snapshot(<<0, 1, 2, 3>>, 2) == "<<0, 1, ... (4 bytes)>>"
+
+    assert RustlerTest.term_assert_snapshot([%{a: 1}], "[%{a: 1}]") == :ok
+    assert_raise ErlangError, fn -> RustlerTest.term_assert_snapshot([%{a: 1}], "[%{a: 2}]") end
+  end
+
+  test "row decoding" do
+    assert {[], [], []} == RustlerTest.rows_decode([])
+
+    assert {[1, 2], ["a", "b"], [0.5, 1.5]} ==
+             RustlerTest.rows_decode([{1, "a", 0.5}, [2, "b", 1.5]])
+  end
+
+  test "row decoding errors" do
+    exception =
+      assert_raise ErlangError, fn -> RustlerTest.rows_decode([{1, "a", 0.5}, {2, "b"}]) end
+    assert exception.original == "Row 1 is not a tuple or list of the expected size"
+
+    exception = assert_raise ErlangError, fn -> RustlerTest.rows_decode([{1, :a, 0.5}]) end
+    assert exception.original == "Could not decode column 1 of row 0"
+
+    assert_raise ArgumentError, fn -> RustlerTest.rows_decode(:rows) end
+  end
+
+  test "term size estimation" do
+    assert RustlerTest.term_byte_size_estimate(:atom) == 0
+    assert RustlerTest.term_byte_size_estimate(42) == 0
+    assert RustlerTest.term_byte_size_estimate([1, 2, 3]) == 48
+    assert RustlerTest.term_byte_size_estimate({1, [2]}) == 40
+    assert RustlerTest.term_byte_size_estimate(:binary.copy("x", 1000)) == 1048
+  end
+
+  test "term size limit" do
+    list = Enum.to_list(1..1000)
+    refute RustlerTest.term_byte_size_exceeds(list, 16_000)
+    assert RustlerTest.term_byte_size_exceeds(list, 15_999)
+    assert RustlerTest.term_byte_size_exceeds(%{key: list}, 1000)
+  end
+
+  test "term types" do
+    assert "Atom" == RustlerTest.term_type(:atom)
+    assert "Binary" == RustlerTest.term_type("binary")
+    assert "EmptyList" == RustlerTest.term_type([])
+    assert "List" == RustlerTest.term_type([1])
+    assert "List" == RustlerTest.term_type([1 | 2])
+    assert "Map" == RustlerTest.term_type(%{})
+    assert "Number" == RustlerTest.term_type(1)
+    assert "Number" == RustlerTest.term_type(1_267_650_600_228_229_401_496_703_205_376)
+    assert "Number" == RustlerTest.term_type(1.5)
+    assert "Fun" == RustlerTest.term_type(&Enum.map/2)
+    assert "Pid" == RustlerTest.term_type(self())
+    assert "Port" == RustlerTest.term_type(hd(Port.list()))
+    assert "Ref" == RustlerTest.term_type(make_ref())
+    assert "Tuple" == RustlerTest.term_type({})
+    assert "Unknown" == RustlerTest.term_type(<<1::3>>)
+  end
+
+  test "decoding or raising" do
+    assert 42 == RustlerTest.term_decode_or_raise(42, false)
+    assert 3 == RustlerTest.term_decode_or_raise([1, 2, 3], true)
+
+    assert_raise ArgumentError, "term could not be decoded as u32, got a binary", fn ->
+      RustlerTest.term_decode_or_raise("42", false)
+    end
+
+    assert_raise ArgumentError,
+                 "term could not be decoded as alloc::vec::Vec<i64>, got a map",
+                 fn -> RustlerTest.term_decode_or_raise(%{}, true) end
+  end
+
+  test "lazy decoding" do
+    assert {6, true} == RustlerTest.lazy_sum(true, [1, 2, 3])
+    assert {0, false} == RustlerTest.lazy_sum(false, [1, 2, 3])
+    assert {0, false} == RustlerTest.lazy_sum(false, :not_a_list)
+    assert_raise ArgumentError, fn -> RustlerTest.lazy_sum(true, :not_a_list) end
+  end
 end
@@ -1 +1,44 @@
-ExUnit.start()
+# Tests tagged with `nif_version: "2.x"` need that version of the NIF API, and are excluded on
+# older VMs, where the test library doesn't register their NIFs.
+[major, minor] =
+  :erlang.system_info(:nif_version)
+  |> to_string()
+  |> String.split(".")
+  |> Enum.map(&String.to_integer/1)
+
+newer_nif_versions =
+  13..16
+  |> Enum.filter(&(&1 > minor))
+  |> Enum.map(&{:nif_version, "#{major}.#{&1}"})
+
+# Tests tagged with `:unix` need Unix domain sockets or Unix commands.
+unix_only =
+  case :os.type() do
+    {:unix, _} -> []
+    _ -> [:unix]
+  end
+
+ExUnit.start(exclude: newer_nif_versions ++ unix_only)
+
+defmodule RustlerTest.Helper do
+  @doc """
+  Builds a range with a `step`, without the `first..last//step` syntax and `%Range{step: step}`
+  literals of Elixir 1.12.
+  """
+  def range(first, last, step), do: Map.put(%{1..2 | first: first, last: last}, :step, step)
+
+  @doc "Polls `fun` every 10ms until it returns a truthy value, for at most a second."
+  def wait_until(fun, attempts \\ 100) do
+    cond do
+      fun.() ->
+        true
+
+      attempts == 0 ->
+        false
+
+      true ->
+        Process.sleep(10)
+        wait_until(fun, attempts - 1)
+    end
+  end
+end
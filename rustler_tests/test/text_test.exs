@@ -0,0 +1,34 @@
+defmodule RustlerTest.TextTest do
+  use ExUnit.Case, async: true
+
+  test "normalization" do
+    decomposed = "é"
+    assert RustlerTest.text_nfc(decomposed) == "é"
+    assert RustlerTest.text_nfc("plain") == "plain"
+    assert RustlerTest.text_nfd("é") == decomposed
+    assert RustlerTest.text_nfc(decomposed) == :unicode.characters_to_nfc_binary(decomposed)
+    assert_raise ArgumentError, fn -> RustlerTest.text_nfc(<<0xFF>>) end
+  end
+
+  test "graphemes" do
+    text = "éa🇫🇷"
+    assert RustlerTest.text_graphemes(text) == String.graphemes(text)
+    assert RustlerTest.text_graphemes("") == []
+    assert_raise ArgumentError, fn -> RustlerTest.text_graphemes(<<0xC3>>) end
+  end
+
+  test "utf-8 validation" do
+    assert RustlerTest.text_invalid_utf8_offset("héllo") == nil
+    assert RustlerTest.text_invalid_utf8_offset(<<"ab", 0xFF, "c">>) == 2
+    assert RustlerTest.text_invalid_utf8_offset(<<"ab", 0xC3>>) == 2
+  end
+
+  test "similarity" do
+    assert RustlerTest.text_levenshtein("kitten", "sitting") == 3
+    assert RustlerTest.text_levenshtein("", "abc") == 3
+    assert RustlerTest.text_levenshtein("é", "é") == 1
+    assert RustlerTest.text_similarity("abcd", "abcd") == 1.0
+    assert RustlerTest.text_similarity("abcd", "abce") == 0.75
+    assert RustlerTest.text_similarity("", "") == 1.0
+  end
+end
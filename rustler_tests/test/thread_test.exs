@@ -56,4 +56,32 @@ defmodule RustlerTest.ThreadTest do
       msg -> assert msg == {:error, "threaded_fac: integer overflow"}
     end
   end
+
+  test "thread pool replies to the caller" do
+    for n <- 1..20, do: assert(:ok == RustlerTest.pool_fac(n))
+
+    for n <- 1..20 do
+      expected = Enum.reduce(1..n, 1, &(&1 * &2))
+      assert_receive {:pool_fac, ^n, ^expected}, 1000
+    end
+  end
+
+  test "thread pool job panicking" do
+    assert :ok == RustlerTest.pool_fac(21)
+    assert_receive {:error, "pool_fac: integer overflow"}, 1000
+  end
+
+  test "thread pool refuses jobs when its queue is full" do
+    # The first job is either running on the only thread, or still queued.
+    results = RustlerTest.pool_sleep(1, 2, [1, 2, 3, 4, 5], 100)
+    accepted = for {:ok, job} <- Enum.zip(results, 1..5), do: job
+
+    assert [:ok, :ok | _] = results
+    assert List.last(results) == :error
+    assert accepted in [[1, 2], [1, 2, 3]]
+
+    for job <- accepted do
+      assert_receive ^job, 1000
+    end
+  end
 end
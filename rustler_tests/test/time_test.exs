@@ -0,0 +1,80 @@
+defmodule RustlerTest.TimeTest do
+  use ExUnit.Case, async: true
+
+  test "SystemTime is encoded as a DateTime in UTC" do
+    now = DateTime.utc_now()
+
+    assert now == RustlerTest.system_time_echo(now)
+    assert DateTime.to_unix(now, :microsecond) == RustlerTest.system_time_unix_micros(now)
+  end
+
+  test "SystemTime before the epoch" do
+    time = DateTime.from_unix!(-1_750_000, :microsecond)
+
+    assert time == RustlerTest.system_time_echo(time)
+    assert -1_750_000 == RustlerTest.system_time_unix_micros(time)
+  end
+
+  test "SystemTime is decoded from a DateTime with an offset" do
+    time = %DateTime{
+      year: 2021,
+      month: 3,
+      day: 4,
+      hour: 7,
+      minute: 30,
+      second: 0,
+      microsecond: {0, 0},
+      std_offset: 3600,
+      utc_offset: 3600,
+      time_zone: "Europe/Paris",
+      zone_abbr: "CEST"
+    }
+
+    assert DateTime.from_unix!(1_614_835_800_000_000, :microsecond) ==
+             RustlerTest.system_time_echo(time)
+  end
+
+  test "SystemTime out of the range of DateTime" do
+    assert DateTime.from_unix!(1_614_835_800_000_000, :microsecond) ==
+             RustlerTest.system_time_after_epoch(1_614_835_800)
+
+    assert_raise ArgumentError, fn -> RustlerTest.system_time_after_epoch(10_000_000_000_000) end
+  end
+
+  test "SystemTime is decoded from an Erlang timestamp" do
+    timestamp = {1614, 835_800, 123_456}
+
+    assert 1_614_835_800_123_456 == RustlerTest.system_time_unix_micros(timestamp)
+  end
+
+  test "invalid dates are rejected" do
+    time = %{DateTime.from_unix!(1_614_470_400_000_000, :microsecond) | day: 30}
+
+    assert_raise ArgumentError, fn -> RustlerTest.system_time_echo(time) end
+    assert_raise ArgumentError, fn -> RustlerTest.system_time_echo(~N[2021-02-28 00:00:00]) end
+  end
+
+  test "Duration is an Erlang timestamp" do
+    assert {1614, 835_800, 123_456} == RustlerTest.duration_echo({1614, 835_800, 123_456})
+    assert {1, 0, 0} == RustlerTest.duration_echo({0, 1_000_000, 0})
+  end
+
+  test "chrono DateTime<Utc>" do
+    time = DateTime.from_unix!(1_609_459_140)
+
+    assert DateTime.from_unix!(1_609_459_230_000_000, :microsecond) ==
+             RustlerTest.utc_date_time_add_seconds(time, 90)
+  end
+
+  test "chrono NaiveDateTime" do
+    time = ~N[2020-02-29 12:34:56.789012]
+
+    assert time == RustlerTest.naive_date_time_echo(time)
+    assert_raise ArgumentError, fn -> RustlerTest.naive_date_time_echo(DateTime.utc_now()) end
+  end
+
+  test "chrono NaiveDate" do
+    assert ~D[2020-02-29] == RustlerTest.naive_date_echo(~D[2020-02-29])
+    assert_raise ArgumentError, fn -> RustlerTest.naive_date_echo(%{~D[2021-02-28] | day: 29}) end
+  end
+end
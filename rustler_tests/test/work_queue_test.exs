@@ -0,0 +1,63 @@
+defmodule RustlerTest.WorkQueueTest do
+  use ExUnit.Case, async: true
+  import RustlerTest.Helper
+
+  test "jobs are run by the workers" do
+    queue = RustlerTest.work_queue_new(10, 2)
+
+    for id <- 1..5 do
+      assert :ok == RustlerTest.work_queue_push(queue, id, 10)
+    end
+
+    for id <- 1..5 do
+      assert_receive {:job_done, ^id}, 1000
+    end
+
+    # The counters are updated once the handler returns, after it replied.
+    assert wait_until(fn -> RustlerTest.work_queue_stats(queue).completed == 5 end)
+
+    assert %{depth: 0, capacity: 10, workers: 2, enqueued: 5, completed: 5, rejected: 0} =
+             RustlerTest.work_queue_stats(queue)
+  end
+
+  test "pushing into a full queue fails" do
+    queue = RustlerTest.work_queue_new(2, 1)
+
+    assert :ok == RustlerTest.work_queue_push(queue, 1, 200)
+    assert wait_until(fn -> RustlerTest.work_queue_stats(queue).busy == 1 end)
+    assert :ok == RustlerTest.work_queue_push(queue, 2, 0)
+    assert :ok == RustlerTest.work_queue_push(queue, 3, 0)
+    assert {:error, :full} == RustlerTest.work_queue_push(queue, 4, 0)
+
+    assert %{depth: 2, busy: 1, enqueued: 3, rejected: 1} = RustlerTest.work_queue_stats(queue)
+
+    for id <- 1..3 do
+      assert_receive {:job_done, ^id}, 1000
+    end
+
+    refute_received {:job_done, 4}
+  end
+
+  test "a closed queue runs the queued jobs and refuses new ones" do
+    queue = RustlerTest.work_queue_new(5, 1)
+
+    assert :ok == RustlerTest.work_queue_push(queue, 1, 50)
+    assert :ok == RustlerTest.work_queue_push(queue, 2, 0)
+    assert :ok == RustlerTest.work_queue_close(queue)
+    assert {:error, :closed} == RustlerTest.work_queue_push(queue, 3, 0)
+
+    assert_receive {:job_done, 1}, 1000
+    assert_receive {:job_done, 2}, 1000
+  end
+
+  test "a panicking job doesn't stop its worker" do
+    queue = RustlerTest.work_queue_new(5, 1)
+
+    assert :ok == RustlerTest.work_queue_push(queue, 1, 0xFFFFFFFFFFFFFFFF)
+    assert :ok == RustlerTest.work_queue_push(queue, 2, 0)
+    assert_receive {:job_done, 2}, 1000
+
+    assert wait_until(fn -> RustlerTest.work_queue_stats(queue).completed == 1 end)
+    assert %{completed: 1, panicked: 1} = RustlerTest.work_queue_stats(queue)
+  end
+end